@@ -1,5 +1,69 @@
 use std::path;
 
+use crate::tuple::Tuple;
+
+mod constraint;
+mod difference;
+mod json;
+mod sidecar;
+mod signal;
+mod timeline;
+
+pub use constraint::*;
+pub use sidecar::*;
+pub use signal::*;
+pub use timeline::*;
+
+// Anything that can be blended between two values, which is what lets it be keyframed.
+pub trait Interpolate {
+    // Returns self when t is 0 and other when t is 1.
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl<T, const N: usize> Interpolate for Tuple<T, N> {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        let mut out = Self::default();
+        for i in 0..N {
+            out[i] = self[i].interpolate(&other[i], t);
+        }
+        out
+    }
+}
+
+// Like a LinearScale, but the keyframe values can be anything that can be interpolated (colors,
+// points, transforms...). The values are spread evenly over the domain.
+pub struct Keyframes<T> {
+    domain: (f64, f64),
+    values: Vec<T>,
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    pub fn new(domain: (f64, f64), values: Vec<T>) -> Self {
+        if values.is_empty() {
+            panic!("keyframes need at least one value")
+        }
+        Self { domain, values }
+    }
+
+    pub fn at(&self, input: f64) -> T {
+        let clamped_input = input.clamp(self.domain.0, self.domain.1);
+        let frac = normalize_progress(clamped_input, &self.domain);
+        if self.values.len() == 1 || frac.is_nan() {
+            return self.values[0].interpolate(&self.values[0], 0.0);
+        }
+
+        let position = frac * (self.values.len() - 1) as f64;
+        let index = (position.floor() as usize).min(self.values.len() - 2);
+        self.values[index].interpolate(&self.values[index + 1], position - index as f64)
+    }
+}
+
 #[derive(Default)]
 pub struct LinearScale {
     domain: (f64, f64),
@@ -39,18 +103,67 @@ fn normalize_progress(input: f64, domain: &(f64, f64)) -> f64 {
     (input - domain.0) / (domain.1 - domain.0)
 }
 
+// How long the virtual camera's shutter stays open each frame, as a fraction of the time between
+// frames, and how many sub-frame snapshots are taken while it's open. Averaging the snapshots
+// blurs anything that moves during the frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Shutter {
+    pub samples: usize,
+    pub open: f64,
+}
+
+impl Default for Shutter {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            open: 0.0,
+        }
+    }
+}
+
 pub struct Animator {
     pub frame_count: usize,
+    pub shutter: Shutter,
 }
 
+#[derive(Clone, Copy, Debug)]
 pub struct Frame {
     pub current: usize,
     count: usize,
+    time: f64,
+    shutter: Shutter,
 }
 
 impl Frame {
     pub fn new(current: usize, count: usize) -> Self {
-        Self { current, count }
+        Self {
+            current,
+            count,
+            time: current as f64,
+            shutter: Shutter::default(),
+        }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter) -> Self {
+        Self { shutter, ..self }
+    }
+
+    // The point in the animation this frame shows, measured in frames. It's only fractional for
+    // sub-frames. Use it rather than `current` to drive scales and keyframes so that sub-frames
+    // move.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // The snapshots to average for this frame, evenly spaced from the start of the frame while
+    // the shutter is open. Without a shutter this is just the frame itself.
+    pub fn subframes(&self) -> Vec<Frame> {
+        (0..self.shutter.samples)
+            .map(|i| Frame {
+                time: self.time + self.shutter.open * i as f64 / self.shutter.samples as f64,
+                ..*self
+            })
+            .collect()
     }
 
     pub fn filename(&self, path: &str, name: &str, ext: &str) -> String {
@@ -70,11 +183,31 @@ impl Frame {
             ..LinearScale::default()
         }
     }
+
+    pub fn keyframes<T: Interpolate>(&self, values: Vec<T>) -> Keyframes<T> {
+        Keyframes::new((0.0, self.count as f64), values)
+    }
 }
 
 impl Animator {
     pub fn new(frame_count: usize) -> Self {
-        Self { frame_count }
+        Self {
+            frame_count,
+            shutter: Shutter::default(),
+        }
+    }
+
+    pub fn with_shutter(self, samples: usize, open: f64) -> Self {
+        if samples == 0 {
+            panic!("the shutter needs at least one sample")
+        }
+        if !(0.0..=1.0).contains(&open) {
+            panic!("the shutter can only be open for between 0 and 1 frames")
+        }
+        Self {
+            shutter: Shutter { samples, open },
+            ..self
+        }
     }
 
     pub fn animate<F>(&self, animate: F)
@@ -82,7 +215,111 @@ impl Animator {
         F: Fn(Frame),
     {
         for current_frame in 0..self.frame_count {
-            animate(Frame::new(current_frame, self.frame_count))
+            animate(Frame::new(current_frame, self.frame_count).with_shutter(self.shutter))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, color::Color, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn interpolating_numbers() {
+        assert_fuzzy_eq!(2.0, 2.0.interpolate(&4.0, 0.0));
+        assert_fuzzy_eq!(3.0, 2.0.interpolate(&4.0, 0.5));
+        assert_fuzzy_eq!(4.0, 2.0.interpolate(&4.0, 1.0));
+    }
+
+    #[test]
+    fn interpolating_tuples() {
+        let c = Color::BLACK.interpolate(&Color::new(1.0, 0.5, 0.0), 0.5);
+
+        assert_fuzzy_eq!(Color::new(0.5, 0.25, 0.0), c);
+    }
+
+    #[test]
+    fn keyframes_are_spread_over_the_domain() {
+        let keyframes = Keyframes::new((0.0, 10.0), vec![0.0, 1.0, 0.0]);
+
+        assert_fuzzy_eq!(0.0, keyframes.at(0.0));
+        assert_fuzzy_eq!(0.5, keyframes.at(2.5));
+        assert_fuzzy_eq!(1.0, keyframes.at(5.0));
+        assert_fuzzy_eq!(0.5, keyframes.at(7.5));
+        assert_fuzzy_eq!(0.0, keyframes.at(10.0));
+    }
+
+    #[test]
+    fn keyframes_clamp_to_the_domain() {
+        let keyframes = Keyframes::new((0.0, 10.0), vec![1.0, 2.0]);
+
+        assert_fuzzy_eq!(1.0, keyframes.at(-5.0));
+        assert_fuzzy_eq!(2.0, keyframes.at(15.0));
+    }
+
+    #[test]
+    fn a_single_keyframe_is_constant() {
+        let keyframes = Keyframes::new((0.0, 10.0), vec![3.0]);
+
+        assert_fuzzy_eq!(3.0, keyframes.at(5.0));
+    }
+
+    #[test]
+    fn frames_build_keyframes_over_the_animation() {
+        let frame = Frame::new(30, 60);
+
+        assert_fuzzy_eq!(
+            0.5,
+            frame.keyframes(vec![0.0, 1.0]).at(frame.current as f64)
+        );
+    }
+
+    #[test]
+    fn a_frame_without_a_shutter_is_its_only_subframe() {
+        let subframes = Frame::new(3, 10).subframes();
+
+        assert_eq!(1, subframes.len());
+        assert_fuzzy_eq!(3.0, subframes[0].time());
+    }
+
+    #[test]
+    fn subframes_are_spread_over_the_open_shutter() {
+        let frame = Frame::new(3, 10).with_shutter(Shutter {
+            samples: 4,
+            open: 0.5,
+        });
+
+        let times: Vec<f64> = frame.subframes().iter().map(|f| f.time()).collect();
+
+        assert_eq!(4, times.len());
+        assert_fuzzy_eq!(3.0, times[0]);
+        assert_fuzzy_eq!(3.125, times[1]);
+        assert_fuzzy_eq!(3.25, times[2]);
+        assert_fuzzy_eq!(3.375, times[3]);
+    }
+
+    #[test]
+    fn subframes_evaluate_keyframes_between_frames() {
+        let frame = Frame::new(5, 10).with_shutter(Shutter {
+            samples: 2,
+            open: 1.0,
+        });
+        let keyframes = frame.keyframes(vec![0.0, 10.0]);
+
+        let values: Vec<f64> = frame
+            .subframes()
+            .iter()
+            .map(|f| keyframes.at(f.time()))
+            .collect();
+
+        assert_fuzzy_eq!(5.0, values[0]);
+        assert_fuzzy_eq!(5.5, values[1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "the shutter needs at least one sample")]
+    fn a_shutter_needs_samples() {
+        Animator::new(10).with_shutter(0, 0.5);
+    }
+}
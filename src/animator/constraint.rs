@@ -0,0 +1,322 @@
+use super::{
+    json::{self, Json},
+    BodyRef, TimelineError,
+};
+use crate::{
+    camera::Camera,
+    fuzzy_eq::EPISILON,
+    light::Light,
+    matrix::Matrix,
+    point::Point,
+    transform::{Transform, Transformable},
+    vector::Vector,
+    world::World,
+};
+
+// A rule placing one thing relative to another, worked out again every frame after the tracks
+// have moved things, so that e.g. a light parented to a bouncing ball bounces with it. Constraints
+// are applied in order, so one can follow something another constraint has just moved.
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    // Keeps the body placed by `offset` in the parent's space.
+    ParentBody {
+        body: BodyRef,
+        parent: BodyRef,
+        offset: Transform,
+    },
+    // Keeps a point light, by its index in the world's lights, at `offset` in the parent's space.
+    ParentLight {
+        light: usize,
+        parent: BodyRef,
+        offset: Point,
+    },
+    // Turns the body where it stands so that its +z axis points at the target's origin, replacing
+    // any rotation it had.
+    LookAt {
+        body: BodyRef,
+        target: BodyRef,
+    },
+    // Turns the camera where it stands to face the target's origin.
+    CameraLookAt {
+        target: BodyRef,
+    },
+}
+
+fn origin_of(world: &World, body: &BodyRef) -> Result<Point, TimelineError> {
+    let index = body.index_in(world)?;
+    Ok(world.bodies[index].transform() * Point::new(0.0, 0.0, 0.0))
+}
+
+// The way up for something at `from` looking at `to`: +y, unless that's straight along the view,
+// when it can't tell which way the sides are, so +z stands in for it.
+fn up_for(from: Point, to: Point) -> Vector {
+    let up = Vector::new(0.0, 1.0, 0.0);
+    if (to - from).normalize().cross(&up).magnitude() < EPISILON {
+        Vector::new(0.0, 0.0, 1.0)
+    } else {
+        up
+    }
+}
+
+impl Constraint {
+    pub(crate) fn apply(&self, world: &mut World, camera: Camera) -> Result<Camera, TimelineError> {
+        match self {
+            Constraint::ParentBody {
+                body,
+                parent,
+                offset,
+            } => {
+                let parent = world.bodies[parent.index_in(world)?].transform();
+                let index = body.index_in(world)?;
+                world.bodies[index] = world.bodies[index]
+                    .clone()
+                    .with_transform(parent * offset.matrix());
+            }
+            Constraint::ParentLight {
+                light,
+                parent,
+                offset,
+            } => {
+                let parent = world.bodies[parent.index_in(world)?].transform();
+                match world.lights.get_mut(*light) {
+                    Some(Light::Point(l)) => l.position = parent * *offset,
+                    _ => return Err(TimelineError::NoSuchPointLight(*light)),
+                }
+            }
+            Constraint::LookAt { body, target } => {
+                let target = origin_of(world, target)?;
+                let index = body.index_in(world)?;
+                let m = world.bodies[index].transform();
+                let origin = m * Point::new(0.0, 0.0, 0.0);
+                if (target - origin).magnitude() > 0.0 {
+                    // Keep where the body is and how big it is, and start the turn from scratch
+                    // so that applying the constraint again doesn't turn it any further.
+                    let length = |col: usize| (0..3).map(|row| m[row][col] * m[row][col]).sum();
+                    let unturned = Matrix::translate(origin[0], origin[1], origin[2])
+                        * Matrix::scale(
+                            f64::sqrt(length(0)),
+                            f64::sqrt(length(1)),
+                            f64::sqrt(length(2)),
+                        );
+                    world.bodies[index] = world.bodies[index]
+                        .clone()
+                        .with_transform(unturned)
+                        .look_at(target, up_for(origin, target));
+                }
+            }
+            Constraint::CameraLookAt { target } => {
+                let target = origin_of(world, target)?;
+                let from = camera.transform.inverse() * Point::new(0.0, 0.0, 0.0);
+                return Ok(camera.look_at_from_position(from, target, up_for(from, target)));
+            }
+        }
+        Ok(camera)
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        let body = |body: &BodyRef| match body {
+            BodyRef::Name(name) => json::quote(name),
+            BodyRef::Index(index) => index.to_string(),
+        };
+        let triple = |v: [f64; 3]| format!("[{}, {}, {}]", v[0], v[1], v[2]);
+        let vector = |v: Vector| triple([v[0], v[1], v[2]]);
+        match self {
+            Constraint::ParentBody {
+                body: b,
+                parent,
+                offset,
+            } => format!(
+                "{{ \"constraint\": \"parent\", \"body\": {}, \"parent\": {}, \"translation\": {}, \"rotation\": {}, \"scale\": {} }}",
+                body(b),
+                body(parent),
+                vector(offset.translation),
+                vector(offset.rotation),
+                vector(offset.scale)
+            ),
+            Constraint::ParentLight {
+                light,
+                parent,
+                offset,
+            } => format!(
+                "{{ \"constraint\": \"parent\", \"light\": {}, \"parent\": {}, \"position\": {} }}",
+                light,
+                body(parent),
+                triple([offset[0], offset[1], offset[2]])
+            ),
+            Constraint::LookAt { body: b, target } => format!(
+                "{{ \"constraint\": \"look_at\", \"body\": {}, \"target\": {} }}",
+                body(b),
+                body(target)
+            ),
+            Constraint::CameraLookAt { target } => format!(
+                "{{ \"constraint\": \"camera_look_at\", \"target\": {} }}",
+                body(target)
+            ),
+        }
+    }
+}
+
+// A body picked out in a constraint, by its name as a string or its index as a number.
+pub(crate) fn body_ref(value: Option<&Json>) -> Option<BodyRef> {
+    match value? {
+        Json::String(name) => Some(BodyRef::Name(name.clone())),
+        Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(BodyRef::Index(*n as usize)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        animator::{Key, Timeline, Track},
+        assert_fuzzy_eq,
+        fixtures::scene,
+        fuzzy_eq::FuzzyEq,
+    };
+
+    fn bouncing() -> Timeline {
+        Timeline::new(10).with_track(Track::Body {
+            body: BodyRef::Name("ball".to_string()),
+            keys: vec![
+                Key {
+                    frame: 0.0,
+                    value: Transform::default(),
+                },
+                Key {
+                    frame: 10.0,
+                    value: Transform::default().with_translation(0.0, 4.0, 0.0),
+                },
+            ],
+        })
+    }
+
+    fn name(name: &str) -> BodyRef {
+        BodyRef::Name(name.to_string())
+    }
+
+    fn origin(world: &World, name: &str) -> Point {
+        world.body_named(name).unwrap().transform() * Point::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn parented_things_follow_their_parent() {
+        let timeline = bouncing()
+            .with_constraint(Constraint::ParentLight {
+                light: 0,
+                parent: name("ball"),
+                offset: Point::new(0.0, 2.0, 0.0),
+            })
+            .with_constraint(Constraint::ParentBody {
+                body: name("moon"),
+                parent: name("ball"),
+                offset: Transform::default().with_translation(3.0, 0.0, 0.0),
+            });
+        let (mut world, camera) = scene();
+
+        timeline.apply(&mut world, camera, 5.0).unwrap();
+
+        match world.lights[0] {
+            Light::Point(l) => assert_fuzzy_eq!(Point::new(0.0, 4.0, 0.0), l.position),
+            Light::Directional(_) => panic!("expected a point light"),
+        }
+        assert_fuzzy_eq!(Point::new(3.0, 2.0, 0.0), origin(&world, "moon"));
+    }
+
+    #[test]
+    fn look_at_turns_a_body_towards_its_target_every_frame() {
+        let timeline = bouncing().with_constraint(Constraint::LookAt {
+            body: name("turret"),
+            target: name("ball"),
+        });
+        let (mut world, camera) = scene();
+        world.bodies[2] = world.bodies[2]
+            .clone()
+            .with_transform(Matrix::translate(0.0, 0.0, -4.0));
+
+        // Applying it again doesn't turn it any further.
+        for _ in 0..2 {
+            timeline
+                .apply(&mut world, Camera::new(1, 1, 1.0), 10.0)
+                .unwrap();
+            let turret = world.body_named("turret").unwrap().transform();
+            let forward = turret * Vector::new(0.0, 0.0, 1.0);
+            assert_fuzzy_eq!(Vector::new(0.0, 4.0, 4.0).normalize(), forward.normalize());
+            assert_fuzzy_eq!(Point::new(0.0, 0.0, -4.0), origin(&world, "turret"));
+        }
+
+        // The camera stays where it is and turns to face the ball.
+        let timeline = bouncing().with_constraint(Constraint::CameraLookAt {
+            target: name("ball"),
+        });
+        let camera = timeline.apply(&mut world, camera, 5.0).unwrap();
+        assert_fuzzy_eq!(
+            Point::new(0.0, 0.0, -5.0),
+            camera.transform.inverse() * Point::new(0.0, 0.0, 0.0)
+        );
+        let (x, y) = camera.project(Point::new(0.0, 2.0, 0.0)).unwrap();
+        assert_fuzzy_eq!(5.0, x);
+        assert_fuzzy_eq!(5.0, y);
+    }
+
+    #[test]
+    fn looking_straight_up_at_a_target_still_turns_towards_it() {
+        let (mut world, _) = scene();
+        let below = Camera::new(10, 10, 1.0).look_at_from_position(
+            Point::new(0.0, -5.0, 0.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let timeline = bouncing()
+            .with_constraint(Constraint::LookAt {
+                body: name("turret"),
+                target: name("ball"),
+            })
+            .with_constraint(Constraint::CameraLookAt {
+                target: name("ball"),
+            });
+
+        // The ball ends up right above the turret and the camera.
+        let camera = timeline.apply(&mut world, below, 10.0).unwrap();
+
+        let turret = world.body_named("turret").unwrap().transform();
+        let forward = turret * Vector::new(0.0, 0.0, 1.0);
+        assert_fuzzy_eq!(Vector::new(0.0, 1.0, 0.0), forward.normalize());
+        assert_fuzzy_eq!(Point::new(0.0, 0.0, 0.0), origin(&world, "turret"));
+        let (x, y) = camera.project(Point::new(0.0, 4.0, 0.0)).unwrap();
+        assert_fuzzy_eq!(5.0, x);
+        assert_fuzzy_eq!(5.0, y);
+    }
+
+    #[test]
+    fn constraints_survive_being_written_and_read() {
+        let timeline = bouncing()
+            .with_constraint(Constraint::ParentLight {
+                light: 0,
+                parent: BodyRef::Index(1),
+                offset: Point::new(0.0, 2.0, 0.0),
+            })
+            .with_constraint(Constraint::ParentBody {
+                body: name("moon"),
+                parent: name("ball"),
+                offset: Transform::default().with_scale(0.5, 0.5, 0.5),
+            })
+            .with_constraint(Constraint::LookAt {
+                body: name("turret"),
+                target: name("ball"),
+            })
+            .with_constraint(Constraint::CameraLookAt {
+                target: name("ball"),
+            });
+        let json = timeline.to_json();
+
+        let read = Timeline::from_json(&json).unwrap();
+
+        assert_eq!(4, read.constraints.len());
+        assert_eq!(json, read.to_json());
+        assert!(Timeline::from_json(
+            r#"{ "frame_count": 1, "constraints": [{ "constraint": "look_at", "body": -1, "target": "ball" }] }"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{Animator, Frame};
+use crate::canvas::{Canvas, ToPng};
+
+impl Frame {
+    pub fn difference_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("difference{:06}.png", self.current))
+    }
+}
+
+impl Animator {
+    // Like animate, for closures that return the frame they rendered. Every frame after the first
+    // gets a heat map of how much it changed since the frame before (see Canvas::difference)
+    // written into the directory, which shows where an animation flickers: anything that didn't
+    // move should stay black.
+    pub fn animate_with_differences<F>(&self, dir: &Path, gain: f64, animate: F) -> io::Result<()>
+    where
+        F: Fn(Frame) -> Canvas,
+    {
+        fs::create_dir_all(dir)?;
+        let mut previous: Option<Canvas> = None;
+        for current_frame in 0..self.frame_count {
+            let frame = Frame::new(current_frame, self.frame_count).with_shutter(self.shutter);
+            let canvas = animate(frame);
+            if let Some(previous) = &previous {
+                let f = fs::File::create(frame.difference_path(dir))?;
+                canvas
+                    .difference(previous, gain)
+                    .to_png(f)
+                    .map_err(io::Error::other)?;
+            }
+            previous = Some(canvas);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, color::Color, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn every_frame_after_the_first_gets_a_difference() {
+        let dir =
+            std::env::temp_dir().join(format!("raytracer-differences-{}", std::process::id()));
+        let animator = Animator::new(3);
+
+        animator
+            .animate_with_differences(&dir, 1.0, |frame| {
+                let mut canvas = Canvas::new(2, 2);
+                if frame.current == 2 {
+                    canvas.write_pixel(1, 1, Color::WHITE);
+                }
+                canvas
+            })
+            .unwrap();
+
+        let first = Frame::new(0, 3).difference_path(&dir).exists();
+        let read = |current| {
+            let f = fs::File::open(Frame::new(current, 3).difference_path(&dir)).unwrap();
+            Canvas::from_png(f).unwrap()
+        };
+        let (still, moved) = (read(1), read(2));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!first);
+        assert_fuzzy_eq!(Color::BLACK, still.read_pixel(1, 1));
+        assert_fuzzy_eq!(Color::WHITE, moved.read_pixel(1, 1));
+        assert_fuzzy_eq!(Color::BLACK, moved.read_pixel(0, 1));
+    }
+}
@@ -0,0 +1,307 @@
+// Just enough of a JSON reader for the animation files, which are small and written by hand or by
+// `to_json`, so errors only say what was expected and not where.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            at: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.at != parser.chars.len() {
+            return Err("unexpected text after the value".to_string());
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+// The string as a JSON string, quotes and all, escaped so that `Json::parse` reads it back.
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.at).is_some_and(|c| c.is_whitespace()) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.at) == Some(&c) {
+            self.at += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", c))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        let end = self.at + word.len();
+        if end <= self.chars.len() && self.chars[self.at..end].iter().copied().eq(word.chars()) {
+            self.at = end;
+            Ok(value)
+        } else {
+            Err(format!("expected {}", word))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.at) {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('n') => self.keyword("null", Json::Null),
+            Some(_) => self.number(),
+            None => Err("expected a value".to_string()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.at) == Some(&'}') {
+            self.at += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.chars.get(self.at) {
+                Some(',') => self.at += 1,
+                Some('}') => {
+                    self.at += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err("expected ',' or '}'".to_string()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.at) == Some(&']') {
+            self.at += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.get(self.at) {
+                Some(',') => self.at += 1,
+                Some(']') => {
+                    self.at += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err("expected ',' or ']'".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.get(self.at) {
+                Some('"') => {
+                    self.at += 1;
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    let escaped = match self.chars.get(self.at + 1) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some(&c @ ('"' | '\\' | '/')) => c,
+                        Some('u') => {
+                            self.at += 2;
+                            s.push(self.unicode_escape()?);
+                            continue;
+                        }
+                        _ => return Err("unsupported escape in string".to_string()),
+                    };
+                    s.push(escaped);
+                    self.at += 2;
+                }
+                Some(&c) => {
+                    s.push(c);
+                    self.at += 1;
+                }
+                None => return Err("unclosed string".to_string()),
+            }
+        }
+    }
+
+    // The character of a \u escape, just after the "\u". Characters outside of the basic plane are
+    // written as two escapes, a surrogate pair.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| "unpaired surrogate in string".to_string());
+        }
+        if self.chars.get(self.at) != Some(&'\\') || self.chars.get(self.at + 1) != Some(&'u') {
+            return Err("unpaired surrogate in string".to_string());
+        }
+        self.at += 2;
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err("unpaired surrogate in string".to_string());
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| "unpaired surrogate in string".to_string())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.chars.get(self.at..self.at + 4);
+        let Some(code) = digits.and_then(|d| {
+            d.iter()
+                .try_fold(0, |code, c| Some(code * 16 + c.to_digit(16)?))
+        }) else {
+            return Err("expected four hex digits after \\u".to_string());
+        };
+        self.at += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.at;
+        while self
+            .chars
+            .get(self.at)
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.at += 1;
+        }
+        let text: String = self.chars[start..self.at].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("'{}' is not a number", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_nested_values() {
+        let json =
+            Json::parse(r#" { "a": [1, -2.5e1, true, null], "b": { "c": "d\"e" } } "#).unwrap();
+
+        assert_eq!(
+            Some(&Json::Array(vec![
+                Json::Number(1.0),
+                Json::Number(-25.0),
+                Json::Bool(true),
+                Json::Null
+            ])),
+            json.get("a")
+        );
+        assert_eq!(
+            Some("d\"e"),
+            json.get("b").and_then(|b| b.get("c")?.as_str())
+        );
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("{\"a\" 1}").is_err());
+        assert!(Json::parse("[1] 2").is_err());
+        assert!(Json::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn parsing_escapes() {
+        let json = Json::parse(r#""a\r\b\f\u00e9\u20AC\ud83d\ude00""#).unwrap();
+
+        assert_eq!(Some("a\r\u{8}\u{c}é€😀"), json.as_str());
+        assert!(Json::parse(r#""\u12""#).is_err());
+        assert!(Json::parse(r#""\ud83d""#).is_err());
+        assert!(Json::parse(r#""\ude00""#).is_err());
+    }
+
+    #[test]
+    fn quoted_strings_read_back_the_same() {
+        for s in [
+            "plain",
+            "say \"hi\"",
+            "back\\slash",
+            "lines\r\n\ttab",
+            "\u{1}\u{7f}",
+            "é😀",
+        ] {
+            assert_eq!(Json::String(s.to_string()), Json::parse(&quote(s)).unwrap());
+        }
+        assert_eq!(r#""a\u0001""#, quote("a\u{1}"));
+    }
+}
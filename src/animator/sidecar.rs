@@ -0,0 +1,171 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use super::{Animator, Frame};
+use crate::{matrix::Matrix, rng::Rng};
+
+// What went into rendering one frame, written next to it as JSON so that other tools can tell
+// which frames exist and re-render any of them the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameMetadata {
+    pub frame: Frame,
+    pub camera_transform: Matrix<4>,
+    pub render_time: Duration,
+}
+
+impl Frame {
+    // A seed for anything random in this frame. It only depends on the frame number, so a frame
+    // comes out the same however many times it's rendered.
+    pub fn seed(&self) -> u64 {
+        Rng::new(self.current as u64).next_u64()
+    }
+
+    pub fn sidecar_path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("frame{:06}.json", self.current))
+    }
+}
+
+impl FrameMetadata {
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self
+            .camera_transform
+            .as_rows()
+            .iter()
+            .map(|row| format!("[{}, {}, {}, {}]", row[0], row[1], row[2], row[3]))
+            .collect();
+        format!(
+            concat!(
+                "{{\n",
+                "  \"frame\": {},\n",
+                "  \"frame_count\": {},\n",
+                "  \"time\": {},\n",
+                "  \"seed\": {},\n",
+                "  \"shutter\": {{ \"samples\": {}, \"open\": {} }},\n",
+                "  \"camera_transform\": [{}],\n",
+                "  \"render_seconds\": {}\n",
+                "}}\n"
+            ),
+            self.frame.current,
+            self.frame.count,
+            self.frame.time,
+            self.frame.seed(),
+            self.frame.shutter.samples,
+            self.frame.shutter.open,
+            rows.join(", "),
+            self.render_time.as_secs_f64(),
+        )
+    }
+}
+
+impl Animator {
+    // Like animate, but writes a JSON sidecar for each frame and a manifest for the whole
+    // sequence into the given directory. The closure returns the camera transform it rendered
+    // the frame with, or None if it skipped the frame (in which case any sidecar from an earlier
+    // run is kept).
+    pub fn animate_with_sidecars<F>(&self, dir: &Path, animate: F) -> io::Result<()>
+    where
+        F: Fn(Frame) -> Option<Matrix<4>>,
+    {
+        fs::create_dir_all(dir)?;
+        let mut frames = Vec::with_capacity(self.frame_count);
+        for current_frame in 0..self.frame_count {
+            let frame = Frame::new(current_frame, self.frame_count).with_shutter(self.shutter);
+            let start = Instant::now();
+            if let Some(camera_transform) = animate(frame) {
+                let metadata = FrameMetadata {
+                    frame,
+                    camera_transform,
+                    render_time: start.elapsed(),
+                };
+                fs::write(frame.sidecar_path(dir), metadata.to_json())?;
+            }
+            frames.push(frame);
+        }
+        self.write_manifest(dir, &frames)
+    }
+
+    // Lists every frame that has a sidecar.
+    fn write_manifest(&self, dir: &Path, frames: &[Frame]) -> io::Result<()> {
+        let sidecars: Vec<String> = frames
+            .iter()
+            .map(|frame| frame.sidecar_path(dir))
+            .filter(|path| path.exists())
+            .filter_map(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .map(|name| format!("\"{}\"", name))
+            .collect();
+
+        let mut f = fs::File::create(dir.join("manifest.json"))?;
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"frame_count\": {},", self.frame_count)?;
+        writeln!(
+            f,
+            "  \"shutter\": {{ \"samples\": {}, \"open\": {} }},",
+            self.shutter.samples, self.shutter.open
+        )?;
+        writeln!(f, "  \"frames\": [{}]", sidecars.join(", "))?;
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_seeds_are_stable_and_differ_between_frames() {
+        assert_eq!(Frame::new(3, 10).seed(), Frame::new(3, 10).seed());
+        assert_ne!(Frame::new(3, 10).seed(), Frame::new(4, 10).seed());
+    }
+
+    #[test]
+    fn metadata_is_written_as_json() {
+        let metadata = FrameMetadata {
+            frame: Frame::new(2, 5),
+            camera_transform: Matrix::translate(1.0, 2.0, 3.0),
+            render_time: Duration::from_millis(1500),
+        };
+
+        let json = metadata.to_json();
+
+        assert!(json.contains("\"frame\": 2,"));
+        assert!(json.contains("\"frame_count\": 5,"));
+        assert!(json.contains(&format!("\"seed\": {},", Frame::new(2, 5).seed())));
+        assert!(json.contains(
+            "\"camera_transform\": [[1, 0, 0, 1], [0, 1, 0, 2], [0, 0, 1, 3], [0, 0, 0, 1]],"
+        ));
+        assert!(json.contains("\"render_seconds\": 1.5"));
+    }
+
+    #[test]
+    fn animating_with_sidecars_writes_one_per_rendered_frame_and_a_manifest() {
+        let dir = std::env::temp_dir().join(format!("raytracer-sidecars-{}", std::process::id()));
+        let animator = Animator::new(3);
+
+        animator
+            .animate_with_sidecars(&dir, |frame| {
+                if frame.current == 1 {
+                    None
+                } else {
+                    Some(Matrix::identity())
+                }
+            })
+            .unwrap();
+
+        let manifest = fs::read_to_string(dir.join("manifest.json")).unwrap();
+        let skipped = Frame::new(1, 3).sidecar_path(&dir).exists();
+        let rendered = Frame::new(2, 3).sidecar_path(&dir).exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!skipped);
+        assert!(rendered);
+        assert!(manifest.contains("\"frame_count\": 3,"));
+        assert!(manifest.contains("\"frames\": [\"frame000000.json\", \"frame000002.json\"]"));
+    }
+}
@@ -0,0 +1,262 @@
+use std::{error::Error, fmt};
+
+use super::{Frame, Interpolate, Keyframes};
+use crate::vector::Vector;
+
+// Data to drive an animation with, e.g. the loudness of a song over time. There's one sample per
+// frame and each sample has the same number of channels; in between frames the samples are
+// blended.
+#[derive(Clone, Debug)]
+pub struct Signal {
+    samples: Vec<Vec<f64>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseSignalError {
+    Empty,
+    InvalidNumber { line: usize, value: String },
+    MismatchedChannels { line: usize },
+    InvalidJson(String),
+}
+
+impl fmt::Display for ParseSignalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSignalError::Empty => write!(f, "the signal has no samples"),
+            ParseSignalError::InvalidNumber { line, value } => {
+                write!(f, "line {}: '{}' is not a number", line, value)
+            }
+            ParseSignalError::MismatchedChannels { line } => write!(
+                f,
+                "line {}: every sample must have the same number of channels",
+                line
+            ),
+            ParseSignalError::InvalidJson(s) => write!(f, "invalid signal json: {}", s),
+        }
+    }
+}
+
+impl Error for ParseSignalError {}
+
+impl Signal {
+    pub fn new(samples: Vec<Vec<f64>>) -> Self {
+        if samples.is_empty() || samples[0].is_empty() {
+            panic!("a signal needs at least one sample")
+        }
+        if samples.iter().any(|s| s.len() != samples[0].len()) {
+            panic!("every sample must have the same number of channels")
+        }
+        Self { samples }
+    }
+
+    // One sample per line, with the channels separated by commas. A first line that isn't numbers
+    // is taken to be a header and skipped.
+    pub fn from_csv(csv: &str) -> Result<Self, ParseSignalError> {
+        let mut samples: Vec<Vec<f64>> = Vec::new();
+        for (i, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parsed: Result<Vec<f64>, String> = line
+                .split(',')
+                .map(|v| v.trim().parse::<f64>().map_err(|_| v.trim().to_string()))
+                .collect();
+            match parsed {
+                Ok(sample) => {
+                    if samples
+                        .first()
+                        .is_some_and(|first| first.len() != sample.len())
+                    {
+                        return Err(ParseSignalError::MismatchedChannels { line: i + 1 });
+                    }
+                    samples.push(sample);
+                }
+                Err(_) if i == 0 => continue,
+                Err(value) => return Err(ParseSignalError::InvalidNumber { line: i + 1, value }),
+            }
+        }
+        if samples.is_empty() {
+            return Err(ParseSignalError::Empty);
+        }
+        Ok(Self { samples })
+    }
+
+    // Either an array of numbers, for a signal with one channel, or an array of arrays of numbers.
+    pub fn from_json(json: &str) -> Result<Self, ParseSignalError> {
+        let invalid = |s: &str| ParseSignalError::InvalidJson(s.to_string());
+        let outer = json
+            .trim()
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| invalid("expected an array"))?
+            .trim();
+        if outer.is_empty() {
+            return Err(ParseSignalError::Empty);
+        }
+
+        let parse_numbers = |s: &str| -> Result<Vec<f64>, ParseSignalError> {
+            s.split(',')
+                .map(|v| v.trim().parse::<f64>().map_err(|_| invalid(v.trim())))
+                .collect()
+        };
+
+        let samples: Vec<Vec<f64>> = if outer.starts_with('[') {
+            let mut samples = Vec::new();
+            let mut rest = outer;
+            while let Some(start) = rest.find('[') {
+                let end = rest[start..]
+                    .find(']')
+                    .ok_or_else(|| invalid("unclosed array"))?
+                    + start;
+                samples.push(parse_numbers(&rest[start + 1..end])?);
+                rest = &rest[end + 1..];
+            }
+            samples
+        } else {
+            parse_numbers(outer)?.into_iter().map(|v| vec![v]).collect()
+        };
+
+        if samples.iter().any(|s| s.len() != samples[0].len()) {
+            return Err(ParseSignalError::MismatchedChannels { line: 1 });
+        }
+        Ok(Self { samples })
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn channels(&self) -> usize {
+        self.samples[0].len()
+    }
+
+    // Every channel at the given time, measured in frames. Times past either end of the signal
+    // get the first or last sample.
+    pub fn at(&self, time: f64) -> Vec<f64> {
+        let position = time.clamp(0.0, (self.len() - 1) as f64);
+        let index = (position.floor() as usize).min(self.len().saturating_sub(2));
+        if self.len() == 1 {
+            return self.samples[0].clone();
+        }
+        let t = position - index as f64;
+        self.samples[index]
+            .iter()
+            .zip(self.samples[index + 1].iter())
+            .map(|(a, b)| a.interpolate(b, t))
+            .collect()
+    }
+
+    pub fn scalar_at(&self, time: f64) -> f64 {
+        self.at(time)[0]
+    }
+
+    // The first three channels as a vector.
+    pub fn vector_at(&self, time: f64) -> Vector {
+        if self.channels() < 3 {
+            panic!("a vector signal needs at least three channels")
+        }
+        let v = self.at(time);
+        Vector::new(v[0], v[1], v[2])
+    }
+
+    // The smallest and largest values the channel takes.
+    pub fn range(&self, channel: usize) -> (f64, f64) {
+        self.samples
+            .iter()
+            .map(|s| s[channel])
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            })
+    }
+
+    // Keyframes spread over the range of the channel, so the quietest sample picks the first value
+    // and the loudest the last. Look them up with the channel's value, e.g.
+    // `keyframes.at(signal.scalar_at(frame.time()))`.
+    pub fn keyframes<T: Interpolate>(&self, channel: usize, values: Vec<T>) -> Keyframes<T> {
+        Keyframes::new(self.range(channel), values)
+    }
+}
+
+impl Frame {
+    // The first channel of the signal at this frame's time.
+    pub fn signal(&self, signal: &Signal) -> f64 {
+        signal.scalar_at(self.time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn reading_a_signal_from_csv() {
+        let s = Signal::from_csv("x,y,z\n0, 1, 2\n\n3,4,5\n").unwrap();
+
+        assert_eq!(2, s.len());
+        assert_eq!(3, s.channels());
+        assert_fuzzy_eq!(Vector::new(1.5, 2.5, 3.5), s.vector_at(0.5));
+    }
+
+    #[test]
+    fn invalid_csv_signals() {
+        assert_eq!(
+            Err(ParseSignalError::InvalidNumber {
+                line: 2,
+                value: "loud".to_string()
+            }),
+            Signal::from_csv("1\nloud\n").map(|s| s.len())
+        );
+        assert_eq!(
+            Err(ParseSignalError::MismatchedChannels { line: 2 }),
+            Signal::from_csv("1,2\n3\n").map(|s| s.len())
+        );
+        assert_eq!(
+            Err(ParseSignalError::Empty),
+            Signal::from_csv("amplitude\n").map(|s| s.len())
+        );
+    }
+
+    #[test]
+    fn reading_a_signal_from_json() {
+        let scalar = Signal::from_json("[0.0, 0.5, 1.0]").unwrap();
+        let vector = Signal::from_json(" [[0, 0, 0], [2, 4, 6]] ").unwrap();
+
+        assert_eq!(1, scalar.channels());
+        assert_fuzzy_eq!(0.75, scalar.scalar_at(1.5));
+        assert_eq!(3, vector.channels());
+        assert_fuzzy_eq!(Vector::new(1.0, 2.0, 3.0), vector.vector_at(0.5));
+        assert!(Signal::from_json("{\"a\": 1}").is_err());
+        assert!(Signal::from_json("[1, \"two\"]").is_err());
+    }
+
+    #[test]
+    fn times_past_the_ends_clamp_to_the_first_and_last_samples() {
+        let s = Signal::new(vec![vec![1.0], vec![2.0], vec![4.0]]);
+
+        assert_fuzzy_eq!(1.0, s.scalar_at(-3.0));
+        assert_fuzzy_eq!(4.0, s.scalar_at(10.0));
+        assert_fuzzy_eq!(3.0, s.scalar_at(1.5));
+    }
+
+    #[test]
+    fn binding_a_signal_to_keyframes() {
+        let s = Signal::new(vec![vec![2.0], vec![6.0], vec![4.0]]);
+        let scale = s.keyframes(0, vec![1.0, 3.0]);
+
+        assert_fuzzy_eq!(1.0, scale.at(Frame::new(0, 3).signal(&s)));
+        assert_fuzzy_eq!(3.0, scale.at(Frame::new(1, 3).signal(&s)));
+        assert_fuzzy_eq!(2.0, scale.at(Frame::new(2, 3).signal(&s)));
+    }
+
+    #[test]
+    #[should_panic(expected = "every sample must have the same number of channels")]
+    fn samples_must_have_the_same_channels() {
+        Signal::new(vec![vec![1.0], vec![1.0, 2.0]]);
+    }
+}
@@ -0,0 +1,627 @@
+use std::{error::Error, fmt};
+
+use super::{
+    constraint::{body_ref, Constraint},
+    json::{self, Json},
+    Animator, Interpolate, Shutter,
+};
+use crate::{
+    camera::Camera,
+    light::Light,
+    point::Point,
+    transform::{Transform, Transformable},
+    vector::Vector,
+    world::World,
+};
+
+// A keyed value at a point in the animation, measured in frames like `Frame::time`.
+#[derive(Clone, Copy, Debug)]
+pub struct Key<T> {
+    pub frame: f64,
+    pub value: T,
+}
+
+// Where the camera is and what it looks at. Up is always up the y axis.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraKey {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Interpolate for CameraKey {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        Self {
+            from: self.from.interpolate(&other.from, t),
+            to: self.to.interpolate(&other.to, t),
+        }
+    }
+}
+
+// Picks out a body to animate, by name if it has one or else by where it is in the world's bodies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BodyRef {
+    Name(String),
+    Index(usize),
+}
+
+impl BodyRef {
+    pub(crate) fn index_in(&self, world: &World) -> Result<usize, TimelineError> {
+        match self {
+            BodyRef::Name(name) => world.body_id(name),
+            BodyRef::Index(index) => Some(*index).filter(|&i| i < world.bodies.len()),
+        }
+        .ok_or_else(|| TimelineError::NoSuchBody(self.clone()))
+    }
+}
+
+// The keys for one thing in the scene. Keys hold the whole value rather than a change to it, so
+// applying the same frame twice does nothing more, and anything left out of the timeline stays as
+// the scene put it.
+#[derive(Clone, Debug)]
+pub enum Track {
+    Body {
+        body: BodyRef,
+        keys: Vec<Key<Transform>>,
+    },
+    // The position of a point light, by its index in the world's lights.
+    Light {
+        index: usize,
+        keys: Vec<Key<Point>>,
+    },
+    Camera {
+        keys: Vec<Key<CameraKey>>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimelineError {
+    InvalidJson(String),
+    NoSuchBody(BodyRef),
+    NoSuchPointLight(usize),
+}
+
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineError::InvalidJson(s) => write!(f, "invalid timeline json: {}", s),
+            TimelineError::NoSuchBody(BodyRef::Name(name)) => {
+                write!(f, "there is no body named {:?}", name)
+            }
+            TimelineError::NoSuchBody(BodyRef::Index(index)) => {
+                write!(f, "there is no body {}", index)
+            }
+            TimelineError::NoSuchPointLight(index) => {
+                write!(f, "there is no point light {}", index)
+            }
+        }
+    }
+}
+
+impl Error for TimelineError {}
+
+// The value at the time, blending the keys on either side of it. Before the first key and after
+// the last one the value holds still. The keys must be in order.
+fn value_at<T: Interpolate + Copy>(keys: &[Key<T>], time: f64) -> T {
+    let after = keys.iter().position(|k| k.frame > time);
+    match after {
+        Some(0) => keys[0].value,
+        Some(i) => {
+            let (a, b) = (&keys[i - 1], &keys[i]);
+            a.value
+                .interpolate(&b.value, (time - a.frame) / (b.frame - a.frame))
+        }
+        None => keys[keys.len() - 1].value,
+    }
+}
+
+// A whole animation as data: how many frames it has and how the bodies, lights and camera move,
+// so that it can be saved, edited and rendered without writing code for it.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    pub frame_count: usize,
+    pub shutter: Shutter,
+    pub tracks: Vec<Track>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Timeline {
+    pub fn new(frame_count: usize) -> Self {
+        Self {
+            frame_count,
+            shutter: Shutter::default(),
+            tracks: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn with_shutter(self, samples: usize, open: f64) -> Self {
+        let animator = Animator::new(self.frame_count).with_shutter(samples, open);
+        Self {
+            shutter: animator.shutter,
+            ..self
+        }
+    }
+
+    pub fn with_track(mut self, track: Track) -> Self {
+        let empty = match &track {
+            Track::Body { keys, .. } => keys.is_empty(),
+            Track::Light { keys, .. } => keys.is_empty(),
+            Track::Camera { keys } => keys.is_empty(),
+        };
+        if empty {
+            panic!("a track needs at least one key")
+        }
+        self.tracks.push(track);
+        self
+    }
+
+    pub fn with_constraint(mut self, constraint: Constraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    // An animator for rendering the timeline's frames.
+    pub fn animator(&self) -> Animator {
+        Animator {
+            frame_count: self.frame_count,
+            shutter: self.shutter,
+        }
+    }
+
+    // Moves everything with a track to where it is at the time and then applies the constraints,
+    // returning the camera moved too.
+    pub fn apply(
+        &self,
+        world: &mut World,
+        camera: Camera,
+        time: f64,
+    ) -> Result<Camera, TimelineError> {
+        let mut camera = camera;
+        for track in &self.tracks {
+            match track {
+                Track::Body { body, keys } => {
+                    let index = body.index_in(world)?;
+                    world.bodies[index] = world.bodies[index]
+                        .clone()
+                        .with_transform(value_at(keys, time).matrix());
+                }
+                Track::Light { index, keys } => match world.lights.get_mut(*index) {
+                    Some(Light::Point(light)) => light.position = value_at(keys, time),
+                    _ => return Err(TimelineError::NoSuchPointLight(*index)),
+                },
+                Track::Camera { keys } => {
+                    let key = value_at(keys, time);
+                    camera =
+                        camera.look_at_from_position(key.from, key.to, Vector::new(0.0, 1.0, 0.0));
+                }
+            }
+        }
+        for constraint in &self.constraints {
+            camera = constraint.apply(world, camera)?;
+        }
+        Ok(camera)
+    }
+
+    pub fn to_json(&self) -> String {
+        let triple = |v: [f64; 3]| format!("[{}, {}, {}]", v[0], v[1], v[2]);
+        let point = |p: Point| triple([p[0], p[1], p[2]]);
+        let vector = |v: Vector| triple([v[0], v[1], v[2]]);
+        let keys = |keys: Vec<String>| format!("[\n      {}\n    ]", keys.join(",\n      "));
+
+        let tracks: Vec<String> = self
+            .tracks
+            .iter()
+            .map(|track| match track {
+                Track::Body { body, keys: k } => {
+                    let body = match body {
+                        BodyRef::Name(name) => format!("\"name\": {}", json::quote(name)),
+                        BodyRef::Index(index) => format!("\"index\": {}", index),
+                    };
+                    let k = k
+                        .iter()
+                        .map(|k| {
+                            format!(
+                                "{{ \"frame\": {}, \"translation\": {}, \"rotation\": {}, \"scale\": {} }}",
+                                k.frame,
+                                vector(k.value.translation),
+                                vector(k.value.rotation),
+                                vector(k.value.scale)
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "{{\n    \"target\": \"body\",\n    {},\n    \"keys\": {}\n  }}",
+                        body,
+                        keys(k)
+                    )
+                }
+                Track::Light { index, keys: k } => {
+                    let k = k
+                        .iter()
+                        .map(|k| {
+                            format!(
+                                "{{ \"frame\": {}, \"position\": {} }}",
+                                k.frame,
+                                point(k.value)
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "{{\n    \"target\": \"light\",\n    \"index\": {},\n    \"keys\": {}\n  }}",
+                        index,
+                        keys(k)
+                    )
+                }
+                Track::Camera { keys: k } => {
+                    let k = k
+                        .iter()
+                        .map(|k| {
+                            format!(
+                                "{{ \"frame\": {}, \"from\": {}, \"to\": {} }}",
+                                k.frame,
+                                point(k.value.from),
+                                point(k.value.to)
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "{{\n    \"target\": \"camera\",\n    \"keys\": {}\n  }}",
+                        keys(k)
+                    )
+                }
+            })
+            .collect();
+
+        let constraints: Vec<String> = self.constraints.iter().map(Constraint::to_json).collect();
+
+        format!(
+            "{{\n  \"frame_count\": {},\n  \"shutter\": {{ \"samples\": {}, \"open\": {} }},\n  \"tracks\": [\n  {}\n  ],\n  \"constraints\": [\n    {}\n  ]\n}}\n",
+            self.frame_count,
+            self.shutter.samples,
+            self.shutter.open,
+            tracks.join(",\n  "),
+            constraints.join(",\n    ")
+        )
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, TimelineError> {
+        let json = Json::parse(json).map_err(TimelineError::InvalidJson)?;
+        let invalid = |s: &str| TimelineError::InvalidJson(s.to_string());
+        let number = |value: &Json, key: &str| {
+            value
+                .get(key)
+                .and_then(Json::as_f64)
+                .ok_or_else(|| invalid(&format!("expected a number for \"{}\"", key)))
+        };
+        let count = |value: &Json, key: &str| {
+            number(value, key).and_then(|n| {
+                if n >= 0.0 && n.fract() == 0.0 {
+                    Ok(n as usize)
+                } else {
+                    Err(invalid(&format!("expected a whole number for \"{}\"", key)))
+                }
+            })
+        };
+        let triple = |value: &Json, key: &str, default: [f64; 3]| -> Result<[f64; 3], _> {
+            let values = match value.get(key) {
+                Some(v) => v.as_array().unwrap_or(&[]),
+                None => return Ok(default),
+            };
+            match values
+                .iter()
+                .map(Json::as_f64)
+                .collect::<Option<Vec<f64>>>()
+            {
+                Some(v) if v.len() == 3 => Ok([v[0], v[1], v[2]]),
+                _ => Err(invalid(&format!("expected three numbers for \"{}\"", key))),
+            }
+        };
+        let point = |value: &Json, key: &str| {
+            let [x, y, z] = triple(value, key, [f64::NAN; 3])?;
+            if x.is_nan() {
+                return Err(invalid(&format!("expected \"{}\"", key)));
+            }
+            Ok(Point::new(x, y, z))
+        };
+        let vector = |value: &Json, key: &str, default: [f64; 3]| {
+            triple(value, key, default).map(|[x, y, z]| Vector::new(x, y, z))
+        };
+        // A transform with whichever of its parts are given.
+        let transform = |value: &Json| {
+            Ok(Transform {
+                translation: vector(value, "translation", [0.0; 3])?,
+                rotation: vector(value, "rotation", [0.0; 3])?,
+                scale: vector(value, "scale", [1.0; 3])?,
+            })
+        };
+        fn keys_of<T>(
+            track: &Json,
+            key: impl Fn(&Json) -> Result<T, TimelineError>,
+            invalid: impl Fn(&str) -> TimelineError,
+        ) -> Result<Vec<Key<T>>, TimelineError> {
+            let keys = track
+                .get("keys")
+                .and_then(Json::as_array)
+                .filter(|keys| !keys.is_empty())
+                .ok_or_else(|| invalid("expected a track to have keys"))?;
+            let mut out = Vec::with_capacity(keys.len());
+            for k in keys {
+                let frame = k
+                    .get("frame")
+                    .and_then(Json::as_f64)
+                    .ok_or_else(|| invalid("expected a number for \"frame\""))?;
+                if out.last().is_some_and(|last: &Key<T>| last.frame >= frame) {
+                    return Err(invalid("expected keys in order of frame"));
+                }
+                out.push(Key {
+                    frame,
+                    value: key(k)?,
+                });
+            }
+            Ok(out)
+        }
+
+        let mut timeline = Timeline::new(count(&json, "frame_count")?);
+        if let Some(shutter) = json.get("shutter") {
+            let samples = count(shutter, "samples")?;
+            let open = number(shutter, "open")?;
+            if samples == 0 || !(0.0..=1.0).contains(&open) {
+                return Err(invalid(
+                    "expected a shutter with samples and open in [0, 1]",
+                ));
+            }
+            timeline = timeline.with_shutter(samples, open);
+        }
+
+        let tracks = match json.get("tracks") {
+            Some(tracks) => tracks
+                .as_array()
+                .ok_or_else(|| invalid("expected \"tracks\" to be an array"))?,
+            None => &[],
+        };
+        for track in tracks {
+            let track = match track.get("target").and_then(Json::as_str) {
+                Some("body") => {
+                    let body = match (track.get("name").and_then(Json::as_str), track.get("index"))
+                    {
+                        (Some(name), _) => BodyRef::Name(name.to_string()),
+                        (None, Some(_)) => BodyRef::Index(count(track, "index")?),
+                        (None, None) => return Err(invalid("expected a body's name or index")),
+                    };
+                    let keys = keys_of(track, transform, invalid)?;
+                    Track::Body { body, keys }
+                }
+                Some("light") => Track::Light {
+                    index: count(track, "index")?,
+                    keys: keys_of(track, |k| point(k, "position"), invalid)?,
+                },
+                Some("camera") => Track::Camera {
+                    keys: keys_of(
+                        track,
+                        |k| {
+                            Ok(CameraKey {
+                                from: point(k, "from")?,
+                                to: point(k, "to")?,
+                            })
+                        },
+                        invalid,
+                    )?,
+                },
+                _ => {
+                    return Err(invalid(
+                        "expected a track's target to be body, light or camera",
+                    ))
+                }
+            };
+            timeline = timeline.with_track(track);
+        }
+
+        let constraints = match json.get("constraints") {
+            Some(constraints) => constraints
+                .as_array()
+                .ok_or_else(|| invalid("expected \"constraints\" to be an array"))?,
+            None => &[],
+        };
+        for c in constraints {
+            let body = |key: &str| {
+                body_ref(c.get(key)).ok_or_else(|| {
+                    invalid(&format!("expected a body's name or index for \"{}\"", key))
+                })
+            };
+            let constraint = match c.get("constraint").and_then(Json::as_str) {
+                Some("parent") if c.get("light").is_some() => Constraint::ParentLight {
+                    light: count(c, "light")?,
+                    parent: body("parent")?,
+                    offset: point(c, "position")?,
+                },
+                Some("parent") => Constraint::ParentBody {
+                    body: body("body")?,
+                    parent: body("parent")?,
+                    offset: transform(c)?,
+                },
+                Some("look_at") => Constraint::LookAt {
+                    body: body("body")?,
+                    target: body("target")?,
+                },
+                Some("camera_look_at") => Constraint::CameraLookAt {
+                    target: body("target")?,
+                },
+                _ => {
+                    return Err(invalid(
+                        "expected a constraint to be parent, look_at or camera_look_at",
+                    ))
+                }
+            };
+            timeline = timeline.with_constraint(constraint);
+        }
+        Ok(timeline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_3;
+
+    use super::*;
+    use crate::{assert_fuzzy_eq, fixtures::scene, fuzzy_eq::FuzzyEq, matrix::Matrix};
+
+    fn timeline() -> Timeline {
+        Timeline::new(20)
+            .with_shutter(2, 0.5)
+            .with_track(Track::Body {
+                body: BodyRef::Name("ball".to_string()),
+                keys: vec![
+                    Key {
+                        frame: 0.0,
+                        value: Transform::default(),
+                    },
+                    Key {
+                        frame: 10.0,
+                        value: Transform::default().with_translation(4.0, 0.0, 0.0),
+                    },
+                ],
+            })
+            .with_track(Track::Light {
+                index: 0,
+                keys: vec![
+                    Key {
+                        frame: 5.0,
+                        value: Point::new(0.0, 10.0, 0.0),
+                    },
+                    Key {
+                        frame: 15.0,
+                        value: Point::new(10.0, 10.0, 0.0),
+                    },
+                ],
+            })
+            .with_track(Track::Camera {
+                keys: vec![Key {
+                    frame: 0.0,
+                    value: CameraKey {
+                        from: Point::new(0.0, 1.0, -5.0),
+                        to: Point::new(0.0, 1.0, 0.0),
+                    },
+                }],
+            })
+    }
+
+    fn light_position(world: &World) -> Point {
+        match world.lights[0] {
+            Light::Point(l) => l.position,
+            Light::Directional(_) => panic!("expected a point light"),
+        }
+    }
+
+    #[test]
+    fn applying_a_timeline_moves_what_it_has_tracks_for() {
+        let (mut world, camera) = scene();
+        let ball = world.body_id("ball").unwrap();
+        let moon = world.body_id("moon").unwrap();
+
+        let camera = timeline().apply(&mut world, camera, 2.5).unwrap();
+
+        assert_fuzzy_eq!(
+            Matrix::translate(1.0, 0.0, 0.0),
+            world.bodies[ball].transform()
+        );
+        // The light's keys haven't started yet, and the other body has no track.
+        assert_fuzzy_eq!(Point::new(0.0, 10.0, 0.0), light_position(&world));
+        assert_fuzzy_eq!(Matrix::<4>::identity(), world.bodies[moon].transform());
+        assert_fuzzy_eq!(
+            Point::new(0.0, 1.0, -5.0),
+            camera.transform.inverse() * Point::new(0.0, 0.0, 0.0)
+        );
+
+        timeline()
+            .apply(&mut world, Camera::new(10, 10, FRAC_PI_3), 20.0)
+            .unwrap();
+        assert_fuzzy_eq!(
+            Matrix::translate(4.0, 0.0, 0.0),
+            world.bodies[ball].transform()
+        );
+        assert_fuzzy_eq!(Point::new(10.0, 10.0, 0.0), light_position(&world));
+    }
+
+    #[test]
+    fn tracks_for_missing_things_are_errors() {
+        let (mut world, camera) = scene();
+        let timeline = Timeline::new(1).with_track(Track::Light {
+            index: 3,
+            keys: vec![Key {
+                frame: 0.0,
+                value: Point::new(0.0, 0.0, 0.0),
+            }],
+        });
+
+        assert!(matches!(
+            timeline.apply(&mut world, camera, 0.0),
+            Err(TimelineError::NoSuchPointLight(3))
+        ));
+    }
+
+    #[test]
+    fn a_timeline_survives_being_written_and_read() {
+        let json = timeline().to_json();
+
+        let read = Timeline::from_json(&json).unwrap();
+
+        assert_eq!(json, read.to_json());
+        assert_eq!(20, read.animator().frame_count);
+        assert_eq!(2, read.animator().shutter.samples);
+    }
+
+    #[test]
+    fn body_names_are_escaped_when_written() {
+        let name = "a \"quoted\"\\name\u{1}";
+        let timeline = Timeline::new(1).with_track(Track::Body {
+            body: BodyRef::Name(name.to_string()),
+            keys: vec![Key {
+                frame: 0.0,
+                value: Transform::default(),
+            }],
+        });
+
+        let read = Timeline::from_json(&timeline.to_json()).unwrap();
+
+        assert!(matches!(
+            &read.tracks[0],
+            Track::Body { body: BodyRef::Name(n), .. } if n == name
+        ));
+    }
+
+    #[test]
+    fn keys_can_leave_out_parts_of_a_transform() {
+        let read = Timeline::from_json(
+            r#"{ "frame_count": 4, "tracks": [
+                { "target": "body", "index": 0, "keys": [{ "frame": 0, "translation": [1, 2, 3] }] }
+            ] }"#,
+        )
+        .unwrap();
+        let (mut world, camera) = scene();
+
+        read.apply(&mut world, camera, 0.0).unwrap();
+
+        assert_fuzzy_eq!(
+            Matrix::translate(1.0, 2.0, 3.0),
+            world.bodies[0].transform()
+        );
+    }
+
+    #[test]
+    fn malformed_timelines_are_errors() {
+        for json in &[
+            "[]",
+            r#"{ "frame_count": 1.5 }"#,
+            r#"{ "frame_count": 1, "tracks": [{ "target": "sun", "keys": [] }] }"#,
+            r#"{ "frame_count": 1, "tracks": [{ "target": "camera", "keys": [] }] }"#,
+            r#"{ "frame_count": 1, "tracks": [{ "target": "light", "index": 0, "keys": [
+                { "frame": 1, "position": [0, 0, 0] }, { "frame": 0, "position": [0, 0, 0] }
+            ] }] }"#,
+            r#"{ "frame_count": 1, "tracks": [{ "target": "light", "index": 0, "keys": [
+                { "frame": 0, "position": [0, 0] }
+            ] }] }"#,
+        ] {
+            assert!(Timeline::from_json(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -1,23 +1,25 @@
 use std::{
     f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4, PI},
     fs,
+    path::Path,
     process::Command,
-    sync::Mutex,
 };
 
-use itertools::Itertools;
-use rayon::prelude::*;
 use raytracer::{
-    animator::Animator,
+    animator::{Animator, Frame},
     aspect,
     camera::Camera,
-    canvas::{Canvas, ToPng},
+    canvas::ToPng,
     color::Color,
     light::PointLight,
-    material::Phong,
+    material::{Material, Phong},
     matrix::{Matrix, Rotation},
+    pattern::Pattern,
     point::Point,
+    profile,
+    render::{self, CancellationToken, RenderSettings},
     sphere::Sphere,
+    transform::Transform,
     vector::Vector,
     world::World,
 };
@@ -26,115 +28,27 @@ fn main() {
     let (canvas_width, canvas_height) = aspect::SIZE_1080P;
     let frame_rate = 60;
     let animation_time = 5;
-    let animator = Animator::new(frame_rate * animation_time);
-    animator.animate(|frame| {
-        let light_rotation_scale = frame.linear_scale().with_breakpoints(vec![0.0, PI * 2.0]);
-        let light_transformation_matrix = Matrix::rotate(
-            Rotation::Y,
-            light_rotation_scale.scale(frame.current as f64),
-        );
+    // Motion blur: average four snapshots taken over the first half of each frame.
+    let animator = Animator::new(frame_rate * animation_time).with_shutter(4, 0.5);
 
-        let light = PointLight::new(
-            light_transformation_matrix * Point::new(-2.0, 4.0, -2.0),
-            Color::new(1.0, 1.0, 1.0),
-        );
+    // Stop rendering on Ctrl-C. The frame in progress is saved along with a checkpoint, and the
+    // remaining frames are skipped.
+    let cancellation_token = CancellationToken::new();
+    let handler_token = cancellation_token.clone();
+    ctrlc::set_handler(move || handler_token.cancel()).expect("error setting Ctrl-C handler");
 
-        // Floor and walls. Cheat by using squashed spheres...
-        let floor_and_wall_material = Phong {
-            color: Color::new(0.5, 0.45, 0.45),
-            specular: 0.0,
-            ..Phong::default()
-        }
-        .into();
-
-        let floor_sphere = Sphere::default()
-            .with_material(floor_and_wall_material)
-            .with_transform(Matrix::scale(10.0, 0.01, 10.0));
-
-        let left_wall_sphere = Sphere::default()
-            .with_material(floor_and_wall_material)
-            .with_transform(
-                Matrix::translate(0.0, 0.0, 5.0)
-                    * Matrix::rotate(Rotation::Y, -FRAC_PI_4)
-                    * Matrix::rotate(Rotation::X, FRAC_PI_2)
-                    * Matrix::scale(10.0, 0.01, 10.0),
-            );
-
-        let right_wall_sphere = Sphere::default()
-            .with_material(floor_and_wall_material)
-            .with_transform(
-                Matrix::translate(0.0, 0.0, 5.0)
-                    * Matrix::rotate(Rotation::Y, FRAC_PI_4)
-                    * Matrix::rotate(Rotation::X, FRAC_PI_2)
-                    * Matrix::scale(10.0, 0.01, 10.0),
-            );
-
-        let left_material = Phong {
-            color: Color::new(1.0, 0.8, 0.1),
-            diffuse: 0.7,
-            specular: 0.3,
-            ..Phong::default()
-        }
-        .into();
-        let left_sphere_translation_scale =
-            frame.linear_scale().with_breakpoints(vec![0.33, 0.5, 0.33]);
-        let left_size = left_sphere_translation_scale.scale(frame.current as f64);
-        let left_sphere = Sphere::default()
-            .with_material(left_material)
-            .with_transform(
-                Matrix::translate(-1.5, 0.33, -0.75)
-                    * Matrix::scale(left_size, left_size, left_size),
-            );
-
-        let middle_material = Phong {
-            color: Color::new(0.1, 1.0, 0.5),
-            diffuse: 0.7,
-            specular: 0.3,
-            ..Phong::default()
-        }
-        .into();
-        let middle_sphere_translation_scale = frame
-            .linear_scale()
-            .with_breakpoints(vec![-0.5, -0.5, 0.5, -0.5]);
-        let middle_sphere = Sphere::default()
-            .with_material(middle_material)
-            .with_transform(Matrix::translate(
-                middle_sphere_translation_scale.scale(frame.current as f64),
-                1.0,
-                0.5,
-            ));
-
-        let right_material = Phong {
-            color: Color::new(0.5, 1.0, 0.1),
-            diffuse: 0.7,
-            specular: 0.3,
-            ..Phong::default()
+    let result = animator.animate_with_sidecars(Path::new("output"), |frame| {
+        let filename = frame.filename(".\\output", "output", ".png");
+        let checkpoint_filename = frame.filename(".\\output", "output", ".checkpoint");
+        let checkpoint_path = Path::new(&checkpoint_filename);
+        if cancellation_token.is_cancelled()
+            || (Path::new(&filename).exists() && !checkpoint_path.exists())
+        {
+            // Either we're stopping, or this frame was finished by a previous run.
+            return None;
         }
-        .into();
-        let right_sphere_translation_scale = frame
-            .linear_scale()
-            .with_breakpoints(vec![0.5, 1.0, 3.0, 0.5]);
-        let right_sphere = Sphere::default()
-            .with_material(right_material)
-            .with_transform(
-                Matrix::translate(
-                    1.5,
-                    right_sphere_translation_scale.scale(frame.current as f64),
-                    -0.5,
-                ) * Matrix::scale(0.5, 0.5, 0.5),
-            );
-
-        let world = World::new(
-            vec![
-                floor_sphere.into(),
-                left_wall_sphere.into(),
-                right_wall_sphere.into(),
-                middle_sphere.into(),
-                left_sphere.into(),
-                right_sphere.into(),
-            ],
-            vec![light],
-        );
+
+        let worlds: Vec<World> = frame.subframes().iter().map(build_world).collect();
 
         let camera = Camera::new(canvas_width, canvas_height, FRAC_PI_3).look_at_from_position(
             Point::new(0.0, 2.5, -5.0),
@@ -142,28 +56,28 @@ fn main() {
             Vector::new(0.0, 1.0, 0.0),
         );
 
-        let progress = indicatif::ProgressBar::new((canvas_width * canvas_height) as u64);
-        progress.set_draw_rate(5);
-
-        let canvas_mutex = Mutex::new(Canvas::new(canvas_width, canvas_height));
-        (0..canvas_height)
-            .cartesian_product(0..canvas_width)
-            .par_bridge()
-            .for_each(|(row, col)| {
-                let color = world.color_at(camera.ray_for_pixel(col, row));
-                let mut canvas = canvas_mutex.lock().unwrap();
-                canvas.write_pixel(col, row, color);
-                progress.inc(1);
-            });
-
-        progress.finish();
-        let filename = frame.filename(".\\output", "output", ".png");
+        let settings = RenderSettings::default()
+            .with_cancellation_token(cancellation_token.clone())
+            .with_progress();
+        let output =
+            render::render_exposure_resumable(&worlds, &camera, &settings, checkpoint_path)
+                .expect("error reading or writing the render checkpoint");
+
         println!("Saving {}...", filename);
         let f = fs::File::create(filename).expect("error saving file");
+        output.canvas.to_png(f).expect("error writing file data");
 
-        let canvas = canvas_mutex.lock().unwrap();
-        canvas.to_png(f).expect("error writing file data");
+        if profile::ENABLED {
+            print!("{}", profile::take_report());
+        }
+        Some(camera.transform)
     });
+    result.expect("error writing the frame sidecars");
+
+    if cancellation_token.is_cancelled() {
+        println!("Render interrupted; run again to resume.");
+        return;
+    }
 
     println!("Rendering video...");
     Command::new("ffmpeg")
@@ -180,3 +94,132 @@ fn main() {
         .expect("rendering video with ffmpeg failed");
     println!("Rendering video...done!");
 }
+
+// The scene at the given (possibly sub-frame) time.
+fn build_world(frame: &Frame) -> World {
+    let light_rotation_scale = frame.linear_scale().with_breakpoints(vec![0.0, PI * 2.0]);
+    let light_transformation_matrix =
+        Matrix::rotate(Rotation::Y, light_rotation_scale.scale(frame.time()));
+
+    let light = PointLight::new(
+        light_transformation_matrix * Point::new(-2.0, 4.0, -2.0),
+        Color::WHITE,
+    );
+
+    // Floor and walls. Cheat by using squashed spheres...
+    let floor_and_wall_material: Material = Phong {
+        color: Color::new(0.5, 0.45, 0.45),
+        specular: 0.0,
+        ..Phong::default()
+    }
+    .into();
+
+    // The floor's stripes scroll by two stripe widths over the animation so it loops
+    // seamlessly. The floor is scaled by 10, so scale the pattern down to keep the stripes
+    // one unit wide.
+    let stripe_transform = frame
+        .keyframes(vec![
+            Transform::default().with_scale(0.1, 0.1, 0.1),
+            Transform::default()
+                .with_translation(0.2, 0.0, 0.0)
+                .with_scale(0.1, 0.1, 0.1),
+        ])
+        .at(frame.time());
+    let floor_material = Phong {
+        pattern: Some(
+            Pattern::stripe(Color::new(0.5, 0.45, 0.45), Color::new(0.4, 0.35, 0.35))
+                .with_transform(stripe_transform.into()),
+        ),
+        specular: 0.0,
+        ..Phong::default()
+    }
+    .into();
+
+    let floor_sphere = Sphere::default()
+        .with_material(floor_material)
+        .with_transform(Matrix::scale(10.0, 0.01, 10.0));
+
+    let left_wall_sphere = Sphere::default()
+        .with_material(floor_and_wall_material.clone())
+        .with_transform(
+            Matrix::translate(0.0, 0.0, 5.0)
+                * Matrix::rotate(Rotation::Y, -FRAC_PI_4)
+                * Matrix::rotate(Rotation::X, FRAC_PI_2)
+                * Matrix::scale(10.0, 0.01, 10.0),
+        );
+
+    let right_wall_sphere = Sphere::default()
+        .with_material(floor_and_wall_material)
+        .with_transform(
+            Matrix::translate(0.0, 0.0, 5.0)
+                * Matrix::rotate(Rotation::Y, FRAC_PI_4)
+                * Matrix::rotate(Rotation::X, FRAC_PI_2)
+                * Matrix::scale(10.0, 0.01, 10.0),
+        );
+
+    let left_material = Phong {
+        color: Color::new(1.0, 0.8, 0.1),
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Phong::default()
+    }
+    .into();
+    let left_sphere_translation_scale =
+        frame.linear_scale().with_breakpoints(vec![0.33, 0.5, 0.33]);
+    let left_size = left_sphere_translation_scale.scale(frame.time());
+    let left_sphere = Sphere::default()
+        .with_material(left_material)
+        .with_transform(
+            Matrix::translate(-1.5, 0.33, -0.75) * Matrix::scale(left_size, left_size, left_size),
+        );
+
+    let middle_material = Phong {
+        color: Color::new(0.1, 1.0, 0.5),
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Phong::default()
+    }
+    .into();
+    let middle_sphere_translation_scale = frame
+        .linear_scale()
+        .with_breakpoints(vec![-0.5, -0.5, 0.5, -0.5]);
+    let middle_sphere = Sphere::default()
+        .with_material(middle_material)
+        .with_transform(Matrix::translate(
+            middle_sphere_translation_scale.scale(frame.time()),
+            1.0,
+            0.5,
+        ));
+
+    let right_material = Phong {
+        color: Color::new(0.5, 1.0, 0.1),
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Phong::default()
+    }
+    .into();
+    let right_sphere_translation_scale = frame
+        .linear_scale()
+        .with_breakpoints(vec![0.5, 1.0, 3.0, 0.5]);
+    let right_sphere = Sphere::default()
+        .with_material(right_material)
+        .with_transform(
+            Matrix::translate(
+                1.5,
+                right_sphere_translation_scale.scale(frame.time()),
+                -0.5,
+            ) * Matrix::scale(0.5, 0.5, 0.5),
+        );
+
+    World::new(
+        vec![
+            floor_sphere.into(),
+            left_wall_sphere.into(),
+            right_wall_sphere.into(),
+            middle_sphere.into(),
+            left_sphere.into(),
+            right_sphere.into(),
+        ],
+        vec![light.into()],
+    )
+}
@@ -1,20 +1,23 @@
 use std::{
     f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4},
     fs,
-    sync::Mutex,
+    path::Path,
+    time::{Duration, Instant},
 };
 
-use itertools::Itertools;
-use rayon::prelude::*;
 use raytracer::{
     camera::Camera,
-    canvas::{Canvas, ToPng},
+    canvas::ToPng,
     color::Color,
     light::PointLight,
-    material::Phong,
+    material::{Material, Phong},
     matrix::{Matrix, Rotation},
+    pattern::Pattern,
     point::Point,
+    profile,
+    render::{self, CancellationToken, RenderReport, RenderSettings},
     sphere::Sphere,
+    stats::{RenderEstimate, SceneBudget, SceneStats},
     vector::Vector,
     world::World,
 };
@@ -23,22 +26,33 @@ fn main() {
     let canvas_width = 3840;
     let canvas_height = 2160;
 
-    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
 
     // Floor and walls. Cheat by using squashed spheres...
-    let floor_and_wall_material = Phong {
+    let floor_and_wall_material: Material = Phong {
         color: Color::new(0.5, 0.45, 0.45),
         specular: 0.0,
         ..Phong::default()
     }
     .into();
 
+    // One unit checkers on the floor.
+    let floor_material = Phong {
+        pattern: Some(
+            Pattern::checker(Color::new(0.5, 0.45, 0.45), Color::new(0.4, 0.35, 0.35))
+                .with_transform(Matrix::scale(0.1, 1.0, 0.1)),
+        ),
+        specular: 0.0,
+        ..Phong::default()
+    }
+    .into();
+
     let floor_sphere = Sphere::default()
-        .with_material(floor_and_wall_material)
+        .with_material(floor_material)
         .with_transform(Matrix::scale(10.0, 0.01, 10.0));
 
     let left_wall_sphere = Sphere::default()
-        .with_material(floor_and_wall_material)
+        .with_material(floor_and_wall_material.clone())
         .with_transform(
             Matrix::translate(0.0, 0.0, 5.0)
                 * Matrix::rotate(Rotation::Y, -FRAC_PI_4)
@@ -97,7 +111,7 @@ fn main() {
             left_sphere.into(),
             right_sphere.into(),
         ],
-        vec![light],
+        vec![light.into()],
     );
 
     let camera = Camera::new(canvas_width, canvas_height, FRAC_PI_3).look_at_from_position(
@@ -106,26 +120,44 @@ fn main() {
         Vector::new(0.0, 1.0, 0.0),
     );
 
-    let canvas_mutex = Mutex::new(Canvas::new(canvas_width, canvas_height));
-
-    let progress = indicatif::ProgressBar::new((canvas_width * canvas_height) as u64);
-    progress.set_draw_rate(5);
+    // Stop rendering on Ctrl-C, keeping whatever tiles are already done.
+    let cancellation_token = CancellationToken::new();
+    let handler_token = cancellation_token.clone();
+    ctrlc::set_handler(move || handler_token.cancel()).expect("error setting Ctrl-C handler");
 
-    (0..canvas_height)
-        .cartesian_product(0..canvas_width)
-        .par_bridge()
-        .for_each(|(row, col)| {
-            let color = world.color_at(camera.ray_for_pixel(col, row));
-            let mut canvas = canvas_mutex.lock().unwrap();
-            canvas.write_pixel(col, row, color);
-            progress.inc(1);
-        });
+    for warning in SceneBudget::default().check(&SceneStats::of(&world)) {
+        println!("Warning: {}", warning);
+    }
+    let estimate = RenderEstimate::measure(&world, &camera, 1000);
+    println!(
+        "Rendering {} rays; this should take about {:.0?} on one core.",
+        estimate.primary_rays, estimate.time
+    );
 
-    progress.finish();
+    let settings = RenderSettings::default()
+        .with_cancellation_token(cancellation_token)
+        .with_preview("output.preview.png", Duration::from_secs(10))
+        .with_progress();
+    let start = Instant::now();
+    let output =
+        render::render_resumable(&world, &camera, &settings, Path::new("output.checkpoint"))
+            .expect("error reading or writing the render checkpoint");
+
+    let filename = if output.complete {
+        "output.png"
+    } else {
+        println!("Render interrupted; run again to resume from 'output.checkpoint'.");
+        "output.partial.png"
+    };
 
     println!("Saving to PNG...");
-    let f = fs::File::create("output.png").expect("error creating 'output.png'");
-
-    let canvas = canvas_mutex.lock().unwrap();
-    canvas.to_png(f).expect("error writing file data");
+    let f = fs::File::create(filename).expect("error creating output file");
+    output.canvas.to_png(f).expect("error writing file data");
+    RenderReport::new(&settings, start.elapsed())
+        .write(&RenderReport::path_for(Path::new(filename)))
+        .expect("error writing the render report");
+
+    if profile::ENABLED {
+        print!("{}", profile::take_report());
+    }
 }
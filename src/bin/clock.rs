@@ -28,7 +28,7 @@ fn main() {
     let mut canvas = Canvas::new(WIDTH, HEIGHT);
     let pt = Point::new(R as f64, 0.0, 0.0);
     let origin = Point::new((WIDTH / 2) as f64, (HEIGHT / 2) as f64, 0.0);
-    let color = Color::new(1.0, 0.0, 0.0);
+    let color = Color::RED;
     let translation = Matrix::translate(origin[0], origin[1], 0.0);
 
     for i in 0..12 {
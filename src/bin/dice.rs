@@ -0,0 +1,25 @@
+use std::{f64::consts::FRAC_PI_3, fs};
+
+use raytracer::{
+    camera::Camera,
+    canvas::ToPng,
+    point::Point,
+    prefabs,
+    render::{self, RenderSettings},
+    vector::Vector,
+};
+
+fn main() {
+    let world = prefabs::dice_scene();
+    let camera = Camera::new(1920, 1080, FRAC_PI_3).look_at_from_position(
+        Point::new(-1.0, 4.5, -7.0),
+        Point::new(1.0, 0.8, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+
+    let output = render::render(&world, &camera, &RenderSettings::default().with_progress());
+
+    println!("Saving to PNG...");
+    let f = fs::File::create("dice.png").expect("error creating 'dice.png'");
+    output.canvas.to_png(f).expect("error writing file data");
+}
@@ -1,6 +1,5 @@
-use std::{fs, sync::Mutex};
+use std::fs;
 
-use itertools::Itertools;
 use rayon::prelude::*;
 use raytracer::{
     canvas::{Canvas, ToPng},
@@ -23,15 +22,14 @@ fn main() {
     let color = Color::new(0.5, 0.2, 0.1);
     let sphere: Sphere = Sphere::default().with_transform(Matrix::identity());
 
-    let canvas_mutex = Mutex::new(Canvas::new(canvas_size, canvas_size));
+    let mut canvas = Canvas::new(canvas_size, canvas_size);
 
     let progress = indicatif::ProgressBar::new((canvas_size * canvas_size) as u64);
     progress.set_draw_rate(5);
 
-    (0..canvas_size)
-        .cartesian_product(0..canvas_size)
-        .par_bridge()
-        .for_each(|(row, col)| {
+    canvas.par_bands_mut(1).for_each(|mut band| {
+        let row = band.y;
+        for col in 0..canvas_size {
             let target_point = Point::new(
                 (col as f64 * pixel_world_ratio) - wall_size / 2.0,
                 (row as f64 * pixel_world_ratio) - wall_size / 2.0,
@@ -41,17 +39,15 @@ fn main() {
 
             let intersections = sphere.intersect(ray);
             if intersections.hit().is_some() {
-                let mut canvas = canvas_mutex.lock().unwrap();
-                canvas.write_pixel(col, row, color);
+                band.write_pixel(col, row, color);
             }
-            progress.inc(1);
-        });
+        }
+        progress.inc(canvas_size as u64);
+    });
 
     progress.finish();
 
     println!("Saving to PNG...");
     let f = fs::File::create("output.png").expect("error creating 'output.png'");
-
-    let canvas = canvas_mutex.lock().unwrap();
     canvas.to_png(f).expect("error writing file data");
 }
@@ -0,0 +1,45 @@
+// Renders every scene in the gallery into a directory, along with an index.html showing them all.
+//
+//     cargo run --release --bin gallery -- [output directory] [width] [height]
+//
+// The defaults are "gallery" and 640x360.
+
+use std::{env, fs, path::PathBuf, time::Instant};
+
+use raytracer::{
+    canvas::ToPng,
+    gallery,
+    render::{self, RenderSettings},
+};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let dir = PathBuf::from(args.first().map_or("gallery", String::as_str));
+    let size = |i: usize, default: usize| {
+        args.get(i).map_or(default, |s| {
+            s.parse()
+                .expect("the width and height must be whole numbers")
+        })
+    };
+    let (width, height) = (size(1, 640), size(2, 360));
+
+    fs::create_dir_all(&dir).expect("error creating the output directory");
+    let scenes = gallery::scenes();
+    for scene in &scenes {
+        let start = Instant::now();
+        let output = render::render(
+            &scene.world,
+            &scene.camera(width, height),
+            &RenderSettings::default(),
+        );
+        let path = dir.join(format!("{}.png", scene.name));
+        let f = fs::File::create(&path).expect("error creating output file");
+        output.canvas.to_png(f).expect("error writing file data");
+        println!("Rendered {} in {:.1?}", path.display(), start.elapsed());
+    }
+
+    let index = dir.join("index.html");
+    fs::write(&index, gallery::index_html(&scenes, width, height))
+        .expect("error writing the index");
+    println!("Wrote {}", index.display());
+}
@@ -1,6 +1,5 @@
-use std::{fs, sync::Mutex};
+use std::fs;
 
-use itertools::Itertools;
 use rayon::prelude::*;
 use raytracer::{
     canvas::{Canvas, ToPng},
@@ -28,17 +27,16 @@ fn main() {
     .into();
     let sphere: Sphere = Sphere::default().with_material(material);
 
-    let light = PointLight::new(Point::new(20.0, 30.0, -20.0), Color::new(1.0, 1.0, 1.0));
+    let light = PointLight::new(Point::new(20.0, 30.0, -20.0), Color::WHITE).into();
 
-    let canvas_mutex = Mutex::new(Canvas::new(canvas_size, canvas_size));
+    let mut canvas = Canvas::new(canvas_size, canvas_size);
 
     let progress = indicatif::ProgressBar::new((canvas_size * canvas_size) as u64);
     progress.set_draw_rate(5);
 
-    (0..canvas_size)
-        .cartesian_product(0..canvas_size)
-        .par_bridge()
-        .for_each(|(row, col)| {
+    canvas.par_bands_mut(1).for_each(|mut band| {
+        let row = band.y;
+        for col in 0..canvas_size {
             let target_point = Point::new(
                 (col as f64 * pixel_world_ratio) - wall_size / 2.0,
                 -(row as f64 * pixel_world_ratio) + wall_size / 2.0,
@@ -51,6 +49,7 @@ fn main() {
             if let Some(hit) = hit {
                 let computed = hit.computed();
                 let color = hit.body.material().lighting(
+                    &hit.body,
                     &light,
                     computed.position,
                     computed.eye,
@@ -58,17 +57,15 @@ fn main() {
                     ShadowState::Clear,
                 );
 
-                let mut canvas = canvas_mutex.lock().unwrap();
-                canvas.write_pixel(col, row, color);
+                band.write_pixel(col, row, color);
             }
-            progress.inc(1);
-        });
+        }
+        progress.inc(canvas_size as u64);
+    });
 
     progress.finish();
 
     println!("Saving to PNG...");
     let f = fs::File::create("output.png").expect("error creating 'output.png'");
-
-    let canvas = canvas_mutex.lock().unwrap();
     canvas.to_png(f).expect("error writing file data");
 }
@@ -0,0 +1,223 @@
+// Renders one of the gallery's scenes, or an animation of it from a timeline file, or a batch of
+// renders listed in a file.
+//
+//     cargo run --release --bin render -- <scene> [width] [height] [--animate <timeline.json>]
+//         [--light-gizmos <marker size>] [--differences <gain>]
+//     cargo run --release --bin render -- --batch <jobs.txt> [--parallel <jobs at once>]
+//
+// A still is written to <scene>.png. An animation is written to output/<scene>NNNNNN.png, one file
+// per frame, with the timeline moving the scene's bodies, lights and camera. Each line of a batch
+// file is a job, "<scene> <width> <height> <output.png>", and lines starting with # are skipped.
+// --light-gizmos marks where the point lights are and outlines their radii. --differences also
+// writes output/differenceNNNNNN.png for each frame of an animation after the first, a heat map of
+// how much it changed since the frame before, amplified by the gain.
+
+use std::{collections::HashMap, env, fs, mem, path::Path, process};
+
+use raytracer::{
+    animator::{Frame, Timeline},
+    canvas::ToPng,
+    gallery::{self, GalleryScene},
+    render::{self, Batch, Job, LightGizmos, RenderSettings},
+    world::{FrozenWorld, World},
+};
+
+fn scene(name: &str) -> GalleryScene {
+    let mut scenes = gallery::scenes();
+    let i = scene_index(&scenes, name);
+    scenes.swap_remove(i)
+}
+
+// Where the scene with the name is in the list, exiting with the names there are if it isn't.
+fn scene_index(scenes: &[GalleryScene], name: &str) -> usize {
+    scenes
+        .iter()
+        .position(|s| s.name == name)
+        .unwrap_or_else(|| {
+            let names: Vec<&str> = scenes.iter().map(|s| s.name).collect();
+            eprintln!("there is no scene named {:?}; try one of {:?}", name, names);
+            process::exit(1)
+        })
+}
+
+// The value after a flag, taking both out of the arguments.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == flag)?;
+    let value = args
+        .get(i + 1)
+        .unwrap_or_else(|| panic!("{} needs a value", flag))
+        .clone();
+    args.drain(i..i + 2);
+    Some(value)
+}
+
+fn run_batch(path: &str, parallel_jobs: usize) {
+    let text = fs::read_to_string(path).expect("error reading the batch file");
+    // Each scene is only built once, however many jobs use it.
+    let mut scenes = gallery::scenes();
+    let mut worlds: HashMap<String, FrozenWorld> = HashMap::new();
+    let mut jobs = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (name, width, height, output) = match fields.as_slice() {
+            [name, width, height, output] => match (width.parse(), height.parse()) {
+                (Ok(width), Ok(height)) => (*name, width, height, *output),
+                _ => {
+                    eprintln!(
+                        "{}:{}: the width and height must be whole numbers",
+                        path,
+                        i + 1
+                    );
+                    process::exit(1)
+                }
+            },
+            _ => {
+                eprintln!(
+                    "{}:{}: expected <scene> <width> <height> <output.png>",
+                    path,
+                    i + 1
+                );
+                process::exit(1)
+            }
+        };
+        let i = scene_index(&scenes, name);
+        let scene = &mut scenes[i];
+        let camera = scene.camera(width, height);
+        let world = worlds
+            .entry(name.to_string())
+            .or_insert_with(|| mem::take(&mut scene.world).freeze())
+            .clone();
+        jobs.push(Job {
+            name: format!("{} {}x{}", name, width, height),
+            world,
+            camera,
+            settings: RenderSettings::default(),
+            output: output.into(),
+        });
+    }
+
+    let mut failed = false;
+    for result in Batch::new(jobs).with_parallel_jobs(parallel_jobs).run() {
+        match result.saved {
+            Ok(()) => println!(
+                "Rendered {} to {} in {:.1?}",
+                result.name,
+                result.output.display(),
+                result.elapsed
+            ),
+            Err(e) => {
+                eprintln!(
+                    "error saving {} to {}: {}",
+                    result.name,
+                    result.output.display(),
+                    e
+                );
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1)
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(path) = take_flag(&mut args, "--batch") {
+        let parallel_jobs = take_flag(&mut args, "--parallel").map_or(1, |n| {
+            n.parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .expect("--parallel must be a whole number above zero")
+        });
+        run_batch(&path, parallel_jobs);
+        return;
+    }
+    let timeline = take_flag(&mut args, "--animate").map(|path| {
+        let json = fs::read_to_string(&path).expect("error reading the timeline");
+        Timeline::from_json(&json).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            process::exit(1)
+        })
+    });
+    let gizmos = take_flag(&mut args, "--light-gizmos").map(|size| {
+        let size = size.parse().expect("the marker size must be a number");
+        LightGizmos::new(size).with_radii()
+    });
+    let gain = take_flag(&mut args, "--differences")
+        .map(|gain| gain.parse::<f64>().expect("the gain must be a number"));
+    let name = args
+        .first()
+        .expect("usage: render <scene> [width] [height] [--animate <timeline.json>]")
+        .clone();
+    let size = |i: usize, default: usize| {
+        args.get(i).map_or(default, |s| {
+            s.parse()
+                .expect("the width and height must be whole numbers")
+        })
+    };
+    let (width, height) = (size(1, 640), size(2, 360));
+    let settings = RenderSettings::default().with_progress();
+    let settings = match gizmos {
+        Some(gizmos) => settings.with_light_gizmos(gizmos),
+        None => settings,
+    };
+
+    let timeline = match timeline {
+        Some(timeline) => timeline,
+        None => {
+            let scene = scene(&name);
+            let output = render::render(&scene.world, &scene.camera(width, height), &settings);
+            let filename = format!("{}.png", name);
+            let f = fs::File::create(&filename).expect("error creating output file");
+            output.canvas.to_png(f).expect("error writing file data");
+            println!("Saved {}", filename);
+            return;
+        }
+    };
+
+    fs::create_dir_all("output").expect("error creating the output directory");
+    let render_frame = |frame: Frame| {
+        // The timeline moves things to where they are at each time from wherever they start, so
+        // each subframe starts from a fresh copy of the scene, built once for the frame.
+        let scene = scene(&name);
+        let mut camera = None;
+        let worlds: Vec<World> = frame
+            .subframes()
+            .iter()
+            .map(|subframe| {
+                let mut world = scene.world.clone();
+                let start = scene.camera(width, height);
+                let moved = timeline
+                    .apply(&mut world, start, subframe.time())
+                    .unwrap_or_else(|e| {
+                        eprintln!("{}", e);
+                        process::exit(1)
+                    });
+                // The camera is where it is when the shutter opens.
+                camera.get_or_insert(moved);
+                world
+            })
+            .collect();
+
+        let output = render::render_exposure(&worlds, camera.as_ref().unwrap(), &settings);
+        let filename = frame.filename("output", &name, ".png");
+        let f = fs::File::create(&filename).expect("error creating output file");
+        output.canvas.to_png(f).expect("error writing file data");
+        println!("Saved {}", filename);
+        output.canvas
+    };
+    let animator = timeline.animator();
+    match gain {
+        Some(gain) => animator
+            .animate_with_differences(Path::new("output"), gain, render_frame)
+            .expect("error writing the differences"),
+        None => animator.animate(|frame| {
+            render_frame(frame);
+        }),
+    }
+}
@@ -21,7 +21,7 @@ fn main() {
     while particle.position[1] >= 0.0 {
         println!("{:?}", particle.position);
         if let Some((x, y)) = particle.pos_in_canvas(&canvas) {
-            canvas.write_pixel(x, y, Color::new(1.0, 0.0, 0.0));
+            canvas.write_pixel(x, y, Color::RED);
         }
         particle.step();
     }
@@ -59,8 +59,8 @@ impl Particle {
     }
 
     fn step(&mut self) {
-        self.velocity = self.velocity + self.acceleration;
-        self.position = self.position + self.velocity;
+        self.velocity += self.acceleration;
+        self.position += self.velocity;
     }
 
     fn pos_in_canvas<T>(&self, canvas: &T) -> Option<(usize, usize)>
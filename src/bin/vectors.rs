@@ -40,7 +40,7 @@ impl Particle {
     }
 
     fn step(&mut self) {
-        self.velocity = self.velocity + self.acceleration;
-        self.position = self.position + self.velocity;
+        self.velocity += self.acceleration;
+        self.position += self.velocity;
     }
 }
@@ -1,20 +1,83 @@
 use crate::{
+    bounds::{Bounded, Bounds},
+    capsule::Capsule,
     fuzzy_eq::FuzzyEq,
     intersection::{Intersectable, Intersections, Normal},
     material::Material,
+    matrix::Matrix,
+    plane::Plane,
     ray::Ray,
+    shell::Shell,
     sphere::Sphere,
+    transform::Transformable,
+    triangle::Triangle,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Body {
     Sphere(Sphere),
+    Shell(Shell),
+    Capsule(Capsule),
+    Plane(Plane),
+    Triangle(Triangle),
 }
 
 impl Body {
     pub fn material(&self) -> Material {
         match self {
-            Body::Sphere(s) => s.material,
+            Body::Sphere(s) => s.material.clone(),
+            Body::Shell(s) => s.material.clone(),
+            Body::Capsule(c) => c.material.clone(),
+            Body::Plane(p) => p.material.clone(),
+            Body::Triangle(t) => t.material.clone(),
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Self {
+        match self {
+            Body::Sphere(s) => s.with_material(material).into(),
+            Body::Shell(s) => s.with_material(material).into(),
+            Body::Capsule(c) => c.with_material(material).into(),
+            Body::Plane(p) => p.with_material(material).into(),
+            Body::Triangle(t) => t.with_material(material).into(),
+        }
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        match self {
+            Body::Sphere(s) => s.transform(),
+            Body::Shell(s) => s.transform(),
+            Body::Capsule(c) => c.transform(),
+            Body::Plane(p) => p.transform(),
+            Body::Triangle(t) => t.transform(),
+        }
+    }
+}
+
+impl Bounded for Body {
+    fn bounds(&self) -> Bounds {
+        match self {
+            Body::Sphere(s) => s.bounds(),
+            Body::Shell(s) => s.bounds(),
+            Body::Capsule(c) => c.bounds(),
+            Body::Plane(p) => p.bounds(),
+            Body::Triangle(t) => t.bounds(),
+        }
+    }
+}
+
+impl Transformable for Body {
+    fn transform(&self) -> Matrix<4> {
+        Body::transform(self)
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        match self {
+            Body::Sphere(s) => s.with_transform(transform).into(),
+            Body::Shell(s) => s.with_transform(transform).into(),
+            Body::Capsule(c) => c.with_transform(transform).into(),
+            Body::Plane(p) => p.with_transform(transform).into(),
+            Body::Triangle(t) => t.with_transform(transform).into(),
         }
     }
 }
@@ -25,12 +88,39 @@ impl From<Sphere> for Body {
     }
 }
 
+impl From<Shell> for Body {
+    fn from(s: Shell) -> Self {
+        Body::Shell(s)
+    }
+}
+
+impl From<Capsule> for Body {
+    fn from(c: Capsule) -> Self {
+        Body::Capsule(c)
+    }
+}
+
+impl From<Plane> for Body {
+    fn from(p: Plane) -> Self {
+        Body::Plane(p)
+    }
+}
+
+impl From<Triangle> for Body {
+    fn from(t: Triangle) -> Self {
+        Body::Triangle(t)
+    }
+}
+
 impl FuzzyEq for Body {
     fn fuzzy_eq(&self, other: Self) -> bool {
-        match self {
-            Body::Sphere(s) => match other {
-                Body::Sphere(os) => s.fuzzy_eq(os),
-            },
+        match (self, other) {
+            (Body::Sphere(s), Body::Sphere(os)) => s.fuzzy_eq(os),
+            (Body::Shell(s), Body::Shell(os)) => s.fuzzy_eq(os),
+            (Body::Capsule(c), Body::Capsule(oc)) => c.fuzzy_eq(oc),
+            (Body::Plane(p), Body::Plane(op)) => p.fuzzy_eq(op),
+            (Body::Triangle(t), Body::Triangle(ot)) => t.fuzzy_eq(ot),
+            _ => false,
         }
     }
 }
@@ -39,6 +129,10 @@ impl Intersectable for Body {
     fn intersect(&self, r: Ray) -> Intersections {
         match self {
             Body::Sphere(s) => s.intersect(r),
+            Body::Shell(s) => s.intersect(r),
+            Body::Capsule(c) => c.intersect(r),
+            Body::Plane(pl) => pl.intersect(r),
+            Body::Triangle(t) => t.intersect(r),
         }
     }
 }
@@ -47,6 +141,10 @@ impl Normal for Body {
     fn normal_at(&self, p: crate::point::Point) -> crate::vector::Vector {
         match self {
             Body::Sphere(s) => s.normal_at(p),
+            Body::Shell(s) => s.normal_at(p),
+            Body::Capsule(c) => c.normal_at(p),
+            Body::Plane(pl) => pl.normal_at(p),
+            Body::Triangle(t) => t.normal_at(p),
         }
     }
 }
@@ -0,0 +1,254 @@
+use crate::{fuzzy_eq::FuzzyEq, matrix::Matrix, point::Point, ray::Ray};
+
+// An axis aligned box that a body fits inside of.
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+pub trait Bounded {
+    // The bounds of the body in its own object space, before its transform is applied.
+    fn bounds(&self) -> Bounds;
+}
+
+impl FuzzyEq for Bounds {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.min.fuzzy_eq(other.min) && self.max.fuzzy_eq(other.max)
+    }
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    pub fn infinite() -> Self {
+        Self::new(
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|i| self.min[i].is_finite() && self.max[i].is_finite())
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point::new(min[0], min[1], min[2]),
+            Point::new(min[0], min[1], max[2]),
+            Point::new(min[0], max[1], min[2]),
+            Point::new(min[0], max[1], max[2]),
+            Point::new(max[0], min[1], min[2]),
+            Point::new(max[0], min[1], max[2]),
+            Point::new(max[0], max[1], min[2]),
+            Point::new(max[0], max[1], max[2]),
+        ]
+    }
+
+    // Whether the ray passes through the box anywhere ahead of its origin, using the slab method.
+    pub fn intersects(&self, ray: Ray) -> bool {
+        let (t_min, t_max) = self.slab_range(ray);
+        t_min <= t_max && t_max >= 0.0
+    }
+
+    // Whether the line the ray is on passes through the box, ahead of the ray's origin or behind
+    // it. Refraction needs to know about what's behind the ray too, to tell what it's inside of.
+    pub fn intersects_line(&self, ray: Ray) -> bool {
+        let (t_min, t_max) = self.slab_range(ray);
+        t_min <= t_max
+    }
+
+    // Where the ray is inside the box, as the range of t, if it gets there ahead of its origin.
+    pub fn crossing(&self, ray: Ray) -> Option<(f64, f64)> {
+        let (t_min, t_max) = self.slab_range(ray);
+        (t_min <= t_max && t_max >= 0.0).then_some((t_min.max(0.0), t_max))
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+    }
+
+    // Whether the ray passes through the box before it's gone the distance.
+    pub fn intersects_segment(&self, ray: Ray, distance: f64) -> bool {
+        let (t_min, t_max) = self.slab_range(ray);
+        t_min <= t_max && t_max >= 0.0 && t_min <= distance
+    }
+
+    // Where along the ray it's inside of all three slabs of the box. It misses the box if the
+    // range is empty.
+    fn slab_range(&self, ray: Ray) -> (f64, f64) {
+        let mut t_min = f64::NEG_INFINITY;
+        let mut t_max = f64::INFINITY;
+        for i in 0..3 {
+            let inverse = 1.0 / ray.direction[i];
+            let mut t0 = (self.min[i] - ray.origin[i]) * inverse;
+            let mut t1 = (self.max[i] - ray.origin[i]) * inverse;
+            if t0.is_nan() || t1.is_nan() {
+                // The ray is parallel to an infinite slab, so it's always inside it.
+                continue;
+            }
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+        }
+        (t_min, t_max)
+    }
+
+    // The smallest box around both of these.
+    pub fn union(&self, other: &Bounds) -> Self {
+        let mut min = self.min;
+        let mut max = self.max;
+        for i in 0..3 {
+            min[i] = min[i].min(other.min[i]);
+            max[i] = max[i].max(other.max[i]);
+        }
+        Self::new(min, max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let (x, y, z) = (
+            self.max[0] - self.min[0],
+            self.max[1] - self.min[1],
+            self.max[2] - self.min[2],
+        );
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    pub fn centroid(&self) -> Point {
+        Point::new(
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+            (self.min[2] + self.max[2]) / 2.0,
+        )
+    }
+
+    // The smallest axis aligned box around these bounds once they're transformed. Transforming an
+    // infinite box could put it anywhere, so those stay infinite.
+    pub fn transformed(&self, transform: Matrix<4>) -> Self {
+        if !self.is_finite() {
+            return Self::infinite();
+        }
+
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for corner in self.corners() {
+            let p = transform * corner;
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+        Self::new(
+            Point::new(min[0], min[1], min[2]),
+            Point::new(max[0], max[1], max[2]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use super::*;
+    use crate::{assert_fuzzy_eq, matrix::Rotation, vector::Vector};
+
+    #[test]
+    fn transforming_bounds_fits_a_box_around_the_corners() {
+        let b = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+
+        let t = b
+            .transformed(Matrix::translate(1.0, 2.0, 3.0) * Matrix::rotate(Rotation::Y, FRAC_PI_4));
+
+        let half = 2f64.sqrt();
+        assert_fuzzy_eq!(Point::new(1.0 - half, 1.0, 3.0 - half), t.min);
+        assert_fuzzy_eq!(Point::new(1.0 + half, 3.0, 3.0 + half), t.max);
+    }
+
+    #[test]
+    fn transformed_infinite_bounds_are_infinite() {
+        let t = Bounds::infinite().transformed(Matrix::translate(1.0, 0.0, 0.0));
+
+        assert!(!t.is_finite());
+    }
+
+    #[test]
+    fn rays_hitting_and_missing_a_box() {
+        let b = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+
+        let cases = [
+            (Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0), true),
+            (Point::new(0.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 0.0, 1.0), true),
+            (
+                Point::new(-2.0, 0.0, 0.0),
+                Vector::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(2.0, 2.0, 0.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+            (Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0), false),
+        ];
+        for (origin, direction, hits) in cases {
+            assert_eq!(hits, b.intersects(Ray::new(origin, direction)));
+        }
+    }
+
+    #[test]
+    fn where_a_ray_crosses_a_box() {
+        let b = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let along_z = |z: f64| Ray::new(Point::new(0.0, 0.0, z), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(Some((4.0, 6.0)), b.crossing(along_z(-5.0)));
+        assert_eq!(Some((0.0, 1.0)), b.crossing(along_z(0.0)));
+        assert_eq!(None, b.crossing(along_z(5.0)));
+        assert!(b.contains(Point::new(1.0, 0.0, -0.5)));
+        assert!(!b.contains(Point::new(1.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn a_box_behind_the_ray_is_only_on_its_line() {
+        let b = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(!b.intersects(r));
+        assert!(b.intersects_line(r));
+    }
+
+    #[test]
+    fn the_union_of_two_boxes() {
+        let a = Bounds::new(Point::new(-1.0, 0.0, 2.0), Point::new(1.0, 1.0, 3.0));
+        let b = Bounds::new(Point::new(0.0, -2.0, 0.0), Point::new(4.0, 0.5, 1.0));
+
+        let u = a.union(&b);
+
+        assert_fuzzy_eq!(Point::new(-1.0, -2.0, 0.0), u.min);
+        assert_fuzzy_eq!(Point::new(4.0, 1.0, 3.0), u.max);
+        assert_fuzzy_eq!(Point::new(1.5, -0.5, 1.5), u.centroid());
+        assert_fuzzy_eq!(78.0, u.surface_area());
+    }
+
+    #[test]
+    fn rays_hitting_an_infinite_box() {
+        let floor = Bounds::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        );
+
+        assert!(floor.intersects(Ray::new(
+            Point::new(3.0, 1.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0)
+        )));
+        assert!(!floor.intersects(Ray::new(
+            Point::new(3.0, 1.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0)
+        )));
+    }
+}
@@ -0,0 +1,569 @@
+use crate::{bounds::Bounds, parallel, ray::Ray};
+
+// A bounding volume hierarchy: a tree of boxes over a list of things, so that finding what a ray
+// hits only means looking at the things in the boxes it passes through rather than every one of
+// them. The tree only knows the things by their index in the list it was built from.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    // The indices of the things, arranged so that the ones in each leaf are next to each other.
+    indices: Vec<usize>,
+    // Things with infinite bounds (like planes) don't fit in a box, so every ray has to look at
+    // them.
+    unbounded: Vec<usize>,
+    // The tree's cost (see `cost`) when it was built, to tell how much refitting has worn it down.
+    built_cost: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Node {
+    Leaf {
+        bounds: Bounds,
+        start: usize,
+        end: usize,
+    },
+    Interior {
+        bounds: Bounds,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Node {
+    fn bounds(&self) -> Bounds {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => *bounds,
+        }
+    }
+}
+
+// Splitting any further than this costs more in box tests than it saves.
+const MAX_LEAF_SIZE: usize = 4;
+
+impl Bvh {
+    // How many bytes the tree takes up, not counting the things it's over.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.nodes.capacity() * size_of::<Node>()
+            + (self.indices.capacity() + self.unbounded.capacity()) * size_of::<usize>()
+    }
+
+    // Builds the tree over things with the given bounds.
+    pub fn new(bounds: &[Bounds]) -> Self {
+        Self::with_quality(bounds, BuildQuality::default())
+    }
+
+    pub fn with_quality(bounds: &[Bounds], quality: BuildQuality) -> Self {
+        let (mut indices, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..bounds.len()).partition(|&i| bounds[i].is_finite());
+
+        let nodes = if indices.is_empty() {
+            Vec::new()
+        } else {
+            build(&mut indices, 0, bounds, quality)
+        };
+        let mut bvh = Self {
+            nodes,
+            indices,
+            unbounded,
+            built_cost: 0.0,
+        };
+        bvh.built_cost = bvh.cost();
+        bvh
+    }
+
+    // Updates the boxes for things that have moved, keeping the shape of the tree. That's much
+    // quicker than building it again, but things that were close together when it was built may
+    // have moved apart, leaving boxes that are bigger than they need to be. Rebuild once
+    // `degradation` says it's gotten too slow.
+    pub fn refit(&mut self, bounds: &[Bounds]) {
+        // Children always come after their parents, so going backwards reaches every node after
+        // its children.
+        for n in (0..self.nodes.len()).rev() {
+            let refitted = match self.nodes[n] {
+                Node::Leaf { start, end, .. } => self.indices[start..end]
+                    .iter()
+                    .map(|&i| bounds[i])
+                    .reduce(|a, b| a.union(&b))
+                    .unwrap(),
+                Node::Interior { left, right, .. } => {
+                    self.nodes[left].bounds().union(&self.nodes[right].bounds())
+                }
+            };
+            match &mut self.nodes[n] {
+                Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => *bounds = refitted,
+            }
+        }
+    }
+
+    // How many times slower the tree is than when it was built, going by the surface area
+    // heuristic. Freshly built trees are at 1.
+    pub fn degradation(&self) -> f64 {
+        if self.built_cost > 0.0 {
+            self.cost() / self.built_cost
+        } else {
+            1.0
+        }
+    }
+
+    // Roughly how many boxes and things a ray through the root box is expected to test: the
+    // chance of hitting each node's box goes with its surface area, and hitting a leaf means
+    // testing everything in it.
+    fn cost(&self) -> f64 {
+        let root_area = match self.nodes.first() {
+            Some(root) => root.bounds().surface_area(),
+            None => return 0.0,
+        };
+        if !(root_area > 0.0 && root_area.is_finite()) {
+            return 0.0;
+        }
+        self.nodes
+            .iter()
+            .map(|node| {
+                let count = match node {
+                    Node::Leaf { start, end, .. } => (end - start) as f64,
+                    Node::Interior { .. } => 1.0,
+                };
+                node.bounds().surface_area() / root_area * count
+            })
+            .sum()
+    }
+
+    // A box around everything in the tree, or None if it's empty.
+    pub fn bounds(&self) -> Option<Bounds> {
+        if !self.unbounded.is_empty() {
+            Some(Bounds::infinite())
+        } else {
+            self.nodes.first().map(Node::bounds)
+        }
+    }
+
+    // Calls visit with the index of every thing the ray might hit, returning how many boxes that
+    // took to work out. Things behind the ray are included, since the line the ray is on is what's
+    // tested.
+    pub fn visit(&self, ray: Ray, visit: impl FnMut(usize)) -> usize {
+        let mut tests = 0;
+        self.visit_levels(ray, visit, |_| tests += 1);
+        tests
+    }
+
+    // Like visit, but calls `tested` with the depth of every box tested, the root being 0, rather
+    // than counting them.
+    pub(crate) fn visit_levels(
+        &self,
+        ray: Ray,
+        mut visit: impl FnMut(usize),
+        mut tested: impl FnMut(usize),
+    ) {
+        for &i in &self.unbounded {
+            visit(i);
+        }
+
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push((0, 0));
+        }
+        while let Some((n, depth)) = stack.pop() {
+            let node = self.nodes[n];
+            tested(depth);
+            if !node.bounds().intersects_line(ray) {
+                continue;
+            }
+            match node {
+                Node::Leaf { start, end, .. } => {
+                    for &i in &self.indices[start..end] {
+                        visit(i);
+                    }
+                }
+                Node::Interior { left, right, .. } => {
+                    stack.push((right, depth + 1));
+                    stack.push((left, depth + 1));
+                }
+            }
+        }
+    }
+}
+
+// How carefully the tree is put together. Better trees take longer to build but need fewer box
+// tests per ray, which pays off for meshes that are built once and then rendered many times.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BuildQuality {
+    // Split each box at the middle thing along its longest side.
+    Fast,
+    // Split wherever the surface area heuristic says rays will need the fewest tests, trying a
+    // handful of places along each side.
+    #[default]
+    Balanced,
+    // Like Balanced, but trying many more places.
+    Best,
+}
+
+impl BuildQuality {
+    // How many evenly spaced places along each side to try splitting at.
+    fn sah_bins(self) -> Option<usize> {
+        match self {
+            BuildQuality::Fast => None,
+            BuildQuality::Balanced => Some(12),
+            BuildQuality::Best => Some(64),
+        }
+    }
+}
+
+// Boxes with more things than this in them have their halves built on separate threads. Below it
+// handing the work off costs more than it saves.
+const PARALLEL_BUILD_SIZE: usize = 4096;
+
+// Builds the subtree over the given indices, which start at `offset` in the whole list. The root
+// of the subtree is the first node, and children are referred to by their position in it.
+fn build(
+    indices: &mut [usize],
+    offset: usize,
+    bounds: &[Bounds],
+    quality: BuildQuality,
+) -> Vec<Node> {
+    let node_bounds = indices
+        .iter()
+        .map(|&i| bounds[i])
+        .reduce(|a, b| a.union(&b))
+        .unwrap();
+    if indices.len() <= MAX_LEAF_SIZE {
+        return vec![Node::Leaf {
+            bounds: node_bounds,
+            start: offset,
+            end: offset + indices.len(),
+        }];
+    }
+
+    let centroids = indices
+        .iter()
+        .map(|&i| {
+            let c = bounds[i].centroid();
+            Bounds::new(c, c)
+        })
+        .reduce(|a, b| a.union(&b))
+        .unwrap();
+    let middle = quality
+        .sah_bins()
+        .and_then(|bins| sah_partition(indices, bounds, &centroids, bins))
+        .unwrap_or_else(|| median_partition(indices, bounds, &centroids));
+
+    let size = indices.len();
+    let (left_indices, right_indices) = indices.split_at_mut(middle);
+    let mut build_left = || build(left_indices, offset, bounds, quality);
+    let mut build_right = || build(right_indices, offset + middle, bounds, quality);
+    let (left, right) = if size >= PARALLEL_BUILD_SIZE {
+        parallel::join(build_left, build_right)
+    } else {
+        (build_left(), build_right())
+    };
+
+    let right_start = 1 + left.len();
+    let mut nodes = Vec::with_capacity(right_start + right.len());
+    nodes.push(Node::Interior {
+        bounds: node_bounds,
+        left: 1,
+        right: right_start,
+    });
+    nodes.extend(shifted(left, 1));
+    nodes.extend(shifted(right, right_start));
+    nodes
+}
+
+// Moves a subtree's nodes along by the given number of places, to go after others in a list.
+fn shifted(nodes: Vec<Node>, by: usize) -> impl Iterator<Item = Node> {
+    nodes.into_iter().map(move |node| match node {
+        Node::Interior {
+            bounds,
+            left,
+            right,
+        } => Node::Interior {
+            bounds,
+            left: left + by,
+            right: right + by,
+        },
+        leaf => leaf,
+    })
+}
+
+// Splits the indices in half at the middle thing along the longest side of the box around their
+// centers, returning where the second half starts.
+fn median_partition(indices: &mut [usize], bounds: &[Bounds], centroids: &Bounds) -> usize {
+    let axis = (0..3)
+        .max_by(|&a, &b| {
+            let extent = |i: usize| centroids.max[i] - centroids.min[i];
+            extent(a).total_cmp(&extent(b))
+        })
+        .unwrap();
+
+    let middle = indices.len() / 2;
+    indices.select_nth_unstable_by(middle, |&a, &b| {
+        bounds[a].centroid()[axis].total_cmp(&bounds[b].centroid()[axis])
+    });
+    middle
+}
+
+// Splits the indices using the surface area heuristic: the chance of a ray hitting a box goes with
+// its surface area, so the best split keeps the area of each half times the number of things in
+// it small. The box around their centers is cut into bins along each side and every boundary
+// between bins is tried. Returns where the second half starts, or None if the things can't be
+// told apart (e.g. their centers are all in the same place).
+fn sah_partition(
+    indices: &mut [usize],
+    bounds: &[Bounds],
+    centroids: &Bounds,
+    bins: usize,
+) -> Option<usize> {
+    let bin_of = |i: usize, axis: usize| -> usize {
+        let extent = centroids.max[axis] - centroids.min[axis];
+        let position = (bounds[i].centroid()[axis] - centroids.min[axis]) / extent;
+        ((position * bins as f64) as usize).min(bins - 1)
+    };
+
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        if centroids.max[axis] - centroids.min[axis] <= 0.0 {
+            continue;
+        }
+
+        let mut binned: Vec<(Option<Bounds>, usize)> = vec![(None, 0); bins];
+        for &i in indices.iter() {
+            let (bin_bounds, count) = &mut binned[bin_of(i, axis)];
+            *bin_bounds = Some(bin_bounds.map_or(bounds[i], |b| b.union(&bounds[i])));
+            *count += 1;
+        }
+
+        // The area and count of everything to the right of each boundary, swept from the right.
+        let mut right_costs = vec![0.0; bins];
+        let mut right: (Option<Bounds>, usize) = (None, 0);
+        for split in (1..bins).rev() {
+            right = merge(right, binned[split]);
+            right_costs[split] = cost(right);
+        }
+
+        let mut left: (Option<Bounds>, usize) = (None, 0);
+        for split in 1..bins {
+            left = merge(left, binned[split - 1]);
+            if left.1 == 0 || left.1 == indices.len() {
+                continue;
+            }
+            let split_cost = cost(left) + right_costs[split];
+            if best.is_none_or(|(best_cost, _, _)| split_cost < best_cost) {
+                best = Some((split_cost, axis, split));
+            }
+        }
+    }
+
+    let (_, axis, split) = best?;
+    let mut middle = 0;
+    for j in 0..indices.len() {
+        if bin_of(indices[j], axis) < split {
+            indices.swap(j, middle);
+            middle += 1;
+        }
+    }
+    Some(middle)
+}
+
+fn merge(a: (Option<Bounds>, usize), b: (Option<Bounds>, usize)) -> (Option<Bounds>, usize) {
+    let bounds = match (a.0, b.0) {
+        (Some(a), Some(b)) => Some(a.union(&b)),
+        (a, b) => a.or(b),
+    };
+    (bounds, a.1 + b.1)
+}
+
+fn cost((bounds, count): (Option<Bounds>, usize)) -> f64 {
+    bounds.map_or(0.0, |b| b.surface_area() * count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Matrix, point::Point, vector::Vector};
+
+    // A row of unit boxes along the x axis, one at each whole number.
+    fn row(n: usize) -> Vec<Bounds> {
+        (0..n)
+            .map(|i| {
+                let x = i as f64;
+                Bounds::new(
+                    Point::new(x - 0.5, -0.5, -0.5),
+                    Point::new(x + 0.5, 0.5, 0.5),
+                )
+            })
+            .collect()
+    }
+
+    // A tight cluster of small boxes, with a few big ones spread out far away from it.
+    fn clusters() -> Vec<Bounds> {
+        let mut bounds: Vec<Bounds> = (0..200)
+            .map(|i| {
+                let x = (i % 20) as f64 * 0.05;
+                let y = (i / 20) as f64 * 0.05;
+                Bounds::new(Point::new(x, y, 0.0), Point::new(x + 0.04, y + 0.04, 0.04))
+            })
+            .collect();
+        bounds.extend((1..=8).map(|i| {
+            let x = 10.0 * i as f64;
+            Bounds::new(Point::new(x, -2.0, -2.0), Point::new(x + 4.0, 2.0, 2.0))
+        }));
+        bounds
+    }
+
+    fn visited(bvh: &Bvh, ray: Ray) -> Vec<usize> {
+        let mut visited = Vec::new();
+        bvh.visit(ray, |i| visited.push(i));
+        visited.sort_unstable();
+        visited
+    }
+
+    #[test]
+    fn an_empty_tree_has_nothing_to_visit() {
+        let bvh = Bvh::new(&[]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(bvh.bounds().is_none());
+        assert_eq!(0, bvh.visit(r, |_| panic!("nothing to visit")));
+    }
+
+    #[test]
+    fn the_tree_is_bounded_by_everything_in_it() {
+        let bvh = Bvh::new(&row(10));
+
+        let bounds = bvh.bounds().unwrap();
+
+        assert_fuzzy_eq!(Point::new(-0.5, -0.5, -0.5), bounds.min);
+        assert_fuzzy_eq!(Point::new(9.5, 0.5, 0.5), bounds.max);
+    }
+
+    #[test]
+    fn a_ray_only_visits_the_boxes_it_passes_through() {
+        let bvh = Bvh::new(&row(100));
+        let r = Ray::new(Point::new(42.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let mut visited = Vec::new();
+        let tests = bvh.visit(r, |i| visited.push(i));
+
+        assert!(visited.contains(&42));
+        assert!(visited.len() <= MAX_LEAF_SIZE);
+        assert!(tests < 100);
+    }
+
+    #[test]
+    fn a_ray_along_the_row_visits_everything() {
+        let bvh = Bvh::new(&row(20));
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+
+        assert_eq!((0..20).collect::<Vec<_>>(), visited(&bvh, r));
+    }
+
+    #[test]
+    fn things_behind_the_ray_are_visited() {
+        let bvh = Bvh::new(&row(20));
+        let r = Ray::new(Point::new(7.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let visited = visited(&bvh, r);
+        assert!(visited.contains(&7));
+        assert!(visited.len() <= MAX_LEAF_SIZE);
+    }
+
+    #[test]
+    fn every_quality_finds_the_same_things() {
+        let bounds = clusters();
+        let rays = [
+            Ray::new(Point::new(0.0, 0.0, -50.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(20.0, 0.3, -50.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(-5.0, 0.1, 0.2), Vector::new(1.0, 0.01, 0.0)),
+        ];
+
+        for r in rays {
+            let expected: Vec<usize> = (0..bounds.len())
+                .filter(|&i| bounds[i].intersects_line(r))
+                .collect();
+            for quality in [
+                BuildQuality::Fast,
+                BuildQuality::Balanced,
+                BuildQuality::Best,
+            ] {
+                let bvh = Bvh::with_quality(&bounds, quality);
+                let found = visited(&bvh, r);
+                assert!(expected.iter().all(|i| found.contains(i)));
+            }
+        }
+    }
+
+    #[test]
+    fn the_surface_area_heuristic_makes_a_better_tree() {
+        let bounds = clusters();
+        let median = Bvh::with_quality(&bounds, BuildQuality::Fast);
+        let sah = Bvh::with_quality(&bounds, BuildQuality::Best);
+
+        let tests = |bvh: &Bvh| -> usize {
+            (0..100)
+                .map(|i| {
+                    let y = i as f64 / 100.0 - 0.5;
+                    bvh.visit(
+                        Ray::new(Point::new(-5.0, y, -50.0), Vector::new(0.2, 0.0, 1.0)),
+                        |_| {},
+                    )
+                })
+                .sum()
+        };
+
+        assert!(tests(&sah) < tests(&median));
+    }
+
+    #[test]
+    fn building_a_big_tree_in_parallel() {
+        let bounds = row(2 * PARALLEL_BUILD_SIZE);
+        let bvh = Bvh::new(&bounds);
+        let r = Ray::new(Point::new(5000.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(visited(&bvh, r).contains(&5000));
+    }
+
+    #[test]
+    fn a_refitted_tree_finds_things_where_they_moved_to() {
+        let mut bounds = row(50);
+        let mut bvh = Bvh::new(&bounds);
+        for b in bounds.iter_mut() {
+            *b = b.transformed(Matrix::translate(0.0, 3.0, 0.0));
+        }
+
+        bvh.refit(&bounds);
+
+        let r = Ray::new(Point::new(20.0, 3.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(visited(&bvh, r).contains(&20));
+        assert_fuzzy_eq!(Point::new(-0.5, 2.5, -0.5), bvh.bounds().unwrap().min);
+        // Moving everything together doesn't make the tree any worse.
+        assert_fuzzy_eq!(1.0, bvh.degradation());
+    }
+
+    #[test]
+    fn scattering_things_degrades_a_refitted_tree() {
+        let mut bounds = row(50);
+        let mut bvh = Bvh::new(&bounds);
+        // Swap the two ends of the row around, so neighbours in the tree are far apart.
+        for (i, b) in bounds.iter_mut().enumerate() {
+            let x = if i % 2 == 0 { 0.0 } else { -100.0 };
+            *b = b.transformed(Matrix::translate(x, 0.0, 0.0));
+        }
+
+        bvh.refit(&bounds);
+
+        assert!(bvh.degradation() > 2.0);
+        assert_fuzzy_eq!(1.0, Bvh::new(&bounds).degradation());
+        let r = Ray::new(Point::new(-99.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(visited(&bvh, r).contains(&1));
+    }
+
+    #[test]
+    fn unbounded_things_are_always_visited() {
+        let mut bounds = row(10);
+        bounds.push(Bounds::infinite());
+        let bvh = Bvh::new(&bounds);
+        let r = Ray::new(Point::new(0.0, 50.0, 5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(vec![10], visited(&bvh, r));
+        assert!(!bvh.bounds().unwrap().is_finite());
+    }
+}
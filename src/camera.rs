@@ -1,10 +1,27 @@
-use crate::{matrix::Matrix, point::Point, ray::Ray, vector::Vector};
+use std::f64::consts::FRAC_PI_3;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{body::Body, matrix::Matrix, point::Point, ray::Ray, vector::Vector};
+
+mod lens;
+mod panorama;
+
+pub use lens::*;
+pub use panorama::*;
 
 pub struct Camera {
     pub transform: Matrix<4>,
     pub vsize: usize,
     pub hsize: usize,
     pub field_of_view: f64,
+    // Depth of field. Things focal_distance in front of the camera are sharp, and the wider the
+    // aperture the blurrier everything else is. An aperture of 0 is a pinhole with everything in
+    // focus.
+    pub aperture: f64,
+    pub focal_distance: f64,
+    pub projection: Projection,
 
     half_width: f64,
     half_height: f64,
@@ -29,12 +46,24 @@ impl Camera {
             vsize,
             hsize,
             field_of_view,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            projection: Projection::Perspective,
             half_width,
             half_height,
             pixel_size,
         }
     }
 
+    // A camera framing World::quickstart, standing back from the sphere and looking slightly down.
+    pub fn quickstart(hsize: usize, vsize: usize) -> Self {
+        Self::new(hsize, vsize, FRAC_PI_3).look_at_from_position(
+            Point::new(0.0, 1.5, -5.0),
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+
     pub fn with_transform(self, transform: Matrix<4>) -> Self {
         Self { transform, ..self }
     }
@@ -60,23 +89,187 @@ impl Camera {
         }
     }
 
-    pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-        let offset_x = (0.5 + x as f64) * self.pixel_size;
-        let offset_y = (0.5 + y as f64) * self.pixel_size;
+    pub fn with_aperture(self, aperture: f64) -> Self {
+        if aperture < 0.0 {
+            panic!("aperture must not be negative")
+        }
+        Self { aperture, ..self }
+    }
+
+    pub fn with_focal_distance(self, focal_distance: f64) -> Self {
+        if focal_distance <= 0.0 {
+            panic!("focal distance must be greater than zero")
+        }
+        Self {
+            focal_distance,
+            ..self
+        }
+    }
+
+    // Focuses on the plane through the point that faces the camera. Set the camera's transform
+    // first, since the distance is measured from where the camera is looking from.
+    pub fn focus_at(self, point: Point) -> Self {
+        let depth = -(self.transform * point)[2];
+        if depth <= 0.0 {
+            panic!("the camera can only focus on points in front of it")
+        }
+        self.with_focal_distance(depth)
+    }
+
+    // Focuses on the body's origin, which for most bodies is their middle.
+    pub fn focus_on(self, body: &Body) -> Self {
+        self.focus_at(body.transform() * Point::new(0.0, 0.0, 0.0))
+    }
+
+    // A ray through the pixel from a point on the lens, given as (u, v) in [0, 1) squared. Rays
+    // from anywhere on the lens meet again at the focal distance, so averaging many of them
+    // blurs everything else. Panoramas are always pinholes.
+    pub fn lens_ray_for_pixel(&self, x: usize, y: usize, u: f64, v: f64) -> Ray {
+        self.lens_ray_near_pixel(x, y, 0.0, 0.0, u, v)
+    }
+
+    // Like lens_ray_for_pixel, for a ray (dx, dy) pixels from the middle of the pixel, as in
+    // ray_near_pixel.
+    pub fn lens_ray_near_pixel(&self, x: usize, y: usize, dx: f64, dy: f64, u: f64, v: f64) -> Ray {
+        if self.projection != Projection::Perspective {
+            return self.ray_near_pixel(x, y, dx, dy);
+        }
+        let offset_x = (0.5 + x as f64 + dx) * self.pixel_size;
+        let offset_y = (0.5 + y as f64 + dy) * self.pixel_size;
         let world_x = self.half_width - offset_x;
         let world_y = self.half_height - offset_y;
 
+        // Spread the samples evenly over the disk of the lens.
+        let radius = self.aperture / 2.0 * u.sqrt();
+        let angle = 2.0 * std::f64::consts::PI * v;
+        let lens_point = Point::new(radius * angle.cos(), radius * angle.sin(), 0.0);
+        let focus_point = Point::new(
+            world_x * self.focal_distance,
+            world_y * self.focal_distance,
+            -self.focal_distance,
+        );
+
         let inverse_view_transform = self.transform.inverse();
+        let origin = inverse_view_transform * lens_point;
+        let target = inverse_view_transform * focus_point;
+        let spread = self.pixel_size / Vector::new(world_x, world_y, 1.0).magnitude();
+        Ray::new(origin, (target - origin).normalize())
+            .with_back_face_culling()
+            .with_spread(spread)
+    }
+
+    pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
+        self.ray_for_position(x as f64 + 0.5, y as f64 + 0.5)
+    }
+
+    // A ray through anywhere in the image, in pixels from the top left corner like `project`, so
+    // that (x + 0.5, y + 0.5) is the middle of pixel (x, y).
+    pub fn ray_for_position(&self, x: f64, y: f64) -> Ray {
+        self.ray_through(x, y, self.transform.inverse())
+    }
+
+    // A ray (dx, dy) pixels from the middle of pixel (x, y), for sampling around it. Unlike
+    // ray_for_position, samples from a stereo panorama stay in the pixel's eye.
+    pub fn ray_near_pixel(&self, x: usize, y: usize, dx: f64, dy: f64) -> Ray {
+        let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+        match self.projection {
+            Projection::Perspective => self.ray_for_position(px + dx, py + dy),
+            Projection::StereoPanorama {
+                interpupillary_distance,
+            } => self.panorama_ray(
+                px + dx,
+                py + dy,
+                py,
+                interpupillary_distance,
+                self.transform.inverse(),
+            ),
+        }
+    }
+
+    // Every pixel's ray, as (x, y, ray), a row at a time from the top left, for render loops of
+    // your own.
+    pub fn rays(&self) -> impl Iterator<Item = (usize, usize, Ray)> + '_ {
+        let inverse_view_transform = self.transform.inverse();
+        (0..self.vsize).flat_map(move |y| {
+            (0..self.hsize).map(move |x| {
+                let ray = self.ray_through(x as f64 + 0.5, y as f64 + 0.5, inverse_view_transform);
+                (x, y, ray)
+            })
+        })
+    }
+
+    // The same rays as `rays`, shared out over rayon's threads. Collecting them keeps them in
+    // the same order.
+    #[cfg(feature = "parallel")]
+    pub fn par_rays(&self) -> impl IndexedParallelIterator<Item = (usize, usize, Ray)> + '_ {
+        let inverse_view_transform = self.transform.inverse();
+        (0..self.hsize * self.vsize).into_par_iter().map(move |i| {
+            let (x, y) = (i % self.hsize, i / self.hsize);
+            let ray = self.ray_through(x as f64 + 0.5, y as f64 + 0.5, inverse_view_transform);
+            (x, y, ray)
+        })
+    }
+
+    fn ray_through(&self, x: f64, y: f64, inverse_view_transform: Matrix<4>) -> Ray {
+        if let Projection::StereoPanorama {
+            interpupillary_distance,
+        } = self.projection
+        {
+            return self.panorama_ray(x, y, y, interpupillary_distance, inverse_view_transform);
+        }
+        let offset_x = x * self.pixel_size;
+        let offset_y = y * self.pixel_size;
+        let world_x = self.half_width - offset_x;
+        let world_y = self.half_height - offset_y;
 
         let wall_point = inverse_view_transform * Point::new(world_x, world_y, -1.0);
         let ray_origin = inverse_view_transform * Point::new(0.0, 0.0, 0.0);
+        // The pixel is pixel_size across where the ray crosses the canvas, which is further away
+        // towards the corners.
+        let spread = self.pixel_size / Vector::new(world_x, world_y, 1.0).magnitude();
         Ray::new(ray_origin, (wall_point - ray_origin).normalize())
+            .with_back_face_culling()
+            .with_spread(spread)
+    }
+
+    // Where the point is seen in the camera's image, in pixels from the top left corner, so that
+    // the middle of pixel (x, y) is at (x + 0.5, y + 0.5). Points behind the camera aren't seen.
+    // Stereo panoramas see everything, and give where it is for the left eye as if it were far
+    // away (see project_direction).
+    pub fn project(&self, point: Point) -> Option<(f64, f64)> {
+        let p = self.transform * point;
+        if self.projection != Projection::Perspective {
+            return self.panorama_pixels(p - Point::new(0.0, 0.0, 0.0));
+        }
+        self.to_pixels(p[0], p[1], p[2])
+    }
+
+    // Where things infinitely far away in the direction are seen, like the sky.
+    pub fn project_direction(&self, direction: Vector) -> Option<(f64, f64)> {
+        let d = self.transform * direction;
+        if self.projection != Projection::Perspective {
+            return self.panorama_pixels(d);
+        }
+        self.to_pixels(d[0], d[1], d[2])
+    }
+
+    // Undoes ray_for_pixel for a position in camera space.
+    fn to_pixels(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64)> {
+        if z >= 0.0 {
+            return None;
+        }
+        let world_x = x / -z;
+        let world_y = y / -z;
+        Some((
+            (self.half_width - world_x) / self.pixel_size,
+            (self.half_height - world_y) / self.pixel_size,
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{fuzzy_eq::FuzzyEq, matrix::Rotation, vector::Vector};
+    use crate::{fuzzy_eq::FuzzyEq, matrix::Rotation, sphere::Sphere, vector::Vector};
     use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
 
     use crate::assert_fuzzy_eq;
@@ -122,6 +315,24 @@ mod tests {
         assert_fuzzy_eq!(Vector::new(0.66519, 0.33259, -0.66851), r.direction);
     }
 
+    #[test]
+    fn camera_rays_spread_over_their_pixel() {
+        let c = Camera::new(201, 101, FRAC_PI_2);
+        let pixel_size = 2.0 / 201.0;
+
+        assert_fuzzy_eq!(pixel_size, c.ray_for_pixel(100, 50).spread);
+        // The corner pixel is further from the camera, so it spreads less.
+        let corner = c.ray_for_pixel(0, 0).spread;
+        assert!(corner < pixel_size && corner > 0.6 * pixel_size);
+        // Rays that go on from them spread as much.
+        let r = c.ray_for_pixel(100, 50);
+        assert_fuzzy_eq!(
+            pixel_size,
+            r.continued(Point::new(0.0, 0.0, -1.0), Vector::new(1.0, 0.0, 0.0))
+                .spread
+        );
+    }
+
     #[test]
     fn constructing_a_ray_when_camera_is_transformed() {
         let c = Camera::new(201, 101, FRAC_PI_2).with_transform(
@@ -133,6 +344,35 @@ mod tests {
         assert_fuzzy_eq!(Vector::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2), r.direction);
     }
 
+    #[test]
+    fn the_cameras_rays_cover_the_image_a_row_at_a_time() {
+        let c = Camera::new(4, 3, FRAC_PI_2).with_transform(Matrix::translate(0.0, -2.0, 5.0));
+
+        let rays: Vec<(usize, usize, Ray)> = c.rays().collect();
+
+        assert_eq!(12, rays.len());
+        for (i, (x, y, ray)) in rays.iter().enumerate() {
+            assert_eq!((i % 4, i / 4), (*x, *y));
+            let expected = c.ray_for_pixel(*x, *y);
+            assert_fuzzy_eq!(expected.origin, ray.origin);
+            assert_fuzzy_eq!(expected.direction, ray.direction);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn the_parallel_rays_are_the_same_rays() {
+        let c = Camera::quickstart(16, 9);
+
+        let rays: Vec<(usize, usize, Ray)> = c.par_rays().collect();
+
+        assert_eq!(c.rays().count(), rays.len());
+        for ((x, y, a), (px, py, b)) in c.rays().zip(rays) {
+            assert_eq!((x, y), (px, py));
+            assert_fuzzy_eq!(a.direction, b.direction);
+        }
+    }
+
     #[test]
     fn view_transform_for_the_default_orientation() {
         let from = Point::new(0.0, 0.0, 0.0);
@@ -180,4 +420,60 @@ mod tests {
             camera.transform
         )
     }
+
+    fn camera_looking_down_z() -> Camera {
+        Camera::new(11, 11, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn focusing_at_a_point_uses_its_depth_in_front_of_the_camera() {
+        let c = camera_looking_down_z().focus_at(Point::new(3.0, 2.0, 1.0));
+
+        assert_fuzzy_eq!(6.0, c.focal_distance);
+    }
+
+    #[test]
+    fn focusing_on_a_body() {
+        let body = Body::from(Sphere::default().with_transform(Matrix::translate(0.0, 1.0, 2.0)));
+
+        let c = camera_looking_down_z().focus_on(&body);
+
+        assert_fuzzy_eq!(7.0, c.focal_distance);
+    }
+
+    #[test]
+    #[should_panic(expected = "the camera can only focus on points in front of it")]
+    fn focusing_behind_the_camera_panics() {
+        camera_looking_down_z().focus_at(Point::new(0.0, 0.0, -10.0));
+    }
+
+    #[test]
+    fn lens_rays_meet_at_the_focal_distance() {
+        let c = camera_looking_down_z()
+            .with_aperture(0.5)
+            .focus_at(Point::new(0.0, 0.0, 1.0));
+        let pinhole = c.ray_for_pixel(2, 3);
+
+        for (u, v) in [(0.0, 0.0), (0.9, 0.1), (0.5, 0.75)] {
+            let r = c.lens_ray_for_pixel(2, 3, u, v);
+            let t = 6.0 / r.direction[2];
+            let pinhole_t = 6.0 / pinhole.direction[2];
+            assert_fuzzy_eq!(pinhole.position(pinhole_t), r.position(t));
+        }
+    }
+
+    #[test]
+    fn a_pinhole_lens_ray_is_the_plain_ray() {
+        let c = camera_looking_down_z().with_focal_distance(3.0);
+
+        let r = c.lens_ray_for_pixel(0, 10, 0.7, 0.2);
+        let plain = c.ray_for_pixel(0, 10);
+
+        assert_fuzzy_eq!(plain.origin, r.origin);
+        assert_fuzzy_eq!(plain.direction, r.direction);
+    }
 }
@@ -0,0 +1,203 @@
+use super::Camera;
+
+// Setting up a camera the way a photographer would, with a lens's focal length and the size of
+// the sensor behind it, rather than an angle.
+
+// A sensor's size in millimeters, given landscape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sensor {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Sensor {
+    // 35mm film, which focal lengths are usually quoted for.
+    pub const FULL_FRAME: Sensor = Sensor {
+        width: 36.0,
+        height: 24.0,
+    };
+    pub const APS_C: Sensor = Sensor {
+        width: 23.6,
+        height: 15.7,
+    };
+    pub const MICRO_FOUR_THIRDS: Sensor = Sensor {
+        width: 17.3,
+        height: 13.0,
+    };
+    // The film gate of most digital cinema cameras.
+    pub const SUPER_35: Sensor = Sensor {
+        width: 24.89,
+        height: 18.66,
+    };
+
+    pub fn diagonal(&self) -> f64 {
+        self.width.hypot(self.height)
+    }
+
+    // How much smaller the sensor is than full frame, across the diagonal. A lens on this sensor
+    // sees as much as one this many times longer on full frame.
+    pub fn crop_factor(&self) -> f64 {
+        Sensor::FULL_FRAME.diagonal() / self.diagonal()
+    }
+
+    // The focal length that would see as much on full frame as `focal_length` does on this sensor.
+    pub fn equivalent_focal_length(&self, focal_length: f64) -> f64 {
+        focal_length * self.crop_factor()
+    }
+
+    // How much of the sensor an image of the given shape uses, in millimeters. Portrait images turn
+    // the sensor on its side, like turning the camera, and an image of a different shape to the
+    // sensor uses all of one side and as much of the other as it needs.
+    pub fn fit(&self, hsize: usize, vsize: usize) -> (f64, f64) {
+        let aspect = hsize as f64 / vsize as f64;
+        let (long, short) = (self.width.max(self.height), self.width.min(self.height));
+        let (width, height) = if aspect >= 1.0 {
+            (long, short)
+        } else {
+            (short, long)
+        };
+        if aspect >= width / height {
+            (width, width / aspect)
+        } else {
+            (height * aspect, height)
+        }
+    }
+}
+
+// Common lenses, by their focal length on full frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LensPreset {
+    UltraWide,
+    Wide,
+    Normal,
+    Portrait,
+    Telephoto,
+}
+
+impl LensPreset {
+    pub fn focal_length(&self) -> f64 {
+        match self {
+            LensPreset::UltraWide => 14.0,
+            LensPreset::Wide => 24.0,
+            LensPreset::Normal => 50.0,
+            LensPreset::Portrait => 85.0,
+            LensPreset::Telephoto => 200.0,
+        }
+    }
+}
+
+// The angle a lens sees across a length of sensor, both in millimeters.
+pub fn field_of_view(focal_length: f64, sensor_size: f64) -> f64 {
+    2.0 * (sensor_size / (2.0 * focal_length)).atan()
+}
+
+// The focal length that sees the angle across a length of sensor.
+pub fn focal_length(field_of_view: f64, sensor_size: f64) -> f64 {
+    sensor_size / (2.0 * (field_of_view / 2.0).tan())
+}
+
+impl Camera {
+    // A camera with a lens of the focal length, in millimeters, in front of the sensor. The image
+    // covers as much of the sensor as fits its shape; see Sensor::fit.
+    pub fn with_lens(hsize: usize, vsize: usize, focal_length: f64, sensor: Sensor) -> Self {
+        if focal_length <= 0.0 {
+            panic!("focal length must be greater than zero")
+        }
+        // Camera::new's field of view is across the longer side of the image.
+        let (width, height) = sensor.fit(hsize, vsize);
+        Camera::new(hsize, vsize, field_of_view(focal_length, width.max(height)))
+    }
+
+    // A camera with one of the common lenses on a full frame sensor.
+    pub fn with_preset(hsize: usize, vsize: usize, preset: LensPreset) -> Self {
+        Camera::with_lens(hsize, vsize, preset.focal_length(), Sensor::FULL_FRAME)
+    }
+
+    // The focal length that would give this camera's view on the sensor.
+    pub fn focal_length(&self, sensor: Sensor) -> f64 {
+        let (width, height) = sensor.fit(self.hsize, self.vsize);
+        focal_length(self.field_of_view, width.max(height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn a_normal_lens_sees_about_40_degrees_across_full_frame() {
+        assert!((field_of_view(50.0, 36.0).to_degrees() - 39.5978).abs() < 1e-4);
+        assert_fuzzy_eq!(50.0, focal_length(field_of_view(50.0, 36.0), 36.0));
+        // A lens as long as the sensor is wide sees twice atan(1/2) across it.
+        assert_fuzzy_eq!(2.0 * 0.5f64.atan(), field_of_view(36.0, 36.0));
+    }
+
+    #[test]
+    fn a_lens_on_a_smaller_sensor_sees_less() {
+        assert!((Sensor::MICRO_FOUR_THIRDS.crop_factor() - 1.9994).abs() < 1e-4);
+        assert!((Sensor::APS_C.equivalent_focal_length(35.0) - 53.4246).abs() < 1e-4);
+
+        // Half of full frame, the same shape, sees as much with half the focal length.
+        let half_frame = Sensor {
+            width: 18.0,
+            height: 12.0,
+        };
+        let small = Camera::with_lens(300, 200, 25.0, half_frame);
+        let full = Camera::with_lens(
+            300,
+            200,
+            half_frame.equivalent_focal_length(25.0),
+            Sensor::FULL_FRAME,
+        );
+        assert_fuzzy_eq!(2.0, half_frame.crop_factor());
+        assert_fuzzy_eq!(full.field_of_view, small.field_of_view);
+    }
+
+    #[test]
+    fn images_use_as_much_of_the_sensor_as_fits_their_shape() {
+        let sensor = Sensor::FULL_FRAME;
+
+        let (w, h) = sensor.fit(300, 200);
+        assert_fuzzy_eq!(36.0, w);
+        assert_fuzzy_eq!(24.0, h);
+        // Wider images crop the top and bottom, and squarer ones the sides.
+        let (w, h) = sensor.fit(1920, 1080);
+        assert_fuzzy_eq!(36.0, w);
+        assert_fuzzy_eq!(20.25, h);
+        let (w, h) = sensor.fit(100, 100);
+        assert_fuzzy_eq!(24.0, w);
+        assert_fuzzy_eq!(24.0, h);
+        // Portrait images turn the sensor.
+        let (w, h) = sensor.fit(200, 300);
+        assert_fuzzy_eq!(24.0, w);
+        assert_fuzzy_eq!(36.0, h);
+    }
+
+    #[test]
+    fn turning_the_camera_doesnt_change_the_view() {
+        let landscape = Camera::with_preset(300, 200, LensPreset::Normal);
+        let portrait = Camera::with_preset(200, 300, LensPreset::Normal);
+
+        assert_fuzzy_eq!(landscape.field_of_view, portrait.field_of_view);
+        assert_fuzzy_eq!(field_of_view(50.0, 36.0), landscape.field_of_view);
+        assert_fuzzy_eq!(50.0, portrait.focal_length(Sensor::FULL_FRAME));
+    }
+
+    #[test]
+    fn longer_presets_see_less() {
+        let presets = [
+            LensPreset::UltraWide,
+            LensPreset::Wide,
+            LensPreset::Normal,
+            LensPreset::Portrait,
+            LensPreset::Telephoto,
+        ];
+        let views: Vec<f64> = presets
+            .iter()
+            .map(|&p| Camera::with_preset(16, 9, p).field_of_view)
+            .collect();
+
+        assert!(views.windows(2).all(|w| w[0] > w[1]));
+    }
+}
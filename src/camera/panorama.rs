@@ -0,0 +1,176 @@
+use std::f64::consts::PI;
+
+use super::Camera;
+use crate::{matrix::Matrix, point::Point, ray::Ray, vector::Vector};
+
+// How the camera turns pixels into rays.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    // A flat image across the field of view, like a photo.
+    #[default]
+    Perspective,
+    // Everything around the camera for both eyes, for viewing in a VR headset: the left eye's
+    // equirectangular panorama in the top half of the image and the right eye's in the bottom
+    // half, each going all the way around and from straight up to straight down. The middle of
+    // each half looks the way the camera faces.
+    //
+    // This is omni-directional stereo: every column gets its rays from where the eyes would be if
+    // the viewer turned their head to look that way, the eyes sitting interpupillary_distance
+    // apart on a circle around the camera's position. Looking up or down the eyes are drawn in
+    // towards the middle, so that at the very top and bottom both see the same thing, and there's
+    // no seam where the halves meet or where the panorama wraps around.
+    StereoPanorama {
+        interpupillary_distance: f64,
+    },
+}
+
+impl Camera {
+    // Shorthand for a stereo panorama camera. Headsets usually want each eye twice as wide as
+    // it is tall, so a square image, e.g. 4096 x 4096. About 0.064 is a typical
+    // interpupillary distance if the scene is in meters.
+    pub fn stereo_panorama(hsize: usize, vsize: usize, interpupillary_distance: f64) -> Self {
+        Camera::new(hsize, vsize, PI).with_projection(Projection::StereoPanorama {
+            interpupillary_distance,
+        })
+    }
+
+    pub fn with_projection(self, projection: Projection) -> Self {
+        if let Projection::StereoPanorama {
+            interpupillary_distance,
+        } = projection
+        {
+            if !self.vsize.is_multiple_of(2) {
+                panic!("a stereo panorama needs an even number of rows, half for each eye")
+            }
+            if interpupillary_distance < 0.0 {
+                panic!("interpupillary distance must not be negative")
+            }
+        }
+        Self { projection, ..self }
+    }
+
+    // The ray for a position in a stereo panorama, in pixels from the top left corner, from the
+    // eye whose half `eye_y` is in. Positions past the left or right edge wrap around, and past
+    // the top or bottom of the eye's half go on over the pole rather than into the other eye's.
+    pub(super) fn panorama_ray(
+        &self,
+        x: f64,
+        y: f64,
+        eye_y: f64,
+        interpupillary_distance: f64,
+        inverse_view_transform: Matrix<4>,
+    ) -> Ray {
+        let eye_rows = (self.vsize / 2) as f64;
+        let (top, side) = if eye_y < eye_rows {
+            (0.0, -1.0)
+        } else {
+            (eye_rows, 1.0)
+        };
+        // How far the view is turned to the right, and up. Over the pole the latitude is past a
+        // right angle, which turns both the view and the eyes around.
+        let longitude = (x / self.hsize as f64 - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - (y - top) / eye_rows) * PI;
+
+        let (sin_long, cos_long) = longitude.sin_cos();
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+        // The camera looks down -z with +x on its left.
+        let direction = Vector::new(-sin_long * cos_lat, sin_lat, -cos_long * cos_lat);
+        let right = Vector::new(-cos_long, 0.0, sin_long);
+        let eye =
+            Point::new(0.0, 0.0, 0.0) + right * (side * interpupillary_distance / 2.0 * cos_lat);
+
+        let origin = inverse_view_transform * eye;
+        // Each pixel spans the same angle across.
+        let spread = 2.0 * PI / self.hsize as f64;
+        Ray::new(origin, (inverse_view_transform * direction).normalize())
+            .with_back_face_culling()
+            .with_spread(spread)
+    }
+
+    // Where a direction in camera space is in the left eye's half of a stereo panorama. Far
+    // enough away, points are in the same place for both eyes, and the right eye's is this many
+    // rows further down.
+    pub(super) fn panorama_pixels(&self, direction: Vector) -> Option<(f64, f64)> {
+        let length = direction.magnitude();
+        if length == 0.0 {
+            return None;
+        }
+        let longitude = (-direction[0]).atan2(-direction[2]);
+        let latitude = (direction[1] / length).clamp(-1.0, 1.0).asin();
+        Some((
+            (longitude / (2.0 * PI) + 0.5) * self.hsize as f64,
+            (0.5 - latitude / PI) * (self.vsize / 2) as f64,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn the_middle_of_each_eye_looks_ahead_from_either_side() {
+        let c = Camera::stereo_panorama(400, 200, 0.064);
+
+        let left = c.ray_for_position(200.0, 50.0);
+        let right = c.ray_for_position(200.0, 150.0);
+
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), left.direction);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), right.direction);
+        // The camera's left is +x.
+        assert_fuzzy_eq!(Point::new(0.032, 0.0, 0.0), left.origin);
+        assert_fuzzy_eq!(Point::new(-0.032, 0.0, 0.0), right.origin);
+    }
+
+    #[test]
+    fn the_eyes_turn_with_the_view() {
+        let c = Camera::stereo_panorama(400, 200, 0.064);
+
+        // A quarter of the way round to the right.
+        let right = c.ray_for_position(300.0, 150.0);
+
+        assert_fuzzy_eq!(Vector::new(-1.0, 0.0, 0.0), right.direction);
+        assert_fuzzy_eq!(Point::new(0.0, 0.0, 0.032), right.origin);
+    }
+
+    #[test]
+    fn the_panorama_has_no_seams() {
+        let c = Camera::stereo_panorama(400, 200, 0.064);
+
+        // Wrapping around.
+        let first = c.ray_for_position(0.0, 40.0);
+        let last = c.ray_for_position(400.0, 40.0);
+        assert_fuzzy_eq!(first.origin, last.origin);
+        assert_fuzzy_eq!(first.direction, last.direction);
+        // At the poles both eyes are in the middle.
+        let bottom = c.ray_for_position(123.0, 100.0 - 1e-9);
+        let top = c.ray_for_position(321.0, 100.0);
+        assert_fuzzy_eq!(Vector::new(0.0, -1.0, 0.0), bottom.direction);
+        assert_fuzzy_eq!(Vector::new(0.0, 1.0, 0.0), top.direction);
+        assert_fuzzy_eq!(bottom.origin, top.origin);
+        // Samples around the left eye's bottom row go over the pole, not into the right eye.
+        let over = c.ray_near_pixel(0, 99, 0.0, 1.0);
+        let across = c.ray_for_position(200.5, 99.5);
+        assert_fuzzy_eq!(across.origin, over.origin);
+        assert_fuzzy_eq!(across.direction, over.direction);
+    }
+
+    #[test]
+    fn directions_project_back_to_where_the_left_eye_sees_them() {
+        let c = Camera::stereo_panorama(400, 200, 0.064)
+            .with_transform(Matrix::translate(1.0, 2.0, 3.0));
+
+        let ray = c.ray_for_position(250.0, 30.0);
+        let (x, y) = c.project_direction(ray.direction).unwrap();
+
+        assert_fuzzy_eq!(250.0, x);
+        assert_fuzzy_eq!(30.0, y);
+    }
+
+    #[test]
+    #[should_panic(expected = "a stereo panorama needs an even number of rows, half for each eye")]
+    fn each_eye_needs_as_many_rows() {
+        Camera::stereo_panorama(400, 201, 0.064);
+    }
+}
@@ -1,9 +1,27 @@
 use crate::color::Color;
 
+mod bands;
+mod color_profile;
+mod difference;
+mod dither;
+mod encoded;
+mod from_png;
+mod glare;
+#[cfg(feature = "image")]
+mod image_buffer;
+mod lut;
+mod resize;
 mod to_png;
 mod to_ppm;
 mod to_rgba;
 
+pub use bands::*;
+pub use color_profile::*;
+pub use dither::*;
+pub use encoded::*;
+pub use glare::*;
+pub use lut::*;
+pub use resize::*;
 pub use to_png::*;
 pub use to_ppm::*;
 pub use to_rgba::*;
@@ -13,6 +31,7 @@ pub trait Rectangle {
     fn height(&self) -> usize;
 }
 
+#[derive(Clone, Debug)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
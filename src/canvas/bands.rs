@@ -0,0 +1,135 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::Canvas;
+use crate::color::Color;
+
+// A run of whole rows of a canvas, borrowed on its own so that each band can be written from a
+// different thread. Bands can't overlap, so no locking is needed, and the borrow checker makes
+// sure nothing else touches the canvas until they're all done with.
+#[derive(Debug)]
+pub struct Band<'a> {
+    // The canvas row the band starts at.
+    pub y: usize,
+    pub width: usize,
+    pixels: &'a mut [Color],
+}
+
+impl Band<'_> {
+    pub fn height(&self) -> usize {
+        self.pixels.len() / self.width
+    }
+
+    // The canvas rows the band covers.
+    pub fn rows(&self) -> std::ops::Range<usize> {
+        self.y..self.y + self.height()
+    }
+
+    // Pixels are given by where they are on the canvas, not in the band.
+    pub fn write_pixel(&mut self, x: usize, y: usize, c: Color) {
+        let idx = self.pixel_index_at(x, y);
+        self.pixels[idx] = c;
+    }
+
+    pub fn read_pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[self.pixel_index_at(x, y)]
+    }
+
+    fn pixel_index_at(&self, x: usize, y: usize) -> usize {
+        if x >= self.width || !self.rows().contains(&y) {
+            panic!("({}, {}) is outside of the band", x, y)
+        }
+        (y - self.y) * self.width + x
+    }
+}
+
+impl Canvas {
+    // The canvas split into bands of `rows` rows from the top, the last of which may be shorter.
+    pub fn bands_mut(&mut self, rows: usize) -> impl Iterator<Item = Band<'_>> {
+        let width = self.width;
+        self.pixels
+            .chunks_mut(band_len(width, rows))
+            .enumerate()
+            .map(move |(i, pixels)| Band {
+                y: i * rows,
+                width,
+                pixels,
+            })
+    }
+
+    // The same bands as bands_mut, shared out over rayon's threads.
+    #[cfg(feature = "parallel")]
+    pub fn par_bands_mut(&mut self, rows: usize) -> impl IndexedParallelIterator<Item = Band<'_>> {
+        let width = self.width;
+        self.pixels
+            .par_chunks_mut(band_len(width, rows))
+            .enumerate()
+            .map(move |(i, pixels)| Band {
+                y: i * rows,
+                width,
+                pixels,
+            })
+    }
+}
+
+fn band_len(width: usize, rows: usize) -> usize {
+    if rows == 0 {
+        panic!("a band needs at least one row")
+    }
+    // A canvas with no width has nothing to split up, but chunks can't be empty.
+    (width * rows).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn bands_cover_the_canvas_without_overlapping() {
+        let mut canvas = Canvas::new(3, 5);
+
+        let rows: Vec<_> = canvas.bands_mut(2).map(|band| band.rows()).collect();
+
+        assert_eq!(vec![0..2, 2..4, 4..5], rows);
+    }
+
+    #[test]
+    fn writing_to_bands_writes_to_the_canvas() {
+        let mut canvas = Canvas::new(4, 4);
+
+        for mut band in canvas.bands_mut(3) {
+            for y in band.rows() {
+                band.write_pixel(y, y, Color::new(y as f64, 0.0, 0.0));
+            }
+        }
+
+        for i in 0..4 {
+            assert_fuzzy_eq!(Color::new(i as f64, 0.0, 0.0), canvas.read_pixel(i, i));
+        }
+        assert_fuzzy_eq!(Color::BLACK, canvas.read_pixel(1, 0));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn bands_can_be_written_in_parallel() {
+        let mut canvas = Canvas::new(8, 8);
+
+        canvas.par_bands_mut(1).for_each(|mut band| {
+            for x in 0..band.width {
+                band.write_pixel(x, band.y, Color::new(x as f64, band.y as f64, 0.0));
+            }
+        });
+
+        assert_fuzzy_eq!(Color::new(5.0, 3.0, 0.0), canvas.read_pixel(5, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of the band")]
+    fn bands_cannot_write_outside_of_themselves() {
+        let mut canvas = Canvas::new(2, 4);
+        let mut band = canvas.bands_mut(2).nth(1).unwrap();
+
+        band.write_pixel(0, 1, Color::WHITE);
+    }
+}
@@ -0,0 +1,49 @@
+use std::{borrow::Cow, io::Write, sync::Arc};
+
+use png::{EncodingError, SrgbRenderingIntent};
+
+use crate::color::Color;
+
+// How the values in an image file are meant to be read. Viewers that aren't told guess, and they
+// don't all guess the same way, so the same render can look washed out in one and too dark in
+// another. Tagging a file makes them agree, as long as the values written match the tag.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorProfile {
+    // The canvas's values are written as they are, with nothing saying how to read them.
+    #[default]
+    Untagged,
+    // The canvas's linear values are encoded with the sRGB transfer function, and the file is
+    // tagged as sRGB, which is what viewers and browsers expect.
+    Srgb,
+    // The canvas's values are written as they are, along with an ICC profile saying how to read
+    // them, e.g. one for linear light.
+    Icc(Arc<[u8]>),
+}
+
+impl ColorProfile {
+    // The color to write for the linear color from the canvas.
+    pub fn encode(&self, color: Color) -> Color {
+        match self {
+            ColorProfile::Srgb => color.clamp(0.0, 1.0).linear_to_srgb(),
+            ColorProfile::Untagged | ColorProfile::Icc(_) => color,
+        }
+    }
+
+    // A PNG encoder for an 8 bit RGBA image tagged with the profile.
+    pub(crate) fn png_encoder<W: Write>(
+        &self,
+        w: W,
+        width: usize,
+        height: usize,
+    ) -> Result<png::Encoder<'static, W>, EncodingError> {
+        let mut info = png::Info::with_size(width as u32, height as u32);
+        info.color_type = png::ColorType::Rgba;
+        info.bit_depth = png::BitDepth::Eight;
+        match self {
+            ColorProfile::Untagged => {}
+            ColorProfile::Srgb => info.srgb = Some(SrgbRenderingIntent::Perceptual),
+            ColorProfile::Icc(profile) => info.icc_profile = Some(Cow::Owned(profile.to_vec())),
+        }
+        png::Encoder::with_info(w, info)
+    }
+}
@@ -0,0 +1,65 @@
+use super::Canvas;
+use crate::render::heat;
+
+impl Canvas {
+    // How much each pixel differs from the same pixel of the other canvas, by its most changed
+    // channel, as a heat map: black where nothing changed, through blue, red and yellow, to white
+    // for changes of 1 / gain or more. A high gain, e.g. 20, shows up the slight flicker noisy
+    // renders have from one frame to the next.
+    pub fn difference(&self, other: &Canvas, gain: f64) -> Canvas {
+        let mut heat_map = Canvas::new(self.width, self.height);
+        for (x, y, change) in self.changes(other) {
+            heat_map.write_pixel(x, y, heat(change * gain));
+        }
+        heat_map
+    }
+
+    // The average of how much each pixel differs from the other canvas, by its most changed
+    // channel, e.g. to check that a change made an animation steadier. Empty canvases don't differ.
+    pub fn mean_difference(&self, other: &Canvas) -> f64 {
+        let total: f64 = self.changes(other).map(|(_, _, change)| change).sum();
+        total / (self.width * self.height).max(1) as f64
+    }
+
+    fn changes<'a>(&'a self, other: &'a Canvas) -> impl Iterator<Item = (usize, usize, f64)> + 'a {
+        if self.width != other.width || self.height != other.height {
+            panic!("canvases must be the same size to compare them")
+        }
+        (0..self.height).flat_map(move |y| {
+            (0..self.width).map(move |x| {
+                let change = self.read_pixel(x, y) - other.read_pixel(x, y);
+                (
+                    x,
+                    y,
+                    change[0].abs().max(change[1].abs()).max(change[2].abs()),
+                )
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, color::Color, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn unchanged_pixels_are_black_and_changes_are_amplified() {
+        let before = Canvas::new(2, 1);
+        let mut after = Canvas::new(2, 1);
+        after.write_pixel(1, 0, Color::new(0.0, -0.05, 0.02));
+
+        let difference = after.difference(&before, 20.0);
+
+        assert_fuzzy_eq!(Color::BLACK, difference.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::WHITE, difference.read_pixel(1, 0));
+        assert_fuzzy_eq!(0.025, after.mean_difference(&before));
+    }
+
+    #[test]
+    fn empty_canvases_dont_differ() {
+        let empty = Canvas::new(0, 3);
+
+        assert_eq!(0.0, empty.mean_difference(&Canvas::new(0, 3)));
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::OnceLock;
+
+// Rounding each pixel to the nearest of 256 levels turns smooth gradients, like a sky or the edge
+// of a soft shadow, into visible bands, worst in dark areas where the levels are far apart by eye.
+// Dithering nudges each pixel up or down by up to one level before rounding, by a different amount
+// from pixel to pixel, so the bands break up into fine noise that averages out to the right shade.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    // Plain rounding.
+    #[default]
+    None,
+    // A repeating 8x8 Bayer matrix. Cheap and even, but its cross-hatched pattern can show.
+    Ordered,
+    // A repeating tile of blue noise, which spreads the nudges out evenly without any pattern the
+    // eye picks up on.
+    BlueNoise,
+}
+
+impl Dither {
+    // How far to nudge the pixel, in [0, 1) of a level.
+    pub(crate) fn threshold(&self, x: usize, y: usize) -> f64 {
+        match self {
+            Dither::None => 0.5,
+            Dither::Ordered => {
+                let rank = bayer_rank(x % 8, y % 8, 8);
+                (rank as f64 + 0.5) / 64.0
+            }
+            Dither::BlueNoise => {
+                let ranks = blue_noise();
+                let rank = ranks[(y % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + x % BLUE_NOISE_SIZE];
+                (rank as f64 + 0.5) / (BLUE_NOISE_SIZE * BLUE_NOISE_SIZE) as f64
+            }
+        }
+    }
+}
+
+// Where the cell comes in the order of a size x size Bayer matrix, built up recursively from the
+// 2x2 one.
+fn bayer_rank(x: usize, y: usize, size: usize) -> usize {
+    if size == 1 {
+        return 0;
+    }
+    let half = size / 2;
+    let quadrant = [0, 2, 3, 1][(y / half) * 2 + x / half];
+    4 * bayer_rank(x % half, y % half, half) + quadrant
+}
+
+const BLUE_NOISE_SIZE: usize = 32;
+
+// The order to fill the cells of the tile in, made once with the void-and-cluster method: each
+// cell in turn goes in the middle of the biggest gap between the ones already filled, so every
+// threshold's cells are spread out evenly, and the tile wraps around without a seam.
+fn blue_noise() -> &'static [usize] {
+    static RANKS: OnceLock<Vec<usize>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        let n = BLUE_NOISE_SIZE;
+        // How crowded each cell is by the filled ones, falling off with distance around the tile.
+        let mut energy = vec![0.0; n * n];
+        let mut ranks = vec![usize::MAX; n * n];
+        for rank in 0..n * n {
+            let (cell, _) = energy
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| ranks[*i] == usize::MAX)
+                .min_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+                .unwrap();
+            ranks[cell] = rank;
+
+            let (cx, cy) = (cell % n, cell / n);
+            for (i, e) in energy.iter_mut().enumerate() {
+                let dx = wrapped_distance(i % n, cx, n);
+                let dy = wrapped_distance(i / n, cy, n);
+                *e += (-((dx * dx + dy * dy) as f64) / (2.0 * 1.5 * 1.5)).exp();
+            }
+        }
+        ranks
+    })
+}
+
+fn wrapped_distance(a: usize, b: usize, size: usize) -> usize {
+    let d = a.abs_diff(b);
+    d.min(size - d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        canvas::{Canvas, ToPng, ToRgba},
+        color::Color,
+    };
+
+    // A canvas the same shade all over, a fraction of the way between two levels.
+    fn flat(width: usize, height: usize, level: f64) -> Canvas {
+        let mut c = Canvas::new(width, height);
+        let shade = level / 255.0;
+        for y in 0..height {
+            for x in 0..width {
+                c.write_pixel(x, y, Color::new(shade, shade, shade));
+            }
+        }
+        c
+    }
+
+    // How many pixels of the red channel were rounded up.
+    fn rounded_up(rgba: &[u8], level: u8) -> usize {
+        rgba.chunks_exact(4).filter(|p| p[0] > level).count()
+    }
+
+    #[test]
+    fn no_dithering_is_plain_rounding() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 1.5, -1.0));
+        c.write_pixel(1, 0, Color::new(0.2, 0.4, 0.6));
+
+        assert_eq!(c.to_rgba(), c.dithered(Dither::None).to_rgba());
+    }
+
+    #[test]
+    fn the_bayer_matrix_uses_every_rank_once() {
+        let mut ranks: Vec<usize> = (0..64).map(|i| bayer_rank(i % 8, i / 8, 8)).collect();
+        ranks.sort_unstable();
+
+        assert_eq!((0..64).collect::<Vec<_>>(), ranks);
+        assert_eq!(
+            [0, 2, 3, 1],
+            [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(x, y)| bayer_rank(x, y, 2))
+        );
+    }
+
+    #[test]
+    fn the_blue_noise_tile_uses_every_rank_once() {
+        let mut ranks = blue_noise().to_vec();
+        ranks.sort_unstable();
+
+        assert_eq!(
+            (0..BLUE_NOISE_SIZE * BLUE_NOISE_SIZE).collect::<Vec<_>>(),
+            ranks
+        );
+    }
+
+    #[test]
+    fn dithering_averages_out_to_the_shade_between_levels() {
+        for dither in [Dither::Ordered, Dither::BlueNoise] {
+            let rgba = flat(64, 64, 10.25).dithered(dither).to_rgba();
+
+            assert_eq!(1024, rounded_up(&rgba, 10));
+            assert!(rgba.chunks_exact(4).all(|p| p[0] == 10 || p[0] == 11));
+        }
+    }
+
+    #[test]
+    fn blue_noise_spreads_out_the_pixels_it_rounds_up() {
+        let rgba = flat(32, 32, 10.125).dithered(Dither::BlueNoise).to_rgba();
+
+        // An eighth of the pixels round up, and with the gaps even none of them touch.
+        let up = |x: usize, y: usize| rgba[((y % 32) * 32 + x % 32) * 4] > 10;
+        for y in 0..32 {
+            for x in 0..32 {
+                if up(x, y) {
+                    assert!(!up(x + 1, y) && !up(x, y + 1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dithered_canvases_can_be_written_as_png() {
+        let mut png = Vec::new();
+
+        flat(4, 4, 100.5)
+            .dithered(Dither::Ordered)
+            .to_png(&mut png)
+            .unwrap();
+
+        let read = Canvas::from_png(png.as_slice()).unwrap();
+        assert_eq!(4, read.width);
+    }
+}
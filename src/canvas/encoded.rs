@@ -0,0 +1,118 @@
+use super::{to_rgba::RgbaRows, Canvas, ColorProfile, Dither, Rectangle};
+use crate::color::Color;
+
+// A canvas ready to be written as an 8 bit image, with the color profile to tag it with and how
+// to dither it.
+pub struct Encoded<'a> {
+    canvas: &'a Canvas,
+    profile: ColorProfile,
+    dither: Dither,
+}
+
+impl Canvas {
+    pub fn encoded(&self, profile: ColorProfile) -> Encoded<'_> {
+        Encoded {
+            canvas: self,
+            profile,
+            dither: Dither::default(),
+        }
+    }
+
+    pub fn dithered(&self, dither: Dither) -> Encoded<'_> {
+        self.encoded(ColorProfile::default()).with_dither(dither)
+    }
+}
+
+impl Encoded<'_> {
+    pub fn with_dither(self, dither: Dither) -> Self {
+        Self { dither, ..self }
+    }
+
+    // The row's colors encoded for the profile, before they're quantized.
+    pub(crate) fn encoded_row(&self, y: usize) -> impl Iterator<Item = Color> + '_ {
+        (0..self.canvas.width).map(move |x| self.profile.encode(self.canvas.read_pixel(x, y)))
+    }
+}
+
+impl Rectangle for Encoded<'_> {
+    fn width(&self) -> usize {
+        self.canvas.width
+    }
+
+    fn height(&self) -> usize {
+        self.canvas.height
+    }
+}
+
+impl RgbaRows for Encoded<'_> {
+    fn rgba_row(&self, y: usize, row: &mut [u8]) {
+        for (x, color) in self.encoded_row(y).enumerate() {
+            let clamped = color.clamp(0.0, 1.0);
+            let threshold = self.dither.threshold(x, y);
+            for c in 0..3 {
+                row[x * 4 + c] = (clamped[c] * 255.0 + threshold).floor().min(255.0) as u8;
+            }
+            row[x * 4 + 3] = 255;
+        }
+    }
+
+    fn color_profile(&self) -> ColorProfile {
+        self.profile.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::canvas::{ToPng, ToRgba};
+
+    fn has_chunk(png: &[u8], name: &[u8; 4]) -> bool {
+        png.windows(4).any(|w| w == name)
+    }
+
+    #[test]
+    fn srgb_output_applies_the_transfer_function() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.0, 1.0));
+        c.write_pixel(1, 0, Color::new(0.001, 0.2, 2.0));
+
+        assert_eq!(
+            vec![188u8, 0, 255, 255, 3, 124, 255, 255],
+            c.encoded(ColorProfile::Srgb).to_rgba()
+        );
+    }
+
+    #[test]
+    fn pngs_are_tagged_with_their_profile() {
+        let c = Canvas::new(2, 2);
+        let png = |profile: ColorProfile| {
+            let mut png = Vec::new();
+            c.encoded(profile).to_png(&mut png).unwrap();
+            png
+        };
+
+        let untagged = png(ColorProfile::Untagged);
+        assert!(!has_chunk(&untagged, b"sRGB") && !has_chunk(&untagged, b"iCCP"));
+        assert!(has_chunk(&png(ColorProfile::Srgb), b"sRGB"));
+        assert!(has_chunk(
+            &png(ColorProfile::Icc(Arc::from(vec![0u8; 16]))),
+            b"iCCP"
+        ));
+    }
+
+    #[test]
+    fn an_untagged_canvas_writes_the_same_png_as_ever() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(0.3, 0.6, 0.9));
+        let (mut plain, mut encoded) = (Vec::new(), Vec::new());
+
+        c.to_png(&mut plain).unwrap();
+        c.encoded(ColorProfile::Untagged)
+            .to_png(&mut encoded)
+            .unwrap();
+
+        assert_eq!(plain, encoded);
+    }
+}
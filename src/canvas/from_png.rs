@@ -0,0 +1,100 @@
+use std::io::Read;
+
+use png::{BitDepth, ColorType, DecodingError, Transformations};
+
+use super::Canvas;
+use crate::color::Color;
+
+impl Canvas {
+    // Reads an 8 or 16 bit PNG, keeping all 16 bits of the latter. Like to_png, channel values map
+    // linearly to 0..=1 without any gamma correction, unless the file is tagged as sRGB, in which
+    // case they're decoded back to linear light. Alpha is ignored.
+    pub fn from_png<R: Read>(r: R) -> Result<Self, DecodingError> {
+        let mut decoder = png::Decoder::new(r);
+        decoder.set_transformations(Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        // Anything less than 8 bits is expanded to 8.
+        let sixteen = info.bit_depth == BitDepth::Sixteen;
+        let bytes = if sixteen { 2 } else { 1 };
+
+        let channels = match info.color_type {
+            ColorType::Grayscale => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            // Indexed images are expanded to RGB(A).
+            ColorType::Rgba | ColorType::Indexed => 4,
+        };
+        let (width, height) = (info.width as usize, info.height as usize);
+        let srgb = reader.info().srgb.is_some();
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            let row = &buf[y * info.line_size..];
+            for x in 0..width {
+                let px = &row[x * channels * bytes..];
+                let channel = |i: usize| {
+                    if sixteen {
+                        u16::from_be_bytes([px[2 * i], px[2 * i + 1]]) as f64 / 65535.0
+                    } else {
+                        px[i] as f64 / 255.0
+                    }
+                };
+                let color = if channels < 3 {
+                    Color::new(channel(0), channel(0), channel(0))
+                } else {
+                    Color::new(channel(0), channel(1), channel(2))
+                };
+                let color = if srgb { color.srgb_to_linear() } else { color };
+                canvas.write_pixel(x, y, color);
+            }
+        }
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        canvas::{ColorProfile, ToPng},
+        fuzzy_eq::FuzzyEq,
+    };
+
+    #[test]
+    fn a_png_round_trips() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(2, 1, Color::new(0.0, 0.2, 1.0));
+        let mut buf = Vec::new();
+        c.to_png(&mut buf).unwrap();
+
+        let read = Canvas::from_png(buf.as_slice()).unwrap();
+
+        assert_eq!(3, read.width);
+        assert_eq!(2, read.height);
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), read.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::new(0.0, 0.2, 1.0), read.read_pixel(2, 1));
+        assert_fuzzy_eq!(Color::BLACK, read.read_pixel(1, 0));
+    }
+
+    #[test]
+    fn an_srgb_png_is_read_back_as_linear_light() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.2, 0.0));
+        let mut buf = Vec::new();
+        c.encoded(ColorProfile::Srgb).to_png(&mut buf).unwrap();
+
+        let read = Canvas::from_png(buf.as_slice()).unwrap();
+
+        let difference = read.read_pixel(0, 0) - Color::new(0.5, 0.2, 0.0);
+        assert!((0..3).all(|i| difference[i].abs() < 0.005));
+    }
+
+    #[test]
+    fn reading_something_that_is_not_a_png_fails() {
+        assert!(Canvas::from_png(&b"P3\n1 1\n255\n0 0 0\n"[..]).is_err());
+    }
+}
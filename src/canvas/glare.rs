@@ -0,0 +1,177 @@
+use std::f64::consts::PI;
+
+use super::Canvas;
+use crate::color::Color;
+
+// Camera lenses don't focus bright lights perfectly: some of the light is smeared into streaks
+// by the aperture blades and some bounces between the lens elements to land as faint ghosts on
+// the other side of the middle of the image. Adding this back in afterwards makes bright lights
+// in a render read as bright, where they'd otherwise just clip to white. Only the light above
+// `threshold` in a linear canvas glares, so it needs to be done before the canvas is clamped or
+// tone mapped.
+#[derive(Clone, Copy, Debug)]
+pub struct Glare {
+    pub threshold: f64,
+    // How much of the light above the threshold goes into streaks and ghosts.
+    pub strength: f64,
+    // The number of streaks in the star around each bright pixel (an aperture with n blades
+    // makes n of them, or 2n for an odd n), and how long they are, in pixels.
+    pub streaks: usize,
+    pub streak_length: usize,
+    // The number of ghosts, each a smaller or bigger copy of the bright parts of the image
+    // flipped through its middle.
+    pub ghosts: usize,
+}
+
+impl Default for Glare {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            strength: 0.1,
+            streaks: 6,
+            streak_length: 32,
+            ghosts: 3,
+        }
+    }
+}
+
+impl Glare {
+    pub fn with_threshold(self, threshold: f64) -> Self {
+        Self { threshold, ..self }
+    }
+
+    pub fn with_strength(self, strength: f64) -> Self {
+        Self { strength, ..self }
+    }
+
+    pub fn with_streaks(self, streaks: usize, streak_length: usize) -> Self {
+        Self {
+            streaks,
+            streak_length,
+            ..self
+        }
+    }
+
+    pub fn with_ghosts(self, ghosts: usize) -> Self {
+        Self { ghosts, ..self }
+    }
+}
+
+// The colors ghosts are tinted, cycled through, since each bounce between lens coatings lets
+// through some colors more than others.
+const GHOST_TINTS: [(f64, f64, f64); 3] = [(0.6, 0.8, 1.0), (1.0, 0.7, 0.4), (0.6, 1.0, 0.6)];
+
+impl Canvas {
+    pub fn with_glare(&self, glare: &Glare) -> Canvas {
+        let mut glared = self.clone();
+        let (cx, cy) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        let add = |glared: &mut Canvas, x: f64, y: f64, c: Color| {
+            if x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height {
+                let (x, y) = (x as usize, y as usize);
+                glared.write_pixel(x, y, glared.read_pixel(x, y) + c);
+            }
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = self.read_pixel(x, y);
+                let over = Color::new(
+                    (c[0] - glare.threshold).max(0.0),
+                    (c[1] - glare.threshold).max(0.0),
+                    (c[2] - glare.threshold).max(0.0),
+                );
+                if over[0] + over[1] + over[2] <= 0.0 {
+                    continue;
+                }
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+
+                // The streaks fade out along their length, sharing the light between them.
+                if glare.streaks > 0 && glare.streak_length > 0 {
+                    let total: f64 = (1..=glare.streak_length).map(|s| fade(s, glare)).sum();
+                    let share = glare.strength / (glare.streaks as f64 * total);
+                    for i in 0..glare.streaks {
+                        let angle = PI / 4.0 + 2.0 * PI * i as f64 / glare.streaks as f64;
+                        let (dx, dy) = (angle.cos(), angle.sin());
+                        for s in 1..=glare.streak_length {
+                            let (sx, sy) = (px + dx * s as f64, py + dy * s as f64);
+                            add(&mut glared, sx, sy, over * (share * fade(s, glare)));
+                        }
+                    }
+                }
+
+                // Each ghost is fainter than the last, and spread over a bigger or smaller image.
+                for g in 0..glare.ghosts {
+                    let scale = [-0.5, -1.0, 0.3, -1.6, 0.7][g % 5] * (1.0 + (g / 5) as f64);
+                    let (r, gr, b) = GHOST_TINTS[g % GHOST_TINTS.len()];
+                    let tint = Color::new(r, gr, b) * (glare.strength * 0.5f64.powi(g as i32 + 1));
+                    let (gx, gy) = (cx + (px - cx) * scale, cy + (py - cy) * scale);
+                    add(&mut glared, gx, gy, over * tint);
+                }
+            }
+        }
+        glared
+    }
+}
+
+// How bright a streak is s pixels out, relative to the start.
+fn fade(s: usize, glare: &Glare) -> f64 {
+    (-4.0 * s as f64 / glare.streak_length as f64).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    fn lit(x: usize, y: usize, brightness: f64) -> Canvas {
+        let mut c = Canvas::new(41, 41);
+        c.write_pixel(x, y, Color::new(brightness, brightness, brightness));
+        c
+    }
+
+    fn total(c: &Canvas) -> f64 {
+        let mut sum = 0.0;
+        for y in 0..c.height {
+            for x in 0..c.width {
+                let p = c.read_pixel(x, y);
+                sum += p[0] + p[1] + p[2];
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn nothing_below_the_threshold_glares() {
+        let c = lit(20, 20, 0.9);
+
+        let glared = c.with_glare(&Glare::default());
+
+        assert_fuzzy_eq!(total(&c), total(&glared));
+    }
+
+    #[test]
+    fn bright_pixels_streak_out_diagonally() {
+        let glare = Glare::default().with_streaks(4, 10).with_ghosts(0);
+
+        let glared = lit(20, 20, 11.0).with_glare(&glare);
+
+        // The light over the threshold, spread among the streaks.
+        assert_fuzzy_eq!(total(&lit(20, 20, 11.0)) + 3.0 * 10.0 * 0.1, total(&glared));
+        assert!(glared.read_pixel(23, 23)[0] > 0.0);
+        assert!(glared.read_pixel(17, 23)[0] > 0.0);
+        assert_fuzzy_eq!(Color::BLACK, glared.read_pixel(23, 20));
+    }
+
+    #[test]
+    fn ghosts_land_across_the_middle() {
+        let glare = Glare::default().with_streaks(0, 0).with_ghosts(2);
+
+        let glared = lit(4, 4, 3.0).with_glare(&glare);
+
+        // The pixel's middle is 16 from the image's middle, so the ghosts are 8 and 16 the other
+        // way.
+        assert!(glared.read_pixel(28, 28)[2] > 0.0);
+        assert!(glared.read_pixel(36, 36)[0] > 0.0);
+        assert_fuzzy_eq!(Color::new(3.0, 3.0, 3.0), glared.read_pixel(4, 4));
+    }
+}
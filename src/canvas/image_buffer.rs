@@ -0,0 +1,120 @@
+use image::{Rgb32FImage, RgbaImage};
+
+use super::{Canvas, ToRgba};
+use crate::color::Color;
+
+// Conversions to and from the image crate's buffers, for its filters and encoders. Like to_png,
+// 8 bit channels map linearly to 0..=1 without any gamma correction, and colors outside that are
+// clamped. 32 bit float images keep colors exactly as they are, including anything brighter than
+// white. Alpha is ignored coming in and opaque going out.
+
+impl From<&Canvas> for RgbaImage {
+    fn from(canvas: &Canvas) -> Self {
+        RgbaImage::from_raw(canvas.width as u32, canvas.height as u32, canvas.to_rgba())
+            .expect("a canvas has four bytes for every pixel")
+    }
+}
+
+impl From<Canvas> for RgbaImage {
+    fn from(canvas: Canvas) -> Self {
+        RgbaImage::from(&canvas)
+    }
+}
+
+impl From<&Canvas> for Rgb32FImage {
+    fn from(canvas: &Canvas) -> Self {
+        Rgb32FImage::from_fn(canvas.width as u32, canvas.height as u32, |x, y| {
+            let c = canvas.read_pixel(x as usize, y as usize);
+            image::Rgb([c[0] as f32, c[1] as f32, c[2] as f32])
+        })
+    }
+}
+
+impl From<Canvas> for Rgb32FImage {
+    fn from(canvas: Canvas) -> Self {
+        Rgb32FImage::from(&canvas)
+    }
+}
+
+impl From<&RgbaImage> for Canvas {
+    fn from(image: &RgbaImage) -> Self {
+        let mut canvas = Canvas::new(image.width() as usize, image.height() as usize);
+        for (x, y, px) in image.enumerate_pixels() {
+            let channel = |i: usize| px[i] as f64 / 255.0;
+            canvas.write_pixel(
+                x as usize,
+                y as usize,
+                Color::new(channel(0), channel(1), channel(2)),
+            );
+        }
+        canvas
+    }
+}
+
+impl From<RgbaImage> for Canvas {
+    fn from(image: RgbaImage) -> Self {
+        Canvas::from(&image)
+    }
+}
+
+impl From<&Rgb32FImage> for Canvas {
+    fn from(image: &Rgb32FImage) -> Self {
+        let mut canvas = Canvas::new(image.width() as usize, image.height() as usize);
+        for (x, y, px) in image.enumerate_pixels() {
+            let channel = |i: usize| px[i] as f64;
+            canvas.write_pixel(
+                x as usize,
+                y as usize,
+                Color::new(channel(0), channel(1), channel(2)),
+            );
+        }
+        canvas
+    }
+}
+
+impl From<Rgb32FImage> for Canvas {
+    fn from(image: Rgb32FImage) -> Self {
+        Canvas::from(&image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    fn canvas() -> Canvas {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(2, 1, Color::new(0.0, 0.2, 1.0));
+        c.write_pixel(1, 1, Color::new(0.5, 4.0, -1.0));
+        c
+    }
+
+    #[test]
+    fn a_canvas_round_trips_through_an_rgba_image() {
+        let image: RgbaImage = canvas().into();
+
+        assert_eq!((3, 2), image.dimensions());
+        assert_eq!(image::Rgba([255, 0, 0, 255]), *image.get_pixel(0, 0));
+        let back: Canvas = image.into();
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), back.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::new(0.0, 0.2, 1.0), back.read_pixel(2, 1));
+        // Out of range colors are clamped on the way out.
+        assert_fuzzy_eq!(Color::new(128.0 / 255.0, 1.0, 0.0), back.read_pixel(1, 1));
+    }
+
+    #[test]
+    fn a_canvas_round_trips_through_a_float_image_unclamped() {
+        let c = canvas();
+        let image = Rgb32FImage::from(&c);
+
+        assert_eq!((3, 2), image.dimensions());
+        let back = Canvas::from(&image);
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_fuzzy_eq!(c.read_pixel(x, y), back.read_pixel(x, y));
+            }
+        }
+    }
+}
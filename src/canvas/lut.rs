@@ -0,0 +1,312 @@
+use std::{error::Error, fmt};
+
+use super::Canvas;
+use crate::{animator::Interpolate, color::Color};
+
+// A lookup table for color grading, e.g. to give renders the look of a film stock. Colors are
+// looked up by where they fall between the domain's min and max, with anything outside clamped
+// to it, and the entries on either side are blended.
+//
+// Most grading tools can export their looks as .cube files, which is what from_cube reads. LUTs
+// are usually made for display-referred colors in [0, 1], so grade after tone mapping (or after
+// encoding, e.g. with Color::linear_to_srgb, if the LUT expects sRGB) unless the LUT's domain says
+// otherwise.
+#[derive(Clone, Debug)]
+pub enum Lut {
+    // Each channel goes through its own curve, which takes its values from that channel of the
+    // entries. The entries are spread evenly over the domain.
+    OneD {
+        domain_min: Color,
+        domain_max: Color,
+        entries: Vec<Color>,
+    },
+    // A cube of size x size x size entries spread evenly over the domain, red changing fastest,
+    // then green, then blue. Every channel can affect every other.
+    ThreeD {
+        domain_min: Color,
+        domain_max: Color,
+        size: usize,
+        entries: Vec<Color>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseLutError {
+    InvalidLine { line: usize, value: String },
+    NoSize,
+    // A 3D LUT with more entries than there are numbers for.
+    TooBig { size: usize },
+    // A channel whose DOMAIN_MIN and DOMAIN_MAX are the same, so nothing can fall between them.
+    EmptyDomain,
+    WrongEntryCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for ParseLutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseLutError::InvalidLine { line, value } => {
+                write!(f, "line {}: can't read '{}'", line, value)
+            }
+            ParseLutError::NoSize => write!(f, "the LUT has no LUT_1D_SIZE or LUT_3D_SIZE"),
+            ParseLutError::TooBig { size } => write!(f, "a LUT_3D_SIZE of {} is too big", size),
+            ParseLutError::EmptyDomain => {
+                write!(f, "the LUT's DOMAIN_MIN and DOMAIN_MAX must differ")
+            }
+            ParseLutError::WrongEntryCount { expected, found } => write!(
+                f,
+                "the LUT should have {} entries but has {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl Error for ParseLutError {}
+
+// Where a value falls along `size` entries spread over [min, max]: the entry before it, the one
+// after, and how far it is between them.
+fn position(value: f64, min: f64, max: f64, size: usize) -> (usize, usize, f64) {
+    let t = ((value - min) / (max - min)).clamp(0.0, 1.0) * (size - 1) as f64;
+    let before = (t.floor() as usize).min(size - 1);
+    let after = (before + 1).min(size - 1);
+    (before, after, t - before as f64)
+}
+
+impl Lut {
+    // The same curve for every channel, taking [0, 1] to the values, which are spread evenly
+    // over it. E.g. [0.0, 0.6, 1.0] lifts the midtones.
+    pub fn curve(values: &[f64]) -> Self {
+        if values.len() < 2 {
+            panic!("a curve needs at least two values")
+        }
+        Lut::OneD {
+            domain_min: Color::BLACK,
+            domain_max: Color::WHITE,
+            entries: values.iter().map(|&v| Color::new(v, v, v)).collect(),
+        }
+    }
+
+    // Reads an Adobe/Resolve .cube file, with either a 1D or a 3D table.
+    pub fn from_cube(text: &str) -> Result<Self, ParseLutError> {
+        let mut domain_min = Color::BLACK;
+        let mut domain_max = Color::WHITE;
+        let mut size_1d = None;
+        let mut size_3d = None;
+        let mut entries = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("TITLE") {
+                continue;
+            }
+            let invalid = || ParseLutError::InvalidLine {
+                line: i + 1,
+                value: line.to_string(),
+            };
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap();
+            let is_entry = keyword.parse::<f64>().is_ok();
+            let words: Vec<&str> = if is_entry {
+                line.split_whitespace().collect()
+            } else {
+                words.collect()
+            };
+            let numbers: Vec<f64> = words
+                .iter()
+                .map(|w| w.parse().ok())
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            let size = || match numbers.as_slice() {
+                [n] if *n >= 2.0 && n.fract() == 0.0 => Ok(Some(*n as usize)),
+                _ => Err(invalid()),
+            };
+            match (keyword, numbers.as_slice()) {
+                ("LUT_1D_SIZE", _) => size_1d = size()?,
+                ("LUT_3D_SIZE", _) => size_3d = size()?,
+                ("DOMAIN_MIN", &[r, g, b]) => domain_min = Color::new(r, g, b),
+                ("DOMAIN_MAX", &[r, g, b]) => domain_max = Color::new(r, g, b),
+                // Resolve's way of giving the same domain to every channel.
+                ("LUT_1D_INPUT_RANGE", &[min, max]) | ("LUT_3D_INPUT_RANGE", &[min, max]) => {
+                    domain_min = Color::new(min, min, min);
+                    domain_max = Color::new(max, max, max);
+                }
+                (_, &[r, g, b]) if is_entry => entries.push(Color::new(r, g, b)),
+                _ => return Err(invalid()),
+            }
+        }
+
+        if (0..3).any(|i| domain_min[i] == domain_max[i]) {
+            return Err(ParseLutError::EmptyDomain);
+        }
+        let check = |expected: usize| {
+            if entries.len() == expected {
+                Ok(())
+            } else {
+                Err(ParseLutError::WrongEntryCount {
+                    expected,
+                    found: entries.len(),
+                })
+            }
+        };
+        match (size_3d, size_1d) {
+            (Some(size), _) => {
+                let count = size
+                    .checked_mul(size)
+                    .and_then(|square| square.checked_mul(size))
+                    .ok_or(ParseLutError::TooBig { size })?;
+                check(count)?;
+                Ok(Lut::ThreeD {
+                    domain_min,
+                    domain_max,
+                    size,
+                    entries,
+                })
+            }
+            (None, Some(size)) => {
+                check(size)?;
+                Ok(Lut::OneD {
+                    domain_min,
+                    domain_max,
+                    entries,
+                })
+            }
+            (None, None) => Err(ParseLutError::NoSize),
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        match self {
+            Lut::OneD {
+                domain_min,
+                domain_max,
+                entries,
+            } => {
+                let channel = |i: usize| {
+                    let (before, after, t) =
+                        position(color[i], domain_min[i], domain_max[i], entries.len());
+                    entries[before][i] + (entries[after][i] - entries[before][i]) * t
+                };
+                Color::new(channel(0), channel(1), channel(2))
+            }
+            Lut::ThreeD {
+                domain_min,
+                domain_max,
+                size,
+                entries,
+            } => {
+                let [r, g, b] =
+                    [0, 1, 2].map(|i| position(color[i], domain_min[i], domain_max[i], *size));
+                let at = |r: usize, g: usize, b: usize| entries[r + size * (g + size * b)];
+                // Blend along red, then green, then blue.
+                let along_red = |g: usize, b: usize| at(r.0, g, b).interpolate(&at(r.1, g, b), r.2);
+                let along_green = |b: usize| along_red(g.0, b).interpolate(&along_red(g.1, b), g.2);
+                along_green(b.0).interpolate(&along_green(b.1), b.2)
+            }
+        }
+    }
+}
+
+impl Canvas {
+    pub fn with_lut(&self, lut: &Lut) -> Canvas {
+        let mut graded = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                graded.write_pixel(x, y, lut.apply(self.read_pixel(x, y)));
+            }
+        }
+        graded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    // A 2x2x2 cube that swaps red and blue.
+    const SWAP_CUBE: &str = "
+        # Swaps red and blue
+        TITLE \"swap\"
+        LUT_3D_SIZE 2
+        0 0 0
+        0 0 1
+        0 1 0
+        0 1 1
+        1 0 0
+        1 0 1
+        1 1 0
+        1 1 1
+    ";
+
+    #[test]
+    fn a_curve_blends_between_its_values() {
+        let lut = Lut::curve(&[0.0, 0.6, 1.0]);
+
+        assert_fuzzy_eq!(
+            Color::new(0.6, 0.3, 0.8),
+            lut.apply(Color::new(0.5, 0.25, 0.75))
+        );
+        // Outside the domain is clamped to it.
+        assert_fuzzy_eq!(
+            Color::new(0.0, 1.0, 1.0),
+            lut.apply(Color::new(-1.0, 2.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn a_3d_lut_blends_trilinearly() {
+        let lut = Lut::from_cube(SWAP_CUBE).unwrap();
+
+        assert_fuzzy_eq!(
+            Color::new(0.7, 0.5, 0.2),
+            lut.apply(Color::new(0.2, 0.5, 0.7))
+        );
+        let canvas = Canvas::new(2, 1).with_lut(&lut);
+        assert_fuzzy_eq!(Color::BLACK, canvas.read_pixel(1, 0));
+    }
+
+    #[test]
+    fn a_1d_cube_uses_its_domain() {
+        let text = "LUT_1D_SIZE 3\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2\n0 0 0\n0.5 0.2 0.1\n1 1 1\n";
+        let lut = Lut::from_cube(text).unwrap();
+
+        assert_fuzzy_eq!(
+            Color::new(0.75, 0.6, 0.1),
+            lut.apply(Color::new(1.5, 1.5, 1.0))
+        );
+    }
+
+    #[test]
+    fn broken_cubes_are_rejected() {
+        assert_eq!(
+            Err(ParseLutError::NoSize),
+            Lut::from_cube("0 0 0\n1 1 1").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseLutError::WrongEntryCount {
+                expected: 8,
+                found: 2
+            }),
+            Lut::from_cube("LUT_3D_SIZE 2\n0 0 0\n1 1 1").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseLutError::InvalidLine {
+                line: 2,
+                value: "0 zero 0".to_string()
+            }),
+            Lut::from_cube("LUT_1D_SIZE 2\n0 zero 0\n1 1 1").map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseLutError::TooBig {
+                size: 1 << (usize::BITS / 2)
+            }),
+            Lut::from_cube(&format!("LUT_3D_SIZE {}\n0 0 0", 1u64 << (usize::BITS / 2)))
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseLutError::EmptyDomain),
+            Lut::from_cube("LUT_1D_SIZE 2\nDOMAIN_MIN 0 0.5 0\nDOMAIN_MAX 1 0.5 1\n0 0 0\n1 1 1")
+                .map(|_| ())
+        );
+    }
+}
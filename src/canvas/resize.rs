@@ -0,0 +1,108 @@
+use super::Canvas;
+use crate::color::Color;
+
+// How the values in a canvas relate to the light they represent. Rendered canvases are linear;
+// canvases loaded from image files are usually sRGB encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Linear,
+    Srgb,
+}
+
+impl Encoding {
+    fn decode(&self, c: Color) -> Color {
+        match self {
+            Encoding::Linear => c,
+            Encoding::Srgb => c.srgb_to_linear(),
+        }
+    }
+
+    fn encode(&self, c: Color) -> Color {
+        match self {
+            Encoding::Linear => c,
+            Encoding::Srgb => c.linear_to_srgb(),
+        }
+    }
+}
+
+impl Canvas {
+    // Shrinks the canvas by a whole factor, e.g. for a thumbnail. Each new pixel averages a factor
+    // x factor block of the old ones, with blocks hanging off the edge averaging what's there.
+    // Averaging sRGB values directly darkens the image, so they're averaged as linear light and
+    // encoded again afterwards.
+    pub fn downsampled(&self, factor: usize, encoding: Encoding) -> Canvas {
+        if factor == 0 {
+            panic!("downsampling factor must be at least 1")
+        }
+
+        let width = self.width.div_ceil(factor);
+        let height = self.height.div_ceil(factor);
+        let mut res = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = Color::BLACK;
+                let mut count = 0;
+                for sy in y * factor..((y + 1) * factor).min(self.height) {
+                    for sx in x * factor..((x + 1) * factor).min(self.width) {
+                        sum += encoding.decode(self.read_pixel(sx, sy));
+                        count += 1;
+                    }
+                }
+                res.write_pixel(x, y, encoding.encode(sum * (1.0 / count as f64)));
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    fn checkerboard() -> Canvas {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::WHITE);
+        c.write_pixel(1, 1, Color::WHITE);
+        c
+    }
+
+    #[test]
+    fn downsampling_a_linear_canvas_averages_its_values() {
+        let small = checkerboard().downsampled(2, Encoding::Linear);
+
+        assert_eq!(1, small.width);
+        assert_eq!(1, small.height);
+        assert_fuzzy_eq!(Color::new(0.5, 0.5, 0.5), small.read_pixel(0, 0));
+    }
+
+    #[test]
+    fn downsampling_an_srgb_canvas_averages_in_linear_light() {
+        let small = checkerboard().downsampled(2, Encoding::Srgb);
+
+        // Half as much light as white, which is brighter than a 0.5 sRGB grey.
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5).linear_to_srgb(),
+            small.read_pixel(0, 0)
+        );
+        assert!(small.read_pixel(0, 0)[0] > 0.7);
+    }
+
+    #[test]
+    fn blocks_off_the_edge_average_what_is_there() {
+        let mut c = Canvas::new(3, 1);
+        c.write_pixel(2, 0, Color::WHITE);
+
+        let small = c.downsampled(2, Encoding::Linear);
+
+        assert_eq!(2, small.width);
+        assert_fuzzy_eq!(Color::BLACK, small.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::WHITE, small.read_pixel(1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "downsampling factor must be at least 1")]
+    fn downsampling_by_zero_panics() {
+        checkerboard().downsampled(0, Encoding::Linear);
+    }
+}
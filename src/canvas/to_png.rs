@@ -1,8 +1,12 @@
 use std::io::Write;
 
-use png::EncodingError;
+use png::{EncodingError, StreamWriter};
 
-use super::{to_rgba::ToRgba, Rectangle};
+use super::{to_rgba::RgbaRows, Canvas, ColorProfile, Encoded, Rectangle};
+use crate::{
+    color::Color,
+    profile::{self, Section},
+};
 
 pub trait ToPng<T>
 where
@@ -13,17 +17,213 @@ where
 
 impl<T, U> ToPng<U> for T
 where
-    T: ToRgba + Rectangle,
+    T: RgbaRows,
     U: Write,
 {
     fn to_png(&self, w: U) -> Result<(), EncodingError> {
-        let mut encoder = png::Encoder::new(w, self.width() as u32, self.height() as u32);
-        encoder.set_color(png::ColorType::Rgba);
-        encoder.set_depth(png::BitDepth::Eight);
-        let mut writer = encoder.write_header()?;
-        writer.write_image_data(&self.to_rgba())?;
-        writer.finish()?;
+        profile::time(Section::CanvasIo, || {
+            let mut writer = self
+                .color_profile()
+                .png_encoder(w, self.width(), self.height())?
+                .write_header()?;
+            let mut stream = writer.stream_writer()?;
+            let mut row = vec![0u8; self.width() * 4];
+            for y in 0..self.height() {
+                self.rgba_row(y, &mut row);
+                stream.write_all(&row)?;
+            }
+            stream.finish()?;
+            writer.finish()
+        })
+    }
+}
+
+impl Encoded<'_> {
+    // Writes a PNG with 16 bits per channel, for renders that are going to be graded further. The
+    // levels are close enough together that dithering isn't needed, so it's skipped.
+    pub fn to_png_16<W: Write>(&self, w: W) -> Result<(), EncodingError> {
+        profile::time(Section::CanvasIo, || {
+            let mut encoder = self
+                .color_profile()
+                .png_encoder(w, self.width(), self.height())?;
+            encoder.set_depth(png::BitDepth::Sixteen);
+            let mut writer = encoder.write_header()?;
+            let mut stream = writer.stream_writer()?;
+            let mut row = Vec::with_capacity(self.width() * 8);
+            for y in 0..self.height() {
+                row.clear();
+                for color in self.encoded_row(y) {
+                    for c in 0..3 {
+                        let level = (color[c].clamp(0.0, 1.0) * 65535.0).round() as u16;
+                        row.extend_from_slice(&level.to_be_bytes());
+                    }
+                    row.extend_from_slice(&u16::MAX.to_be_bytes());
+                }
+                stream.write_all(&row)?;
+            }
+            stream.finish()?;
+            writer.finish()
+        })
+    }
+}
 
+impl Canvas {
+    pub fn to_png_16<W: Write>(&self, w: W) -> Result<(), EncodingError> {
+        self.encoded(ColorProfile::default()).to_png_16(w)
+    }
+}
+
+// Writes a PNG a row at a time, top to bottom, so the whole image never has to be in memory at
+// once. Rows can be written as soon as they're done, e.g. when a row of tiles finishes rendering.
+// The stream owns what it writes to until it's finished, so that has to be e.g. a File.
+pub struct PngStream<W: Write + 'static> {
+    writer: StreamWriter<'static, W>,
+    width: usize,
+    profile: ColorProfile,
+    row: Vec<u8>,
+}
+
+impl<W: Write + 'static> PngStream<W> {
+    pub fn new(w: W, width: usize, height: usize) -> Result<Self, EncodingError> {
+        Self::with_profile(w, width, height, ColorProfile::default())
+    }
+
+    // A stream that encodes the colors of each row for the profile and tags the image with it.
+    pub fn with_profile(
+        w: W,
+        width: usize,
+        height: usize,
+        profile: ColorProfile,
+    ) -> Result<Self, EncodingError> {
+        let writer = profile
+            .png_encoder(w, width, height)?
+            .write_header()?
+            .into_stream_writer()?;
+        Ok(Self {
+            writer,
+            width,
+            profile,
+            row: Vec::with_capacity(width * 4),
+        })
+    }
+
+    pub fn write_row(&mut self, colors: &[Color]) -> Result<(), EncodingError> {
+        if colors.len() != self.width {
+            panic!("a row must have one color for every pixel of the image's width")
+        }
+        let mut row = std::mem::take(&mut self.row);
+        row.clear();
+        for color in colors {
+            row.extend_from_slice(&super::to_rgba::rgba(self.profile.encode(*color)));
+        }
+        let res = self.write_rgba_row(&row);
+        self.row = row;
+        res
+    }
+
+    pub fn write_rgba_row(&mut self, row: &[u8]) -> Result<(), EncodingError> {
+        self.writer.write_all(row)?;
         Ok(())
     }
+
+    // Fails if fewer rows were written than the image is tall.
+    pub fn finish(self) -> Result<(), EncodingError> {
+        self.writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::{assert_fuzzy_eq, canvas::Canvas, fuzzy_eq::FuzzyEq};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("raytracer-{}-{}.png", name, std::process::id()))
+    }
+
+    #[test]
+    fn streaming_rows_makes_the_same_png_as_writing_a_canvas() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        canvas.write_pixel(2, 1, Color::new(0.0, 0.4, 1.0));
+        let mut whole = Vec::new();
+        canvas.to_png(&mut whole).unwrap();
+
+        let path = temp_path("stream");
+        let mut stream = PngStream::new(fs::File::create(&path).unwrap(), 3, 2).unwrap();
+        for y in 0..2 {
+            let row: Vec<Color> = (0..3).map(|x| canvas.read_pixel(x, y)).collect();
+            stream.write_row(&row).unwrap();
+        }
+        stream.finish().unwrap();
+        let streamed = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(whole, streamed);
+        let read = Canvas::from_png(streamed.as_slice()).unwrap();
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), read.read_pixel(0, 0));
+    }
+
+    #[test]
+    fn streaming_with_a_profile_makes_the_same_png_as_an_encoded_canvas() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::new(0.2, 0.4, 0.8));
+        let mut whole = Vec::new();
+        canvas
+            .encoded(ColorProfile::Srgb)
+            .to_png(&mut whole)
+            .unwrap();
+
+        let path = temp_path("profile");
+        let mut stream =
+            PngStream::with_profile(fs::File::create(&path).unwrap(), 2, 1, ColorProfile::Srgb)
+                .unwrap();
+        stream
+            .write_row(&[canvas.read_pixel(0, 0), canvas.read_pixel(1, 0)])
+            .unwrap();
+        stream.finish().unwrap();
+        let streamed = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(whole, streamed);
+    }
+
+    #[test]
+    fn a_16_bit_png_keeps_more_precision() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(0.1234, 0.5, 1.5));
+        canvas.write_pixel(1, 0, Color::new(0.001, 0.0, 0.999));
+        let mut png = Vec::new();
+
+        canvas.to_png_16(&mut png).unwrap();
+
+        let read = Canvas::from_png(png.as_slice()).unwrap();
+        let difference = read.read_pixel(0, 0) - Color::new(0.1234, 0.5, 1.0);
+        assert!((0..3).all(|i| difference[i].abs() < 1e-4));
+        let difference = read.read_pixel(1, 0) - Color::new(0.001, 0.0, 0.999);
+        assert!((0..3).all(|i| difference[i].abs() < 1e-4));
+    }
+
+    #[test]
+    fn finishing_a_stream_early_fails() {
+        let path = temp_path("early");
+        let mut stream = PngStream::new(fs::File::create(&path).unwrap(), 2, 2).unwrap();
+        stream
+            .write_row(&[Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0)])
+            .unwrap();
+
+        let res = stream.finish();
+        fs::remove_file(&path).unwrap();
+
+        assert!(res.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "a row must have one color for every pixel")]
+    fn rows_must_match_the_image_width() {
+        let mut stream = PngStream::new(Vec::new(), 2, 2).unwrap();
+        stream.write_row(&[Color::new(1.0, 1.0, 1.0)]).unwrap();
+    }
 }
@@ -1,4 +1,4 @@
-use super::{to_rgba::ToRgba, Rectangle};
+use super::{to_rgba::ToRgba, Canvas, Rectangle};
 
 pub trait ToPpm {
     fn ppm_header(&self) -> Vec<u8>
@@ -9,6 +9,10 @@ pub trait ToPpm {
     }
 
     fn to_ppm(&self) -> Vec<u8>;
+
+    // The binary (P6) flavor of PPM, a fraction of the size of the ASCII one and much quicker to
+    // write.
+    fn to_ppm_binary(&self) -> Vec<u8>;
 }
 
 impl<T> ToPpm for T
@@ -17,44 +21,98 @@ where
 {
     fn to_ppm(&self) -> Vec<u8> {
         let mut res = self.ppm_header();
-        let mut pixels_written = 0usize;
-        let mut row_width = 0usize;
-        for bytes in self.to_rgba().chunks(4) {
-            // skip the alpha value
-            for b in bytes.iter().take(3) {
-                let comp = format!("{}", b);
-                let want_to_write = if row_width == 0 {
-                    // at the beginning of a row, we don't write the leading space
-                    comp.len()
-                } else {
-                    // otherwise, we write the leading space
-                    1 + comp.len()
-                };
-
-                if row_width + want_to_write > 70 {
-                    // wrap at 70 characters
-                    res.extend(b"\n");
-                    row_width = 0;
-                }
-
-                if row_width != 0 {
-                    res.extend(b" ");
-                    row_width += 1;
-                }
-                res.extend(comp.as_bytes());
-                row_width += comp.len();
-            }
+        write_ascii(&mut res, self.width(), rgb_samples(&self.to_rgba()));
+        res
+    }
+
+    fn to_ppm_binary(&self) -> Vec<u8> {
+        let mut res: Vec<u8> = format!("P6\n{} {}\n{}\n", self.width(), self.height(), 255).into();
+        res.reserve(self.width() * self.height() * 3);
+        res.extend(rgb_samples(&self.to_rgba()).map(|s| s as u8));
+        res
+    }
+}
+
+impl Canvas {
+    // A PPM with channel values from 0 to max_value rather than 0 to 255, e.g. 65535 to keep
+    // more precision than 8 bits can.
+    pub fn to_ppm_with_max_value(&self, max_value: u16) -> Vec<u8> {
+        let mut res: Vec<u8> =
+            format!("P3\n{} {}\n{}\n", self.width, self.height, max_value).into();
+        write_ascii(&mut res, self.width, self.samples(max_value));
+        res
+    }
 
-            pixels_written += 1;
-            if pixels_written == self.width() {
-                // wrap after we write a width's worth of pixels
-                res.extend(b"\n");
-                pixels_written = 0;
-                row_width = 0;
+    // The binary flavor of to_ppm_with_max_value. Max values over 255 take two bytes per channel,
+    // most significant first.
+    pub fn to_ppm_binary_with_max_value(&self, max_value: u16) -> Vec<u8> {
+        let mut res: Vec<u8> =
+            format!("P6\n{} {}\n{}\n", self.width, self.height, max_value).into();
+        for sample in self.samples(max_value) {
+            if max_value > 255 {
+                res.extend(sample.to_be_bytes());
+            } else {
+                res.push(sample as u8);
             }
         }
         res
     }
+
+    fn samples(&self, max_value: u16) -> impl Iterator<Item = u16> + '_ {
+        if max_value == 0 {
+            panic!("a PPM's max value must be at least 1")
+        }
+        (0..self.height).flat_map(move |y| {
+            (0..self.width).flat_map(move |x| {
+                let color = self.read_pixel(x, y).clamp(0.0, 1.0);
+                [0, 1, 2].map(|c| (color[c] * max_value as f64).round() as u16)
+            })
+        })
+    }
+}
+
+// The red, green and blue of each pixel, skipping the alpha.
+fn rgb_samples(rgba: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    rgba.chunks(4)
+        .flat_map(|bytes| bytes.iter().take(3).map(|&b| b as u16))
+}
+
+// Writes the samples, three to a pixel, wrapping lines at 70 characters and after every row of
+// pixels.
+fn write_ascii(res: &mut Vec<u8>, width: usize, samples: impl Iterator<Item = u16>) {
+    let mut samples_written = 0usize;
+    let mut row_width = 0usize;
+    for sample in samples {
+        let comp = format!("{}", sample);
+        let want_to_write = if row_width == 0 {
+            // at the beginning of a row, we don't write the leading space
+            comp.len()
+        } else {
+            // otherwise, we write the leading space
+            1 + comp.len()
+        };
+
+        if row_width + want_to_write > 70 {
+            // wrap at 70 characters
+            res.extend(b"\n");
+            row_width = 0;
+        }
+
+        if row_width != 0 {
+            res.extend(b" ");
+            row_width += 1;
+        }
+        res.extend(comp.as_bytes());
+        row_width += comp.len();
+
+        samples_written += 1;
+        if samples_written == width * 3 {
+            // wrap after we write a width's worth of pixels
+            res.extend(b"\n");
+            samples_written = 0;
+            row_width = 0;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -143,4 +201,39 @@ mod tests {
         exp.extend(exp_pixel_data.into_bytes());
         assert_eq!(c.to_ppm(), exp);
     }
+
+    #[test]
+    fn a_ppm_can_have_a_max_value_other_than_255() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.5, 0.0));
+        c.write_pixel(1, 0, Color::new(0.25, 2.0, -1.0));
+
+        assert_eq!(
+            b"P3\n2 1\n1023\n1023 512 0 256 1023 0\n".to_vec(),
+            c.to_ppm_with_max_value(1023)
+        );
+        assert_eq!(c.to_ppm(), c.to_ppm_with_max_value(255));
+    }
+
+    #[test]
+    fn a_binary_ppm_with_a_big_max_value_takes_two_bytes_per_channel() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.5, 0.0));
+
+        let mut exp = b"P6\n1 1\n65535\n".to_vec();
+        exp.extend([0xffu8, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        assert_eq!(exp, c.to_ppm_binary_with_max_value(65535));
+        assert_eq!(c.to_ppm_binary(), c.to_ppm_binary_with_max_value(255));
+    }
+
+    #[test]
+    fn test_construct_binary_ppm() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, 0.0));
+        c.write_pixel(1, 1, Color::new(0.0, 0.5, 1.0));
+
+        let mut exp = b"P6\n2 2\n255\n".to_vec();
+        exp.extend([255u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 255]);
+        assert_eq!(exp, c.to_ppm_binary());
+    }
 }
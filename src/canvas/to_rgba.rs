@@ -1,21 +1,45 @@
-use super::Canvas;
+use super::{Canvas, ColorProfile, Rectangle};
+use crate::color::Color;
 
 pub trait ToRgba {
     fn to_rgba(&self) -> Vec<u8>;
 }
 
-impl ToRgba for Canvas {
+// An image that can be converted to 8 bit RGBA one row at a time, so big images don't need a copy
+// of every pixel converted at once.
+pub trait RgbaRows: Rectangle {
+    // Fills in the row, which is 4 bytes per pixel.
+    fn rgba_row(&self, y: usize, row: &mut [u8]);
+
+    // What to tag the image with when it's written, which has to match how the rows were encoded.
+    fn color_profile(&self) -> ColorProfile {
+        ColorProfile::Untagged
+    }
+}
+
+pub(crate) fn rgba(color: Color) -> [u8; 4] {
+    let clamped = color.clamp(0.0, 1.0);
+    let r = (clamped[0] * 255.0).round() as u8;
+    let g = (clamped[1] * 255.0).round() as u8;
+    let b = (clamped[2] * 255.0).round() as u8;
+    [r, g, b, 255] // opaque alpha channel
+}
+
+impl RgbaRows for Canvas {
+    fn rgba_row(&self, y: usize, row: &mut [u8]) {
+        for x in 0..self.width {
+            row[x * 4..x * 4 + 4].copy_from_slice(&rgba(self.read_pixel(x, y)));
+        }
+    }
+}
+
+impl<T: RgbaRows> ToRgba for T {
     fn to_rgba(&self) -> Vec<u8> {
-        let mut data: Vec<u8> = Vec::new();
-        for pixel in self.pixels.iter() {
-            let clamped = pixel.clamp(0.0, 1.0);
-            let r = (clamped[0] * 255.0).round() as u8;
-            let g = (clamped[1] * 255.0).round() as u8;
-            let b = (clamped[2] * 255.0).round() as u8;
-            data.push(r);
-            data.push(g);
-            data.push(b);
-            data.push(255); // alpha channel
+        let mut data = vec![0u8; self.width() * self.height() * 4];
+        if self.width() > 0 {
+            for (y, row) in data.chunks_exact_mut(self.width() * 4).enumerate() {
+                self.rgba_row(y, row);
+            }
         }
         data
     }
@@ -23,8 +47,6 @@ impl ToRgba for Canvas {
 
 #[cfg(test)]
 mod tests {
-    use crate::color::Color;
-
     use super::*;
 
     #[test]
@@ -40,4 +62,15 @@ mod tests {
             c.to_rgba()
         )
     }
+
+    #[test]
+    fn a_single_row_converts_to_rgba() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 1, Color::new(0.5, 1.5, -1.0));
+        let mut row = [0u8; 8];
+
+        c.rgba_row(1, &mut row);
+
+        assert_eq!([0u8, 0, 0, 255, 128, 255, 0, 255], row);
+    }
 }
@@ -0,0 +1,297 @@
+use crate::{
+    bounds::{Bounded, Bounds},
+    fuzzy_eq::FuzzyEq,
+    intersection::{HitRecord, Intersectable, Intersections, Normal},
+    material::Material,
+    matrix::Matrix,
+    point::Point,
+    ray::Ray,
+    transform::Transformable,
+    vector::Vector,
+};
+
+// A capsule is a sphere swept along a line segment: a cylinder with hemispherical ends. Thin ones
+// make good wires, hair and blades of grass. In object space the segment runs from `a` to `b`.
+#[derive(Clone, Debug)]
+pub struct Capsule {
+    transform: Matrix<4>,
+    a: Point,
+    b: Point,
+    radius: f64,
+    pub material: Material,
+}
+
+impl Default for Capsule {
+    fn default() -> Self {
+        Self {
+            transform: Matrix::identity(),
+            a: Point::new(0.0, -1.0, 0.0),
+            b: Point::new(0.0, 1.0, 0.0),
+            radius: 0.25,
+            material: Material::default(),
+        }
+    }
+}
+
+impl FuzzyEq for Capsule {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.transform.fuzzy_eq(other.transform)
+            && self.a.fuzzy_eq(other.a)
+            && self.b.fuzzy_eq(other.b)
+            && self.radius.fuzzy_eq(other.radius)
+    }
+}
+
+// The roots of at^2 + bt + c, if there are any.
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    let descriminant = b * b - 4.0 * a * c;
+    if a.abs() < f64::EPSILON || descriminant < 0.0 {
+        None
+    } else {
+        let t1 = (-b - descriminant.sqrt()) / (2.0 * a);
+        let t2 = (-b + descriminant.sqrt()) / (2.0 * a);
+        Some((t1, t2))
+    }
+}
+
+impl Intersectable for Capsule {
+    fn intersect(&self, r: Ray) -> Intersections {
+        let ray = r.transform(self.transform.inverse());
+        let axis = self.b - self.a;
+        let length = axis.magnitude();
+        let axis = axis.normalize();
+        let along_axis = |t: f64| (ray.position(t) - self.a).dot(&axis);
+
+        // Each wall is only part of the surface where it isn't covered by another: the cylinder
+        // between the ends and each end's sphere beyond the segment. The capsule is convex, so the
+        // surviving roots are where the ray goes in and out.
+        let mut ts = vec![];
+
+        let oc = ray.origin - self.a;
+        let direction_across = ray.direction - axis * ray.direction.dot(&axis);
+        let origin_across = oc - axis * oc.dot(&axis);
+        if let Some((t1, t2)) = solve_quadratic(
+            direction_across.dot(&direction_across),
+            2.0 * direction_across.dot(&origin_across),
+            origin_across.dot(&origin_across) - self.radius * self.radius,
+        ) {
+            ts.extend(
+                [t1, t2]
+                    .iter()
+                    .filter(|&&t| (0.0..=length).contains(&along_axis(t))),
+            );
+        }
+
+        for (center, on_end) in [(self.a, true), (self.b, false)] {
+            let to_ray = ray.origin - center;
+            if let Some((t1, t2)) = solve_quadratic(
+                ray.direction.dot(&ray.direction),
+                2.0 * ray.direction.dot(&to_ray),
+                to_ray.dot(&to_ray) - self.radius * self.radius,
+            ) {
+                ts.extend([t1, t2].iter().filter(|&&t| {
+                    if on_end {
+                        along_axis(t) < 0.0
+                    } else {
+                        along_axis(t) > length
+                    }
+                }));
+            }
+        }
+
+        if ts.is_empty() {
+            return vec![].into();
+        }
+        let t_min = ts.iter().copied().fold(f64::INFINITY, f64::min);
+        let t_max = ts.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        vec![
+            HitRecord::new(t_min, r, self.clone().into()),
+            HitRecord::new(t_max, r, self.clone().into()),
+        ]
+        .into()
+    }
+}
+
+impl Normal for Capsule {
+    fn normal_at(&self, p: Point) -> Vector {
+        let t_inv = self.transform.inverse();
+        let object_point = t_inv * p;
+
+        // The normal points away from the closest point on the segment.
+        let axis = self.b - self.a;
+        let s = ((object_point - self.a).dot(&axis) / axis.dot(&axis)).clamp(0.0, 1.0);
+        let closest = self.a + axis * s;
+        let object_normal = (object_point - closest).normalize();
+
+        let world_normal = t_inv.transpose() * object_normal;
+        Vector::new(world_normal[0], world_normal[1], world_normal[2]).normalize()
+    }
+}
+
+impl Bounded for Capsule {
+    fn bounds(&self) -> Bounds {
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+        for i in 0..3 {
+            min[i] = self.a[i].min(self.b[i]) - self.radius;
+            max[i] = self.a[i].max(self.b[i]) + self.radius;
+        }
+        Bounds::new(
+            Point::new(min[0], min[1], min[2]),
+            Point::new(max[0], max[1], max[2]),
+        )
+    }
+}
+
+impl Transformable for Capsule {
+    fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+}
+
+impl Capsule {
+    pub fn new(a: Point, b: Point, radius: f64) -> Self {
+        Self::default().with_segment(a, b).with_radius(radius)
+    }
+
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+
+    pub fn with_material(self, material: Material) -> Self {
+        Self { material, ..self }
+    }
+
+    pub fn with_segment(self, a: Point, b: Point) -> Self {
+        if (b - a).magnitude() < f64::EPSILON {
+            panic!("capsule ends must be different points")
+        }
+        Self { a, b, ..self }
+    }
+
+    pub fn with_radius(self, radius: f64) -> Self {
+        if radius <= 0.0 {
+            panic!("capsule radius must be positive")
+        }
+        Self { radius, ..self }
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    pub fn segment(&self) -> (Point, Point) {
+        (self.a, self.b)
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_fuzzy_eq;
+
+    #[test]
+    fn a_ray_through_the_side_of_a_capsule() {
+        let c = Capsule::default();
+        let r = Ray::new(Point::new(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = c.intersect(r);
+
+        assert_eq!(2, xs.len());
+        assert_fuzzy_eq!(4.75, xs[0].t);
+        assert_fuzzy_eq!(5.25, xs[1].t);
+    }
+
+    #[test]
+    fn a_ray_along_the_axis_hits_the_ends() {
+        let c = Capsule::default();
+        let r = Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        let xs = c.intersect(r);
+
+        assert_eq!(2, xs.len());
+        assert_fuzzy_eq!(3.75, xs[0].t);
+        assert_fuzzy_eq!(6.25, xs[1].t);
+    }
+
+    #[test]
+    fn a_ray_through_a_rounded_end() {
+        let c = Capsule::default();
+        // Passes through the top hemisphere, above the end of the segment.
+        let r = Ray::new(Point::new(0.0, 1.15, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = c.intersect(r);
+        let half_chord = (0.25f64 * 0.25 - 0.15 * 0.15).sqrt();
+
+        assert_eq!(2, xs.len());
+        assert_fuzzy_eq!(5.0 - half_chord, xs[0].t);
+        assert_fuzzy_eq!(5.0 + half_chord, xs[1].t);
+    }
+
+    #[test]
+    fn a_ray_missing_a_capsule() {
+        let c = Capsule::default();
+
+        for r in [
+            Ray::new(Point::new(0.0, 1.3, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+        ] {
+            assert_eq!(0, c.intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn a_capsule_between_arbitrary_points() {
+        let c = Capsule::new(Point::new(-1.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0), 0.5);
+        let r = Ray::new(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        let xs = c.intersect(r);
+
+        assert_fuzzy_eq!(4.5, xs[0].t);
+        assert_fuzzy_eq!(5.5, xs[1].t);
+    }
+
+    #[test]
+    fn normals_on_a_capsule() {
+        let c = Capsule::default();
+
+        assert_fuzzy_eq!(
+            Vector::new(0.0, 0.0, -1.0),
+            c.normal_at(Point::new(0.0, 0.5, -0.25))
+        );
+        assert_fuzzy_eq!(
+            Vector::new(0.0, 1.0, 0.0),
+            c.normal_at(Point::new(0.0, 1.25, 0.0))
+        );
+        assert_fuzzy_eq!(
+            Vector::new(0.0, -1.0, 0.0),
+            c.normal_at(Point::new(0.0, -1.25, 0.0))
+        );
+    }
+
+    #[test]
+    fn intersecting_a_transformed_capsule() {
+        let c = Capsule::default().with_transform(Matrix::translate(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = c.intersect(r);
+
+        assert_fuzzy_eq!(5.75, xs[0].t);
+        assert_fuzzy_eq!(6.25, xs[1].t);
+    }
+
+    #[test]
+    #[should_panic(expected = "capsule radius must be positive")]
+    fn a_capsule_needs_a_radius() {
+        Capsule::default().with_radius(0.0);
+    }
+}
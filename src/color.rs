@@ -1,3 +1,5 @@
+use std::{error::Error, fmt, str::FromStr};
+
 use crate::tuple::{ElementwiseMul, Tuple, TupleAdd, TupleSub};
 
 #[derive(Clone, Copy, Debug)]
@@ -11,10 +13,69 @@ impl ElementwiseMul for ColorTuple {}
 pub type Color = Tuple<ColorTuple, 3>;
 
 impl Color {
+    pub const BLACK: Color = Color::from_array([0.0, 0.0, 0.0]);
+    pub const WHITE: Color = Color::from_array([1.0, 1.0, 1.0]);
+    pub const RED: Color = Color::from_array([1.0, 0.0, 0.0]);
+    // The constants are the CSS colors of the same name, so this is the darker CSS "green"
+    // (#008000) and LIME is the pure green primary.
+    pub const GREEN: Color = Color::from_array([0.0, 128.0 / 255.0, 0.0]);
+    pub const LIME: Color = Color::from_array([0.0, 1.0, 0.0]);
+    pub const BLUE: Color = Color::from_array([0.0, 0.0, 1.0]);
+    pub const YELLOW: Color = Color::from_array([1.0, 1.0, 0.0]);
+    pub const CYAN: Color = Color::from_array([0.0, 1.0, 1.0]);
+    pub const MAGENTA: Color = Color::from_array([1.0, 0.0, 1.0]);
+
     pub fn new(r: f64, g: f64, b: f64) -> Self {
         Color::from([r, g, b])
     }
 
+    // Builds a color from a 0xRRGGBB value, e.g. Color::from_hex(0x6495ed).
+    pub fn from_hex(hex: u32) -> Self {
+        Color::new(
+            ((hex >> 16) & 0xff) as f64 / 255.0,
+            ((hex >> 8) & 0xff) as f64 / 255.0,
+            (hex & 0xff) as f64 / 255.0,
+        )
+    }
+
+    // Looks up one of the CSS named colors, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        NAMED_COLORS
+            .binary_search_by_key(&name.as_str(), |&(n, _)| n)
+            .ok()
+            .map(|i| Color::from_hex(NAMED_COLORS[i].1))
+    }
+
+    // Converts from sRGB encoded values (what image files usually hold) to linear light.
+    pub fn srgb_to_linear(&self) -> Self {
+        let decode = |c: f64| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Color::new(decode(self[0]), decode(self[1]), decode(self[2]))
+    }
+
+    pub fn linear_to_srgb(&self) -> Self {
+        let encode = |c: f64| {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        Color::new(encode(self[0]), encode(self[1]), encode(self[2]))
+    }
+
+    // How bright the color looks, with linear sRGB's weights for how sensitive eyes are to each
+    // channel.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self[0] + 0.7152 * self[1] + 0.0722 * self[2]
+    }
+
     pub fn clamp(&self, lower: f64, upper: f64) -> Self {
         Color::new(
             self[0].clamp(lower, upper),
@@ -22,4 +83,335 @@ impl Color {
             self[2].clamp(lower, upper),
         )
     }
+
+    // Turns the color around the color wheel, by rotating it about the grey axis, so greys stay
+    // the same and a whole turn gets back to where it started.
+    pub fn with_hue_shift(&self, turns: f64) -> Self {
+        let angle = turns * 2.0 * std::f64::consts::PI;
+        let (sin, cos) = angle.sin_cos();
+        let k = (1.0 - cos) / 3.0;
+        let s = sin / 3f64.sqrt();
+        let (r, g, b) = (self[0], self[1], self[2]);
+        Color::new(
+            r * (cos + k) + g * (k - s) + b * (k + s),
+            r * (k + s) + g * (cos + k) + b * (k - s),
+            r * (k - s) + g * (k + s) + b * (cos + k),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseColorError {
+    UnknownName(String),
+    InvalidHex(String),
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColorError::UnknownName(s) => write!(f, "unknown color name '{}'", s),
+            ParseColorError::InvalidHex(s) => write!(f, "invalid hex color '{}'", s),
+        }
+    }
+}
+
+impl Error for ParseColorError {}
+
+// Parses either a CSS color name ("cornflowerblue") or a hex color ("#6495ed" or "#69e").
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let hex = match s.strip_prefix('#') {
+            Some(hex) => hex,
+            None => {
+                return Color::from_name(s).ok_or_else(|| ParseColorError::UnknownName(s.into()))
+            }
+        };
+
+        let invalid = || ParseColorError::InvalidHex(s.into());
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        match hex.len() {
+            6 => Ok(Color::from_hex(value)),
+            // Each digit of the short form is doubled up, so #69e is #6699ee.
+            3 => Ok(Color::from_hex(
+                ((value & 0xf00) << 12)
+                    | ((value & 0xf00) << 8)
+                    | ((value & 0x0f0) << 8)
+                    | ((value & 0x0f0) << 4)
+                    | ((value & 0x00f) << 4)
+                    | (value & 0x00f),
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// The CSS named colors, sorted by name so they can be binary searched.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn named_color_constants() {
+        assert_fuzzy_eq!(Color::new(0.0, 0.0, 0.0), Color::BLACK);
+        assert_fuzzy_eq!(Color::new(1.0, 1.0, 1.0), Color::WHITE);
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), Color::RED);
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 1.0), Color::MAGENTA);
+        assert_fuzzy_eq!(Color::new(0.0, 1.0, 0.0), Color::LIME);
+    }
+
+    #[test]
+    fn named_color_constants_match_their_css_names() {
+        let constants = [
+            ("black", Color::BLACK),
+            ("white", Color::WHITE),
+            ("red", Color::RED),
+            ("green", Color::GREEN),
+            ("lime", Color::LIME),
+            ("blue", Color::BLUE),
+            ("yellow", Color::YELLOW),
+            ("cyan", Color::CYAN),
+            ("magenta", Color::MAGENTA),
+        ];
+        for (name, color) in constants {
+            assert_fuzzy_eq!(color, Color::from_name(name).unwrap());
+        }
+    }
+
+    #[test]
+    fn the_named_color_table_is_sorted() {
+        assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn looking_up_a_color_by_name() {
+        assert_fuzzy_eq!(
+            Color::new(100.0 / 255.0, 149.0 / 255.0, 237.0 / 255.0),
+            Color::from_name("cornflowerblue").unwrap()
+        );
+        assert_fuzzy_eq!(
+            Color::new(1.0, 99.0 / 255.0, 71.0 / 255.0),
+            Color::from_name("Tomato").unwrap()
+        );
+        assert!(Color::from_name("notacolor").is_none());
+    }
+
+    #[test]
+    fn parsing_hex_colors() {
+        assert_fuzzy_eq!(
+            Color::new(100.0 / 255.0, 149.0 / 255.0, 237.0 / 255.0),
+            "#6495ED".parse::<Color>().unwrap()
+        );
+        assert_fuzzy_eq!(
+            Color::new(
+                0x66 as f64 / 255.0,
+                0x99 as f64 / 255.0,
+                0xee as f64 / 255.0
+            ),
+            "#69e".parse::<Color>().unwrap()
+        );
+    }
+
+    #[test]
+    fn parsing_color_names() {
+        assert_fuzzy_eq!(Color::WHITE, " white ".parse::<Color>().unwrap());
+    }
+
+    #[test]
+    fn parsing_invalid_colors() {
+        assert_eq!(
+            Err(ParseColorError::UnknownName("blurple".into())),
+            "blurple".parse::<Color>().map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseColorError::InvalidHex("#12345".into())),
+            "#12345".parse::<Color>().map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseColorError::InvalidHex("#+12".into())),
+            "#+12".parse::<Color>().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn shifting_hues() {
+        assert_fuzzy_eq!(Color::LIME, Color::RED.with_hue_shift(1.0 / 3.0));
+        assert_fuzzy_eq!(Color::RED, Color::BLUE.with_hue_shift(1.0 / 3.0));
+        // Half a turn takes a color to the far side of grey.
+        assert_fuzzy_eq!(
+            Color::new(1.0 / 3.0, 1.0 / 3.0, 4.0 / 3.0),
+            Color::YELLOW.with_hue_shift(0.5)
+        );
+        let grey = Color::new(0.3, 0.3, 0.3);
+        assert_fuzzy_eq!(grey, grey.with_hue_shift(0.2));
+        let c = Color::new(0.1, 0.5, 0.8);
+        assert_fuzzy_eq!(c, c.with_hue_shift(0.3).with_hue_shift(-0.3));
+    }
+
+    #[test]
+    fn converting_between_srgb_and_linear() {
+        let mid_grey = Color::new(0.5, 0.5, 0.5).srgb_to_linear();
+
+        assert!((mid_grey[0] - 0.21404).abs() < 1e-5);
+        assert_fuzzy_eq!(Color::new(0.5, 0.5, 0.5), mid_grey.linear_to_srgb());
+        assert_fuzzy_eq!(Color::BLACK, Color::BLACK.srgb_to_linear());
+        assert_fuzzy_eq!(Color::WHITE, Color::WHITE.linear_to_srgb());
+    }
 }
@@ -1,4 +1,8 @@
-use crate::{intersection::Intersection, point::Point, vector::Vector};
+use crate::{intersection::HitRecord, material::Material, point::Point, vector::Vector};
+
+// The most a footprint is stretched for meeting the surface at a slant, so it doesn't grow without
+// bound as the ray gets close to grazing it.
+const MAX_STRETCH: f64 = 10.0;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Orientation {
@@ -6,32 +10,236 @@ pub enum Orientation {
     Outside,
 }
 
+// What happens to light crossing the surface from the n1 side to the n2 side.
+#[derive(Clone, Copy, Debug)]
+pub enum Refraction {
+    Transmitted { direction: Vector, cos_t: f64 },
+    // The light hits the surface at too shallow an angle to get out of the denser material, so
+    // all of it is reflected back in.
+    TotalInternalReflection,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct ComputedIntersection<'a> {
-    pub intersection: &'a Intersection,
+    pub intersection: &'a HitRecord,
     pub position: Point,
     pub over_point: Point,
+    pub under_point: Point,
+    // How far the over and under points are from the surface.
+    pub offset: f64,
     pub normal: Vector,
     pub eye: Vector,
+    pub reflect: Vector,
     pub orientation: Orientation,
+    // The refractive indices of the materials the ray is leaving and entering.
+    pub n1: f64,
+    pub n2: f64,
 }
 
 impl<'a> ComputedIntersection<'a> {
     pub fn new(
-        intersection: &'a Intersection,
+        intersection: &'a HitRecord,
         position: Point,
         over_point: Point,
         normal: Vector,
         eye: Vector,
         orientation: Orientation,
     ) -> Self {
+        let under_point = position + (position - over_point);
+        let reflect = intersection.ray.direction.reflect(normal);
         Self {
             intersection,
             position,
             over_point,
+            under_point,
+            offset: (over_point - position).magnitude(),
             normal,
             eye,
+            reflect,
             orientation,
+            n1: 1.0,
+            n2: 1.0,
         }
     }
+
+    // Moves the over and under points to the given distance from the surface, for scenes where
+    // the offset worked out from the hit is still too small or too big.
+    pub fn with_offset(self, offset: f64) -> Self {
+        let over_point = self.position + self.normal * offset;
+        Self {
+            over_point,
+            under_point: self.position + (self.position - over_point),
+            offset,
+            ..self
+        }
+    }
+
+    pub fn with_refractive_indices(self, n1: f64, n2: f64) -> Self {
+        Self { n1, n2, ..self }
+    }
+
+    // How wide a patch of the surface the ray stands for here, in world space: how much it has
+    // spread over the whole way from the camera, stretched out along the surface the more it
+    // slants across it. It's 0 for rays that don't spread.
+    pub fn footprint(&self) -> f64 {
+        let ray = &self.intersection.ray;
+        if ray.spread == 0.0 {
+            return 0.0;
+        }
+        let distance = ray.travelled + self.intersection.t * ray.direction.magnitude();
+        let cos = self.eye.normalize().dot(&self.normal).abs();
+        ray.spread * distance * (1.0 / cos).min(MAX_STRETCH)
+    }
+
+    // The material of what was hit, with its patterns filtered over the footprint.
+    pub fn material(&self) -> Material {
+        self.intersection
+            .body
+            .material()
+            .with_footprint(self.footprint())
+    }
+
+    // Snell's law, worked out from the eye's side of the surface.
+    pub fn refraction(&self) -> Refraction {
+        let n_ratio = self.n1 / self.n2;
+        let cos_i = self.eye.dot(&self.normal);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return Refraction::TotalInternalReflection;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = self.normal * (n_ratio * cos_i - cos_t) - self.eye * n_ratio;
+        Refraction::Transmitted { direction, cos_t }
+    }
+
+    // Schlick's approximation of the fraction of light that's reflected rather than refracted.
+    pub fn schlick(&self) -> f64 {
+        let mut cos = self.eye.dot(&self.normal);
+        if self.n1 > self.n2 {
+            match self.refraction() {
+                Refraction::TotalInternalReflection => return 1.0,
+                Refraction::Transmitted { cos_t, .. } => cos = cos_t,
+            }
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, body::Body, fuzzy_eq::FuzzyEq, intersection::Intersections,
+        material::Phong, plane::Plane, ray::Ray, sphere::Sphere,
+    };
+
+    fn glass_sphere() -> Body {
+        Sphere::default()
+            .with_material(
+                Phong {
+                    transparency: 1.0,
+                    refractive_index: 1.5,
+                    ..Phong::default()
+                }
+                .into(),
+            )
+            .into()
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        let shape = glass_sphere();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, FRAC_1_SQRT_2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs: Intersections = vec![
+            HitRecord::new(-FRAC_1_SQRT_2, r, shape.clone()),
+            HitRecord::new(FRAC_1_SQRT_2, r, shape),
+        ]
+        .into();
+
+        let c = xs[1].computed_with(&xs);
+
+        assert!(matches!(
+            c.refraction(),
+            Refraction::TotalInternalReflection
+        ));
+        assert_fuzzy_eq!(1.0, c.schlick());
+    }
+
+    #[test]
+    fn refracting_straight_through_the_surface() {
+        let shape = glass_sphere();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Intersections = vec![
+            HitRecord::new(4.0, r, shape.clone()),
+            HitRecord::new(6.0, r, shape),
+        ]
+        .into();
+
+        let c = xs[0].computed_with(&xs);
+
+        match c.refraction() {
+            Refraction::Transmitted { direction, cos_t } => {
+                assert_fuzzy_eq!(Vector::new(0.0, 0.0, 1.0), direction);
+                assert_fuzzy_eq!(1.0, cos_t);
+            }
+            Refraction::TotalInternalReflection => panic!("expected the ray to be transmitted"),
+        }
+    }
+
+    #[test]
+    fn the_footprint_grows_with_distance_and_slant() {
+        let plane: Body = Plane::default().into();
+        let straight = Ray::new(Point::new(0.0, 4.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let slanted = Ray::new(
+            Point::new(0.0, 4.0, -4.0),
+            Vector::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let footprint = |r: Ray, t: f64| HitRecord::new(t, r, plane.clone()).computed().footprint();
+
+        assert_fuzzy_eq!(0.0, footprint(straight, 4.0));
+        assert_fuzzy_eq!(0.04, footprint(straight.with_spread(0.01), 4.0));
+        // Going twice as far spreads twice as much.
+        let bounced = Ray {
+            travelled: 4.0,
+            ..straight.with_spread(0.01)
+        };
+        assert_fuzzy_eq!(0.08, footprint(bounced, 4.0));
+        // The slanted ray goes √2 times as far, and is stretched √2 times as much across the
+        // plane.
+        assert_fuzzy_eq!(0.08, footprint(slanted.with_spread(0.01), 4.0 * SQRT_2));
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_a_perpendicular_viewing_angle() {
+        let shape = glass_sphere();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs: Intersections = vec![
+            HitRecord::new(-1.0, r, shape.clone()),
+            HitRecord::new(1.0, r, shape),
+        ]
+        .into();
+
+        let c = xs[1].computed_with(&xs);
+
+        assert_fuzzy_eq!(0.04, c.schlick());
+    }
+
+    #[test]
+    fn the_schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let shape = glass_sphere();
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Intersections = vec![HitRecord::new(1.8589, r, shape)].into();
+
+        let c = xs[0].computed_with(&xs);
+
+        assert!((0.48873 - c.schlick()).abs() < 1e-4);
+    }
 }
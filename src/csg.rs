@@ -0,0 +1,236 @@
+use crate::{
+    body::Body,
+    bounds::{Bounded, Bounds},
+    fuzzy_eq::EPISILON,
+    intersection::{HitRecord, Intersectable},
+    matrix::Matrix,
+    ray::Ray,
+    transform::Transformable,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    // Everything in either side.
+    Union,
+    // Only what's in both sides.
+    Intersection,
+    // What's in the left side but not the right, e.g. a die with its pips cut out of it.
+    Difference,
+}
+
+impl Operation {
+    // Whether a hit on one side is on the surface of the combined solid, given what the ray is
+    // inside of at the hit.
+    fn allows(self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Operation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            Operation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            Operation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+// Constructive solid geometry: a solid made by combining two others, where only the parts of
+// their surfaces that are on the surface of the combination are hit. Each side is a closed
+// surface, like a sphere or the triangles of a closed mesh, or several that don't overlap, like
+// the pips of a die. Worlds hold them apart from their plain bodies (see World::with_csg), and
+// what's hit is shaded with the material of the side it's on, so the walls of a hole cut in
+// something are the color of what cut it.
+#[derive(Clone, Debug)]
+pub struct Csg {
+    pub operation: Operation,
+    left: Vec<Body>,
+    right: Vec<Body>,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, left: Vec<Body>, right: Vec<Body>) -> Self {
+        if left.is_empty() {
+            panic!("the left side of a CSG solid needs at least one body")
+        }
+        Self {
+            operation,
+            left,
+            right,
+        }
+    }
+
+    pub fn union(left: Vec<Body>, right: Vec<Body>) -> Self {
+        Self::new(Operation::Union, left, right)
+    }
+
+    pub fn intersection(left: Vec<Body>, right: Vec<Body>) -> Self {
+        Self::new(Operation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Vec<Body>, right: Vec<Body>) -> Self {
+        Self::new(Operation::Difference, left, right)
+    }
+
+    // The solid moved by the transform, on top of where it already is.
+    pub fn transformed(self, transform: Matrix<4>) -> Self {
+        let place = |bodies: Vec<Body>| {
+            bodies
+                .into_iter()
+                .map(|b| {
+                    let transform = transform * b.transform();
+                    b.with_transform(transform)
+                })
+                .collect()
+        };
+        Self {
+            left: place(self.left),
+            right: place(self.right),
+            ..self
+        }
+    }
+
+    // The bodies of the left side and then the right.
+    pub fn bodies(&self) -> impl Iterator<Item = &Body> {
+        self.left.iter().chain(&self.right)
+    }
+
+    // The box around both sides, in world space. What the operation leaves is somewhere inside.
+    pub fn bounds(&self) -> Bounds {
+        self.bodies()
+            .map(|b| b.bounds().transformed(b.transform()))
+            .reduce(|a, b| a.union(&b))
+            .unwrap()
+    }
+
+    // The hits of the ray on the surface of the solid, with the bodies numbered from `first_id`
+    // in the order of `bodies`.
+    pub fn intersect(&self, ray: Ray, first_id: usize) -> Vec<HitRecord> {
+        // Every crossing of each side counts to know what the ray is inside of, so none of the
+        // backs can be skipped.
+        let all_faces = Ray {
+            cull_back_faces: false,
+            ..ray
+        };
+        let mut xs: Vec<(HitRecord, bool)> = self
+            .bodies()
+            .enumerate()
+            .flat_map(|(i, body)| {
+                let left = i < self.left.len();
+                body.intersect(all_faces)
+                    .into_iter()
+                    .map(move |x| (HitRecord { ray, ..x }.with_body_id(first_id + i), left))
+            })
+            .collect();
+        xs.sort_by(|(a, _), (b, _)| a.t.total_cmp(&b.t).then(a.body_id.cmp(&b.body_id)));
+
+        let (mut in_left, mut in_right) = (false, false);
+        // The last crossing of each side, so that a ray through an edge shared by two triangles,
+        // which hits both, only crosses once.
+        let mut last: [Option<(f64, bool)>; 2] = [None, None];
+        let mut kept = Vec::new();
+        for (x, left) in xs {
+            let enters = x.enters();
+            let side = &mut last[usize::from(!left)];
+            if side.is_some_and(|(t, entered)| x.t - t <= EPISILON && entered == enters) {
+                continue;
+            }
+            *side = Some((x.t, enters));
+
+            if self.operation.allows(left, in_left, in_right) {
+                kept.push(x);
+            }
+            if left {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+        }
+        kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, point::Point, sphere::Sphere, vector::Vector};
+
+    // Two unit spheres, the right one a little further along z.
+    fn spheres(operation: Operation) -> Csg {
+        let right = Sphere::default().with_transform(Matrix::translate(0.0, 0.0, 0.5));
+        Csg::new(
+            operation,
+            vec![Sphere::default().into()],
+            vec![right.into()],
+        )
+    }
+
+    #[test]
+    fn what_each_operation_allows() {
+        // For a hit on the left and then the right, and every combination of what it's inside.
+        let cases = [
+            (
+                Operation::Union,
+                [false, true, false, true, false, false, true, true],
+            ),
+            (
+                Operation::Intersection,
+                [true, false, true, false, true, true, false, false],
+            ),
+            (
+                Operation::Difference,
+                [false, true, false, true, true, true, false, false],
+            ),
+        ];
+        for (operation, allowed) in cases {
+            let mut i = 0;
+            for left_hit in [true, false] {
+                for in_left in [true, false] {
+                    for in_right in [true, false] {
+                        assert_eq!(
+                            allowed[i],
+                            operation.allows(left_hit, in_left, in_right),
+                            "{:?} {} {} {}",
+                            operation,
+                            left_hit,
+                            in_left,
+                            in_right
+                        );
+                        i += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_ray_hits_only_the_surface_of_the_combination() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let cases = [
+            (Operation::Union, vec![(4.0, 0), (6.5, 1)]),
+            (Operation::Intersection, vec![(4.5, 1), (6.0, 0)]),
+            (Operation::Difference, vec![(4.0, 0), (4.5, 1)]),
+        ];
+
+        for (operation, expected) in cases {
+            let xs = spheres(operation).intersect(r, 0);
+
+            assert_eq!(expected.len(), xs.len(), "{:?}", operation);
+            for (x, (t, id)) in xs.iter().zip(expected) {
+                assert_fuzzy_eq!(t, x.t);
+                assert_eq!(id, x.body_id);
+            }
+        }
+    }
+
+    #[test]
+    fn a_ray_that_misses_the_combination_hits_nothing() {
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(spheres(Operation::Union).intersect(r, 0).is_empty());
+    }
+
+    #[test]
+    fn moving_a_combination_moves_both_sides() {
+        let csg = spheres(Operation::Union).transformed(Matrix::translate(0.0, 0.0, 1.0));
+        let bounds = csg.bounds();
+
+        assert_fuzzy_eq!(Point::new(-1.0, -1.0, 0.0), bounds.min);
+        assert_fuzzy_eq!(Point::new(1.0, 1.0, 2.5), bounds.max);
+    }
+}
@@ -0,0 +1,259 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    body::Body,
+    instance::{Instance, Mesh},
+    noise::seeded_noise,
+    plane::Plane,
+    point::Point,
+    sphere::Sphere,
+    triangle::Triangle,
+    vector::Vector,
+};
+
+// Bumps made of real geometry rather than faked with the normals: the surface is cut up into
+// triangles when the scene is built and each corner is pushed in or out by some noise, so the
+// bumps show along the outline and cast their own shadows.
+#[derive(Clone, Copy, Debug)]
+pub struct Displacement {
+    // The furthest the surface is pushed either way, in object space.
+    pub height: f64,
+    // How many bumps there are per unit.
+    pub scale: f64,
+    // How many layers of finer, smaller bumps go on top of the big ones.
+    pub octaves: u32,
+    // Which bumps. Bodies displaced with the same settings and seed get the same bumps.
+    pub seed: u64,
+}
+
+impl Default for Displacement {
+    fn default() -> Self {
+        Self {
+            height: 0.1,
+            scale: 4.0,
+            octaves: 3,
+            seed: 0,
+        }
+    }
+}
+
+impl Displacement {
+    pub fn with_height(self, height: f64) -> Self {
+        Self { height, ..self }
+    }
+
+    pub fn with_scale(self, scale: f64) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn with_octaves(self, octaves: u32) -> Self {
+        Self { octaves, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    // How far the surface at the object space point is pushed out along its normal, in
+    // [-height, height].
+    pub fn offset_at(&self, p: Point) -> f64 {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        for _ in 0..self.octaves {
+            let f = self.scale * frequency;
+            sum += seeded_noise(Point::new(p[0] * f, p[1] * f, p[2] * f), self.seed) / frequency;
+            total += 1.0 / frequency;
+            frequency *= 2.0;
+        }
+        if total == 0.0 {
+            return 0.0;
+        }
+        (self.height * sum / total).clamp(-self.height, self.height)
+    }
+}
+
+// A triangle through the points, facing the same way as `out`.
+fn facing(p1: Point, p2: Point, p3: Point, out: Vector) -> Triangle {
+    if (p3 - p1).cross(&(p2 - p1)).dot(&out) < 0.0 {
+        Triangle::new(p1, p3, p2)
+    } else {
+        Triangle::new(p1, p2, p3)
+    }
+}
+
+// The triangles become a mesh of their own, placed where the body was.
+fn placed(triangles: Vec<Triangle>, body: impl Into<Body>) -> Instance {
+    let body = body.into();
+    let material = body.material();
+    let bodies = triangles
+        .into_iter()
+        .map(|t| t.with_material(material.clone()).into())
+        .collect();
+    Instance::new(Arc::new(Mesh::new(bodies))).with_transform(body.transform())
+}
+
+impl Sphere {
+    // The sphere as a displaced mesh, with `detail` rows of triangles from pole to pole and twice
+    // as many around the equator.
+    pub fn displaced(&self, displacement: &Displacement, detail: usize) -> Instance {
+        assert!(detail >= 2, "a displaced sphere needs at least 2 rows");
+        let columns = 2 * detail;
+        let corner = |row: usize, column: usize| {
+            let theta = PI * row as f64 / detail as f64;
+            // Every column meets at the poles, and the last one is the first one again, so work
+            // them out the same way to leave no cracks.
+            let phi = if row == 0 || row == detail {
+                0.0
+            } else {
+                2.0 * PI * (column % columns) as f64 / columns as f64
+            };
+            let p = Point::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            );
+            let radius = 1.0 + displacement.offset_at(p);
+            Point::new(p[0] * radius, p[1] * radius, p[2] * radius)
+        };
+
+        let mut triangles = Vec::with_capacity(2 * detail * columns);
+        let origin = Point::new(0.0, 0.0, 0.0);
+        for row in 0..detail {
+            for column in 0..columns {
+                let a = corner(row, column);
+                let b = corner(row, column + 1);
+                let c = corner(row + 1, column);
+                let d = corner(row + 1, column + 1);
+                // The quads next to the poles have two corners in the same place, so they're
+                // only one triangle.
+                if row != 0 {
+                    triangles.push(facing(a, b, d, a - origin));
+                }
+                if row != detail - 1 {
+                    triangles.push(facing(a, d, c, a - origin));
+                }
+            }
+        }
+        placed(triangles, self.clone())
+    }
+}
+
+impl Plane {
+    // A `size` by `size` square of the plane around its origin as a displaced mesh, with `detail`
+    // squares along each side. The plane itself goes on forever, which a mesh can't.
+    pub fn displaced(&self, displacement: &Displacement, size: f64, detail: usize) -> Instance {
+        assert!(detail >= 1, "a displaced plane needs at least 1 square");
+        let corner = |row: usize, column: usize| {
+            let x = size * (column as f64 / detail as f64 - 0.5);
+            let z = size * (row as f64 / detail as f64 - 0.5);
+            Point::new(x, displacement.offset_at(Point::new(x, 0.0, z)), z)
+        };
+
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let mut triangles = Vec::with_capacity(2 * detail * detail);
+        for row in 0..detail {
+            for column in 0..detail {
+                let a = corner(row, column);
+                let b = corner(row, column + 1);
+                let c = corner(row + 1, column);
+                let d = corner(row + 1, column + 1);
+                triangles.push(facing(a, b, d, up));
+                triangles.push(facing(a, d, c, up));
+            }
+        }
+        placed(triangles, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, fuzzy_eq::FuzzyEq, intersection::Normal, matrix::Matrix, ray::Ray,
+        world::World,
+    };
+
+    fn hit_distance(instance: Instance, ray: Ray) -> f64 {
+        let world = World::new(vec![], vec![]).with_instances(vec![instance]);
+        world.intersect(ray).hit().unwrap().t
+    }
+
+    #[test]
+    fn offsets_stay_within_the_height() {
+        let d = Displacement::default().with_height(0.3);
+
+        let offsets: Vec<f64> = (0..200)
+            .map(|i| d.offset_at(Point::new(i as f64 * 0.137, i as f64 * -0.071, 0.5)))
+            .collect();
+
+        assert!(offsets.iter().all(|o| o.abs() <= 0.3));
+        assert!(offsets.iter().any(|o| o.abs() > 0.01));
+        assert_fuzzy_eq!(0.0, d.with_octaves(0).offset_at(Point::new(0.3, 0.4, 0.5)));
+    }
+
+    #[test]
+    fn a_displaced_sphere_bulges_where_the_noise_says() {
+        let d = Displacement::default().with_height(0.2);
+        let sphere = Sphere::default().with_transform(Matrix::translate(0.0, 0.0, 1.0));
+
+        // With 8 rows, there's a corner right where the ray meets the sphere.
+        let t = hit_distance(
+            sphere.displaced(&d, 8),
+            Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+        );
+
+        let bulge = d.offset_at(Point::new(0.0, 0.0, -1.0));
+        assert_fuzzy_eq!(5.0 - bulge, t);
+    }
+
+    #[test]
+    fn displacing_a_sphere_changes_its_outline() {
+        let instance = Sphere::default().displaced(&Displacement::default().with_height(0.2), 16);
+
+        let radii: Vec<f64> = instance
+            .mesh()
+            .bodies()
+            .iter()
+            .map(|b| match b {
+                Body::Triangle(t) => (t.points().0 - Point::new(0.0, 0.0, 0.0)).magnitude(),
+                _ => panic!("expected triangles"),
+            })
+            .collect();
+
+        assert!(radii.iter().all(|r| (0.8 - 1e-9..=1.2 + 1e-9).contains(r)));
+        assert!(radii.iter().any(|r| (r - 1.0).abs() > 0.02));
+    }
+
+    #[test]
+    fn a_displaced_sphere_faces_out() {
+        let instance = Sphere::default().displaced(&Displacement::default(), 6);
+
+        for body in instance.mesh().bodies() {
+            let (p1, p2, p3) = match body {
+                Body::Triangle(t) => t.points(),
+                _ => panic!("expected triangles"),
+            };
+            let out = p1 - Point::new(0.0, 0.0, 0.0);
+            assert!(body.normal_at(p1).dot(&out) > 0.0, "{:?}", (p1, p2, p3));
+        }
+    }
+
+    #[test]
+    fn a_displaced_plane_keeps_its_material_and_transform() {
+        let plane = Plane::default().with_transform(Matrix::translate(0.0, 1.0, 0.0));
+        let d = Displacement::default().with_height(0.5);
+
+        let instance = plane.displaced(&d, 4.0, 8);
+
+        assert_eq!(128, instance.mesh().bodies().len());
+        assert_fuzzy_eq!(plane.transform(), instance.transform());
+        // Straight down onto a corner of the grid.
+        let t = hit_distance(
+            instance,
+            Ray::new(Point::new(0.5, 10.0, -1.0), Vector::new(0.0, -1.0, 0.0)),
+        );
+        let bump = d.offset_at(Point::new(0.5, 0.0, -1.0));
+        assert_fuzzy_eq!(9.0 - bump, t);
+    }
+}
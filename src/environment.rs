@@ -0,0 +1,92 @@
+use crate::{
+    color::Color, probe::LightProbe, ray::Ray, sky::Sky, skydome::Skydome, starfield::Starfield,
+};
+
+// What a ray sees when it doesn't hit anything.
+#[derive(Clone, Debug)]
+pub enum Environment {
+    Color(Color),
+    // Blends from the horizon color to the zenith color going up, and stays at the horizon color
+    // below the horizon.
+    Gradient { horizon: Color, zenith: Color },
+    Sky(Sky),
+    Skydome(Skydome),
+    Starfield(Starfield),
+    // The scene as seen from somewhere else, e.g. another world's, captured ahead of time.
+    Probe(LightProbe),
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::Color(Color::BLACK)
+    }
+}
+
+impl Environment {
+    pub fn color_at(&self, ray: Ray) -> Color {
+        match self {
+            Environment::Color(c) => *c,
+            Environment::Gradient { horizon, zenith } => {
+                let up = ray.direction.normalize()[1].max(0.0);
+                *horizon + (*zenith - *horizon) * up
+            }
+            Environment::Sky(sky) => sky.color_at(ray.direction),
+            Environment::Skydome(skydome) => skydome.color_at(ray.direction),
+            Environment::Starfield(stars) => stars.color_at(ray.direction),
+            Environment::Probe(probe) => probe.color_at(ray.direction),
+        }
+    }
+}
+
+impl From<Color> for Environment {
+    fn from(c: Color) -> Self {
+        Environment::Color(c)
+    }
+}
+
+impl From<Sky> for Environment {
+    fn from(sky: Sky) -> Self {
+        Environment::Sky(sky)
+    }
+}
+
+impl From<Skydome> for Environment {
+    fn from(skydome: Skydome) -> Self {
+        Environment::Skydome(skydome)
+    }
+}
+
+impl From<LightProbe> for Environment {
+    fn from(probe: LightProbe) -> Self {
+        Environment::Probe(probe)
+    }
+}
+
+impl From<Starfield> for Environment {
+    fn from(stars: Starfield) -> Self {
+        Environment::Starfield(stars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, point::Point, vector::Vector};
+
+    #[test]
+    fn a_gradient_blends_from_the_horizon_to_the_zenith() {
+        let env = Environment::Gradient {
+            horizon: Color::WHITE,
+            zenith: Color::BLUE,
+        };
+        let looking = |x, y, z| Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(x, y, z));
+
+        assert_fuzzy_eq!(Color::WHITE, env.color_at(looking(1.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(Color::WHITE, env.color_at(looking(1.0, -1.0, 0.0)));
+        assert_fuzzy_eq!(Color::BLUE, env.color_at(looking(0.0, 2.0, 0.0)));
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 1.0),
+            env.color_at(looking(0.0, 0.5, 0.75f64.sqrt()))
+        );
+    }
+}
@@ -0,0 +1,29 @@
+// Scenes shared by the tests of more than one module.
+
+use std::f64::consts::FRAC_PI_3;
+
+use crate::{
+    camera::Camera, color::Color, light::PointLight, matrix::Matrix, point::Point, sphere::Sphere,
+    vector::Vector, world::World,
+};
+
+// Three named spheres at the origin, "ball" and "moon" with no transform and "turret" twice their
+// size, lit from above and seen from in front.
+pub fn scene() -> (World, Camera) {
+    let world = World::new(
+        vec![],
+        vec![PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).into()],
+    )
+    .with_named_body("ball", Sphere::default())
+    .with_named_body("moon", Sphere::default())
+    .with_named_body(
+        "turret",
+        Sphere::default().with_transform(Matrix::scale(2.0, 2.0, 2.0)),
+    );
+    let camera = Camera::new(10, 10, FRAC_PI_3).look_at_from_position(
+        Point::new(0.0, 0.0, -5.0),
+        Point::new(0.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    (world, camera)
+}
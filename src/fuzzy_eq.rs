@@ -1,6 +1,6 @@
 pub const EPISILON: f64 = 0.00001;
 
-pub trait FuzzyEq: Copy {
+pub trait FuzzyEq: Sized {
     fn fuzzy_eq(&self, other: Self) -> bool;
     fn fuzzy_ne(&self, other: Self) -> bool {
         !self.fuzzy_eq(other)
@@ -18,18 +18,13 @@ where
     T: FuzzyEq,
 {
     fn fuzzy_eq(&self, other: Self) -> bool {
-        for i in 0..N {
-            if self[i].fuzzy_ne(other[i]) {
-                return false;
-            }
-        }
-        true
+        self.iter().zip(other).all(|(a, b)| a.fuzzy_eq(b))
     }
 }
 
 impl<T> FuzzyEq for Option<T>
 where
-    T: Copy + FuzzyEq,
+    T: FuzzyEq,
 {
     fn fuzzy_eq(&self, other: Self) -> bool {
         match (self, other) {
@@ -43,6 +38,8 @@ where
 #[macro_export]
 macro_rules! assert_fuzzy_eq {
     ($x:expr, $y:expr) => {
-        assert!(($x).fuzzy_eq(($y)), "want: {:?}, got: {:?}", $x, $y);
+        match (&$x, &$y) {
+            (x, y) => assert!(x.fuzzy_eq(y.clone()), "want: {:?}, got: {:?}", x, y),
+        }
     };
 }
@@ -0,0 +1,142 @@
+use std::f64::consts::FRAC_PI_3;
+
+use crate::{
+    camera::Camera, color::Color, environment::Environment, group::Group, light::PointLight,
+    material::Phong, plane::Plane, point::Point, prefabs, vector::Vector, world::World,
+};
+
+// The scenes that come with the raytracer, for rendering all together after changing the
+// renderer to see whether anything looks different.
+pub struct GalleryScene {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub world: World,
+    from: Point,
+    to: Point,
+}
+
+impl GalleryScene {
+    pub fn camera(&self, width: usize, height: usize) -> Camera {
+        Camera::new(width, height, FRAC_PI_3).look_at_from_position(
+            self.from,
+            self.to,
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+}
+
+// A prefab on a plain floor under a sky.
+fn on_a_floor(prefab: Group) -> World {
+    let floor = Plane::default().with_material(
+        Phong {
+            color: Color::new(0.7, 0.7, 0.7),
+            specular: 0.0,
+            ..Phong::default()
+        }
+        .into(),
+    );
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+    let mut bodies = vec![floor.into()];
+    bodies.extend(prefab.bodies());
+    World::new(bodies, vec![light.into()]).with_environment(Environment::Gradient {
+        horizon: Color::new(0.85, 0.9, 1.0),
+        zenith: Color::new(0.25, 0.45, 0.85),
+    })
+}
+
+pub fn scenes() -> Vec<GalleryScene> {
+    vec![
+        GalleryScene {
+            name: "quickstart",
+            description: "A sphere on a checkered floor under a gradient sky.",
+            world: World::quickstart(),
+            from: Point::new(0.0, 1.5, -5.0),
+            to: Point::new(0.0, 1.0, 0.0),
+        },
+        GalleryScene {
+            name: "dice",
+            description: "Two dice on a checkered floor, casting shadows.",
+            world: prefabs::dice_scene(),
+            from: Point::new(-1.0, 4.5, -7.0),
+            to: Point::new(1.0, 0.8, 0.0),
+        },
+        GalleryScene {
+            name: "snowman",
+            description: "A snowman made of spheres and capsules.",
+            world: on_a_floor(prefabs::snowman()),
+            from: Point::new(0.5, 1.5, -4.0),
+            to: Point::new(0.0, 1.0, 0.0),
+        },
+        GalleryScene {
+            name: "table-and-chairs",
+            description: "A wooden table and chairs made of boxes and capsules.",
+            world: on_a_floor(prefabs::table_and_chairs()),
+            from: Point::new(-2.0, 2.5, -3.5),
+            to: Point::new(0.0, 0.4, 0.0),
+        },
+    ]
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// An HTML page showing the rendered scenes, which are expected to be next to it as <name>.png.
+pub fn index_html(scenes: &[GalleryScene], width: usize, height: usize) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Gallery</title>\n</head>\n<body>\n<h1>Gallery</h1>\n",
+    );
+    for scene in scenes {
+        let name = escape(scene.name);
+        html.push_str(&format!(
+            "<figure>\n<img src=\"{name}.png\" width=\"{width}\" height=\"{height}\" alt=\"{name}\">\n<figcaption><b>{name}</b>: {}</figcaption>\n</figure>\n",
+            escape(scene.description),
+        ));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::render::{render, RenderSettings};
+
+    #[test]
+    fn every_scene_has_its_own_name_and_renders() {
+        let scenes = scenes();
+        let names: HashSet<&str> = scenes.iter().map(|s| s.name).collect();
+        assert_eq!(scenes.len(), names.len());
+
+        for scene in &scenes {
+            let output = render(
+                &scene.world,
+                &scene.camera(8, 6),
+                &RenderSettings::default(),
+            );
+            assert!(output.complete);
+        }
+    }
+
+    #[test]
+    fn the_index_shows_every_scene() {
+        let scenes = scenes();
+
+        let html = index_html(&scenes, 320, 180);
+
+        for scene in &scenes {
+            assert!(html.contains(&format!("<img src=\"{}.png\"", scene.name)));
+        }
+        assert!(html.contains("width=\"320\" height=\"180\""));
+    }
+
+    #[test]
+    fn descriptions_are_escaped() {
+        assert_eq!("a &lt;b&gt; &amp; &quot;c&quot;", escape("a <b> & \"c\""));
+    }
+}
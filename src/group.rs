@@ -0,0 +1,202 @@
+use crate::{body::Body, matrix::Matrix, transform::Transformable};
+
+// A collection of bodies (and other groups) that move together, e.g. the triangles of a mesh. A
+// group's transform applies on top of the transforms of everything in it. Worlds hold plain
+// bodies, so a group is added to one by flattening it with `bodies`.
+#[derive(Clone, Debug)]
+pub struct Group {
+    transform: Matrix<4>,
+    bodies: Vec<Body>,
+    groups: Vec<Group>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            transform: Matrix::identity(),
+            bodies: vec![],
+            groups: vec![],
+        }
+    }
+}
+
+impl Group {
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+
+    pub fn with_body(mut self, body: impl Into<Body>) -> Self {
+        self.bodies.push(body.into());
+        self
+    }
+
+    pub fn with_group(mut self, group: Group) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    // Lets camera rays skip the backs of all the triangles in the group and its subgroups, which
+    // halves how many they hit. Only do this for closed meshes, since the backs vanish.
+    pub fn with_back_face_culling(self) -> Self {
+        let bodies = self
+            .bodies
+            .into_iter()
+            .map(|body| match body {
+                Body::Triangle(t) => t.with_back_face_culling().into(),
+                _ => body,
+            })
+            .collect();
+        let groups = self
+            .groups
+            .into_iter()
+            .map(Group::with_back_face_culling)
+            .collect();
+        Self {
+            bodies,
+            groups,
+            ..self
+        }
+    }
+
+    // Every body in the group and its subgroups, with the transforms of the groups they're in
+    // folded into their own.
+    pub fn bodies(&self) -> Vec<Body> {
+        let mut bodies = Vec::new();
+        self.collect_bodies(Matrix::identity(), &mut bodies);
+        bodies
+    }
+
+    fn collect_bodies(&self, parent: Matrix<4>, bodies: &mut Vec<Body>) {
+        let transform = parent * self.transform;
+        bodies.extend(
+            self.bodies
+                .iter()
+                .map(|b| b.clone().with_transform(transform * b.transform())),
+        );
+        for group in &self.groups {
+            group.collect_bodies(transform, bodies);
+        }
+    }
+
+    // Flattens the group and pushes all of the transforms down into the triangles' points, so
+    // rays don't need transforming to hit them. This is only worth it for meshes that stay put;
+    // moving a baked mesh means moving every one of its points. Bodies other than triangles have
+    // no points to bake into, so they keep their (combined) transforms.
+    pub fn bake_transforms(&self) -> Self {
+        let bodies = self
+            .bodies()
+            .into_iter()
+            .map(|body| match body {
+                Body::Triangle(t) => t.baked().into(),
+                _ => body,
+            })
+            .collect();
+        Self {
+            bodies,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        fuzzy_eq::FuzzyEq,
+        intersection::{Intersectable, Normal},
+        point::Point,
+        ray::Ray,
+        sphere::Sphere,
+        triangle::Triangle,
+        vector::Vector,
+    };
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn flattening_a_group_combines_the_transforms() {
+        let inner = Group::default()
+            .with_transform(Matrix::scale(2.0, 2.0, 2.0))
+            .with_body(Sphere::default().with_transform(Matrix::translate(1.0, 0.0, 0.0)));
+        let outer = Group::default()
+            .with_transform(Matrix::translate(0.0, 5.0, 0.0))
+            .with_body(Sphere::default())
+            .with_group(inner);
+
+        let bodies = outer.bodies();
+
+        assert_eq!(2, bodies.len());
+        assert_fuzzy_eq!(Matrix::translate(0.0, 5.0, 0.0), bodies[0].transform());
+        assert_fuzzy_eq!(
+            Matrix::translate(0.0, 5.0, 0.0)
+                * Matrix::scale(2.0, 2.0, 2.0)
+                * Matrix::translate(1.0, 0.0, 0.0),
+            bodies[1].transform()
+        );
+    }
+
+    #[test]
+    fn baking_moves_transforms_into_the_triangles() {
+        let group = Group::default()
+            .with_transform(Matrix::translate(0.0, 0.0, 5.0))
+            .with_group(
+                Group::default()
+                    .with_transform(Matrix::scale(1.0, -2.0, 1.0))
+                    .with_body(triangle()),
+            );
+        let r = Ray::new(Point::new(0.0, -1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let unbaked = group.bodies();
+        let baked = group.bake_transforms().bodies();
+
+        assert_eq!(1, baked.len());
+        assert_fuzzy_eq!(Matrix::<4>::identity(), baked[0].transform());
+        let (xs, baked_xs) = (unbaked[0].intersect(r), baked[0].intersect(r));
+        assert_eq!(1, baked_xs.len());
+        assert_fuzzy_eq!(xs[0].t, baked_xs[0].t);
+        let p = r.position(baked_xs[0].t);
+        assert_fuzzy_eq!(unbaked[0].normal_at(p), baked[0].normal_at(p));
+    }
+
+    #[test]
+    fn baking_keeps_the_combined_transform_of_other_bodies() {
+        let group = Group::default()
+            .with_transform(Matrix::translate(0.0, 5.0, 0.0))
+            .with_body(Sphere::default().with_transform(Matrix::scale(2.0, 2.0, 2.0)));
+
+        let baked = group.bake_transforms();
+
+        assert_fuzzy_eq!(Matrix::<4>::identity(), baked.transform());
+        assert_fuzzy_eq!(
+            Matrix::translate(0.0, 5.0, 0.0) * Matrix::scale(2.0, 2.0, 2.0),
+            baked.bodies()[0].transform()
+        );
+    }
+
+    #[test]
+    fn culling_a_group_culls_the_triangles_in_its_subgroups() {
+        let group = Group::default()
+            .with_body(Sphere::default())
+            .with_group(Group::default().with_body(triangle()))
+            .with_back_face_culling();
+        let back = Ray::new(Point::new(0.0, 0.5, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        let bodies = group.bodies();
+
+        assert!(bodies[1]
+            .intersect(back.with_back_face_culling())
+            .is_empty());
+        assert_eq!(1, bodies[1].intersect(back).len());
+    }
+}
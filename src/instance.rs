@@ -0,0 +1,593 @@
+use std::sync::Arc;
+
+use crate::{
+    body::Body,
+    bounds::{Bounded, Bounds},
+    bvh::{BuildQuality, Bvh},
+    group::Group,
+    intersection::{HitRecord, Intersectable},
+    material::Material,
+    matrix::Matrix,
+    point::Point,
+    ray::Ray,
+    rng::Rng,
+    stats::RenderStats,
+    transform::Transformable,
+    vector::Vector,
+};
+
+// Instancing uses two levels of bounding volume hierarchy. Each mesh has its own, built once over
+// its bodies in the mesh's space. The instances that place meshes in the world have another one
+// over where they are, which only has a box per instance, so rebuilding it when instances move
+// between frames is cheap no matter how detailed the meshes are.
+
+// A set of bodies that's placed in the world as a unit, any number of times.
+#[derive(Debug)]
+pub struct Mesh {
+    bodies: Vec<Body>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    pub fn new(bodies: Vec<Body>) -> Self {
+        Self::with_quality(bodies, BuildQuality::default())
+    }
+
+    // Builds the mesh with a hierarchy of the given quality. Meshes are only built once, so it's
+    // usually worth asking for the best one for big meshes.
+    pub fn with_quality(bodies: Vec<Body>, quality: BuildQuality) -> Self {
+        let bounds: Vec<Bounds> = bodies
+            .iter()
+            .map(|b| b.bounds().transformed(b.transform()))
+            .collect();
+        Self {
+            bvh: Bvh::with_quality(&bounds, quality),
+            bodies,
+        }
+    }
+
+    pub fn bodies(&self) -> &[Body] {
+        &self.bodies
+    }
+
+    pub(crate) fn bvh(&self) -> &Bvh {
+        &self.bvh
+    }
+}
+
+impl From<Group> for Mesh {
+    fn from(group: Group) -> Self {
+        Self::new(group.bodies())
+    }
+}
+
+impl Bounded for Mesh {
+    fn bounds(&self) -> Bounds {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        self.bvh.bounds().unwrap_or(Bounds::new(origin, origin))
+    }
+}
+
+// One copy of a mesh in the world. Copies share the mesh rather than each having their own.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    mesh: Arc<Mesh>,
+    transform: Matrix<4>,
+    inverse: Matrix<4>,
+    // How this copy's colors differ from the mesh's; see with_color_shift.
+    hue_shift: f64,
+    brightness: f64,
+}
+
+impl Instance {
+    pub fn new(mesh: Arc<Mesh>) -> Self {
+        Self {
+            mesh,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+            hue_shift: 0.0,
+            brightness: 1.0,
+        }
+    }
+
+    // Shades this copy's bodies with their colors turned around the color wheel by `hue_shift`
+    // turns (see Color::with_hue_shift) and scaled by `brightness`.
+    pub fn with_color_shift(self, hue_shift: f64, brightness: f64) -> Self {
+        Self {
+            hue_shift,
+            brightness,
+            ..self
+        }
+    }
+
+    // The body's material as this copy is shaded with it.
+    fn material_for(&self, material: Material) -> Material {
+        if self.hue_shift == 0.0 && self.brightness == 1.0 {
+            return material;
+        }
+        material.map_colors(|c| c.with_hue_shift(self.hue_shift) * self.brightness)
+    }
+
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self {
+            transform,
+            inverse: transform.inverse(),
+            ..self
+        }
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+}
+
+impl Bounded for Instance {
+    fn bounds(&self) -> Bounds {
+        self.mesh.bounds()
+    }
+}
+
+impl Transformable for Instance {
+    fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        Instance::with_transform(self, transform)
+    }
+}
+
+// Small random differences between the copies of a mesh, so that a field of spheres or a forest
+// of trees doesn't look like the same thing over and over. Each copy's are picked by hashing its
+// index in the list with the seed, so they're the same every render, and the same when other
+// copies are added after it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Variation {
+    pub seed: u64,
+    // The most a copy's colors are turned around the color wheel either way, in turns.
+    pub hue: f64,
+    // The most a copy's colors are brightened or darkened, as a fraction.
+    pub brightness: f64,
+    // The most a copy is scaled up or down about its origin, as a fraction.
+    pub scale: f64,
+}
+
+impl Variation {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_hue(self, hue: f64) -> Self {
+        Self { hue, ..self }
+    }
+
+    pub fn with_brightness(self, brightness: f64) -> Self {
+        if !(0.0..1.0).contains(&brightness) {
+            panic!("brightness variation must be at least 0 and less than 1")
+        }
+        Self { brightness, ..self }
+    }
+
+    pub fn with_scale(self, scale: f64) -> Self {
+        if !(0.0..1.0).contains(&scale) {
+            panic!("scale variation must be at least 0 and less than 1")
+        }
+        Self { scale, ..self }
+    }
+
+    // Varies each of the instances.
+    pub fn apply(&self, instances: Vec<Instance>) -> Vec<Instance> {
+        instances
+            .into_iter()
+            .enumerate()
+            .map(|(i, instance)| {
+                let mut rng = Rng::new(self.seed ^ (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                // Somewhere in [-1, 1).
+                let mut jitter = || 2.0 * rng.next_f64() - 1.0;
+                let hue = self.hue * jitter();
+                let brightness = 1.0 + self.brightness * jitter();
+                let scale = 1.0 + self.scale * jitter();
+                let transform = instance.transform * Matrix::scale(scale, scale, scale);
+                let (hue, brightness) =
+                    (instance.hue_shift + hue, instance.brightness * brightness);
+                instance
+                    .with_transform(transform)
+                    .with_color_shift(hue, brightness)
+            })
+            .collect()
+    }
+}
+
+// How much slower refitting can make the top level hierarchy before it's rebuilt instead.
+const MAX_DEGRADATION: f64 = 1.5;
+
+// All of the instances in a world, with the top level hierarchy over them.
+#[derive(Clone, Debug, Default)]
+pub struct Instances {
+    instances: Vec<Instance>,
+    bvh: Bvh,
+    // Bodies in instances are numbered one after the other, for ordering intersections. This is
+    // the number of the first body in each instance.
+    first_ids: Vec<usize>,
+}
+
+impl Instances {
+    pub fn new(instances: Vec<Instance>) -> Self {
+        let mut first_ids = Vec::with_capacity(instances.len());
+        let mut next_id = 0;
+        for instance in &instances {
+            first_ids.push(next_id);
+            next_id += instance.mesh.bodies.len();
+        }
+
+        let mut instances = Self {
+            instances,
+            bvh: Bvh::default(),
+            first_ids,
+        };
+        instances.rebuild();
+        instances
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    // How many bodies there are in all the instances together, counting every copy.
+    pub fn body_count(&self) -> usize {
+        match (self.first_ids.last(), self.instances.last()) {
+            (Some(first), Some(last)) => first + last.mesh.bodies.len(),
+            _ => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter()
+    }
+
+    pub(crate) fn bvh(&self) -> &Bvh {
+        &self.bvh
+    }
+
+    // Moves one of the instances. Only the top level hierarchy is updated; the meshes' stay as
+    // they are. It's refitted around where the instances are now, and only rebuilt once that has
+    // made it too much slower, so moving things a little each frame stays cheap.
+    pub fn set_transform(&mut self, index: usize, transform: Matrix<4>) {
+        let instance = self.instances[index].clone();
+        self.instances[index] = instance.with_transform(transform);
+        self.bvh.refit(&self.bounds());
+        if self.bvh.degradation() > MAX_DEGRADATION {
+            self.rebuild();
+        }
+    }
+
+    // The id of the first body in the same instance as the body with the id, both counted from
+    // the first body in the instances.
+    pub fn first_id_in_instance(&self, id: usize) -> Option<usize> {
+        let instance = self
+            .first_ids
+            .partition_point(|&first| first <= id)
+            .checked_sub(1)?;
+        (id < self.body_count()).then(|| self.first_ids[instance])
+    }
+
+    // Where the body with the id, counted from the first body in the instances, is placed in the
+    // world.
+    pub fn body_transform(&self, id: usize) -> Option<Matrix<4>> {
+        let instance = self
+            .first_ids
+            .partition_point(|&first| first <= id)
+            .checked_sub(1)?;
+        let body = self.instances[instance]
+            .mesh
+            .bodies
+            .get(id - self.first_ids[instance])?;
+        Some(self.instances[instance].transform * body.transform())
+    }
+
+    fn bounds(&self) -> Vec<Bounds> {
+        self.instances
+            .iter()
+            .map(|i| i.bounds().transformed(i.transform))
+            .collect()
+    }
+
+    fn rebuild(&mut self) {
+        // This is rebuilt whenever things have moved a lot, so build it quickly rather than well.
+        self.bvh = Bvh::with_quality(&self.bounds(), BuildQuality::Fast);
+    }
+
+    // Every intersection of the ray with bodies in the instances. The bodies are given the
+    // transforms of their instances so that they can be shaded like any other body, and ids
+    // starting from `first_id`.
+    pub fn intersect(&self, ray: Ray, first_id: usize) -> Vec<HitRecord> {
+        let mut xs = Vec::new();
+        self.visit(ray, |instance, index, object_space_ray| {
+            let id = first_id + self.first_ids[instance] + index;
+            let instance = &self.instances[instance];
+            let body = &instance.mesh.bodies[index];
+            let hits = body.intersect(object_space_ray);
+            if hits.is_empty() {
+                return;
+            }
+            // Only bodies that are hit are placed, as most that the hierarchy visits are missed.
+            let placed = body
+                .clone()
+                .with_transform(instance.transform * body.transform())
+                .with_material(instance.material_for(body.material()));
+            // Transforming a ray doesn't change how far along it things are, so the t values carry
+            // over. Any normals the body worked out are in the instance's space, so they're turned
+            // into world space the way normal_at would.
+            let to_world = instance.inverse.transpose();
+            xs.extend(hits.iter().map(|x| HitRecord {
+                ray,
+                body: placed.clone(),
+                body_id: id,
+                normal: x.normal.map(|n| {
+                    let n = to_world * n;
+                    Vector::new(n[0], n[1], n[2]).normalize()
+                }),
+                ..*x
+            }));
+        });
+        xs
+    }
+
+    // How many bounding boxes finding what the ray hits takes, across both levels.
+    pub fn bounds_tests(&self, ray: Ray) -> usize {
+        self.visit(ray, |_, _, _| {})
+    }
+
+    // Adds what finding what the ray hits takes to the stats: the bodies tested and hit in each
+    // instance, and the boxes tested at each level of both trees.
+    pub(crate) fn record(&self, ray: Ray, stats: &mut RenderStats) {
+        let RenderStats {
+            instances,
+            instance_levels,
+            mesh_levels,
+            ..
+        } = stats;
+        let count_level = |levels: &mut Vec<usize>, depth: usize| {
+            if levels.len() <= depth {
+                levels.resize(depth + 1, 0);
+            }
+            levels[depth] += 1;
+        };
+        self.bvh.visit_levels(
+            ray,
+            |i| {
+                let instance = &self.instances[i];
+                let object_space_ray = ray.transform(instance.inverse);
+                let (mut body_tests, mut intersections, mut box_tests) = (0, 0, 0);
+                instance.mesh.bvh.visit_levels(
+                    object_space_ray,
+                    |index| {
+                        body_tests += 1;
+                        intersections += instance.mesh.bodies[index]
+                            .intersect(object_space_ray)
+                            .len();
+                    },
+                    |depth| {
+                        box_tests += 1;
+                        count_level(mesh_levels, depth);
+                    },
+                );
+                instances[i].body_tests += body_tests;
+                instances[i].intersections += intersections;
+                instances[i].box_tests += box_tests;
+            },
+            |depth| count_level(instance_levels, depth),
+        );
+    }
+
+    // Calls visit with the instance and body index of each body the ray might hit, and the ray in
+    // that instance's space, returning how many boxes that took.
+    fn visit(&self, ray: Ray, mut visit: impl FnMut(usize, usize, Ray)) -> usize {
+        let mut mesh_tests = 0;
+        let instance_tests = self.bvh.visit(ray, |instance| {
+            let object_space_ray = ray.transform(self.instances[instance].inverse);
+            mesh_tests += self.instances[instance]
+                .mesh
+                .bvh
+                .visit(object_space_ray, |index| {
+                    visit(instance, index, object_space_ray)
+                });
+        });
+        instance_tests + mesh_tests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, color::Color, fuzzy_eq::FuzzyEq, intersection::Normal, matrix::Rotation,
+        sphere::Sphere, triangle::Triangle,
+    };
+
+    // A row of ten small spheres along the x axis.
+    fn mesh() -> Arc<Mesh> {
+        let bodies = (0..10)
+            .map(|i| {
+                Sphere::default()
+                    .with_transform(
+                        Matrix::translate(i as f64, 0.0, 0.0) * Matrix::scale(0.25, 0.25, 0.25),
+                    )
+                    .into()
+            })
+            .collect();
+        Arc::new(Mesh::new(bodies))
+    }
+
+    #[test]
+    fn intersecting_an_instance_places_its_bodies_in_the_world() {
+        let instances = Instances::new(vec![
+            Instance::new(mesh()).with_transform(Matrix::translate(0.0, 5.0, 0.0))
+        ]);
+        let r = Ray::new(Point::new(3.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = instances.intersect(r, 0);
+
+        assert_eq!(2, xs.len());
+        assert_fuzzy_eq!(4.75, xs[0].t);
+        assert_eq!(3, xs[0].body_id);
+        let p = r.position(xs[0].t);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), xs[0].body.normal_at(p));
+    }
+
+    #[test]
+    fn normals_worked_out_in_an_instance_are_turned_to_match_it() {
+        let triangle = Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        );
+        let mesh = Arc::new(Mesh::new(vec![triangle.into()]));
+        let instances = Instances::new(vec![Instance::new(mesh)
+            .with_transform(Matrix::rotate(Rotation::Y, std::f64::consts::FRAC_PI_2))]);
+        let r = Ray::new(Point::new(-5.0, 0.25, 0.0), Vector::new(1.0, 0.0, 0.0));
+
+        let xs = instances.intersect(r, 0);
+
+        let p = r.position(xs[0].t);
+        assert_fuzzy_eq!(Vector::new(-1.0, 0.0, 0.0), xs[0].normal.unwrap());
+        assert_fuzzy_eq!(xs[0].body.normal_at(p), xs[0].normal.unwrap());
+    }
+
+    #[test]
+    fn finding_where_a_body_in_an_instance_is_placed() {
+        let instances = Instances::new(vec![
+            Instance::new(mesh()),
+            Instance::new(mesh()).with_transform(Matrix::translate(0.0, 0.0, 2.0)),
+        ]);
+
+        let transform = instances.body_transform(13).unwrap();
+
+        assert_fuzzy_eq!(
+            Point::new(3.0, 0.0, 2.0),
+            transform * Point::new(0.0, 0.0, 0.0)
+        );
+        assert!(instances.body_transform(20).is_none());
+    }
+
+    #[test]
+    fn instances_share_a_mesh_but_number_their_bodies_apart() {
+        let mesh = mesh();
+        let instances = Instances::new(vec![
+            Instance::new(mesh.clone()),
+            Instance::new(mesh).with_transform(Matrix::translate(0.0, 0.0, 2.0)),
+        ]);
+        let r = Ray::new(Point::new(1.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let mut ids: Vec<usize> = instances
+            .intersect(r, 100)
+            .iter()
+            .map(|x| x.body_id)
+            .collect();
+        ids.sort_unstable();
+
+        assert_eq!(vec![101, 101, 111, 111], ids);
+    }
+
+    #[test]
+    fn moving_an_instance_rebuilds_the_top_level() {
+        let mut instances = Instances::new(vec![Instance::new(mesh())]);
+        let r = Ray::new(Point::new(3.0, 10.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(instances.intersect(r, 0).is_empty());
+
+        instances.set_transform(0, Matrix::translate(0.0, 10.0, 0.0));
+
+        assert_eq!(2, instances.intersect(r, 0).len());
+    }
+
+    #[test]
+    fn the_top_level_is_refitted_until_it_gets_too_slow() {
+        let row = |i: usize| Matrix::translate(0.0, 0.0, 3.0 * i as f64);
+        let mut instances = Instances::new(
+            (0..20)
+                .map(|i| Instance::new(mesh()).with_transform(row(i)))
+                .collect(),
+        );
+
+        // Nudging every instance a little keeps the same tree.
+        for i in 0..20 {
+            let y = if i % 2 == 0 { 0.1 } else { -0.1 };
+            instances.set_transform(i, Matrix::translate(0.0, y, 0.0) * row(i));
+        }
+        let degradation = instances.bvh.degradation();
+        assert!(degradation > 1.0 && degradation <= MAX_DEGRADATION);
+        let r = Ray::new(Point::new(4.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(40, instances.intersect(r, 0).len());
+
+        // Flinging them all over the place has it rebuilt instead.
+        for i in 0..20 {
+            let x = if i % 2 == 0 { 1000.0 } else { -1000.0 };
+            instances.set_transform(i, Matrix::translate(x, 0.0, 0.0) * row(i));
+        }
+        assert!(instances.bvh.degradation() <= MAX_DEGRADATION);
+    }
+
+    #[test]
+    fn a_ray_that_misses_every_instance_takes_one_test() {
+        let instances = Instances::new(vec![Instance::new(mesh())]);
+        let r = Ray::new(Point::new(0.0, 10.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(1, instances.bounds_tests(r));
+    }
+
+    fn first_hit_color(instances: &Instances, x: f64) -> Color {
+        let r = Ray::new(Point::new(x, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = instances.intersect(r, 0);
+        let hit = xs.iter().min_by(|a, b| a.t.total_cmp(&b.t)).unwrap();
+        hit.body.material().flat_color(&hit.body, r.position(hit.t))
+    }
+
+    #[test]
+    fn variation_gives_each_copy_its_own_colors_and_size() {
+        let copies: Vec<Instance> = (0..3)
+            .map(|i| Instance::new(mesh()).with_transform(Matrix::translate(0.0, 0.0, i as f64)))
+            .collect();
+        let variation = Variation::new(7)
+            .with_hue(0.1)
+            .with_brightness(0.2)
+            .with_scale(0.5);
+
+        let varied = variation.apply(copies.clone());
+
+        assert_eq!(3, varied.len());
+        for (plain, varied) in copies.iter().zip(&varied) {
+            assert!(varied.hue_shift.abs() <= 0.1 && varied.hue_shift != 0.0);
+            assert!((0.8..=1.2).contains(&varied.brightness));
+            let origin = Point::new(0.0, 0.0, 0.0);
+            assert_fuzzy_eq!(plain.transform * origin, varied.transform * origin);
+        }
+        assert!(!varied[0].transform.fuzzy_eq(varied[1].transform));
+        // The same seed varies them the same way every time.
+        let again = variation.apply(copies);
+        assert_fuzzy_eq!(varied[2].transform, again[2].transform);
+        assert_fuzzy_eq!(varied[2].hue_shift, again[2].hue_shift);
+    }
+
+    #[test]
+    fn copies_are_shaded_in_their_shifted_colors() {
+        let plain = Instances::new(vec![Instance::new(mesh())]);
+        let shifted = Instances::new(vec![Instance::new(mesh()).with_color_shift(1.0 / 3.0, 0.5)]);
+
+        let color = first_hit_color(&plain, 0.0);
+        assert_fuzzy_eq!(
+            color.with_hue_shift(1.0 / 3.0) * 0.5,
+            first_hit_color(&shifted, 0.0)
+        );
+    }
+}
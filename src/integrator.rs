@@ -0,0 +1,364 @@
+use std::f64::consts::PI;
+
+use crate::{
+    body::Body,
+    color::Color,
+    computed_intersection::ComputedIntersection,
+    intersection::{Intersectable, Normal},
+    material::{Material, Phong},
+    measure::Measured,
+    point::Point,
+    ray::Ray,
+    rng::Rng,
+    sample::{perpendiculars, SampleSurface},
+    vector::Vector,
+    world::World,
+};
+
+// The pieces of a stochastic integrator: rather than asking each light how bright a point is, the
+// light reaching the point is estimated by following random directions, and averaging many
+// estimates converges on the right answer. Every way of picking a direction comes with its pdf,
+// the chance of picking it per unit of solid angle, which is what each estimate is divided by.
+
+// A body that gives off light evenly over the outside of its surface. Points are picked evenly
+// over the body in object space, so the pdfs are only exact for bodies that are scaled evenly.
+// Lights added to a world (see World::with_area_light) light what's around them, but aren't seen
+// themselves; add the body to the world too for that.
+#[derive(Clone, Debug)]
+pub struct AreaLight {
+    pub body: Body,
+    pub radiance: Color,
+    // How many estimates of its light to average at each point shaded, when lighting a world.
+    pub samples: usize,
+}
+
+// A direction towards a point picked on a light.
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    pub direction: Vector,
+    pub distance: f64,
+    pub pdf: f64,
+}
+
+impl AreaLight {
+    pub fn new(body: impl Into<Body>, radiance: Color) -> Self {
+        Self {
+            body: body.into(),
+            radiance,
+            samples: 16,
+        }
+    }
+
+    pub fn with_samples(self, samples: usize) -> Self {
+        if samples == 0 {
+            panic!("an area light needs at least one sample")
+        }
+        Self { samples, ..self }
+    }
+
+    // Picks a point on the light to light the given point with.
+    pub fn sample(&self, from: Point, rng: &mut Rng) -> LightSample {
+        let (p, normal) = self.body.sample_surface(rng);
+        let distance = (p - from).magnitude();
+        let direction = (p - from).normalize();
+        LightSample {
+            direction,
+            distance,
+            pdf: self.solid_angle_pdf(distance, normal.dot(&-direction)),
+        }
+    }
+
+    // The pdf of `sample` picking the direction, and how far away the light is along it, if the
+    // direction points at the light at all.
+    pub fn pdf(&self, from: Point, direction: Vector) -> Option<(f64, f64)> {
+        let ray = Ray::new(from, direction);
+        let xs = self.body.intersect(ray);
+        let hit = xs.iter().find(|x| x.t > 0.0)?;
+        let normal = self.body.normal_at(ray.position(hit.t));
+        Some((self.solid_angle_pdf(hit.t, normal.dot(&-direction)), hit.t))
+    }
+
+    // Points are picked evenly by area, and a patch of the light covers less of the sky the
+    // further away and more side on it is. The back of the light gives off nothing, so it's never
+    // worth picking.
+    fn solid_angle_pdf(&self, distance: f64, cos_light: f64) -> f64 {
+        if cos_light <= 0.0 {
+            return 0.0;
+        }
+        distance * distance / (cos_light * self.body.surface_area())
+    }
+}
+
+// Phong as a physically based model: a Lambertian diffuse lobe plus a specular lobe around the
+// mirror direction, scaled so that neither gives off more light than falls on it. This is what
+// the estimates use in place of `Illuminated::lighting`.
+impl Phong {
+    // How much of the light arriving from `direction` leaves towards the eye, per unit of solid
+    // angle. `color` is the color of the surface at the point, which may come from a pattern.
+    pub fn brdf(&self, color: Color, normal: Vector, eye: Vector, direction: Vector) -> Color {
+        if direction.dot(&normal) <= 0.0 || eye.dot(&normal) <= 0.0 {
+            return Color::BLACK;
+        }
+        let diffuse = color * (self.diffuse / PI);
+        let cos_alpha = eye.reflect(normal).dot(&-direction).max(0.0);
+        let specular =
+            self.specular * (self.shininess + 2.0) / (2.0 * PI) * cos_alpha.powf(self.shininess);
+        diffuse + Color::WHITE * specular
+    }
+
+    // Picks a direction to look for light in. Rough surfaces favour the directions near the
+    // normal, which count for more, and shiny ones the directions near the mirror direction,
+    // where the highlight is; the shinier the surface, the tighter they're bunched around it.
+    pub fn sample_direction(&self, normal: Vector, eye: Vector, rng: &mut Rng) -> Vector {
+        if rng.next_f64() < self.specular_chance() {
+            // cos^n(alpha) around the mirror direction, the same shape as the specular lobe.
+            let cos_alpha = rng.next_f64().powf(1.0 / (self.shininess + 1.0));
+            around(mirror(normal, eye), cos_alpha, rng)
+        } else {
+            let cos_theta = rng.next_f64().sqrt();
+            around(normal, cos_theta, rng)
+        }
+    }
+
+    // The pdf of `sample_direction` picking the direction.
+    pub fn pdf(&self, normal: Vector, eye: Vector, direction: Vector) -> f64 {
+        let specular = self.specular_chance();
+        let diffuse_pdf = direction.dot(&normal).max(0.0) / PI;
+        let cos_alpha = direction.dot(&mirror(normal, eye)).max(0.0);
+        let specular_pdf = (self.shininess + 1.0) / (2.0 * PI) * cos_alpha.powf(self.shininess);
+        (1.0 - specular) * diffuse_pdf + specular * specular_pdf
+    }
+
+    // How often `sample_direction` picks from the specular lobe rather than the diffuse one.
+    fn specular_chance(&self) -> f64 {
+        let total = self.diffuse + self.specular;
+        if total <= 0.0 {
+            0.0
+        } else {
+            self.specular / total
+        }
+    }
+}
+
+// The direction light has to come from to be reflected straight into the eye.
+fn mirror(normal: Vector, eye: Vector) -> Vector {
+    -eye.reflect(normal)
+}
+
+// A direction at the given cosine from the axis, at a random angle around it.
+fn around(axis: Vector, cos: f64, rng: &mut Rng) -> Vector {
+    let (u, w) = perpendiculars(axis);
+    let sin = (1.0 - cos * cos).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.next_f64();
+    (u * (sin * phi.cos()) + w * (sin * phi.sin()) + axis * cos).normalize()
+}
+
+// How much of an estimate to keep when two strategies could have picked the same direction, with
+// the given pdfs. The strategy that was more likely to pick it gets most of the say, so neither
+// strategy's bad cases (tiny lights for picking by BRDF, sharp highlights for picking by light)
+// turn into noise. The weights for the two strategies add up to one.
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
+
+// An estimate of the light from the area light leaving the intersection towards the eye. It takes
+// one direction picked on the light and one picked by the BRDF and combines them with the power
+// heuristic, which does well for both rough surfaces and shiny ones.
+pub fn direct_light(
+    world: &World,
+    light: &AreaLight,
+    c: &ComputedIntersection,
+    rng: &mut Rng,
+) -> Color {
+    let body = &c.intersection.body;
+    let phong = match body.material() {
+        Material::Phong(p) => p,
+        Material::Medium(_) => return Color::BLACK,
+    };
+    let color = c.material().flat_color(body, c.position);
+    let visible = |direction: Vector, distance: f64| {
+        // The light may be one of the world's bodies too, so don't let it block itself.
+        !world.is_blocked(Ray::new(c.over_point, direction), distance - c.offset)
+    };
+
+    let mut total = Color::BLACK;
+
+    let sample = light.sample(c.over_point, rng);
+    let cos = sample.direction.dot(&c.normal);
+    if sample.pdf > 0.0 && cos > 0.0 && visible(sample.direction, sample.distance) {
+        let brdf_pdf = phong.pdf(c.normal, c.eye, sample.direction);
+        let f = phong.brdf(color, c.normal, c.eye, sample.direction);
+        total += f * light.radiance * (cos * power_heuristic(sample.pdf, brdf_pdf) / sample.pdf);
+    }
+
+    let direction = phong.sample_direction(c.normal, c.eye, rng);
+    let brdf_pdf = phong.pdf(c.normal, c.eye, direction);
+    let cos = direction.dot(&c.normal);
+    if brdf_pdf > 0.0 && cos > 0.0 {
+        if let Some((light_pdf, distance)) = light.pdf(c.over_point, direction) {
+            if light_pdf > 0.0 && visible(direction, distance) {
+                let f = phong.brdf(color, c.normal, c.eye, direction);
+                total +=
+                    f * light.radiance * (cos * power_heuristic(brdf_pdf, light_pdf) / brdf_pdf);
+            }
+        }
+    }
+
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, fuzzy_eq::FuzzyEq, intersection::HitRecord, matrix::Matrix, plane::Plane,
+        sample::random_direction, sphere::Sphere,
+    };
+
+    // A sphere of radius 1 hanging 4 above the origin.
+    fn light() -> AreaLight {
+        AreaLight::new(
+            Sphere::default().with_transform(Matrix::translate(0.0, 4.0, 0.0)),
+            Color::WHITE,
+        )
+    }
+
+    fn floor(phong: Phong) -> Body {
+        Plane::default().with_material(phong.into()).into()
+    }
+
+    #[test]
+    fn the_weights_of_two_strategies_add_up_to_one() {
+        for (a, b) in [(1.0, 1.0), (0.1, 5.0), (3.0, 0.0)] {
+            assert_fuzzy_eq!(1.0, power_heuristic(a, b) + power_heuristic(b, a));
+        }
+        assert_fuzzy_eq!(0.5, power_heuristic(2.0, 2.0));
+        assert_fuzzy_eq!(0.0, power_heuristic(0.0, 0.0));
+    }
+
+    #[test]
+    fn a_light_samples_pdf_matches_looking_the_direction_up() {
+        let l = light();
+        let from = Point::new(0.5, 0.0, 0.2);
+        let mut rng = Rng::new(1);
+
+        for _ in 0..100 {
+            let sample = l.sample(from, &mut rng);
+            if sample.pdf == 0.0 {
+                continue;
+            }
+            let (pdf, distance) = l.pdf(from, sample.direction).unwrap();
+            assert_fuzzy_eq!(sample.pdf, pdf);
+            assert_fuzzy_eq!(sample.distance, distance);
+        }
+    }
+
+    #[test]
+    fn a_direction_away_from_the_light_has_no_pdf() {
+        assert!(light()
+            .pdf(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, -1.0, 0.0))
+            .is_none());
+    }
+
+    #[test]
+    fn rough_brdf_samples_are_in_the_hemisphere_with_a_matching_pdf() {
+        let phong = Phong {
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let mut rng = Rng::new(2);
+
+        for _ in 0..100 {
+            let d = phong.sample_direction(normal, normal, &mut rng);
+            assert!(d.dot(&normal) >= 0.0);
+            assert_fuzzy_eq!(d.dot(&normal) / PI, phong.pdf(normal, normal, d));
+        }
+    }
+
+    #[test]
+    fn shiny_brdf_samples_bunch_up_around_the_mirror_direction() {
+        let phong = Phong {
+            diffuse: 0.0,
+            specular: 1.0,
+            shininess: 500.0,
+            ..Phong::default()
+        };
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let eye = Vector::new(1.0, 1.0, 0.0).normalize();
+        let mut rng = Rng::new(5);
+
+        for _ in 0..100 {
+            let d = phong.sample_direction(normal, eye, &mut rng);
+            assert!(d.dot(&Vector::new(-1.0, 1.0, 0.0).normalize()) > 0.95);
+        }
+    }
+
+    #[test]
+    fn the_brdf_pdf_covers_every_direction_once() {
+        let phong = Phong {
+            diffuse: 0.5,
+            specular: 0.5,
+            shininess: 10.0,
+            ..Phong::default()
+        };
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let eye = Vector::new(0.0, 1.0, -1.0).normalize();
+        let mut rng = Rng::new(6);
+
+        // Integrate the pdf over the sphere by picking directions evenly.
+        let n = 100_000;
+        let total: f64 = (0..n)
+            .map(|_| phong.pdf(normal, eye, random_direction(&mut rng)) * 4.0 * PI)
+            .sum();
+
+        assert!((total / n as f64 - 1.0).abs() < 0.02);
+    }
+
+    // Under a sphere of radiance 1 that covers a cone of half-angle theta, a white Lambertian
+    // surface gives off sin^2(theta) = (1/4)^2.
+    #[test]
+    fn direct_light_on_a_rough_floor_converges_on_the_exact_answer() {
+        let phong = Phong {
+            diffuse: 1.0,
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let body = floor(phong);
+        let world = World::new(vec![body.clone()], vec![]);
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = HitRecord::new(1.0, ray, body);
+        let c = hit.computed();
+        let mut rng = Rng::new(3);
+
+        let n = 20000;
+        let total = (0..n).fold(Color::BLACK, |total, _| {
+            total + direct_light(&world, &light(), &c, &mut rng)
+        });
+        let mean = total * (1.0 / n as f64);
+
+        assert!((mean[0] - 1.0 / 16.0).abs() < 0.002);
+    }
+
+    #[test]
+    fn a_blocked_light_gives_no_direct_light() {
+        let body = floor(Phong::default());
+        let blocker = Sphere::default().with_transform(Matrix::translate(0.0, 2.0, 0.0));
+        let world = World::new(vec![body.clone(), blocker.into()], vec![]);
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = HitRecord::new(1.0, ray, body);
+        let mut rng = Rng::new(4);
+
+        for _ in 0..100 {
+            assert_fuzzy_eq!(
+                Color::BLACK,
+                direct_light(&world, &light(), &hit.computed(), &mut rng)
+            );
+        }
+    }
+}
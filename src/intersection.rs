@@ -17,21 +17,86 @@ pub trait Normal {
     fn normal_at(&self, p: Point) -> Vector;
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Intersection {
+// How far along a ray a hit can be before the offset for rays leaving it starts to grow.
+const OFFSET_DISTANCE: f64 = 10.0;
+
+// Everything known about where a ray crosses a body, worked out while finding the crossing so it
+// doesn't have to be worked out again when shading.
+#[derive(Clone, Debug)]
+pub struct HitRecord {
     pub t: f64,
     pub ray: Ray,
     pub body: Body,
+    // Identifies which body in the world was hit, so that intersections at the same t always sort
+    // the same way. The world fills this in with the body's index.
+    pub body_id: usize,
+    // Where on the body's surface the hit is, for bodies that have surface coordinates. For
+    // triangles these are the barycentric coordinates of the hit, weighting the second and third
+    // points.
+    pub u: f64,
+    pub v: f64,
+    // The world space normal at the hit, if the body worked it out along the way. Otherwise it's
+    // asked for when the hit is shaded.
+    pub normal: Option<Vector>,
 }
 
-impl Intersection {
+impl HitRecord {
     pub fn new(t: f64, ray: Ray, body: Body) -> Self {
-        Self { t, ray, body }
+        Self {
+            t,
+            ray,
+            body,
+            body_id: 0,
+            u: 0.0,
+            v: 0.0,
+            normal: None,
+        }
+    }
+
+    pub fn with_body_id(self, body_id: usize) -> Self {
+        Self { body_id, ..self }
+    }
+
+    pub fn with_uv(self, u: f64, v: f64) -> Self {
+        Self { u, v, ..self }
+    }
+
+    pub fn with_normal(self, normal: Vector) -> Self {
+        Self {
+            normal: Some(normal),
+            ..self
+        }
+    }
+
+    // How far off the surface rays leaving the hit start, so rounding errors don't have them hit
+    // the same surface again. The errors grow with how far along the ray the hit is and with how
+    // much the body is scaled up, so the offset does too, instead of being EPISILON everywhere,
+    // which is too small for huge or distant surfaces and too big for tiny ones close up.
+    pub fn surface_offset(&self) -> f64 {
+        let distance = self.t.abs() * self.ray.direction.magnitude();
+        let m = self.body.transform();
+        let scale = (0..3)
+            .map(|col| {
+                (0..3)
+                    .map(|row| m[row][col] * m[row][col])
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .fold(0.0, f64::max);
+        EPISILON * scale.max(distance / OFFSET_DISTANCE)
     }
 
-    pub fn computed(&self) -> ComputedIntersection {
+    // Whether the ray goes into the body at the hit rather than out of it.
+    pub(crate) fn enters(&self) -> bool {
+        let normal = self
+            .normal
+            .unwrap_or_else(|| self.body.normal_at(self.ray.position(self.t)));
+        normal.dot(&self.ray.direction) < 0.0
+    }
+
+    pub fn computed(&self) -> ComputedIntersection<'_> {
         let position = self.ray.position(self.t);
-        let mut normal = self.body.normal_at(position);
+        let mut normal = self.normal.unwrap_or_else(|| self.body.normal_at(position));
         let eye = -self.ray.direction;
 
         let orientation = if normal.dot(&eye) < 0.0 {
@@ -41,30 +106,65 @@ impl Intersection {
             Orientation::Outside
         };
 
-        let over_point = position + normal * EPISILON;
+        let over_point = position + normal * self.surface_offset();
 
         ComputedIntersection::new(self, position, over_point, normal, eye, orientation)
     }
+
+    // Like computed, but also works out the refractive indices on either side of the surface
+    // from the bodies the ray is inside of at this point. The intersections must include this
+    // one.
+    pub fn computed_with(&self, xs: &Intersections) -> ComputedIntersection<'_> {
+        let mut containers: Vec<&Body> = Vec::new();
+        let mut n1 = 1.0;
+        let mut n2 = 1.0;
+        let refractive_index = |containers: &Vec<&Body>| {
+            containers.last().map_or(1.0, |body| {
+                body.material().refractive_index_for(self.ray.wavelength)
+            })
+        };
+
+        for x in xs.iter() {
+            let is_hit = x.t.fuzzy_eq(self.t) && x.body.fuzzy_eq(self.body.clone());
+            if is_hit {
+                n1 = refractive_index(&containers);
+            }
+
+            match containers
+                .iter()
+                .position(|body| body.fuzzy_eq(x.body.clone()))
+            {
+                Some(i) => {
+                    containers.remove(i);
+                }
+                None => containers.push(&x.body),
+            }
+
+            if is_hit {
+                n2 = refractive_index(&containers);
+                break;
+            }
+        }
+
+        self.computed().with_refractive_indices(n1, n2)
+    }
 }
 
-impl FuzzyEq for &Intersection {
+impl FuzzyEq for &HitRecord {
     fn fuzzy_eq(&self, other: Self) -> bool {
-        self.t.fuzzy_eq(other.t) && self.body.fuzzy_eq(other.body)
+        self.t.fuzzy_eq(other.t) && self.body.fuzzy_eq(other.body.clone())
     }
 }
 
 pub struct Intersections {
-    intersections: Vec<Intersection>,
+    intersections: Vec<HitRecord>,
 }
 
 impl Intersections {
-    pub fn hit(&self) -> Option<&Intersection> {
-        for intersection in self.intersections.iter() {
-            if intersection.t > 0.0 {
-                return Some(intersection);
-            }
-        }
-        None
+    pub fn hit(&self) -> Option<&HitRecord> {
+        self.intersections
+            .iter()
+            .find(|intersection| intersection.t > 0.0)
     }
 
     pub fn len(&self) -> usize {
@@ -74,17 +174,50 @@ impl Intersections {
     pub fn is_empty(&self) -> bool {
         self.intersections.is_empty()
     }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, HitRecord> {
+        self.intersections.iter()
+    }
+
+    // Merges runs of intersections that are within epsilon of each other into the first one of
+    // the run, which is the one with the lowest body id. Coincident surfaces otherwise flicker
+    // between bodies from one ray to the next as floating point error picks a different winner.
+    // Only hits going the same way, into bodies or out of them, are merged: where one body ends
+    // and another starts, like two boxes side by side, the ray leaves one and enters the other,
+    // and both have to be kept for the ray to know what it's inside of.
+    pub fn welded(self, epsilon: f64) -> Self {
+        let mut intersections: Vec<(HitRecord, bool)> =
+            Vec::with_capacity(self.intersections.len());
+        for x in self.intersections {
+            let enters = x.enters();
+            let merged = intersections
+                .iter()
+                .rev()
+                .take_while(|(kept, _)| x.t - kept.t <= epsilon)
+                .any(|&(_, kept_enters)| kept_enters == enters);
+            if !merged {
+                intersections.push((x, enters));
+            }
+        }
+        Self {
+            intersections: intersections.into_iter().map(|(x, _)| x).collect(),
+        }
+    }
 }
 
-impl From<Vec<Intersection>> for Intersections {
-    fn from(mut intersections: Vec<Intersection>) -> Self {
-        intersections.sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+impl From<Vec<HitRecord>> for Intersections {
+    fn from(mut intersections: Vec<HitRecord>) -> Self {
+        intersections.sort_by(|a, b| {
+            a.t.partial_cmp(&b.t)
+                .unwrap()
+                .then(a.body_id.cmp(&b.body_id))
+        });
         Self { intersections }
     }
 }
 
 impl Index<usize> for Intersections {
-    type Output = Intersection;
+    type Output = HitRecord;
 
     fn index(&self, index: usize) -> &Self::Output {
         &self.intersections[index]
@@ -98,7 +231,7 @@ impl IndexMut<usize> for Intersections {
 }
 
 impl IntoIterator for Intersections {
-    type Item = Intersection;
+    type Item = HitRecord;
 
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
@@ -109,7 +242,12 @@ impl IntoIterator for Intersections {
 
 #[cfg(test)]
 mod tests {
-    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, sphere::Sphere};
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::{
+        assert_fuzzy_eq, fuzzy_eq::FuzzyEq, material::Phong, matrix::Matrix, plane::Plane,
+        sphere::Sphere,
+    };
 
     use super::*;
 
@@ -118,20 +256,30 @@ mod tests {
         let b = Body::from(Sphere::default());
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
 
-        let i = Intersection::new(3.5, r, b);
+        let i = HitRecord::new(3.5, r, b.clone());
         assert_fuzzy_eq!(3.5, i.t);
         assert_fuzzy_eq!(b, i.body);
     }
 
+    #[test]
+    fn a_precomputed_normal_is_used_for_shading() {
+        let b = Body::from(Sphere::default());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let i = HitRecord::new(4.0, r, b).with_normal(Vector::new(0.0, 1.0, 0.0));
+
+        assert_fuzzy_eq!(Vector::new(0.0, 1.0, 0.0), i.computed().normal);
+    }
+
     #[test]
     fn the_hit_when_all_intersections_have_positive_t() {
         let b = Body::from(Sphere::default());
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
 
-        let i1 = Intersection::new(1.0, r, b);
-        let i2 = Intersection::new(2.0, r, b);
+        let i1 = HitRecord::new(1.0, r, b.clone());
+        let i2 = HitRecord::new(2.0, r, b);
 
-        let xs: Intersections = vec![i2, i1].into();
+        let xs: Intersections = vec![i2, i1.clone()].into();
 
         assert_fuzzy_eq!(Some(&i1), xs.hit());
     }
@@ -141,10 +289,10 @@ mod tests {
         let b = Body::from(Sphere::default());
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
 
-        let i1 = Intersection::new(-1.0, r, b);
-        let i2 = Intersection::new(1.0, r, b);
+        let i1 = HitRecord::new(-1.0, r, b.clone());
+        let i2 = HitRecord::new(1.0, r, b);
 
-        let xs: Intersections = vec![i2, i1].into();
+        let xs: Intersections = vec![i2.clone(), i1].into();
 
         assert_fuzzy_eq!(Some(&i2), xs.hit());
     }
@@ -154,12 +302,12 @@ mod tests {
         let b = Body::from(Sphere::default());
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
 
-        let i1 = Intersection::new(-2.0, r, b);
-        let i2 = Intersection::new(-1.0, r, b);
+        let i1 = HitRecord::new(-2.0, r, b.clone());
+        let i2 = HitRecord::new(-1.0, r, b);
 
         let xs: Intersections = vec![i2, i1].into();
 
-        let exp: Option<&Intersection> = None;
+        let exp: Option<&HitRecord> = None;
         assert_fuzzy_eq!(xs.hit(), exp);
     }
 
@@ -167,7 +315,7 @@ mod tests {
     fn precomputing_the_state_of_an_intersection() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let body = Body::from(Sphere::default());
-        let i = Intersection::new(4.0, r, body);
+        let i = HitRecord::new(4.0, r, body);
         let c = i.computed();
 
         assert_fuzzy_eq!(&i, c.intersection);
@@ -180,7 +328,7 @@ mod tests {
     fn the_hit_when_an_intersection_occurs_on_the_outside() {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let body = Body::from(Sphere::default());
-        let i = Intersection::new(4.0, r, body);
+        let i = HitRecord::new(4.0, r, body);
         let c = i.computed();
 
         assert_eq!(Orientation::Outside, c.orientation);
@@ -190,19 +338,197 @@ mod tests {
     fn the_hit_when_an_intersection_occurs_on_the_inside() {
         let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let body = Body::from(Sphere::default());
-        let i = Intersection::new(1.0, r, body);
+        let i = HitRecord::new(1.0, r, body);
         let c = i.computed();
 
         assert_eq!(Orientation::Inside, c.orientation);
         assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), c.normal);
     }
 
+    #[test]
+    fn intersections_at_the_same_t_are_ordered_by_body_id() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let a = Body::from(Sphere::default());
+        let b = Body::from(Sphere::default().with_transform(Matrix::scale(2.0, 2.0, 2.0)));
+
+        let xs: Intersections = vec![
+            HitRecord::new(2.0, r, b.clone()).with_body_id(1),
+            HitRecord::new(1.0, r, b).with_body_id(1),
+            HitRecord::new(1.0, r, a).with_body_id(0),
+        ]
+        .into();
+
+        assert_eq!(
+            vec![0, 1, 1],
+            xs.iter().map(|x| x.body_id).collect::<Vec<_>>()
+        );
+        assert_fuzzy_eq!(1.0, xs[1].t);
+    }
+
+    #[test]
+    fn welding_merges_coincident_intersections_into_the_lowest_body_id() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let a = Body::from(Sphere::default());
+        let b = Body::from(Sphere::default().with_transform(Matrix::scale(2.0, 2.0, 2.0)));
+
+        let xs: Intersections = vec![
+            HitRecord::new(1.0005, r, b).with_body_id(1),
+            HitRecord::new(1.0, r, a.clone()).with_body_id(0),
+            HitRecord::new(3.0, r, a).with_body_id(0),
+        ]
+        .into();
+        let welded = xs.welded(0.001);
+
+        assert_eq!(2, welded.len());
+        assert_eq!(0, welded[0].body_id);
+        assert_fuzzy_eq!(1.0, welded[0].t);
+        assert_fuzzy_eq!(3.0, welded[1].t);
+    }
+
+    #[test]
+    fn welding_keeps_a_ray_leaving_one_body_where_it_enters_another() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let front = Body::from(Sphere::default());
+        let back = Body::from(Sphere::default().with_transform(Matrix::translate(0.0, 0.0, 2.0)));
+
+        let xs: Intersections = vec![
+            HitRecord::new(4.0, r, front.clone()).with_body_id(0),
+            HitRecord::new(6.0, r, front).with_body_id(0),
+            HitRecord::new(6.0005, r, back.clone()).with_body_id(1),
+            HitRecord::new(8.0, r, back).with_body_id(1),
+        ]
+        .into();
+        let welded = xs.welded(0.001);
+
+        assert_eq!(4, welded.len());
+        assert_eq!(0, welded[1].body_id);
+        assert_eq!(1, welded[2].body_id);
+    }
+
+    fn glass_sphere(transform: Matrix<4>, refractive_index: f64) -> Body {
+        Sphere::default()
+            .with_transform(transform)
+            .with_material(
+                Phong {
+                    transparency: 1.0,
+                    refractive_index,
+                    ..Phong::default()
+                }
+                .into(),
+            )
+            .into()
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let a = glass_sphere(Matrix::scale(2.0, 2.0, 2.0), 1.5);
+        let b = glass_sphere(Matrix::translate(0.0, 0.0, -0.25), 2.0);
+        let c = glass_sphere(Matrix::translate(0.0, 0.0, 0.25), 2.5);
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Intersections = vec![
+            HitRecord::new(2.0, r, a.clone()),
+            HitRecord::new(2.75, r, b.clone()),
+            HitRecord::new(3.25, r, c.clone()),
+            HitRecord::new(4.75, r, b),
+            HitRecord::new(5.25, r, c),
+            HitRecord::new(6.0, r, a),
+        ]
+        .into();
+
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in expected.iter().enumerate() {
+            let c = xs[i].computed_with(&xs);
+            assert_fuzzy_eq!(*n1, c.n1);
+            assert_fuzzy_eq!(*n2, c.n2);
+        }
+    }
+
+    #[test]
+    fn the_under_point_is_offset_below_the_surface() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = glass_sphere(Matrix::translate(0.0, 0.0, 1.0), 1.5);
+        let xs: Intersections = vec![HitRecord::new(5.0, r, shape)].into();
+
+        let c = xs[0].computed_with(&xs);
+
+        assert!(c.under_point[2] > EPISILON / 2.0);
+        assert!(c.position[2] < c.under_point[2]);
+    }
+
+    #[test]
+    fn the_offset_grows_with_distance_and_scale() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let near = HitRecord::new(4.0, r, Sphere::default().into());
+        let far = HitRecord::new(4000.0, r, Sphere::default().into());
+        let big = HitRecord::new(
+            4.0,
+            r,
+            Sphere::default()
+                .with_transform(Matrix::scale(1.0, 500.0, 1.0))
+                .into(),
+        );
+
+        assert_fuzzy_eq!(EPISILON, near.surface_offset());
+        assert_fuzzy_eq!(400.0 * EPISILON, far.surface_offset());
+        assert_fuzzy_eq!(500.0 * EPISILON, big.surface_offset());
+        let c = far.computed();
+        assert_fuzzy_eq!(400.0 * EPISILON, c.offset);
+        assert_fuzzy_eq!(-400.0 * EPISILON, c.over_point[2] - c.position[2]);
+    }
+
+    #[test]
+    fn the_offset_shrinks_for_tiny_bodies_close_up() {
+        let r = Ray::new(Point::new(0.0, 0.0, -0.06), Vector::new(0.0, 0.0, 1.0));
+        let tiny = HitRecord::new(
+            0.05,
+            r,
+            Sphere::default()
+                .with_transform(Matrix::scale(0.01, 0.01, 0.01))
+                .into(),
+        );
+
+        // The offset is tiny too, so it's compared as a multiple of EPISILON.
+        assert_fuzzy_eq!(0.01, tiny.surface_offset() / EPISILON);
+    }
+
+    #[test]
+    fn the_offset_can_be_set_by_hand() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = HitRecord::new(4.0, r, Sphere::default().into());
+
+        let c = hit.computed().with_offset(0.01);
+
+        assert_fuzzy_eq!(Point::new(0.0, 0.0, -1.01), c.over_point);
+        assert_fuzzy_eq!(Point::new(0.0, 0.0, -0.99), c.under_point);
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let r = Ray::new(
+            Point::new(0.0, 1.0, -1.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let body = Body::from(Plane::default());
+        let i = HitRecord::new(2f64.sqrt(), r, body);
+
+        let c = i.computed();
+
+        assert_fuzzy_eq!(Vector::new(0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2), c.reflect);
+    }
+
     //   #[test]
     //   fn the_hit_should_offset_the_point() {
     //     let material = Material::default();
     //     let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
     //     let s1 = Sphere::new(material, Matrix::translation(0.0, 0.0, 1.0));
-    //     let i = Intersection::new(5.0, r, s1.into());
+    //     let i = HitRecord::new(5.0, r, s1.into());
     //     let c = i.get_computed();
     //     assert!(c.over_point.z < -EPSILON / 2.0);
     //     assert!(c.point.z > c.over_point.z);
@@ -1,18 +1,56 @@
 pub mod animator;
 pub mod aspect;
 pub mod body;
+pub mod bounds;
+pub mod bvh;
 pub mod camera;
 pub mod canvas;
+pub mod capsule;
 pub mod color;
 pub mod computed_intersection;
+pub mod csg;
+pub mod displacement;
+pub mod environment;
+#[cfg(test)]
+mod fixtures;
 pub mod fuzzy_eq;
+pub mod gallery;
+pub mod group;
+pub mod instance;
+pub mod integrator;
 pub mod intersection;
 pub mod light;
 pub mod material;
 pub mod matrix;
+pub mod measure;
+pub mod medium;
+pub mod noise;
+mod parallel;
+pub mod pattern;
+pub mod plane;
 pub mod point;
+pub mod prefabs;
+pub mod probe;
+pub mod profile;
 pub mod ray;
+pub mod render;
+pub mod rng;
+pub mod sample;
+pub mod sampler;
+pub mod shell;
+pub mod sky;
+pub mod skydome;
 pub mod sphere;
+pub mod starfield;
+pub mod stats;
+pub mod studio;
+pub mod transform;
+pub mod triangle;
 mod tuple;
 pub mod vector;
 pub mod world;
+
+// Builds the code in README.md as doctests, so that it's kept working.
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+pub struct ReadmeDoctests;
@@ -1,8 +1,83 @@
-use crate::{color::Color, point::Point};
+use std::f64::consts::PI;
 
+use crate::{
+    color::Color, fuzzy_eq::EPISILON, matrix::Matrix, point::Point, rng::Rng,
+    sample::perpendiculars, sampler::golden_spiral_point, sky::Sky, vector::Vector,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+    Point(PointLight),
+    Directional(DirectionalLight),
+}
+
+impl Light {
+    pub fn intensity(&self) -> Color {
+        match self {
+            Light::Point(l) => l.intensity,
+            Light::Directional(l) => l.intensity,
+        }
+    }
+
+    // The unit vector pointing from the given position towards the light.
+    pub fn direction_from(&self, position: Point) -> Vector {
+        match self {
+            Light::Point(l) => (l.position - position).normalize(),
+            Light::Directional(l) => -l.direction,
+        }
+    }
+
+    // How far the light is from the given position. Directional lights are infinitely far away,
+    // so anything in the way of one casts a shadow.
+    pub fn distance_from(&self, position: Point) -> f64 {
+        match self {
+            Light::Point(l) => (l.position - position).magnitude(),
+            Light::Directional(_) => f64::INFINITY,
+        }
+    }
+
+    // The light moved by the transform, e.g. along with the rest of a scene it's part of.
+    pub fn transformed(&self, transform: Matrix<4>) -> Self {
+        match self {
+            Light::Point(l) => PointLight {
+                position: transform * l.position,
+                ..*l
+            }
+            .into(),
+            Light::Directional(l) => {
+                DirectionalLight::new(transform * l.direction, l.intensity).into()
+            }
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(l: PointLight) -> Self {
+        Light::Point(l)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(l: DirectionalLight) -> Self {
+        Light::Directional(l)
+    }
+}
+
+// A light's intensity is the light falling on a surface facing it, the same whatever the distance,
+// so a white light of intensity 1 lights a white surface with a diffuse of 1 to exactly white.
+// Brighter lights are fine, but anything lit by them can go past white.
+#[derive(Clone, Copy, Debug)]
 pub struct PointLight {
     pub position: Point,
     pub intensity: Color,
+    // A cheap stand-in for an area light: shadow rays aim at `samples` points spread over a disk
+    // this wide facing the point being shaded, and the fraction that get through softens the edges
+    // of shadows. With a radius of zero the light is a point and shadows are hard.
+    pub radius: f64,
+    pub samples: usize,
+    // Shadows are only soft at their edges. If this is fewer than `samples`, that many rays are
+    // tried first, and the rest are only sent when some of those are blocked and some aren't.
+    pub min_samples: usize,
 }
 
 impl PointLight {
@@ -10,6 +85,252 @@ impl PointLight {
         PointLight {
             position,
             intensity,
+            radius: 0.0,
+            samples: 1,
+            min_samples: 1,
+        }
+    }
+
+    // Sets the number of samples for every point, so this goes before `with_min_samples`.
+    pub fn with_radius(self, radius: f64, samples: usize) -> Self {
+        if samples == 0 {
+            panic!("a light needs at least one shadow sample")
+        }
+        Self {
+            radius,
+            samples,
+            min_samples: samples,
+            ..self
+        }
+    }
+
+    pub fn with_min_samples(self, min_samples: usize) -> Self {
+        if min_samples == 0 || min_samples > self.samples {
+            panic!(
+                "the minimum shadow samples must be between 1 and {}",
+                self.samples
+            )
+        }
+        Self {
+            min_samples,
+            ..self
+        }
+    }
+
+    // Whether shadows from the light start with fewer rays and only add more at their edges.
+    pub fn is_adaptive(&self) -> bool {
+        self.min_samples < self.samples && self.radius > 0.0
+    }
+
+    // The points on the light to aim shadow rays from the given position at. The sample, in
+    // [0, 1), picks which way round the light they're turned, e.g. from a PixelSampler so that
+    // they're stratified along with the rest of a pixel's samples. Without one, they're turned by
+    // a different amount for every position.
+    pub fn shadow_targets(
+        &self,
+        from: Point,
+        sample: Option<f64>,
+    ) -> impl ExactSizeIterator<Item = Point> + '_ {
+        self.spread_targets(from, self.samples, sample)
+    }
+
+    // The points to try first when the light is adaptive. They cover the whole light, just more
+    // sparsely.
+    pub fn first_shadow_targets(
+        &self,
+        from: Point,
+        sample: Option<f64>,
+    ) -> impl ExactSizeIterator<Item = Point> + '_ {
+        self.spread_targets(from, self.min_samples, sample)
+    }
+
+    // The targets are worked out as they're used rather than collected, as this runs for every
+    // shading point.
+    fn spread_targets(
+        &self,
+        from: Point,
+        count: usize,
+        sample: Option<f64>,
+    ) -> impl ExactSizeIterator<Item = Point> + '_ {
+        // Without a size, the one target is the middle of the light.
+        let (count, radius) = if self.radius <= 0.0 || count == 1 {
+            (1, 0.0)
+        } else {
+            (count, self.radius)
+        };
+
+        // From the light's own position there's no way to face, so any will do.
+        let to_light = self.position - from;
+        let facing = if to_light.magnitude() > EPISILON {
+            to_light.normalize()
+        } else {
+            Vector::new(0.0, 1.0, 0.0)
+        };
+        let (u, v) = perpendiculars(facing);
+        // The points follow a sunflower spiral, which covers the disk evenly for any count. The
+        // spiral is turned so that neighbouring pixels don't all see the same points, which would
+        // show up as bands instead of noise.
+        let sample = sample.unwrap_or_else(|| {
+            let seed = from[0].to_bits()
+                ^ from[1].to_bits().rotate_left(21)
+                ^ from[2].to_bits().rotate_left(42);
+            Rng::new(seed).next_f64()
+        });
+        let (sin, cos) = (2.0 * PI * sample).sin_cos();
+        (0..count).map(move |i| {
+            let (x, y) = golden_spiral_point(i, count);
+            let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+            self.position + u * (radius * x) + v * (radius * y)
+        })
+    }
+}
+
+// A light so far away that its rays are parallel, like the sun. `direction` is the way the light
+// travels, so a light shining straight down has a direction of (0, -1, 0).
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionalLight {
+    pub direction: Vector,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector, intensity: Color) -> Self {
+        DirectionalLight {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+
+    // Sunlight coming from the sky's sun.
+    pub fn from_sky(sky: &Sky, intensity: Color) -> Self {
+        Self::new(-sky.sun_direction(), intensity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Rotation};
+
+    #[test]
+    fn a_point_light_is_a_finite_distance_away() {
+        let light: Light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).into();
+        let p = Point::new(0.0, 4.0, 0.0);
+
+        assert_fuzzy_eq!(Vector::new(0.0, 1.0, 0.0), light.direction_from(p));
+        assert_fuzzy_eq!(6.0, light.distance_from(p));
+    }
+
+    #[test]
+    fn a_point_light_without_a_radius_is_aimed_at_its_center() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).with_radius(0.0, 8);
+
+        let targets: Vec<_> = light
+            .shadow_targets(Point::new(1.0, 2.0, 3.0), None)
+            .collect();
+
+        assert_eq!(1, targets.len());
+        assert_fuzzy_eq!(light.position, targets[0]);
+    }
+
+    #[test]
+    fn shadow_targets_cover_a_disk_facing_the_position() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).with_radius(2.0, 16);
+
+        let targets: Vec<_> = light
+            .shadow_targets(Point::new(0.0, 0.0, 0.0), None)
+            .collect();
+
+        assert_eq!(16, targets.len());
+        for t in &targets {
+            assert_fuzzy_eq!(10.0, t[1]);
+            assert!((*t - light.position).magnitude() <= 2.0);
+        }
+        // Spread over the whole disk rather than bunched up in the middle.
+        assert!(targets
+            .iter()
+            .any(|t| (*t - light.position).magnitude() > 1.5));
+    }
+
+    #[test]
+    fn shadow_targets_from_the_lights_own_position_are_on_the_light() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).with_radius(2.0, 16);
+
+        for t in light.shadow_targets(light.position, None) {
+            assert!((t - light.position).magnitude() <= 2.0);
+        }
+    }
+
+    #[test]
+    fn adaptive_lights_try_fewer_targets_first() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE)
+            .with_radius(2.0, 32)
+            .with_min_samples(4);
+        let from = Point::new(0.0, 0.0, 0.0);
+
+        assert!(light.is_adaptive());
+        assert_eq!(4, light.first_shadow_targets(from, None).len());
+        assert_eq!(32, light.shadow_targets(from, None).len());
+        // Setting the radius again starts over without adaptive sampling.
+        assert!(!light.with_radius(2.0, 16).is_adaptive());
+    }
+
+    #[test]
+    #[should_panic(expected = "the minimum shadow samples must be between 1 and 8")]
+    fn the_minimum_shadow_samples_cannot_be_more_than_the_maximum() {
+        PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE)
+            .with_radius(1.0, 8)
+            .with_min_samples(9);
+    }
+
+    #[test]
+    fn transforming_a_point_light_keeps_its_radius() {
+        let light: Light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::WHITE)
+            .with_radius(0.5, 4)
+            .into();
+
+        match light.transformed(Matrix::translate(1.0, 2.0, 3.0)) {
+            Light::Point(l) => {
+                assert_fuzzy_eq!(Point::new(1.0, 2.0, 3.0), l.position);
+                assert_eq!((0.5, 4), (l.radius, l.samples));
+            }
+            Light::Directional(_) => panic!("a point light should stay a point light"),
         }
     }
+
+    #[test]
+    fn a_directional_light_is_the_same_from_everywhere() {
+        let light: Light = DirectionalLight::new(Vector::new(0.0, -2.0, 0.0), Color::WHITE).into();
+
+        for p in [Point::new(0.0, 0.0, 0.0), Point::new(100.0, -3.0, 7.0)] {
+            assert_fuzzy_eq!(Vector::new(0.0, 1.0, 0.0), light.direction_from(p));
+            assert_eq!(f64::INFINITY, light.distance_from(p));
+        }
+    }
+
+    #[test]
+    fn sunlight_shines_from_the_sun() {
+        let sky = Sky::default().with_sun_direction(Vector::new(1.0, 1.0, 0.0));
+        let light = DirectionalLight::from_sky(&sky, Color::WHITE);
+
+        assert_fuzzy_eq!(Vector::new(-1.0, -1.0, 0.0).normalize(), light.direction);
+    }
+
+    #[test]
+    fn transforming_a_directional_light_turns_it() {
+        let light: Light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE).into();
+
+        let moved = light.transformed(
+            Matrix::translate(5.0, 0.0, 0.0)
+                * Matrix::scale(1.0, 3.0, 1.0)
+                * Matrix::rotate(Rotation::Z, FRAC_PI_2),
+        );
+
+        assert_fuzzy_eq!(
+            Vector::new(-1.0, 0.0, 0.0),
+            moved.direction_from(Point::new(0.0, 0.0, 0.0))
+        );
+    }
 }
@@ -1,9 +1,13 @@
-use crate::{color::Color, fuzzy_eq::FuzzyEq, light::PointLight, point::Point, vector::Vector};
+use crate::{
+    body::Body, color::Color, fuzzy_eq::FuzzyEq, light::Light, medium::Medium, pattern::Pattern,
+    point::Point, vector::Vector,
+};
 
 pub trait Illuminated {
     fn lighting(
         &self,
-        light: &PointLight,
+        body: &Body,
+        light: &Light,
         position: Point,
         eye_vector: Vector,
         normal_vector: Vector,
@@ -11,24 +15,129 @@ pub trait Illuminated {
     ) -> Color;
 }
 
-#[derive(Clone, Copy, Debug)]
+// Phong is far bigger than Medium, as it carries its patterns and maps, but nearly every material
+// is a Phong, so boxing it would save no memory and only add an allocation to every copy.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Material {
     Phong(Phong),
+    Medium(Medium),
 }
 
 impl Illuminated for Material {
     fn lighting(
         &self,
-        light: &PointLight,
+        body: &Body,
+        light: &Light,
         position: Point,
         eye_vector: Vector,
         normal_vector: Vector,
         shadow_state: ShadowState,
     ) -> Color {
         match self {
-            Material::Phong(p) => {
-                p.lighting(light, position, eye_vector, normal_vector, shadow_state)
+            Material::Phong(p) => p.lighting(
+                body,
+                light,
+                position,
+                eye_vector,
+                normal_vector,
+                shadow_state,
+            ),
+            // Media don't have a surface to light; the world shades the inside of them instead.
+            Material::Medium(_) => Color::BLACK,
+        }
+    }
+}
+
+impl Material {
+    pub fn reflective(&self) -> f64 {
+        match self {
+            Material::Phong(p) => p.reflective,
+            Material::Medium(_) => 0.0,
+        }
+    }
+
+    pub fn transparency(&self) -> f64 {
+        match self {
+            Material::Phong(p) => p.transparency,
+            Material::Medium(_) => 1.0,
+        }
+    }
+
+    // The transparency at a point on the body, for materials whose transparency varies.
+    pub fn transparency_at(&self, body: &Body, position: Point) -> f64 {
+        match self {
+            Material::Phong(p) => p.transparency_at(body, position),
+            Material::Medium(_) => 1.0,
+        }
+    }
+
+    pub fn refractive_index(&self) -> f64 {
+        match self {
+            Material::Phong(p) => p.refractive_index,
+            Material::Medium(_) => 1.0,
+        }
+    }
+
+    pub fn max_bounces(&self) -> Option<u32> {
+        match self {
+            Material::Phong(p) => p.max_bounces,
+            Material::Medium(_) => None,
+        }
+    }
+
+    // The refractive index for light of the wavelength, in nanometers, or for white light.
+    pub fn refractive_index_for(&self, wavelength: Option<f64>) -> f64 {
+        match (self, wavelength) {
+            (Material::Phong(p), Some(wavelength)) => p.refractive_index_at(wavelength),
+            _ => self.refractive_index(),
+        }
+    }
+
+    // The same material in other colors, e.g. to tell copies of a mesh apart. Only the colors
+    // change, not how much light the surface reflects or lets through.
+    pub fn map_colors(self, f: impl Fn(Color) -> Color) -> Self {
+        match self {
+            Material::Phong(p) => Material::Phong(Phong {
+                color: f(p.color),
+                pattern: p.pattern.map(|pattern| pattern.map_colors(&f)),
+                ..p
+            }),
+            Material::Medium(m) => Material::Medium(Medium {
+                color: f(m.color),
+                ..m
+            }),
+        }
+    }
+
+    // The same material with its patterns filtered over the given width of surface, in world
+    // space, e.g. what a pixel covers where the camera sees it (see Pattern::with_footprint).
+    pub fn with_footprint(self, footprint: f64) -> Self {
+        match self {
+            Material::Phong(p) if footprint > 0.0 => {
+                let filtered = |pattern: Option<Pattern>| {
+                    pattern.map(|pattern| pattern.with_footprint(footprint))
+                };
+                Material::Phong(Phong {
+                    pattern: filtered(p.pattern),
+                    specular_map: filtered(p.specular_map),
+                    shininess_map: filtered(p.shininess_map),
+                    transparency_map: filtered(p.transparency_map),
+                    ..p
+                })
             }
+            material => material,
+        }
+    }
+
+    // The color of the surface at the point before any light falls on it.
+    pub fn flat_color(&self, body: &Body, position: Point) -> Color {
+        match self {
+            Material::Phong(p) => match &p.pattern {
+                Some(pattern) => pattern.color_at_body(body, position),
+                None => p.color,
+            },
+            Material::Medium(m) => m.color,
         }
     }
 }
@@ -39,6 +148,12 @@ impl From<Phong> for Material {
     }
 }
 
+impl From<Medium> for Material {
+    fn from(m: Medium) -> Self {
+        Material::Medium(m)
+    }
+}
+
 impl Default for Material {
     fn default() -> Self {
         Material::Phong(Phong::default())
@@ -47,10 +162,10 @@ impl Default for Material {
 
 impl FuzzyEq for Material {
     fn fuzzy_eq(&self, other: Self) -> bool {
-        match self {
-            Material::Phong(p) => match other {
-                Material::Phong(op) => p.fuzzy_eq(op),
-            },
+        match (self, other) {
+            (Material::Phong(p), Material::Phong(op)) => p.fuzzy_eq(op),
+            (Material::Medium(m), Material::Medium(om)) => m.fuzzy_eq(om),
+            _ => false,
         }
     }
 }
@@ -58,34 +173,123 @@ impl FuzzyEq for Material {
 pub enum ShadowState {
     Shadow,
     Clear,
+    // Some of the light gets through, as at the soft edge of a shadow from a light with a radius.
+    // Holds the fraction that does.
+    Partial(f64),
+}
+
+impl ShadowState {
+    // How much of the light reaches the point.
+    pub fn visible(&self) -> f64 {
+        match self {
+            ShadowState::Shadow => 0.0,
+            ShadowState::Clear => 1.0,
+            ShadowState::Partial(fraction) => *fraction,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+// All the light a surface sends back comes from the light falling on it: the color and each of the
+// fractions below are in [0, 1], and a surface stays no brighter than what lights it as long as
+// its reflectance is at most 1, which the defaults are. Going over is allowed, e.g. for a
+// stylized look, but stacking bright surfaces, mirrors and glass then blows out quickly. See
+// World::audit_lighting.
+#[derive(Clone, Debug)]
 pub struct Phong {
+    // The fraction of each channel of the light the surface reflects.
     pub color: Color,
+    // A stand-in for light bounced off the rest of the scene, as a fraction of the light's
+    // intensity. It isn't blocked by shadows, so it should stay small.
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    pub pattern: Option<Pattern>,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
+    // How much the refractive index changes with the wavelength of the light, which is what splits
+    // white light into a rainbow through a prism. It's the B of Cauchy's equation, n = A + B / λ²,
+    // in square micrometers, e.g. about 0.0042 for crown glass and 0.01 for flint glass, and only
+    // matters to spectral renders. The refractive index is the one for yellow light, at 587.6 nm.
+    pub dispersion: f64,
+    // How many reflections and refractions deep rays leaving the surface are followed, counting
+    // from the camera, in place of the world's limit (see World::color_at_depth). Glass usually
+    // needs more than other surfaces to look right, and a mirror that's only seen directly needs
+    // fewer, so this is a way to spend the time where it shows.
+    pub max_bounces: Option<u32>,
+    // Patterns that vary the specular, shininess and transparency over the surface, e.g. dulling
+    // the rusty patches of a metal or the worn patches of paint. The shade of the pattern at a
+    // point scales the value above there, so white leaves it as it is and black takes it away.
+    pub specular_map: Option<Pattern>,
+    pub shininess_map: Option<Pattern>,
+    pub transparency_map: Option<Pattern>,
+}
+
+// The value scaled by the map at the point, if there is one.
+fn mapped(value: f64, map: Option<&Pattern>, body: &Body, position: Point) -> f64 {
+    match map {
+        Some(map) => value * map.value_at_body(body, position),
+        None => value,
+    }
+}
+
+impl Phong {
+    pub fn specular_at(&self, body: &Body, position: Point) -> f64 {
+        mapped(self.specular, self.specular_map.as_ref(), body, position)
+    }
+
+    pub fn shininess_at(&self, body: &Body, position: Point) -> f64 {
+        mapped(self.shininess, self.shininess_map.as_ref(), body, position)
+    }
+
+    pub fn transparency_at(&self, body: &Body, position: Point) -> f64 {
+        mapped(
+            self.transparency,
+            self.transparency_map.as_ref(),
+            body,
+            position,
+        )
+    }
+
+    // The refractive index for light of the wavelength, in nanometers.
+    pub fn refractive_index_at(&self, wavelength: f64) -> f64 {
+        let inverse_square = |nanometers: f64| 1e6 / (nanometers * nanometers);
+        self.refractive_index
+            + self.dispersion * (inverse_square(wavelength) - inverse_square(587.6))
+    }
+
+    // The most light the surface can send back, as a fraction of the light falling on it, not
+    // counting ambient light. The highlight is only as bright as `specular` right where it
+    // peaks, and falls off faster the shinier the surface is, so all together it sends back
+    // 2 / (shininess + 2) of that, at most: a shiny highlight is bright but small.
+    pub fn reflectance(&self) -> f64 {
+        let highlight = self.specular * 2.0 / (self.shininess + 2.0);
+        self.diffuse + highlight + self.reflective + self.transparency
+    }
 }
 
 impl Illuminated for Phong {
     fn lighting(
         &self,
-        light: &PointLight,
+        body: &Body,
+        light: &Light,
         position: Point,
         eye_vector: Vector,
         normal_vector: Vector,
         shadow_state: ShadowState,
     ) -> Color {
-        let ambient_light: Color;
         let diffuse_light: Color;
         let specular_light: Color;
 
-        let effective_color = self.color * light.intensity;
-        let light_vector = (light.position - position).normalize();
+        let color = match &self.pattern {
+            Some(pattern) => pattern.color_at_body(body, position),
+            None => self.color,
+        };
+        let effective_color = color * light.intensity();
+        let light_vector = light.direction_from(position);
 
-        ambient_light = effective_color * self.ambient;
+        let ambient_light = effective_color * self.ambient;
 
         if let ShadowState::Shadow = shadow_state {
             return ambient_light;
@@ -94,8 +298,8 @@ impl Illuminated for Phong {
         let light_dot_normal = light_vector.dot(&normal_vector);
         if light_dot_normal < 0.0 {
             // Light is on the other side of the surface
-            diffuse_light = Color::new(0.0, 0.0, 0.0);
-            specular_light = Color::new(0.0, 0.0, 0.0);
+            diffuse_light = Color::BLACK;
+            specular_light = Color::BLACK;
         } else {
             // Light is on the side the surface is pointing to
             diffuse_light = effective_color * self.diffuse * light_dot_normal;
@@ -104,25 +308,34 @@ impl Illuminated for Phong {
             let reflect_dot_eye = reflect_vector.dot(&eye_vector);
 
             if reflect_dot_eye <= 0.0 {
-                specular_light = Color::new(0.0, 0.0, 0.0);
+                specular_light = Color::BLACK;
             } else {
-                let factor = reflect_dot_eye.powf(self.shininess);
-                specular_light = light.intensity * self.specular * factor;
+                let factor = reflect_dot_eye.powf(self.shininess_at(body, position));
+                specular_light = light.intensity() * self.specular_at(body, position) * factor;
             }
         }
 
-        ambient_light + diffuse_light + specular_light
+        ambient_light + (diffuse_light + specular_light) * shadow_state.visible()
     }
 }
 
 impl Default for Phong {
     fn default() -> Self {
         Phong {
-            color: Color::new(1.0, 1.0, 1.0),
+            color: Color::WHITE,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            pattern: None,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            dispersion: 0.0,
+            max_bounces: None,
+            specular_map: None,
+            shininess_map: None,
+            transparency_map: None,
         }
     }
 }
@@ -134,6 +347,15 @@ impl FuzzyEq for Phong {
             && self.diffuse.fuzzy_eq(other.diffuse)
             && self.specular.fuzzy_eq(other.specular)
             && self.shininess.fuzzy_eq(other.shininess)
+            && self.pattern.fuzzy_eq(other.pattern)
+            && self.reflective.fuzzy_eq(other.reflective)
+            && self.transparency.fuzzy_eq(other.transparency)
+            && self.refractive_index.fuzzy_eq(other.refractive_index)
+            && self.dispersion.fuzzy_eq(other.dispersion)
+            && self.max_bounces == other.max_bounces
+            && self.specular_map.fuzzy_eq(other.specular_map)
+            && self.shininess_map.fuzzy_eq(other.shininess_map)
+            && self.transparency_map.fuzzy_eq(other.transparency_map)
     }
 }
 
@@ -143,6 +365,9 @@ mod tests {
 
     use crate::assert_fuzzy_eq;
     use crate::fuzzy_eq::FuzzyEq;
+    use crate::light::PointLight;
+    use crate::matrix::Matrix;
+    use crate::sphere::Sphere;
 
     use super::*;
 
@@ -155,6 +380,9 @@ mod tests {
         assert_fuzzy_eq!(0.9, m.diffuse);
         assert_fuzzy_eq!(0.9, m.specular);
         assert_fuzzy_eq!(200.0, m.shininess);
+        assert_fuzzy_eq!(0.0, m.reflective);
+        assert_fuzzy_eq!(0.0, m.transparency);
+        assert_fuzzy_eq!(1.0, m.refractive_index);
     }
 
     #[test]
@@ -175,44 +403,47 @@ mod tests {
 
     #[test]
     fn lighting_with_the_eye_between_the_light_and_the_surface() {
+        let body = Body::from(Sphere::default());
         let m = Phong::default();
         let position = Point::new(0.0, 0.0, 0.0);
 
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)).into();
 
         assert_fuzzy_eq!(
             Color::new(1.9, 1.9, 1.9),
-            m.lighting(&light, position, eye, normal, ShadowState::Clear)
+            m.lighting(&body, &light, position, eye, normal, ShadowState::Clear)
         )
     }
 
     #[test]
     fn lighting_with_the_eye_between_the_light_and_the_surface_eye_offset_by_45_degrees() {
+        let body = Body::from(Sphere::default());
         let m = Phong::default();
         let position = Point::new(0.0, 0.0, 0.0);
 
         let eye = Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
         let normal = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)).into();
 
         assert_fuzzy_eq!(
             Color::new(1.0, 1.0, 1.0),
-            m.lighting(&light, position, eye, normal, ShadowState::Clear)
+            m.lighting(&body, &light, position, eye, normal, ShadowState::Clear)
         )
     }
 
     #[test]
     fn lighting_with_the_eye_opposite_surface_light_offset_by_45_degrees() {
+        let body = Body::from(Sphere::default());
         let m = Phong::default();
         let position = Point::new(0.0, 0.0, 0.0);
 
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)).into();
 
-        let actual_result = m.lighting(&light, position, eye, normal, ShadowState::Clear);
+        let actual_result = m.lighting(&body, &light, position, eye, normal, ShadowState::Clear);
 
         let expected_result = Color::new(0.7364, 0.7364, 0.7364);
 
@@ -221,14 +452,15 @@ mod tests {
 
     #[test]
     fn lighting_with_the_eye_in_path_of_the_reflection_vector() {
+        let body = Body::from(Sphere::default());
         let m = Phong::default();
         let position = Point::new(0.0, 0.0, 0.0);
 
         let eye = Vector::new(0.0, -FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
         let normal = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)).into();
 
-        let actual_result = m.lighting(&light, position, eye, normal, ShadowState::Clear);
+        let actual_result = m.lighting(&body, &light, position, eye, normal, ShadowState::Clear);
 
         let expected_result = Color::new(1.6364, 1.6364, 1.6364);
 
@@ -237,14 +469,15 @@ mod tests {
 
     #[test]
     fn lighting_with_light_behind_the_surface() {
+        let body = Body::from(Sphere::default());
         let m = Phong::default();
         let position = Point::new(0.0, 0.0, 0.0);
 
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0)).into();
 
-        let actual_result = m.lighting(&light, position, eye, normal, ShadowState::Clear);
+        let actual_result = m.lighting(&body, &light, position, eye, normal, ShadowState::Clear);
 
         let expected_result = Color::new(0.1, 0.1, 0.1);
 
@@ -253,6 +486,7 @@ mod tests {
 
     #[test]
     fn lighting_with_surface_in_shadow() {
+        let body = Body::from(Sphere::default());
         let m = Phong {
             ambient: 0.22,
             ..Phong::default()
@@ -261,11 +495,161 @@ mod tests {
 
         let eye = Vector::new(0.0, 0.0, -1.0);
         let normal = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)).into();
 
         assert_fuzzy_eq!(
             Color::new(0.22, 0.22, 0.22),
-            m.lighting(&light, position, eye, normal, ShadowState::Shadow)
+            m.lighting(&body, &light, position, eye, normal, ShadowState::Shadow)
         )
     }
+
+    #[test]
+    fn lighting_with_surface_in_partial_shadow() {
+        let body = Body::from(Sphere::default());
+        let m = Phong {
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let position = Point::new(0.0, 0.0, 0.0);
+
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)).into();
+
+        assert_fuzzy_eq!(
+            Color::new(0.55, 0.55, 0.55),
+            m.lighting(
+                &body,
+                &light,
+                position,
+                eye,
+                normal,
+                ShadowState::Partial(0.5)
+            )
+        )
+    }
+
+    #[test]
+    fn lighting_with_a_pattern_applied() {
+        let body = Body::from(Sphere::default());
+        let m = Phong {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            pattern: Some(Pattern::stripe(Color::WHITE, Color::BLACK)),
+            ..Phong::default()
+        };
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE).into();
+
+        let c1 = m.lighting(
+            &body,
+            &light,
+            Point::new(0.9, 0.0, 0.0),
+            eye,
+            normal,
+            ShadowState::Clear,
+        );
+        let c2 = m.lighting(
+            &body,
+            &light,
+            Point::new(1.1, 0.0, 0.0),
+            eye,
+            normal,
+            ShadowState::Clear,
+        );
+
+        assert_fuzzy_eq!(Color::WHITE, c1);
+        assert_fuzzy_eq!(Color::BLACK, c2);
+    }
+
+    #[test]
+    fn a_specular_map_varies_the_highlight() {
+        let body = Body::from(Sphere::default());
+        let m = Phong {
+            specular_map: Some(Pattern::stripe(Color::WHITE, Color::BLACK)),
+            ..Phong::default()
+        };
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        // With the light straight in front of each point, so the highlight is at its brightest.
+        let at = |x: f64| {
+            let light = PointLight::new(Point::new(x, 0.0, -10.0), Color::WHITE).into();
+            m.lighting(
+                &body,
+                &light,
+                Point::new(x, 0.0, 0.0),
+                eye,
+                normal,
+                ShadowState::Clear,
+            )
+        };
+
+        assert_fuzzy_eq!(Color::new(1.9, 1.9, 1.9), at(0.9));
+        assert_fuzzy_eq!(Color::new(1.0, 1.0, 1.0), at(1.1));
+    }
+
+    #[test]
+    fn maps_scale_the_values_by_their_shade() {
+        let body = Body::from(Sphere::default());
+        let grey = Pattern::stripe(Color::new(0.5, 0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        let m = Phong {
+            shininess: 100.0,
+            transparency: 0.8,
+            shininess_map: Some(grey.clone()),
+            transparency_map: Some(grey),
+            ..Phong::default()
+        };
+        let p = Point::new(0.0, 0.0, 0.0);
+
+        assert_fuzzy_eq!(50.0, m.shininess_at(&body, p));
+        assert_fuzzy_eq!(0.4, m.transparency_at(&body, p));
+        assert_fuzzy_eq!(0.9, m.specular_at(&body, p));
+    }
+
+    #[test]
+    fn a_pattern_follows_the_body_transform() {
+        let body = Body::from(Sphere::default().with_transform(Matrix::translate(1.0, 0.0, 0.0)));
+        let m = Phong {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            pattern: Some(Pattern::stripe(Color::WHITE, Color::BLACK)),
+            ..Phong::default()
+        };
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE).into();
+
+        let c = m.lighting(
+            &body,
+            &light,
+            Point::new(1.5, 0.0, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            ShadowState::Clear,
+        );
+
+        assert_fuzzy_eq!(Color::WHITE, c);
+    }
+
+    #[test]
+    fn dispersion_bends_blue_light_more_than_red() {
+        let glass: Material = Phong {
+            refractive_index: 1.5,
+            dispersion: 0.0042,
+            ..Phong::default()
+        }
+        .into();
+
+        assert_fuzzy_eq!(1.5, glass.refractive_index_for(None));
+        assert_fuzzy_eq!(1.5, glass.refractive_index_for(Some(587.6)));
+        let (blue, red) = (
+            glass.refractive_index_for(Some(450.0)),
+            glass.refractive_index_for(Some(650.0)),
+        );
+        assert!(blue > 1.5 && red < 1.5);
+        assert_fuzzy_eq!(0.0042 * (1e6 / 202500.0 - 1e6 / 422500.0), blue - red);
+    }
 }
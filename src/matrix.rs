@@ -1,11 +1,15 @@
 use std::{
     fmt::Debug,
-    ops::{Index, IndexMut, Mul},
+    ops::{Index, IndexMut, Mul, MulAssign},
 };
 
 use crate::{fuzzy_eq::FuzzyEq, tuple::Tuple};
 
+// Matrices are stored row-major, which matches how they're written in the book and lets a
+// matrix-tuple product be N independent dot products over contiguous rows. The layout is fixed
+// with repr(C) so the raw data can be handed to FFI or uploaded to GPU buffers.
 #[derive(Debug, Copy, Clone)]
+#[repr(C)]
 pub struct Matrix<const N: usize> {
     data: [[f64; N]; N],
 }
@@ -39,8 +43,10 @@ impl<const N: usize> IndexMut<usize> for Matrix<N> {
     }
 }
 
-impl<const N: usize> Mul for Matrix<N> {
-    type Output = Self;
+// Multiplication is implemented on references so hot loops and generic code don't have to copy
+// matrices around. The by-value forms forward to it.
+impl<const N: usize> Mul for &Matrix<N> {
+    type Output = Matrix<N>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         let mut res: Self::Output = Default::default();
@@ -57,20 +63,48 @@ impl<const N: usize> Mul for Matrix<N> {
     }
 }
 
-impl<const N: usize> Mul<f64> for Matrix<N> {
+impl<const N: usize> Mul for Matrix<N> {
     type Output = Self;
 
+    fn mul(self, rhs: Self) -> Self::Output {
+        Mul::mul(&self, &rhs)
+    }
+}
+
+impl<const N: usize> MulAssign<&Matrix<N>> for Matrix<N> {
+    fn mul_assign(&mut self, rhs: &Self) {
+        *self = &*self * rhs;
+    }
+}
+
+impl<const N: usize> MulAssign for Matrix<N> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self *= &rhs;
+    }
+}
+
+impl<const N: usize> Mul<f64> for &Matrix<N> {
+    type Output = Matrix<N>;
+
     fn mul(self, rhs: f64) -> Self::Output {
         let mut res: Self::Output = Default::default();
         for i in 0..N {
             for j in 0..N {
-                res[i][j] *= rhs;
+                res[i][j] = self[i][j] * rhs;
             }
         }
         res
     }
 }
 
+impl<const N: usize> Mul<f64> for Matrix<N> {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Mul::mul(&self, rhs)
+    }
+}
+
 impl<const N: usize> FuzzyEq for Matrix<N> {
     fn fuzzy_eq(&self, other: Self) -> bool {
         for i in 0..N {
@@ -102,6 +136,69 @@ impl<const N: usize> Matrix<N> {
         }
         res
     }
+
+    pub fn transpose_in_place(&mut self) {
+        for i in 0..N {
+            for j in i + 1..N {
+                let tmp = self[i][j];
+                self[i][j] = self[j][i];
+                self[j][i] = tmp;
+            }
+        }
+    }
+
+    // The elements in row-major order.
+    pub fn as_slice(&self) -> &[f64] {
+        self.data.as_flattened()
+    }
+
+    pub fn as_rows(&self) -> &[[f64; N]; N] {
+        &self.data
+    }
+
+    // The elements in column-major order, which is what e.g. OpenGL and most shading languages
+    // expect.
+    pub fn to_cols_array(&self) -> [[f64; N]; N] {
+        self.transpose().data
+    }
+
+    pub fn from_rows<T>(rows: [Tuple<T, N>; N]) -> Self {
+        let mut res = Self::default();
+        for (i, row) in rows.iter().enumerate() {
+            for j in 0..N {
+                res[i][j] = row[j];
+            }
+        }
+        res
+    }
+
+    pub fn from_cols<T>(cols: [Tuple<T, N>; N]) -> Self {
+        Self::from_rows(cols).transpose()
+    }
+
+    // The sum of the diagonal.
+    pub fn trace(&self) -> f64 {
+        (0..N).map(|i| self[i][i]).sum()
+    }
+
+    // The square root of the sum of the squares of every element.
+    pub fn frobenius_norm(&self) -> f64 {
+        self.data
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|x| x * x)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.fuzzy_eq(Self::identity())
+    }
+
+    // Orthogonal matrices (rotations and reflections) have their transpose as their inverse.
+    pub fn is_orthogonal(&self) -> bool {
+        (*self * self.transpose()).is_identity()
+    }
 }
 
 // The implementation for determinant is special for 2x2.
@@ -287,10 +384,10 @@ impl Matrix<4> {
 
 // We only have 4-element vectors and points so let's only implement matrix-tuple
 // multiplication between 4x4 matrices and 4 element tuples.
-impl<T> Mul<Tuple<T, 4>> for Matrix<4> {
+impl<T> Mul<&Tuple<T, 4>> for &Matrix<4> {
     type Output = Tuple<T, 4>;
 
-    fn mul(self, rhs: Tuple<T, 4>) -> Self::Output {
+    fn mul(self, rhs: &Tuple<T, 4>) -> Self::Output {
         let mut res = Self::Output::default();
         for i in 0..4 {
             let row = self[i];
@@ -302,6 +399,14 @@ impl<T> Mul<Tuple<T, 4>> for Matrix<4> {
     }
 }
 
+impl<T> Mul<Tuple<T, 4>> for Matrix<4> {
+    type Output = Tuple<T, 4>;
+
+    fn mul(self, rhs: Tuple<T, 4>) -> Self::Output {
+        Mul::mul(&self, &rhs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
@@ -825,4 +930,111 @@ mod tests {
         let p5 = transform * p;
         assert_fuzzy_eq!(Point::new(15.0, 0.0, 7.0), p5);
     }
+
+    #[test]
+    fn matrix_trace() {
+        let m = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+
+        assert_fuzzy_eq!(15.0, m.trace());
+        assert_fuzzy_eq!(4.0, Matrix::<4>::identity().trace());
+    }
+
+    #[test]
+    fn matrix_frobenius_norm() {
+        let m = Matrix::from([[1.0, -2.0], [2.0, 4.0]]);
+
+        assert_fuzzy_eq!(5.0, m.frobenius_norm());
+    }
+
+    #[test]
+    fn matrix_is_identity() {
+        assert!(Matrix::<4>::identity().is_identity());
+        assert!(
+            (Matrix::translate(1.0, 2.0, 3.0) * Matrix::translate(-1.0, -2.0, -3.0)).is_identity()
+        );
+        assert!(!Matrix::scale(2.0, 1.0, 1.0).is_identity());
+    }
+
+    #[test]
+    fn matrix_is_orthogonal() {
+        assert!(Matrix::rotate(Rotation::Y, FRAC_PI_4).is_orthogonal());
+        assert!(Matrix::scale(-1.0, 1.0, 1.0).is_orthogonal());
+        assert!(!Matrix::scale(2.0, 1.0, 1.0).is_orthogonal());
+        assert!(!Matrix::translate(1.0, 0.0, 0.0).is_orthogonal());
+    }
+
+    #[test]
+    fn matrix_from_rows_and_cols() {
+        let x = Vector::new(1.0, 2.0, 3.0);
+        let y = Vector::new(4.0, 5.0, 6.0);
+        let z = Vector::new(7.0, 8.0, 9.0);
+        let zero = Vector::new(0.0, 0.0, 0.0);
+
+        let rows = Matrix::from_rows([x, y, z, zero]);
+        let cols = Matrix::from_cols([x, y, z, zero]);
+
+        assert_fuzzy_eq!(
+            Matrix::from([
+                [1.0, 2.0, 3.0, 0.0],
+                [4.0, 5.0, 6.0, 0.0],
+                [7.0, 8.0, 9.0, 0.0],
+                [0.0, 0.0, 0.0, 0.0],
+            ]),
+            rows
+        );
+        assert_fuzzy_eq!(rows.transpose(), cols);
+    }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn multiplying_matrices_by_reference() {
+        let a = Matrix::rotate(Rotation::X, FRAC_PI_2);
+        let b = Matrix::translate(1.0, 2.0, 3.0);
+        let p = Point::new(1.0, 0.0, 1.0);
+
+        assert_fuzzy_eq!(a * b, &a * &b);
+        assert_fuzzy_eq!(a * p, &a * &p);
+    }
+
+    #[test]
+    fn multiplying_a_matrix_in_place() {
+        let a = Matrix::rotate(Rotation::X, FRAC_PI_2);
+        let b = Matrix::translate(1.0, 2.0, 3.0);
+
+        let mut m = a;
+        m *= &b;
+
+        assert_fuzzy_eq!(a * b, m);
+    }
+
+    #[test]
+    fn multiplying_a_matrix_by_a_scalar() {
+        let m = Matrix::from([[1.0, 2.0], [3.0, 4.0]]);
+
+        assert_fuzzy_eq!(Matrix::from([[2.0, 4.0], [6.0, 8.0]]), m * 2.0);
+    }
+
+    #[test]
+    fn transposing_a_matrix_in_place() {
+        let m = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+
+        let mut t = m;
+        t.transpose_in_place();
+
+        assert_fuzzy_eq!(m.transpose(), t);
+    }
+
+    #[test]
+    fn matrix_raw_layout() {
+        let m = Matrix::from([[1.0, 2.0], [3.0, 4.0]]);
+
+        assert_eq!(&[1.0, 2.0, 3.0, 4.0], m.as_slice());
+        assert_eq!(&[[1.0, 2.0], [3.0, 4.0]], m.as_rows());
+        assert_eq!([[1.0, 3.0], [2.0, 4.0]], m.to_cols_array());
+        assert_eq!(
+            std::mem::align_of::<f64>(),
+            std::mem::align_of::<Matrix<4>>()
+        );
+        assert_eq!(128, std::mem::size_of::<Matrix<4>>());
+    }
 }
@@ -0,0 +1,177 @@
+use std::f64::consts::PI;
+
+use crate::{
+    body::Body, capsule::Capsule, matrix::Matrix, plane::Plane, shell::Shell, sphere::Sphere,
+    transform::Transformable, triangle::Triangle,
+};
+
+// Surface area and volume of a body in world space, e.g. for spreading an emissive body's power
+// over its surface.
+pub trait Measured: Transformable {
+    // The measurements in object space, before the transform is applied.
+    fn object_surface_area(&self) -> f64;
+    fn object_volume(&self) -> f64;
+
+    fn volume(&self) -> f64 {
+        self.object_volume() * volume_scale(self.transform())
+    }
+
+    // Exact for transforms that scale evenly in every direction. A squashed or stretched body is
+    // approximated as if it were scaled evenly to the same volume.
+    fn surface_area(&self) -> f64 {
+        self.object_surface_area() * volume_scale(self.transform()).powf(2.0 / 3.0)
+    }
+}
+
+// How much the transform grows volumes by. Transforms have 0 0 0 1 on the bottom row, so this is
+// the same as the determinant of the upper 3x3.
+fn volume_scale(transform: Matrix<4>) -> f64 {
+    transform.determinant().abs()
+}
+
+impl Measured for Sphere {
+    fn object_surface_area(&self) -> f64 {
+        4.0 * PI
+    }
+
+    fn object_volume(&self) -> f64 {
+        4.0 / 3.0 * PI
+    }
+}
+
+impl Measured for Shell {
+    // Both the outside and the inside wall.
+    fn object_surface_area(&self) -> f64 {
+        4.0 * PI * (1.0 + self.inner_radius().powi(2))
+    }
+
+    fn object_volume(&self) -> f64 {
+        4.0 / 3.0 * PI * (1.0 - self.inner_radius().powi(3))
+    }
+}
+
+impl Measured for Capsule {
+    fn object_surface_area(&self) -> f64 {
+        let (a, b) = self.segment();
+        let r = self.radius();
+        2.0 * PI * r * (b - a).magnitude() + 4.0 * PI * r * r
+    }
+
+    fn object_volume(&self) -> f64 {
+        let (a, b) = self.segment();
+        let r = self.radius();
+        PI * r * r * (b - a).magnitude() + 4.0 / 3.0 * PI * r.powi(3)
+    }
+}
+
+impl Measured for Plane {
+    fn object_surface_area(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    fn object_volume(&self) -> f64 {
+        0.0
+    }
+}
+
+impl Measured for Triangle {
+    fn object_surface_area(&self) -> f64 {
+        let (p1, p2, p3) = self.points();
+        (p2 - p1).cross(&(p3 - p1)).magnitude() / 2.0
+    }
+
+    fn object_volume(&self) -> f64 {
+        0.0
+    }
+}
+
+impl Measured for Body {
+    fn object_surface_area(&self) -> f64 {
+        match self {
+            Body::Sphere(s) => s.object_surface_area(),
+            Body::Shell(s) => s.object_surface_area(),
+            Body::Capsule(c) => c.object_surface_area(),
+            Body::Plane(p) => p.object_surface_area(),
+            Body::Triangle(t) => t.object_surface_area(),
+        }
+    }
+
+    fn object_volume(&self) -> f64 {
+        match self {
+            Body::Sphere(s) => s.object_volume(),
+            Body::Shell(s) => s.object_volume(),
+            Body::Capsule(c) => c.object_volume(),
+            Body::Plane(p) => p.object_volume(),
+            Body::Triangle(t) => t.object_volume(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Rotation, point::Point};
+
+    #[test]
+    fn measuring_a_unit_sphere() {
+        let s = Sphere::default();
+
+        assert_fuzzy_eq!(4.0 * PI, s.surface_area());
+        assert_fuzzy_eq!(4.0 / 3.0 * PI, s.volume());
+    }
+
+    #[test]
+    fn measuring_a_scaled_sphere() {
+        let s = Sphere::default().with_transform(
+            Matrix::translate(5.0, 0.0, 0.0)
+                * Matrix::rotate(Rotation::Y, 1.0)
+                * Matrix::scale(2.0, 2.0, 2.0),
+        );
+
+        assert_fuzzy_eq!(16.0 * PI, s.surface_area());
+        assert_fuzzy_eq!(32.0 / 3.0 * PI, s.volume());
+    }
+
+    #[test]
+    fn a_squashed_sphere_keeps_its_exact_volume() {
+        let s = Sphere::default().with_transform(Matrix::scale(2.0, 0.5, 1.0));
+
+        assert_fuzzy_eq!(4.0 / 3.0 * PI, s.volume());
+    }
+
+    #[test]
+    fn measuring_a_shell() {
+        let s = Shell::default().with_inner_radius(0.5);
+
+        assert_fuzzy_eq!(5.0 * PI, s.surface_area());
+        assert_fuzzy_eq!(7.0 / 6.0 * PI, s.volume());
+    }
+
+    #[test]
+    fn measuring_a_capsule() {
+        let c = Capsule::new(Point::new(0.0, 0.0, 0.0), Point::new(0.0, 2.0, 0.0), 1.0);
+
+        assert_fuzzy_eq!(8.0 * PI, c.surface_area());
+        assert_fuzzy_eq!(10.0 / 3.0 * PI, c.volume());
+    }
+
+    #[test]
+    fn a_plane_goes_on_forever_but_has_no_volume() {
+        let p = Body::from(Plane::default());
+
+        assert!(p.surface_area().is_infinite());
+        assert_fuzzy_eq!(0.0, p.volume());
+    }
+
+    #[test]
+    fn measuring_a_triangle() {
+        let t = Triangle::new(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(2.0, 0.0, 0.0),
+            Point::new(0.0, 3.0, 0.0),
+        );
+
+        assert_fuzzy_eq!(3.0, t.surface_area());
+        assert_fuzzy_eq!(0.0, t.volume());
+    }
+}
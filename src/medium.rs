@@ -0,0 +1,63 @@
+use crate::{color::Color, fuzzy_eq::FuzzyEq};
+
+mod index_field;
+
+pub use index_field::*;
+
+// A homogeneous participating medium like fog, smoke or dusty air, filling a closed body. Light
+// passing through it is attenuated, and light from the scene's light is scattered back towards
+// the eye, so shadows cast into the medium show up as shafts of light.
+//
+// `density` is the fraction of light absorbed or scattered per unit of distance, and `color` is
+// the color of the scattered light. A thick enough medium lit from everywhere converges to `color`
+// times the light's intensity. `steps` is how many samples are taken along each ray through the
+// medium to find its lit and shadowed parts.
+#[derive(Clone, Copy, Debug)]
+pub struct Medium {
+    pub color: Color,
+    pub density: f64,
+    pub steps: usize,
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            density: 0.5,
+            steps: 16,
+        }
+    }
+}
+
+impl Medium {
+    // The fraction of light that makes it through the given distance of the medium.
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        (-self.density * distance).exp()
+    }
+}
+
+impl FuzzyEq for Medium {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.color.fuzzy_eq(other.color)
+            && self.density.fuzzy_eq(other.density)
+            && self.steps == other.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_fuzzy_eq;
+
+    #[test]
+    fn transmittance_falls_off_exponentially() {
+        let m = Medium {
+            density: 2.0,
+            ..Medium::default()
+        };
+
+        assert_fuzzy_eq!(1.0, m.transmittance(0.0));
+        assert_fuzzy_eq!((-1.0f64).exp(), m.transmittance(0.5));
+        assert_fuzzy_eq!((-2.0f64).exp(), m.transmittance(1.0));
+    }
+}
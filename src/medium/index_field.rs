@@ -0,0 +1,119 @@
+use std::{fmt, sync::Arc};
+
+use crate::{bounds::Bounds, point::Point, vector::Vector};
+
+// A stretch of space where the refractive index changes smoothly from place to place, like air
+// over hot tarmac, or the space around something heavy enough to bend light. Rays through it
+// don't go straight but curve towards where the index is higher, which makes heat haze shimmer,
+// mirages and gravitational lenses. It's experimental: rays are followed through the field in
+// steps `step` long, each one bent a little, which is slow, and after `max_distance` they carry on
+// straight. The index is given by a function of where in the world a point is, and only matters
+// inside of `bounds`; outside, rays go straight as usual.
+#[derive(Clone)]
+pub struct IndexField {
+    pub bounds: Bounds,
+    pub step: f64,
+    pub max_distance: f64,
+    index: Arc<dyn Fn(Point) -> f64 + Send + Sync>,
+}
+
+impl IndexField {
+    pub fn new(bounds: Bounds, index: impl Fn(Point) -> f64 + Send + Sync + 'static) -> Self {
+        if !bounds.is_finite() {
+            panic!("an index field must have finite bounds")
+        }
+        Self {
+            bounds,
+            step: 0.05,
+            max_distance: 100.0,
+            index: Arc::new(index),
+        }
+    }
+
+    // Shorter steps follow the curves more closely, and take longer.
+    pub fn with_step(self, step: f64) -> Self {
+        if step <= 0.0 {
+            panic!("the step must be greater than zero")
+        }
+        Self { step, ..self }
+    }
+
+    pub fn with_max_distance(self, max_distance: f64) -> Self {
+        Self {
+            max_distance,
+            ..self
+        }
+    }
+
+    pub fn index_at(&self, p: Point) -> f64 {
+        (self.index)(p)
+    }
+
+    // Which way the index goes up fastest, and how fast, from central differences a tenth of a
+    // step either side of the point.
+    pub fn gradient_at(&self, p: Point) -> Vector {
+        let h = self.step / 10.0;
+        let along =
+            |axis: Vector| (self.index_at(p + axis * h) - self.index_at(p + axis * -h)) / (2.0 * h);
+        Vector::new(
+            along(Vector::new(1.0, 0.0, 0.0)),
+            along(Vector::new(0.0, 1.0, 0.0)),
+            along(Vector::new(0.0, 0.0, 1.0)),
+        )
+    }
+
+    // One step along a ray heading in the direction from the point: where it gets to and which way
+    // it's heading by then. By the ray equation of geometric optics, d(n t)/ds = ∇n, the direction
+    // turns towards the part of the gradient across it, by more the lower the index.
+    pub fn step_from(&self, p: Point, direction: Vector) -> (Point, Vector) {
+        let gradient = self.gradient_at(p);
+        let across = gradient - direction * gradient.dot(&direction);
+        let turned = direction + across * (self.step / self.index_at(p));
+        (p + direction * self.step, turned.normalize())
+    }
+}
+
+impl fmt::Debug for IndexField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexField")
+            .field("bounds", &self.bounds)
+            .field("step", &self.step)
+            .field("max_distance", &self.max_distance)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    fn unit_box() -> Bounds {
+        Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn rays_go_straight_through_an_even_index() {
+        let field = IndexField::new(unit_box(), |_| 1.3);
+        let direction = Vector::new(0.0, 0.6, 0.8);
+
+        let (p, d) = field.step_from(Point::new(0.0, 0.0, 0.0), direction);
+
+        assert_fuzzy_eq!(Point::new(0.0, 0.03, 0.04), p);
+        assert_fuzzy_eq!(direction, d);
+    }
+
+    #[test]
+    fn rays_bend_towards_a_higher_index() {
+        // Denser air below, like the air over a cold sea, which bends light down.
+        let field = IndexField::new(unit_box(), |p| 1.0 - 0.1 * p[1]).with_step(0.1);
+
+        assert_fuzzy_eq!(
+            Vector::new(0.0, -0.1, 0.0),
+            field.gradient_at(Point::new(0.3, 0.2, 0.0))
+        );
+        let (_, d) = field.step_from(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(d[1] < 0.0);
+        assert_fuzzy_eq!(1.0, d.magnitude());
+    }
+}
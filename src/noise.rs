@@ -0,0 +1,209 @@
+use crate::{point::Point, rng::Rng};
+
+// Perlin's "improved noise", with a fixed permutation so renders are reproducible.
+//
+// Noise only depends on the point and the seed, never on anything that changes from frame to
+// frame, so a texture sampled in an object's own space stays put on it while it moves instead of
+// crawling ("boiling") across it. Copies of an object that should look different get different
+// seeds, e.g. from `seed_for` with their names, rather than being told apart by where they are.
+const PERMUTATION: [u8; 256] = [
+    228, 55, 232, 74, 105, 14, 5, 239, 40, 195, 196, 43, 152, 109, 210, 208, 84, 121, 32, 20, 159,
+    116, 28, 211, 71, 163, 39, 143, 222, 164, 237, 125, 214, 80, 31, 138, 213, 201, 141, 131, 144,
+    203, 182, 82, 12, 185, 177, 126, 118, 79, 100, 0, 124, 155, 231, 37, 204, 235, 137, 26, 243,
+    142, 44, 192, 45, 10, 104, 187, 21, 29, 92, 160, 146, 90, 115, 247, 253, 225, 119, 166, 226,
+    224, 240, 86, 178, 3, 17, 98, 218, 220, 36, 251, 50, 189, 77, 62, 78, 15, 168, 151, 246, 57,
+    13, 101, 52, 180, 53, 167, 25, 99, 242, 91, 47, 6, 134, 238, 249, 171, 56, 72, 22, 206, 216,
+    139, 186, 250, 64, 157, 34, 179, 199, 202, 58, 95, 88, 65, 107, 75, 150, 108, 114, 19, 1, 255,
+    70, 120, 209, 140, 61, 172, 194, 73, 69, 188, 128, 67, 161, 16, 241, 154, 184, 190, 156, 173,
+    244, 200, 132, 169, 97, 106, 85, 76, 66, 96, 117, 153, 170, 7, 8, 230, 42, 11, 46, 127, 198,
+    130, 35, 175, 136, 149, 30, 110, 81, 49, 38, 145, 212, 245, 122, 59, 111, 68, 217, 103, 197,
+    158, 33, 27, 229, 248, 254, 236, 51, 205, 48, 215, 4, 112, 41, 165, 135, 23, 2, 148, 102, 129,
+    221, 60, 123, 18, 233, 162, 93, 83, 234, 147, 183, 252, 191, 87, 207, 193, 219, 9, 176, 54,
+    174, 63, 89, 113, 223, 24, 94, 227, 133, 181,
+];
+
+fn permute(i: usize) -> usize {
+    PERMUTATION[i & 255] as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// Picks one of 12 gradient directions from the low bits of the hash and dots it with (x, y, z).
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+// A seed for noise that stays the same from run to run for the same name, so a named object keeps
+// its look however the scene around it changes. This is FNV-1a.
+pub fn seed_for(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// Seeds pick which gradients the lattice points get by shifting where in the permutation they're
+// looked up. Seed 0 is the plain noise.
+fn lattice_offset(seed: u64) -> (usize, usize, usize) {
+    if seed == 0 {
+        return (0, 0, 0);
+    }
+    let bits = Rng::new(seed).next_u64() as usize;
+    (bits & 255, (bits >> 8) & 255, (bits >> 16) & 255)
+}
+
+// Smooth noise in roughly [-1, 1]. It is zero at every integer lattice point.
+pub fn noise(p: Point) -> f64 {
+    seeded_noise(p, 0)
+}
+
+// Noise with the same character as `noise`, but a different one for each seed.
+pub fn seeded_noise(p: Point, seed: u64) -> f64 {
+    let (x, y, z) = (p[0], p[1], p[2]);
+    let (xf, yf, zf) = (x.floor(), y.floor(), z.floor());
+    let (ox, oy, oz) = lattice_offset(seed);
+    let (xi, yi, zi) = (
+        (xf.rem_euclid(256.0) as usize + ox) & 255,
+        (yf.rem_euclid(256.0) as usize + oy) & 255,
+        (zf.rem_euclid(256.0) as usize + oz) & 255,
+    );
+    let (x, y, z) = (x - xf, y - yf, z - zf);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = permute(xi) + yi;
+    let aa = permute(a) + zi;
+    let ab = permute(a + 1) + zi;
+    let b = permute(xi + 1) + yi;
+    let ba = permute(b) + zi;
+    let bb = permute(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(permute(aa), x, y, z),
+                grad(permute(ba), x - 1.0, y, z),
+            ),
+            lerp(
+                u,
+                grad(permute(ab), x, y - 1.0, z),
+                grad(permute(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(permute(aa + 1), x, y, z - 1.0),
+                grad(permute(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(permute(ab + 1), x, y - 1.0, z - 1.0),
+                grad(permute(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+// Sums the absolute value of successively finer, fainter octaves of noise. The result is always
+// non-negative and less than 2.
+pub fn turbulence(p: Point, octaves: u32) -> f64 {
+    seeded_turbulence(p, octaves, 0)
+}
+
+pub fn seeded_turbulence(p: Point, octaves: u32, seed: u64) -> f64 {
+    let mut sum = 0.0;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        sum += seeded_noise(
+            Point::new(p[0] * frequency, p[1] * frequency, p[2] * frequency),
+            seed,
+        )
+        .abs()
+            / frequency;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn noise_is_zero_on_the_lattice() {
+        assert_fuzzy_eq!(0.0, noise(Point::new(0.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(0.0, noise(Point::new(3.0, -7.0, 12.0)));
+    }
+
+    #[test]
+    fn noise_is_deterministic() {
+        let p = Point::new(1.3, 2.7, -0.4);
+
+        assert_eq!(noise(p), noise(p));
+    }
+
+    #[test]
+    fn noise_varies_between_lattice_points() {
+        let values: Vec<f64> = (0..10)
+            .map(|i| noise(Point::new(0.5 + i as f64 * 0.37, 0.25, 0.75)))
+            .collect();
+
+        assert!(values.iter().any(|v| v.abs() > 0.01));
+        assert!(values.iter().all(|v| v.abs() <= 1.0));
+    }
+
+    #[test]
+    fn turbulence_is_non_negative_and_bounded() {
+        for i in 0..100 {
+            let p = Point::new(i as f64 * 0.173, i as f64 * -0.311, i as f64 * 0.057);
+            let t = turbulence(p, 4);
+            assert!((0.0..2.0).contains(&t));
+        }
+    }
+
+    #[test]
+    fn zero_octaves_of_turbulence_is_nothing() {
+        assert_fuzzy_eq!(0.0, turbulence(Point::new(0.5, 0.5, 0.5), 0));
+    }
+
+    #[test]
+    fn seeds_give_different_noise() {
+        let points: Vec<Point> = (0..10)
+            .map(|i| Point::new(0.5 + i as f64 * 0.37, 0.25, 0.75))
+            .collect();
+
+        assert!(points.iter().all(|&p| noise(p) == seeded_noise(p, 0)));
+        assert!(points.iter().any(|&p| noise(p) != seeded_noise(p, 1)));
+        assert!(points
+            .iter()
+            .all(|&p| seeded_noise(p, 5) == seeded_noise(p, 5)));
+        assert_fuzzy_eq!(0.0, seeded_noise(Point::new(3.0, -7.0, 12.0), 5));
+    }
+
+    #[test]
+    fn names_give_lasting_seeds() {
+        assert_eq!(seed_for("pillar"), seed_for("pillar"));
+        assert_ne!(seed_for("pillar"), seed_for("pillar 2"));
+        // FNV-1a's value for "a", so the seed can't quietly change between versions.
+        assert_eq!(0xaf63_dc4c_8601_ec8c, seed_for("a"));
+    }
+}
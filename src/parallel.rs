@@ -0,0 +1,92 @@
+// Where the renderer does work in parallel, it goes through these. With the `parallel` feature (on
+// by default) they hand the work to rayon. Without it they're plain loops on the calling thread,
+// so the crate builds for targets without threads and renders come out one pixel at a time, in
+// order, which helps when hunting down a bug that might be a race.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Calls `f` on each item, keeping the results in the same order as the items.
+#[cfg(feature = "parallel")]
+pub fn map<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync + Send,
+{
+    items.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn map<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    F: Fn(T) -> R,
+{
+    items.into_iter().map(f).collect()
+}
+
+// Like `map`, leaving out the items `f` returns None for.
+#[cfg(feature = "parallel")]
+pub fn filter_map<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> Option<R> + Sync + Send,
+{
+    items.into_par_iter().filter_map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn filter_map<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    F: Fn(T) -> Option<R>,
+{
+    items.into_iter().filter_map(f).collect()
+}
+
+// Runs both closures, at the same time if there's a thread free to take one.
+#[cfg(feature = "parallel")]
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    rayon::join(a, b)
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA,
+    B: FnOnce() -> RB,
+{
+    (a(), b())
+}
+
+// How many threads the work is spread over.
+pub fn threads() -> usize {
+    #[cfg(feature = "parallel")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "parallel"))]
+    return 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_keep_the_order_of_the_items() {
+        let items: Vec<usize> = (0..1000).collect();
+
+        let doubled = map(items.clone(), |i| i * 2);
+        let odd = filter_map(items, |i| if i % 2 == 1 { Some(i) } else { None });
+
+        assert_eq!((0..1000).map(|i| i * 2).collect::<Vec<_>>(), doubled);
+        assert_eq!((0..1000).filter(|i| i % 2 == 1).collect::<Vec<_>>(), odd);
+        assert_eq!((3, "b"), join(|| 1 + 2, || "b"));
+        assert!(threads() >= 1);
+    }
+}
@@ -0,0 +1,1120 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    body::Body,
+    canvas::Canvas,
+    color::Color,
+    fuzzy_eq::{FuzzyEq, EPISILON},
+    intersection::Normal,
+    matrix::Matrix,
+    noise::seeded_turbulence,
+    point::Point,
+    vector::Vector,
+};
+
+#[derive(Clone, Debug)]
+pub enum PatternKind {
+    Stripe(Color, Color),
+    Gradient(Color, Color),
+    Ring(Color, Color),
+    Checker(Color, Color),
+    Wood(SolidTexture),
+    Marble(SolidTexture),
+    Granite(SolidTexture),
+    Image(ImageTexture),
+    Brick(Masonry),
+    SolidBrick(Masonry),
+}
+
+// The settings shared by the noise based solid textures. `scale` is how many features (rings,
+// veins) there are per unit, `turbulence` is how strongly the noise distorts them and `octaves` is
+// how many layers of finer noise go into that distortion. The noise is the same for the same seed
+// every time it's sampled, so it stays put on a moving body; give bodies that share a texture
+// different seeds, e.g. `noise::seed_for` their names, for them not to look alike.
+#[derive(Clone, Copy, Debug)]
+pub struct SolidTexture {
+    pub a: Color,
+    pub b: Color,
+    pub scale: f64,
+    pub turbulence: f64,
+    pub octaves: u32,
+    pub seed: u64,
+}
+
+impl SolidTexture {
+    pub fn new(a: Color, b: Color) -> Self {
+        Self {
+            a,
+            b,
+            scale: 1.0,
+            turbulence: 1.0,
+            octaves: 4,
+            seed: 0,
+        }
+    }
+
+    pub fn with_scale(self, scale: f64) -> Self {
+        Self { scale, ..self }
+    }
+
+    pub fn with_turbulence(self, turbulence: f64) -> Self {
+        Self { turbulence, ..self }
+    }
+
+    pub fn with_octaves(self, octaves: u32) -> Self {
+        Self { octaves, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    fn blend(&self, t: f64) -> Color {
+        self.a + (self.b - self.a) * t
+    }
+
+    fn turbulence_at(&self, p: Point) -> f64 {
+        self.turbulence * seeded_turbulence(p, self.octaves, self.seed)
+    }
+}
+
+impl FuzzyEq for SolidTexture {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.a.fuzzy_eq(other.a)
+            && self.b.fuzzy_eq(other.b)
+            && self.scale.fuzzy_eq(other.scale)
+            && self.turbulence.fuzzy_eq(other.turbulence)
+            && self.octaves == other.octaves
+            && self.seed == other.seed
+    }
+}
+
+// Bricks or tiles laid in rows along x, stacked up y, with lines of mortar between them. `size` is
+// how big each one is across x, up y and along z, measured from the middle of the mortar on one
+// side to the middle on the other, and `mortar_width` is how wide the lines of mortar are. Each row
+// is moved along x by `row_offset` of a brick from the one below, so a half for a running bond
+// and none for square tiles.
+#[derive(Clone, Copy, Debug)]
+pub struct Masonry {
+    pub brick: Color,
+    pub mortar: Color,
+    pub size: [f64; 3],
+    pub mortar_width: f64,
+    pub row_offset: f64,
+}
+
+impl Masonry {
+    // Bricks in a running bond, twice as long as they're high.
+    pub fn bricks(brick: Color, mortar: Color) -> Self {
+        Self {
+            brick,
+            mortar,
+            size: [1.0, 0.5, 0.5],
+            mortar_width: 0.05,
+            row_offset: 0.5,
+        }
+    }
+
+    // Square tiles in a grid, with thinner lines of grout.
+    pub fn tiles(tile: Color, grout: Color) -> Self {
+        Self {
+            brick: tile,
+            mortar: grout,
+            size: [1.0, 1.0, 1.0],
+            mortar_width: 0.03,
+            row_offset: 0.0,
+        }
+    }
+
+    pub fn with_size(self, across: f64, up: f64, along: f64) -> Self {
+        if across <= 0.0 || up <= 0.0 || along <= 0.0 {
+            panic!("bricks must be bigger than zero every way")
+        }
+        Self {
+            size: [across, up, along],
+            ..self
+        }
+    }
+
+    pub fn with_mortar_width(self, mortar_width: f64) -> Self {
+        Self {
+            mortar_width,
+            ..self
+        }
+    }
+
+    pub fn with_row_offset(self, row_offset: f64) -> Self {
+        Self { row_offset, ..self }
+    }
+
+    // Whether the point is on mortar rather than a brick. Flat masonry only looks at x and y, so
+    // it runs straight through along z. Solid masonry is laid in z too, with each row moved along
+    // z the same as along x, so it looks like a wall from any side, corners included.
+    fn is_mortar(&self, p: Point, solid: bool) -> bool {
+        let row = (p[1] / self.size[1]).floor();
+        let shift = row * self.row_offset;
+        let on_joint = |x: f64, size: f64| {
+            let along = (x / size).rem_euclid(1.0) * size;
+            along.min(size - along) < self.mortar_width / 2.0
+        };
+        on_joint(p[1], self.size[1])
+            || on_joint(p[0] + shift * self.size[0], self.size[0])
+            || (solid && on_joint(p[2] + shift * self.size[2], self.size[2]))
+    }
+}
+
+impl FuzzyEq for Masonry {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.brick.fuzzy_eq(other.brick)
+            && self.mortar.fuzzy_eq(other.mortar)
+            && (0..3).all(|i| self.size[i].fuzzy_eq(other.size[i]))
+            && self.mortar_width.fuzzy_eq(other.mortar_width)
+            && self.row_offset.fuzzy_eq(other.row_offset)
+    }
+}
+
+// An image covering the square from (0, 0) to (1, 1) in pattern space, x across and y up, and
+// repeating across the rest of the plane. It's the same all the way along z, so on anything but a
+// flat surface facing z it wants a triplanar projection.
+#[derive(Clone, Debug)]
+pub struct ImageTexture {
+    // Shared, so copies of the pattern, and of every material and body it's on, don't copy the
+    // image too.
+    image: Arc<Canvas>,
+}
+
+impl ImageTexture {
+    pub fn new(image: Canvas) -> Self {
+        Self {
+            image: Arc::new(image),
+        }
+    }
+
+    // Blends the four pixels around the point, so the texture doesn't look blocky up close.
+    fn color_at(&self, u: f64, v: f64) -> Color {
+        let (w, h) = (self.image.width, self.image.height);
+        let x = u.rem_euclid(1.0) * w as f64 - 0.5;
+        let y = (1.0 - v.rem_euclid(1.0)) * h as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let pixel = |x: f64, y: f64| {
+            let x = (x as i64).rem_euclid(w as i64) as usize;
+            let y = (y as i64).rem_euclid(h as i64) as usize;
+            self.image.read_pixel(x, y)
+        };
+        let top = pixel(x0, y0) + (pixel(x0 + 1.0, y0) - pixel(x0, y0)) * fx;
+        let bottom = pixel(x0, y0 + 1.0) + (pixel(x0 + 1.0, y0 + 1.0) - pixel(x0, y0 + 1.0)) * fx;
+        top + (bottom - top) * fy
+    }
+}
+
+// How a pattern is put onto a surface.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    // The pattern fills space, and the surface shows wherever it cuts through it.
+    #[default]
+    Solid,
+    // For flat patterns, like images, that only vary across x and y. The pattern is projected
+    // onto the surface along each axis in turn, and the three are blended by how much the surface
+    // faces along each, which covers meshes that don't have texture coordinates. The higher the
+    // sharpness, the narrower the blends between projections.
+    Triplanar {
+        sharpness: f64,
+    },
+}
+
+// Which space a pattern is fixed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    // The pattern moves, turns and stretches with the body it's on.
+    #[default]
+    Object,
+    // The pattern stays put in the world and bodies slide through it, e.g. so a floor made of
+    // several planes lines up, or so things moving through a pattern seem to carve it out. The
+    // pattern's transform then takes pattern space straight to world space.
+    World,
+}
+
+// Where a flat pattern is laid across the surface: after the pattern has been projected down to
+// x and y, they're turned by `rotation` radians, multiplied by `scale` and then moved along by
+// `offset`, all in units of the pattern's repeat. A scale of 4 tiles an image four times across
+// what it used to cover. Unlike the pattern's transform, which moves it through space, this only
+// slides and stretches it across the surface, so with a triplanar projection every side gets the
+// same tiling, and an image's tiles stay square however the floor under them is stretched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tiling {
+    pub offset: [f64; 2],
+    pub scale: [f64; 2],
+    pub rotation: f64,
+}
+
+impl Default for Tiling {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            scale: [1.0, 1.0],
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Tiling {
+    // The pattern repeated this many times across and up what it used to cover.
+    pub fn repeat(across: f64, up: f64) -> Self {
+        Self {
+            scale: [across, up],
+            ..Self::default()
+        }
+    }
+
+    pub fn with_offset(self, across: f64, up: f64) -> Self {
+        Self {
+            offset: [across, up],
+            ..self
+        }
+    }
+
+    pub fn with_rotation(self, rotation: f64) -> Self {
+        Self { rotation, ..self }
+    }
+
+    // The most tiling stretches the pattern across the surface.
+    fn largest_scale(&self) -> f64 {
+        self.scale[0].abs().max(self.scale[1].abs())
+    }
+
+    // Which way a surface with the given normal in pattern space faces once the pattern is tiled.
+    // Normals are taken through by the inverse transpose of what apply does to points.
+    fn normal(&self, n: Vector) -> Vector {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (n[0] * cos - n[1] * sin, n[0] * sin + n[1] * cos);
+        Vector::new(x / self.scale[0], y / self.scale[1], n[2])
+    }
+
+    // Where a point on the pattern's x-y plane lands once tiled; z is left alone.
+    fn apply(&self, p: Point) -> Point {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (x, y) = (p[0] * cos - p[1] * sin, p[0] * sin + p[1] * cos);
+        Point::new(
+            x * self.scale[0] + self.offset[0],
+            y * self.scale[1] + self.offset[1],
+            p[2],
+        )
+    }
+}
+
+impl FuzzyEq for Tiling {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        (0..2).all(|i| {
+            self.offset[i].fuzzy_eq(other.offset[i]) && self.scale[i].fuzzy_eq(other.scale[i])
+        }) && self.rotation.fuzzy_eq(other.rotation)
+    }
+}
+
+// A pattern maps points in pattern space to colors. The transform takes pattern space to object
+// space, so e.g. scaling the pattern up makes its features bigger.
+//
+// Stripes and checkers have hard edges that alias badly when they're small on screen, e.g. on a
+// floor stretching off towards the horizon. On bodies they're box filtered over the patch of
+// surface the ray that hit them stands for (see with_footprint), which blends the colors near the
+// edges more the smaller the squares are on screen. `filter_width` is the least width in pattern
+// space they're filtered over, for blurring them further or filtering them without a ray.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub transform: Matrix<4>,
+    pub filter_width: f64,
+    pub projection: Projection,
+    pub anchor: Anchor,
+    pub tiling: Tiling,
+    // The width of the patch of surface the color is wanted for, in world space.
+    footprint: f64,
+}
+
+// The most the transform stretches anything, taken as the longest of the columns it takes the
+// axes to.
+fn largest_scale(m: Matrix<4>) -> f64 {
+    (0..3)
+        .map(|col| {
+            (0..3)
+                .map(|row| m[row][col] * m[row][col])
+                .sum::<f64>()
+                .sqrt()
+        })
+        .fold(0.0, f64::max)
+}
+
+// How wide a filter of the given width is along each axis when it's laid flat on a surface with
+// the given unit normal. A surface doesn't reach along the axes it faces down, so filtering along
+// them would blend in squares beside it rather than on it, e.g. turning a floor at y = 0 grey.
+fn surface_widths(width: f64, n: Vector) -> [f64; 3] {
+    [0, 1, 2].map(|i| width * (1.0 - n[i] * n[i]).max(0.0).sqrt())
+}
+
+// A square wave which is 1 where floor(x) is even and -1 where it's odd, averaged over
+// [x - width / 2, x + width / 2]. The average is the difference of the wave's integral, which is a
+// triangle wave, at either end of the filter.
+fn filtered_square_wave(x: f64, width: f64) -> f64 {
+    if width <= 0.0 {
+        return if x.floor() as i64 % 2 == 0 { 1.0 } else { -1.0 };
+    }
+    let integral = |x: f64| {
+        let u = x.rem_euclid(2.0);
+        if u < 1.0 {
+            u
+        } else {
+            2.0 - u
+        }
+    };
+    (integral(x + width / 2.0) - integral(x - width / 2.0)) / width
+}
+
+// Picks between a and b given a filtered square wave value, where 1 is all a and -1 is all b.
+fn blend_square_wave(a: Color, b: Color, wave: f64) -> Color {
+    a + (b - a) * (0.5 * (1.0 - wave))
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind) -> Self {
+        Self {
+            kind,
+            transform: Matrix::identity(),
+            filter_width: 0.0,
+            projection: Projection::default(),
+            anchor: Anchor::default(),
+            tiling: Tiling::default(),
+            footprint: 0.0,
+        }
+    }
+
+    pub fn stripe(a: Color, b: Color) -> Self {
+        Self::new(PatternKind::Stripe(a, b))
+    }
+
+    pub fn gradient(a: Color, b: Color) -> Self {
+        Self::new(PatternKind::Gradient(a, b))
+    }
+
+    pub fn ring(a: Color, b: Color) -> Self {
+        Self::new(PatternKind::Ring(a, b))
+    }
+
+    pub fn checker(a: Color, b: Color) -> Self {
+        Self::new(PatternKind::Checker(a, b))
+    }
+
+    pub fn wood(texture: SolidTexture) -> Self {
+        Self::new(PatternKind::Wood(texture))
+    }
+
+    pub fn marble(texture: SolidTexture) -> Self {
+        Self::new(PatternKind::Marble(texture))
+    }
+
+    pub fn granite(texture: SolidTexture) -> Self {
+        Self::new(PatternKind::Granite(texture))
+    }
+
+    pub fn image(texture: ImageTexture) -> Self {
+        Self::new(PatternKind::Image(texture))
+    }
+
+    // Masonry across x and y, for flat surfaces facing z, texture coordinates or triplanar
+    // projections.
+    pub fn brick(masonry: Masonry) -> Self {
+        Self::new(PatternKind::Brick(masonry))
+    }
+
+    // Masonry filling space, so anything cut out of it is built of bricks all the way through.
+    pub fn solid_brick(masonry: Masonry) -> Self {
+        Self::new(PatternKind::SolidBrick(masonry))
+    }
+
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+
+    pub fn with_filter_width(self, filter_width: f64) -> Self {
+        Self {
+            filter_width,
+            ..self
+        }
+    }
+
+    // Filters the pattern on bodies over the given width of surface, in world space, as worked
+    // out for each hit by ComputedIntersection::footprint. The width is taken to pattern space by
+    // the most any of the transforms between them scales it.
+    pub fn with_footprint(self, footprint: f64) -> Self {
+        Self { footprint, ..self }
+    }
+
+    pub fn with_projection(self, projection: Projection) -> Self {
+        Self { projection, ..self }
+    }
+
+    pub fn with_anchor(self, anchor: Anchor) -> Self {
+        Self { anchor, ..self }
+    }
+
+    pub fn with_tiling(self, tiling: Tiling) -> Self {
+        Self { tiling, ..self }
+    }
+
+    // The same pattern in other colors. Image textures keep theirs.
+    pub fn map_colors(self, f: impl Fn(Color) -> Color) -> Self {
+        let texture = |t: SolidTexture| SolidTexture {
+            a: f(t.a),
+            b: f(t.b),
+            ..t
+        };
+        let masonry = |m: Masonry| Masonry {
+            brick: f(m.brick),
+            mortar: f(m.mortar),
+            ..m
+        };
+        let kind = match self.kind {
+            PatternKind::Stripe(a, b) => PatternKind::Stripe(f(a), f(b)),
+            PatternKind::Gradient(a, b) => PatternKind::Gradient(f(a), f(b)),
+            PatternKind::Ring(a, b) => PatternKind::Ring(f(a), f(b)),
+            PatternKind::Checker(a, b) => PatternKind::Checker(f(a), f(b)),
+            PatternKind::Wood(t) => PatternKind::Wood(texture(t)),
+            PatternKind::Marble(t) => PatternKind::Marble(texture(t)),
+            PatternKind::Granite(t) => PatternKind::Granite(texture(t)),
+            PatternKind::Image(t) => PatternKind::Image(t),
+            PatternKind::Brick(m) => PatternKind::Brick(masonry(m)),
+            PatternKind::SolidBrick(m) => PatternKind::SolidBrick(masonry(m)),
+        };
+        Self { kind, ..self }
+    }
+
+    // The color of the pattern at a point in pattern space.
+    pub fn color_at(&self, p: Point) -> Color {
+        self.filtered_color_at(p, [self.filter_width; 3])
+    }
+
+    // color_at, with stripes and checkers filtered over the given widths along each axis of
+    // pattern space.
+    fn filtered_color_at(&self, p: Point, filter_widths: [f64; 3]) -> Color {
+        match self.kind {
+            PatternKind::Stripe(a, b) => {
+                blend_square_wave(a, b, filtered_square_wave(p[0], filter_widths[0]))
+            }
+            PatternKind::Gradient(a, b) => a + (b - a) * (p[0] - p[0].floor()),
+            PatternKind::Ring(a, b) => {
+                if (p[0] * p[0] + p[2] * p[2]).sqrt().floor() as i64 % 2 == 0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            // A checker is the product of a square wave along each axis, and the box filter is
+            // separable, so each axis can be filtered on its own.
+            PatternKind::Checker(a, b) => {
+                let wave = (0..3)
+                    .map(|i| filtered_square_wave(p[i], filter_widths[i]))
+                    .product();
+                blend_square_wave(a, b, wave)
+            }
+            // Rings around the y axis, wobbled by the noise.
+            PatternKind::Wood(texture) => {
+                let r = (p[0] * p[0] + p[2] * p[2]).sqrt();
+                let phase = r * texture.scale + texture.turbulence_at(p);
+                texture.blend(0.5 * (1.0 + (2.0 * PI * phase).sin()))
+            }
+            // Bands along x, pushed around by the noise so they look like veins.
+            PatternKind::Marble(texture) => {
+                let phase = p[0] * texture.scale + texture.turbulence_at(p);
+                texture.blend(0.5 * (1.0 + (PI * phase).sin()))
+            }
+            // Just the noise, so the colors come out speckled.
+            PatternKind::Granite(texture) => {
+                let q = Point::new(
+                    p[0] * texture.scale,
+                    p[1] * texture.scale,
+                    p[2] * texture.scale,
+                );
+                texture.blend(texture.turbulence_at(q).min(1.0))
+            }
+            PatternKind::Image(ref texture) => texture.color_at(p[0], p[1]),
+            PatternKind::Brick(m) | PatternKind::SolidBrick(m) => {
+                let solid = matches!(self.kind, PatternKind::SolidBrick(_));
+                if m.is_mortar(p, solid) {
+                    m.mortar
+                } else {
+                    m.brick
+                }
+            }
+        }
+    }
+
+    // The color of the pattern on the given body at a point in world space.
+    pub fn color_at_body(&self, body: &Body, world_point: Point) -> Color {
+        // The transform from the space the pattern is anchored in to world space.
+        let anchor_transform = match self.anchor {
+            Anchor::Object => body.transform(),
+            Anchor::World => Matrix::identity(),
+        };
+        let anchor_point = anchor_transform.inverse() * world_point;
+        let pattern_point = self.transform.inverse() * anchor_point;
+        let filter_width = if self.footprint > 0.0 {
+            let to_pattern = self.transform.inverse() * anchor_transform.inverse();
+            self.filter_width
+                .max(self.footprint * self.tiling.largest_scale() * largest_scale(to_pattern))
+        } else {
+            self.filter_width
+        };
+        // Normals go from world to pattern space by the transpose of the transforms that bring
+        // points the other way.
+        let pattern_normal = || {
+            self.transform.transpose()
+                * (anchor_transform.transpose() * body.normal_at(world_point))
+        };
+        match self.projection {
+            Projection::Solid => {
+                let p = self.tiling.apply(pattern_point);
+                if filter_width <= 0.0 {
+                    return self.filtered_color_at(p, [0.0; 3]);
+                }
+                let normal = self.tiling.normal(pattern_normal()).normalize();
+                // A surface lying along an edge between squares, like a floor at y = 0, isn't
+                // filtered across it, so which side it's on is down to rounding in the hit point.
+                // Looking just in front of it keeps to the side it faces.
+                self.filtered_color_at(p + normal * EPISILON, surface_widths(filter_width, normal))
+            }
+            Projection::Triplanar { sharpness } => {
+                let normal = pattern_normal();
+                let weights = [0, 1, 2].map(|i| normal[i].abs().powf(sharpness));
+                let total: f64 = weights.iter().sum();
+                let p = pattern_point;
+                let projections = [
+                    Point::new(p[2], p[1], 0.0),
+                    Point::new(p[0], p[2], 0.0),
+                    Point::new(p[0], p[1], 0.0),
+                ];
+                // Each projection is flat, so it's only filtered across x and y.
+                let filter_widths = [filter_width, filter_width, 0.0];
+                projections
+                    .iter()
+                    .zip(weights)
+                    .fold(Color::BLACK, |sum, (&q, w)| {
+                        sum + self.filtered_color_at(self.tiling.apply(q), filter_widths)
+                            * (w / total)
+                    })
+            }
+        }
+    }
+
+    // The pattern read as a number rather than a color, e.g. for varying a material's shininess
+    // over a surface: the average of the channels, so grey patterns give their shade.
+    pub fn value_at_body(&self, body: &Body, world_point: Point) -> f64 {
+        let c = self.color_at_body(body, world_point);
+        (c[0] + c[1] + c[2]) / 3.0
+    }
+}
+
+impl FuzzyEq for PatternKind {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        match (self, other) {
+            (PatternKind::Stripe(a, b), PatternKind::Stripe(oa, ob))
+            | (PatternKind::Gradient(a, b), PatternKind::Gradient(oa, ob))
+            | (PatternKind::Ring(a, b), PatternKind::Ring(oa, ob))
+            | (PatternKind::Checker(a, b), PatternKind::Checker(oa, ob)) => {
+                a.fuzzy_eq(oa) && b.fuzzy_eq(ob)
+            }
+            (PatternKind::Wood(t), PatternKind::Wood(ot))
+            | (PatternKind::Marble(t), PatternKind::Marble(ot))
+            | (PatternKind::Granite(t), PatternKind::Granite(ot)) => t.fuzzy_eq(ot),
+            (PatternKind::Image(t), PatternKind::Image(ot)) => Arc::ptr_eq(&t.image, &ot.image),
+            (PatternKind::Brick(m), PatternKind::Brick(om))
+            | (PatternKind::SolidBrick(m), PatternKind::SolidBrick(om)) => m.fuzzy_eq(om),
+            _ => false,
+        }
+    }
+}
+
+impl FuzzyEq for Pattern {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.kind.fuzzy_eq(other.kind)
+            && self.transform.fuzzy_eq(other.transform)
+            && self.filter_width.fuzzy_eq(other.filter_width)
+            && self.projection == other.projection
+            && self.anchor == other.anchor
+            && self.tiling.fuzzy_eq(other.tiling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+    use super::*;
+    use crate::{
+        animator::Frame, assert_fuzzy_eq, plane::Plane, sphere::Sphere, transform::Transform,
+        triangle::Triangle,
+    };
+
+    const WHITE: Color = Color::WHITE;
+    const BLACK: Color = Color::BLACK;
+
+    #[test]
+    fn creating_a_stripe_pattern() {
+        let p = Pattern::stripe(WHITE, BLACK);
+
+        assert_fuzzy_eq!(Matrix::<4>::identity(), p.transform);
+    }
+
+    #[test]
+    fn a_stripe_pattern_is_constant_in_y_and_z() {
+        let p = Pattern::stripe(WHITE, BLACK);
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 1.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 2.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 1.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 2.0)));
+    }
+
+    #[test]
+    fn a_stripe_pattern_alternates_in_x() {
+        let p = Pattern::stripe(WHITE, BLACK);
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.9, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(1.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(-0.1, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(-1.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(-1.1, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn stripes_with_an_object_transformation() {
+        let body: Body = Sphere::default()
+            .with_transform(Matrix::scale(2.0, 2.0, 2.0))
+            .into();
+        let p = Pattern::stripe(WHITE, BLACK);
+
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(1.5, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn stripes_with_a_pattern_transformation() {
+        let body: Body = Sphere::default().into();
+        let p = Pattern::stripe(WHITE, BLACK).with_transform(Matrix::scale(2.0, 2.0, 2.0));
+
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(1.5, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn stripes_with_both_an_object_and_a_pattern_transformation() {
+        let body: Body = Sphere::default()
+            .with_transform(Matrix::scale(2.0, 2.0, 2.0))
+            .into();
+        let p = Pattern::stripe(WHITE, BLACK).with_transform(Matrix::translate(0.5, 0.0, 0.0));
+
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(2.5, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn world_anchored_patterns_stay_put_as_the_body_moves() {
+        let p = Pattern::stripe(WHITE, BLACK).with_anchor(Anchor::World);
+        let body: Body = Sphere::default()
+            .with_transform(Matrix::translate(3.0, 0.0, 0.0) * Matrix::scale(2.0, 2.0, 2.0))
+            .into();
+
+        // Anchored to the object, the point would be at x = 0.25 in pattern space, which is white.
+        assert_fuzzy_eq!(BLACK, p.color_at_body(&body, Point::new(3.5, 0.0, 0.0)));
+        assert_fuzzy_eq!(
+            WHITE,
+            p.clone()
+                .with_anchor(Anchor::Object)
+                .color_at_body(&body, Point::new(3.5, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(2.2, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn a_gradient_linearly_interpolates_between_colors() {
+        let p = Pattern::gradient(WHITE, BLACK);
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(
+            Color::new(0.75, 0.75, 0.75),
+            p.color_at(Point::new(0.25, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.color_at(Point::new(0.5, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(
+            Color::new(0.25, 0.25, 0.25),
+            p.color_at(Point::new(0.75, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn a_ring_should_extend_in_both_x_and_z() {
+        let p = Pattern::ring(WHITE, BLACK);
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(1.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.0, 0.0, 1.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.708, 0.0, 0.708)));
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_each_dimension() {
+        let p = Pattern::checker(WHITE, BLACK);
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.99, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(1.01, 0.0, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.99, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.0, 1.01, 0.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.99)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.0, 0.0, 1.01)));
+    }
+
+    #[test]
+    fn filtered_checkers_are_unchanged_away_from_the_edges() {
+        let p = Pattern::checker(WHITE, BLACK).with_filter_width(0.2);
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.5, 0.5, 0.5)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(1.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn filtered_checkers_blend_across_an_edge() {
+        let p = Pattern::checker(WHITE, BLACK).with_filter_width(0.2);
+
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.color_at(Point::new(1.0, 0.5, 0.5))
+        );
+        assert_fuzzy_eq!(
+            Color::new(0.75, 0.75, 0.75),
+            p.color_at(Point::new(0.95, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn checkers_filtered_wider_than_a_square_fade_to_gray() {
+        let p = Pattern::checker(WHITE, BLACK).with_filter_width(2.0);
+
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.color_at(Point::new(0.3, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn filtered_stripes_blend_across_an_edge() {
+        let p = Pattern::stripe(WHITE, BLACK).with_filter_width(0.5);
+
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.color_at(Point::new(0.0, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.5, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn checkers_on_a_body_are_filtered_over_the_footprint() {
+        let body: Body = Plane::default().into();
+        let p = Pattern::checker(WHITE, BLACK).with_footprint(0.2);
+
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.color_at_body(&body, Point::new(1.0, 0.0, 0.5))
+        );
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(0.5, 0.0, 0.5)));
+        // Unless the pattern's own width is wider.
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.clone()
+                .with_filter_width(2.0)
+                .color_at_body(&body, Point::new(0.5, 0.0, 0.5))
+        );
+        // The floor lies along an edge between squares at y = 0, but doesn't reach across it, so
+        // squares above and below it aren't blended in.
+        assert_fuzzy_eq!(BLACK, p.color_at_body(&body, Point::new(1.5, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn triplanar_checkers_are_only_filtered_across_each_projection() {
+        // The projections are flat, at z = 0, so filtering along z would turn them all grey.
+        let body: Body = Plane::default().into();
+        let p = Pattern::checker(WHITE, BLACK)
+            .with_projection(Projection::Triplanar { sharpness: 4.0 })
+            .with_footprint(0.2);
+
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(0.5, 0.0, 0.5)));
+        assert_fuzzy_eq!(BLACK, p.color_at_body(&body, Point::new(1.5, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn the_footprint_shrinks_on_bigger_patterns() {
+        // The squares are twice as big, so a footprint of 0.2 only covers 0.1 of one.
+        let body: Body = Plane::default().into();
+        let p = Pattern::checker(WHITE, BLACK)
+            .with_transform(Matrix::scale(2.0, 2.0, 2.0))
+            .with_footprint(0.2);
+
+        assert_fuzzy_eq!(
+            Color::new(0.75, 0.75, 0.75),
+            p.color_at_body(&body, Point::new(1.95, 0.0, 1.0))
+        );
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&body, Point::new(1.85, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn a_pattern_transform_can_be_animated() {
+        let body: Body = Sphere::default().into();
+        let keyframes = vec![
+            Transform::default(),
+            Transform::default().with_translation(1.0, 0.0, 0.0),
+        ];
+        let pattern_at = |current| {
+            let frame = Frame::new(current, 10);
+            let transform = frame.keyframes(keyframes.clone()).at(frame.current as f64);
+            Pattern::stripe(WHITE, BLACK).with_transform(transform.into())
+        };
+
+        // Halfway through, the stripes have scrolled by half a stripe.
+        let p = Point::new(0.25, 0.0, 0.0);
+        assert_fuzzy_eq!(WHITE, pattern_at(0).color_at_body(&body, p));
+        assert_fuzzy_eq!(BLACK, pattern_at(5).color_at_body(&body, p));
+    }
+
+    #[test]
+    fn wood_without_turbulence_has_evenly_spaced_rings() {
+        let p = Pattern::wood(SolidTexture::new(WHITE, BLACK).with_turbulence(0.0));
+
+        // The blend is at its midpoint on the axis and peaks a quarter of a ring out.
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.color_at(Point::new(0.0, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.25, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.0, 3.0, 1.25)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.75, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn marble_without_turbulence_has_straight_bands() {
+        let p = Pattern::marble(
+            SolidTexture::new(WHITE, BLACK)
+                .with_turbulence(0.0)
+                .with_scale(2.0),
+        );
+
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.25, 0.0, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.25, 5.0, -2.0)));
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.75, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn granite_is_the_first_color_where_there_is_no_noise() {
+        let p = Pattern::granite(SolidTexture::new(WHITE, BLACK));
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn turbulence_distorts_solid_textures() {
+        let texture = SolidTexture::new(WHITE, BLACK).with_octaves(3);
+        let smooth = Pattern::marble(texture.with_turbulence(0.0));
+        let turbulent = Pattern::marble(texture.with_turbulence(2.0));
+
+        let differs = (0..20)
+            .map(|i| Point::new(i as f64 * 0.13, i as f64 * 0.29, 0.4))
+            .any(|p| !smooth.color_at(p).fuzzy_eq(turbulent.color_at(p)));
+        assert!(differs);
+    }
+
+    // A 2x2 image, red and green along the top and blue and white along the bottom.
+    fn quadrants() -> ImageTexture {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(1, 0, Color::new(0.0, 1.0, 0.0));
+        c.write_pixel(0, 1, Color::new(0.0, 0.0, 1.0));
+        c.write_pixel(1, 1, WHITE);
+        ImageTexture::new(c)
+    }
+
+    #[test]
+    fn copies_of_an_image_pattern_share_the_image() {
+        let p = Pattern::image(quadrants());
+
+        assert!(p.fuzzy_eq(p.clone()));
+        // Another texture is another image, even if its pixels are the same.
+        assert!(!p.fuzzy_eq(Pattern::image(quadrants())));
+    }
+
+    #[test]
+    fn an_image_covers_the_unit_square_and_repeats() {
+        let p = Pattern::image(quadrants());
+
+        assert_fuzzy_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            p.color_at(Point::new(0.25, 0.75, 0.0))
+        );
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.75, 0.25, 3.0)));
+        assert_fuzzy_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            p.color_at(Point::new(-0.75, 2.75, 0.0))
+        );
+        // Halfway between pixels blends them.
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.0),
+            p.color_at(Point::new(0.5, 0.75, 0.0))
+        );
+    }
+
+    #[test]
+    fn triplanar_projection_follows_the_way_the_surface_faces() {
+        let p = Pattern::gradient(BLACK, WHITE)
+            .with_projection(Projection::Triplanar { sharpness: 4.0 });
+        let facing_x: Body = Triangle::new(
+            Point::new(0.5, 0.0, 0.0),
+            Point::new(0.5, 1.0, 0.0),
+            Point::new(0.5, 0.0, 1.0),
+        )
+        .into();
+        let facing_z: Body = Triangle::new(
+            Point::new(0.0, 0.0, 0.5),
+            Point::new(1.0, 0.0, 0.5),
+            Point::new(0.0, 1.0, 0.5),
+        )
+        .into();
+
+        // Facing x, the pattern is projected along x, so its x comes from the point's z.
+        assert_fuzzy_eq!(
+            Color::new(0.7, 0.7, 0.7),
+            p.color_at_body(&facing_x, Point::new(0.5, 0.2, 0.7))
+        );
+        assert_fuzzy_eq!(
+            Color::new(0.3, 0.3, 0.3),
+            p.color_at_body(&facing_z, Point::new(0.3, 0.2, 0.5))
+        );
+    }
+
+    #[test]
+    fn triplanar_projections_blend_where_the_surface_faces_between_axes() {
+        let p = Pattern::gradient(BLACK, WHITE)
+            .with_projection(Projection::Triplanar { sharpness: 4.0 });
+        let ball: Body = Sphere::default().into();
+        let point = Point::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0);
+
+        // Half of the projection along x, which is black there, and half of the one along y.
+        let grey = FRAC_1_SQRT_2 / 2.0;
+        assert_fuzzy_eq!(Color::new(grey, grey, grey), p.color_at_body(&ball, point));
+    }
+
+    #[test]
+    fn seeds_change_solid_textures() {
+        let texture = SolidTexture::new(WHITE, BLACK).with_turbulence(2.0);
+        let points: Vec<Point> = (0..10)
+            .map(|i| Point::new(0.3 + i as f64 * 0.41, 0.2, 0.6))
+            .collect();
+        let colors = |p: Pattern| -> Vec<Color> { points.iter().map(|&q| p.color_at(q)).collect() };
+
+        let plain = colors(Pattern::marble(texture));
+        let seeded = colors(Pattern::marble(texture.with_seed(3)));
+
+        assert!(plain.iter().zip(&seeded).any(|(a, b)| !a.fuzzy_eq(*b)));
+        let again = colors(Pattern::marble(texture.with_seed(3)));
+        assert!(seeded.iter().zip(&again).all(|(a, b)| a.fuzzy_eq(*b)));
+    }
+
+    #[test]
+    fn solid_textures_move_with_their_body() {
+        let p = Pattern::granite(SolidTexture::new(WHITE, BLACK).with_seed(9));
+        let still: Body = Sphere::default().into();
+        let moved: Body = Sphere::default()
+            .with_transform(Matrix::translate(2.5, 0.0, 0.0))
+            .into();
+
+        assert_fuzzy_eq!(
+            p.color_at_body(&still, Point::new(0.0, 0.6, 0.8)),
+            p.color_at_body(&moved, Point::new(2.5, 0.6, 0.8))
+        );
+    }
+
+    #[test]
+    fn tiling_repeats_an_image_across_a_floor_without_moving_the_floor() {
+        let floor: Body = Plane::default()
+            .with_transform(Matrix::scale(3.0, 1.0, 3.0))
+            .into();
+        let p = Pattern::image(quadrants())
+            .with_projection(Projection::Triplanar { sharpness: 4.0 })
+            .with_tiling(Tiling::repeat(2.0, 2.0));
+
+        // The floor is projected along y onto x and z. Twice the tiles means the top left pixel
+        // turns up again at what used to be the middle of the image.
+        assert_fuzzy_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            p.color_at_body(&floor, Point::new(0.375, 0.0, 1.125))
+        );
+        assert_fuzzy_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            p.color_at_body(&floor, Point::new(1.875, 0.0, 2.625))
+        );
+        assert_fuzzy_eq!(
+            p.clone()
+                .with_tiling(Tiling::default())
+                .color_at_body(&floor, Point::new(0.75, 0.0, 2.25)),
+            p.color_at_body(&floor, Point::new(0.375, 0.0, 1.125))
+        );
+    }
+
+    #[test]
+    fn tiling_turns_and_slides_the_pattern_across_the_surface() {
+        let p = Pattern::stripe(WHITE, BLACK);
+        let ball: Body = Sphere::default().into();
+        let point = Point::new(0.0, 0.5, 0.0);
+
+        assert_fuzzy_eq!(WHITE, p.color_at_body(&ball, point));
+        // Turned a quarter, the stripes run along x instead, so y picks them.
+        let turned = p
+            .clone()
+            .with_tiling(Tiling::default().with_rotation(FRAC_PI_2));
+        assert_fuzzy_eq!(BLACK, turned.color_at_body(&ball, point));
+        let slid = p.with_tiling(Tiling::default().with_offset(1.0, 0.0));
+        assert_fuzzy_eq!(BLACK, slid.color_at_body(&ball, point));
+    }
+
+    #[test]
+    fn bricks_are_laid_in_offset_rows_with_mortar_between() {
+        let p = Pattern::brick(Masonry::bricks(WHITE, BLACK));
+        let at = |x, y, z| p.color_at(Point::new(x, y, z));
+
+        assert_fuzzy_eq!(WHITE, at(0.5, 0.25, 0.0));
+        assert_fuzzy_eq!(WHITE, at(0.5, 0.25, 7.3));
+        assert_fuzzy_eq!(BLACK, at(0.0, 0.25, 0.0));
+        assert_fuzzy_eq!(BLACK, at(0.5, 0.5, 0.0));
+        // The next row up is moved along by half a brick.
+        assert_fuzzy_eq!(WHITE, at(0.0, 0.75, 0.0));
+        assert_fuzzy_eq!(BLACK, at(0.5, 0.75, 0.0));
+    }
+
+    #[test]
+    fn tiles_line_up_in_a_grid() {
+        let p = Pattern::brick(Masonry::tiles(WHITE, BLACK));
+
+        assert_fuzzy_eq!(WHITE, p.color_at(Point::new(0.5, 1.5, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.0, 1.5, 0.0)));
+        assert_fuzzy_eq!(BLACK, p.color_at(Point::new(0.5, 2.01, 0.0)));
+    }
+
+    #[test]
+    fn solid_bricks_have_mortar_along_z_too() {
+        let p = Pattern::solid_brick(Masonry::bricks(WHITE, BLACK));
+        let at = |x, y, z| p.color_at(Point::new(x, y, z));
+
+        assert_fuzzy_eq!(BLACK, at(0.5, 0.25, 0.0));
+        assert_fuzzy_eq!(WHITE, at(0.5, 0.25, 0.25));
+        // Rows move along z by the same half a brick as along x.
+        assert_fuzzy_eq!(WHITE, at(0.0, 0.75, 0.0));
+        assert_fuzzy_eq!(BLACK, at(0.0, 0.75, 0.25));
+    }
+}
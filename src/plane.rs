@@ -0,0 +1,156 @@
+use crate::{
+    bounds::{Bounded, Bounds},
+    fuzzy_eq::{FuzzyEq, EPISILON},
+    intersection::{HitRecord, Intersectable, Intersections, Normal},
+    material::Material,
+    matrix::Matrix,
+    point::Point,
+    ray::Ray,
+    transform::Transformable,
+    vector::Vector,
+};
+
+// An infinite plane. In object space it's the xz plane, facing up the y axis.
+#[derive(Clone, Debug)]
+pub struct Plane {
+    transform: Matrix<4>,
+    pub material: Material,
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Self {
+            transform: Matrix::identity(),
+            material: Material::default(),
+        }
+    }
+}
+
+impl FuzzyEq for Plane {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.transform.fuzzy_eq(other.transform)
+    }
+}
+
+impl Intersectable for Plane {
+    fn intersect(&self, r: Ray) -> Intersections {
+        let inverse = self.transform.inverse();
+        let object_space_ray = r.transform(inverse);
+        if object_space_ray.direction[1].abs() < EPISILON {
+            // Parallel to the plane (or in it, in which case it's edge on and can't be seen).
+            return vec![].into();
+        }
+
+        let t = -object_space_ray.origin[1] / object_space_ray.direction[1];
+        let hit = HitRecord::new(t, r, self.clone().into()).with_normal(normal(inverse));
+        vec![hit].into()
+    }
+}
+
+impl Normal for Plane {
+    fn normal_at(&self, _p: Point) -> Vector {
+        normal(self.transform.inverse())
+    }
+}
+
+// The plane's normal in world space, the same all over, from the inverse of its transform.
+fn normal(inverse: Matrix<4>) -> Vector {
+    let world_normal = inverse.transpose() * Vector::new(0.0, 1.0, 0.0);
+    Vector::new(world_normal[0], world_normal[1], world_normal[2]).normalize()
+}
+
+impl Bounded for Plane {
+    fn bounds(&self) -> Bounds {
+        // Infinitely wide, but flat.
+        Bounds::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+}
+
+impl Transformable for Plane {
+    fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+}
+
+impl Plane {
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+
+    pub fn with_material(self, material: Material) -> Self {
+        Self { material, ..self }
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{assert_fuzzy_eq, matrix::Rotation};
+
+    #[test]
+    fn the_normal_of_a_plane_is_constant_everywhere() {
+        let p = Plane::default();
+
+        for point in [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(10.0, 0.0, -10.0),
+            Point::new(-5.0, 0.0, 150.0),
+        ] {
+            assert_fuzzy_eq!(Vector::new(0.0, 1.0, 0.0), p.normal_at(point));
+        }
+    }
+
+    #[test]
+    fn intersecting_with_a_ray_parallel_to_or_in_the_plane() {
+        let p = Plane::default();
+
+        for r in [
+            Ray::new(Point::new(0.0, 10.0, 0.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0)),
+        ] {
+            assert!(p.intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_ray_intersecting_a_plane_from_above_and_below() {
+        let p = Plane::default();
+
+        let above = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let below = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        for r in [above, below] {
+            let xs = p.intersect(r);
+            assert_eq!(1, xs.len());
+            assert_fuzzy_eq!(1.0, xs[0].t);
+        }
+    }
+
+    #[test]
+    fn a_transformed_plane() {
+        let p = Plane::default().with_transform(Matrix::rotate(Rotation::Z, FRAC_PI_2));
+        let r = Ray::new(Point::new(-3.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+
+        let xs = p.intersect(r);
+
+        assert_fuzzy_eq!(3.0, xs[0].t);
+        assert_fuzzy_eq!(
+            Vector::new(-1.0, 0.0, 0.0),
+            p.normal_at(Point::new(0.0, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(Vector::new(-1.0, 0.0, 0.0), xs[0].normal.unwrap());
+    }
+}
@@ -7,7 +7,7 @@ use crate::tuple::Tuple;
 pub struct PointStruct {}
 pub type Point = Tuple<PointStruct, 4>;
 
-impl Sub for Point {
+impl Sub for &Point {
     type Output = Vector;
 
     fn sub(self, rhs: Self) -> Self::Output {
@@ -15,11 +15,27 @@ impl Sub for Point {
     }
 }
 
+impl Sub for Point {
+    type Output = Vector;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Sub::sub(&self, &rhs)
+    }
+}
+
+impl Add<&Vector> for &Point {
+    type Output = Point;
+
+    fn add(self, rhs: &Vector) -> Self::Output {
+        Point::new(self[0] + rhs[0], self[1] + rhs[1], self[2] + rhs[2])
+    }
+}
+
 impl Add<Vector> for Point {
     type Output = Self;
 
     fn add(self, rhs: Vector) -> Self::Output {
-        Point::new(self[0] + rhs[0], self[1] + rhs[1], self[2] + rhs[2])
+        Add::add(&self, &rhs)
     }
 }
 
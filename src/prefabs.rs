@@ -0,0 +1,360 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::{
+    capsule::Capsule,
+    color::Color,
+    csg::Csg,
+    group::Group,
+    light::PointLight,
+    material::{Material, Phong},
+    matrix::{Matrix, Rotation},
+    pattern::{Pattern, SolidTexture},
+    plane::Plane,
+    point::Point,
+    sphere::Sphere,
+    triangle::Triangle,
+    vector::Vector,
+    world::World,
+};
+
+// Ready-made objects for filling out demo scenes, built out of groups of simpler bodies. Each one
+// stands on the ground (y = 0) around the origin, facing -z, at roughly life size in meters, and
+// can be placed with the group's transform. They're also examples of putting groups together.
+
+fn phong(color: Color) -> Material {
+    Phong {
+        color,
+        ..Phong::default()
+    }
+    .into()
+}
+
+fn sphere(center: Point, radius: f64, material: Material) -> Sphere {
+    Sphere::default()
+        .with_transform(
+            Matrix::translate(center[0], center[1], center[2])
+                * Matrix::scale(radius, radius, radius),
+        )
+        .with_material(material)
+}
+
+// The six faces of a cube, as the direction each one faces along with two axes across it, in the
+// order 1 to 6 go on a die, so opposite faces add up to 7.
+const FACES: [([f64; 3], [f64; 3], [f64; 3]); 6] = [
+    ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+    ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+];
+
+fn vector([x, y, z]: [f64; 3]) -> Vector {
+    Vector::new(x, y, z)
+}
+
+// The two triangles of one face of the cube from -1 to 1, wound so that their normals face out.
+fn face(normal: Vector, u: Vector, v: Vector, material: Material) -> [Triangle; 2] {
+    let center = Point::new(0.0, 0.0, 0.0) + normal;
+    let corners = [
+        center + (-u - v),
+        center + (u - v),
+        center + (u + v),
+        center + (v - u),
+    ];
+    [
+        Triangle::new(corners[0], corners[2], corners[1]).with_material(material.clone()),
+        Triangle::new(corners[0], corners[3], corners[2]).with_material(material),
+    ]
+}
+
+// A cube from -1 to 1 made of triangles, with a material for each face in the order of FACES.
+fn cube(materials: [Material; 6]) -> Group {
+    FACES
+        .iter()
+        .zip(materials)
+        .flat_map(|(&(n, u, v), material)| face(vector(n), vector(u), vector(v), material))
+        .fold(Group::default(), Group::with_body)
+}
+
+// A box with the given half sizes, its bottom at the given point.
+fn slab(bottom: Point, half: [f64; 3], material: Material) -> Group {
+    cube(std::array::from_fn(|_| material.clone())).with_transform(
+        Matrix::translate(bottom[0], bottom[1] + half[1], bottom[2])
+            * Matrix::scale(half[0], half[1], half[2]),
+    )
+}
+
+fn leg(x: f64, z: f64, height: f64, radius: f64, material: Material) -> Capsule {
+    Capsule::new(
+        Point::new(x, radius, z),
+        Point::new(x, height - radius, z),
+        radius,
+    )
+    .with_material(material)
+}
+
+pub fn snowman() -> Group {
+    let snow: Material = Phong {
+        color: Color::new(0.95, 0.95, 1.0),
+        specular: 0.1,
+        ..Phong::default()
+    }
+    .into();
+    let coal = phong(Color::new(0.05, 0.05, 0.05));
+    let carrot = phong(Color::new(1.0, 0.45, 0.1));
+
+    let body = Group::default()
+        .with_body(sphere(Point::new(0.0, 0.5, 0.0), 0.5, snow.clone()))
+        .with_body(sphere(Point::new(0.0, 1.25, 0.0), 0.35, snow.clone()))
+        .with_body(sphere(Point::new(0.0, 1.8, 0.0), 0.225, snow));
+
+    // Buttons down the front of the middle ball, and a face on the head.
+    let mut details = Group::default();
+    for dy in [-0.15, 0.0, 0.15] {
+        let z = -(0.35f64.powi(2) - dy * dy).sqrt();
+        details = details.with_body(sphere(Point::new(0.0, 1.25 + dy, z), 0.03, coal.clone()));
+    }
+    for x in [-0.075, 0.075] {
+        details = details.with_body(sphere(Point::new(x, 1.85, -0.2), 0.025, coal.clone()));
+    }
+    let nose = Capsule::new(
+        Point::new(0.0, 1.8, -0.2),
+        Point::new(0.0, 1.78, -0.38),
+        0.025,
+    )
+    .with_material(carrot);
+
+    body.with_group(details.with_body(nose))
+}
+
+// A table with four chairs around it. There are no cylinders, so the legs are capsules.
+pub fn table_and_chairs() -> Group {
+    let wood: Material = Phong {
+        pattern: Some(
+            Pattern::wood(SolidTexture::new(
+                Color::new(0.55, 0.35, 0.18),
+                Color::new(0.4, 0.24, 0.1),
+            ))
+            .with_transform(Matrix::scale(0.05, 0.05, 0.05)),
+        ),
+        specular: 0.3,
+        shininess: 50.0,
+        ..Phong::default()
+    }
+    .into();
+
+    let mut table = Group::default().with_group(slab(
+        Point::new(0.0, 0.72, 0.0),
+        [0.8, 0.03, 0.5],
+        wood.clone(),
+    ));
+    for (x, z) in [(-0.7, -0.4), (0.7, -0.4), (-0.7, 0.4), (0.7, 0.4)] {
+        table = table.with_body(leg(x, z, 0.72, 0.035, wood.clone()));
+    }
+
+    // Each chair faces -z, with its back behind it, then it's turned to face the table.
+    let mut chairs = Group::default();
+    for (x, z, angle) in [
+        (0.0, -0.75, PI),
+        (0.0, 0.75, 0.0),
+        (-1.05, 0.0, -FRAC_PI_2),
+        (1.05, 0.0, FRAC_PI_2),
+    ] {
+        let mut chair = Group::default()
+            .with_group(slab(
+                Point::new(0.0, 0.42, 0.0),
+                [0.2, 0.02, 0.2],
+                wood.clone(),
+            ))
+            .with_group(slab(
+                Point::new(0.0, 0.44, 0.18),
+                [0.2, 0.25, 0.02],
+                wood.clone(),
+            ));
+        for (lx, lz) in [(-0.17, -0.17), (0.17, -0.17), (-0.17, 0.17), (0.17, 0.17)] {
+            chair = chair.with_body(leg(lx, lz, 0.42, 0.02, wood.clone()));
+        }
+        chairs = chairs.with_group(
+            chair.with_transform(Matrix::translate(x, 0.0, z) * Matrix::rotate(Rotation::Y, angle)),
+        );
+    }
+
+    table.with_group(chairs)
+}
+
+// Where the pips go on each face of a die, across the face from -1 to 1.
+fn pips(count: usize) -> Vec<(f64, f64)> {
+    let (near, far) = (-0.5, 0.5);
+    let mut at = Vec::new();
+    if count % 2 == 1 {
+        at.push((0.0, 0.0));
+    }
+    if count >= 2 {
+        at.extend([(near, near), (far, far)]);
+    }
+    if count >= 4 {
+        at.extend([(near, far), (far, near)]);
+    }
+    if count == 6 {
+        at.extend([(near, 0.0), (far, 0.0)]);
+    }
+    at
+}
+
+// A die 2 across, with its pips cut into the faces. It's a CSG solid rather than a group, so it's
+// added to a world with World::with_csg, and placed with Csg::transformed.
+pub fn die(color: Color, pip_color: Color) -> Csg {
+    let face_material: Material = Phong {
+        color,
+        specular: 0.6,
+        shininess: 100.0,
+        ..Phong::default()
+    }
+    .into();
+    let pip_material = phong(pip_color);
+
+    let mut pip_bodies = Vec::new();
+    for (i, &(n, u, v)) in FACES.iter().enumerate() {
+        let (n, u, v) = (vector(n), vector(u), vector(v));
+        for (pu, pv) in pips(i + 1) {
+            // Flattened spheres centered on the face, so cutting them out leaves shallow dimples.
+            let center = Point::new(0.0, 0.0, 0.0) + n + u * pu + v * pv;
+            let (u, v, n) = (u * 0.15, v * 0.15, n * 0.08);
+            let transform = Matrix::from([
+                [u[0], v[0], n[0], center[0]],
+                [u[1], v[1], n[1], center[1]],
+                [u[2], v[2], n[2], center[2]],
+                [0.0, 0.0, 0.0, 1.0],
+            ]);
+            pip_bodies.push(
+                Sphere::default()
+                    .with_transform(transform)
+                    .with_material(pip_material.clone())
+                    .into(),
+            );
+        }
+    }
+
+    Csg::difference(
+        cube(std::array::from_fn(|_| face_material.clone())).bodies(),
+        pip_bodies,
+    )
+    .transformed(Matrix::translate(0.0, 1.0, 0.0))
+}
+
+// A pair of dice on a checkered floor, lit from above and behind the left shoulder of a camera
+// looking down at them from -z. The big die sits at the origin, 2 across with its 1 on top, and
+// the small one to its right.
+pub fn dice_scene() -> World {
+    let floor = Plane::default().with_material(
+        Phong {
+            pattern: Some(Pattern::checker(
+                Color::new(0.8, 0.8, 0.8),
+                Color::new(0.6, 0.6, 0.65),
+            )),
+            specular: 0.0,
+            ..Phong::default()
+        }
+        .into(),
+    );
+    let big = die(Color::new(0.9, 0.1, 0.1), Color::WHITE)
+        .transformed(Matrix::rotate(Rotation::Y, PI / 6.0));
+    let small = die(Color::new(0.95, 0.95, 0.9), Color::new(0.05, 0.05, 0.05)).transformed(
+        Matrix::translate(2.5, 0.0, -0.5)
+            * Matrix::rotate(Rotation::Y, -0.4)
+            * Matrix::scale(0.5, 0.5, 0.5),
+    );
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+
+    World::new(vec![floor.into()], vec![light.into()])
+        .with_csg(big)
+        .with_csg(small)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        body::Body,
+        bounds::{Bounded, Bounds},
+        fuzzy_eq::FuzzyEq,
+        intersection::Normal,
+        ray::Ray,
+    };
+
+    fn bounds(group: &Group) -> Bounds {
+        group
+            .bodies()
+            .iter()
+            .map(|b| b.bounds().transformed(b.transform()))
+            .reduce(|a, b| a.union(&b))
+            .unwrap()
+    }
+
+    #[test]
+    fn a_cubes_faces_point_outwards() {
+        let bodies = cube(Default::default()).bodies();
+
+        assert_eq!(12, bodies.len());
+        for body in bodies {
+            let Body::Triangle(t) = body else {
+                panic!("a cube is made of triangles")
+            };
+            let (p1, p2, p3) = t.points();
+            let middle = Point::new(
+                (p1[0] + p2[0] + p3[0]) / 3.0,
+                (p1[1] + p2[1] + p3[1]) / 3.0,
+                (p1[2] + p2[2] + p3[2]) / 3.0,
+            );
+            let outwards = middle - Point::new(0.0, 0.0, 0.0);
+            assert!(t.normal_at(middle).dot(&outwards) > 0.0);
+        }
+    }
+
+    #[test]
+    fn prefabs_stand_on_the_ground() {
+        for group in [snowman(), table_and_chairs()] {
+            assert_fuzzy_eq!(0.0, bounds(&group).min[1]);
+        }
+        // The pips are cut into the die, so it's the cube that stands on the ground.
+        let from_below = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = die(Color::WHITE, Color::BLACK).intersect(from_below, 0);
+        assert_fuzzy_eq!(1.0, xs[0].t);
+    }
+
+    #[test]
+    fn the_pips_are_cut_into_the_die() {
+        let die = die(Color::WHITE, Color::BLACK);
+        // Looking down at the 1 on top, and just beside it.
+        let down = |x| Ray::new(Point::new(x, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        let pip = die.intersect(down(0.0), 0);
+        let face = die.intersect(down(0.4), 0);
+
+        // The dimple is the pip's color, and the only way in and out of the die there.
+        assert_eq!(2, pip.len());
+        assert_fuzzy_eq!(3.08, pip[0].t);
+        assert!(pip[0].body.material().fuzzy_eq(phong(Color::BLACK)));
+        // Beside it, the ray goes through the cube's faces.
+        assert_eq!(2, face.len());
+        assert_fuzzy_eq!(3.0, face[0].t);
+    }
+
+    #[test]
+    fn opposite_faces_of_a_die_add_up_to_seven() {
+        let counts: Vec<usize> = (1..=6).map(|i| pips(i).len()).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], counts);
+
+        for (i, &(n, _, _)) in FACES.iter().enumerate() {
+            let opposite = FACES
+                .iter()
+                .position(|&(m, _, _)| vector(m).fuzzy_eq(-vector(n)))
+                .unwrap();
+            assert_eq!(7, i + 1 + opposite + 1);
+        }
+        // The twelve triangles of the cube, and a pip for each spot.
+        assert_eq!(12 + 21, die(Color::WHITE, Color::BLACK).bodies().count());
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use crate::{
+    canvas::Canvas, color::Color, parallel, point::Point, ray::Ray, vector::Vector, world::World,
+};
+
+// The six faces of the cube, as the direction each one faces, and which ways are right and up on
+// it.
+const FACES: [([f64; 3], [f64; 3], [f64; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, 0.0, 1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+];
+
+fn face_axes(face: usize) -> (Vector, Vector, Vector) {
+    let v = |[x, y, z]: [f64; 3]| Vector::new(x, y, z);
+    let (forward, right, up) = FACES[face];
+    (v(forward), v(right), v(up))
+}
+
+// What the scene looks like from one point in every direction, rendered into the six faces of a
+// cube, usually at a low resolution. It can stand in for the scene as the environment (see
+// Environment::Probe), or for what certain bodies reflect (see World::with_probe), which is much
+// cheaper than tracing their reflections and good enough for shiny things off to the side of a
+// scene. Everything is seen as if from the probe's position, so reflections are only right for
+// bodies near it.
+#[derive(Clone, Debug)]
+pub struct LightProbe {
+    pub position: Point,
+    // The faces in the order +x, -x, +y, -y, +z, -z.
+    faces: Arc<[Canvas; 6]>,
+}
+
+impl LightProbe {
+    // Renders the world from the position onto faces `size` pixels square. Anything the position
+    // is inside of, it sees from the inside, so put it in the open.
+    pub fn capture(world: &World, position: Point, size: usize) -> Self {
+        if size == 0 {
+            panic!("a light probe needs at least one pixel a side")
+        }
+        let pixels: Vec<(usize, usize, usize)> = (0..6)
+            .flat_map(|face| (0..size * size).map(move |i| (face, i % size, i / size)))
+            .collect();
+        let colors = parallel::map(pixels, |(face, x, y)| {
+            let (forward, right, up) = face_axes(face);
+            let a = 2.0 * (x as f64 + 0.5) / size as f64 - 1.0;
+            let b = 1.0 - 2.0 * (y as f64 + 0.5) / size as f64;
+            let direction = (forward + right * a + up * b).normalize();
+            world.color_at(Ray::new(position, direction))
+        });
+
+        let faces = std::array::from_fn(|face| {
+            let mut canvas = Canvas::new(size, size);
+            for i in 0..size * size {
+                canvas.write_pixel(i % size, i / size, colors[face * size * size + i]);
+            }
+            canvas
+        });
+        Self {
+            position,
+            faces: Arc::new(faces),
+        }
+    }
+
+    pub fn face(&self, i: usize) -> &Canvas {
+        &self.faces[i]
+    }
+
+    // What the probe saw in the direction, from the nearest pixel.
+    pub fn color_at(&self, direction: Vector) -> Color {
+        let major = (0..3)
+            .max_by(|&i, &j| direction[i].abs().total_cmp(&direction[j].abs()))
+            .unwrap();
+        let face = 2 * major + usize::from(direction[major] < 0.0);
+        let (forward, right, up) = face_axes(face);
+        let depth = direction.dot(&forward);
+        let (a, b) = (direction.dot(&right) / depth, direction.dot(&up) / depth);
+
+        let canvas = &self.faces[face];
+        let size = canvas.width;
+        let x = ((0.5 * (a + 1.0) * size as f64) as usize).min(size - 1);
+        let y = ((0.5 * (1.0 - b) * size as f64) as usize).min(size - 1);
+        canvas.read_pixel(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, environment::Environment, fuzzy_eq::FuzzyEq, light::PointLight,
+        material::Phong, matrix::Matrix, sphere::Sphere,
+    };
+
+    #[test]
+    fn a_probe_sees_the_sky_in_every_direction() {
+        let world = World::default().with_environment(Environment::Gradient {
+            horizon: Color::BLACK,
+            zenith: Color::WHITE,
+        });
+
+        let probe = LightProbe::capture(&world, Point::new(0.0, 0.0, 0.0), 8);
+
+        assert_fuzzy_eq!(Color::BLACK, probe.color_at(Vector::new(0.0, -1.0, 0.0)));
+        assert!(probe.color_at(Vector::new(0.0, 1.0, 0.0))[0] > 0.9);
+        assert!(probe.color_at(Vector::new(1.0, 0.1, 0.0))[0] < 0.2);
+        // Every face is looked up the same way it was rendered, so the middles of its pixels are
+        // just what was seen there.
+        for face in 0..6 {
+            let (forward, right, up) = face_axes(face);
+            let direction = forward + right * 0.375 + up * 0.625;
+            assert_fuzzy_eq!(
+                world
+                    .environment
+                    .color_at(Ray::new(Point::new(0.0, 0.0, 0.0), direction)),
+                probe.color_at(direction)
+            );
+        }
+    }
+
+    #[test]
+    fn a_probe_sees_the_bodies_around_it() {
+        let red = Phong {
+            color: Color::new(1.0, 0.0, 0.0),
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let ball = Sphere::default()
+            .with_transform(Matrix::translate(0.0, 0.0, 5.0) * Matrix::scale(3.0, 3.0, 3.0))
+            .with_material(red.into());
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::WHITE);
+        let world = World::new(vec![ball.into()], vec![light.into()]);
+
+        let probe = LightProbe::capture(&world, Point::new(0.0, 0.0, 0.0), 4);
+
+        assert_fuzzy_eq!(
+            Color::new(1.0, 0.0, 0.0),
+            probe.color_at(Vector::new(0.0, 0.0, 1.0))
+        );
+        assert_fuzzy_eq!(Color::BLACK, probe.color_at(Vector::new(0.0, 0.0, -1.0)));
+    }
+}
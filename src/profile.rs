@@ -0,0 +1,165 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+// A tiny built-in profiler for seeing where a scene spends its time without reaching for an
+// external profiler. It only records anything when the crate is built with the `profiling`
+// feature; otherwise `time` just calls the closure and compiles away.
+pub const ENABLED: bool = cfg!(feature = "profiling");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Section {
+    Intersection,
+    Shading,
+    ShadowRays,
+    CanvasIo,
+}
+
+impl Section {
+    const ALL: [Section; 4] = [
+        Section::Intersection,
+        Section::Shading,
+        Section::ShadowRays,
+        Section::CanvasIo,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Section::Intersection => "intersection",
+            Section::Shading => "shading",
+            Section::ShadowRays => "shadow rays",
+            Section::CanvasIo => "canvas io",
+        }
+    }
+}
+
+// Totals are shared by every rendering thread, so the times add up to CPU time rather than wall
+// clock time.
+static NANOS: [AtomicU64; 4] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+static CALLS: [AtomicU64; 4] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+// Runs f, adding the time it takes to the given section.
+#[inline]
+pub fn time<T, F: FnOnce() -> T>(section: Section, f: F) -> T {
+    if !ENABLED {
+        return f();
+    }
+    let start = Instant::now();
+    let res = f();
+    let i = section.index();
+    NANOS[i].fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    CALLS[i].fetch_add(1, Ordering::Relaxed);
+    res
+}
+
+// Returns everything recorded since the last call and starts over, so calling this after each
+// render gives a breakdown per render.
+pub fn take_report() -> Report {
+    let sections = Section::ALL
+        .iter()
+        .map(|&section| {
+            let i = section.index();
+            SectionTime {
+                section,
+                time: Duration::from_nanos(NANOS[i].swap(0, Ordering::Relaxed)),
+                calls: CALLS[i].swap(0, Ordering::Relaxed),
+            }
+        })
+        .collect();
+    Report { sections }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SectionTime {
+    pub section: Section,
+    pub time: Duration,
+    pub calls: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub sections: Vec<SectionTime>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: f64 = self.sections.iter().map(|s| s.time.as_secs_f64()).sum();
+        writeln!(
+            f,
+            "{:<14} {:>12} {:>8} {:>14}",
+            "section", "time (s)", "share", "calls"
+        )?;
+        for s in &self.sections {
+            let share = if total > 0.0 {
+                100.0 * s.time.as_secs_f64() / total
+            } else {
+                0.0
+            };
+            writeln!(
+                f,
+                "{:<14} {:>12.3} {:>7.1}% {:>14}",
+                s.section.name(),
+                s.time.as_secs_f64(),
+                share,
+                s.calls
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_returns_the_result() {
+        assert_eq!(4, time(Section::Shading, || 2 + 2));
+    }
+
+    #[test]
+    fn a_report_prints_a_row_per_section() {
+        let report = Report {
+            sections: vec![
+                SectionTime {
+                    section: Section::Intersection,
+                    time: Duration::from_millis(750),
+                    calls: 10,
+                },
+                SectionTime {
+                    section: Section::Shading,
+                    time: Duration::from_millis(250),
+                    calls: 4,
+                },
+            ],
+        };
+
+        let lines: Vec<String> = report.to_string().lines().map(String::from).collect();
+
+        assert_eq!(3, lines.len());
+        assert_eq!(
+            "intersection          0.750    75.0%             10",
+            lines[1]
+        );
+        assert_eq!(
+            "shading               0.250    25.0%              4",
+            lines[2]
+        );
+    }
+}
@@ -4,11 +4,78 @@ use crate::{matrix::Matrix, point::Point, vector::Vector};
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    // Whether the ray can skip the backs of triangles that allow it (see
+    // Triangle::with_back_face_culling). Only rays from the camera do: shadow rays and rays
+    // refracted into a mesh have to see the backs of its triangles from inside.
+    pub cull_back_faces: bool,
+    // The wavelength of the light the ray carries, in nanometers, when rendering spectrally.
+    // Refraction bends it by how much the materials disperse that wavelength. Without one the ray
+    // is the usual mix of every color, and materials' refractive indices are used as they are.
+    pub wavelength: Option<f64>,
+    // How many times the ray has been reflected or refracted since it left the camera.
+    pub bounces: u32,
+    // How far the light has come from the camera to the ray's origin, over all of its bounces.
+    pub travelled: f64,
+    // Where in [0, 1) the pixel sampler put this sample of the lights, for rays traced for one of
+    // a pixel's samples. Soft shadows use it to pick their points on lights (see
+    // PointLight::shadow_targets), and it carries on through reflections and refractions.
+    pub light_sample: Option<f64>,
+    // How much wider the patch of the scene the ray stands for gets with each unit it goes, e.g.
+    // what a camera ray's pixel covers. It's 0 for rays that stand for a single line. Patterns
+    // are filtered over the patch where the ray hits them (see Pattern::color_at_body).
+    pub spread: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Self {
-        Self { origin, direction }
+        Self {
+            origin,
+            direction,
+            cull_back_faces: false,
+            wavelength: None,
+            bounces: 0,
+            travelled: 0.0,
+            light_sample: None,
+            spread: 0.0,
+        }
+    }
+
+    pub fn with_wavelength(self, wavelength: f64) -> Self {
+        Self {
+            wavelength: Some(wavelength),
+            ..self
+        }
+    }
+
+    // A ray carrying on from this one, e.g. reflected off or refracted through what it hit, with
+    // the same wavelength and one more bounce.
+    pub fn continued(&self, origin: Point, direction: Vector) -> Self {
+        Self {
+            wavelength: self.wavelength,
+            bounces: self.bounces + 1,
+            travelled: self.travelled + (origin - self.origin).magnitude(),
+            light_sample: self.light_sample,
+            spread: self.spread,
+            ..Self::new(origin, direction)
+        }
+    }
+
+    pub fn with_light_sample(self, light_sample: f64) -> Self {
+        Self {
+            light_sample: Some(light_sample),
+            ..self
+        }
+    }
+
+    pub fn with_spread(self, spread: f64) -> Self {
+        Self { spread, ..self }
+    }
+
+    pub fn with_back_face_culling(self) -> Self {
+        Self {
+            cull_back_faces: true,
+            ..self
+        }
     }
 
     pub fn position(&self, t: f64) -> Point {
@@ -19,6 +86,7 @@ impl Ray {
         Self {
             origin: m * self.origin,
             direction: m * self.direction,
+            ..*self
         }
     }
 }
@@ -0,0 +1,794 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    parallel,
+    ray::Ray,
+    sampler::{Correlation, PixelSampler},
+    world::World,
+};
+use indicatif::ProgressBar;
+
+mod batch;
+mod checkpoint;
+mod debug;
+mod depth;
+mod exposure;
+mod filter;
+mod minimap;
+mod motion;
+mod passes;
+mod preview;
+mod pyramid;
+mod report;
+#[cfg(feature = "spectral")]
+mod spectral;
+mod work_log;
+
+pub use batch::*;
+pub use checkpoint::*;
+pub use debug::*;
+pub use depth::*;
+pub use exposure::*;
+pub use filter::*;
+pub use minimap::*;
+pub use motion::*;
+pub use passes::*;
+pub use preview::*;
+pub use pyramid::*;
+pub use report::*;
+#[cfg(feature = "spectral")]
+pub use spectral::*;
+pub use work_log::*;
+
+// A cheaply cloneable flag that can be flipped from any thread (or a signal handler) to ask a
+// render in progress to stop.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+// How much work goes into each pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Quality {
+    // Just enough to see where things are, for working on the composition and camera before
+    // committing to a full render: flat colors shaded by which way the surface faces (see
+    // World::layout_color_at), for the first world of an exposure only.
+    Preview,
+    #[default]
+    Full,
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    pub tile_size: usize,
+    pub time_budget: Option<Duration>,
+    pub cancellation_token: CancellationToken,
+    pub show_progress: bool,
+    // Renders the debug view instead of the scene.
+    pub debug_view: Option<DebugView>,
+    pub preview: Option<Preview>,
+    // Seeds anything random in the render, so the same seed gives the same image.
+    pub seed: u64,
+    pub quality: Quality,
+    // Rays traced for each pixel, spread over the area the filter reaches and weighted by it.
+    pub samples_per_pixel: usize,
+    // How the sampler orders each pixel's samples in the pixel, on the lens and on the lights.
+    pub correlation: Correlation,
+    pub filter: Arc<dyn Filter>,
+    // Draws the lights over the render.
+    pub light_gizmos: Option<LightGizmos>,
+    pub exposure: Exposure,
+    // How many wavelengths to trace spectrally, if any (see with_wavelengths).
+    #[cfg(feature = "spectral")]
+    pub wavelengths: Option<usize>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            tile_size: 16,
+            time_budget: None,
+            cancellation_token: CancellationToken::new(),
+            show_progress: false,
+            debug_view: None,
+            preview: None,
+            seed: 0,
+            quality: Quality::default(),
+            samples_per_pixel: 1,
+            correlation: Correlation::default(),
+            filter: Arc::new(BoxFilter),
+            light_gizmos: None,
+            exposure: Exposure::default(),
+            #[cfg(feature = "spectral")]
+            wavelengths: None,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn with_tile_size(self, tile_size: usize) -> Self {
+        if tile_size == 0 {
+            panic!("tile size must be greater than zero")
+        }
+        Self { tile_size, ..self }
+    }
+
+    pub fn with_time_budget(self, time_budget: Duration) -> Self {
+        Self {
+            time_budget: Some(time_budget),
+            ..self
+        }
+    }
+
+    pub fn with_cancellation_token(self, cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token,
+            ..self
+        }
+    }
+
+    pub fn with_debug_view(self, debug_view: DebugView) -> Self {
+        Self {
+            debug_view: Some(debug_view),
+            ..self
+        }
+    }
+
+    // Saves a PNG of the render so far to the path every interval, with unfinished tiles
+    // checkered. Big renders are shrunk for it.
+    pub fn with_preview(self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            preview: Some(Preview {
+                path: path.into(),
+                interval,
+            }),
+            ..self
+        }
+    }
+
+    pub fn with_quality(self, quality: Quality) -> Self {
+        Self { quality, ..self }
+    }
+
+    // Antialiases by tracing more than one ray for each pixel. The sampler spreads them evenly,
+    // so counts that make a square grid, like 4 or 16, work best.
+    pub fn with_samples_per_pixel(self, samples_per_pixel: usize) -> Self {
+        if samples_per_pixel == 0 {
+            panic!("a pixel needs at least one sample")
+        }
+        Self {
+            samples_per_pixel,
+            ..self
+        }
+    }
+
+    pub fn with_correlation(self, correlation: Correlation) -> Self {
+        Self {
+            correlation,
+            ..self
+        }
+    }
+
+    // How the samples in each pixel are weighted. Only matters with more than one sample.
+    pub fn with_filter(self, filter: impl Filter + 'static) -> Self {
+        Self {
+            filter: Arc::new(filter),
+            ..self
+        }
+    }
+
+    pub fn with_light_gizmos(self, light_gizmos: LightGizmos) -> Self {
+        Self {
+            light_gizmos: Some(light_gizmos),
+            ..self
+        }
+    }
+
+    pub fn with_progress(self) -> Self {
+        Self {
+            show_progress: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    // Splits a width x height image into tiles of at most tile_size x tile_size, in row-major
+    // order.
+    pub fn split(width: usize, height: usize, tile_size: usize) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y in (0..height).step_by(tile_size) {
+            for x in (0..width).step_by(tile_size) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                });
+            }
+        }
+        tiles
+    }
+}
+
+pub struct RenderOutput {
+    pub canvas: Canvas,
+    pub tile_size: usize,
+    // Whether each tile, in the order given by Tile::split, has been rendered. Tiles that were
+    // skipped are left black.
+    pub completed_tiles: Vec<bool>,
+    // False if the render was cancelled or ran out of time before every tile was rendered.
+    pub complete: bool,
+}
+
+impl RenderOutput {
+    // Whether the output could have come from rendering with the camera in tiles of the size, so
+    // that it can be resumed with them.
+    pub fn fits(&self, camera: &Camera, tile_size: usize) -> bool {
+        self.canvas.width == camera.hsize
+            && self.canvas.height == camera.vsize
+            && self.tile_size == tile_size
+            && self.completed_tiles.len()
+                == Tile::split(camera.hsize, camera.vsize, tile_size).len()
+    }
+}
+
+pub fn render(world: &World, camera: &Camera, settings: &RenderSettings) -> RenderOutput {
+    render_exposure(std::slice::from_ref(world), camera, settings)
+}
+
+// Continues a render that was stopped early, only rendering the tiles that the previous output
+// is missing.
+pub fn resume(
+    world: &World,
+    camera: &Camera,
+    settings: &RenderSettings,
+    previous: RenderOutput,
+) -> RenderOutput {
+    resume_exposure(std::slice::from_ref(world), camera, settings, previous)
+}
+
+// Renders snapshots of a world taken at different times while the camera's shutter was open
+// (see animator::Frame::subframes). Each pixel is the average of its color in every snapshot,
+// which motion blurs anything that moves.
+pub fn render_exposure(
+    worlds: &[World],
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> RenderOutput {
+    let tile_count = Tile::split(camera.hsize, camera.vsize, settings.tile_size).len();
+    let previous = RenderOutput {
+        canvas: Canvas::new(camera.hsize, camera.vsize),
+        tile_size: settings.tile_size,
+        completed_tiles: vec![false; tile_count],
+        complete: false,
+    };
+    resume_exposure(worlds, camera, settings, previous)
+}
+
+pub fn resume_exposure(
+    worlds: &[World],
+    camera: &Camera,
+    settings: &RenderSettings,
+    previous: RenderOutput,
+) -> RenderOutput {
+    if worlds.is_empty() {
+        panic!("an exposure needs at least one world")
+    }
+    let settings = &settings.metered(worlds, camera);
+    let tiles = Tile::split(camera.hsize, camera.vsize, settings.tile_size);
+    if !previous.fits(camera, settings.tile_size) {
+        panic!("previous render does not match the camera and render settings")
+    }
+
+    let start = Instant::now();
+    let should_stop = || {
+        settings.cancellation_token.is_cancelled()
+            || settings
+                .time_budget
+                .is_some_and(|budget| start.elapsed() >= budget)
+    };
+
+    let remaining_pixels: usize = tiles
+        .iter()
+        .zip(previous.completed_tiles.iter())
+        .filter(|(_, &done)| !done)
+        .map(|(tile, _)| tile.width * tile.height)
+        .sum();
+    let progress = if settings.show_progress {
+        ProgressBar::new(remaining_pixels as u64)
+    } else {
+        ProgressBar::hidden()
+    };
+    progress.set_draw_rate(5);
+    let previewer = settings
+        .preview
+        .as_ref()
+        .map(|preview| Previewer::new(preview, &previous, &tiles));
+
+    let pending: Vec<(usize, &Tile)> = tiles
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !previous.completed_tiles[*i])
+        .collect();
+    let rendered: Vec<(usize, Vec<Color>)> = parallel::filter_map(pending, |(i, tile)| {
+        if should_stop() {
+            return None;
+        }
+        let mut pixels = Vec::with_capacity(tile.width * tile.height);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                pixels.push(pixel_color(worlds, camera, settings, x, y));
+            }
+        }
+        progress.inc(pixels.len() as u64);
+        if let Some(previewer) = &previewer {
+            previewer.tile_done(i, &pixels);
+        }
+        Some((i, pixels))
+    });
+    progress.finish();
+
+    let mut canvas = previous.canvas;
+    let mut completed_tiles = previous.completed_tiles;
+    for (i, pixels) in rendered {
+        let tile = tiles[i];
+        for (j, color) in pixels.into_iter().enumerate() {
+            canvas.write_pixel(tile.x + j % tile.width, tile.y + j / tile.width, color);
+        }
+        completed_tiles[i] = true;
+    }
+
+    let complete = completed_tiles.iter().all(|&done| done);
+    RenderOutput {
+        canvas,
+        tile_size: settings.tile_size,
+        completed_tiles,
+        complete,
+    }
+}
+
+// The color of one pixel of an exposure, as the settings ask for it to be rendered.
+fn pixel_color(
+    worlds: &[World],
+    camera: &Camera,
+    settings: &RenderSettings,
+    x: usize,
+    y: usize,
+) -> Color {
+    let color = scene_color(worlds, camera, settings, x, y);
+    match settings.light_gizmos {
+        Some(gizmos) => gizmos.overlay(&worlds[0], camera.ray_for_pixel(x, y), color),
+        None => color,
+    }
+}
+
+fn scene_color(
+    worlds: &[World],
+    camera: &Camera,
+    settings: &RenderSettings,
+    x: usize,
+    y: usize,
+) -> Color {
+    let ray = camera.ray_for_pixel(x, y);
+    if let Some(view) = settings.debug_view {
+        return view.color_at(&worlds[0], ray);
+    }
+    if settings.quality == Quality::Preview {
+        return worlds[0].layout_color_at(ray);
+    }
+    let exposure = |ray| {
+        let total = worlds.iter().fold(Color::default(), |sum, world| {
+            sum + traced_color(world, ray, settings)
+        });
+        total * (settings.exposure.scale() / worlds.len() as f64)
+    };
+    // A lens needs more than the one ray through the middle of the pixel to blur anything.
+    let lens = camera.aperture > 0.0;
+    if settings.samples_per_pixel == 1 && !lens {
+        return exposure(ray);
+    }
+
+    let sampler = PixelSampler::new(x, y, settings.samples_per_pixel, settings.seed)
+        .with_correlation(settings.correlation);
+    let radius = settings.filter.radius();
+    let samples = (0..sampler.count()).map(|i| {
+        let (u, v) = sampler.sample_2d(PIXEL_AREA_DIMENSION, i);
+        let (dx, dy) = ((2.0 * u - 1.0) * radius, (2.0 * v - 1.0) * radius);
+        let ray = if lens {
+            let (u, v) = sampler.sample_2d(LENS_DIMENSION, i);
+            camera.lens_ray_near_pixel(x, y, dx, dy, u, v)
+        } else {
+            camera.ray_near_pixel(x, y, dx, dy)
+        };
+        let ray = ray.with_light_sample(sampler.sample_2d(LIGHT_DIMENSION, i).0);
+        (dx, dy, exposure(ray))
+    });
+    filtered_average(settings.filter.as_ref(), samples)
+}
+
+#[cfg(feature = "spectral")]
+fn traced_color(world: &World, ray: Ray, settings: &RenderSettings) -> Color {
+    match settings.wavelengths {
+        Some(wavelengths) => world.spectral_color_at(ray, wavelengths),
+        None => world.color_at(ray),
+    }
+}
+
+#[cfg(not(feature = "spectral"))]
+fn traced_color(world: &World, ray: Ray, _settings: &RenderSettings) -> Color {
+    world.color_at(ray)
+}
+
+// The PixelSampler dimension for where in the pixel each ray goes through.
+const PIXEL_AREA_DIMENSION: usize = 0;
+// And where on the lens it comes from.
+const LENS_DIMENSION: usize = 1;
+// And which points on the lights its shadow rays go to.
+const LIGHT_DIMENSION: usize = 2;
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, body::Body, fuzzy_eq::FuzzyEq, light::PointLight, material::Phong,
+        matrix::Matrix, plane::Plane, point::Point, sphere::Sphere, vector::Vector,
+    };
+
+    fn create_default_world() -> World {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let material = Phong {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Phong::default()
+        }
+        .into();
+
+        let s1: Body = Sphere::default().with_material(material).into();
+        let s2: Body = Sphere::default()
+            .with_transform(Matrix::scale(0.5, 0.5, 0.5))
+            .into();
+
+        World::new(vec![s1, s2], vec![light.into()])
+    }
+
+    fn create_default_camera() -> Camera {
+        Camera::new(11, 11, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn splitting_an_image_into_tiles() {
+        let tiles = Tile::split(5, 3, 2);
+
+        assert_eq!(6, tiles.len());
+        assert_eq!(
+            Tile {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 2
+            },
+            tiles[0]
+        );
+        assert_eq!(
+            Tile {
+                x: 4,
+                y: 2,
+                width: 1,
+                height: 1
+            },
+            tiles[5]
+        );
+    }
+
+    #[test]
+    fn rendering_a_world_with_a_camera() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+
+        let output = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_tile_size(4),
+        );
+
+        assert!(output.complete);
+        assert_fuzzy_eq!(
+            Color::new(0.38066, 0.47583, 0.2855),
+            output.canvas.read_pixel(5, 5)
+        );
+    }
+
+    #[test]
+    fn more_samples_per_pixel_soften_edges() {
+        let material = Phong {
+            color: Color::WHITE,
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let world = World::new(
+            vec![Sphere::default().with_material(material.into()).into()],
+            vec![PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE).into()],
+        );
+        let camera = create_default_camera();
+        let partly_covered = |output: &RenderOutput| {
+            (0..11)
+                .flat_map(|y| (0..11).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    let c = output.canvas.read_pixel(x, y)[0];
+                    c > 0.01 && c < 0.99
+                })
+                .count()
+        };
+
+        let aliased = render(&world, &camera, &RenderSettings::default());
+        let settings = RenderSettings::default().with_samples_per_pixel(16);
+        let boxed = render(&world, &camera, &settings);
+        let filtered = render(
+            &world,
+            &camera,
+            &settings.with_filter(MitchellFilter::default()),
+        );
+
+        assert_eq!(0, partly_covered(&aliased));
+        for output in &[boxed, filtered] {
+            assert!(partly_covered(output) > 0);
+            // The Mitchell filter's negative lobes can overshoot a little next to an edge.
+            assert!(output.canvas.read_pixel(5, 5)[0] > 0.9);
+            assert_fuzzy_eq!(Color::BLACK, output.canvas.read_pixel(0, 0));
+        }
+    }
+
+    #[test]
+    fn an_open_aperture_blurs_what_is_out_of_focus() {
+        let material = Phong {
+            color: Color::WHITE,
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let world = World::new(
+            vec![Sphere::default().with_material(material.into()).into()],
+            vec![PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE).into()],
+        );
+        let settings = RenderSettings::default().with_samples_per_pixel(16);
+        let blurred_pixels = |camera: Camera| {
+            let output = render(&world, &camera, &settings);
+            (0..11)
+                .flat_map(|y| (0..11).map(move |x| (x, y)))
+                .filter(|&(x, y)| {
+                    let c = output.canvas.read_pixel(x, y)[0];
+                    c > 0.01 && c < 0.99
+                })
+                .count()
+        };
+
+        let pinhole = blurred_pixels(create_default_camera());
+        let in_focus = blurred_pixels(
+            create_default_camera()
+                .with_aperture(2.0)
+                .focus_at(Point::new(0.0, 0.0, -1.0)),
+        );
+        let out_of_focus = blurred_pixels(
+            create_default_camera()
+                .with_aperture(2.0)
+                .with_focal_distance(1.0),
+        );
+
+        assert_eq!(pinhole, in_focus);
+        assert!(out_of_focus > 2 * pinhole);
+    }
+
+    #[test]
+    fn soft_shadows_are_sampled_in_the_order_the_settings_ask_for() {
+        let light =
+            PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE).with_radius(3.0, 4);
+        let floor = Plane::default().with_transform(Matrix::translate(0.0, -1.0, 0.0));
+        let world = World::new(
+            vec![Sphere::default().into(), floor.into()],
+            vec![light.into()],
+        );
+        let camera = create_default_camera();
+        let settings = RenderSettings::default().with_samples_per_pixel(4);
+        let shared = settings.clone().with_correlation(Correlation::Shared);
+
+        let scrambled = render(&world, &camera, &settings).canvas;
+        let shared = render(&world, &camera, &shared).canvas;
+        let differing = (0..11)
+            .flat_map(|y| (0..11).map(move |x| (x, y)))
+            .filter(|&(x, y)| !shared.read_pixel(x, y).fuzzy_eq(scrambled.read_pixel(x, y)))
+            .count();
+
+        assert!(differing > 0);
+    }
+
+    #[test]
+    fn a_preview_render_skips_the_lighting() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+
+        let output = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_quality(Quality::Preview),
+        );
+
+        assert!(output.complete);
+        assert_fuzzy_eq!(Color::new(0.8, 1.0, 0.6), output.canvas.read_pixel(5, 5));
+    }
+
+    #[test]
+    fn a_cancelled_render_is_incomplete() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let output = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_cancellation_token(token),
+        );
+
+        assert!(!output.complete);
+        assert_fuzzy_eq!(Color::new(0.0, 0.0, 0.0), output.canvas.read_pixel(5, 5));
+    }
+
+    #[test]
+    fn a_render_out_of_time_is_incomplete() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+
+        let output = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_time_budget(Duration::from_secs(0)),
+        );
+
+        assert!(!output.complete);
+    }
+
+    #[test]
+    fn resuming_a_render_only_renders_the_missing_tiles() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+        let settings = RenderSettings::default().with_tile_size(4);
+        let mut previous = render(&world, &camera, &settings);
+
+        // Pretend the middle tile never got rendered.
+        previous.completed_tiles[4] = false;
+        previous.canvas.write_pixel(5, 5, Color::new(0.0, 0.0, 0.0));
+        // And that one of the finished tiles has a color we'd never render.
+        previous.canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 1.0));
+
+        let output = resume(&world, &camera, &settings, previous);
+
+        assert!(output.complete);
+        assert_fuzzy_eq!(
+            Color::new(0.38066, 0.47583, 0.2855),
+            output.canvas.read_pixel(5, 5)
+        );
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 1.0), output.canvas.read_pixel(0, 0));
+    }
+
+    #[test]
+    fn an_exposure_averages_its_worlds() {
+        let camera = create_default_camera();
+        let empty = World::new(vec![], vec![]);
+        let settings = RenderSettings::default().with_tile_size(4);
+
+        let output = render_exposure(&[create_default_world(), empty], &camera, &settings);
+
+        assert!(output.complete);
+        assert_fuzzy_eq!(
+            Color::new(0.38066, 0.47583, 0.2855) * 0.5,
+            output.canvas.read_pixel(5, 5)
+        );
+    }
+
+    #[test]
+    fn an_exposure_of_one_world_is_a_plain_render() {
+        let camera = create_default_camera();
+        let settings = RenderSettings::default().with_tile_size(4);
+
+        let output = render_exposure(&[create_default_world()], &camera, &settings);
+
+        assert_fuzzy_eq!(
+            Color::new(0.38066, 0.47583, 0.2855),
+            output.canvas.read_pixel(5, 5)
+        );
+    }
+
+    #[test]
+    fn rendering_a_debug_view_instead_of_the_scene() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+        let settings = RenderSettings::default()
+            .with_debug_view(DebugView::BoundsTests { max: 2 })
+            .with_tile_size(4);
+
+        let output = render(&world, &camera, &settings);
+
+        assert_fuzzy_eq!(Color::WHITE, output.canvas.read_pixel(5, 5));
+        assert_fuzzy_eq!(Color::WHITE, output.canvas.read_pixel(0, 0));
+    }
+
+    #[test]
+    fn rendering_with_a_preview_writes_it_as_tiles_finish() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+        let path =
+            std::env::temp_dir().join(format!("raytracer-preview-{}.png", std::process::id()));
+        let settings = RenderSettings::default()
+            .with_tile_size(4)
+            .with_preview(&path, Duration::from_secs(0));
+
+        let output = render(&world, &camera, &settings);
+        let preview = Canvas::from_png(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(output.complete);
+        assert_eq!(camera.hsize, preview.width);
+    }
+
+    #[test]
+    #[should_panic(expected = "previous render does not match the camera and render settings")]
+    fn resuming_with_different_settings_panics() {
+        let world = create_default_world();
+        let camera = create_default_camera();
+        let previous = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_tile_size(4),
+        );
+
+        resume(
+            &world,
+            &camera,
+            &RenderSettings::default().with_tile_size(2),
+            previous,
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use super::{render, RenderSettings};
+use crate::{camera::Camera, canvas::ToPng, parallel, world::FrozenWorld};
+
+// One render in a batch. Jobs that show the same scene can share its world, so it's only built
+// (and its meshes and textures only loaded) once however many shots there are of it.
+pub struct Job {
+    pub name: String,
+    pub world: FrozenWorld,
+    pub camera: Camera,
+    pub settings: RenderSettings,
+    // Where to save the PNG. Any missing directories are made.
+    pub output: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct JobResult {
+    pub name: String,
+    pub output: PathBuf,
+    pub elapsed: Duration,
+    // False if the job's render was cancelled or ran out of time before it finished. What was
+    // rendered is saved anyway.
+    pub complete: bool,
+    pub saved: io::Result<()>,
+}
+
+// A list of renders to get through unattended, e.g. overnight. A job that fails to save doesn't
+// stop the others.
+pub struct Batch {
+    jobs: Vec<Job>,
+    parallel_jobs: usize,
+}
+
+impl Batch {
+    pub fn new(jobs: Vec<Job>) -> Self {
+        Self {
+            jobs,
+            parallel_jobs: 1,
+        }
+    }
+
+    // How many jobs to render at once. Each render already uses every core, so this only helps
+    // when the jobs are small enough that a single one can't keep them all busy.
+    pub fn with_parallel_jobs(self, parallel_jobs: usize) -> Self {
+        if parallel_jobs == 0 {
+            panic!("a batch needs to render at least one job at a time")
+        }
+        Self {
+            parallel_jobs,
+            ..self
+        }
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    // Renders every job and saves it, returning how each went in the same order as the jobs. The
+    // jobs are dealt out in turn to as many lanes as there are parallel jobs, and the lanes are
+    // run side by side, each one job at a time. Without the `parallel` feature, it's all one job
+    // at a time.
+    pub fn run(&self) -> Vec<JobResult> {
+        let lane_count = self.parallel_jobs.min(self.jobs.len()).max(1);
+        let mut lanes: Vec<Vec<(usize, &Job)>> = (0..lane_count).map(|_| Vec::new()).collect();
+        for (i, job) in self.jobs.iter().enumerate() {
+            lanes[i % lane_count].push((i, job));
+        }
+
+        let mut results: Vec<(usize, JobResult)> = parallel::map(lanes, |lane| {
+            lane.into_iter()
+                .map(|(i, job)| (i, run_job(job)))
+                .collect::<Vec<_>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect();
+        results.sort_by_key(|&(i, _)| i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+fn run_job(job: &Job) -> JobResult {
+    let start = Instant::now();
+    let output = render(&job.world, &job.camera, &job.settings);
+    let saved = job
+        .output
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::File::create(&job.output))
+        .and_then(|f| output.canvas.to_png(f).map_err(io::Error::other));
+    JobResult {
+        name: job.name.clone(),
+        output: job.output.clone(),
+        elapsed: start.elapsed(),
+        complete: output.complete,
+        saved,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{canvas::Canvas, world::World};
+
+    fn job(name: &str, world: &FrozenWorld, output: PathBuf) -> Job {
+        Job {
+            name: name.to_string(),
+            world: world.clone(),
+            camera: Camera::quickstart(8, 6),
+            settings: RenderSettings::default(),
+            output,
+        }
+    }
+
+    #[test]
+    fn a_batch_renders_every_job_in_order() {
+        let dir = std::env::temp_dir().join(format!("raytracer-batch-{}", std::process::id()));
+        let world = World::quickstart().freeze();
+        let jobs = (0..3)
+            .map(|i| {
+                job(
+                    &format!("shot {}", i),
+                    &world,
+                    dir.join(format!("nested/shot{}.png", i)),
+                )
+            })
+            .collect();
+
+        let results = Batch::new(jobs).with_parallel_jobs(2).run();
+
+        let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(vec!["shot 0", "shot 1", "shot 2"], names);
+        for result in &results {
+            assert!(result.saved.is_ok() && result.complete);
+            let read = Canvas::from_png(fs::File::open(&result.output).unwrap()).unwrap();
+            assert_eq!(8, read.width);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_job_that_cannot_be_saved_does_not_stop_the_others() {
+        let dir = std::env::temp_dir().join(format!("raytracer-batch-bad-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let world = World::quickstart().freeze();
+        // The first job's output is a directory, which can't be written over with a file.
+        let jobs = vec![
+            job("bad", &world, dir.clone()),
+            job("good", &world, dir.join("good.png")),
+        ];
+
+        let results = Batch::new(jobs).run();
+
+        assert!(results[0].saved.is_err());
+        assert!(results[1].saved.is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
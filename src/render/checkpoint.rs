@@ -0,0 +1,315 @@
+use std::{
+    convert::TryInto,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use super::{
+    render_exposure, report::settings_hash, resume_exposure, RenderOutput, RenderSettings, Tile,
+};
+use crate::{
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    profile::{self, Section},
+    world::World,
+};
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"RTCHKPT2";
+
+// Checkpoints store the unclamped canvas colors so that a resumed render is identical to one that
+// was never interrupted. They also store a hash of the render settings that change the image (the
+// one in RenderReport), so that tiles rendered with different settings are never stitched
+// together. Everything is little-endian:
+//
+//   magic | settings hash: u64 | width: u64 | height: u64 | tile_size: u64 | tile count: u64
+//   one byte per tile (1 if completed)
+//   r, g, b as f64 for each pixel in row-major order
+impl RenderOutput {
+    // Writes a checkpoint for the output, rendered with the settings.
+    pub fn write_checkpoint<W: Write>(&self, settings: &RenderSettings, w: W) -> io::Result<()> {
+        profile::time(Section::CanvasIo, || {
+            self.write_checkpoint_untimed(settings, w)
+        })
+    }
+
+    // Reads a checkpoint back, to be resumed with the settings. It's an error if it was written
+    // with settings that render a different image.
+    pub fn read_checkpoint<R: Read>(settings: &RenderSettings, r: R) -> io::Result<Self> {
+        profile::time(Section::CanvasIo, || {
+            Self::read_checkpoint_untimed(settings, r)
+        })
+    }
+
+    fn write_checkpoint_untimed<W: Write>(
+        &self,
+        settings: &RenderSettings,
+        mut w: W,
+    ) -> io::Result<()> {
+        w.write_all(CHECKPOINT_MAGIC)?;
+        w.write_all(&settings_hash(settings).to_le_bytes())?;
+        for n in [
+            self.canvas.width,
+            self.canvas.height,
+            self.tile_size,
+            self.completed_tiles.len(),
+        ] {
+            w.write_all(&(n as u64).to_le_bytes())?;
+        }
+
+        let completed: Vec<u8> = self.completed_tiles.iter().map(|&c| c as u8).collect();
+        w.write_all(&completed)?;
+
+        for y in 0..self.canvas.height {
+            for x in 0..self.canvas.width {
+                let color = self.canvas.read_pixel(x, y);
+                for i in 0..3 {
+                    w.write_all(&color[i].to_le_bytes())?;
+                }
+            }
+        }
+        w.flush()
+    }
+
+    fn read_checkpoint_untimed<R: Read>(settings: &RenderSettings, mut r: R) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(invalid("not a render checkpoint"));
+        }
+
+        let mut hash = [0u8; 8];
+        r.read_exact(&mut hash)?;
+        if u64::from_le_bytes(hash) != settings_hash(settings) {
+            return Err(invalid(
+                "the checkpoint was rendered with different render settings",
+            ));
+        }
+
+        let mut read_u64 = || -> io::Result<usize> {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf) as usize)
+        };
+        let width = read_u64()?;
+        let height = read_u64()?;
+        let tile_size = read_u64()?;
+        let tile_count = read_u64()?;
+
+        // The sizes are checked against what's actually there before anything is allocated for
+        // them, so a corrupted header is an error rather than running out of memory.
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)?;
+        let expected_len = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3 * 8))
+            .and_then(|len| len.checked_add(tile_count));
+        if tile_size == 0 || expected_len != Some(data.len()) {
+            return Err(invalid("the checkpoint's header doesn't match its size"));
+        }
+        if tile_count != Tile::split(width, height, tile_size).len() {
+            return Err(invalid(
+                "the checkpoint's tile count doesn't match its size",
+            ));
+        }
+
+        let (completed, pixels) = data.split_at(tile_count);
+        let completed_tiles: Vec<bool> = completed.iter().map(|&c| c != 0).collect();
+
+        let mut canvas = Canvas::new(width, height);
+        for (i, pixel) in pixels.chunks_exact(3 * 8).enumerate() {
+            let mut color = Color::default();
+            for (c, bytes) in pixel.chunks_exact(8).enumerate() {
+                color[c] = f64::from_le_bytes(bytes.try_into().unwrap());
+            }
+            canvas.write_pixel(i % width, i / width, color);
+        }
+
+        let complete = completed_tiles.iter().all(|&done| done);
+        Ok(Self {
+            canvas,
+            tile_size,
+            completed_tiles,
+            complete,
+        })
+    }
+}
+
+// Renders the world, picking up from the checkpoint at the given path if there is one. If the
+// render is stopped early a new checkpoint is written to the path; once it completes the
+// checkpoint is removed.
+pub fn render_resumable(
+    world: &World,
+    camera: &Camera,
+    settings: &RenderSettings,
+    checkpoint_path: &Path,
+) -> io::Result<RenderOutput> {
+    render_exposure_resumable(
+        std::slice::from_ref(world),
+        camera,
+        settings,
+        checkpoint_path,
+    )
+}
+
+// Like render_resumable, for an exposure made up of several snapshots of a world.
+pub fn render_exposure_resumable(
+    worlds: &[World],
+    camera: &Camera,
+    settings: &RenderSettings,
+    checkpoint_path: &Path,
+) -> io::Result<RenderOutput> {
+    let output = if checkpoint_path.exists() {
+        let previous = RenderOutput::read_checkpoint(
+            settings,
+            io::BufReader::new(fs::File::open(checkpoint_path)?),
+        )?;
+        if !previous.fits(camera, settings.tile_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the checkpoint doesn't match the camera",
+            ));
+        }
+        resume_exposure(worlds, camera, settings, previous)
+    } else {
+        render_exposure(worlds, camera, settings)
+    };
+
+    if output.complete {
+        if checkpoint_path.exists() {
+            fs::remove_file(checkpoint_path)?;
+        }
+    } else {
+        output.write_checkpoint(
+            settings,
+            io::BufWriter::new(fs::File::create(checkpoint_path)?),
+        )?;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        fuzzy_eq::FuzzyEq,
+        render::{render, Quality},
+    };
+
+    #[test]
+    fn a_checkpoint_round_trips() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.5, -0.25, 0.125));
+        canvas.write_pixel(2, 1, Color::new(0.0, 0.5, 1.0));
+        let output = RenderOutput {
+            canvas,
+            tile_size: 2,
+            completed_tiles: vec![true, false],
+            complete: false,
+        };
+
+        let mut buf = Vec::new();
+        output
+            .write_checkpoint(&RenderSettings::default(), &mut buf)
+            .unwrap();
+        let read =
+            RenderOutput::read_checkpoint(&RenderSettings::default(), buf.as_slice()).unwrap();
+
+        assert_eq!(3, read.canvas.width);
+        assert_eq!(2, read.canvas.height);
+        assert_eq!(2, read.tile_size);
+        assert_eq!(vec![true, false], read.completed_tiles);
+        assert!(!read.complete);
+        assert_fuzzy_eq!(Color::new(1.5, -0.25, 0.125), read.canvas.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::new(0.0, 0.5, 1.0), read.canvas.read_pixel(2, 1));
+    }
+
+    #[test]
+    fn a_checkpoint_with_a_bad_header_is_rejected() {
+        let output = RenderOutput {
+            canvas: Canvas::new(3, 2),
+            tile_size: 2,
+            completed_tiles: vec![true, false],
+            complete: false,
+        };
+        let mut buf = Vec::new();
+        output
+            .write_checkpoint(&RenderSettings::default(), &mut buf)
+            .unwrap();
+        let with_header = |offset: usize, n: u64| {
+            let mut buf = buf.clone();
+            buf[offset..offset + 8].copy_from_slice(&n.to_le_bytes());
+            buf
+        };
+
+        // A huge width, a tile count that doesn't fit the rest, a tile count that doesn't match
+        // the tiles, and a tile size of zero.
+        for bad in [
+            with_header(16, u64::MAX),
+            with_header(40, 1 << 40),
+            {
+                let mut buf = with_header(40, 3);
+                buf.insert(48, 0);
+                buf
+            },
+            with_header(32, 0),
+        ] {
+            let res = RenderOutput::read_checkpoint(&RenderSettings::default(), bad.as_slice());
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+    }
+
+    #[test]
+    fn resuming_from_a_checkpoint_for_another_camera_fails() {
+        let world = World::quickstart();
+        let settings = RenderSettings::default().with_tile_size(4);
+        let output = render(&world, &Camera::quickstart(8, 8), &settings);
+        let path = std::env::temp_dir().join(format!(
+            "raytracer-mismatched-{}.checkpoint",
+            std::process::id()
+        ));
+        output
+            .write_checkpoint(&settings, fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let res = render_resumable(&world, &Camera::quickstart(12, 8), &settings, &path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+    }
+
+    #[test]
+    fn resuming_from_a_checkpoint_with_other_settings_fails() {
+        let world = World::quickstart();
+        let settings = RenderSettings::default().with_tile_size(4);
+        let output = render(&world, &Camera::quickstart(8, 8), &settings);
+        let mut buf = Vec::new();
+        output.write_checkpoint(&settings, &mut buf).unwrap();
+
+        for other in [
+            RenderSettings {
+                seed: 1,
+                ..settings.clone()
+            },
+            settings.clone().with_samples_per_pixel(4),
+            settings.clone().with_quality(Quality::Preview),
+        ] {
+            let res = RenderOutput::read_checkpoint(&other, buf.as_slice());
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+    }
+
+    #[test]
+    fn reading_something_that_is_not_a_checkpoint_fails() {
+        let res = RenderOutput::read_checkpoint(
+            &RenderSettings::default(),
+            &b"P3\n1 1\n255\n0 0 0\n"[..],
+        );
+
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+    }
+}
@@ -0,0 +1,211 @@
+use std::f64::consts::PI;
+
+use crate::{
+    animator::Interpolate, color::Color, light::Light, point::Point, ray::Ray, world::World,
+};
+
+// Renders that show how the renderer is doing its job rather than what the scene looks like.
+#[derive(Clone, Copy, Debug)]
+pub enum DebugView {
+    // Colors each pixel by how many bounding boxes were tested to find what its ray hits, from
+    // black for none up to white for `max` or more.
+    BoundsTests { max: usize },
+}
+
+impl DebugView {
+    pub fn color_at(&self, world: &World, ray: Ray) -> Color {
+        match self {
+            DebugView::BoundsTests { max } => {
+                heat(world.bounds_tests(ray) as f64 / (*max).max(1) as f64)
+            }
+        }
+    }
+}
+
+// Draws where the point lights are on top of a render, so a lighting setup can be understood
+// from the image itself. Each light gets a small marker glowing in its color, hidden by anything
+// in front of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightGizmos {
+    // How big the markers are across, in world units.
+    pub marker_size: f64,
+    // Also outlines each soft light's radius, which is how big the shadows treat it as being (see
+    // PointLight::with_radius), as a see-through wireframe sphere.
+    pub show_radii: bool,
+}
+
+// How many lines of latitude and longitude the wireframe has per half turn, how wide they are as a
+// fraction of the spacing, and how much of the color behind shows through them.
+const WIRE_LINES: f64 = 6.0;
+const WIRE_WIDTH: f64 = 0.1;
+const WIRE_OPACITY: f64 = 0.5;
+
+impl LightGizmos {
+    pub fn new(marker_size: f64) -> Self {
+        Self {
+            marker_size,
+            show_radii: false,
+        }
+    }
+
+    pub fn with_radii(self) -> Self {
+        Self {
+            show_radii: true,
+            ..self
+        }
+    }
+
+    // The color seen along the ray with the gizmos drawn over `color`, which is what the ray saw
+    // of the scene.
+    pub fn overlay(&self, world: &World, ray: Ray, color: Color) -> Color {
+        let scene_distance = world
+            .intersect(ray)
+            .hit()
+            .map_or(f64::INFINITY, |hit| hit.t);
+        let lights = world.lights.iter().filter_map(|light| match light {
+            Light::Point(l) => Some(l),
+            Light::Directional(_) => None,
+        });
+
+        let mut color = color;
+        for light in lights {
+            let glow = marker_color(light.intensity);
+            if self.show_radii && light.radius > 0.0 {
+                let wires = crossings(ray, light.position, light.radius)
+                    .iter()
+                    .filter(|&&t| t > 0.0 && t < scene_distance)
+                    .filter(|&&t| on_wire(ray.position(t), light.position, light.radius))
+                    .count();
+                for _ in 0..wires {
+                    color = color * (1.0 - WIRE_OPACITY) + glow * WIRE_OPACITY;
+                }
+            }
+            let radius = self.marker_size / 2.0;
+            let hits_marker = crossings(ray, light.position, radius)
+                .iter()
+                .any(|&t| t > 0.0 && t < scene_distance);
+            if hits_marker {
+                return glow;
+            }
+        }
+        color
+    }
+}
+
+// A light's color at full brightness, so dim and bright lights are both easy to see.
+fn marker_color(intensity: Color) -> Color {
+    let brightest = intensity[0].max(intensity[1]).max(intensity[2]);
+    if brightest > 0.0 {
+        intensity * (1.0 / brightest)
+    } else {
+        Color::WHITE
+    }
+}
+
+// Where the ray crosses the sphere, if it does.
+fn crossings(ray: Ray, center: Point, radius: f64) -> Vec<f64> {
+    let to_origin = ray.origin - center;
+    let a = ray.direction.dot(&ray.direction);
+    let b = 2.0 * ray.direction.dot(&to_origin);
+    let c = to_origin.dot(&to_origin) - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    let root = discriminant.sqrt();
+    vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+}
+
+// Whether the point on the sphere is on one of the wireframe's lines of latitude or longitude.
+fn on_wire(p: Point, center: Point, radius: f64) -> bool {
+    let d = (p - center) * (1.0 / radius);
+    let latitude = d[1].clamp(-1.0, 1.0).acos();
+    let longitude = d[2].atan2(d[0]);
+    let near_line = |angle: f64| {
+        let lines = angle / PI * WIRE_LINES;
+        (lines - lines.round()).abs() < WIRE_WIDTH / 2.0
+    };
+    near_line(latitude) || near_line(longitude)
+}
+
+// A black body style color ramp: black, blue, red, yellow, then white at 1 and above.
+pub fn heat(fraction: f64) -> Color {
+    let stops = [
+        Color::BLACK,
+        Color::new(0.0, 0.0, 1.0),
+        Color::new(1.0, 0.0, 0.0),
+        Color::new(1.0, 1.0, 0.0),
+        Color::WHITE,
+    ];
+    let position = fraction.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (position as usize).min(stops.len() - 2);
+    stops[index].interpolate(&stops[index + 1], position - index as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, body::Body, fuzzy_eq::FuzzyEq, light::PointLight, matrix::Matrix,
+        sphere::Sphere, vector::Vector,
+    };
+
+    #[test]
+    fn the_heat_ramp_goes_from_black_to_white() {
+        assert_fuzzy_eq!(Color::BLACK, heat(0.0));
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), heat(0.5));
+        assert_fuzzy_eq!(Color::new(0.5, 0.0, 0.5), heat(0.375));
+        assert_fuzzy_eq!(Color::WHITE, heat(1.0));
+        assert_fuzzy_eq!(Color::WHITE, heat(3.0));
+    }
+
+    #[test]
+    fn the_bounds_view_shows_how_many_boxes_a_ray_tested() {
+        let w = World::new(
+            vec![Sphere::default().into(), Sphere::default().into()],
+            vec![],
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let color = DebugView::BoundsTests { max: 4 }.color_at(&w, r);
+
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), color);
+    }
+
+    fn lit_world(bodies: Vec<Body>) -> World {
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::new(2.0, 1.0, 0.0))
+            .with_radius(2.0, 4);
+        World::new(bodies, vec![light.into()])
+    }
+
+    #[test]
+    fn lights_are_marked_in_their_color_unless_something_is_in_front() {
+        let gizmos = LightGizmos::new(0.2);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let blocker = Sphere::default()
+            .with_transform(Matrix::translate(0.0, 0.0, -3.0) * Matrix::scale(0.5, 0.5, 0.5));
+
+        let seen = gizmos.overlay(&lit_world(vec![]), r, Color::BLACK);
+        let hidden = gizmos.overlay(&lit_world(vec![blocker.into()]), r, Color::BLACK);
+
+        assert_fuzzy_eq!(Color::new(1.0, 0.5, 0.0), seen);
+        assert_fuzzy_eq!(Color::BLACK, hidden);
+    }
+
+    #[test]
+    fn a_lights_radius_is_drawn_as_a_see_through_wireframe() {
+        let w = lit_world(vec![]);
+        let gizmos = LightGizmos::new(0.1);
+        // Through the equator on both sides of the sphere, and then between the lines.
+        let on_wires = Ray::new(Point::new(1.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let between = Ray::new(Point::new(1.2, 0.3, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_fuzzy_eq!(Color::BLACK, gizmos.overlay(&w, on_wires, Color::BLACK));
+        let gizmos = gizmos.with_radii();
+        assert_fuzzy_eq!(
+            Color::new(0.75, 0.375, 0.0),
+            gizmos.overlay(&w, on_wires, Color::BLACK)
+        );
+        assert_fuzzy_eq!(Color::BLACK, gizmos.overlay(&w, between, Color::BLACK));
+    }
+}
@@ -0,0 +1,217 @@
+use std::io::{self, Write};
+
+use crate::{
+    camera::{Camera, Projection},
+    canvas::{rgba, Canvas},
+    color::Color,
+    parallel,
+    point::Point,
+    ray::Ray,
+    vector::Vector,
+    world::World,
+};
+
+// How far away what's seen in each pixel is, in the world's own units, for compositing and for
+// turning the image back into 3D. Depth is measured along the way the camera faces, so a flat
+// wall facing the camera is the same depth all over. Stereo panoramas face every way, so for them
+// it's the distance from the eye instead. Pixels that only see the sky have no depth.
+pub struct DepthMap {
+    pub width: usize,
+    pub height: usize,
+    depths: Vec<Option<f64>>,
+}
+
+// How far along the ray each unit of depth is.
+fn distance_along(camera: &Camera, ray: Ray) -> f64 {
+    match camera.projection {
+        Projection::Perspective => {
+            let forward = (camera.transform.inverse() * Vector::new(0.0, 0.0, -1.0)).normalize();
+            1.0 / ray.direction.dot(&forward)
+        }
+        Projection::StereoPanorama { .. } => 1.0,
+    }
+}
+
+impl DepthMap {
+    pub fn at(&self, x: usize, y: usize) -> Option<f64> {
+        self.depths[y * self.width + x]
+    }
+
+    // The depths as a grey image, white up close fading to black at `max` and beyond, with
+    // nothing at all black too.
+    pub fn to_canvas(&self, max: f64) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let shade = self
+                    .at(x, y)
+                    .map_or(0.0, |depth| (1.0 - depth / max).clamp(0.0, 1.0));
+                canvas.write_pixel(x, y, Color::new(shade, shade, shade));
+            }
+        }
+        canvas
+    }
+
+    // Where the surface seen in each pixel is in the world, worked back out from its depth and
+    // the camera's ray through the pixel, as (x, y, point). The camera must be the one the map was
+    // made with.
+    pub fn points(&self, camera: &Camera) -> Vec<(usize, usize, Point)> {
+        camera
+            .rays()
+            .filter_map(|(x, y, ray)| {
+                let depth = self.at(x, y)?;
+                Some((x, y, ray.position(depth * distance_along(camera, ray))))
+            })
+            .collect()
+    }
+
+    // Writes the visible surface as an ASCII PLY point cloud, one vertex per pixel with a depth,
+    // colored by that pixel of the render the same way to_png would write it.
+    pub fn write_ply<W: Write>(
+        &self,
+        camera: &Camera,
+        beauty: &Canvas,
+        mut w: W,
+    ) -> io::Result<()> {
+        if beauty.width != self.width || beauty.height != self.height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the render must be the same size as the depth map",
+            ));
+        }
+        let points = self.points(camera);
+        write!(
+            w,
+            concat!(
+                "ply\n",
+                "format ascii 1.0\n",
+                "element vertex {}\n",
+                "property float x\n",
+                "property float y\n",
+                "property float z\n",
+                "property uchar red\n",
+                "property uchar green\n",
+                "property uchar blue\n",
+                "end_header\n"
+            ),
+            points.len()
+        )?;
+        for (x, y, p) in points {
+            let [r, g, b, _] = rgba(beauty.read_pixel(x, y));
+            writeln!(w, "{} {} {} {} {} {}", p[0], p[1], p[2], r, g, b)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn depth_map(world: &World, camera: &Camera) -> DepthMap {
+    let pixels: Vec<(usize, usize)> = (0..camera.vsize)
+        .flat_map(|y| (0..camera.hsize).map(move |x| (x, y)))
+        .collect();
+    let depths = parallel::map(pixels, |(x, y)| {
+        let pick = world.pick(x, y, camera)?;
+        Some(pick.t / distance_along(camera, camera.ray_for_pixel(x, y)))
+    });
+    DepthMap {
+        width: camera.hsize,
+        height: camera.vsize,
+        depths,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        fuzzy_eq::FuzzyEq,
+        matrix::{Matrix, Rotation},
+        plane::Plane,
+        sphere::Sphere,
+    };
+
+    // A wall facing the camera 5 units in front of it.
+    fn wall() -> World {
+        let wall = Plane::default().with_transform(
+            Matrix::translate(0.0, 0.0, 5.0) * Matrix::rotate(Rotation::X, FRAC_PI_2),
+        );
+        World::new(vec![wall.into()], vec![])
+    }
+
+    #[test]
+    fn a_wall_facing_the_camera_is_the_same_depth_all_over() {
+        let camera = Camera::new(9, 9, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let depths = depth_map(&wall(), &camera);
+
+        assert_fuzzy_eq!(5.0, depths.at(4, 4).unwrap());
+        assert_fuzzy_eq!(5.0, depths.at(0, 8).unwrap());
+        assert_fuzzy_eq!(0.5, depths.to_canvas(10.0).read_pixel(2, 3)[0]);
+    }
+
+    #[test]
+    fn points_come_back_out_where_the_camera_saw_them() {
+        let world = World::quickstart();
+        let camera = Camera::quickstart(16, 9);
+
+        let depths = depth_map(&world, &camera);
+        let points = depths.points(&camera);
+
+        // The sky has no depth.
+        assert!(depths.at(0, 0).is_none());
+        assert!(!points.is_empty());
+        for (x, y, p) in points {
+            assert_fuzzy_eq!(world.pick(x, y, &camera).unwrap().position, p);
+        }
+    }
+
+    #[test]
+    fn writing_a_point_cloud() {
+        let world = World::new(
+            vec![Sphere::default()
+                .with_transform(Matrix::translate(0.0, 0.0, 5.0))
+                .into()],
+            vec![],
+        );
+        let camera = Camera::new(3, 3, 0.1).look_at_from_position(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let mut beauty = Canvas::new(3, 3);
+        beauty.write_pixel(1, 1, Color::new(1.0, 0.5, 0.0));
+
+        let mut ply = Vec::new();
+        depth_map(&world, &camera)
+            .write_ply(&camera, &beauty, &mut ply)
+            .unwrap();
+        let ply = String::from_utf8(ply).unwrap();
+
+        assert!(ply.starts_with("ply\nformat ascii 1.0\nelement vertex 9\n"));
+        assert!(ply.contains("end_header\n"));
+        // The middle pixel sees the front of the sphere.
+        let middle = ply.lines().nth(10 + 4).unwrap();
+        let numbers: Vec<f64> = middle.split(' ').map(|n| n.parse().unwrap()).collect();
+        assert_fuzzy_eq!(0.0, numbers[0]);
+        assert_fuzzy_eq!(4.0, numbers[2]);
+        assert_eq!([255.0, 128.0, 0.0], numbers[3..6]);
+    }
+
+    #[test]
+    fn a_render_of_another_size_cant_color_the_point_cloud() {
+        let camera = Camera::new(3, 3, 0.1);
+        let depths = depth_map(&wall(), &camera);
+
+        let err = depths
+            .write_ply(&camera, &Canvas::new(4, 3), Vec::new())
+            .unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    }
+}
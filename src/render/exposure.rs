@@ -0,0 +1,233 @@
+use super::RenderSettings;
+use crate::{camera::Camera, parallel, world::World};
+
+// How much brighter or darker the render is made than the light in the scene, like a camera's
+// exposure setting. (Not to be confused with render_exposure, which averages snapshots of a
+// world taken while the shutter was open.)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exposure {
+    // Colors are doubled for every stop up and halved for every stop down.
+    Stops(f64),
+    // Measured from the scene before rendering, so a new scene comes out about right without
+    // tuning its lights.
+    Auto(AutoExposure),
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Exposure::Stops(0.0)
+    }
+}
+
+impl Exposure {
+    // How much colors are scaled by. Auto exposure is measured when a render starts, and counts
+    // as no change until then.
+    pub fn scale(&self) -> f64 {
+        match self {
+            Exposure::Stops(stops) => stops.exp2(),
+            Exposure::Auto(_) => 1.0,
+        }
+    }
+}
+
+// How the scene's brightness is measured from the probe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metering {
+    // The average luminance, on a log scale so a few bright highlights or a dark corner don't
+    // throw it off.
+    Average,
+    // The luminance that this fraction of the probe is darker than, e.g. 0.95 to expose for the
+    // highlights.
+    Percentile(f64),
+}
+
+// Auto exposure renders a quick probe of about probe_size x probe_size pixels (fewer along the
+// image's shorter side) from the first world, measures it, and picks the exposure that makes
+// what it measured come out at `key`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoExposure {
+    pub metering: Metering,
+    pub key: f64,
+    pub probe_size: usize,
+}
+
+impl Default for AutoExposure {
+    // The average brought to middle grey.
+    fn default() -> Self {
+        Self {
+            metering: Metering::Average,
+            key: 0.18,
+            probe_size: 32,
+        }
+    }
+}
+
+impl AutoExposure {
+    // Highlights brought to just under white.
+    pub fn highlights() -> Self {
+        Self {
+            metering: Metering::Percentile(0.95),
+            key: 0.9,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_probe_size(self, probe_size: usize) -> Self {
+        if probe_size == 0 {
+            panic!("the exposure probe needs at least one pixel")
+        }
+        Self { probe_size, ..self }
+    }
+
+    // The exposure, in stops, for the world as the camera sees it. Render an animation's first
+    // frame with this and pin it with Exposure::Stops, or every frame is measured afresh and the
+    // brightness can flicker.
+    pub fn measure(&self, world: &World, camera: &Camera) -> f64 {
+        let long_side = camera.hsize.max(camera.vsize) as f64;
+        let step = (long_side / self.probe_size as f64).max(1.0);
+        let (columns, rows) = (
+            ((camera.hsize as f64 / step).round() as usize).max(1),
+            ((camera.vsize as f64 / step).round() as usize).max(1),
+        );
+        let luminances: Vec<Vec<f64>> = parallel::map((0..rows).collect(), |row| {
+            (0..columns)
+                .map(|column| {
+                    let x = (column as f64 + 0.5) / columns as f64 * camera.hsize as f64;
+                    let y = (row as f64 + 0.5) / rows as f64 * camera.vsize as f64;
+                    world.color_at(camera.ray_for_position(x, y)).luminance()
+                })
+                .collect()
+        });
+        let mut luminances: Vec<f64> = luminances.into_iter().flatten().collect();
+
+        let measured = match self.metering {
+            Metering::Average => {
+                // Nudged off zero so black pixels don't take the log to minus infinity.
+                let log_sum: f64 = luminances.iter().map(|l| (l.max(0.0) + 1e-4).ln()).sum();
+                (log_sum / luminances.len() as f64).exp()
+            }
+            Metering::Percentile(p) => {
+                luminances.sort_by(|a, b| a.total_cmp(b));
+                let index = (p.clamp(0.0, 1.0) * (luminances.len() - 1) as f64).round();
+                luminances[index as usize]
+            }
+        };
+        if measured <= 1e-3 {
+            // Too dark to tell, e.g. nothing is lit.
+            return 0.0;
+        }
+        (self.key / measured).log2()
+    }
+}
+
+impl RenderSettings {
+    pub fn with_exposure(self, stops: f64) -> Self {
+        Self {
+            exposure: Exposure::Stops(stops),
+            ..self
+        }
+    }
+
+    pub fn with_auto_exposure(self, auto_exposure: AutoExposure) -> Self {
+        Self {
+            exposure: Exposure::Auto(auto_exposure),
+            ..self
+        }
+    }
+
+    // These settings with auto exposure measured for the render, if it's on.
+    pub(super) fn metered(&self, worlds: &[World], camera: &Camera) -> RenderSettings {
+        match self.exposure {
+            Exposure::Auto(auto) => self.clone().with_exposure(auto.measure(&worlds[0], camera)),
+            Exposure::Stops(_) => self.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        color::Color,
+        environment::Environment,
+        fuzzy_eq::FuzzyEq,
+        render::{render, DebugView},
+    };
+
+    fn sky(color: Color) -> World {
+        World::default().with_environment(Environment::Color(color))
+    }
+
+    #[test]
+    fn auto_exposure_brings_the_scene_to_the_key() {
+        let camera = Camera::quickstart(16, 9);
+        let dim = sky(Color::new(0.045, 0.045, 0.045));
+
+        let stops = AutoExposure::default().measure(&dim, &camera);
+
+        assert!((stops - 2.0).abs() < 0.01);
+        let settings = RenderSettings::default().with_auto_exposure(AutoExposure::default());
+        let output = render(&dim, &camera, &settings);
+        let pixel = output.canvas.read_pixel(3, 4);
+        assert!((pixel[0] - 0.18).abs() < 0.001);
+    }
+
+    #[test]
+    fn percentile_metering_exposes_for_the_highlights() {
+        let camera = Camera::quickstart(32, 32);
+        let world = World::quickstart();
+
+        // The brightest part of the scene comes out white.
+        let brightest = AutoExposure {
+            metering: Metering::Percentile(1.0),
+            key: 1.0,
+            probe_size: 32,
+        };
+        let stops = brightest.measure(&world, &camera);
+        let output = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_exposure(stops),
+        );
+        let max = (0..32)
+            .flat_map(|y| (0..32).map(move |x| (x, y)))
+            .map(|(x, y)| output.canvas.read_pixel(x, y).luminance())
+            .fold(0.0, f64::max);
+        assert!((max - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn black_scenes_are_left_alone() {
+        let camera = Camera::quickstart(8, 8);
+
+        assert_fuzzy_eq!(
+            0.0,
+            AutoExposure::default().measure(&sky(Color::BLACK), &camera)
+        );
+    }
+
+    #[test]
+    fn exposure_doesnt_brighten_debug_views() {
+        let camera = Camera::quickstart(8, 8);
+        let world = World::quickstart();
+        let view = DebugView::BoundsTests { max: 4 };
+        let plain = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_debug_view(view),
+        );
+        let bright = render(
+            &world,
+            &camera,
+            &RenderSettings::default()
+                .with_debug_view(view)
+                .with_exposure(2.0),
+        );
+
+        assert_fuzzy_eq!(
+            plain.canvas.read_pixel(4, 4),
+            bright.canvas.read_pixel(4, 4)
+        );
+    }
+}
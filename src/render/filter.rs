@@ -0,0 +1,283 @@
+use std::fmt;
+
+use crate::color::Color;
+
+// How much a sample counts towards a pixel, by where it landed relative to the pixel's middle.
+// Samples are taken all over the area the filter reaches, which can be more than the pixel
+// itself, so that neighboring pixels share some of each other's detail and edges come out
+// smoother than a plain average would make them.
+pub trait Filter: fmt::Debug + Send + Sync {
+    // How far the filter reaches from the middle of the pixel, in pixels, across and down.
+    fn radius(&self) -> f64;
+
+    // The weight of a sample (dx, dy) pixels from the middle of the pixel. Only asked for samples
+    // within the radius. It can be negative, to sharpen.
+    fn weight(&self, dx: f64, dy: f64) -> f64;
+
+    // How the filter is written in render reports, e.g. "tent 1". Filters that render::parse_filter
+    // can't read back can't be used to render from a report again.
+    fn name(&self) -> String;
+}
+
+// Every sample in the pixel counts the same. The same as a plain average.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BoxFilter;
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        0.5
+    }
+
+    fn weight(&self, _dx: f64, _dy: f64) -> f64 {
+        1.0
+    }
+
+    fn name(&self) -> String {
+        "box".to_string()
+    }
+}
+
+// Weights fall off in a straight line to nothing at the radius.
+#[derive(Clone, Copy, Debug)]
+pub struct TentFilter {
+    pub radius: f64,
+}
+
+impl Default for TentFilter {
+    fn default() -> Self {
+        Self { radius: 1.0 }
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn weight(&self, dx: f64, dy: f64) -> f64 {
+        let tent = |d: f64| (1.0 - d.abs() / self.radius).max(0.0);
+        tent(dx) * tent(dy)
+    }
+
+    fn name(&self) -> String {
+        format!("tent {}", self.radius)
+    }
+}
+
+// A bell curve, shifted down so it reaches nothing at the radius. The bigger alpha, the narrower
+// the bell and the sharper the image.
+#[derive(Clone, Copy, Debug)]
+pub struct GaussianFilter {
+    pub radius: f64,
+    pub alpha: f64,
+}
+
+impl Default for GaussianFilter {
+    fn default() -> Self {
+        Self {
+            radius: 1.5,
+            alpha: 2.0,
+        }
+    }
+}
+
+impl Filter for GaussianFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn weight(&self, dx: f64, dy: f64) -> f64 {
+        let edge = (-self.alpha * self.radius * self.radius).exp();
+        let gaussian = |d: f64| ((-self.alpha * d * d).exp() - edge).max(0.0);
+        gaussian(dx) * gaussian(dy)
+    }
+
+    fn name(&self) -> String {
+        format!("gaussian {} {}", self.radius, self.alpha)
+    }
+}
+
+// Mitchell and Netravali's cubic, which has small negative lobes that keep edges crisp without
+// much ringing. The defaults, b = c = 1/3, are the ones the paper recommends.
+#[derive(Clone, Copy, Debug)]
+pub struct MitchellFilter {
+    pub radius: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl Default for MitchellFilter {
+    fn default() -> Self {
+        Self {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+}
+
+impl MitchellFilter {
+    // The cubic over [-2, 2].
+    fn cubic(&self, x: f64) -> f64 {
+        let (b, c) = (self.b, self.c);
+        let x = x.abs();
+        let value = if x < 1.0 {
+            (12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                + (6.0 - 2.0 * b)
+        } else if x < 2.0 {
+            (-b - 6.0 * c) * x * x * x
+                + (6.0 * b + 30.0 * c) * x * x
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c)
+        } else {
+            0.0
+        };
+        value / 6.0
+    }
+}
+
+impl Filter for MitchellFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn weight(&self, dx: f64, dy: f64) -> f64 {
+        let scale = 2.0 / self.radius;
+        self.cubic(dx * scale) * self.cubic(dy * scale)
+    }
+
+    fn name(&self) -> String {
+        format!("mitchell {} {} {}", self.radius, self.b, self.c)
+    }
+}
+
+// Reads back a built-in filter from its name.
+pub fn parse_filter(s: &str) -> Option<Box<dyn Filter>> {
+    let mut parts = s.split_whitespace();
+    let kind = parts.next()?;
+    let numbers: Vec<f64> = parts.map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let filter: Box<dyn Filter> = match (kind, numbers.as_slice()) {
+        ("box", []) => Box::new(BoxFilter),
+        ("tent", [radius]) => Box::new(TentFilter { radius: *radius }),
+        ("gaussian", [radius, alpha]) => Box::new(GaussianFilter {
+            radius: *radius,
+            alpha: *alpha,
+        }),
+        ("mitchell", [radius, b, c]) => Box::new(MitchellFilter {
+            radius: *radius,
+            b: *b,
+            c: *c,
+        }),
+        _ => return None,
+    };
+    Some(filter)
+}
+
+// The color of a pixel from samples given as (dx, dy, color), (dx, dy) being where the sample
+// landed relative to the pixel's middle. With only a few samples, a filter with negative lobes
+// can end up with weights that cancel out, in which case the samples are just averaged.
+pub fn filtered_average(
+    filter: &dyn Filter,
+    samples: impl IntoIterator<Item = (f64, f64, Color)>,
+) -> Color {
+    let mut weighted = Color::BLACK;
+    let mut total_weight = 0.0;
+    let mut sum = Color::BLACK;
+    let mut count = 0;
+    for (dx, dy, color) in samples {
+        let weight = filter.weight(dx, dy);
+        weighted += color * weight;
+        total_weight += weight;
+        sum += color;
+        count += 1;
+    }
+    if count == 0 {
+        Color::BLACK
+    } else if total_weight > 1e-6 {
+        weighted * (1.0 / total_weight)
+    } else {
+        sum * (1.0 / count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn filters_weigh_the_middle_most() {
+        let filters: [&dyn Filter; 3] = [
+            &TentFilter::default(),
+            &GaussianFilter::default(),
+            &MitchellFilter::default(),
+        ];
+        for filter in filters.iter() {
+            let r = filter.radius();
+            assert!(filter.weight(0.0, 0.0) > filter.weight(r / 4.0, 0.0));
+            assert!(filter.weight(r / 4.0, 0.0) > filter.weight(r / 2.0, r / 2.0));
+            assert_fuzzy_eq!(0.0, filter.weight(r, 0.0));
+            assert_fuzzy_eq!(filter.weight(0.3, -0.2), filter.weight(-0.3, 0.2));
+        }
+        assert_fuzzy_eq!(0.25, TentFilter::default().weight(0.5, 0.5));
+    }
+
+    #[test]
+    fn the_mitchell_filter_has_negative_lobes() {
+        let filter = MitchellFilter::default();
+
+        assert_fuzzy_eq!(16.0 / 18.0, filter.cubic(0.0));
+        assert!(filter.weight(1.5, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn a_box_filter_is_a_plain_average() {
+        let samples = vec![
+            (-0.25, -0.25, Color::WHITE),
+            (0.25, 0.25, Color::BLACK),
+            (0.4, -0.1, Color::RED),
+        ];
+
+        let color = filtered_average(&BoxFilter, samples);
+
+        assert_fuzzy_eq!(Color::new(2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0), color);
+    }
+
+    #[test]
+    fn samples_nearer_the_middle_count_for_more() {
+        let samples = vec![(0.0, 0.0, Color::WHITE), (0.5, 0.0, Color::BLACK)];
+
+        let color = filtered_average(&TentFilter::default(), samples);
+
+        // The tent weighs the middle twice as much as halfway to its edge.
+        assert_fuzzy_eq!(Color::new(2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0), color);
+    }
+
+    #[test]
+    fn weights_that_cancel_out_fall_back_to_an_average() {
+        let filter = MitchellFilter::default();
+        let samples = vec![(1.5, 0.0, Color::WHITE), (0.0, 1.5, Color::BLACK)];
+
+        let color = filtered_average(&filter, samples);
+
+        assert_fuzzy_eq!(Color::new(0.5, 0.5, 0.5), color);
+    }
+
+    #[test]
+    fn filters_can_be_read_back_from_their_names() {
+        let filters: [&dyn Filter; 4] = [
+            &BoxFilter,
+            &TentFilter { radius: 1.25 },
+            &GaussianFilter::default(),
+            &MitchellFilter::default(),
+        ];
+        for filter in filters.iter() {
+            let read = parse_filter(&filter.name()).unwrap();
+            assert_eq!(filter.name(), read.name());
+            assert_fuzzy_eq!(filter.weight(0.3, 0.1), read.weight(0.3, 0.1));
+        }
+        assert!(parse_filter("tent").is_none());
+        assert!(parse_filter("lanczos 2").is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    bounds::{Bounded, Bounds},
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    point::Point,
+    ray::Ray,
+    vector::Vector,
+    world::World,
+};
+
+// A quick map of the scene from straight above, for checking where things are when building a
+// scene in code. Everything solid is drawn as a grey silhouette, lighter the higher its top is,
+// like a shadow map of light falling straight down. The camera is a red dot with the edges of what
+// it sees drawn out from it in yellow. x is to the right and z is up the map, so a camera looking
+// along z looks up it.
+//
+// The map is `size` pixels square and covers the camera and everything with bounds, so a ground
+// plane fills the background but doesn't make the map go on forever.
+pub fn minimap(world: &World, camera: &Camera, size: usize) -> Canvas {
+    let eye = camera.transform.inverse() * Point::new(0.0, 0.0, 0.0);
+    let extent = scene_bounds(world).map_or(Bounds::new(eye, eye), |bounds| {
+        bounds.union(&Bounds::new(eye, eye))
+    });
+    let map = Map::new(extent, size);
+    let (frustum, eye_color) = (Color::new(1.0, 0.8, 0.1), Color::new(1.0, 0.1, 0.1));
+
+    let rays: Vec<Ray> = (0..size * size)
+        .map(|i| {
+            let (x, z) = map.to_world((i % size) as f64 + 0.5, (i / size) as f64 + 0.5);
+            Ray::new(Point::new(x, map.top + 1.0, z), Vector::new(0.0, -1.0, 0.0))
+        })
+        .collect();
+    let mut canvas = Canvas::new(size, size);
+    for (i, hit) in world.trace_hits(&rays).into_iter().enumerate() {
+        if let Some(hit) = hit {
+            let height = rays[i].position(hit.t)[1];
+            let shade =
+                0.25 + 0.75 * ((height - map.bottom) / (map.top - map.bottom)).clamp(0.0, 1.0);
+            canvas.write_pixel(i % size, i / size, Color::new(shade, shade, shade));
+        }
+    }
+
+    // The edges of the view run out from the eye through the image's corners, far enough to
+    // leave the map.
+    let reach = map.span * 2.0;
+    let (w, h) = (camera.hsize as f64, camera.vsize as f64);
+    let corners: Vec<Point> = [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)]
+        .iter()
+        .map(|&(x, y)| camera.ray_for_position(x, y).position(reach))
+        .collect();
+    let from = map.to_pixels(eye);
+    for (i, &corner) in corners.iter().enumerate() {
+        let next = corners[(i + 1) % corners.len()];
+        draw_line(&mut canvas, from, map.to_pixels(corner), frustum);
+        draw_line(
+            &mut canvas,
+            map.to_pixels(corner),
+            map.to_pixels(next),
+            frustum,
+        );
+    }
+    for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+        plot(&mut canvas, from.0 + dx, from.1 + dy, eye_color);
+    }
+    canvas
+}
+
+// Where everything with bounds is, bodies and instances both.
+fn scene_bounds(world: &World) -> Option<Bounds> {
+    let bodies = world
+        .bodies
+        .iter()
+        .map(|b| b.bounds().transformed(b.transform()));
+    let instances = world
+        .instances
+        .iter()
+        .map(|i| i.bounds().transformed(i.transform()));
+    bodies
+        .chain(instances)
+        .filter(Bounds::is_finite)
+        .reduce(|a, b| a.union(&b))
+}
+
+// How the map's pixels line up with the world.
+struct Map {
+    // The corner of the map at the top left, i.e. the least x and the most z.
+    left: f64,
+    far: f64,
+    // How much of the world the map is across, and per pixel.
+    span: f64,
+    pixel_size: f64,
+    top: f64,
+    bottom: f64,
+}
+
+impl Map {
+    // Square, around the bounds with a margin of a tenth of them on every side.
+    fn new(bounds: Bounds, size: usize) -> Self {
+        let across = (bounds.max[0] - bounds.min[0]).max(bounds.max[2] - bounds.min[2]);
+        let span = (across * 1.2).max(1.0);
+        let middle = bounds.centroid();
+        Self {
+            left: middle[0] - span / 2.0,
+            far: middle[2] + span / 2.0,
+            span,
+            pixel_size: span / size as f64,
+            top: bounds.max[1],
+            bottom: bounds.min[1],
+        }
+    }
+
+    fn to_world(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.left + x * self.pixel_size,
+            self.far - y * self.pixel_size,
+        )
+    }
+
+    fn to_pixels(&self, p: Point) -> (f64, f64) {
+        (
+            (p[0] - self.left) / self.pixel_size,
+            (self.far - p[2]) / self.pixel_size,
+        )
+    }
+}
+
+// Colors every pixel the line between the two positions, in pixels, passes through, skipping any
+// off the canvas.
+fn draw_line(canvas: &mut Canvas, from: (f64, f64), to: (f64, f64), color: Color) {
+    let steps = (to.0 - from.0)
+        .abs()
+        .max((to.1 - from.1).abs())
+        .ceil()
+        .max(1.0) as usize;
+    for i in 0..=steps {
+        let t = i as f64 / steps as f64;
+        let (x, y) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+        plot(canvas, x, y, color);
+    }
+}
+
+// Colors the pixel the position is in, if it's on the canvas.
+fn plot(canvas: &mut Canvas, x: f64, y: f64, color: Color) {
+    if x >= 0.0 && y >= 0.0 && (x as usize) < canvas.width && (y as usize) < canvas.height {
+        canvas.write_pixel(x as usize, y as usize, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Matrix, plane::Plane, sphere::Sphere};
+
+    #[test]
+    fn the_minimap_shows_bodies_from_above_and_the_camera_looking_at_them() {
+        // A sphere at the origin with the camera 4 units behind it along z, looking at it.
+        let world = World::new(vec![Sphere::default().into()], vec![]);
+        let camera = Camera::new(20, 20, std::f64::consts::FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, -4.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let map = minimap(&world, &camera, 60);
+
+        // The map is 6 units across, from z = 1.5 at the top to z = -4.5 at the bottom, so the
+        // sphere's top is a quarter of the way down and the camera near the bottom.
+        assert!(map.read_pixel(30, 15)[0] > 0.95);
+        assert_fuzzy_eq!(Color::BLACK, map.read_pixel(30, 2));
+        assert_fuzzy_eq!(Color::new(1.0, 0.1, 0.1), map.read_pixel(30, 55));
+        // With a quarter turn field of view, the view's edges go out at 45 degrees from the eye.
+        let row: Vec<Color> = (0..60).map(|x| map.read_pixel(x, 45)).collect();
+        let drawn: Vec<usize> = (0..60)
+            .filter(|&x| row[x].fuzzy_eq(Color::new(1.0, 0.8, 0.1)))
+            .collect();
+        assert_eq!(2, drawn.len());
+        for x in drawn {
+            assert_fuzzy_eq!(10.0, (x as f64 + 0.5 - 30.0).abs().round());
+        }
+    }
+
+    #[test]
+    fn bodies_without_bounds_are_drawn_but_do_not_set_the_extent() {
+        let floor = Plane::default().with_transform(Matrix::translate(0.0, -1.0, 0.0));
+        let world = World::new(vec![Sphere::default().into(), floor.into()], vec![]);
+        let camera = Camera::quickstart(16, 9);
+
+        let map = minimap(&world, &camera, 32);
+
+        // The floor is below everything else, so it's the darkest grey.
+        assert_fuzzy_eq!(Color::new(0.25, 0.25, 0.25), map.read_pixel(0, 0));
+    }
+}
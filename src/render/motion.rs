@@ -0,0 +1,159 @@
+use crate::{camera::Camera, canvas::Canvas, color::Color, parallel, world::World};
+
+// How far what's seen in each pixel moved across the image since the previous frame, in pixels,
+// for denoisers that reuse earlier frames and for adding motion blur afterwards. A vector of
+// (3, -1) means the surface in the pixel was 3 pixels to the left and 1 below in the previous
+// frame.
+pub struct MotionVectors {
+    pub width: usize,
+    pub height: usize,
+    vectors: Vec<(f64, f64)>,
+}
+
+impl MotionVectors {
+    pub fn at(&self, x: usize, y: usize) -> (f64, f64) {
+        self.vectors[y * self.width + x]
+    }
+
+    // The vectors as an image that can be written to an ordinary file, the usual way: red is
+    // across and green is down, with no motion at 0.5 and `max` pixels either way at 0 or 1.
+    pub fn to_canvas(&self, max: f64) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (dx, dy) = self.at(x, y);
+                let encode = |d: f64| (0.5 + 0.5 * d / max).clamp(0.0, 1.0);
+                canvas.write_pixel(x, y, Color::new(encode(dx), encode(dy), 0.0));
+            }
+        }
+        canvas
+    }
+}
+
+// The motion vectors for a frame, given the world and camera for it and for the frame before.
+// The bodies in both worlds must be in the same order, which they are when both are made by the
+// same code for different times. Each hit point is carried along with its body to where that body
+// was placed in the previous world. Bodies that weren't in the previous world, and anything the
+// previous camera couldn't see, don't move.
+pub fn motion_vectors(
+    world: &World,
+    camera: &Camera,
+    previous_world: &World,
+    previous_camera: &Camera,
+) -> MotionVectors {
+    let pixels: Vec<(usize, usize)> = (0..camera.vsize)
+        .flat_map(|y| (0..camera.hsize).map(move |x| (x, y)))
+        .collect();
+    let vectors = parallel::map(pixels, |(x, y)| {
+        let previous = match world.pick(x, y, camera) {
+            Some(pick) => previous_world
+                .body_transform(pick.body_id)
+                .map(|transform| {
+                    let local = pick.body.transform().inverse() * pick.position;
+                    transform * local
+                })
+                .and_then(|p| previous_camera.project(p)),
+            None => previous_camera.project_direction(camera.ray_for_pixel(x, y).direction),
+        };
+        match previous {
+            Some((px, py)) => (x as f64 + 0.5 - px, y as f64 + 0.5 - py),
+            None => (0.0, 0.0),
+        }
+    });
+    MotionVectors {
+        width: camera.hsize,
+        height: camera.vsize,
+        vectors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        fuzzy_eq::FuzzyEq,
+        matrix::{Matrix, Rotation},
+        point::Point,
+        sphere::Sphere,
+        vector::Vector,
+    };
+
+    fn camera() -> Camera {
+        Camera::new(21, 21, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+
+    fn ball_at(x: f64) -> World {
+        World::new(
+            vec![Sphere::default()
+                .with_transform(Matrix::translate(x, 0.0, 0.0))
+                .into()],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn a_still_scene_has_no_motion() {
+        let v = motion_vectors(&ball_at(0.0), &camera(), &ball_at(0.0), &camera());
+
+        for (x, y) in [(10, 10), (0, 0)] {
+            let (dx, dy) = v.at(x, y);
+            assert_fuzzy_eq!(0.0, dx);
+            assert_fuzzy_eq!(0.0, dy);
+        }
+    }
+
+    #[test]
+    fn a_moving_body_moves_across_the_image() {
+        // The camera sees 8 units across its 21 pixels at the front of the ball, 4 units away, so
+        // moving the ball 1 unit moves it about 21 / 8 pixels.
+        let v = motion_vectors(&ball_at(1.0), &camera(), &ball_at(0.0), &camera());
+
+        let (dx, dy) = v.at(13, 10);
+        assert!((dx - 21.0 / 8.0).abs() < 0.05, "{}", dx);
+        assert_fuzzy_eq!(0.0, dy);
+        // The background stays put.
+        assert_fuzzy_eq!(0.0, v.at(20, 0).0);
+    }
+
+    #[test]
+    fn turning_the_camera_moves_the_background() {
+        let turned = camera().with_transform(camera().transform * Matrix::rotate(Rotation::Y, 0.1));
+
+        let v = motion_vectors(&World::default(), &turned, &World::default(), &camera());
+
+        assert!(v.at(10, 10).0.abs() > 1.0);
+    }
+
+    #[test]
+    fn projecting_undoes_casting_a_ray() {
+        let c = camera();
+        let ray = c.ray_for_pixel(3, 17);
+
+        let (x, y) = c.project(ray.position(2.5)).unwrap();
+
+        assert_fuzzy_eq!(3.5, x);
+        assert_fuzzy_eq!(17.5, y);
+        assert!(c.project(Point::new(0.0, 0.0, -10.0)).is_none());
+    }
+
+    #[test]
+    fn motion_can_be_written_as_an_image() {
+        let v = MotionVectors {
+            width: 2,
+            height: 1,
+            vectors: vec![(0.0, 0.0), (4.0, -8.0)],
+        };
+
+        let canvas = v.to_canvas(8.0);
+
+        assert_fuzzy_eq!(Color::new(0.5, 0.5, 0.0), canvas.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::new(0.75, 0.0, 0.0), canvas.read_pixel(1, 0));
+    }
+}
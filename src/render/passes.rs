@@ -0,0 +1,103 @@
+use super::{render, RenderOutput, RenderSettings};
+use crate::{
+    camera::Camera,
+    world::{MaterialOverride, World},
+};
+
+// One look to render a scene in, e.g. the finished render, a clay render for checking the
+// lighting, and a wireframe for checking the meshes.
+#[derive(Clone)]
+pub struct Pass {
+    pub name: String,
+    // Replaces the materials in the scene for this pass. Without one, the pass is the scene as
+    // it was built.
+    pub material_override: Option<MaterialOverride>,
+    pub settings: RenderSettings,
+}
+
+impl Pass {
+    pub fn new(name: &str, settings: RenderSettings) -> Self {
+        Self {
+            name: name.to_string(),
+            material_override: None,
+            settings,
+        }
+    }
+
+    pub fn beauty(settings: RenderSettings) -> Self {
+        Self::new("beauty", settings)
+    }
+
+    pub fn clay(settings: RenderSettings) -> Self {
+        Self::new("clay", settings).with_material_override(MaterialOverride::clay())
+    }
+
+    pub fn wireframe(settings: RenderSettings) -> Self {
+        Self::new("wireframe", settings).with_material_override(MaterialOverride::wireframe(0.02))
+    }
+
+    pub fn with_material_override(self, material_override: MaterialOverride) -> Self {
+        Self {
+            material_override: Some(material_override),
+            ..self
+        }
+    }
+}
+
+pub struct PassOutput {
+    pub name: String,
+    pub output: RenderOutput,
+}
+
+// Renders the world once for each pass, in order. The world is only built once, so its meshes'
+// hierarchies and its textures are shared by every pass. It's borrowed mutably to swap each
+// pass's material override in, and has its own put back afterwards.
+pub fn render_passes(world: &mut World, camera: &Camera, passes: &[Pass]) -> Vec<PassOutput> {
+    let own = world.material_override.take();
+    let outputs = passes
+        .iter()
+        .map(|pass| {
+            world.material_override = pass.material_override.clone().or_else(|| own.clone());
+            PassOutput {
+                name: pass.name.clone(),
+                output: render(world, camera, &pass.settings),
+            }
+        })
+        .collect();
+    world.material_override = own;
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn each_pass_renders_the_same_world_its_own_way() {
+        let mut world = World::quickstart();
+        let camera = Camera::quickstart(16, 9);
+        let settings = RenderSettings::default();
+        let plain = render(&world, &camera, &settings);
+
+        let outputs = render_passes(
+            &mut world,
+            &camera,
+            &[Pass::beauty(settings.clone()), Pass::clay(settings)],
+        );
+
+        assert_eq!(
+            vec!["beauty", "clay"],
+            outputs.iter().map(|o| o.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_fuzzy_eq!(
+            plain.canvas.read_pixel(8, 5),
+            outputs[0].output.canvas.read_pixel(8, 5)
+        );
+        // The clay pass is grey all over.
+        let clay = outputs[1].output.canvas.read_pixel(8, 5);
+        assert_fuzzy_eq!(clay[0], clay[1]);
+        assert_fuzzy_eq!(clay[1], clay[2]);
+        assert!(world.material_override.is_none());
+    }
+}
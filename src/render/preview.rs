@@ -0,0 +1,148 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{RenderOutput, Tile};
+use crate::{
+    canvas::{Canvas, Encoding, ToPng},
+    color::Color,
+};
+
+// Previews are only for checking on a render, so they're shrunk until neither side is bigger than
+// this, which keeps writing them cheap even for very big renders.
+const MAX_PREVIEW_SIZE: usize = 512;
+
+// Where and how often to save a preview of a render in progress, so a long render can be checked
+// on by opening the file.
+#[derive(Clone, Debug)]
+pub struct Preview {
+    pub path: PathBuf,
+    pub interval: Duration,
+}
+
+// Copies the finished tiles into a canvas as they come in and writes it out whenever the interval
+// has passed since the last time.
+pub(super) struct Previewer<'a> {
+    preview: &'a Preview,
+    tiles: Vec<Tile>,
+    state: Mutex<PreviewState>,
+    // Held while a preview is written, so two threads never write the file at once.
+    writing: Mutex<()>,
+}
+
+struct PreviewState {
+    canvas: Canvas,
+    completed_tiles: Vec<bool>,
+    last_written: Instant,
+}
+
+impl<'a> Previewer<'a> {
+    pub(super) fn new(preview: &'a Preview, previous: &RenderOutput, tiles: &[Tile]) -> Self {
+        Self {
+            preview,
+            tiles: tiles.to_vec(),
+            state: Mutex::new(PreviewState {
+                canvas: previous.canvas.clone(),
+                completed_tiles: previous.completed_tiles.clone(),
+                last_written: Instant::now(),
+            }),
+            writing: Mutex::new(()),
+        }
+    }
+
+    pub(super) fn tile_done(&self, index: usize, pixels: &[Color]) {
+        let tile = self.tiles[index];
+        let (canvas, completed_tiles) = {
+            let mut state = self.state.lock().unwrap();
+            for (j, color) in pixels.iter().enumerate() {
+                state
+                    .canvas
+                    .write_pixel(tile.x + j % tile.width, tile.y + j / tile.width, *color);
+            }
+            state.completed_tiles[index] = true;
+
+            if state.last_written.elapsed() < self.preview.interval {
+                return;
+            }
+            state.last_written = Instant::now();
+            (state.canvas.clone(), state.completed_tiles.clone())
+        };
+
+        // The other threads carry on rendering while the preview's made. If another preview is
+        // still being written, this one is skipped; the next will have these tiles too.
+        let Ok(_writing) = self.writing.try_lock() else {
+            return;
+        };
+        let preview = fill_holes(&canvas, &self.tiles, &completed_tiles).downsampled(
+            preview_factor(canvas.width, canvas.height),
+            Encoding::Linear,
+        );
+        // A preview that can't be written shouldn't stop the render, so the error is dropped;
+        // the next one will try again.
+        let _ = self.write(&preview);
+    }
+
+    // Writes next to the preview and then moves it into place, so the preview file is never half
+    // written.
+    fn write(&self, canvas: &Canvas) -> Result<(), Box<dyn std::error::Error>> {
+        let temp_path = self.preview.path.with_extension("preview-tmp");
+        canvas.to_png(fs::File::create(&temp_path)?)?;
+        fs::rename(&temp_path, &self.preview.path)?;
+        Ok(())
+    }
+}
+
+// How much to shrink a render of the size by for its preview.
+fn preview_factor(width: usize, height: usize) -> usize {
+    width.max(height).div_ceil(MAX_PREVIEW_SIZE).max(1)
+}
+
+// A copy of the canvas with the tiles that haven't been rendered yet filled in with a grey checker
+// pattern, so they stand out from tiles that are meant to be dark.
+pub fn fill_holes(canvas: &Canvas, tiles: &[Tile], completed_tiles: &[bool]) -> Canvas {
+    let mut res = canvas.clone();
+    for (tile, _) in tiles
+        .iter()
+        .zip(completed_tiles.iter())
+        .filter(|(_, &done)| !done)
+    {
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                let shade = if (x / 8 + y / 8) % 2 == 0 { 0.4 } else { 0.6 };
+                res.write_pixel(x, y, Color::new(shade, shade, shade));
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn holes_are_filled_with_a_checker() {
+        let mut canvas = Canvas::new(32, 16);
+        canvas.write_pixel(0, 0, Color::WHITE);
+        let tiles = Tile::split(32, 16, 16);
+
+        let filled = fill_holes(&canvas, &tiles, &[true, false]);
+
+        assert_fuzzy_eq!(Color::WHITE, filled.read_pixel(0, 0));
+        assert_fuzzy_eq!(Color::BLACK, filled.read_pixel(15, 15));
+        assert_fuzzy_eq!(Color::new(0.4, 0.4, 0.4), filled.read_pixel(16, 0));
+        assert_fuzzy_eq!(Color::new(0.6, 0.6, 0.6), filled.read_pixel(24, 0));
+    }
+
+    #[test]
+    fn big_renders_get_smaller_previews() {
+        assert_eq!(1, preview_factor(320, 240));
+        assert_eq!(1, preview_factor(MAX_PREVIEW_SIZE, 100));
+        assert_eq!(2, preview_factor(100, MAX_PREVIEW_SIZE + 1));
+        assert_eq!(8, preview_factor(3840, 2160));
+    }
+}
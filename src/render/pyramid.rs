@@ -0,0 +1,163 @@
+use super::{pixel_color, RenderSettings};
+use crate::{camera::Camera, canvas::Canvas, color::Color, parallel, world::World};
+
+// Renders the image coarse to fine, for previews that need to keep up with a moving camera. The
+// first level traces one pixel in every `coarsest` x `coarsest` block, and each level after that
+// halves the blocks until every pixel is traced. A traced pixel fills the rest of its block until
+// a finer level gets to it, so every level is a whole image, and pixels traced for a coarser level
+// are kept rather than traced again. After each level `level_done` is called with the block size
+// and the image so far. Halving only ends at single pixels from a power of two, so `coarsest` is
+// rounded up to one.
+//
+// Cancelling the render stops it between levels, returning the finest image finished so far, so a
+// preview can start over as soon as the camera moves and refine for as long as it stays still.
+pub fn render_pyramid(
+    world: &World,
+    camera: &Camera,
+    settings: &RenderSettings,
+    coarsest: usize,
+    mut level_done: impl FnMut(usize, &Canvas),
+) -> Canvas {
+    let coarsest = coarsest.next_power_of_two();
+    let worlds = std::slice::from_ref(world);
+    let settings = &settings.metered(worlds, camera);
+    let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+    let mut block = coarsest;
+    loop {
+        if settings.cancellation_token.is_cancelled() {
+            break;
+        }
+
+        // The pixels coarser levels traced are the ones on the corners of the old blocks.
+        let traced_before = |x: usize, y: usize| {
+            block != coarsest && x.is_multiple_of(block * 2) && y.is_multiple_of(block * 2)
+        };
+        let rows: Vec<(usize, Vec<(usize, Color)>)> =
+            parallel::map((0..camera.vsize).step_by(block).collect(), |y| {
+                let colors = (0..camera.hsize)
+                    .step_by(block)
+                    .filter(|&x| !traced_before(x, y))
+                    .map(|x| (x, pixel_color(worlds, camera, settings, x, y)))
+                    .collect();
+                (y, colors)
+            });
+
+        for (y, colors) in rows {
+            for (x, color) in colors {
+                fill_block(&mut canvas, x, y, block, color);
+            }
+        }
+        level_done(block, &canvas);
+
+        if block == 1 {
+            break;
+        }
+        block /= 2;
+    }
+    canvas
+}
+
+fn fill_block(canvas: &mut Canvas, x: usize, y: usize, block: usize, color: Color) {
+    for by in y..(y + block).min(canvas.height) {
+        for bx in x..(x + block).min(canvas.width) {
+            canvas.write_pixel(bx, by, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        fuzzy_eq::FuzzyEq,
+        light::PointLight,
+        point::Point,
+        render::{render, CancellationToken},
+        sphere::Sphere,
+        vector::Vector,
+    };
+
+    fn world() -> World {
+        World::new(
+            vec![Sphere::default().into()],
+            vec![PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE).into()],
+        )
+    }
+
+    fn camera() -> Camera {
+        Camera::new(13, 9, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn each_level_halves_the_blocks_and_ends_at_the_full_render() {
+        let (w, c) = (world(), camera());
+        let settings = RenderSettings::default();
+
+        let mut levels = Vec::new();
+        let canvas = render_pyramid(&w, &c, &settings, 8, |block, _| levels.push(block));
+
+        assert_eq!(vec![8, 4, 2, 1], levels);
+        let full = render(&w, &c, &settings).canvas;
+        for y in 0..c.vsize {
+            for x in 0..c.hsize {
+                assert_fuzzy_eq!(full.read_pixel(x, y), canvas.read_pixel(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn a_coarse_level_fills_each_block_with_its_corner() {
+        let (w, c) = (world(), camera());
+
+        let mut coarse = None;
+        render_pyramid(&w, &c, &RenderSettings::default(), 4, |block, canvas| {
+            if block == 4 {
+                coarse = Some(canvas.clone());
+            }
+        });
+
+        let coarse = coarse.unwrap();
+        for (x, y) in [(0, 0), (5, 6), (12, 8)] {
+            assert_fuzzy_eq!(
+                coarse.read_pixel(x - x % 4, y - y % 4),
+                coarse.read_pixel(x, y)
+            );
+        }
+    }
+
+    #[test]
+    fn a_cancelled_pyramid_stops_before_the_next_level() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let settings = RenderSettings::default().with_cancellation_token(token);
+
+        let mut levels = 0;
+        render_pyramid(&world(), &camera(), &settings, 8, |_, _| levels += 1);
+
+        assert_eq!(0, levels);
+    }
+
+    #[test]
+    fn the_coarsest_level_is_rounded_up_to_a_power_of_two() {
+        for (coarsest, first) in [(3, 4), (0, 1)] {
+            let mut levels = Vec::new();
+            render_pyramid(
+                &world(),
+                &camera(),
+                &RenderSettings::default(),
+                coarsest,
+                |block, _| levels.push(block),
+            );
+
+            assert_eq!(first, levels[0]);
+            assert_eq!(Some(&1), levels.last());
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{
+    parse_filter, AutoExposure, DebugView, Exposure, LightGizmos, Metering, Quality, RenderSettings,
+};
+use crate::{parallel, sampler::Correlation};
+
+// Everything needed to render an image again exactly the same way, saved next to the image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderReport {
+    pub seed: u64,
+    pub tile_size: usize,
+    pub debug_view: Option<String>,
+    pub light_gizmos: Option<String>,
+    // Left out when the exposure isn't changed.
+    pub exposure: Option<String>,
+    pub quality: Quality,
+    pub samples_per_pixel: usize,
+    pub filter: String,
+    // Left out when the samples are scrambled, as they are by default.
+    pub correlation: Option<String>,
+    // Left out when the render wasn't spectral.
+    #[cfg(feature = "spectral")]
+    pub wavelengths: Option<usize>,
+    // A hash of the settings that change the image, to check two renders used the same ones.
+    pub settings_hash: u64,
+    pub crate_version: String,
+    pub duration: Duration,
+    pub threads: usize,
+}
+
+impl RenderSettings {
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    // The file name to save a render with these settings to, e.g. "output-seed42.png", so
+    // renders with different seeds don't overwrite each other.
+    pub fn seeded_filename(&self, name: &str, ext: &str) -> String {
+        format!("{}-seed{}.{}", name, self.seed, ext)
+    }
+
+    // The settings a report was written for. Settings that don't change the image, like the time
+    // budget and progress bar, are left at their defaults.
+    pub fn from_report(path: &Path) -> io::Result<Self> {
+        let report = RenderReport::read(path)?;
+        let debug_view = match report.debug_view.as_deref() {
+            None => None,
+            Some(view) => Some(
+                parse_debug_view(view)
+                    .ok_or_else(|| invalid_data(format!("unknown debug view '{}'", view)))?,
+            ),
+        };
+        let light_gizmos = match report.light_gizmos.as_deref() {
+            None => None,
+            Some(gizmos) => Some(
+                parse_light_gizmos(gizmos)
+                    .ok_or_else(|| invalid_data(format!("unknown light gizmos '{}'", gizmos)))?,
+            ),
+        };
+        let exposure = match report.exposure.as_deref() {
+            None => Exposure::default(),
+            Some(exposure) => parse_exposure(exposure)
+                .ok_or_else(|| invalid_data(format!("unknown exposure '{}'", exposure)))?,
+        };
+        let filter = parse_filter(&report.filter)
+            .ok_or_else(|| invalid_data(format!("unknown filter '{}'", report.filter)))?;
+        let correlation = match report.correlation.as_deref() {
+            None => Correlation::default(),
+            Some(correlation) => parse_correlation(correlation)
+                .ok_or_else(|| invalid_data(format!("unknown correlation '{}'", correlation)))?,
+        };
+        let settings = RenderSettings {
+            seed: report.seed,
+            tile_size: report.tile_size,
+            debug_view,
+            quality: report.quality,
+            samples_per_pixel: report.samples_per_pixel,
+            filter: Arc::from(filter),
+            correlation,
+            light_gizmos,
+            exposure,
+            #[cfg(feature = "spectral")]
+            wavelengths: report.wavelengths,
+            ..RenderSettings::default()
+        };
+        if settings_hash(&settings) != report.settings_hash {
+            return Err(invalid_data(
+                "the report's settings don't match its settings hash".to_string(),
+            ));
+        }
+        Ok(settings)
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn format_debug_view(view: &DebugView) -> String {
+    match view {
+        DebugView::BoundsTests { max } => format!("bounds_tests {}", max),
+    }
+}
+
+fn parse_debug_view(s: &str) -> Option<DebugView> {
+    let mut parts = s.split_whitespace();
+    match (parts.next(), parts.next().and_then(|m| m.parse().ok())) {
+        (Some("bounds_tests"), Some(max)) => Some(DebugView::BoundsTests { max }),
+        _ => None,
+    }
+}
+
+fn format_light_gizmos(gizmos: &LightGizmos) -> String {
+    if gizmos.show_radii {
+        format!("{} radii", gizmos.marker_size)
+    } else {
+        gizmos.marker_size.to_string()
+    }
+}
+
+fn parse_light_gizmos(s: &str) -> Option<LightGizmos> {
+    let mut parts = s.split_whitespace();
+    let gizmos = LightGizmos::new(parts.next()?.parse().ok()?);
+    match parts.next() {
+        None => Some(gizmos),
+        Some("radii") => Some(gizmos.with_radii()),
+        Some(_) => None,
+    }
+}
+
+// Auto exposure is written as how it measures rather than what it measured, like the settings
+// were, e.g. "auto 0.18 32 average" or "auto 0.9 32 percentile 0.95".
+fn format_exposure(exposure: &Exposure) -> Option<String> {
+    match exposure {
+        Exposure::Stops(stops) if *stops == 0.0 => None,
+        Exposure::Stops(stops) => Some(stops.to_string()),
+        Exposure::Auto(auto) => Some(match auto.metering {
+            Metering::Average => format!("auto {} {} average", auto.key, auto.probe_size),
+            Metering::Percentile(p) => {
+                format!("auto {} {} percentile {}", auto.key, auto.probe_size, p)
+            }
+        }),
+    }
+}
+
+fn parse_exposure(s: &str) -> Option<Exposure> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    let exposure = match parts.as_slice() {
+        [stops] => Exposure::Stops(stops.parse().ok()?),
+        ["auto", key, probe_size, metering @ ..] => {
+            let metering = match metering {
+                ["average"] => Metering::Average,
+                ["percentile", p] => Metering::Percentile(p.parse().ok()?),
+                _ => return None,
+            };
+            Exposure::Auto(AutoExposure {
+                metering,
+                key: key.parse().ok()?,
+                probe_size: probe_size.parse().ok()?,
+            })
+        }
+        _ => return None,
+    };
+    Some(exposure)
+}
+
+fn format_correlation(correlation: Correlation) -> Option<String> {
+    match correlation {
+        Correlation::Scrambled => None,
+        Correlation::Shared => Some("shared".to_string()),
+    }
+}
+
+fn parse_correlation(s: &str) -> Option<Correlation> {
+    match s {
+        "scrambled" => Some(Correlation::Scrambled),
+        "shared" => Some(Correlation::Shared),
+        _ => None,
+    }
+}
+
+fn format_quality(quality: Quality) -> &'static str {
+    match quality {
+        Quality::Preview => "preview",
+        Quality::Full => "full",
+    }
+}
+
+fn parse_quality(s: &str) -> Option<Quality> {
+    match s {
+        "preview" => Some(Quality::Preview),
+        "full" => Some(Quality::Full),
+        _ => None,
+    }
+}
+
+// FNV-1a over the settings that change the image. Unlike the standard library's hasher it's the
+// same on every machine and Rust version.
+pub(super) fn settings_hash(settings: &RenderSettings) -> u64 {
+    let mut canonical = format!(
+        "seed={};tile_size={};debug_view={};quality={}",
+        settings.seed,
+        settings.tile_size,
+        settings
+            .debug_view
+            .as_ref()
+            .map_or(String::new(), format_debug_view),
+        format_quality(settings.quality)
+    );
+    // Left out when there's only one sample, so reports from before there could be more still
+    // match their hashes.
+    if settings.samples_per_pixel != 1 {
+        canonical += &format!(
+            ";samples_per_pixel={};filter={}",
+            settings.samples_per_pixel,
+            settings.filter.name()
+        );
+    }
+    if let Some(gizmos) = &settings.light_gizmos {
+        canonical += &format!(";light_gizmos={}", format_light_gizmos(gizmos));
+    }
+    if let Some(exposure) = format_exposure(&settings.exposure) {
+        canonical += &format!(";exposure={}", exposure);
+    }
+    if let Some(correlation) = format_correlation(settings.correlation) {
+        canonical += &format!(";correlation={}", correlation);
+    }
+    #[cfg(feature = "spectral")]
+    if let Some(wavelengths) = settings.wavelengths {
+        canonical += &format!(";wavelengths={}", wavelengths);
+    }
+    canonical.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl RenderReport {
+    pub fn new(settings: &RenderSettings, duration: Duration) -> Self {
+        Self {
+            seed: settings.seed,
+            tile_size: settings.tile_size,
+            debug_view: settings.debug_view.as_ref().map(format_debug_view),
+            light_gizmos: settings.light_gizmos.as_ref().map(format_light_gizmos),
+            exposure: format_exposure(&settings.exposure),
+            quality: settings.quality,
+            samples_per_pixel: settings.samples_per_pixel,
+            filter: settings.filter.name(),
+            correlation: format_correlation(settings.correlation),
+            #[cfg(feature = "spectral")]
+            wavelengths: settings.wavelengths,
+            settings_hash: settings_hash(settings),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            duration,
+            threads: parallel::threads(),
+        }
+    }
+
+    // The report for an image goes next to it, e.g. output.png has output.report.txt.
+    pub fn path_for(image_path: &Path) -> PathBuf {
+        image_path.with_extension("report.txt")
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let field = |key: &str| -> io::Result<&str> {
+            text.lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix(": "))
+                .ok_or_else(|| invalid_data(format!("the report has no {}", key)))
+        };
+        let number = |key: &str| -> io::Result<u64> {
+            field(key)?
+                .parse()
+                .map_err(|_| invalid_data(format!("the report's {} is not a number", key)))
+        };
+        let seconds: f64 = field("duration")?
+            .trim_end_matches('s')
+            .parse()
+            .map_err(|_| invalid_data("the report's duration is not a number".to_string()))?;
+
+        Ok(Self {
+            seed: number("seed")?,
+            tile_size: number("tile_size")? as usize,
+            debug_view: field("debug_view").ok().map(|s| s.to_string()),
+            light_gizmos: field("light_gizmos").ok().map(|s| s.to_string()),
+            exposure: field("exposure").ok().map(|s| s.to_string()),
+            quality: parse_quality(field("quality")?)
+                .ok_or_else(|| invalid_data("the report's quality is unknown".to_string()))?,
+            // Reports from before there could be more than one sample don't have these.
+            samples_per_pixel: number("samples_per_pixel").unwrap_or(1) as usize,
+            filter: field("filter").unwrap_or("box").to_string(),
+            correlation: field("correlation").ok().map(|s| s.to_string()),
+            #[cfg(feature = "spectral")]
+            wavelengths: match field("wavelengths") {
+                Ok(_) => Some(number("wavelengths")? as usize),
+                Err(_) => None,
+            },
+            settings_hash: u64::from_str_radix(field("settings_hash")?, 16)
+                .map_err(|_| invalid_data("the report's settings_hash is not hex".to_string()))?,
+            crate_version: field("crate_version")?.to_string(),
+            duration: Duration::from_secs_f64(seconds),
+            threads: number("threads")? as usize,
+        })
+    }
+}
+
+impl fmt::Display for RenderReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed: {}", self.seed)?;
+        writeln!(f, "tile_size: {}", self.tile_size)?;
+        if let Some(view) = &self.debug_view {
+            writeln!(f, "debug_view: {}", view)?;
+        }
+        if let Some(gizmos) = &self.light_gizmos {
+            writeln!(f, "light_gizmos: {}", gizmos)?;
+        }
+        if let Some(exposure) = &self.exposure {
+            writeln!(f, "exposure: {}", exposure)?;
+        }
+        writeln!(f, "quality: {}", format_quality(self.quality))?;
+        writeln!(f, "samples_per_pixel: {}", self.samples_per_pixel)?;
+        writeln!(f, "filter: {}", self.filter)?;
+        if let Some(correlation) = &self.correlation {
+            writeln!(f, "correlation: {}", correlation)?;
+        }
+        #[cfg(feature = "spectral")]
+        if let Some(wavelengths) = self.wavelengths {
+            writeln!(f, "wavelengths: {}", wavelengths)?;
+        }
+        writeln!(f, "settings_hash: {:016x}", self.settings_hash)?;
+        writeln!(f, "crate_version: {}", self.crate_version)?;
+        writeln!(f, "duration: {}s", self.duration.as_secs_f64())?;
+        writeln!(f, "threads: {}", self.threads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::TentFilter;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "raytracer-{}-{}.report.txt",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn seeded_filenames() {
+        let settings = RenderSettings::default().with_seed(42);
+
+        assert_eq!(
+            "output-seed42.png",
+            settings.seeded_filename("output", "png")
+        );
+    }
+
+    #[test]
+    fn a_report_round_trips_through_a_file() {
+        let settings = RenderSettings::default()
+            .with_seed(7)
+            .with_tile_size(8)
+            .with_debug_view(DebugView::BoundsTests { max: 3 })
+            .with_quality(Quality::Preview)
+            .with_samples_per_pixel(4)
+            .with_filter(TentFilter { radius: 1.5 })
+            .with_correlation(Correlation::Shared)
+            .with_light_gizmos(LightGizmos::new(0.25).with_radii())
+            .with_auto_exposure(AutoExposure::highlights());
+        let report = RenderReport::new(&settings, Duration::from_millis(2500));
+        let path = temp_path("round-trip");
+
+        report.write(&path).unwrap();
+        let read = RenderReport::read(&path);
+        let rerun = RenderSettings::from_report(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(report, read.unwrap());
+        let rerun = rerun.unwrap();
+        assert_eq!(7, rerun.seed);
+        assert_eq!(8, rerun.tile_size);
+        assert_eq!(Quality::Preview, rerun.quality);
+        assert_eq!(4, rerun.samples_per_pixel);
+        assert_eq!("tent 1.5", rerun.filter.name());
+        assert_eq!(Correlation::Shared, rerun.correlation);
+        assert_eq!(
+            Some(LightGizmos::new(0.25).with_radii()),
+            rerun.light_gizmos
+        );
+        assert_eq!(Exposure::Auto(AutoExposure::highlights()), rerun.exposure);
+        assert!(matches!(
+            rerun.debug_view,
+            Some(DebugView::BoundsTests { max: 3 })
+        ));
+    }
+
+    #[cfg(feature = "spectral")]
+    #[test]
+    fn spectral_reports_round_trip() {
+        let settings = RenderSettings::default().with_wavelengths(16);
+        let report = RenderReport::new(&settings, Duration::from_secs(1));
+        let path = temp_path("spectral");
+
+        report.write(&path).unwrap();
+        let read = RenderReport::read(&path);
+        let rerun = RenderSettings::from_report(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(report, read.unwrap());
+        assert_eq!(Some(16), rerun.unwrap().wavelengths);
+        assert_ne!(
+            RenderReport::new(&RenderSettings::default(), Duration::from_secs(1)).settings_hash,
+            report.settings_hash
+        );
+    }
+
+    #[test]
+    fn reports_from_before_multisampling_still_read() {
+        let report = RenderReport::new(&RenderSettings::default(), Duration::from_secs(1));
+        let path = temp_path("single-sample");
+        let old: String = report
+            .to_string()
+            .lines()
+            .filter(|line| !line.starts_with("samples_per_pixel") && !line.starts_with("filter"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        fs::write(&path, old).unwrap();
+
+        let rerun = RenderSettings::from_report(&path);
+        fs::remove_file(&path).unwrap();
+
+        let rerun = rerun.unwrap();
+        assert_eq!(1, rerun.samples_per_pixel);
+        assert_eq!("box", rerun.filter.name());
+    }
+
+    #[test]
+    fn a_tampered_report_is_rejected() {
+        let report = RenderReport::new(&RenderSettings::default(), Duration::from_secs(1));
+        let path = temp_path("tampered");
+        fs::write(&path, report.to_string().replace("seed: 0", "seed: 1")).unwrap();
+
+        let res = RenderSettings::from_report(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+    }
+
+    #[test]
+    fn the_report_goes_next_to_the_image() {
+        assert_eq!(
+            PathBuf::from("renders/output.report.txt"),
+            RenderReport::path_for(Path::new("renders/output.png"))
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use super::RenderSettings;
+use crate::{color::Color, ray::Ray, world::World};
+
+// The wavelengths of visible light, in nanometers.
+const VISIBLE: (f64, f64) = (380.0, 720.0);
+
+// How light of a single wavelength looks in linear RGB, from the CIE 1931 color matching
+// functions as fit with a few Gaussians by Wyman, Sloan and Shirley ("Simple Analytic
+// Approximations to the CIE XYZ Color Matching Functions", 2013). Colors outside of what RGB can
+// show are clamped to it.
+pub fn wavelength_to_rgb(wavelength: f64) -> Color {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let spread = if wavelength < mean { below } else { above };
+        let t = (wavelength - mean) / spread;
+        (-0.5 * t * t).exp()
+    };
+    let x = 1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+        - 0.065 * lobe(501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8);
+    Color::new(
+        (3.2406 * x - 1.5372 * y - 0.4986 * z).max(0.0),
+        (-0.9689 * x + 1.8758 * y + 0.0415 * z).max(0.0),
+        (0.0557 * x - 0.2040 * y + 1.0570 * z).max(0.0),
+    )
+}
+
+impl World {
+    // The color seen along the ray, traced once for each of `wavelengths` wavelengths spread
+    // evenly across the visible spectrum, which refract by different amounts through dispersive
+    // materials. Each is weighted by how it looks in RGB, and the weights are evened out so that
+    // without any dispersion this is the same as color_at. Too few wavelengths and rainbows come
+    // out banded; 16 or so is enough for most.
+    pub fn spectral_color_at(&self, ray: Ray, wavelengths: usize) -> Color {
+        if wavelengths == 0 {
+            panic!("a spectral render needs at least one wavelength")
+        }
+        let (mut total, mut weights) = (Color::BLACK, Color::BLACK);
+        for i in 0..wavelengths {
+            let wavelength =
+                VISIBLE.0 + (i as f64 + 0.5) / wavelengths as f64 * (VISIBLE.1 - VISIBLE.0);
+            let weight = wavelength_to_rgb(wavelength);
+            total += self.color_at(ray.with_wavelength(wavelength)) * weight;
+            weights += weight;
+        }
+        let channel = |i: usize| {
+            if weights[i] > 0.0 {
+                total[i] / weights[i]
+            } else {
+                0.0
+            }
+        };
+        Color::new(channel(0), channel(1), channel(2))
+    }
+}
+
+impl RenderSettings {
+    // Renders spectrally (see World::spectral_color_at), which takes about as many times longer as
+    // there are wavelengths.
+    pub fn with_wavelengths(self, wavelengths: usize) -> Self {
+        if wavelengths == 0 {
+            panic!("a spectral render needs at least one wavelength")
+        }
+        Self {
+            wavelengths: Some(wavelengths),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, body::Body, camera::Camera, environment::Environment, fuzzy_eq::FuzzyEq,
+        light::PointLight, material::Phong, matrix::Matrix, pattern::Pattern, plane::Plane,
+        point::Point, render::render, sphere::Sphere, vector::Vector,
+    };
+
+    #[test]
+    fn single_wavelengths_have_their_colors() {
+        let red = wavelength_to_rgb(650.0);
+        let blue = wavelength_to_rgb(450.0);
+
+        assert!(red[0] > 5.0 * red[1] && red[0] > 5.0 * red[2]);
+        assert!(blue[2] > 5.0 * blue[0] && blue[2] > 5.0 * blue[1]);
+    }
+
+    #[test]
+    fn without_dispersion_spectral_renders_look_the_same() {
+        let world = World::quickstart();
+        let camera = Camera::quickstart(16, 9);
+
+        let plain = render(&world, &camera, &RenderSettings::default());
+        let spectral = render(
+            &world,
+            &camera,
+            &RenderSettings::default().with_wavelengths(8),
+        );
+
+        for (x, y) in [(8, 5), (2, 8), (14, 1)] {
+            assert_fuzzy_eq!(
+                plain.canvas.read_pixel(x, y),
+                spectral.canvas.read_pixel(x, y)
+            );
+        }
+    }
+
+    #[test]
+    fn dispersive_glass_splits_white_into_colors() {
+        // A glass ball in front of a wall with a sharp white edge, seen off center so the edge is
+        // bent by a different amount for each color.
+        let glass: Body = Sphere::default()
+            .with_material(
+                Phong {
+                    color: Color::BLACK,
+                    ambient: 0.0,
+                    diffuse: 0.0,
+                    specular: 0.0,
+                    transparency: 1.0,
+                    refractive_index: 1.5,
+                    dispersion: 0.05,
+                    ..Phong::default()
+                }
+                .into(),
+            )
+            .into();
+        let wall: Body = Plane::default()
+            .with_material(
+                Phong {
+                    ambient: 1.0,
+                    diffuse: 0.0,
+                    specular: 0.0,
+                    pattern: Some(Pattern::stripe(Color::WHITE, Color::BLACK).with_transform(
+                        Matrix::translate(0.3, 0.0, 0.0) * Matrix::scale(100.0, 1.0, 1.0),
+                    )),
+                    ..Phong::default()
+                }
+                .into(),
+            )
+            .with_transform(Matrix::translate(0.0, -3.0, 0.0))
+            .into();
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE);
+        let world = World::new(vec![glass, wall], vec![light.into()])
+            .with_environment(Environment::Color(Color::BLACK));
+
+        let fringe = (0..40).map(|i| {
+            let x = -0.6 + i as f64 * 0.03;
+            world.spectral_color_at(
+                Ray::new(Point::new(x, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0)),
+                16,
+            )
+        });
+
+        // Somewhere along the edge, one end of the spectrum gets through and the other doesn't.
+        assert!(fringe.into_iter().any(|c| (c[0] - c[2]).abs() > 0.2));
+    }
+}
@@ -0,0 +1,288 @@
+use std::io::{self, BufRead, Write};
+
+use super::{resume_exposure, CancellationToken, RenderOutput, RenderSettings, Tile};
+use crate::{camera::Camera, canvas::Canvas, rng::Rng, world::World};
+
+// No render has anywhere near this many tiles, so a log whose header says it has more is taken to
+// be corrupt, rather than trusted with sizing the log.
+const MAX_TILES: usize = 1 << 20;
+
+// What a render did, tile by tile: a checksum of the pixels of each tile it finished. Renders are
+// deterministic, so the same tile of the same scene always comes out the same, down to the bit.
+// That lets whoever hands out tiles to be rendered elsewhere check the work that comes back
+// before putting the image together: whether the tiles it was sent are the ones that were logged
+// (see mismatches), and whether they're right, by rendering a few of them again (see
+// spot_check).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkLog {
+    pub width: usize,
+    pub height: usize,
+    pub tile_size: usize,
+    // The checksum of each tile, in the order given by Tile::split, or None if it wasn't rendered.
+    pub checksums: Vec<Option<u64>>,
+}
+
+impl Tile {
+    // A checksum of the tile's part of the canvas, over the exact bits of every channel, so any
+    // difference at all shows up.
+    pub fn checksum(&self, canvas: &Canvas) -> u64 {
+        // 64 bit FNV-1a.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for y in self.y..self.y + self.height {
+            for x in self.x..self.x + self.width {
+                let color = canvas.read_pixel(x, y);
+                for i in 0..3 {
+                    for byte in color[i].to_bits().to_le_bytes() {
+                        hash ^= byte as u64;
+                        hash = hash.wrapping_mul(0x0100_0000_01b3);
+                    }
+                }
+            }
+        }
+        hash
+    }
+}
+
+impl WorkLog {
+    pub fn of(output: &RenderOutput) -> Self {
+        let tiles = Tile::split(output.canvas.width, output.canvas.height, output.tile_size);
+        let checksums = tiles
+            .iter()
+            .zip(&output.completed_tiles)
+            .map(|(tile, &done)| done.then(|| tile.checksum(&output.canvas)))
+            .collect();
+        Self {
+            width: output.canvas.width,
+            height: output.canvas.height,
+            tile_size: output.tile_size,
+            checksums,
+        }
+    }
+
+    fn tiles(&self) -> Vec<Tile> {
+        Tile::split(self.width, self.height, self.tile_size)
+    }
+
+    // The tiles the log has a checksum for that the output is missing, or has different pixels
+    // in, e.g. because they were corrupted on the way. An output of another size, or split into
+    // other tiles, has none of the logged tiles, so all of them are given.
+    pub fn mismatches(&self, output: &RenderOutput) -> Vec<usize> {
+        if output.canvas.width != self.width
+            || output.canvas.height != self.height
+            || output.tile_size != self.tile_size
+        {
+            return (0..self.checksums.len())
+                .filter(|&i| self.checksums[i].is_some())
+                .collect();
+        }
+        self.tiles()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tile)| {
+                let logged = self.checksums[i]?;
+                let matches = output.completed_tiles[i] && tile.checksum(&output.canvas) == logged;
+                (!matches).then_some(i)
+            })
+            .collect()
+    }
+
+    // Renders `count` of the logged tiles again, picked at random by the seed, and gives the ones
+    // that don't come out with the logged checksum. The scene and settings have to be the same as
+    // the ones the log was made with, or nothing will match.
+    pub fn spot_check(
+        &self,
+        worlds: &[World],
+        camera: &Camera,
+        settings: &RenderSettings,
+        count: usize,
+        seed: u64,
+    ) -> Vec<usize> {
+        let mut logged: Vec<usize> = (0..self.checksums.len())
+            .filter(|&i| self.checksums[i].is_some())
+            .collect();
+        // A partial Fisher-Yates shuffle, only as far as the tiles that get checked.
+        let mut rng = Rng::new(seed);
+        let count = count.min(logged.len());
+        for i in 0..count {
+            let j = i + (rng.next_u64() % (logged.len() - i) as u64) as usize;
+            logged.swap(i, j);
+        }
+        let checked = &logged[..count];
+
+        // Everything but the checked tiles is marked as done, so that only they are rendered.
+        let mut completed_tiles = vec![true; self.checksums.len()];
+        for &i in checked {
+            completed_tiles[i] = false;
+        }
+        let previous = RenderOutput {
+            canvas: Canvas::new(self.width, self.height),
+            tile_size: self.tile_size,
+            completed_tiles,
+            complete: false,
+        };
+        let settings = RenderSettings {
+            time_budget: None,
+            cancellation_token: CancellationToken::new(),
+            show_progress: false,
+            preview: None,
+            ..settings.clone()
+        };
+        let output = resume_exposure(worlds, camera, &settings, previous);
+
+        let tiles = self.tiles();
+        checked
+            .iter()
+            .copied()
+            .filter(|&i| Some(tiles[i].checksum(&output.canvas)) != self.checksums[i])
+            .collect()
+    }
+
+    // Logs are written as text, a header line and then the index and checksum of each finished
+    // tile on a line of its own:
+    //
+    //   worklog <width> <height> <tile_size>
+    //   <tile> <checksum in hex>
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "worklog {} {} {}",
+            self.width, self.height, self.tile_size
+        )?;
+        for (i, checksum) in self.checksums.iter().enumerate() {
+            if let Some(checksum) = checksum {
+                writeln!(w, "{} {:016x}", i, checksum)?;
+            }
+        }
+        w.flush()
+    }
+
+    pub fn read<R: BufRead>(r: R) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut lines = r.lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| invalid("the work log is empty"))??;
+        let header: Vec<&str> = header.split_whitespace().collect();
+        let sizes: Vec<usize> = match header.as_slice() {
+            ["worklog", sizes @ ..] if sizes.len() == 3 => sizes
+                .iter()
+                .map(|s| s.parse().map_err(|_| invalid("bad work log header")))
+                .collect::<io::Result<_>>()?,
+            _ => return Err(invalid("not a work log")),
+        };
+        let (width, height, tile_size) = (sizes[0], sizes[1], sizes[2]);
+        let tile_count = match tile_size {
+            0 => None,
+            _ => width
+                .div_ceil(tile_size)
+                .checked_mul(height.div_ceil(tile_size)),
+        };
+        let tile_count = match tile_count {
+            Some(n) if n <= MAX_TILES => n,
+            _ => return Err(invalid("bad work log header")),
+        };
+
+        let mut log = Self {
+            width,
+            height,
+            tile_size,
+            checksums: vec![None; tile_count],
+        };
+        for line in lines {
+            let line = line?;
+            let entry = line
+                .split_once(' ')
+                .and_then(|(i, checksum)| {
+                    Some((
+                        i.parse::<usize>().ok()?,
+                        u64::from_str_radix(checksum, 16).ok()?,
+                    ))
+                })
+                .filter(|&(i, _)| i < log.checksums.len());
+            let (i, checksum) = entry.ok_or_else(|| invalid("bad work log entry"))?;
+            log.checksums[i] = Some(checksum);
+        }
+        Ok(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, fixtures::scene, render::render};
+
+    #[test]
+    fn rendered_tiles_check_out() {
+        let (world, camera) = scene();
+        let settings = RenderSettings::default().with_tile_size(4);
+        let output = render(&world, &camera, &settings);
+
+        let log = WorkLog::of(&output);
+
+        assert_eq!(9, log.checksums.iter().flatten().count());
+        assert!(log.mismatches(&output).is_empty());
+        assert!(log
+            .spot_check(std::slice::from_ref(&world), &camera, &settings, 3, 7)
+            .is_empty());
+    }
+
+    #[test]
+    fn corrupted_tiles_are_caught() {
+        let (world, camera) = scene();
+        let settings = RenderSettings::default().with_tile_size(4);
+        let mut output = render(&world, &camera, &settings);
+        let log = WorkLog::of(&output);
+
+        output.canvas.write_pixel(9, 6, Color::new(1.0, 0.0, 1.0));
+        output.completed_tiles[0] = false;
+
+        assert_eq!(vec![0, 5], log.mismatches(&output));
+        // Rendering every tile again finds a log made from the corrupted render wrong in one.
+        let corrupted = WorkLog::of(&output);
+        assert_eq!(
+            vec![5],
+            corrupted.spot_check(std::slice::from_ref(&world), &camera, &settings, 9, 0)
+        );
+    }
+
+    #[test]
+    fn a_work_log_round_trips() {
+        let log = WorkLog {
+            width: 5,
+            height: 3,
+            tile_size: 2,
+            checksums: vec![Some(1), None, Some(u64::MAX), None, None, Some(0xabc)],
+        };
+
+        let mut buf = Vec::new();
+        log.write(&mut buf).unwrap();
+
+        assert_eq!(log, WorkLog::read(buf.as_slice()).unwrap());
+        let res = WorkLog::read(&b"worklog 5 3 2\n9 ff\n"[..]);
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+    }
+
+    #[test]
+    fn a_work_log_with_too_many_tiles_is_rejected() {
+        for header in [
+            "worklog 18446744073709551615 18446744073709551615 1\n",
+            "worklog 100000 100000 1\n",
+            "worklog 5 3 0\n",
+        ] {
+            let res = WorkLog::read(header.as_bytes());
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+    }
+
+    #[test]
+    fn a_render_of_another_size_mismatches_every_logged_tile() {
+        let (world, camera) = scene();
+        let settings = RenderSettings::default().with_tile_size(4);
+        let mut log = WorkLog::of(&render(&world, &camera, &settings));
+        log.checksums[2] = None;
+
+        let other = render(&world, &camera, &settings.with_tile_size(5));
+
+        assert_eq!(vec![0, 1, 3, 4, 5, 6, 7, 8], log.mismatches(&other));
+    }
+}
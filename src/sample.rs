@@ -0,0 +1,221 @@
+use std::f64::consts::PI;
+
+use crate::{
+    body::Body, capsule::Capsule, intersection::Normal, measure::Measured, plane::Plane,
+    point::Point, rng::Rng, shell::Shell, sphere::Sphere, transform::Transformable,
+    triangle::Triangle, vector::Vector,
+};
+
+// Picking random points on a body's surface, e.g. for lights made out of emissive bodies. Points
+// are spread evenly over the surface in object space, so a body squashed by its transform gets
+// more of them where it's squashed.
+pub trait SampleSurface: Normal + Transformable {
+    // A random point on the surface in object space.
+    fn sample_object_surface(&self, rng: &mut Rng) -> Point;
+
+    // A random point on the surface in world space, along with the surface normal there.
+    fn sample_surface(&self, rng: &mut Rng) -> (Point, Vector) {
+        let p = self.transform() * self.sample_object_surface(rng);
+        (p, self.normal_at(p))
+    }
+}
+
+// A random direction, spread evenly over the unit sphere.
+pub(crate) fn random_direction(rng: &mut Rng) -> Vector {
+    let z = 1.0 - 2.0 * rng.next_f64();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.next_f64();
+    Vector::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Two unit vectors at right angles to each other and to the given unit vector.
+pub(crate) fn perpendiculars(n: Vector) -> (Vector, Vector) {
+    let other = if n[0].abs() < 0.9 {
+        Vector::new(1.0, 0.0, 0.0)
+    } else {
+        Vector::new(0.0, 1.0, 0.0)
+    };
+    let u = n.cross(&other).normalize();
+    (u, n.cross(&u))
+}
+
+fn on_sphere(center: Point, radius: f64, direction: Vector) -> Point {
+    center + direction * radius
+}
+
+impl SampleSurface for Sphere {
+    fn sample_object_surface(&self, rng: &mut Rng) -> Point {
+        on_sphere(Point::new(0.0, 0.0, 0.0), 1.0, random_direction(rng))
+    }
+}
+
+impl SampleSurface for Shell {
+    fn sample_object_surface(&self, rng: &mut Rng) -> Point {
+        // The walls get samples in proportion to their area.
+        let inner = self.inner_radius();
+        let radius = if rng.next_f64() * (1.0 + inner * inner) < 1.0 {
+            1.0
+        } else {
+            inner
+        };
+        on_sphere(Point::new(0.0, 0.0, 0.0), radius, random_direction(rng))
+    }
+}
+
+impl SampleSurface for Capsule {
+    fn sample_object_surface(&self, rng: &mut Rng) -> Point {
+        let (a, b) = self.segment();
+        let r = self.radius();
+        let axis = b - a;
+        let side_area = 2.0 * PI * r * axis.magnitude();
+        let ends_area = 4.0 * PI * r * r;
+
+        if rng.next_f64() * (side_area + ends_area) < side_area {
+            let (u, w) = perpendiculars(axis.normalize());
+            let angle = 2.0 * PI * rng.next_f64();
+            let along = a + axis * rng.next_f64();
+            along + (u * angle.cos() + w * angle.sin()) * r
+        } else {
+            // The two end caps make up one whole sphere; put each half on the end it faces.
+            let direction = random_direction(rng);
+            let center = if direction.dot(&axis) < 0.0 { a } else { b };
+            on_sphere(center, r, direction)
+        }
+    }
+}
+
+impl SampleSurface for Plane {
+    fn sample_object_surface(&self, _rng: &mut Rng) -> Point {
+        panic!("a plane is infinite, so points can't be picked evenly from it")
+    }
+}
+
+impl SampleSurface for Triangle {
+    fn sample_object_surface(&self, rng: &mut Rng) -> Point {
+        // Pick a point in the parallelogram the two edges make, folding the far half back onto the
+        // triangle.
+        let (p1, p2, p3) = self.points();
+        let (mut u, mut v) = (rng.next_f64(), rng.next_f64());
+        if u + v > 1.0 {
+            u = 1.0 - u;
+            v = 1.0 - v;
+        }
+        p1 + (p2 - p1) * u + (p3 - p1) * v
+    }
+}
+
+impl SampleSurface for Body {
+    fn sample_object_surface(&self, rng: &mut Rng) -> Point {
+        match self {
+            Body::Sphere(s) => s.sample_object_surface(rng),
+            Body::Shell(s) => s.sample_object_surface(rng),
+            Body::Capsule(c) => c.sample_object_surface(rng),
+            Body::Plane(p) => p.sample_object_surface(rng),
+            Body::Triangle(t) => t.sample_object_surface(rng),
+        }
+    }
+}
+
+// Picks one of the bodies with a chance in proportion to its surface area, then a point on it, so
+// points are spread evenly over all of them together.
+pub fn sample_surfaces<'a>(bodies: &'a [Body], rng: &mut Rng) -> Option<(&'a Body, Point, Vector)> {
+    let total: f64 = bodies.iter().map(|b| b.surface_area()).sum();
+    if bodies.is_empty() || !total.is_finite() {
+        return None;
+    }
+
+    let mut pick = rng.next_f64() * total;
+    for body in bodies {
+        pick -= body.surface_area();
+        if pick < 0.0 {
+            let (p, n) = body.sample_surface(rng);
+            return Some((body, p, n));
+        }
+    }
+    let body = bodies.last().unwrap();
+    let (p, n) = body.sample_surface(rng);
+    Some((body, p, n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Matrix};
+
+    #[test]
+    fn samples_on_a_sphere_are_on_its_surface() {
+        let s = Sphere::default()
+            .with_transform(Matrix::translate(1.0, 2.0, 3.0) * Matrix::scale(2.0, 2.0, 2.0));
+        let mut rng = Rng::new(1);
+        let center = Point::new(1.0, 2.0, 3.0);
+
+        let mut sum = Vector::new(0.0, 0.0, 0.0);
+        for _ in 0..1000 {
+            let (p, n) = s.sample_surface(&mut rng);
+            assert_fuzzy_eq!(2.0, (p - center).magnitude());
+            assert_fuzzy_eq!((p - center).normalize(), n);
+            sum += n;
+        }
+
+        // Evenly spread normals cancel out.
+        assert!(sum.magnitude() / 1000.0 < 0.1);
+    }
+
+    #[test]
+    fn samples_on_a_shell_are_on_one_of_its_walls() {
+        let s = Shell::default().with_inner_radius(0.5);
+        let mut rng = Rng::new(2);
+
+        let mut inner = 0;
+        for _ in 0..1000 {
+            let (p, n) = s.sample_surface(&mut rng);
+            let distance = (p - Point::new(0.0, 0.0, 0.0)).magnitude();
+            if distance.fuzzy_eq(0.5) {
+                inner += 1;
+                assert_fuzzy_eq!(-(p - Point::new(0.0, 0.0, 0.0)).normalize(), n);
+            } else {
+                assert_fuzzy_eq!(1.0, distance);
+            }
+        }
+
+        // The inner wall has a fifth of the area.
+        assert!((150..250).contains(&inner));
+    }
+
+    #[test]
+    fn samples_on_a_capsule_are_a_radius_from_its_segment() {
+        let c = Capsule::new(Point::new(0.0, -1.0, 0.0), Point::new(0.0, 1.0, 0.0), 0.5);
+        let mut rng = Rng::new(3);
+
+        for _ in 0..1000 {
+            let (p, _) = c.sample_surface(&mut rng);
+            let closest = Point::new(0.0, p[1].clamp(-1.0, 1.0), 0.0);
+            assert_fuzzy_eq!(0.5, (p - closest).magnitude());
+        }
+    }
+
+    #[test]
+    fn sampling_several_bodies_favours_the_bigger_ones() {
+        let bodies = [
+            Body::from(Sphere::default()),
+            Body::from(Sphere::default().with_transform(Matrix::scale(3.0, 3.0, 3.0))),
+        ];
+        let mut rng = Rng::new(4);
+
+        let small = (0..1000)
+            .filter(|_| {
+                let (body, _, _) = sample_surfaces(&bodies, &mut rng).unwrap();
+                body.fuzzy_eq(bodies[0].clone())
+            })
+            .count();
+
+        // The small sphere has a tenth of the total area.
+        assert!((60..140).contains(&small));
+    }
+
+    #[test]
+    #[should_panic(expected = "a plane is infinite")]
+    fn a_plane_cannot_be_sampled() {
+        Plane::default().sample_surface(&mut Rng::new(5));
+    }
+}
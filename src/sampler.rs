@@ -0,0 +1,278 @@
+use std::f64::consts::PI;
+
+use crate::{rng::Rng, vector::Vector};
+
+// Stratified samples for the stochastic parts of rendering a pixel: the pixel's area for
+// antialiasing, the lens, points on lights at each bounce, and so on. Each of these is a
+// dimension, numbered however the caller likes, and the n samples taken in a dimension are spread
+// one to a cell of a grid over [0, 1) squared.
+//
+// If every dimension used the cells in the same order, sample i would land in the same cell for
+// the antialiasing and for the lights, so the top left of each pixel would always see the same
+// part of a light, which shows up as patterns. Scrambling gives every dimension of every pixel
+// its own order, so the dimensions are stratified but independent of each other.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Correlation {
+    // Every dimension of every pixel visits the cells in its own order.
+    #[default]
+    Scrambled,
+    // Every dimension of a pixel visits the cells in the same order. Only useful for seeing the
+    // artifacts scrambling gets rid of.
+    Shared,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PixelSampler {
+    seed: u64,
+    // The cells are laid out in a grid with this many columns and rows.
+    columns: usize,
+    rows: usize,
+    correlation: Correlation,
+}
+
+impl PixelSampler {
+    // A sampler for `count` samples per dimension in the pixel at (x, y). Different seeds give
+    // different samples, e.g. for each frame of an animation.
+    pub fn new(x: usize, y: usize, count: usize, seed: u64) -> Self {
+        if count == 0 {
+            panic!("a pixel needs at least one sample")
+        }
+        // As square a grid as fits the count exactly.
+        let mut columns = (count as f64).sqrt() as usize;
+        while !count.is_multiple_of(columns) {
+            columns -= 1;
+        }
+        Self {
+            seed: hash(&[seed, x as u64, y as u64]),
+            columns,
+            rows: count / columns,
+            correlation: Correlation::default(),
+        }
+    }
+
+    pub fn with_correlation(self, correlation: Correlation) -> Self {
+        Self {
+            correlation,
+            ..self
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    // The index-th sample of the dimension, in [0, 1) squared.
+    pub fn sample_2d(&self, dimension: usize, index: usize) -> (f64, f64) {
+        let count = self.count();
+        if index >= count {
+            panic!("a pixel only has {} samples", count)
+        }
+
+        let order = match self.correlation {
+            Correlation::Scrambled => hash(&[self.seed, dimension as u64]),
+            Correlation::Shared => self.seed,
+        };
+        let cell = permute(index as u32, count as u32, order as u32) as usize;
+
+        // Somewhere random in the cell, picked separately for each dimension so that shared
+        // orders still jitter differently.
+        let mut rng = Rng::new(hash(&[self.seed, dimension as u64, index as u64]));
+        let u = ((cell % self.columns) as f64 + rng.next_f64()) / self.columns as f64;
+        let v = ((cell / self.columns) as f64 + rng.next_f64()) / self.rows as f64;
+        (u, v)
+    }
+}
+
+// How far round each point is turned from the one before in the golden angle sets below. Turning
+// by the golden angle never lines points up with earlier ones, so they spread out evenly however
+// many there are.
+fn golden_angle(i: usize) -> f64 {
+    i as f64 * PI * (3.0 - 5f64.sqrt())
+}
+
+// `count` directions spread evenly over the unit sphere, with each covering about the same area
+// and no clumping at the poles, for when the same points every time are better than random ones,
+// e.g. placing things on a sphere or sampling around a point. They spiral from the north pole
+// (+y) to the south.
+pub fn fibonacci_sphere(count: usize) -> Vec<Vector> {
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f64 + 0.5) / count as f64;
+            let r = (1.0 - y * y).sqrt();
+            let theta = golden_angle(i);
+            Vector::new(r * theta.cos(), y, r * theta.sin())
+        })
+        .collect()
+}
+
+// `count` points spread evenly over the unit disk, as (x, y), spiralling out from the middle like
+// the seeds of a sunflower, e.g. for points on a lens or on a disk shaped light.
+pub fn golden_spiral_disk(count: usize) -> Vec<(f64, f64)> {
+    (0..count).map(|i| golden_spiral_point(i, count)).collect()
+}
+
+// The `i`th of `count` points from golden_spiral_disk, for when they're used one at a time and
+// collecting them all first would be wasted work.
+pub fn golden_spiral_point(i: usize, count: usize) -> (f64, f64) {
+    let r = ((i as f64 + 0.5) / count as f64).sqrt();
+    let theta = golden_angle(i);
+    (r * theta.cos(), r * theta.sin())
+}
+
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |h, &v| {
+        Rng::new(h ^ v.wrapping_mul(0x9e37_79b9_7f4a_7c15)).next_u64()
+    })
+}
+
+// Where i goes in a random permutation of 0..len, picked by the seed, without having to build the
+// whole permutation (Kensler, "Correlated Multi-Jittered Sampling").
+fn permute(mut i: u32, len: u32, seed: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    // Shuffle within the next power of two up, trying again until the result is in range.
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | (seed >> 27));
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            break;
+        }
+    }
+    i.wrapping_add(seed) % len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(s: &PixelSampler, (u, v): (f64, f64)) -> usize {
+        (v * s.rows as f64) as usize * s.columns + (u * s.columns as f64) as usize
+    }
+
+    #[test]
+    fn a_permutation_uses_every_index_once() {
+        for len in [1, 5, 16, 17] {
+            let mut seen: Vec<u32> = (0..len).map(|i| permute(i, len, 1234)).collect();
+            seen.sort_unstable();
+
+            assert_eq!((0..len).collect::<Vec<_>>(), seen);
+        }
+    }
+
+    #[test]
+    fn each_dimension_puts_one_sample_in_each_cell() {
+        let s = PixelSampler::new(3, 4, 16, 0);
+
+        for dimension in 0..4 {
+            let mut cells: Vec<usize> = (0..16)
+                .map(|i| cell(&s, s.sample_2d(dimension, i)))
+                .collect();
+            cells.sort_unstable();
+
+            assert_eq!((0..16).collect::<Vec<_>>(), cells);
+        }
+    }
+
+    #[test]
+    fn counts_that_arent_square_get_a_grid_that_fits() {
+        let s = PixelSampler::new(0, 0, 12, 0);
+
+        assert_eq!((4, 3), (s.rows, s.columns));
+        assert_eq!(12, s.count());
+    }
+
+    #[test]
+    fn scrambled_dimensions_visit_the_cells_in_different_orders() {
+        let s = PixelSampler::new(7, 2, 64, 0);
+        let same_cells = |s: PixelSampler| {
+            (0..64)
+                .filter(|&i| cell(&s, s.sample_2d(0, i)) == cell(&s, s.sample_2d(1, i)))
+                .count()
+        };
+
+        assert_eq!(64, same_cells(s.with_correlation(Correlation::Shared)));
+        assert!(same_cells(s) < 8);
+    }
+
+    #[test]
+    fn pixels_are_scrambled_differently() {
+        let a = PixelSampler::new(0, 0, 16, 0);
+        let b = PixelSampler::new(1, 0, 16, 0);
+
+        let differ = (0..16)
+            .filter(|&i| cell(&a, a.sample_2d(0, i)) != cell(&b, b.sample_2d(0, i)))
+            .count();
+
+        assert!(differ > 8);
+    }
+
+    #[test]
+    fn fibonacci_sphere_points_cover_the_sphere_evenly() {
+        let points = fibonacci_sphere(1000);
+
+        assert_eq!(1000, points.len());
+        assert!(points.iter().all(|p| (p.magnitude() - 1.0).abs() < 1e-9));
+        // Every octant gets close to an eighth of them, and they balance out around the middle.
+        for octant in 0..8 {
+            let sign = |axis: usize| if octant >> axis & 1 == 0 { 1.0 } else { -1.0 };
+            let inside = points
+                .iter()
+                .filter(|p| (0..3).all(|axis| p[axis] * sign(axis) >= 0.0))
+                .count();
+            assert!(
+                (115..=135).contains(&inside),
+                "{} in octant {}",
+                inside,
+                octant
+            );
+        }
+        let total = points
+            .iter()
+            .fold(Vector::new(0.0, 0.0, 0.0), |a, &p| a + p);
+        assert!(total.magnitude() / 1000.0 < 0.01);
+    }
+
+    #[test]
+    fn golden_spiral_points_cover_the_disk_evenly() {
+        let points = golden_spiral_disk(1000);
+
+        assert!(points.iter().all(|(x, y)| x * x + y * y <= 1.0));
+        // A disk of half the radius has a quarter of the area, so it gets a quarter of the points.
+        let inner = points.iter().filter(|(x, y)| x * x + y * y < 0.25).count();
+        assert_eq!(250, inner);
+        // Nothing clumps: no two points are much closer than the spacing an even spread has.
+        let spacing = (PI / 1000.0).sqrt();
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                assert!(((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt() > spacing / 2.0);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "only has 4 samples")]
+    fn asking_for_too_many_samples_panics() {
+        PixelSampler::new(0, 0, 4, 0).sample_2d(0, 4);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    bounds::{Bounded, Bounds},
+    fuzzy_eq::FuzzyEq,
+    intersection::{HitRecord, Intersectable, Intersections, Normal},
+    material::Material,
+    matrix::Matrix,
+    point::Point,
+    ray::Ray,
+    transform::Transformable,
+    vector::Vector,
+};
+
+// A shell is a sphere with a spherical cavity in the middle, e.g. a glass ornament. In object
+// space the outer wall has a radius of 1 and the inner wall has a radius of `inner_radius`.
+#[derive(Clone, Debug)]
+pub struct Shell {
+    transform: Matrix<4>,
+    inner_radius: f64,
+    pub material: Material,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self {
+            transform: Matrix::identity(),
+            inner_radius: 0.9,
+            material: Material::default(),
+        }
+    }
+}
+
+impl FuzzyEq for Shell {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.transform.fuzzy_eq(other.transform) && self.inner_radius.fuzzy_eq(other.inner_radius)
+    }
+}
+
+// Returns the t values where the given object space ray crosses a sphere of the given radius
+// centered at the origin.
+fn intersect_sphere_of_radius(r: Ray, radius: f64) -> Option<(f64, f64)> {
+    let sphere_to_ray = r.origin - Point::new(0.0, 0.0, 0.0);
+    let a = r.direction.dot(&r.direction);
+    let b = 2.0 * r.direction.dot(&sphere_to_ray);
+    let c = sphere_to_ray.dot(&sphere_to_ray) - radius * radius;
+
+    let descriminant = b * b - 4.0 * a * c;
+    if descriminant < 0.0 {
+        None
+    } else {
+        let t1 = (-b - descriminant.sqrt()) / (2.0 * a);
+        let t2 = (-b + descriminant.sqrt()) / (2.0 * a);
+        Some((t1, t2))
+    }
+}
+
+impl Intersectable for Shell {
+    fn intersect(&self, r: Ray) -> Intersections {
+        let object_space_ray = r.transform(self.transform.inverse());
+
+        let mut xs = vec![];
+        if let Some((t1, t2)) = intersect_sphere_of_radius(object_space_ray, 1.0) {
+            xs.push(HitRecord::new(t1, r, self.clone().into()));
+            xs.push(HitRecord::new(t2, r, self.clone().into()));
+        }
+        if let Some((t1, t2)) = intersect_sphere_of_radius(object_space_ray, self.inner_radius) {
+            xs.push(HitRecord::new(t1, r, self.clone().into()));
+            xs.push(HitRecord::new(t2, r, self.clone().into()));
+        }
+        xs.into()
+    }
+}
+
+impl Normal for Shell {
+    fn normal_at(&self, p: Point) -> Vector {
+        let t_inv = self.transform.inverse();
+        let object_point = t_inv * p;
+        let from_center = object_point - Point::new(0.0, 0.0, 0.0);
+
+        // Whichever wall the point is closest to is the one we're on. The inner wall faces the
+        // cavity, so its normal points towards the center.
+        let distance = from_center.magnitude();
+        let object_normal = if distance - self.inner_radius < 1.0 - distance {
+            -from_center.normalize()
+        } else {
+            from_center.normalize()
+        };
+
+        let world_normal = t_inv.transpose() * object_normal;
+        Vector::new(world_normal[0], world_normal[1], world_normal[2]).normalize()
+    }
+}
+
+impl Bounded for Shell {
+    fn bounds(&self) -> Bounds {
+        Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+}
+
+impl Transformable for Shell {
+    fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+}
+
+impl Shell {
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+
+    pub fn with_material(self, material: Material) -> Self {
+        Self { material, ..self }
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    pub fn with_inner_radius(self, inner_radius: f64) -> Self {
+        if inner_radius <= 0.0 || inner_radius >= 1.0 {
+            panic!("shell inner radius must be between 0 and 1")
+        }
+        Self {
+            inner_radius,
+            ..self
+        }
+    }
+
+    pub fn inner_radius(&self) -> f64 {
+        self.inner_radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, body::Body, computed_intersection::Orientation};
+
+    #[test]
+    fn a_ray_through_the_middle_of_a_shell_crosses_both_walls() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Shell::default().with_inner_radius(0.5);
+
+        let xs = s.intersect(r);
+
+        assert_eq!(4, xs.len());
+        assert_fuzzy_eq!(4.0, xs[0].t);
+        assert_fuzzy_eq!(4.5, xs[1].t);
+        assert_fuzzy_eq!(5.5, xs[2].t);
+        assert_fuzzy_eq!(6.0, xs[3].t);
+    }
+
+    #[test]
+    fn a_ray_that_misses_the_cavity_only_crosses_the_outer_wall() {
+        let r = Ray::new(Point::new(0.0, 0.75, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Shell::default().with_inner_radius(0.5);
+
+        let xs = s.intersect(r);
+
+        assert_eq!(2, xs.len());
+    }
+
+    #[test]
+    fn intersecting_a_scaled_shell() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Shell::default()
+            .with_inner_radius(0.5)
+            .with_transform(Matrix::scale(2.0, 2.0, 2.0));
+
+        let xs = s.intersect(r);
+
+        assert_eq!(4, xs.len());
+        assert_fuzzy_eq!(3.0, xs[0].t);
+        assert_fuzzy_eq!(4.0, xs[1].t);
+        assert_fuzzy_eq!(6.0, xs[2].t);
+        assert_fuzzy_eq!(7.0, xs[3].t);
+    }
+
+    #[test]
+    fn the_normal_on_the_outer_wall_points_away_from_the_center() {
+        let s = Shell::default().with_inner_radius(0.5);
+        let n = s.normal_at(Point::new(0.0, 0.0, -1.0));
+
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), n);
+    }
+
+    #[test]
+    fn the_normal_on_the_inner_wall_points_into_the_cavity() {
+        let s = Shell::default().with_inner_radius(0.5);
+        let n = s.normal_at(Point::new(0.0, 0.0, -0.5));
+
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, 1.0), n);
+    }
+
+    #[test]
+    fn hitting_the_inner_wall_from_the_cavity_is_outside_the_material() {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let body = Body::from(Shell::default().with_inner_radius(0.5));
+        let i = HitRecord::new(0.5, r, body);
+        let c = i.computed();
+
+        assert_eq!(Orientation::Outside, c.orientation);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), c.normal);
+    }
+
+    #[test]
+    fn hitting_the_inner_wall_from_within_the_material_is_inside() {
+        let r = Ray::new(Point::new(0.0, 0.0, -0.75), Vector::new(0.0, 0.0, 1.0));
+        let body = Body::from(Shell::default().with_inner_radius(0.5));
+        let i = HitRecord::new(0.25, r, body);
+        let c = i.computed();
+
+        assert_eq!(Orientation::Inside, c.orientation);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), c.normal);
+    }
+
+    #[test]
+    #[should_panic(expected = "shell inner radius must be between 0 and 1")]
+    fn the_inner_radius_must_be_inside_the_outer_wall() {
+        Shell::default().with_inner_radius(1.5);
+    }
+}
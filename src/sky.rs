@@ -0,0 +1,223 @@
+use crate::{color::Color, vector::Vector};
+
+// An analytic daylight sky, following Preetham, Shirley and Smits, "A Practical Analytic Model for
+// Daylight" (1999). The sky's color depends on the direction of the sun and the turbidity, which
+// is how hazy the air is: 2 is a very clear day and 10 is thick haze.
+//
+// The model gives the sky's luminance relative to the zenith, so `brightness` sets the luminance
+// straight up. The sun itself is drawn as a disk of `sun_intensity`, `sun_radius` radians across.
+#[derive(Clone, Copy, Debug)]
+pub struct Sky {
+    sun_direction: Vector,
+    turbidity: f64,
+    pub brightness: f64,
+    pub sun_radius: f64,
+    pub sun_intensity: Color,
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self {
+            sun_direction: Vector::new(0.0, 1.0, 1.0).normalize(),
+            turbidity: 3.0,
+            brightness: 0.5,
+            sun_radius: 0.02,
+            sun_intensity: Color::new(20.0, 19.0, 17.0),
+        }
+    }
+}
+
+// The coefficients of Perez et al.'s sky distribution function for one of Y, x or y.
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    // theta is the angle between the view direction and the zenith, gamma is the angle between
+    // the view direction and the sun.
+    fn distribution(&self, theta: f64, gamma: f64) -> f64 {
+        (1.0 + self.a * (self.b / theta.cos()).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * gamma.cos().powi(2))
+    }
+}
+
+impl Sky {
+    pub fn with_sun_direction(self, sun_direction: Vector) -> Self {
+        Self {
+            sun_direction: sun_direction.normalize(),
+            ..self
+        }
+    }
+
+    pub fn with_turbidity(self, turbidity: f64) -> Self {
+        if !(2.0..=10.0).contains(&turbidity) {
+            panic!("sky turbidity must be between 2 and 10")
+        }
+        Self { turbidity, ..self }
+    }
+
+    pub fn with_brightness(self, brightness: f64) -> Self {
+        Self { brightness, ..self }
+    }
+
+    pub fn with_sun(self, sun_radius: f64, sun_intensity: Color) -> Self {
+        Self {
+            sun_radius,
+            sun_intensity,
+            ..self
+        }
+    }
+
+    // The direction towards the sun.
+    pub fn sun_direction(&self) -> Vector {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    // The color of the sky seen looking in the given direction.
+    pub fn color_at(&self, direction: Vector) -> Color {
+        // The model breaks down below the horizon, so mirror the sky there. Scenes usually have a
+        // floor hiding it anyway.
+        let direction = Vector::new(direction[0], direction[1].abs(), direction[2]).normalize();
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        if gamma < self.sun_radius {
+            return self.sun_intensity;
+        }
+
+        let theta = direction[1].clamp(0.001, 1.0).acos();
+        let theta_sun = self.sun_direction[1].clamp(0.001, 1.0).acos();
+
+        let t = self.turbidity;
+        let luminance = Perez {
+            a: 0.1787 * t - 1.4630,
+            b: -0.3554 * t + 0.4275,
+            c: -0.0227 * t + 5.3251,
+            d: 0.1206 * t - 2.5771,
+            e: -0.0670 * t + 0.3703,
+        };
+        let x = Perez {
+            a: -0.0193 * t - 0.2592,
+            b: -0.0665 * t + 0.0008,
+            c: -0.0004 * t + 0.2125,
+            d: -0.0641 * t - 0.8989,
+            e: -0.0033 * t + 0.0452,
+        };
+        let y = Perez {
+            a: -0.0167 * t - 0.2608,
+            b: -0.0950 * t + 0.0092,
+            c: -0.0079 * t + 0.2102,
+            d: -0.0441 * t - 1.6537,
+            e: -0.0109 * t + 0.0529,
+        };
+
+        let relative =
+            |perez: &Perez| perez.distribution(theta, gamma) / perez.distribution(0.0, theta_sun);
+
+        let (zenith_x, zenith_y) = self.zenith_chromaticity(theta_sun);
+        let big_y = self.brightness * relative(&luminance);
+        let small_x = zenith_x * relative(&x);
+        let small_y = zenith_y * relative(&y);
+
+        xyy_to_rgb(small_x, small_y, big_y)
+    }
+
+    fn zenith_chromaticity(&self, theta_sun: f64) -> (f64, f64) {
+        let t = self.turbidity;
+        let thetas = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.0];
+        let dot = |coefficients: [f64; 4]| -> f64 {
+            coefficients
+                .iter()
+                .zip(thetas.iter())
+                .map(|(c, theta)| c * theta)
+                .sum()
+        };
+
+        let x = t * t * dot([0.00166, -0.00375, 0.00209, 0.0])
+            + t * dot([-0.02903, 0.06377, -0.03202, 0.00394])
+            + dot([0.11693, -0.21196, 0.06052, 0.25886]);
+        let y = t * t * dot([0.00275, -0.00610, 0.00317, 0.0])
+            + t * dot([-0.04214, 0.08970, -0.04153, 0.00516])
+            + dot([0.15346, -0.26756, 0.06670, 0.26688]);
+        (x, y)
+    }
+}
+
+// Converts CIE xyY to linear sRGB.
+fn xyy_to_rgb(x: f64, y: f64, big_y: f64) -> Color {
+    let big_x = x / y * big_y;
+    let big_z = (1.0 - x - y) / y * big_y;
+    Color::new(
+        3.2406 * big_x - 1.5372 * big_y - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * big_y + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * big_y + 1.0570 * big_z,
+    )
+    .clamp(0.0, f64::INFINITY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    #[test]
+    fn the_zenith_has_the_sky_brightness() {
+        let sky = Sky::default().with_brightness(0.8);
+        let c = sky.color_at(Vector::new(0.0, 1.0, 0.0));
+
+        // Linear sRGB's luminance weights.
+        let luminance = 0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2];
+        assert!((luminance - 0.8).abs() < 0.01);
+    }
+
+    #[test]
+    fn a_clear_sky_is_blue_overhead() {
+        let sky = Sky::default().with_turbidity(2.0);
+        let c = sky.color_at(Vector::new(0.0, 1.0, 0.0));
+
+        assert!(c[2] > c[0]);
+    }
+
+    #[test]
+    fn the_sky_near_the_sun_is_brighter() {
+        let sky = Sky::default().with_sun_direction(Vector::new(0.0, 1.0, 1.0));
+        let near = sky.color_at(Vector::new(0.0, 1.0, 1.2));
+        let away = sky.color_at(Vector::new(0.0, 1.0, -1.2));
+
+        assert!(near[1] > away[1]);
+    }
+
+    #[test]
+    fn looking_at_the_sun_shows_the_sun_disk() {
+        let sky = Sky::default()
+            .with_sun_direction(Vector::new(1.0, 1.0, 0.0))
+            .with_sun(0.05, Color::new(10.0, 10.0, 10.0));
+
+        assert_fuzzy_eq!(
+            Color::new(10.0, 10.0, 10.0),
+            sky.color_at(Vector::new(1.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn the_sky_is_mirrored_below_the_horizon() {
+        let sky = Sky::default();
+
+        assert_fuzzy_eq!(
+            sky.color_at(Vector::new(1.0, 0.5, 0.0)),
+            sky.color_at(Vector::new(1.0, -0.5, 0.0))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "sky turbidity must be between 2 and 10")]
+    fn turbidity_must_be_in_the_model_range() {
+        Sky::default().with_turbidity(1.0);
+    }
+}
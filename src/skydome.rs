@@ -0,0 +1,101 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{canvas::Canvas, color::Color, vector::Vector};
+
+// An equirectangular (latitude/longitude) image wrapped around the scene as the sky. The top row
+// of the image is straight up, the bottom row is straight down and the middle column is towards
+// -z before any rotation.
+//
+// This is for ordinary LDR images, so `exposure` scales the colors up to be brighter than the
+// image can store, and `yaw` spins the image around the y axis to put e.g. the sun where it's
+// wanted.
+#[derive(Clone, Debug)]
+pub struct Skydome {
+    image: Arc<Canvas>,
+    pub exposure: f64,
+    pub yaw: f64,
+}
+
+impl Skydome {
+    pub fn new(image: Canvas) -> Self {
+        Self {
+            image: Arc::new(image),
+            exposure: 1.0,
+            yaw: 0.0,
+        }
+    }
+
+    pub fn with_exposure(self, exposure: f64) -> Self {
+        Self { exposure, ..self }
+    }
+
+    pub fn with_yaw(self, yaw: f64) -> Self {
+        Self { yaw, ..self }
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    pub fn color_at(&self, direction: Vector) -> Color {
+        let d = direction.normalize();
+        let longitude = d[0].atan2(-d[2]) + self.yaw;
+        let u = (0.5 + longitude / (2.0 * PI)).rem_euclid(1.0);
+        let v = d[1].clamp(-1.0, 1.0).acos() / PI;
+
+        let (width, height) = (self.image.width, self.image.height);
+        let x = ((u * width as f64) as usize).min(width - 1);
+        let y = ((v * height as f64) as usize).min(height - 1);
+        self.image.read_pixel(x, y) * self.exposure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq};
+
+    // Four columns of different colors on top and a gray bottom half.
+    fn quadrants() -> Canvas {
+        let mut c = Canvas::new(4, 2);
+        for (x, color) in [Color::RED, Color::LIME, Color::BLUE, Color::WHITE]
+            .iter()
+            .enumerate()
+        {
+            c.write_pixel(x, 0, *color);
+            c.write_pixel(x, 1, Color::new(0.5, 0.5, 0.5));
+        }
+        c
+    }
+
+    #[test]
+    fn sampling_a_skydome_by_direction() {
+        let sky = Skydome::new(quadrants());
+
+        // Looking down -z is the middle of the image, just upward of the horizon.
+        assert_fuzzy_eq!(Color::BLUE, sky.color_at(Vector::new(0.0, 0.1, -1.0)));
+        assert_fuzzy_eq!(Color::WHITE, sky.color_at(Vector::new(1.0, 0.1, 0.5)));
+        assert_fuzzy_eq!(Color::RED, sky.color_at(Vector::new(-0.1, 0.1, 1.0)));
+        assert_fuzzy_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            sky.color_at(Vector::new(0.0, -1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn yaw_rotates_the_skydome() {
+        let sky = Skydome::new(quadrants()).with_yaw(0.6 * PI);
+
+        assert_fuzzy_eq!(Color::WHITE, sky.color_at(Vector::new(0.0, 0.1, -1.0)));
+    }
+
+    #[test]
+    fn exposure_brightens_the_skydome() {
+        let sky = Skydome::new(quadrants()).with_exposure(2.0);
+
+        assert_fuzzy_eq!(
+            Color::new(0.0, 0.0, 2.0),
+            sky.color_at(Vector::new(0.0, 0.1, -1.0))
+        );
+    }
+}
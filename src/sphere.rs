@@ -1,14 +1,16 @@
 use crate::{
+    bounds::{Bounded, Bounds},
     fuzzy_eq::FuzzyEq,
-    intersection::{Intersectable, Intersection, Intersections, Normal},
+    intersection::{HitRecord, Intersectable, Intersections, Normal},
     material::Material,
     matrix::Matrix,
     point::Point,
     ray::Ray,
+    transform::Transformable,
     vector::Vector,
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Sphere {
     transform: Matrix<4>,
     pub material: Material,
@@ -45,8 +47,8 @@ impl Intersectable for Sphere {
             let t1 = (-b - descriminant.sqrt()) / (2.0 * a);
             let t2 = (-b + descriminant.sqrt()) / (2.0 * a);
             vec![
-                Intersection::new(t1, r, (*self).into()),
-                Intersection::new(t2, r, (*self).into()),
+                HitRecord::new(t1, r, self.clone().into()),
+                HitRecord::new(t2, r, self.clone().into()),
             ]
             .into()
         }
@@ -63,6 +65,22 @@ impl Normal for Sphere {
     }
 }
 
+impl Bounded for Sphere {
+    fn bounds(&self) -> Bounds {
+        Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+}
+
+impl Transformable for Sphere {
+    fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        Self { transform, ..self }
+    }
+}
+
 impl Sphere {
     pub fn with_transform(self, transform: Matrix<4>) -> Self {
         Self { transform, ..self }
@@ -71,6 +89,10 @@ impl Sphere {
     pub fn with_material(self, material: Material) -> Self {
         Self { material, ..self }
     }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +244,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn computing_the_normal_on_a_translated_sphere() {
         let s: Sphere = Sphere::default().with_transform(Matrix::translate(0.0, 1.0, 0.0));
         let p = Point::new(0.0, 1.70711, -0.70711);
@@ -273,16 +296,17 @@ mod tests {
 
     #[test]
     fn sphere_may_be_assigned_a_material() {
-        let phong = Phong {
+        let phong: Material = Phong {
             color: Color::new(1.0, 1.0, 0.0),
             ambient: 0.05,
             diffuse: 0.7,
             specular: 0.95,
             shininess: 400.0,
+            ..Phong::default()
         }
         .into();
 
-        let s = Sphere::default().with_material(phong);
+        let s = Sphere::default().with_material(phong.clone());
 
         assert_fuzzy_eq!(phong, s.material);
     }
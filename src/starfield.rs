@@ -0,0 +1,171 @@
+use crate::{color::Color, rng::Rng, vector::Vector};
+
+// A night sky of stars for space scenes, made up on the fly from the direction looked in, so it
+// costs no memory and is the same from frame to frame. The sky is split into cells, as if on the
+// faces of a cube around the scene, and each cell has a star in it or not, with its place,
+// brightness and color picked by hashing the cell with the seed.
+#[derive(Clone, Copy, Debug)]
+pub struct Starfield {
+    // About how many stars there are across the whole sky.
+    pub count: usize,
+    // How bright the brightest stars are. Most are much fainter: a star's brightness is this
+    // times a random number in [0, 1) raised to `falloff`, so the higher that is the more faint
+    // stars there are for each bright one.
+    pub brightness: f64,
+    pub falloff: f64,
+    // How big the stars look, in radians. Keep it to a few pixels' worth or smaller.
+    pub size: f64,
+    pub seed: u64,
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        Self {
+            count: 5000,
+            brightness: 4.0,
+            falloff: 4.0,
+            size: 0.001,
+            seed: 0,
+        }
+    }
+}
+
+impl Starfield {
+    pub fn with_count(self, count: usize) -> Self {
+        Self { count, ..self }
+    }
+
+    pub fn with_brightness(self, brightness: f64, falloff: f64) -> Self {
+        Self {
+            brightness,
+            falloff,
+            ..self
+        }
+    }
+
+    pub fn with_size(self, size: f64) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    // How many cells there are along each side of each face of the cube. There are about four
+    // cells for each star, so that the sky doesn't look like a grid with a star in every square.
+    fn resolution(&self) -> usize {
+        ((self.count as f64 * 4.0 / 6.0).sqrt().ceil() as usize).max(1)
+    }
+
+    pub fn color_at(&self, direction: Vector) -> Color {
+        let d = direction.normalize();
+        let n = self.resolution();
+
+        // Which face of the cube the direction goes through, and where on it, in [-1, 1].
+        let axis = (0..3)
+            .max_by(|&a, &b| d[a].abs().total_cmp(&d[b].abs()))
+            .unwrap();
+        let (a1, a2) = ((axis + 1) % 3, (axis + 2) % 3);
+        let sign = d[axis].signum();
+        let face = axis * 2 + (sign < 0.0) as usize;
+        let (u, v) = (d[a1] / d[axis].abs(), d[a2] / d[axis].abs());
+        let cell_of = |x: f64| (((x + 1.0) / 2.0 * n as f64) as usize).min(n - 1);
+        let (i, j) = (cell_of(u), cell_of(v));
+
+        let cell = ((face * n + j) * n + i) as u64;
+        let mut rng = Rng::new(self.seed ^ cell.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        if rng.next_f64() * (6 * n * n) as f64 >= self.count as f64 {
+            return Color::BLACK;
+        }
+
+        // Keep the star away from the edges of its cell so that it doesn't get cut off.
+        let place = |cell: usize, r: f64| -1.0 + (cell as f64 + 0.25 + 0.5 * r) * 2.0 / n as f64;
+        let mut star = Vector::new(0.0, 0.0, 0.0);
+        star[axis] = sign;
+        star[a1] = place(i, rng.next_f64());
+        star[a2] = place(j, rng.next_f64());
+        let star = star.normalize();
+
+        // Close enough to the star's direction, the distance between them is the angle.
+        let angle = (d - star).magnitude();
+        let glow = (-(angle / self.size).powi(2)).exp();
+        let brightness = self.brightness * rng.next_f64().powf(self.falloff);
+        // From cool red stars through white to hot blue ones.
+        let temperature = rng.next_f64();
+        let tint = Color::new(1.0, 0.75, 0.55)
+            + (Color::new(0.65, 0.8, 1.0) - Color::new(1.0, 0.75, 0.55)) * temperature;
+        tint * (brightness * glow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, fuzzy_eq::FuzzyEq, rng::Rng, sample::random_direction};
+
+    // Looks in lots of directions, returning the brightest it saw and how many directions saw
+    // anything at all.
+    fn survey(stars: &Starfield, samples: usize) -> (f64, usize) {
+        let mut rng = Rng::new(1);
+        let colors: Vec<Color> = (0..samples)
+            .map(|_| stars.color_at(random_direction(&mut rng)))
+            .collect();
+        let brightest = colors
+            .iter()
+            .map(|c| c[0].max(c[1]).max(c[2]))
+            .fold(0.0, f64::max);
+        let lit = colors.iter().filter(|c| c[0] + c[1] + c[2] > 1e-3).count();
+        (brightest, lit)
+    }
+
+    #[test]
+    fn the_same_direction_always_sees_the_same_thing() {
+        let stars = Starfield::default().with_seed(7);
+        let d = Vector::new(0.3, -0.2, 0.9);
+
+        assert_fuzzy_eq!(stars.color_at(d), stars.color_at(d));
+        assert_fuzzy_eq!(stars.color_at(d), stars.color_at(d * 5.0));
+    }
+
+    #[test]
+    fn different_seeds_give_different_skies() {
+        let a = Starfield::default().with_size(0.01);
+        let b = a.with_seed(1);
+        let mut rng = Rng::new(3);
+
+        let differ = (0..1000)
+            .map(|_| random_direction(&mut rng))
+            .filter(|&d| !a.color_at(d).fuzzy_eq(b.color_at(d)))
+            .count();
+
+        assert!(differ > 0);
+    }
+
+    #[test]
+    fn more_stars_light_more_of_the_sky() {
+        let few = Starfield::default().with_count(100).with_size(0.01);
+        let many = few.with_count(10000);
+
+        assert!(survey(&few, 20000).1 < survey(&many, 20000).1);
+    }
+
+    #[test]
+    fn no_star_is_brighter_than_the_brightest() {
+        let stars = Starfield::default()
+            .with_count(20000)
+            .with_size(0.02)
+            .with_brightness(2.0, 1.0);
+
+        let (brightest, lit) = survey(&stars, 20000);
+
+        assert!(lit > 0);
+        assert!(brightest <= 2.0 + 1e-9);
+    }
+
+    #[test]
+    fn no_stars_is_a_black_sky() {
+        let stars = Starfield::default().with_count(0);
+
+        assert_eq!(0, survey(&stars, 1000).1);
+    }
+}
@@ -0,0 +1,659 @@
+use std::{
+    collections::HashSet,
+    fmt, mem,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    body::Body,
+    camera::Camera,
+    color::Color,
+    csg::Csg,
+    environment::Environment,
+    instance::{Instance, Mesh},
+    intersection::Intersectable,
+    light::Light,
+    ray::Ray,
+    world::World,
+};
+
+// A summary of how big a scene is, for spotting scenes that will take forever to render before
+// actually rendering them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SceneStats {
+    pub bodies: usize,
+    pub triangles: usize,
+    pub lights: usize,
+    pub estimated_memory: usize,
+}
+
+fn triangle_count(body: &Body) -> usize {
+    match body {
+        Body::Sphere(_) | Body::Shell(_) | Body::Capsule(_) | Body::Plane(_) => 0,
+        Body::Triangle(_) => 1,
+    }
+}
+
+impl SceneStats {
+    pub fn of(world: &World) -> Self {
+        let combined = || world.csgs.iter().flat_map(Csg::bodies);
+        Self {
+            bodies: world.bodies.len() + combined().count(),
+            triangles: world
+                .bodies
+                .iter()
+                .chain(combined())
+                .chain(world.instances.iter().flat_map(|i| i.mesh().bodies()))
+                .map(triangle_count)
+                .sum(),
+            lights: world.lights.len(),
+            estimated_memory: MemoryReport::of(world).total(),
+        }
+    }
+}
+
+// Where a scene's memory goes, in bytes, for seeing what's worth cutting down or instancing when
+// a big scene doesn't fit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    // The world itself, with its bodies, CSG solids and lights.
+    pub bodies: usize,
+    // The bodies in meshes, counted once however many instances share them, and the instances.
+    pub meshes: usize,
+    // The bounding volume hierarchies over the meshes and the instances.
+    pub bvh_nodes: usize,
+    // Images, like a skydome's, and light probes.
+    pub textures: usize,
+}
+
+impl MemoryReport {
+    pub fn of(world: &World) -> Self {
+        let mut meshes: HashSet<*const Mesh> = HashSet::new();
+        let mut report = Self {
+            bodies: mem::size_of::<World>()
+                + world.bodies.capacity() * mem::size_of::<Body>()
+                + world.csgs.iter().map(|c| c.bodies().count()).sum::<usize>()
+                    * mem::size_of::<Body>()
+                + world.lights.capacity() * mem::size_of::<Light>(),
+            meshes: world.instances.len() * mem::size_of::<Instance>(),
+            bvh_nodes: world.instances.bvh().heap_size(),
+            textures: 0,
+        };
+        for instance in world.instances.iter() {
+            let mesh = instance.mesh();
+            if meshes.insert(mesh) {
+                report.meshes += mem::size_of::<Mesh>() + mem::size_of_val(mesh.bodies());
+                report.bvh_nodes += mesh.bvh().heap_size();
+            }
+        }
+        if let Environment::Skydome(skydome) = &world.environment {
+            let image = skydome.image();
+            report.textures += image.width * image.height * mem::size_of::<Color>();
+        }
+        let environment_probe = match &world.environment {
+            Environment::Probe(probe) => Some(probe),
+            _ => None,
+        };
+        for probe in world.probes().chain(environment_probe) {
+            let face = probe.face(0);
+            report.textures += 6 * face.width * face.height * mem::size_of::<Color>();
+        }
+        report
+    }
+
+    pub fn total(&self) -> usize {
+        self.bodies + self.meshes + self.bvh_nodes + self.textures
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "bodies: {}", format_bytes(self.bodies))?;
+        writeln!(f, "meshes: {}", format_bytes(self.meshes))?;
+        writeln!(f, "bvh_nodes: {}", format_bytes(self.bvh_nodes))?;
+        writeln!(f, "textures: {}", format_bytes(self.textures))?;
+        writeln!(f, "total: {}", format_bytes(self.total()))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SceneBudget {
+    pub max_bodies: usize,
+    pub max_triangles: usize,
+    pub max_lights: usize,
+    pub max_memory: usize,
+}
+
+impl Default for SceneBudget {
+    fn default() -> Self {
+        Self {
+            max_bodies: 1_000,
+            max_triangles: 1_000_000,
+            max_lights: 8,
+            max_memory: 1 << 30,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetWarning {
+    TooManyBodies { count: usize, max: usize },
+    TooManyTriangles { count: usize, max: usize },
+    TooManyLights { count: usize, max: usize },
+    TooMuchMemory { bytes: usize, max: usize },
+}
+
+impl fmt::Display for BudgetWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetWarning::TooManyBodies { count, max } => {
+                write!(f, "scene has {} bodies (budget is {})", count, max)
+            }
+            BudgetWarning::TooManyTriangles { count, max } => {
+                write!(f, "scene has {} triangles (budget is {})", count, max)
+            }
+            BudgetWarning::TooManyLights { count, max } => {
+                write!(f, "scene has {} lights (budget is {})", count, max)
+            }
+            BudgetWarning::TooMuchMemory { bytes, max } => write!(
+                f,
+                "scene needs about {} bytes of memory (budget is {})",
+                bytes, max
+            ),
+        }
+    }
+}
+
+impl SceneBudget {
+    pub fn with_max_bodies(self, max_bodies: usize) -> Self {
+        Self { max_bodies, ..self }
+    }
+
+    pub fn with_max_triangles(self, max_triangles: usize) -> Self {
+        Self {
+            max_triangles,
+            ..self
+        }
+    }
+
+    pub fn with_max_lights(self, max_lights: usize) -> Self {
+        Self { max_lights, ..self }
+    }
+
+    pub fn with_max_memory(self, max_memory: usize) -> Self {
+        Self { max_memory, ..self }
+    }
+
+    // Everything about the scene that's over budget.
+    pub fn check(&self, stats: &SceneStats) -> Vec<BudgetWarning> {
+        let mut warnings = Vec::new();
+        if stats.bodies > self.max_bodies {
+            warnings.push(BudgetWarning::TooManyBodies {
+                count: stats.bodies,
+                max: self.max_bodies,
+            });
+        }
+        if stats.triangles > self.max_triangles {
+            warnings.push(BudgetWarning::TooManyTriangles {
+                count: stats.triangles,
+                max: self.max_triangles,
+            });
+        }
+        if stats.lights > self.max_lights {
+            warnings.push(BudgetWarning::TooManyLights {
+                count: stats.lights,
+                max: self.max_lights,
+            });
+        }
+        if stats.estimated_memory > self.max_memory {
+            warnings.push(BudgetWarning::TooMuchMemory {
+                bytes: stats.estimated_memory,
+                max: self.max_memory,
+            });
+        }
+        warnings
+    }
+}
+
+// A rough idea of what rendering a frame will cost. Every primary ray is tested against every
+// body, and so is the shadow ray from each hit to each light.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderEstimate {
+    pub primary_rays: usize,
+    pub intersection_tests: usize,
+    pub time: Duration,
+}
+
+impl RenderEstimate {
+    // Times `samples` pixels spread evenly over the image and scales up to the whole image. The
+    // time is for one thread, so a parallel render should be quicker by about the number of cores.
+    pub fn measure(world: &World, camera: &Camera, samples: usize) -> Self {
+        let primary_rays = camera.hsize * camera.vsize;
+        if primary_rays == 0 {
+            return Self {
+                primary_rays,
+                intersection_tests: 0,
+                time: Duration::ZERO,
+            };
+        }
+        let samples = samples.clamp(1, primary_rays);
+
+        let start = Instant::now();
+        for i in 0..samples {
+            let pixel = i * primary_rays / samples;
+            world.color_at(camera.ray_for_pixel(pixel % camera.hsize, pixel / camera.hsize));
+        }
+        let per_ray = start.elapsed().as_secs_f64() / samples as f64;
+
+        Self {
+            primary_rays,
+            intersection_tests: primary_rays * world.bodies.len() * (1 + world.lights.len()),
+            time: Duration::from_secs_f64(per_ray * primary_rays as f64),
+        }
+    }
+}
+
+// Where the work of finding what rays hit goes, for tracking down the body or mesh that makes a
+// scene slow. It follows the same rays as RenderEstimate: the primary ray through each sampled
+// pixel, and the shadow rays from whatever it hits to each light.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub rays: usize,
+    // The world's own bodies, by id. Every ray tests every one of them.
+    pub bodies: Vec<BodyStats>,
+    // The instances, in the order they were added to the world.
+    pub instances: Vec<InstanceStats>,
+    // Boxes tested at each depth of the tree over the instances, the root first.
+    pub instance_levels: Vec<usize>,
+    // Boxes tested at each depth of the meshes' own trees, for all of them together.
+    pub mesh_levels: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BodyStats {
+    pub tests: usize,
+    pub intersections: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstanceStats {
+    // Bodies in the instance's mesh that rays were tested against, and boxes in its tree.
+    pub body_tests: usize,
+    pub box_tests: usize,
+    pub intersections: usize,
+}
+
+// The instances of one mesh together, since a mesh that's slow to test is slow in every copy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MeshStats {
+    // Indices into RenderStats::instances.
+    pub instances: Vec<usize>,
+    pub body_tests: usize,
+    pub box_tests: usize,
+    pub intersections: usize,
+}
+
+impl RenderStats {
+    // Traces `samples` pixels spread evenly over the image, or every pixel if there are fewer.
+    pub fn measure(world: &World, camera: &Camera, samples: usize) -> Self {
+        let pixels = camera.hsize * camera.vsize;
+        let mut stats = Self {
+            bodies: vec![BodyStats::default(); world.bodies.len()],
+            instances: vec![InstanceStats::default(); world.instances.len()],
+            ..Self::default()
+        };
+        if pixels == 0 {
+            return stats;
+        }
+        let samples = samples.clamp(1, pixels);
+
+        for i in 0..samples {
+            let pixel = i * pixels / samples;
+            let ray = camera.ray_for_pixel(pixel % camera.hsize, pixel / camera.hsize);
+            stats.record(world, ray);
+            let xs = world.intersect(ray);
+            if let Some(hit) = xs.hit() {
+                let from = hit.computed_with(&xs).over_point;
+                for light in &world.lights {
+                    stats.record(world, Ray::new(from, light.direction_from(from)));
+                }
+            }
+        }
+        stats
+    }
+
+    fn record(&mut self, world: &World, ray: Ray) {
+        self.rays += 1;
+        for (body, counts) in world.bodies.iter().zip(&mut self.bodies) {
+            counts.tests += 1;
+            counts.intersections += body.intersect(ray).len();
+        }
+        world.instances.record(ray, self);
+    }
+
+    // Every instance's work added up by the mesh it's a copy of, the busiest first.
+    pub fn by_mesh(&self, world: &World) -> Vec<MeshStats> {
+        let mut meshes: Vec<(*const Mesh, MeshStats)> = Vec::new();
+        for (i, (instance, counts)) in world.instances.iter().zip(&self.instances).enumerate() {
+            let mesh: *const Mesh = instance.mesh();
+            let stats = match meshes.iter_mut().find(|(m, _)| *m == mesh) {
+                Some((_, stats)) => stats,
+                None => {
+                    meshes.push((
+                        mesh,
+                        MeshStats {
+                            instances: vec![],
+                            body_tests: 0,
+                            box_tests: 0,
+                            intersections: 0,
+                        },
+                    ));
+                    &mut meshes.last_mut().unwrap().1
+                }
+            };
+            stats.instances.push(i);
+            stats.body_tests += counts.body_tests;
+            stats.box_tests += counts.box_tests;
+            stats.intersections += counts.intersections;
+        }
+        let mut meshes: Vec<MeshStats> = meshes.into_iter().map(|(_, stats)| stats).collect();
+        meshes.sort_by_key(|m| std::cmp::Reverse(m.body_tests + m.box_tests));
+        meshes
+    }
+
+    // The stats as JSON, with bodies given by name where the world has one for them.
+    pub fn to_json(&self, world: &World) -> String {
+        let levels = |levels: &[usize]| {
+            let levels: Vec<String> = levels.iter().map(usize::to_string).collect();
+            format!("[{}]", levels.join(", "))
+        };
+        let bodies: Vec<String> = self
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(id, b)| {
+                let name = world
+                    .name_of(id)
+                    .map_or("null".to_string(), |name| format!("{:?}", name));
+                format!(
+                    "{{ \"id\": {}, \"name\": {}, \"tests\": {}, \"intersections\": {} }}",
+                    id, name, b.tests, b.intersections
+                )
+            })
+            .collect();
+        let instances: Vec<String> = self
+            .instances
+            .iter()
+            .map(|i| {
+                format!(
+                    "{{ \"body_tests\": {}, \"box_tests\": {}, \"intersections\": {} }}",
+                    i.body_tests, i.box_tests, i.intersections
+                )
+            })
+            .collect();
+        let meshes: Vec<String> = self
+            .by_mesh(world)
+            .iter()
+            .map(|m| {
+                format!(
+                    "{{ \"instances\": {}, \"body_tests\": {}, \"box_tests\": {}, \"intersections\": {} }}",
+                    levels(&m.instances),
+                    m.body_tests,
+                    m.box_tests,
+                    m.intersections
+                )
+            })
+            .collect();
+        let list = |items: Vec<String>| {
+            if items.is_empty() {
+                "[]".to_string()
+            } else {
+                format!("[\n    {}\n  ]", items.join(",\n    "))
+            }
+        };
+        format!(
+            concat!(
+                "{{\n",
+                "  \"rays\": {},\n",
+                "  \"bodies\": {},\n",
+                "  \"instances\": {},\n",
+                "  \"meshes\": {},\n",
+                "  \"instance_levels\": {},\n",
+                "  \"mesh_levels\": {}\n",
+                "}}\n"
+            ),
+            self.rays,
+            list(bodies),
+            list(instances),
+            list(meshes),
+            levels(&self.instance_levels),
+            levels(&self.mesh_levels),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        canvas::Canvas, instance::Instances, light::PointLight, matrix::Matrix, plane::Plane,
+        point::Point, skydome::Skydome, sphere::Sphere, vector::Vector,
+    };
+
+    fn world_with_bodies(n: usize) -> World {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+        World::new(
+            (0..n).map(|_| Sphere::default().into()).collect(),
+            vec![light.into()],
+        )
+    }
+
+    #[test]
+    fn counting_a_scene() {
+        let stats = SceneStats::of(&world_with_bodies(3));
+
+        assert_eq!(3, stats.bodies);
+        assert_eq!(0, stats.triangles);
+        assert_eq!(1, stats.lights);
+        assert!(stats.estimated_memory >= 3 * mem::size_of::<Body>());
+    }
+
+    #[test]
+    fn instances_of_a_mesh_share_its_memory() {
+        let mesh = Arc::new(Mesh::new(
+            (0..100).map(|_| Sphere::default().into()).collect(),
+        ));
+        let mut world = world_with_bodies(0);
+        world.instances = Instances::new(vec![Instance::new(mesh.clone())]);
+        let one = MemoryReport::of(&world);
+        world.instances = Instances::new(vec![
+            Instance::new(mesh.clone()),
+            Instance::new(mesh.clone()),
+            Instance::new(mesh),
+        ]);
+        let three = MemoryReport::of(&world);
+
+        assert!(one.meshes >= 100 * mem::size_of::<Body>());
+        assert_eq!(one.meshes + 2 * mem::size_of::<Instance>(), three.meshes);
+        assert!(one.bvh_nodes > 0);
+    }
+
+    #[test]
+    fn skydome_images_count_as_textures() {
+        let world = world_with_bodies(1).with_environment(Skydome::new(Canvas::new(64, 32)).into());
+
+        let report = MemoryReport::of(&world);
+
+        assert_eq!(64 * 32 * mem::size_of::<Color>(), report.textures);
+        assert_eq!(
+            report.bodies + report.meshes + report.bvh_nodes + report.textures,
+            report.total()
+        );
+    }
+
+    #[test]
+    fn memory_reports_are_readable() {
+        let report = MemoryReport {
+            bodies: 512,
+            meshes: 3 * 1024 * 1024,
+            bvh_nodes: 2048,
+            textures: 0,
+        };
+
+        assert_eq!(
+            "bodies: 512 B\nmeshes: 3.0 MiB\nbvh_nodes: 2.0 KiB\ntextures: 0 B\ntotal: 3.0 MiB\n",
+            report.to_string()
+        );
+    }
+
+    #[test]
+    fn a_scene_within_budget_has_no_warnings() {
+        let stats = SceneStats::of(&world_with_bodies(3));
+
+        assert!(SceneBudget::default().check(&stats).is_empty());
+    }
+
+    #[test]
+    fn a_scene_over_budget_warns() {
+        let stats = SceneStats::of(&world_with_bodies(3));
+        let budget = SceneBudget::default().with_max_bodies(2).with_max_lights(0);
+
+        assert_eq!(
+            vec![
+                BudgetWarning::TooManyBodies { count: 3, max: 2 },
+                BudgetWarning::TooManyLights { count: 1, max: 0 },
+            ],
+            budget.check(&stats)
+        );
+    }
+
+    #[test]
+    fn budget_warnings_are_readable() {
+        let warning = BudgetWarning::TooManyBodies { count: 3, max: 2 };
+
+        assert_eq!("scene has 3 bodies (budget is 2)", warning.to_string());
+    }
+
+    #[test]
+    fn estimating_the_cost_of_a_render() {
+        let world = world_with_bodies(2);
+        let camera = Camera::new(20, 10, FRAC_PI_2);
+
+        let estimate = RenderEstimate::measure(&world, &camera, 10);
+
+        assert_eq!(200, estimate.primary_rays);
+        assert_eq!(800, estimate.intersection_tests);
+    }
+
+    #[test]
+    fn every_light_adds_shadow_rays_to_the_estimate() {
+        let mut world = world_with_bodies(2);
+        world
+            .lights
+            .push(PointLight::new(Point::new(10.0, 10.0, -10.0), Color::WHITE).into());
+
+        let estimate = RenderEstimate::measure(&world, &Camera::new(20, 10, FRAC_PI_2), 10);
+
+        assert_eq!(1200, estimate.intersection_tests);
+    }
+
+    #[test]
+    fn estimating_a_render_with_no_pixels() {
+        let estimate =
+            RenderEstimate::measure(&world_with_bodies(2), &Camera::new(0, 10, FRAC_PI_2), 10);
+
+        assert_eq!(0, estimate.primary_rays);
+        assert_eq!(0, estimate.intersection_tests);
+        assert_eq!(Duration::ZERO, estimate.time);
+    }
+
+    // A floor under a big mesh of small spheres in front of the camera, and a copy of a mesh of
+    // one sphere off to the side.
+    fn instanced_world() -> World {
+        let many = (0..50)
+            .map(|i| {
+                Sphere::default()
+                    .with_transform(
+                        Matrix::translate((i % 10) as f64 * 0.2 - 1.0, (i / 10) as f64 * 0.2, 0.0)
+                            * Matrix::scale(0.1, 0.1, 0.1),
+                    )
+                    .into()
+            })
+            .collect();
+        let many = Arc::new(Mesh::new(many));
+        let one = Arc::new(Mesh::new(vec![Sphere::default().into()]));
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+        World::new(vec![], vec![light.into()])
+            .with_named_body(
+                "floor",
+                Plane::default().with_transform(Matrix::translate(0.0, -1.0, 0.0)),
+            )
+            .with_instances(vec![
+                Instance::new(one).with_transform(Matrix::translate(10.0, 0.0, 0.0)),
+                Instance::new(many),
+            ])
+    }
+
+    #[test]
+    fn render_stats_break_the_work_down_by_body_and_mesh() {
+        let world = instanced_world();
+        let camera = Camera::new(20, 20, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.5, -3.0),
+            Point::new(0.0, 0.5, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let stats = RenderStats::measure(&world, &camera, 400);
+
+        // A ray through every pixel, and a shadow ray from everything they hit.
+        assert!(stats.rays > 400 && stats.rays < 800);
+        assert_eq!(stats.rays, stats.bodies[0].tests);
+        assert!(stats.bodies[0].intersections > 0);
+        // Nothing hits the far off copy, and the big mesh is the busiest.
+        assert_eq!(0, stats.instances[0].intersections);
+        let meshes = stats.by_mesh(&world);
+        assert_eq!(vec![1], meshes[0].instances);
+        assert!(meshes[0].body_tests > 0 && meshes[0].intersections > 0);
+        // The root of the tree over the instances is tested by every ray.
+        assert_eq!(stats.rays, stats.instance_levels[0]);
+        assert_eq!(
+            stats.instances.iter().map(|i| i.box_tests).sum::<usize>(),
+            stats.mesh_levels.iter().sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn measuring_a_render_with_no_pixels() {
+        let world = instanced_world();
+
+        let stats = RenderStats::measure(&world, &Camera::new(4, 0, FRAC_PI_2), 16);
+
+        assert_eq!(0, stats.rays);
+        assert_eq!(vec![BodyStats::default()], stats.bodies);
+    }
+
+    #[test]
+    fn render_stats_are_written_as_json() {
+        let world = instanced_world();
+        let camera = Camera::new(4, 4, FRAC_PI_2);
+
+        let json = RenderStats::measure(&world, &camera, 16).to_json(&world);
+
+        assert!(json.starts_with("{\n  \"rays\": "));
+        assert!(json.contains("{ \"id\": 0, \"name\": \"floor\", \"tests\": "));
+        assert!(json.contains("\"meshes\": [\n    { \"instances\": ["));
+        assert!(json.contains("\"instance_levels\": ["));
+    }
+}
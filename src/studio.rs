@@ -0,0 +1,171 @@
+use std::f64::consts::FRAC_PI_3;
+
+use crate::{
+    bounds::Bounds,
+    camera::Camera,
+    color::Color,
+    environment::Environment,
+    light::{Light, PointLight},
+    material::Phong,
+    matrix::Matrix,
+    plane::Plane,
+    vector::Vector,
+    world::World,
+};
+
+// A photo studio set up around one subject, for showing off a model without building a scene for
+// it: a pale floor for it to stand on, a camera in front of it, a little above and looking down,
+// and the classic three lights. The key light is the main one, in front and off to the left, big
+// enough to soften the shadows. The fill is a dimmer one on the right, to lift the shadows the key
+// leaves, and the rim is behind, to pick out the subject's edges from the background. Everything
+// is placed and sized by the subject's bounds, with the subject facing -z like the prefabs.
+#[derive(Clone, Copy, Debug)]
+pub struct Studio {
+    pub subject: Bounds,
+    pub key: PointLight,
+    pub fill: PointLight,
+    pub rim: PointLight,
+}
+
+impl Studio {
+    pub fn three_point_lighting(subject: Bounds) -> Self {
+        if !subject.is_finite() {
+            panic!("a studio can only be set up around a subject with finite bounds")
+        }
+        let center = subject.centroid();
+        let radius = radius(&subject);
+        let light = |x, y, z, brightness| {
+            let position = center + Vector::new(x, y, z).normalize() * (4.0 * radius);
+            PointLight::new(position, Color::WHITE * brightness)
+        };
+        Self {
+            subject,
+            key: light(-1.0, 1.0, -1.0, 1.0).with_radius(radius, 16),
+            fill: light(1.0, 0.3, -1.0, 0.4),
+            rim: light(0.5, 1.0, 1.0, 0.6),
+        }
+    }
+
+    // The key comes first, so that if the world caches shadows, it's the key's that are cached.
+    pub fn lights(&self) -> Vec<Light> {
+        vec![self.key.into(), self.fill.into(), self.rim.into()]
+    }
+
+    // Puts the world in the studio: its lights are replaced with the studio's, and the floor is
+    // added under the subject against a soft grey background.
+    pub fn stage(&self, mut world: World) -> World {
+        let floor = Plane::default()
+            .with_transform(Matrix::translate(0.0, self.subject.min[1], 0.0))
+            .with_material(
+                Phong {
+                    color: Color::new(0.85, 0.85, 0.85),
+                    specular: 0.0,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+        world.lights = self.lights();
+        world
+            .with_body(floor)
+            .with_environment(Environment::Color(Color::new(0.6, 0.6, 0.62)))
+    }
+
+    // A camera the given size that fits the whole subject in the picture, with a little room
+    // around it.
+    pub fn camera(&self, hsize: usize, vsize: usize) -> Camera {
+        let camera = Camera::new(hsize, vsize, FRAC_PI_3);
+        // The field of view spans the longer side of the picture, so the subject has to fit in the
+        // shorter one.
+        let aspect_ratio = hsize as f64 / vsize as f64;
+        let half_view = ((FRAC_PI_3 / 2.0).tan() / aspect_ratio.max(1.0 / aspect_ratio)).atan();
+        let distance = 1.1 * radius(&self.subject) / half_view.sin();
+
+        let center = self.subject.centroid();
+        let from = center + Vector::new(0.0, 0.3, -1.0).normalize() * distance;
+        camera.look_at_from_position(from, center, Vector::new(0.0, 1.0, 0.0))
+    }
+}
+
+// The radius of the sphere around the bounds, so that the subject fits whatever way it's seen
+// from. A subject with no size at all still gets a little room.
+fn radius(bounds: &Bounds) -> f64 {
+    ((bounds.max - bounds.min).magnitude() / 2.0).max(0.01)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bounds::Bounded, fuzzy_eq::FuzzyEq, point::Point, ray::Ray, sphere::Sphere, vector::Vector,
+    };
+
+    fn subject() -> Bounds {
+        Bounds::new(Point::new(-1.0, 0.0, -1.0), Point::new(1.0, 3.0, 1.0))
+    }
+
+    #[test]
+    fn the_lights_surround_the_subject() {
+        let studio = Studio::three_point_lighting(subject());
+        let center = subject().centroid();
+
+        // The key and fill are in front, on either side, and the rim is behind.
+        assert!(studio.key.position[0] < center[0] && studio.key.position[2] < center[2]);
+        assert!(studio.fill.position[0] > center[0] && studio.fill.position[2] < center[2]);
+        assert!(studio.rim.position[2] > center[2]);
+        assert!(studio.fill.intensity[0] < studio.key.intensity[0]);
+        assert!(studio.key.radius > 0.0);
+        assert!(
+            matches!(studio.lights()[0], Light::Point(l) if l.position.fuzzy_eq(studio.key.position))
+        );
+    }
+
+    #[test]
+    fn the_camera_frames_the_whole_subject() {
+        for (hsize, vsize) in [(80, 40), (40, 80)] {
+            let camera = Studio::three_point_lighting(subject()).camera(hsize, vsize);
+
+            for corner in subject().corners() {
+                let (x, y) = camera.project(corner).unwrap();
+                assert!(
+                    (0.0..hsize as f64).contains(&x),
+                    "{:?} is off the side",
+                    corner
+                );
+                assert!(
+                    (0.0..vsize as f64).contains(&y),
+                    "{:?} is off the top",
+                    corner
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_fill_light_brightens_the_shadow_side() {
+        let ball = Sphere::default().with_transform(Matrix::translate(0.0, 1.0, 0.0));
+        let studio = Studio::three_point_lighting(ball.bounds().transformed(ball.transform()));
+        let world = studio.stage(World::new(vec![ball.clone().into()], vec![]));
+        let mut key_only = studio.stage(World::new(vec![ball.into()], vec![]));
+        key_only.lights.truncate(1);
+        // Looking at the right of the ball, which faces away from the key, from the front.
+        let ray = Ray::new(Point::new(0.9, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(world.color_at(ray)[0] > key_only.color_at(ray)[0] + 0.1);
+    }
+
+    #[test]
+    fn staging_a_world_lights_it_and_adds_the_floor() {
+        let ball = Sphere::default().with_transform(Matrix::translate(0.0, 1.0, 0.0));
+        let bounds = ball.bounds().transformed(ball.transform());
+        let studio = Studio::three_point_lighting(bounds);
+        let world = World::new(vec![ball.into()], vec![]);
+
+        let world = studio.stage(world);
+        let camera = studio.camera(20, 20);
+
+        assert_eq!(3, world.lights.len());
+        assert_eq!(2, world.bodies.len());
+        // The ball, lit from the front, fills the middle of the picture.
+        assert!(world.color_at(camera.ray_for_pixel(10, 10))[0] > 0.5);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    animator::Interpolate,
+    bounds::Bounded,
+    matrix::{Matrix, Rotation},
+    point::Point,
+    vector::Vector,
+};
+
+mod check;
+
+pub use check::*;
+
+// Something placed in the world by a transform, with helpers for placing it without working out
+// the matrices by hand.
+pub trait Transformable: Sized {
+    fn transform(&self) -> Matrix<4>;
+
+    fn with_transform(self, transform: Matrix<4>) -> Self;
+
+    // Turns the object about its origin so that its +z axis points at the target, with its +y axis
+    // as close to `up` as it can be. Any rotation the transform already has is applied first.
+    fn look_at(self, target: Point, up: Vector) -> Self {
+        let transform = self.transform();
+        let origin = transform * Point::new(0.0, 0.0, 0.0);
+        let forward = (target - origin).normalize();
+        let right = up.cross(&forward).normalize();
+        let true_up = forward.cross(&right);
+
+        #[rustfmt::skip]
+        let orientation = Matrix::from([
+            [right[0], true_up[0], forward[0], 0.0],
+            [right[1], true_up[1], forward[1], 0.0],
+            [right[2], true_up[2], forward[2], 0.0],
+            [0.0,      0.0,        0.0,        1.0],
+        ]);
+
+        self.with_transform(
+            Matrix::translate(origin[0], origin[1], origin[2])
+                * orientation
+                * Matrix::translate(-origin[0], -origin[1], -origin[2])
+                * transform,
+        )
+    }
+
+    // Moves the object up or down so that the bottom of it rests at the given height. Objects
+    // that go on forever are left where they are.
+    fn upright_on(self, y: f64) -> Self
+    where
+        Self: Bounded,
+    {
+        let bottom = self.bounds().transformed(self.transform()).min[1];
+        if !bottom.is_finite() {
+            return self;
+        }
+        let transform = Matrix::translate(0.0, y - bottom, 0.0) * self.transform();
+        self.with_transform(transform)
+    }
+}
+
+// A transform broken down into a translation, a rotation (angles about the x, y, then z axes) and
+// a scale. Unlike a Matrix these can be interpolated sensibly, which is what animating a transform
+// needs.
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub translation: Vector,
+    pub rotation: Vector,
+    pub scale: Vector,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector::new(0.0, 0.0, 0.0),
+            rotation: Vector::new(0.0, 0.0, 0.0),
+            scale: Vector::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+    pub fn with_translation(self, x: f64, y: f64, z: f64) -> Self {
+        Self {
+            translation: Vector::new(x, y, z),
+            ..self
+        }
+    }
+
+    pub fn with_rotation(self, x: f64, y: f64, z: f64) -> Self {
+        Self {
+            rotation: Vector::new(x, y, z),
+            ..self
+        }
+    }
+
+    pub fn with_scale(self, x: f64, y: f64, z: f64) -> Self {
+        Self {
+            scale: Vector::new(x, y, z),
+            ..self
+        }
+    }
+
+    // Scales first, then rotates, then translates.
+    pub fn matrix(&self) -> Matrix<4> {
+        Matrix::translate(
+            self.translation[0],
+            self.translation[1],
+            self.translation[2],
+        ) * Matrix::rotate(Rotation::Z, self.rotation[2])
+            * Matrix::rotate(Rotation::Y, self.rotation[1])
+            * Matrix::rotate(Rotation::X, self.rotation[0])
+            * Matrix::scale(self.scale[0], self.scale[1], self.scale[2])
+    }
+}
+
+impl From<Transform> for Matrix<4> {
+    fn from(t: Transform) -> Self {
+        t.matrix()
+    }
+}
+
+impl Interpolate for Transform {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        Self {
+            translation: self.translation.interpolate(&other.translation, t),
+            rotation: self.rotation.interpolate(&other.rotation, t),
+            scale: self.scale.interpolate(&other.scale, t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, capsule::Capsule, fuzzy_eq::FuzzyEq, intersection::Normal, plane::Plane,
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn the_default_transform_is_the_identity() {
+        assert_fuzzy_eq!(Matrix::<4>::identity(), Transform::default().matrix());
+    }
+
+    #[test]
+    fn a_transform_scales_then_rotates_then_translates() {
+        let t = Transform::default()
+            .with_translation(1.0, 0.0, 0.0)
+            .with_rotation(0.0, 0.0, FRAC_PI_2)
+            .with_scale(2.0, 2.0, 2.0);
+
+        assert_fuzzy_eq!(
+            Matrix::translate(1.0, 0.0, 0.0)
+                * Matrix::rotate(Rotation::Z, FRAC_PI_2)
+                * Matrix::scale(2.0, 2.0, 2.0),
+            t.matrix()
+        );
+        assert_fuzzy_eq!(
+            Point::new(1.0, 2.0, 0.0),
+            t.matrix() * Point::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn interpolating_transforms_blends_each_component() {
+        let from = Transform::default();
+        let to = Transform::default()
+            .with_translation(2.0, 0.0, 0.0)
+            .with_rotation(0.0, FRAC_PI_2, 0.0)
+            .with_scale(3.0, 1.0, 1.0);
+
+        let halfway = from.interpolate(&to, 0.5);
+
+        assert_fuzzy_eq!(Vector::new(1.0, 0.0, 0.0), halfway.translation);
+        assert_fuzzy_eq!(Vector::new(0.0, FRAC_PI_4, 0.0), halfway.rotation);
+        assert_fuzzy_eq!(Vector::new(2.0, 1.0, 1.0), halfway.scale);
+    }
+
+    #[test]
+    fn looking_at_a_target_points_the_z_axis_at_it() {
+        let s = Sphere::default()
+            .with_transform(Matrix::translate(1.0, 0.0, 0.0))
+            .look_at(Point::new(1.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_fuzzy_eq!(
+            Point::new(1.0, 0.0, 0.0),
+            s.transform() * Point::new(0.0, 0.0, 0.0)
+        );
+        assert_fuzzy_eq!(
+            Point::new(1.0, 0.0, -1.0),
+            s.transform() * Point::new(0.0, 0.0, 1.0)
+        );
+        assert_fuzzy_eq!(
+            Point::new(1.0, 1.0, 0.0),
+            s.transform() * Point::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn looking_at_a_target_keeps_the_scale() {
+        let c = Capsule::default()
+            .with_transform(Matrix::scale(2.0, 2.0, 2.0))
+            .look_at(Point::new(0.0, 10.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_fuzzy_eq!(
+            Point::new(0.0, 2.0, 0.0),
+            c.transform() * Point::new(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn an_upright_body_rests_on_the_given_height() {
+        let s = Sphere::default()
+            .with_transform(Matrix::translate(3.0, 7.0, 0.0) * Matrix::scale(2.0, 2.0, 2.0))
+            .upright_on(1.0);
+
+        assert_fuzzy_eq!(
+            Point::new(3.0, 3.0, 0.0),
+            s.transform() * Point::new(0.0, 0.0, 0.0)
+        );
+        assert_fuzzy_eq!(
+            Vector::new(0.0, -1.0, 0.0),
+            s.normal_at(Point::new(3.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn an_infinite_body_stays_put_when_stood_upright() {
+        let p = Plane::default()
+            .with_transform(Matrix::rotate(Rotation::X, FRAC_PI_2))
+            .upright_on(1.0);
+
+        assert_fuzzy_eq!(Matrix::rotate(Rotation::X, FRAC_PI_2), p.transform());
+    }
+}
@@ -0,0 +1,371 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    fuzzy_eq::{FuzzyEq, EPISILON},
+    matrix::Matrix,
+    transform::Transformable,
+    vector::Vector,
+    world::World,
+};
+
+// Transforms that squash a body flat or aren't really transforms at all otherwise only show up as
+// bizarre renders, or as a panic when the transform is inverted to intersect the body. These
+// checks find them while the scene is being put together, when it's clear which body is at fault.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransformProblem {
+    // The axis (0 for x, 1 for y, 2 for z) is scaled down to nothing, so the body is flat and the
+    // transform can't be inverted.
+    ZeroScale { axis: usize },
+    // Two or more of the axes point the same way, e.g. after a big shear, which squashes the body
+    // flat just the same.
+    CollapsedAxes,
+    // The body isn't flat, but it's shrunk so small that the transform can't be inverted.
+    TooSmall,
+    // The transform mirrors the body, turning it inside out. This renders fine, but it's rarely on
+    // purpose.
+    Mirrored,
+    // The bottom row isn't 0 0 0 1, so this is a projection rather than a move, turn or scale.
+    NotAffine,
+}
+
+impl fmt::Display for TransformProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformProblem::ZeroScale { axis } => {
+                write!(f, "its {} axis is scaled to zero", ["x", "y", "z"][*axis])
+            }
+            TransformProblem::CollapsedAxes => write!(f, "its axes are squashed onto each other"),
+            TransformProblem::TooSmall => write!(f, "it shrinks the body too small to invert"),
+            TransformProblem::Mirrored => write!(f, "it mirrors the body"),
+            TransformProblem::NotAffine => write!(f, "its bottom row is not 0 0 0 1"),
+        }
+    }
+}
+
+// Bodies thinner than this are flat for all intents and purposes.
+const MIN_SCALE: f64 = 1e-3;
+
+// Comfortably more than the smallest volume a transform can scale a body to and still be inverted.
+const MIN_VOLUME: f64 = 10.0 * EPISILON;
+
+// Where each of the axes ends up under the transform, i.e. the columns of the upper 3x3.
+fn axes(m: &Matrix<4>) -> [Vector; 3] {
+    [0, 1, 2].map(|j| Vector::new(m[0][j], m[1][j], m[2][j]))
+}
+
+pub fn transform_problems(m: &Matrix<4>) -> Vec<TransformProblem> {
+    let mut problems = Vec::new();
+    let affine = m[3]
+        .iter()
+        .zip([0.0, 0.0, 0.0, 1.0])
+        .all(|(a, b)| a.fuzzy_eq(b));
+    if !affine {
+        problems.push(TransformProblem::NotAffine);
+    }
+
+    let axes = axes(m);
+    for (axis, v) in axes.iter().enumerate() {
+        if v.magnitude() < EPISILON {
+            problems.push(TransformProblem::ZeroScale { axis });
+        }
+    }
+
+    // How much volume is left compared to a box with sides as long as the axes, so that tiny
+    // bodies aren't mistaken for flat ones.
+    let volume = axes[0].dot(&axes[1].cross(&axes[2]));
+    let lengths: f64 = axes.iter().map(|v| v.magnitude()).product();
+    if problems
+        .iter()
+        .any(|p| matches!(p, TransformProblem::ZeroScale { .. }))
+    {
+        // Already flat.
+    } else if (volume / lengths).abs() < EPISILON {
+        problems.push(TransformProblem::CollapsedAxes);
+    } else {
+        // For affine transforms this is the determinant, which is what inverting checks.
+        if volume.abs() < EPISILON {
+            problems.push(TransformProblem::TooSmall);
+        }
+        if volume < 0.0 {
+            problems.push(TransformProblem::Mirrored);
+        }
+    }
+    problems
+}
+
+// Fixes what it can of the transform: the bottom row is put back to 0 0 0 1, and if the body is
+// squashed flat the axes are re-orthogonalized, keeping the longest ones where they are. Flattened
+// axes are left very thin rather than made as thick as the others, and bodies too small to invert
+// are grown evenly until they can be. Mirroring is left alone, since the body still renders
+// correctly.
+pub fn repaired(m: Matrix<4>) -> Matrix<4> {
+    let mut m = m;
+    m[3] = [0.0, 0.0, 0.0, 1.0];
+
+    let problems = transform_problems(&m);
+    let flat = problems.iter().any(|p| {
+        matches!(
+            p,
+            TransformProblem::ZeroScale { .. } | TransformProblem::CollapsedAxes
+        )
+    });
+    if flat {
+        m = reorthogonalized(m);
+    }
+
+    let volume = m.determinant().abs();
+    if volume < MIN_VOLUME {
+        let grow = (MIN_VOLUME / volume).cbrt();
+        for i in 0..3 {
+            for j in 0..3 {
+                m[i][j] *= grow;
+            }
+        }
+    }
+    m
+}
+
+fn reorthogonalized(mut m: Matrix<4>) -> Matrix<4> {
+    let axes = axes(&m);
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| axes[b].magnitude().total_cmp(&axes[a].magnitude()));
+
+    let mut units: [Option<Vector>; 3] = [None; 3];
+    let mut made_up = None;
+    for &axis in &order {
+        let mut v = axes[axis];
+        for u in units.iter().flatten() {
+            v -= *u * v.dot(u);
+        }
+        units[axis] = Some(if v.magnitude() >= EPISILON {
+            v.normalize()
+        } else {
+            made_up = Some(axis);
+            perpendicular(axis, &units)
+        });
+    }
+
+    // A made up axis could go either way, so pick the way that doesn't mirror the body.
+    let [x, y, z] = units.map(Option::unwrap);
+    if let Some(axis) = made_up {
+        if x.dot(&y.cross(&z)) < 0.0 {
+            units[axis] = units[axis].map(|u| -u);
+        }
+    }
+
+    for (j, unit) in units.iter().enumerate() {
+        let v = unit.unwrap() * axes[j].magnitude().max(MIN_SCALE);
+        for i in 0..3 {
+            m[i][j] = v[i];
+        }
+    }
+    m
+}
+
+// A unit vector for the axis at right angles to the ones already picked.
+fn perpendicular(axis: usize, units: &[Option<Vector>; 3]) -> Vector {
+    let standard = [
+        Vector::new(1.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 0.0, 1.0),
+    ];
+    let (next, after) = (units[(axis + 1) % 3], units[(axis + 2) % 3]);
+    match (next, after) {
+        (Some(a), Some(b)) => a.cross(&b),
+        (Some(u), None) | (None, Some(u)) => {
+            let other = if u[0].abs() < 0.9 {
+                standard[0]
+            } else {
+                standard[1]
+            };
+            u.cross(&other).normalize()
+        }
+        (None, None) => standard[axis],
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneObject {
+    Body(usize),
+    Instance(usize),
+}
+
+impl fmt::Display for SceneObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneObject::Body(i) => write!(f, "body {}", i),
+            SceneObject::Instance(i) => write!(f, "instance {}", i),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransformError {
+    pub object: SceneObject,
+    pub problem: TransformProblem,
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the transform of {} is degenerate: {}",
+            self.object, self.problem
+        )
+    }
+}
+
+impl Error for TransformError {}
+
+impl World {
+    // Every problem with the transforms of the bodies and instances in the world.
+    pub fn check_transforms(&self) -> Vec<TransformError> {
+        let bodies = self
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (SceneObject::Body(i), b.transform()));
+        let instances = self
+            .instances
+            .iter()
+            .enumerate()
+            .map(|(i, instance)| (SceneObject::Instance(i), instance.transform()));
+        bodies
+            .chain(instances)
+            .flat_map(|(object, transform)| {
+                transform_problems(&transform)
+                    .into_iter()
+                    .map(move |problem| TransformError { object, problem })
+            })
+            .collect()
+    }
+
+    // The world with every transform that can be repaired repaired (see `repaired`).
+    pub fn with_repaired_transforms(mut self) -> Self {
+        for body in self.bodies.iter_mut() {
+            *body = body.clone().with_transform(repaired(body.transform()));
+        }
+        let transforms: Vec<Matrix<4>> = self.instances.iter().map(|i| i.transform()).collect();
+        for (i, transform) in transforms.into_iter().enumerate() {
+            if !transform_problems(&transform).is_empty() {
+                self.instances.set_transform(i, repaired(transform));
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        matrix::{Rotation, Shear},
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn an_ordinary_transform_has_no_problems() {
+        let m = Matrix::translate(1.0, 2.0, 3.0)
+            * Matrix::rotate(Rotation::Y, 0.5)
+            * Matrix::scale(0.1, 0.2, 0.5);
+
+        assert!(transform_problems(&m).is_empty());
+    }
+
+    #[test]
+    fn finding_each_kind_of_problem() {
+        let mut projection = Matrix::identity();
+        projection[3] = [0.0, 0.0, 1.0, 0.0];
+        let cases = [
+            (
+                Matrix::scale(1.0, 0.0, 1.0),
+                vec![TransformProblem::ZeroScale { axis: 1 }],
+            ),
+            (
+                Matrix::scale(1.0, -1.0, 1.0),
+                vec![TransformProblem::Mirrored],
+            ),
+            (
+                Matrix::shear(&[Shear::XY(1.0), Shear::YX(1.0)]),
+                vec![TransformProblem::CollapsedAxes],
+            ),
+            (projection, vec![TransformProblem::NotAffine]),
+        ];
+
+        for (m, expected) in cases {
+            assert_eq!(expected, transform_problems(&m));
+        }
+    }
+
+    #[test]
+    fn repairing_a_flattened_transform_makes_it_invertible() {
+        for m in [
+            Matrix::translate(1.0, 0.0, 0.0) * Matrix::scale(2.0, 0.0, 3.0),
+            Matrix::scale(0.0, 0.0, 0.0),
+            Matrix::shear(&[Shear::XY(1.0), Shear::YX(1.0)]),
+        ] {
+            let fixed = repaired(m);
+
+            assert!(fixed.is_invertible());
+            assert!(transform_problems(&fixed).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_tiny_body_is_too_small_to_invert() {
+        let m = Matrix::scale(0.01, 0.01, 0.01);
+
+        assert_eq!(vec![TransformProblem::TooSmall], transform_problems(&m));
+        assert!(transform_problems(&repaired(m)).is_empty());
+    }
+
+    #[test]
+    fn repairing_keeps_the_axes_that_were_fine() {
+        let fixed = repaired(Matrix::translate(1.0, 0.0, 0.0) * Matrix::scale(2.0, 0.0, 3.0));
+
+        assert_fuzzy_eq!(
+            Matrix::translate(1.0, 0.0, 0.0) * Matrix::scale(2.0, MIN_SCALE, 3.0),
+            fixed
+        );
+    }
+
+    #[test]
+    fn repairing_resets_the_bottom_row_and_leaves_mirroring_alone() {
+        let mut m = Matrix::scale(-1.0, 1.0, 1.0);
+        m[3] = [0.0, 0.5, 0.0, 2.0];
+
+        let fixed = repaired(m);
+
+        assert_fuzzy_eq!(Matrix::scale(-1.0, 1.0, 1.0), fixed);
+    }
+
+    #[test]
+    fn checking_a_world_points_at_the_offending_body() {
+        let w = World::new(
+            vec![
+                Sphere::default().into(),
+                Sphere::default()
+                    .with_transform(Matrix::scale(1.0, 1.0, 0.0))
+                    .into(),
+            ],
+            vec![],
+        );
+
+        let errors = w.check_transforms();
+
+        assert_eq!(
+            vec![TransformError {
+                object: SceneObject::Body(1),
+                problem: TransformProblem::ZeroScale { axis: 2 },
+            }],
+            errors
+        );
+        assert_eq!(
+            "the transform of body 1 is degenerate: its z axis is scaled to zero",
+            errors[0].to_string()
+        );
+        assert!(w.with_repaired_transforms().check_transforms().is_empty());
+    }
+}
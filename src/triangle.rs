@@ -0,0 +1,324 @@
+use crate::{
+    bounds::{Bounded, Bounds},
+    fuzzy_eq::{FuzzyEq, EPISILON},
+    intersection::{HitRecord, Intersectable, Intersections, Normal},
+    material::Material,
+    matrix::Matrix,
+    point::Point,
+    ray::Ray,
+    transform::Transformable,
+    vector::Vector,
+};
+
+// A flat triangle, the building block of meshes. Which way it faces depends on the order of its
+// points: seen from the front, they go around counterclockwise.
+#[derive(Clone, Debug)]
+pub struct Triangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+    transform: Matrix<4>,
+    // Whether the transform is anything but the identity. Baked triangles aren't, so rays don't
+    // need transforming to hit them at all. Keeping the inverse here would save working it out for
+    // every ray into the others too, but would make every body bigger.
+    transformed: bool,
+    pub material: Material,
+    // Whether rays from the camera can skip the triangle when they hit its back, which only looks
+    // right if nothing can see its back, i.e. it's part of a closed mesh seen from outside.
+    cull_back_faces: bool,
+}
+
+impl FuzzyEq for Triangle {
+    fn fuzzy_eq(&self, other: Self) -> bool {
+        self.p1.fuzzy_eq(other.p1)
+            && self.p2.fuzzy_eq(other.p2)
+            && self.p3.fuzzy_eq(other.p3)
+            && self.transform.fuzzy_eq(other.transform)
+    }
+}
+
+impl Intersectable for Triangle {
+    // Möller–Trumbore: solve for the barycentric coordinates of the hit directly, rejecting the ray
+    // as soon as it falls outside of one of the edges.
+    fn intersect(&self, r: Ray) -> Intersections {
+        let object_space_ray = if self.transformed {
+            r.transform(self.transform.inverse())
+        } else {
+            r
+        };
+
+        let dir_cross_e2 = object_space_ray.direction.cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        if det.abs() < EPISILON {
+            // The ray is parallel to the triangle.
+            return vec![].into();
+        }
+        // det is the ray's direction dotted with the normal, so it's positive when the ray comes
+        // at the back of the triangle.
+        if det > 0.0 && self.cull_back_faces && r.cull_back_faces {
+            return vec![].into();
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = object_space_ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return vec![].into();
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * object_space_ray.direction.dot(&origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return vec![].into();
+        }
+
+        let t = f * self.e2.dot(&origin_cross_e1);
+        // The normal is the same all over, so it may as well go with the hit.
+        let hit = HitRecord::new(t, r, self.clone().into())
+            .with_uv(u, v)
+            .with_normal(self.normal_at(self.p1));
+        vec![hit].into()
+    }
+}
+
+impl Normal for Triangle {
+    fn normal_at(&self, _p: Point) -> Vector {
+        if !self.transformed {
+            return self.normal;
+        }
+        let world_normal = self.transform.inverse().transpose() * self.normal;
+        Vector::new(world_normal[0], world_normal[1], world_normal[2]).normalize()
+    }
+}
+
+impl Bounded for Triangle {
+    fn bounds(&self) -> Bounds {
+        let mut min = self.p1;
+        let mut max = self.p1;
+        for p in [self.p2, self.p3] {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+        Bounds::new(min, max)
+    }
+}
+
+impl Transformable for Triangle {
+    fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    fn with_transform(self, transform: Matrix<4>) -> Self {
+        Triangle::with_transform(self, transform)
+    }
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+            transform: Matrix::identity(),
+            transformed: false,
+            material: Material::default(),
+            cull_back_faces: false,
+        }
+    }
+
+    pub fn with_transform(self, transform: Matrix<4>) -> Self {
+        let identity = Matrix::<4>::identity();
+        Self {
+            transform,
+            transformed: (0..4).any(|i| transform[i] != identity[i]),
+            ..self
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Self {
+        Self { material, ..self }
+    }
+
+    pub fn with_back_face_culling(self) -> Self {
+        Self {
+            cull_back_faces: true,
+            ..self
+        }
+    }
+
+    pub fn transform(&self) -> Matrix<4> {
+        self.transform
+    }
+
+    pub fn culls_back_faces(&self) -> bool {
+        self.cull_back_faces
+    }
+
+    pub fn points(&self) -> (Point, Point, Point) {
+        (self.p1, self.p2, self.p3)
+    }
+
+    // The same triangle with its transform applied to the points, leaving it with no transform of
+    // its own, so intersecting it doesn't need to transform the ray first.
+    pub fn baked(&self) -> Self {
+        let t = self.transform;
+        let (p1, p2, p3) = (t * self.p1, t * self.p2, t * self.p3);
+        // A mirroring transform turns the points the other way around, which would flip the
+        // normal worked out from them. Swap two of them back so it faces the same way as before.
+        let baked = if t.determinant() < 0.0 {
+            Self::new(p1, p3, p2)
+        } else {
+            Self::new(p1, p2, p3)
+        };
+        Self {
+            material: self.material.clone(),
+            cull_back_faces: self.cull_back_faces,
+            ..baked
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, matrix::Rotation};
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = triangle();
+
+        assert_fuzzy_eq!(Vector::new(-1.0, -1.0, 0.0), t.e1);
+        assert_fuzzy_eq!(Vector::new(1.0, -1.0, 0.0), t.e2);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), t.normal);
+    }
+
+    #[test]
+    fn the_normal_of_a_triangle_is_the_same_everywhere() {
+        let t = triangle();
+
+        for p in [
+            Point::new(0.0, 0.5, 0.0),
+            Point::new(-0.5, 0.75, 0.0),
+            Point::new(0.5, 0.25, 0.0),
+        ] {
+            assert_fuzzy_eq!(t.normal, t.normal_at(p));
+        }
+    }
+
+    #[test]
+    fn intersecting_a_ray_parallel_to_the_triangle() {
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert!(triangle().intersect(r).is_empty());
+    }
+
+    #[test]
+    fn a_ray_misses_each_edge() {
+        for origin in [
+            Point::new(1.0, 1.0, -2.0),
+            Point::new(-1.0, 1.0, -2.0),
+            Point::new(0.0, -1.0, -2.0),
+        ] {
+            let r = Ray::new(origin, Vector::new(0.0, 0.0, 1.0));
+            assert!(triangle().intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn a_ray_strikes_a_triangle() {
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = triangle().intersect(r);
+
+        assert_eq!(1, xs.len());
+        assert_fuzzy_eq!(2.0, xs[0].t);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), xs[0].normal.unwrap());
+    }
+
+    #[test]
+    fn camera_rays_skip_the_backs_of_culled_triangles() {
+        let t = triangle().with_back_face_culling();
+        let front = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let back = Ray::new(Point::new(0.0, 0.5, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        assert_eq!(1, t.intersect(front.with_back_face_culling()).len());
+        assert!(t.intersect(back.with_back_face_culling()).is_empty());
+        // Other rays, like shadow rays, still see the back.
+        assert_eq!(1, t.intersect(back).len());
+        // And so do camera rays, for triangles that aren't culled.
+        assert_eq!(1, triangle().intersect(back.with_back_face_culling()).len());
+    }
+
+    #[test]
+    fn culling_carries_over_to_baked_triangles() {
+        let t = triangle()
+            .with_back_face_culling()
+            .with_transform(Matrix::scale(-1.0, 1.0, 1.0))
+            .baked();
+        let back = Ray::new(Point::new(0.0, 0.5, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        assert!(t.intersect(back.with_back_face_culling()).is_empty());
+    }
+
+    #[test]
+    fn a_hit_on_a_triangle_records_where_on_it_the_hit_is() {
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = triangle().intersect(r);
+
+        assert_fuzzy_eq!(0.45, xs[0].u);
+        assert_fuzzy_eq!(0.25, xs[0].v);
+    }
+
+    #[test]
+    fn a_baked_triangle_matches_the_transformed_one() {
+        let transforms = [
+            Matrix::translate(1.0, 2.0, 3.0) * Matrix::rotate(Rotation::Y, 0.5),
+            Matrix::scale(2.0, 0.5, 3.0),
+            Matrix::scale(-1.0, 1.0, 1.0),
+        ];
+        let r = Ray::new(Point::new(0.25, 0.5, -10.0), Vector::new(0.0, 0.0, 1.0));
+
+        for transform in transforms {
+            let t = triangle().with_transform(transform);
+            let baked = t.baked();
+
+            assert_fuzzy_eq!(Matrix::<4>::identity(), baked.transform());
+            let (xs, baked_xs) = (t.intersect(r), baked.intersect(r));
+            assert_eq!(xs.len(), baked_xs.len());
+            if !xs.is_empty() {
+                assert_fuzzy_eq!(xs[0].t, baked_xs[0].t);
+            }
+            let p = transform * Point::new(0.0, 0.5, 0.0);
+            assert_fuzzy_eq!(t.normal_at(p), baked.normal_at(p));
+        }
+    }
+
+    #[test]
+    fn only_triangles_with_a_transform_transform_rays() {
+        let moved = triangle().with_transform(Matrix::translate(1.0, 0.0, 0.0));
+
+        assert!(!triangle().transformed);
+        assert!(moved.transformed);
+        assert!(!moved.baked().transformed);
+        assert!(!moved.with_transform(Matrix::identity()).transformed);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     marker::PhantomData,
-    ops::{self, Add, Div, Index, IndexMut, Mul, Neg},
+    ops::{self, Add, AddAssign, Div, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
 use crate::fuzzy_eq::FuzzyEq;
@@ -11,6 +11,16 @@ pub struct Tuple<T, const N: usize> {
     marker: PhantomData<T>,
 }
 
+// Tuples can be built from arrays in const contexts, e.g. for named constants.
+impl<T, const N: usize> Tuple<T, N> {
+    pub const fn from_array(data: [f64; N]) -> Self {
+        Tuple {
+            data,
+            marker: PhantomData,
+        }
+    }
+}
+
 // Default can be generalized for all tuples.
 impl<T, const N: usize> Default for Tuple<T, N> {
     fn default() -> Self {
@@ -61,9 +71,10 @@ where
     }
 }
 
-// Scalar multiplication can be generalized for all tuples.
-impl<T, const N: usize> Mul<f64> for Tuple<T, N> {
-    type Output = Self;
+// Scalar multiplication can be generalized for all tuples. Each operator is implemented on
+// references, and the by-value forms forward to it.
+impl<T, const N: usize> Mul<f64> for &Tuple<T, N> {
+    type Output = Tuple<T, N>;
 
     fn mul(self, rhs: f64) -> Self::Output {
         let mut out = Self::Output::default();
@@ -74,34 +85,73 @@ impl<T, const N: usize> Mul<f64> for Tuple<T, N> {
     }
 }
 
+impl<T, const N: usize> Mul<f64> for Tuple<T, N> {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Mul::mul(&self, rhs)
+    }
+}
+
+impl<T, const N: usize> MulAssign<f64> for Tuple<T, N> {
+    fn mul_assign(&mut self, rhs: f64) {
+        for i in 0..N {
+            self[i] *= rhs;
+        }
+    }
+}
+
 // Scalar division follows from scalar multiplication.
+impl<T, const N: usize> Div<f64> for &Tuple<T, N> {
+    type Output = Tuple<T, N>;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        self * (1.0 / rhs)
+    }
+}
+
 impl<T, const N: usize> Div<f64> for Tuple<T, N> {
     type Output = Self;
 
     fn div(self, rhs: f64) -> Self::Output {
-        let mut out = Self::Output::default();
-        for i in 0..N {
-            out[i] = self[i] * 1.0 / rhs;
-        }
-        out
+        Div::div(&self, rhs)
     }
 }
 
 // Negation follows from scalar multiplication.
+impl<T, const N: usize> Neg for &Tuple<T, N> {
+    type Output = Tuple<T, N>;
+
+    fn neg(self) -> Self::Output {
+        self * -1.0
+    }
+}
+
 impl<T, const N: usize> Neg for Tuple<T, N> {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
+        Neg::neg(&self)
+    }
+}
+
+// Implementation for tuple addition. You get this if your U implements TupleAdd.
+pub trait TupleAdd {}
+impl<T, const N: usize> Add for &Tuple<T, N>
+where
+    T: TupleAdd,
+{
+    type Output = Tuple<T, N>;
+
+    fn add(self, rhs: Self) -> Self::Output {
         let mut out = Self::Output::default();
         for i in 0..N {
-            out[i] = self[i] * -1.0;
+            out[i] = self[i] + rhs[i];
         }
         out
     }
 }
 
-// Implementation for tuple addition. You get this if your U implements TupleAdd.
-pub trait TupleAdd {}
 impl<T, const N: usize> Add for Tuple<T, N>
 where
     T: TupleAdd,
@@ -109,11 +159,27 @@ where
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let mut out = Self::Output::default();
+        Add::add(&self, &rhs)
+    }
+}
+
+impl<T, const N: usize> AddAssign<&Tuple<T, N>> for Tuple<T, N>
+where
+    T: TupleAdd,
+{
+    fn add_assign(&mut self, rhs: &Self) {
         for i in 0..N {
-            out[i] = self[i] + rhs[i];
+            self[i] += rhs[i];
         }
-        out
+    }
+}
+
+impl<T, const N: usize> AddAssign for Tuple<T, N>
+where
+    T: TupleAdd,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        *self += &rhs;
     }
 }
 
@@ -121,11 +187,11 @@ where
 // TODO if you want an output type other than Self, I _think_ we'd need GATs which are not stable yet...
 // For now, for Point subtraction, we'll have to implement it explicitly.
 pub trait TupleSub {}
-impl<T, const N: usize> ops::Sub for Tuple<T, N>
+impl<T, const N: usize> Sub for &Tuple<T, N>
 where
     T: TupleSub,
 {
-    type Output = Self;
+    type Output = Tuple<T, N>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         let mut out = Self::Output::default();
@@ -136,14 +202,45 @@ where
     }
 }
 
+impl<T, const N: usize> Sub for Tuple<T, N>
+where
+    T: TupleSub,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Sub::sub(&self, &rhs)
+    }
+}
+
+impl<T, const N: usize> SubAssign<&Tuple<T, N>> for Tuple<T, N>
+where
+    T: TupleSub,
+{
+    fn sub_assign(&mut self, rhs: &Self) {
+        for i in 0..N {
+            self[i] -= rhs[i];
+        }
+    }
+}
+
+impl<T, const N: usize> SubAssign for Tuple<T, N>
+where
+    T: TupleSub,
+{
+    fn sub_assign(&mut self, rhs: Self) {
+        *self -= &rhs;
+    }
+}
+
 // Implementation for elementwise multiplication. You get this if your U implements ElementwiseMul.
 pub trait ElementwiseMul {}
 
-impl<T, const N: usize> ops::Mul for Tuple<T, N>
+impl<T, const N: usize> ops::Mul for &Tuple<T, N>
 where
     T: ElementwiseMul,
 {
-    type Output = Self;
+    type Output = Tuple<T, N>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         let mut out = Self::Output::default();
@@ -154,6 +251,17 @@ where
     }
 }
 
+impl<T, const N: usize> ops::Mul for Tuple<T, N>
+where
+    T: ElementwiseMul,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        ops::Mul::mul(&self, &rhs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -166,6 +274,10 @@ mod tests {
     struct TestTuple {}
     type Test = Tuple<TestTuple, 4>;
 
+    impl TupleAdd for TestTuple {}
+    impl TupleSub for TestTuple {}
+    impl ElementwiseMul for TestTuple {}
+
     #[test]
     fn test_mut_indexing() {
         let mut t = Test::default();
@@ -201,6 +313,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assign_op_pattern)]
     fn test_scalar_mult() {
         let mut t = Test::from([1.0, 1.0, 1.0, 1.0]);
         t = t * 0.5;
@@ -223,8 +336,6 @@ mod tests {
 
     #[test]
     fn test_add() {
-        impl TupleAdd for TestTuple {}
-
         let t1 = Test::from([1.0, 1.0, 1.0, 1.0]);
         let t2 = Test::from([1.0, 2.0, 3.0, 4.0]);
         let res = t1 + t2;
@@ -233,8 +344,6 @@ mod tests {
 
     #[test]
     fn test_elementwise_mul() {
-        impl ElementwiseMul for TestTuple {}
-
         let t1 = Test::from([-4.0, 3.0, -2.0, 1.0]);
         let t2 = Test::from([1.0, 2.0, 3.0, 4.0]);
         let res = t1 * t2;
@@ -243,11 +352,36 @@ mod tests {
 
     #[test]
     fn test_sub() {
-        impl TupleSub for TestTuple {}
-
         let t1 = Test::from([-4.0, 3.0, -2.0, 1.0]);
         let t2 = Test::from([1.0, 2.0, 3.0, 4.0]);
         let res = t1 - t2;
         assert_fuzzy_eq!(Test::from([-5.0, 1.0, -5.0, -3.0]), res);
     }
+
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn test_ops_by_reference() {
+        let t1 = Test::from([-4.0, 3.0, -2.0, 1.0]);
+        let t2 = Test::from([1.0, 2.0, 3.0, 4.0]);
+
+        assert_fuzzy_eq!(t1 + t2, &t1 + &t2);
+        assert_fuzzy_eq!(t1 - t2, &t1 - &t2);
+        assert_fuzzy_eq!(t1 * t2, &t1 * &t2);
+        assert_fuzzy_eq!(t1 * 2.0, &t1 * 2.0);
+        assert_fuzzy_eq!(t1 / 2.0, &t1 / 2.0);
+        assert_fuzzy_eq!(-t1, -&t1);
+    }
+
+    #[test]
+    fn test_ops_in_place() {
+        let mut t = Test::from([1.0, 1.0, 1.0, 1.0]);
+        t += Test::from([1.0, 2.0, 3.0, 4.0]);
+        assert_fuzzy_eq!(Test::from([2.0, 3.0, 4.0, 5.0]), t);
+
+        t -= &Test::from([1.0, 1.0, 1.0, 1.0]);
+        assert_fuzzy_eq!(Test::from([1.0, 2.0, 3.0, 4.0]), t);
+
+        t *= 2.0;
+        assert_fuzzy_eq!(Test::from([2.0, 4.0, 6.0, 8.0]), t);
+    }
 }
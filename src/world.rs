@@ -1,66 +1,687 @@
+use std::{collections::HashMap, sync::Arc};
+
 use crate::{
     body::Body,
     color::Color,
-    intersection::{Intersectable, Intersection, Intersections},
-    light::PointLight,
-    material::{Illuminated, ShadowState},
+    computed_intersection::{ComputedIntersection, Refraction},
+    csg::Csg,
+    environment::Environment,
+    fuzzy_eq::EPISILON,
+    instance::{Instance, Instances},
+    integrator::AreaLight,
+    intersection::{HitRecord, Intersectable, Intersections},
+    light::{Light, PointLight},
+    material::{Illuminated, Material, Phong, ShadowState},
+    matrix::Matrix,
+    medium::{IndexField, Medium},
+    pattern::Pattern,
+    plane::Plane,
     point::Point,
+    probe::LightProbe,
+    profile::{self, Section},
     ray::Ray,
+    sphere::Sphere,
+    stats::MemoryReport,
+    transform::Transformable,
 };
 
-#[derive(Default)]
+mod area_lights;
+mod audit;
+mod clipping;
+mod curved;
+mod detail;
+mod edit;
+mod export;
+mod frozen;
+mod helpers;
+mod material_override;
+mod pick;
+mod probes;
+mod shadow_cache;
+mod trace;
+
+pub use audit::*;
+pub use clipping::*;
+pub use detail::*;
+pub use edit::*;
+pub use frozen::*;
+pub use helpers::*;
+pub use material_override::*;
+pub use pick::*;
+pub use shadow_cache::*;
+pub use trace::*;
+
+// How many times a ray can be reflected or refracted before we stop following it.
+pub const MAX_BOUNCES: u32 = 5;
+
+#[derive(Clone, Default)]
 pub struct World {
     pub bodies: Vec<Body>,
-    pub lights: Vec<PointLight>,
+    // Copies of meshes, which are kept in bounding volume hierarchies rather than tested one body
+    // at a time like `bodies`.
+    pub instances: Instances,
+    // Solids made by combining bodies (see Csg). Their bodies are numbered after the instances'.
+    pub csgs: Vec<Csg>,
+    pub lights: Vec<Light>,
+    // Lit by estimates of the light they give off (see AreaLight).
+    pub area_lights: Vec<AreaLight>,
+    pub environment: Environment,
+    // If set, intersections closer together than this are merged (see Intersections::welded).
+    pub weld_epsilon: Option<f64>,
+    // If set, shadows from the lights are looked up here, and added to it, rather than always
+    // cast fresh (see ShadowCache).
+    pub shadow_cache: Option<Arc<ShadowCache>>,
+    // If set, everything on one side of it is left out (see ClippingPlane).
+    pub clipping_plane: Option<ClippingPlane>,
+    // If set, the materials of whatever's hit are replaced (see MaterialOverride).
+    pub material_override: Option<MaterialOverride>,
+    // Drawn in layout renders only (see Helper).
+    pub helpers: Vec<Helper>,
+    // If set, surfaces far from the camera are shaded more cheaply (see LevelOfDetail).
+    pub level_of_detail: Option<LevelOfDetail>,
+    // If set, rays curve through it rather than going straight (see IndexField).
+    pub index_field: Option<IndexField>,
+    // Light probes that named bodies reflect instead of the scene, by the body's name.
+    probes: HashMap<String, LightProbe>,
+    // Names given to bodies, by the body's index.
+    names: HashMap<usize, String>,
 }
 
 impl World {
-    pub fn new(bodies: Vec<Body>, lights: Vec<PointLight>) -> Self {
-        Self { bodies, lights }
+    pub fn new(bodies: Vec<Body>, lights: Vec<Light>) -> Self {
+        Self {
+            bodies,
+            instances: Instances::default(),
+            csgs: Vec::new(),
+            lights,
+            area_lights: Vec::new(),
+            environment: Environment::default(),
+            weld_epsilon: None,
+            shadow_cache: None,
+            clipping_plane: None,
+            material_override: None,
+            helpers: Vec::new(),
+            level_of_detail: None,
+            index_field: None,
+            probes: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    // A ready to render scene: a sphere resting on a checkered ground plane under a gradient sky,
+    // lit from above and behind the left shoulder. Pair it with Camera::quickstart.
+    pub fn quickstart() -> Self {
+        let floor = Plane::default().with_material(
+            Phong {
+                pattern: Some(Pattern::checker(
+                    Color::new(0.9, 0.9, 0.9),
+                    Color::new(0.3, 0.3, 0.35),
+                )),
+                specular: 0.0,
+                ..Phong::default()
+            }
+            .into(),
+        );
+        let ball = Sphere::default()
+            .with_transform(Matrix::translate(0.0, 1.0, 0.0))
+            .with_material(
+                Phong {
+                    color: Color::new(0.8, 0.2, 0.1),
+                    diffuse: 0.7,
+                    specular: 0.5,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+
+        World::new(vec![floor.into(), ball.into()], vec![light.into()]).with_environment(
+            Environment::Gradient {
+                horizon: Color::new(0.85, 0.9, 1.0),
+                zenith: Color::new(0.25, 0.45, 0.85),
+            },
+        )
+    }
+
+    pub fn with_environment(self, environment: Environment) -> Self {
+        Self {
+            environment,
+            ..self
+        }
+    }
+
+    pub fn with_instances(self, instances: Vec<Instance>) -> Self {
+        Self {
+            instances: Instances::new(instances),
+            ..self
+        }
+    }
+
+    pub fn with_body(mut self, body: impl Into<Body>) -> Self {
+        self.bodies.push(body.into());
+        self
+    }
+
+    // Adds a body that can be looked up by name, e.g. to say what was clicked on in a preview.
+    // Naming a body the same as another takes the name away from the other one.
+    pub fn with_named_body(mut self, name: &str, body: impl Into<Body>) -> Self {
+        self.names.retain(|_, n| n != name);
+        self.names.insert(self.bodies.len(), name.to_string());
+        self.bodies.push(body.into());
+        self
+    }
+
+    pub fn name_of(&self, body_id: usize) -> Option<&str> {
+        self.names.get(&body_id).map(String::as_str)
+    }
+
+    // The index of the body with the given name.
+    pub fn body_id(&self, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .find_map(|(&id, n)| if n == name { Some(id) } else { None })
+    }
+
+    pub fn body_named(&self, name: &str) -> Option<&Body> {
+        self.body_id(name).map(|id| &self.bodies[id])
+    }
+
+    // Adds the other world's bodies, instances and lights to this one, e.g. to build a scene out
+    // of props kept in worlds of their own. The other world's names come along too, taking them
+    // away from any bodies here with the same names, and so do the probes given to those names.
+    // Everything else about this world is kept.
+    pub fn merge(self, other: World) -> Self {
+        self.merge_with_transform(other, Matrix::identity())
+    }
+
+    // Like merge, but places everything from the other world with the transform first. Probes are
+    // moved to where their bodies end up, but what they captured isn't turned with them.
+    pub fn merge_with_transform(mut self, mut other: World, transform: Matrix<4>) -> Self {
+        let first_id = self.bodies.len();
+        for (id, name) in other.names {
+            self.names.retain(|_, n| *n != name);
+            match other.probes.remove(&name) {
+                Some(mut probe) => {
+                    probe.position = transform * probe.position;
+                    self.probes.insert(name.clone(), probe);
+                }
+                None => {
+                    self.probes.remove(&name);
+                }
+            }
+            self.names.insert(first_id + id, name);
+        }
+        self.bodies.extend(other.bodies.into_iter().map(|b| {
+            let transform = transform * b.transform();
+            b.with_transform(transform)
+        }));
+        self.csgs
+            .extend(other.csgs.into_iter().map(|c| c.transformed(transform)));
+        self.lights
+            .extend(other.lights.iter().map(|l| l.transformed(transform)));
+        self.area_lights
+            .extend(other.area_lights.into_iter().map(|l| {
+                let transform = transform * l.body.transform();
+                AreaLight {
+                    body: l.body.with_transform(transform),
+                    ..l
+                }
+            }));
+
+        if !other.instances.is_empty() {
+            let instances = self
+                .instances
+                .iter()
+                .cloned()
+                .chain(other.instances.iter().map(|i| {
+                    let placed = transform * i.transform();
+                    i.clone().with_transform(placed)
+                }))
+                .collect();
+            self.instances = Instances::new(instances);
+        }
+        self
+    }
+
+    pub fn with_csg(mut self, csg: Csg) -> Self {
+        self.csgs.push(csg);
+        self
+    }
+
+    pub fn with_weld_epsilon(self, weld_epsilon: f64) -> Self {
+        Self {
+            weld_epsilon: Some(weld_epsilon),
+            ..self
+        }
+    }
+
+    pub fn with_shadow_cache(self, shadow_cache: Arc<ShadowCache>) -> Self {
+        Self {
+            shadow_cache: Some(shadow_cache),
+            ..self
+        }
+    }
+
+    // Where the body with the id, as given to its intersections, is placed in the world, whether
+    // it's one of the bodies, in one of the instances or in one of the CSG solids.
+    pub fn body_transform(&self, body_id: usize) -> Option<Matrix<4>> {
+        if let Some(body) = self.bodies.get(body_id) {
+            return Some(body.transform());
+        }
+        let id = body_id - self.bodies.len();
+        let instanced = self.instances.body_count();
+        if id < instanced {
+            return self.instances.body_transform(id);
+        }
+        self.csgs
+            .iter()
+            .flat_map(Csg::bodies)
+            .nth(id - instanced)
+            .map(|body| body.transform())
+    }
+
+    // Where the world's memory goes.
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport::of(self)
     }
 
     pub fn intersect(&self, ray: Ray) -> Intersections {
-        let xss: Vec<Intersection> = self
+        let mut xss: Vec<HitRecord> = self
             .bodies
             .iter()
-            .flat_map(|body| body.intersect(ray))
+            .enumerate()
+            .flat_map(|(id, body)| {
+                body.intersect(ray)
+                    .into_iter()
+                    .map(move |x| x.with_body_id(id))
+            })
             .collect();
-        Intersections::from(xss)
+        xss.extend(self.instances.intersect(ray, self.bodies.len()));
+        let mut first_id = self.bodies.len() + self.instances.body_count();
+        for csg in &self.csgs {
+            xss.extend(csg.intersect(ray, first_id));
+            first_id += csg.bodies().count();
+        }
+        if let Some(clipping_plane) = &self.clipping_plane {
+            xss = clipping_plane.clip(ray, xss);
+        }
+        if let Some(material_override) = &self.material_override {
+            xss = xss
+                .into_iter()
+                .filter_map(|x| material_override.apply(x))
+                .collect();
+        }
+        let xs = Intersections::from(xss);
+        match self.weld_epsilon {
+            Some(epsilon) => xs.welded(epsilon),
+            None => xs,
+        }
+    }
+
+    // How many bounding boxes finding what the ray hits takes. Bodies outside of instances aren't
+    // grouped into a hierarchy, so that's one box for each of them for every ray.
+    pub fn bounds_tests(&self, ray: Ray) -> usize {
+        let combined: usize = self.csgs.iter().map(|c| c.bodies().count()).sum();
+        self.bodies.len() + combined + self.instances.bounds_tests(ray)
     }
 
     pub fn color_at(&self, ray: Ray) -> Color {
+        self.color_at_depth(ray, MAX_BOUNCES)
+    }
+
+    // The color seen along the ray, following at most `remaining` more reflections or
+    // refractions, except from surfaces whose materials set their own limit (see
+    // Phong::max_bounces).
+    pub fn color_at_depth(&self, ray: Ray, remaining: u32) -> Color {
+        if let Some(field) = &self.index_field {
+            if let Some(color) = self.curved_color_at(field, ray, remaining) {
+                return color;
+            }
+        }
+        self.straight_color_at(ray, remaining)
+    }
+
+    // color_at_depth for rays that go straight the whole way.
+    fn straight_color_at(&self, ray: Ray, remaining: u32) -> Color {
+        let xs = profile::time(Section::Intersection, || self.intersect(ray));
+        // Media are see-through, so the hit is the first solid surface.
+        let hit = first_solid_hit(&xs);
+        let color = if let Some(hit) = hit {
+            let c = hit.computed_with(&xs);
+            self.shade_hit(&c, remaining)
+        } else {
+            self.environment.color_at(ray)
+        };
+
+        let distance = hit.map_or(f64::INFINITY, |hit| hit.t);
+        self.through_media(ray, &xs, distance, color)
+    }
+
+    // A quick stand-in for color_at while laying out a scene. Bodies are shaded in their flat
+    // colors, darker the more they turn away from the eye, with no lights, shadows, reflections,
+    // refractions or media. Helpers are drawn too, over anything they're level with.
+    pub fn layout_color_at(&self, ray: Ray) -> Color {
         let xs = self.intersect(ray);
-        let hit = xs.hit();
-        if let Some(hit) = hit {
-            let c = hit.computed();
-            let material = hit.body.material();
-            let shadow_state = self.get_shadow_state(c.over_point);
-            // TODO implement proper lighting using all the lights, not just the first one
-            material.lighting(&self.lights[0], c.position, c.eye, c.normal, shadow_state)
+        let hit = match (first_solid_hit(&xs), self.helper_hit(ray)) {
+            (Some(hit), Some(helper)) if helper.t > hit.t + helper.surface_offset() => {
+                Some(hit.clone())
+            }
+            (hit, helper) => helper.or(hit.cloned()),
+        };
+        match hit {
+            Some(hit) => {
+                let c = hit.computed();
+                let facing = c.normal.dot(&c.eye.normalize());
+                let body = &c.intersection.body;
+                c.material().flat_color(body, c.position) * (0.2 + 0.8 * facing)
+            }
+            None => self.environment.color_at(ray),
+        }
+    }
+
+    pub fn shade_hit(&self, c: &ComputedIntersection, remaining: u32) -> Color {
+        let body = &c.intersection.body;
+        let material = c.material();
+        let soft_shadows = self
+            .level_of_detail
+            .is_none_or(|detail| detail.soft_shadows_at(distance_from_eye(c)));
+        let surface = self
+            .lights
+            .iter()
+            .enumerate()
+            .fold(Color::BLACK, |sum, (i, light)| {
+                let shadow_state = profile::time(Section::ShadowRays, || {
+                    self.shadow_state(
+                        i,
+                        c.over_point,
+                        soft_shadows,
+                        c.intersection.ray.light_sample,
+                    )
+                });
+                sum + profile::time(Section::Shading, || {
+                    material.lighting(body, light, c.position, c.eye, c.normal, shadow_state)
+                })
+            });
+
+        let surface = surface + self.area_lighting(c);
+
+        let reflected = self.reflected_color(c, remaining);
+        let refracted = self.refracted_color(c, remaining);
+        if material.reflective() > 0.0 && material.transparency_at(body, c.position) > 0.0 {
+            let reflectance = c.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
         } else {
-            Color::new(0.0, 0.0, 0.0)
+            surface + reflected + refracted
         }
     }
 
+    pub fn reflected_color(&self, c: &ComputedIntersection, remaining: u32) -> Color {
+        let reflective = c.intersection.body.material().reflective();
+        let remaining = self.bounces_left(c, remaining);
+        if remaining == 0 || reflective == 0.0 {
+            return Color::BLACK;
+        }
+
+        if let Some(probe) = self.probe_for(c.intersection.body_id) {
+            return probe.color_at(c.reflect) * reflective;
+        }
+        let ray = c.intersection.ray.continued(c.over_point, c.reflect);
+        self.color_at_depth(ray, remaining - 1) * reflective
+    }
+
+    pub fn refracted_color(&self, c: &ComputedIntersection, remaining: u32) -> Color {
+        let body = &c.intersection.body;
+        let transparency = body.material().transparency_at(body, c.position);
+        let remaining = self.bounces_left(c, remaining);
+        if remaining == 0 || transparency == 0.0 {
+            return Color::BLACK;
+        }
+
+        let ray = match c.refraction() {
+            Refraction::Transmitted { direction, .. } => {
+                c.intersection.ray.continued(c.under_point, direction)
+            }
+            // None of the light gets through, it all bounces back inside instead.
+            Refraction::TotalInternalReflection => {
+                c.intersection.ray.continued(c.over_point, c.reflect)
+            }
+        };
+        self.color_at_depth(ray, remaining - 1) * transparency
+    }
+
+    // Attenuates the color seen at the given distance along the ray by any media the ray passes
+    // through on the way, and adds the light those media scatter towards the eye.
+    fn through_media(&self, ray: Ray, xs: &Intersections, distance: f64, color: Color) -> Color {
+        let segments = self.media_segments(xs, distance);
+        if segments.is_empty() {
+            return color;
+        }
+
+        let optical_depth = |t: f64| -> f64 {
+            segments
+                .iter()
+                .map(|(medium, start, end)| medium.density * (t.min(*end) - start).max(0.0))
+                .sum()
+        };
+
+        // Each light reaches the points along the ray dimmed by the media on its way there, as well
+        // as by those between the point and the eye.
+        let mut scattered = Color::BLACK;
+        for (index, light) in self.lights.iter().enumerate() {
+            for (medium, start, end) in &segments {
+                let step = (end - start) / medium.steps as f64;
+                for i in 0..medium.steps {
+                    let t = start + (i as f64 + 0.5) * step;
+                    let p = ray.position(t);
+                    let visible = self.shadow_state(index, p, true, None).visible();
+                    if visible == 0.0 {
+                        continue;
+                    }
+                    scattered += medium.color
+                        * light.intensity()
+                        * (medium.density * step * (-optical_depth(t)).exp())
+                        * (visible * self.transmittance(light, p));
+                }
+            }
+        }
+
+        color * (-optical_depth(distance)).exp() + scattered
+    }
+
+    // How much of the light makes it through the media between it and the position.
+    fn transmittance(&self, light: &Light, position: Point) -> f64 {
+        let to_light = Ray::new(position, light.direction_from(position));
+        let xs = self.intersect(to_light);
+        let optical_depth: f64 = self
+            .media_segments(&xs, light.distance_from(position))
+            .iter()
+            .map(|(medium, start, end)| medium.density * (end - start))
+            .sum();
+        (-optical_depth).exp()
+    }
+
+    // The stretches of the ray, up to the given distance, that are inside a medium. The bodies
+    // media fill are closed, so each one's intersections pair up into where the ray goes in and
+    // out.
+    fn media_segments(&self, xs: &Intersections, distance: f64) -> Vec<(Medium, f64, f64)> {
+        let mut segments = Vec::new();
+        let mut seen = Vec::new();
+        for x in xs.iter() {
+            let medium = match x.body.material() {
+                Material::Medium(medium) => medium,
+                _ => continue,
+            };
+            let id = self.medium_id(x.body_id);
+            if seen.contains(&id) {
+                continue;
+            }
+            seen.push(id);
+
+            let ts: Vec<f64> = xs
+                .iter()
+                .filter(|other| {
+                    self.medium_id(other.body_id) == id
+                        && matches!(other.body.material(), Material::Medium(_))
+                })
+                .map(|other| other.t)
+                .collect();
+            for pair in ts.chunks_exact(2) {
+                let (start, end) = (pair[0].max(0.0), pair[1].min(distance));
+                if start < end {
+                    segments.push((medium, start, end));
+                }
+            }
+        }
+        segments
+    }
+
+    // Which medium the body with the id is part of. A mesh's triangles only close around a medium
+    // together, so every body in an instance shares the id of its first.
+    fn medium_id(&self, body_id: usize) -> usize {
+        let first = self.bodies.len();
+        body_id
+            .checked_sub(first)
+            .and_then(|id| self.instances.first_id_in_instance(id))
+            .map_or(body_id, |id| first + id)
+    }
+
+    // How much of the first light gets to the position.
+    #[cfg(test)]
     fn get_shadow_state(&self, position: Point) -> ShadowState {
-        let shadow_vec = self.lights[0].position - position;
-        let distance = shadow_vec.magnitude();
-        let shadow_ray = Ray::new(position, shadow_vec.normalize());
-        let xs = self.intersect(shadow_ray);
-        if let Some(hit) = xs.hit() {
-            if hit.t < distance {
-                return ShadowState::Shadow;
+        self.shadow_state(0, position, true, None)
+    }
+
+    // How much of the light with the given index gets to the position. Soft shadows can be turned
+    // off to cast just one shadow ray, at the middle of the light. The sample picks the points on
+    // the light to cast at (see PointLight::shadow_targets).
+    fn shadow_state(
+        &self,
+        light: usize,
+        position: Point,
+        soft: bool,
+        sample: Option<f64>,
+    ) -> ShadowState {
+        let (clear, cast) = match &self.shadow_cache {
+            _ if !soft => self.hard_shadow_samples(&self.lights[light], position),
+            Some(cache) => cache.samples(light, &self.lights[light], position, || {
+                self.light_samples(&self.lights[light], position, sample)
+            }),
+            None => self.light_samples(&self.lights[light], position, sample),
+        };
+        match clear {
+            0 => ShadowState::Shadow,
+            n if n == cast => ShadowState::Clear,
+            n => ShadowState::Partial(n as f64 / cast as f64),
+        }
+    }
+
+    // How many shadow rays from the position to the light got through, out of how many were cast.
+    fn light_samples(&self, light: &Light, position: Point, sample: Option<f64>) -> (usize, usize) {
+        match light {
+            Light::Point(l) => self.shadow_samples(l, position, sample),
+            // Directional lights have no size to soften shadows with.
+            Light::Directional(_) => self.hard_shadow_samples(light, position),
+        }
+    }
+
+    // Whether the one shadow ray from the position to the middle of the light got through.
+    fn hard_shadow_samples(&self, light: &Light, position: Point) -> (usize, usize) {
+        let shadow_ray = Ray::new(position, light.direction_from(position));
+        let clear = !self.is_blocked(shadow_ray, light.distance_from(position));
+        (usize::from(clear), 1)
+    }
+
+    // How many shadow rays from the position to the point light got through, out of how many were
+    // cast. An adaptive light's first few rays either all agree, and the point is taken to be fully
+    // lit or fully shadowed, or they don't, and it's in a penumbra and the rest are cast too.
+    fn shadow_samples(
+        &self,
+        light: &PointLight,
+        position: Point,
+        sample: Option<f64>,
+    ) -> (usize, usize) {
+        let count_clear = |targets: &mut dyn Iterator<Item = Point>| {
+            targets
+                .filter(|&target| {
+                    let to_light = target - position;
+                    let distance = to_light.magnitude();
+                    // Nothing can be in the way of a target the position is already at.
+                    distance <= EPISILON
+                        || !self.is_blocked(Ray::new(position, to_light.normalize()), distance)
+                })
+                .count()
+        };
+
+        let mut cast = 0;
+        let mut clear = 0;
+        if light.is_adaptive() {
+            let mut first = light.first_shadow_targets(position, sample);
+            cast = first.len();
+            clear = count_clear(&mut first);
+            if clear == 0 || clear == cast {
+                return (clear, cast);
             }
         }
-        ShadowState::Clear
+        // Pooling the first rays with the rest wastes none of them.
+        let mut rest = light.shadow_targets(position, sample);
+        cast += rest.len();
+        (clear + count_clear(&mut rest), cast)
+    }
+
+    // How many more reflections or refractions rays leaving the surface can take. A material with
+    // its own limit replaces `remaining` with however many of its bounces the ray hasn't used yet,
+    // which can be more or fewer, and far away surfaces can be held to fewer still.
+    fn bounces_left(&self, c: &ComputedIntersection, remaining: u32) -> u32 {
+        let left = match c.intersection.body.material().max_bounces() {
+            Some(limit) => limit.saturating_sub(c.intersection.ray.bounces),
+            None => remaining,
+        };
+        match self.level_of_detail {
+            Some(detail) => detail.bounces_at(distance_from_eye(c), left),
+            None => left,
+        }
+    }
+
+    // Whether something solid is in the way of the ray before it's gone the distance.
+    pub fn is_blocked(&self, ray: Ray, distance: f64) -> bool {
+        let xs = self.intersect(ray);
+        first_solid_hit(&xs).is_some_and(|hit| hit.t < distance)
     }
 }
 
+fn first_solid_hit(xs: &Intersections) -> Option<&HitRecord> {
+    xs.iter()
+        .find(|x| x.t > 0.0 && !matches!(x.body.material(), Material::Medium(_)))
+}
+
+// How far the light has come from the camera to the surface.
+fn distance_from_eye(c: &ComputedIntersection) -> f64 {
+    let ray = c.intersection.ray;
+    ray.travelled + (c.position - ray.origin).magnitude()
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{f64::consts::FRAC_1_SQRT_2, sync::Arc};
+
     use crate::{
-        assert_fuzzy_eq, color::Color, fuzzy_eq::FuzzyEq, material::Phong, matrix::Matrix,
-        point::Point, ray::Ray, sphere::Sphere, vector::Vector,
+        assert_fuzzy_eq,
+        camera::Camera,
+        color::Color,
+        fuzzy_eq::FuzzyEq,
+        instance::{Instance, Mesh},
+        light::{DirectionalLight, PointLight},
+        material::Phong,
+        matrix::Matrix,
+        medium::Medium,
+        pattern::Projection,
+        plane::Plane,
+        point::Point,
+        ray::Ray,
+        sky::Sky,
+        sphere::Sphere,
+        triangle::Triangle,
+        vector::Vector,
     };
 
     use super::*;
@@ -80,7 +701,7 @@ mod tests {
             .with_transform(Matrix::scale(0.5, 0.5, 0.5))
             .into();
 
-        World::new(vec![s1, s2], vec![light])
+        World::new(vec![s1, s2], vec![light.into()])
     }
 
     #[test]
@@ -113,6 +734,254 @@ mod tests {
         assert_fuzzy_eq!(Color::new(0.0, 0.0, 0.0), c);
     }
 
+    #[test]
+    fn a_ray_that_misses_sees_the_environment() {
+        let w = create_default_world().with_environment(Color::new(0.2, 0.4, 0.8).into());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_fuzzy_eq!(Color::new(0.2, 0.4, 0.8), w.color_at(r));
+    }
+
+    #[test]
+    fn a_ray_that_misses_can_see_the_sky() {
+        let sky = Sky::default();
+        let w = create_default_world().with_environment(sky.into());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+
+        assert_fuzzy_eq!(sky.color_at(Vector::new(0.0, 1.0, 0.0)), w.color_at(r));
+    }
+
+    #[test]
+    fn a_directional_light_casts_shadows_from_any_distance() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let blocker: Body = Sphere::default()
+            .with_transform(Matrix::translate(0.0, 1000.0, 0.0))
+            .into();
+        let w = World::new(vec![blocker], vec![light.into()]);
+
+        assert!(matches!(
+            w.get_shadow_state(Point::new(0.0, 0.0, 0.0)),
+            ShadowState::Shadow
+        ));
+        assert!(matches!(
+            w.get_shadow_state(Point::new(5.0, 0.0, 0.0)),
+            ShadowState::Clear
+        ));
+    }
+
+    #[test]
+    fn a_light_with_a_radius_casts_soft_edged_shadows() {
+        // A wall at x <= 0 with the light straight above its edge, so a point just under the edge
+        // sees about half the light.
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).with_radius(1.0, 64);
+        let blocker: Body = Sphere::default()
+            .with_transform(Matrix::translate(-50.0, 5.0, 0.0) * Matrix::scale(50.0, 0.1, 50.0))
+            .into();
+        let w = World::new(vec![blocker], vec![light.into()]);
+
+        let edge = w.get_shadow_state(Point::new(0.0, 0.0, 0.0)).visible();
+        assert!((0.3..0.7).contains(&edge), "{}", edge);
+        assert!(matches!(
+            w.get_shadow_state(Point::new(-3.0, 0.0, 0.0)),
+            ShadowState::Shadow
+        ));
+        assert!(matches!(
+            w.get_shadow_state(Point::new(3.0, 0.0, 0.0)),
+            ShadowState::Clear
+        ));
+    }
+
+    #[test]
+    fn a_light_without_a_radius_casts_hard_shadows() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE);
+        let blocker: Body = Sphere::default()
+            .with_transform(Matrix::translate(-50.0, 5.0, 0.0) * Matrix::scale(50.0, 0.1, 50.0))
+            .into();
+        let w = World::new(vec![blocker], vec![light.into()]);
+
+        assert!(matches!(
+            w.get_shadow_state(Point::new(-0.1, 0.0, 0.0)),
+            ShadowState::Shadow
+        ));
+        assert!(matches!(
+            w.get_shadow_state(Point::new(0.1, 0.0, 0.0)),
+            ShadowState::Clear
+        ));
+    }
+
+    #[test]
+    fn adaptive_lights_only_add_shadow_rays_at_shadow_edges() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE)
+            .with_radius(1.0, 64)
+            .with_min_samples(8);
+        let blocker: Body = Sphere::default()
+            .with_transform(Matrix::translate(-50.0, 5.0, 0.0) * Matrix::scale(50.0, 0.1, 50.0))
+            .into();
+        let w = World::new(vec![blocker], vec![light.into()]);
+
+        assert_eq!(
+            (8, 8),
+            w.shadow_samples(&light, Point::new(3.0, 0.0, 0.0), None)
+        );
+        assert_eq!(
+            (0, 8),
+            w.shadow_samples(&light, Point::new(-3.0, 0.0, 0.0), None)
+        );
+        let (clear, cast) = w.shadow_samples(&light, Point::new(0.0, 0.0, 0.0), None);
+        assert_eq!(72, cast);
+        assert!((0.3..0.7).contains(&(clear as f64 / cast as f64)));
+    }
+
+    fn fog_sphere(color: Color, density: f64) -> Body {
+        Sphere::default()
+            .with_material(
+                Medium {
+                    color,
+                    density,
+                    ..Medium::default()
+                }
+                .into(),
+            )
+            .into()
+    }
+
+    #[test]
+    fn a_medium_attenuates_what_is_behind_it() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let w = World::new(vec![fog_sphere(Color::BLACK, 1.0)], vec![light.into()])
+            .with_environment(Color::WHITE.into());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let expected = (-2.0f64).exp();
+        assert_fuzzy_eq!(Color::new(expected, expected, expected), w.color_at(r));
+    }
+
+    #[test]
+    fn media_in_the_same_place_both_attenuate() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let w = World::new(
+            vec![fog_sphere(Color::BLACK, 1.0), fog_sphere(Color::BLACK, 0.5)],
+            vec![light.into()],
+        )
+        .with_environment(Color::WHITE.into());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let expected = (-3.0f64).exp();
+        assert_fuzzy_eq!(Color::new(expected, expected, expected), w.color_at(r));
+    }
+
+    #[test]
+    fn a_medium_can_fill_a_mesh() {
+        // A tetrahedron the ray goes in through one face and out through another.
+        let fog = Material::from(Medium {
+            color: Color::BLACK,
+            density: 1.0,
+            ..Medium::default()
+        });
+        let [a, b, c, d] = [
+            Point::new(-1.0, -1.0, -1.0),
+            Point::new(1.0, -1.0, -1.0),
+            Point::new(0.0, 1.0, -1.0),
+            Point::new(0.0, 0.0, 1.0),
+        ];
+        let faces = [(a, b, c), (a, b, d), (b, c, d), (c, a, d)]
+            .iter()
+            .map(|&(p1, p2, p3)| Triangle::new(p1, p2, p3).with_material(fog.clone()).into())
+            .collect();
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let w = World::new(vec![], vec![light.into()])
+            .with_environment(Color::WHITE.into())
+            .with_instances(vec![Instance::new(Arc::new(Mesh::new(faces)))]);
+        let r = Ray::new(Point::new(0.0, -0.25, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        // In through the base at z = -1 and out through the face below the tip at z = 0.5.
+        let expected = (-1.5f64).exp();
+        assert_fuzzy_eq!(Color::new(expected, expected, expected), w.color_at(r));
+    }
+
+    #[test]
+    fn a_ray_starting_inside_a_medium_is_attenuated_from_its_origin() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let w = World::new(vec![fog_sphere(Color::BLACK, 1.0)], vec![light.into()])
+            .with_environment(Color::WHITE.into());
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+
+        let expected = (-1.0f64).exp();
+        assert_fuzzy_eq!(Color::new(expected, expected, expected), w.color_at(r));
+    }
+
+    #[test]
+    fn a_lit_medium_scatters_light_towards_the_eye() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let w = World::new(vec![fog_sphere(Color::RED, 1.0)], vec![light.into()]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let c = w.color_at(r);
+
+        // The fog dims the light on its way in too, or it would be about 0.86.
+        assert!((0.35..0.5).contains(&c[0]));
+        assert_fuzzy_eq!(0.0, c[1]);
+    }
+
+    #[test]
+    fn a_medium_scatters_every_light() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let one = World::new(vec![fog_sphere(Color::RED, 1.0)], vec![light.into()]);
+        let two = World::new(
+            vec![fog_sphere(Color::RED, 1.0)],
+            vec![light.into(), light.into()],
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_fuzzy_eq!(one.color_at(r) * 2.0, two.color_at(r));
+    }
+
+    #[test]
+    fn fog_dims_the_light_on_its_way_in() {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE);
+        let w = World::new(vec![fog_sphere(Color::WHITE, 1.0)], vec![light.into()]);
+
+        // Points deeper in the fog are further from its top.
+        assert_fuzzy_eq!(
+            1.0,
+            w.transmittance(&w.lights[0], Point::new(0.0, 1.0, 0.0))
+        );
+        assert_fuzzy_eq!(
+            (-1.0f64).exp(),
+            w.transmittance(&w.lights[0], Point::new(0.0, 0.0, 0.0))
+        );
+        assert_fuzzy_eq!(
+            (-2.0f64).exp(),
+            w.transmittance(&w.lights[0], Point::new(0.0, -1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn a_shadowed_medium_scatters_nothing() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let blocker: Body = Sphere::default()
+            .with_transform(Matrix::translate(0.0, 5.0, 0.0) * Matrix::scale(3.0, 1.0, 3.0))
+            .into();
+        let w = World::new(
+            vec![fog_sphere(Color::RED, 1.0), blocker],
+            vec![light.into()],
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_fuzzy_eq!(Color::BLACK, w.color_at(r));
+    }
+
+    #[test]
+    fn media_do_not_cast_shadows() {
+        let light = DirectionalLight::new(Vector::new(0.0, -1.0, 0.0), Color::WHITE);
+        let w = World::new(vec![fog_sphere(Color::BLACK, 1.0)], vec![light.into()]);
+
+        assert!(matches!(
+            w.get_shadow_state(Point::new(0.0, 0.0, 0.0)),
+            ShadowState::Clear
+        ));
+    }
+
     #[test]
     fn color_when_a_ray_hits() {
         let w = create_default_world();
@@ -121,4 +990,519 @@ mod tests {
 
         assert_fuzzy_eq!(Color::new(0.38066, 0.47583, 0.2855), c);
     }
+
+    #[test]
+    fn every_light_shades_what_it_reaches() {
+        let mut w = create_default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let one = w.color_at(r);
+        // The same light again from behind the ball, where it can't reach what the ray hits,
+        // only adds its ambient light.
+        let behind = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::WHITE);
+
+        w.lights.push(w.lights[0]);
+        assert_fuzzy_eq!(one * 2.0, w.color_at(r));
+        w.lights[1] = behind.into();
+        assert_fuzzy_eq!(one + Color::new(0.08, 0.1, 0.06), w.color_at(r));
+    }
+
+    #[test]
+    fn the_quickstart_camera_sees_the_ball_the_floor_and_the_sky() {
+        let w = World::quickstart();
+        let camera = Camera::quickstart(100, 50);
+
+        let ball = w.color_at(camera.ray_for_pixel(50, 25));
+        assert!(ball[0] > ball[1] && ball[0] > ball[2]);
+
+        let sky = w.color_at(camera.ray_for_pixel(0, 0));
+        assert!(sky[2] > sky[0]);
+
+        let floor = w.color_at(camera.ray_for_pixel(5, 49));
+        assert!(floor[0] > 0.0);
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_nonreflective_material() {
+        let mut w = create_default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        w.bodies[1] = Sphere::default()
+            .with_transform(Matrix::scale(0.5, 0.5, 0.5))
+            .with_material(
+                Phong {
+                    ambient: 1.0,
+                    ..Phong::default()
+                }
+                .into(),
+            )
+            .into();
+        let i = HitRecord::new(1.0, r, w.bodies[1].clone());
+
+        let color = w.reflected_color(&i.computed(), MAX_BOUNCES);
+
+        assert_fuzzy_eq!(Color::BLACK, color);
+    }
+
+    fn reflective_floor() -> Body {
+        Plane::default()
+            .with_transform(Matrix::translate(0.0, -1.0, 0.0))
+            .with_material(
+                Phong {
+                    reflective: 0.5,
+                    ..Phong::default()
+                }
+                .into(),
+            )
+            .into()
+    }
+
+    #[test]
+    fn the_reflected_color_for_a_reflective_material() {
+        let mut w = create_default_world();
+        w.bodies.push(reflective_floor());
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let i = HitRecord::new(2f64.sqrt(), r, w.bodies[2].clone());
+
+        let color = w.reflected_color(&i.computed(), MAX_BOUNCES);
+
+        // The book's figures are rounded, so only compare to 4 places.
+        for (expected, actual) in [0.19033, 0.23791, 0.14274]
+            .iter()
+            .zip([color[0], color[1], color[2]])
+        {
+            assert!((expected - actual).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn the_reflected_color_at_the_maximum_recursive_depth() {
+        let mut w = create_default_world();
+        w.bodies.push(reflective_floor());
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let i = HitRecord::new(2f64.sqrt(), r, w.bodies[2].clone());
+
+        let color = w.reflected_color(&i.computed(), 0);
+
+        assert_fuzzy_eq!(Color::BLACK, color);
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces_terminates() {
+        let mirror: Material = Phong {
+            reflective: 1.0,
+            ..Phong::default()
+        }
+        .into();
+        let lower = Plane::default()
+            .with_transform(Matrix::translate(0.0, -1.0, 0.0))
+            .with_material(mirror.clone());
+        let upper = Plane::default()
+            .with_transform(Matrix::translate(0.0, 1.0, 0.0))
+            .with_material(mirror);
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::WHITE);
+        let w = World::new(vec![lower.into(), upper.into()], vec![light.into()]);
+
+        let color = w.color_at(Ray::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+
+        assert!(color[0] > 0.0);
+    }
+
+    #[test]
+    fn materials_can_follow_more_or_fewer_bounces_than_the_world() {
+        let facing_mirrors = |max_bounces| {
+            let mirror: Material = Phong {
+                reflective: 0.5,
+                max_bounces,
+                ..Phong::default()
+            }
+            .into();
+            let lower = Plane::default()
+                .with_transform(Matrix::translate(0.0, -1.0, 0.0))
+                .with_material(mirror.clone());
+            let upper = Plane::default()
+                .with_transform(Matrix::translate(0.0, 1.0, 0.0))
+                .with_material(mirror);
+            let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::WHITE);
+            World::new(vec![lower.into(), upper.into()], vec![light.into()])
+        };
+        let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        let fewer = facing_mirrors(Some(2)).color_at(ray);
+        let more = facing_mirrors(Some(8)).color_at(ray);
+
+        assert_fuzzy_eq!(facing_mirrors(None).color_at_depth(ray, 2), fewer);
+        assert_fuzzy_eq!(facing_mirrors(None).color_at_depth(ray, 8), more);
+        assert!(more[0] > fewer[0]);
+    }
+
+    #[test]
+    fn the_refracted_color_with_an_opaque_surface() {
+        let w = create_default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Intersections = vec![
+            HitRecord::new(4.0, r, w.bodies[0].clone()),
+            HitRecord::new(6.0, r, w.bodies[0].clone()),
+        ]
+        .into();
+
+        let color = w.refracted_color(&xs[0].computed_with(&xs), MAX_BOUNCES);
+
+        assert_fuzzy_eq!(Color::BLACK, color);
+    }
+
+    fn with_glass_outer_sphere(mut w: World) -> World {
+        let outer = match w.bodies[0].clone() {
+            Body::Sphere(s) => s,
+            _ => unreachable!(),
+        };
+        let material = match outer.material.clone() {
+            Material::Phong(p) => Phong {
+                transparency: 1.0,
+                refractive_index: 1.5,
+                ..p
+            },
+            _ => unreachable!(),
+        };
+        w.bodies[0] = outer.with_material(material.into()).into();
+        w
+    }
+
+    #[test]
+    fn a_transparency_map_can_make_glass_opaque_in_places() {
+        let mut w = with_glass_outer_sphere(create_default_world());
+        let glass = match w.bodies[0].clone() {
+            Body::Sphere(s) => s,
+            _ => unreachable!(),
+        };
+        let material = match glass.material.clone() {
+            Material::Phong(p) => Phong {
+                transparency_map: Some(Pattern::stripe(Color::BLACK, Color::BLACK)),
+                ..p
+            },
+            _ => unreachable!(),
+        };
+        w.bodies[0] = glass.with_material(material.into()).into();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Intersections = vec![
+            HitRecord::new(4.0, r, w.bodies[0].clone()),
+            HitRecord::new(6.0, r, w.bodies[0].clone()),
+        ]
+        .into();
+
+        let color = w.refracted_color(&xs[0].computed_with(&xs), MAX_BOUNCES);
+
+        assert_fuzzy_eq!(Color::BLACK, color);
+    }
+
+    #[test]
+    fn the_refracted_color_at_the_maximum_recursive_depth() {
+        let w = with_glass_outer_sphere(create_default_world());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Intersections = vec![
+            HitRecord::new(4.0, r, w.bodies[0].clone()),
+            HitRecord::new(6.0, r, w.bodies[0].clone()),
+        ]
+        .into();
+
+        let color = w.refracted_color(&xs[0].computed_with(&xs), 0);
+
+        assert_fuzzy_eq!(Color::BLACK, color);
+    }
+
+    #[test]
+    fn the_refracted_color_under_total_internal_reflection_is_the_reflected_light() {
+        let w = with_glass_outer_sphere(create_default_world());
+        let r = Ray::new(
+            Point::new(0.0, 0.0, FRAC_1_SQRT_2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs: Intersections = vec![
+            HitRecord::new(-FRAC_1_SQRT_2, r, w.bodies[0].clone()),
+            HitRecord::new(FRAC_1_SQRT_2, r, w.bodies[0].clone()),
+        ]
+        .into();
+        let c = xs[1].computed_with(&xs);
+
+        let color = w.refracted_color(&c, MAX_BOUNCES);
+
+        // Rather than going black, the light bounces back into the sphere.
+        assert!(color.fuzzy_ne(Color::BLACK));
+        assert_fuzzy_eq!(
+            w.color_at_depth(Ray::new(c.over_point, c.reflect), MAX_BOUNCES - 1),
+            color
+        );
+    }
+
+    #[test]
+    fn shading_a_transparent_material() {
+        let mut w = create_default_world();
+        let floor = Plane::default()
+            .with_transform(Matrix::translate(0.0, -1.0, 0.0))
+            .with_material(
+                Phong {
+                    transparency: 0.5,
+                    refractive_index: 1.5,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+        let ball = Sphere::default()
+            .with_transform(Matrix::translate(0.0, -3.5, -0.5))
+            .with_material(
+                Phong {
+                    color: Color::new(1.0, 0.0, 0.0),
+                    ambient: 0.5,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+        w.bodies.push(floor.into());
+        w.bodies.push(ball.into());
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let xs: Intersections = vec![HitRecord::new(2f64.sqrt(), r, w.bodies[2].clone())].into();
+
+        let color = w.shade_hit(&xs[0].computed_with(&xs), MAX_BOUNCES);
+
+        for (expected, actual) in [0.93642, 0.68642, 0.68642]
+            .iter()
+            .zip([color[0], color[1], color[2]])
+        {
+            assert!((expected - actual).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn shading_a_reflective_transparent_material_uses_schlick() {
+        let mut w = create_default_world();
+        let floor = Plane::default()
+            .with_transform(Matrix::translate(0.0, -1.0, 0.0))
+            .with_material(
+                Phong {
+                    reflective: 0.5,
+                    transparency: 0.5,
+                    refractive_index: 1.5,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+        let ball = Sphere::default()
+            .with_transform(Matrix::translate(0.0, -3.5, -0.5))
+            .with_material(
+                Phong {
+                    color: Color::new(1.0, 0.0, 0.0),
+                    ambient: 0.5,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+        w.bodies.push(floor.into());
+        w.bodies.push(ball.into());
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let xs: Intersections = vec![HitRecord::new(2f64.sqrt(), r, w.bodies[2].clone())].into();
+
+        let color = w.shade_hit(&xs[0].computed_with(&xs), MAX_BOUNCES);
+
+        for (expected, actual) in [0.93391, 0.69643, 0.69243]
+            .iter()
+            .zip([color[0], color[1], color[2]])
+        {
+            assert!((expected - actual).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn intersecting_coincident_bodies_prefers_the_first_one() {
+        let red = Phong {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Phong::default()
+        };
+        let blue = Phong {
+            color: Color::new(0.0, 0.0, 1.0),
+            ..Phong::default()
+        };
+        let w = World::new(
+            vec![
+                Sphere::default().with_material(red.into()).into(),
+                Sphere::default().with_material(blue.into()).into(),
+            ],
+            vec![],
+        );
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = w.intersect(r);
+
+        assert_eq!(4, xs.len());
+        assert_eq!(0, xs[0].body_id);
+        assert_eq!(1, xs[1].body_id);
+        assert_eq!(2, w.with_weld_epsilon(1e-5).intersect(r).len());
+    }
+
+    #[test]
+    fn instances_are_intersected_after_the_plain_bodies() {
+        let mesh = Arc::new(Mesh::new(vec![Sphere::default().into()]));
+        let w =
+            World::new(vec![Sphere::default().into()], vec![])
+                .with_instances(vec![
+                    Instance::new(mesh).with_transform(Matrix::translate(0.0, 0.0, 3.0))
+                ]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let xs = w.intersect(r);
+
+        assert_eq!(4, xs.len());
+        assert_fuzzy_eq!(7.0, xs[2].t);
+        assert_eq!(1, xs[2].body_id);
+        assert_fuzzy_eq!(Matrix::translate(0.0, 0.0, 3.0), xs[2].body.transform());
+    }
+
+    #[test]
+    fn the_layout_color_is_the_flat_color_shaded_by_facing() {
+        let w = create_default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_fuzzy_eq!(Color::new(0.8, 1.0, 0.6), w.layout_color_at(r));
+    }
+
+    #[test]
+    fn the_layout_color_of_a_glancing_hit_is_darker() {
+        let w = create_default_world();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -5.0),
+            Vector::new(0.0, 0.6, 5.0).normalize(),
+        );
+
+        let color = w.layout_color_at(r);
+
+        assert!(color[0] < 0.8 && color[0] > 0.16);
+    }
+
+    #[test]
+    fn merging_worlds_places_the_other_worlds_bodies_and_lights() {
+        let prop = World::new(
+            vec![],
+            vec![PointLight::new(Point::new(0.0, 1.0, 0.0), Color::WHITE).into()],
+        )
+        .with_named_body("ball", Sphere::default())
+        .with_instances(vec![Instance::new(Arc::new(Mesh::new(vec![
+            Sphere::default().into(),
+        ])))]);
+        let w = World::new(vec![Plane::default().into()], vec![])
+            .with_named_body("ball", Sphere::default());
+
+        let merged = w.merge_with_transform(prop, Matrix::translate(5.0, 0.0, 0.0));
+
+        assert_eq!(3, merged.bodies.len());
+        assert_eq!(Some(2), merged.body_id("ball"));
+        assert_eq!(None, merged.name_of(1));
+        assert_fuzzy_eq!(
+            Matrix::translate(5.0, 0.0, 0.0),
+            merged.body_named("ball").unwrap().transform()
+        );
+        match merged.lights[0] {
+            Light::Point(l) => assert_fuzzy_eq!(Point::new(5.0, 1.0, 0.0), l.position),
+            _ => panic!("expected a point light"),
+        }
+        assert_eq!(1, merged.instances.len());
+        let r = Ray::new(Point::new(5.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(4, merged.intersect(r).len());
+    }
+
+    #[test]
+    fn merging_a_world_keeps_this_worlds_settings() {
+        let w = World::new(vec![], vec![]).with_weld_epsilon(0.1);
+
+        let merged = w.merge(World::new(vec![Sphere::default().into()], vec![]));
+
+        assert_eq!(Some(0.1), merged.weld_epsilon);
+        assert_eq!(1, merged.bodies.len());
+    }
+
+    #[test]
+    fn checkers_far_off_blur_to_their_average() {
+        let floor = Plane::default().with_material(
+            Phong {
+                pattern: Some(Pattern::checker(Color::WHITE, Color::BLACK)),
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Phong::default()
+            }
+            .into(),
+        );
+        let w = World::new(
+            vec![floor.into()],
+            vec![PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).into()],
+        );
+        let camera = Camera::new(100, 100, std::f64::consts::FRAC_PI_3).look_at_from_position(
+            Point::new(0.5, 1.0, 0.0),
+            Point::new(0.5, 1.0, 10.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        // Just below the horizon, where each pixel covers many squares.
+        let r = camera.ray_for_pixel(50, 52);
+
+        let c = w.color_at(r);
+
+        assert!((0.4..0.6).contains(&c[0]), "{:?}", c);
+        // A ray that stands for a single line sees one square or another.
+        let unfiltered = w.color_at(r.with_spread(0.0))[0];
+        assert!(unfiltered == 0.0 || unfiltered == 1.0);
+    }
+
+    #[test]
+    fn checkers_close_up_stay_sharp() {
+        // The floor lies along an edge between squares at y = 0, which filtering mustn't blend
+        // across, with the pattern filling space or projected onto it.
+        for projection in [Projection::Solid, Projection::Triplanar { sharpness: 4.0 }] {
+            let floor = Plane::default().with_material(
+                Phong {
+                    pattern: Some(
+                        Pattern::checker(Color::WHITE, Color::BLACK).with_projection(projection),
+                    ),
+                    ambient: 1.0,
+                    diffuse: 0.0,
+                    specular: 0.0,
+                    ..Phong::default()
+                }
+                .into(),
+            );
+            let w = World::new(
+                vec![floor.into()],
+                vec![PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).into()],
+            );
+            let camera = Camera::new(100, 100, std::f64::consts::FRAC_PI_3).look_at_from_position(
+                Point::new(0.3, 1.7, -1.3),
+                Point::new(0.2, 0.0, 2.0),
+                Vector::new(0.0, 1.0, 0.0),
+            );
+
+            for y in 50..100 {
+                for x in 0..100 {
+                    let r = camera.ray_for_pixel(x, y);
+                    let p = r.origin + r.direction * (-r.origin[1] / r.direction[1]);
+                    // Only well inside the squares, clear of the blur along their edges.
+                    if [0, 2].iter().any(|&i| (p[i] - p[i].round()).abs() < 0.05) {
+                        continue;
+                    }
+                    let white = (p[0].floor() + p[2].floor()).rem_euclid(2.0) == 0.0;
+                    let want = if white { 1.0 } else { 0.0 };
+                    assert_fuzzy_eq!(want, w.color_at(r)[0]);
+                }
+            }
+        }
+    }
 }
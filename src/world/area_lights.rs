@@ -0,0 +1,71 @@
+use super::World;
+use crate::{
+    color::Color,
+    computed_intersection::ComputedIntersection,
+    integrator::{direct_light, AreaLight},
+    profile::{self, Section},
+    rng::Rng,
+};
+
+impl World {
+    pub fn with_area_light(mut self, light: AreaLight) -> Self {
+        self.area_lights.push(light);
+        self
+    }
+
+    // The light from every area light leaving the intersection towards the eye, averaged over
+    // each light's samples. The random numbers are seeded by where the point is, so renders come
+    // out the same every time, and different points get different noise.
+    pub(super) fn area_lighting(&self, c: &ComputedIntersection) -> Color {
+        if self.area_lights.is_empty() {
+            return Color::BLACK;
+        }
+        let seed = (0..3).fold(0u64, |h, i| {
+            (h ^ c.position[i].to_bits()).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        });
+        let mut rng = Rng::new(seed);
+        profile::time(Section::Shading, || {
+            self.area_lights.iter().fold(Color::BLACK, |sum, light| {
+                let total = (0..light.samples).fold(Color::BLACK, |total, _| {
+                    total + direct_light(self, light, c, &mut rng)
+                });
+                sum + total * (1.0 / light.samples as f64)
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        material::Phong, matrix::Matrix, plane::Plane, point::Point, ray::Ray, sphere::Sphere,
+        vector::Vector,
+    };
+
+    #[test]
+    fn area_lights_light_the_world() {
+        // A sphere of radius 1 hanging 4 above a white floor, which gives off 1/16 under it (see
+        // the integrator's tests).
+        let floor = Plane::default().with_material(
+            Phong {
+                diffuse: 1.0,
+                specular: 0.0,
+                ..Phong::default()
+            }
+            .into(),
+        );
+        let light = AreaLight::new(
+            Sphere::default().with_transform(Matrix::translate(0.0, 4.0, 0.0)),
+            Color::WHITE,
+        );
+        let world = World::new(vec![floor.into()], vec![]);
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        assert_eq!(0.0, world.color_at(ray)[0]);
+        let world = world.with_area_light(light.with_samples(4000));
+        let lit = world.color_at(ray);
+        assert!((lit[0] - 1.0 / 16.0).abs() < 0.005, "{:?}", lit);
+        assert_eq!(lit[0], world.color_at(ray)[0]);
+    }
+}
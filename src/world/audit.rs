@@ -0,0 +1,149 @@
+use super::World;
+use crate::{color::Color, material::Material};
+
+// Something in a scene that can make it brighter than its lights, which is what makes colors blow
+// out when bright surfaces, mirrors and glass are stacked up. The ranges are explained on Phong
+// and PointLight.
+#[derive(Clone, Copy, Debug)]
+pub enum LightingIssue {
+    // The body's material sends back more light than falls on it.
+    Reflectance { body: usize, reflectance: f64 },
+    // The body's ambient and diffuse light add up to more than the light falling on it, so it's
+    // brighter than white when lit head on by a white light.
+    Ambient { body: usize, ambient: f64 },
+    // The body's color reflects more than all of the light in some channel.
+    Color { body: usize, color: Color },
+    // The light is brighter than white, so even surfaces within range blow out.
+    Light { light: usize, intensity: Color },
+}
+
+fn over_one(color: Color) -> bool {
+    (0..3).any(|i| color[i] > 1.0)
+}
+
+impl World {
+    // Everything in the scene out of the range that keeps surfaces no brighter than the lights.
+    pub fn audit_lighting(&self) -> Vec<LightingIssue> {
+        let mut issues = Vec::new();
+        for (body, b) in self.bodies.iter().enumerate() {
+            // Media only scatter the light that reaches them.
+            let Material::Phong(phong) = b.material() else {
+                continue;
+            };
+            if phong.reflectance() > 1.0 {
+                issues.push(LightingIssue::Reflectance {
+                    body,
+                    reflectance: phong.reflectance(),
+                });
+            }
+            if phong.ambient + phong.diffuse > 1.0 {
+                issues.push(LightingIssue::Ambient {
+                    body,
+                    ambient: phong.ambient,
+                });
+            }
+            if over_one(phong.color) {
+                issues.push(LightingIssue::Color {
+                    body,
+                    color: phong.color,
+                });
+            }
+        }
+        for (light, l) in self.lights.iter().enumerate() {
+            if over_one(l.intensity()) {
+                issues.push(LightingIssue::Light {
+                    light,
+                    intensity: l.intensity(),
+                });
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, body::Body, fuzzy_eq::FuzzyEq, light::PointLight, material::Phong,
+        point::Point, sphere::Sphere,
+    };
+
+    fn ball(material: Phong) -> Body {
+        Sphere::default().with_material(material.into()).into()
+    }
+
+    fn world(bodies: Vec<Body>, intensity: Color) -> World {
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), intensity);
+        World::new(bodies, vec![light.into()])
+    }
+
+    #[test]
+    fn a_matte_material_under_a_white_light_is_in_range() {
+        let matte = Phong {
+            specular: 0.1,
+            ..Phong::default()
+        };
+
+        assert!(world(vec![ball(matte)], Color::WHITE)
+            .audit_lighting()
+            .is_empty());
+    }
+
+    #[test]
+    fn the_default_material_under_a_white_light_is_in_range() {
+        assert!(world(vec![ball(Phong::default())], Color::WHITE)
+            .audit_lighting()
+            .is_empty());
+    }
+
+    #[test]
+    fn a_shiny_glass_ball_reflects_too_much() {
+        let glass = Phong {
+            diffuse: 0.1,
+            specular: 1.0,
+            shininess: 0.0,
+            reflective: 0.9,
+            transparency: 0.9,
+            ..Phong::default()
+        };
+
+        let issues = world(vec![ball(glass)], Color::WHITE).audit_lighting();
+
+        assert_eq!(1, issues.len());
+        match issues[0] {
+            LightingIssue::Reflectance { body, reflectance } => {
+                assert_eq!(0, body);
+                assert_fuzzy_eq!(2.9, reflectance);
+            }
+            issue => panic!("expected too much reflectance, got {:?}", issue),
+        }
+    }
+
+    #[test]
+    fn overbright_colors_ambient_and_lights_are_found() {
+        let bright = Phong {
+            color: Color::new(1.5, 0.5, 0.5),
+            ambient: 0.5,
+            diffuse: 0.9,
+            specular: 0.0,
+            ..Phong::default()
+        };
+        let w = world(
+            vec![ball(Phong::default()), ball(bright)],
+            Color::new(2.0, 2.0, 2.0),
+        );
+
+        let issues = w.audit_lighting();
+
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, LightingIssue::Ambient { body: 1, .. })));
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, LightingIssue::Color { body: 1, .. })));
+        assert!(issues
+            .iter()
+            .any(|i| matches!(i, LightingIssue::Light { light: 0, .. })));
+    }
+}
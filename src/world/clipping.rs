@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use super::World;
+use crate::{intersection::HitRecord, material::Material, point::Point, ray::Ray, vector::Vector};
+
+// Cuts the world in two and leaves out everything on the side the normal points to, for cutaway
+// renders that show what's inside. Whatever's cut away is gone for shadows and reflections too,
+// not just for the camera.
+//
+// With a cap material, closed bodies like spheres and capsules are filled in where the plane cuts
+// through them, so they look solid rather than hollow. Bodies that are only surfaces, like planes,
+// and meshes, whose triangles are bodies of their own, are just cut.
+#[derive(Clone, Debug)]
+pub struct ClippingPlane {
+    pub point: Point,
+    pub normal: Vector,
+    pub cap: Option<Material>,
+}
+
+impl ClippingPlane {
+    pub fn new(point: Point, normal: Vector) -> Self {
+        if normal.magnitude() == 0.0 {
+            panic!("a clipping plane needs a normal")
+        }
+        Self {
+            point,
+            normal: normal.normalize(),
+            cap: None,
+        }
+    }
+
+    pub fn with_cap(self, cap: Material) -> Self {
+        Self {
+            cap: Some(cap),
+            ..self
+        }
+    }
+
+    // How far the point is into the side that's cut away.
+    fn depth(&self, p: Point) -> f64 {
+        (p - self.point).dot(&self.normal)
+    }
+
+    // The hits left once the ray's cut, plus caps where the ray crosses the plane inside a closed
+    // body. The hits must all be along the ray.
+    pub fn clip(&self, ray: Ray, mut hits: Vec<HitRecord>) -> Vec<HitRecord> {
+        let mut caps = Vec::new();
+        let facing = ray.direction.dot(&self.normal);
+        if let (Some(cap), true) = (&self.cap, facing != 0.0) {
+            let t = -self.depth(ray.origin) / facing;
+            // A closed body is crossed an even number of times, and the ray's inside it wherever
+            // an odd number of them come before.
+            let mut crossings: HashMap<usize, (HitRecord, usize, usize)> = HashMap::new();
+            for hit in &hits {
+                let entry = crossings.entry(hit.body_id).or_insert((hit.clone(), 0, 0));
+                entry.1 += 1;
+                if hit.t < t {
+                    entry.2 += 1;
+                }
+            }
+            for (hit, count, before) in crossings.into_values() {
+                if count >= 2 && count % 2 == 0 && before % 2 == 1 {
+                    caps.push(
+                        HitRecord::new(t, ray, hit.body.with_material(cap.clone()))
+                            .with_body_id(hit.body_id)
+                            .with_normal(self.normal),
+                    );
+                }
+            }
+        }
+        hits.retain(|hit| self.depth(ray.position(hit.t)) <= 0.0);
+        hits.extend(caps);
+        hits
+    }
+}
+
+impl World {
+    pub fn with_clipping_plane(self, clipping_plane: ClippingPlane) -> Self {
+        Self {
+            clipping_plane: Some(clipping_plane),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, body::Body, color::Color, fuzzy_eq::FuzzyEq, light::PointLight,
+        material::Phong, plane::Plane, sphere::Sphere,
+    };
+
+    // Cuts away everything with z above zero.
+    fn clipped(bodies: Vec<Body>, cap: Option<Material>) -> World {
+        let plane = ClippingPlane::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 2.0));
+        let plane = match cap {
+            Some(cap) => plane.with_cap(cap),
+            None => plane,
+        };
+        World::new(bodies, vec![]).with_clipping_plane(plane)
+    }
+
+    fn red() -> Material {
+        Phong {
+            color: Color::new(1.0, 0.0, 0.0),
+            specular: 0.0,
+            ..Phong::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn the_side_the_normal_points_to_is_cut_away() {
+        let world = clipped(vec![Sphere::default().into()], None);
+
+        let towards = world.intersect(Ray::new(
+            Point::new(0.0, 0.0, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        ));
+        assert_eq!(1, towards.len());
+        assert_fuzzy_eq!(4.0, towards[0].t);
+        // Without a cap, looking in from the cut side shows the inside of the far half.
+        let into = world.intersect(Ray::new(
+            Point::new(0.0, 0.0, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+        ));
+        assert_eq!(1, into.len());
+        assert_fuzzy_eq!(6.0, into[0].t);
+    }
+
+    #[test]
+    fn closed_bodies_are_capped_where_theyre_cut() {
+        let world = clipped(vec![Sphere::default().into()], Some(red()));
+
+        let xs = world.intersect(Ray::new(
+            Point::new(0.2, 0.3, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+        ));
+
+        assert_eq!(2, xs.len());
+        assert_fuzzy_eq!(5.0, xs[0].t);
+        assert_fuzzy_eq!(red(), xs[0].body.material());
+        let c = xs[0].computed();
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, 1.0), c.normal);
+        // Rays that miss the body where it's cut get no cap.
+        let past = world.intersect(Ray::new(
+            Point::new(0.9, 0.9, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+        ));
+        assert_eq!(0, past.len());
+    }
+
+    #[test]
+    fn surfaces_arent_capped() {
+        let world = clipped(vec![Plane::default().into()], Some(red()));
+
+        let xs = world.intersect(Ray::new(
+            Point::new(0.0, 5.0, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+        ));
+
+        assert_eq!(0, xs.len());
+    }
+
+    #[test]
+    fn the_cap_is_lit_like_any_other_surface() {
+        let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::WHITE);
+        let world = clipped(vec![Sphere::default().into()], Some(red()));
+        let world = World {
+            lights: vec![light.into()],
+            ..world
+        };
+
+        let color = world.color_at(Ray::new(
+            Point::new(0.0, 0.0, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+        ));
+
+        assert!(color[0] > 0.5);
+        assert_fuzzy_eq!(0.0, color[1]);
+    }
+}
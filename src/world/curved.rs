@@ -0,0 +1,94 @@
+use super::{first_solid_hit, World};
+use crate::{color::Color, medium::IndexField, ray::Ray};
+
+impl World {
+    pub fn with_index_field(self, index_field: IndexField) -> Self {
+        Self {
+            index_field: Some(index_field),
+            ..self
+        }
+    }
+
+    // The color seen along a ray that passes through the field, following it as it curves, or
+    // None if it hits something before it gets there or misses the field, so goes straight. Media
+    // inside the field are left out.
+    pub(super) fn curved_color_at(
+        &self,
+        field: &IndexField,
+        ray: Ray,
+        remaining: u32,
+    ) -> Option<Color> {
+        let (start, _) = field.bounds.crossing(ray)?;
+        let direction = ray.direction.normalize();
+        let start = start * ray.direction.magnitude();
+        if self.is_blocked(Ray::new(ray.origin, direction), start) {
+            return None;
+        }
+
+        let mut segment = Ray {
+            origin: ray.origin + direction * start,
+            direction,
+            travelled: ray.travelled + start,
+            ..ray
+        };
+        let mut marched = 0.0;
+        while marched < field.max_distance && field.bounds.contains(segment.origin) {
+            let xs = self.intersect(segment);
+            if let Some(hit) = first_solid_hit(&xs).filter(|hit| hit.t <= field.step) {
+                let c = hit.computed_with(&xs);
+                return Some(self.shade_hit(&c, remaining));
+            }
+            let (origin, direction) = field.step_from(segment.origin, segment.direction);
+            segment = Ray {
+                origin,
+                direction,
+                travelled: segment.travelled + field.step,
+                ..segment
+            };
+            marched += field.step;
+        }
+        // Out of the field, or as far through it as rays are followed, so straight from here on.
+        Some(self.straight_color_at(segment, remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, bounds::Bounds, environment::Environment, fuzzy_eq::FuzzyEq, point::Point,
+        vector::Vector,
+    };
+
+    // Looking along z at a sky that's white above the horizon and black below.
+    fn horizon() -> World {
+        World::default().with_environment(Environment::Gradient {
+            horizon: Color::BLACK,
+            zenith: Color::WHITE,
+        })
+    }
+
+    fn slab(index: impl Fn(Point) -> f64 + Send + Sync + 'static) -> IndexField {
+        let bounds = Bounds::new(Point::new(-5.0, -5.0, 0.0), Point::new(5.0, 5.0, 10.0));
+        IndexField::new(bounds, index).with_step(0.1)
+    }
+
+    #[test]
+    fn an_even_field_leaves_rays_straight() {
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.6, 0.8));
+        let world = horizon().with_index_field(slab(|_| 1.5));
+
+        assert_fuzzy_eq!(horizon().color_at(ray), world.color_at(ray));
+    }
+
+    #[test]
+    fn rays_curve_towards_the_higher_index() {
+        // With the index higher further up, a ray aimed just below the horizon curves up into the
+        // sky, like light over hot ground.
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, -0.02, 1.0));
+        let world = horizon().with_index_field(slab(|p| 1.0 + 0.05 * p[1]));
+
+        assert!(horizon().color_at(ray)[0] < 0.01);
+        assert!(world.color_at(ray)[0] > 0.05);
+    }
+}
@@ -0,0 +1,139 @@
+use super::World;
+
+// Cuts back on the costly parts of shading for surfaces far from the camera, which are too small
+// in the image for the difference to show, e.g. the buildings at the back of a city. Distances
+// are how far the light has come from the camera to the surface, counting every bounce on the
+// way, so things seen in a mirror are as far away as they look. Each cut has its own distance,
+// and is off until it's given one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LevelOfDetail {
+    // Past this, soft shadows are cast with one ray at the middle of the light, like hard ones.
+    pub soft_shadow_distance: Option<f64>,
+    // Past this, rays are reflected and refracted at most `far_bounces` more times.
+    pub bounce_distance: Option<f64>,
+    pub far_bounces: u32,
+}
+
+impl LevelOfDetail {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_soft_shadows_within(self, distance: f64) -> Self {
+        Self {
+            soft_shadow_distance: Some(check(distance)),
+            ..self
+        }
+    }
+
+    pub fn with_bounces_within(self, distance: f64, far_bounces: u32) -> Self {
+        Self {
+            bounce_distance: Some(check(distance)),
+            far_bounces,
+            ..self
+        }
+    }
+
+    pub fn soft_shadows_at(&self, distance: f64) -> bool {
+        self.soft_shadow_distance.is_none_or(|d| distance <= d)
+    }
+
+    // How many more bounces rays from a surface the distance away can take, out of `remaining`.
+    pub fn bounces_at(&self, distance: f64, remaining: u32) -> u32 {
+        match self.bounce_distance {
+            Some(d) if distance > d => remaining.min(self.far_bounces),
+            _ => remaining,
+        }
+    }
+}
+
+fn check(distance: f64) -> f64 {
+    if distance < 0.0 {
+        panic!("level of detail distances must not be negative")
+    }
+    distance
+}
+
+impl World {
+    pub fn with_level_of_detail(self, level_of_detail: LevelOfDetail) -> Self {
+        Self {
+            level_of_detail: Some(level_of_detail),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq,
+        color::Color,
+        fuzzy_eq::FuzzyEq,
+        light::PointLight,
+        material::{Material, Phong},
+        matrix::Matrix,
+        plane::Plane,
+        point::Point,
+        ray::Ray,
+        sphere::Sphere,
+        vector::Vector,
+    };
+
+    #[test]
+    fn far_away_shadows_are_hard() {
+        // A ball between a big light and the floor, looked at from 70 or so away, where the edge
+        // of its shadow would be soft.
+        let world = |light: PointLight| {
+            let ball = Sphere::default().with_transform(Matrix::translate(0.0, 5.0, 0.0));
+            World::new(
+                vec![Plane::default().into(), ball.into()],
+                vec![light.into()],
+            )
+        };
+        let light = PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE);
+        let soft = world(light.with_radius(2.0, 16));
+        let hard = world(light);
+        let edge = Point::new(2.0, 0.0, 0.0);
+        let from = Point::new(2.0, 50.0, -50.0);
+        let ray = Ray::new(from, (edge - from).normalize());
+
+        let far = soft.color_at(ray);
+        let with_lod = |distance| {
+            world(light.with_radius(2.0, 16))
+                .with_level_of_detail(LevelOfDetail::new().with_soft_shadows_within(distance))
+                .color_at(ray)
+        };
+
+        assert!((far[0] - hard.color_at(ray)[0]).abs() > 0.01);
+        assert_fuzzy_eq!(hard.color_at(ray), with_lod(50.0));
+        assert_fuzzy_eq!(far, with_lod(100.0));
+    }
+
+    #[test]
+    fn far_away_surfaces_are_held_to_fewer_bounces() {
+        let mirror: Material = Phong {
+            reflective: 0.5,
+            ..Phong::default()
+        }
+        .into();
+        let plane = |y| {
+            Plane::default()
+                .with_transform(Matrix::translate(0.0, y, 0.0))
+                .with_material(mirror.clone())
+                .into()
+        };
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::WHITE);
+        let world = World::new(vec![plane(-1.0), plane(1.0)], vec![light.into()]);
+        let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let full = world.color_at(ray);
+        // The first mirror is close enough for every bounce, but the light has come 3 by the time
+        // it's back at the second, so only one more bounce is followed from there.
+        let expected = world.color_at_depth(ray, 2);
+
+        let world = world.with_level_of_detail(LevelOfDetail::new().with_bounces_within(2.0, 1));
+
+        assert_fuzzy_eq!(expected, world.color_at(ray));
+        assert!(full[0] > expected[0]);
+    }
+}
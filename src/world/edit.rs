@@ -0,0 +1,305 @@
+use std::{error::Error, fmt};
+
+use super::World;
+use crate::{body::Body, material::Material, matrix::Matrix, transform::Transformable};
+
+// Changes to a world, made through an Editor so that they can be undone. Bodies are picked out by
+// name rather than by index, since indices shift as bodies are added and removed.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Edit {
+    AddBody { name: String, body: Body },
+    RemoveBody { name: String },
+    SetTransform { name: String, transform: Matrix<4> },
+    SetMaterial { name: String, material: Material },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditError {
+    NoSuchBody(String),
+    NameTaken(String),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::NoSuchBody(name) => write!(f, "there is no body named {:?}", name),
+            EditError::NameTaken(name) => write!(f, "there is already a body named {:?}", name),
+        }
+    }
+}
+
+impl Error for EditError {}
+
+// What it takes to put the world back the way it was before an edit.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Undo {
+    Remove(usize),
+    Insert(usize, String, Body),
+    Replace(usize, Body),
+}
+
+impl World {
+    // Puts a named body at the index, moving the bodies after it (and their names) up one.
+    fn insert_named_body(&mut self, index: usize, name: String, body: Body) {
+        self.bodies.insert(index, body);
+        self.names = self
+            .names
+            .drain()
+            .map(|(id, n)| (if id >= index { id + 1 } else { id }, n))
+            .collect();
+        self.names.insert(index, name);
+    }
+
+    // Takes the body at the index out, moving the bodies after it (and their names) down one.
+    fn remove_body(&mut self, index: usize) -> (Option<String>, Body) {
+        let body = self.bodies.remove(index);
+        let name = self.names.remove(&index);
+        self.names = self
+            .names
+            .drain()
+            .map(|(id, n)| (if id > index { id - 1 } else { id }, n))
+            .collect();
+        (name, body)
+    }
+
+    fn apply(&mut self, edit: &Edit) -> Result<Undo, EditError> {
+        let find = |name: &str| {
+            self.body_id(name)
+                .ok_or_else(|| EditError::NoSuchBody(name.to_string()))
+        };
+        match edit {
+            Edit::AddBody { name, body } => {
+                if self.body_id(name).is_some() {
+                    return Err(EditError::NameTaken(name.clone()));
+                }
+                let index = self.bodies.len();
+                self.insert_named_body(index, name.clone(), body.clone());
+                Ok(Undo::Remove(index))
+            }
+            Edit::RemoveBody { name } => {
+                let index = find(name)?;
+                let (_, body) = self.remove_body(index);
+                Ok(Undo::Insert(index, name.clone(), body))
+            }
+            Edit::SetTransform { name, transform } => {
+                let index = find(name)?;
+                let old = self.bodies[index].clone();
+                self.bodies[index] = old.clone().with_transform(*transform);
+                Ok(Undo::Replace(index, old))
+            }
+            Edit::SetMaterial { name, material } => {
+                let index = find(name)?;
+                let old = self.bodies[index].clone();
+                self.bodies[index] = old.clone().with_material(material.clone());
+                Ok(Undo::Replace(index, old))
+            }
+        }
+    }
+
+    fn revert(&mut self, undo: Undo) {
+        match undo {
+            Undo::Remove(index) => {
+                self.remove_body(index);
+            }
+            Undo::Insert(index, name, body) => self.insert_named_body(index, name, body),
+            Undo::Replace(index, body) => self.bodies[index] = body,
+        }
+    }
+}
+
+// Makes changes to a world, keeping track of them so that they can be undone and redone.
+pub struct Editor {
+    world: World,
+    done: Vec<(Edit, Undo)>,
+    undone: Vec<Edit>,
+}
+
+impl Editor {
+    pub fn new(world: World) -> Self {
+        Self {
+            world,
+            done: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn into_world(self) -> World {
+        self.world
+    }
+
+    // Makes the change, unless it can't be made, in which case the world is left as it was.
+    // Making a change forgets any that were undone, so they can't be redone any more.
+    pub fn apply(&mut self, edit: Edit) -> Result<(), EditError> {
+        let undo = self.world.apply(&edit)?;
+        self.done.push((edit, undo));
+        self.undone.clear();
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    // Undoes the last change, returning false if there's nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.done.pop() {
+            Some((edit, undo)) => {
+                self.world.revert(undo);
+                self.undone.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Makes the last change that was undone again, returning false if there's nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(edit) => {
+                // The world is back how it was when the change was first made, so it can't fail.
+                let undo = self
+                    .world
+                    .apply(&edit)
+                    .expect("a change that was undone can be made again");
+                self.done.push((edit, undo));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, color::Color, fuzzy_eq::FuzzyEq, material::Phong, point::Point,
+        sphere::Sphere,
+    };
+
+    fn editor() -> Editor {
+        Editor::new(
+            World::new(vec![], vec![])
+                .with_named_body("a", Sphere::default())
+                .with_named_body("b", Sphere::default())
+                .with_named_body("c", Sphere::default()),
+        )
+    }
+
+    fn name(name: &str) -> String {
+        name.to_string()
+    }
+
+    #[test]
+    fn adding_and_undoing_a_body() {
+        let mut e = editor();
+
+        e.apply(Edit::AddBody {
+            name: name("d"),
+            body: Sphere::default().into(),
+        })
+        .unwrap();
+        assert_eq!(Some(3), e.world().body_id("d"));
+
+        assert!(e.undo());
+        assert_eq!(3, e.world().bodies.len());
+        assert_eq!(None, e.world().body_id("d"));
+    }
+
+    #[test]
+    fn undoing_a_removal_puts_the_body_back_where_it_was() {
+        let mut e = editor();
+
+        e.apply(Edit::RemoveBody { name: name("b") }).unwrap();
+        assert_eq!(Some(1), e.world().body_id("c"));
+        assert_eq!(None, e.world().body_id("b"));
+
+        e.undo();
+        assert_eq!(Some(0), e.world().body_id("a"));
+        assert_eq!(Some(1), e.world().body_id("b"));
+        assert_eq!(Some(2), e.world().body_id("c"));
+    }
+
+    #[test]
+    fn undoing_and_redoing_transforms_and_materials() {
+        let mut e = editor();
+        let t = Matrix::translate(1.0, 2.0, 3.0);
+        let red = Phong {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Phong::default()
+        };
+
+        e.apply(Edit::SetTransform {
+            name: name("a"),
+            transform: t,
+        })
+        .unwrap();
+        e.apply(Edit::SetMaterial {
+            name: name("a"),
+            material: red.clone().into(),
+        })
+        .unwrap();
+        e.undo();
+        e.undo();
+
+        let a = e.world().body_named("a").unwrap();
+        assert_fuzzy_eq!(Matrix::<4>::identity(), a.transform());
+        assert_fuzzy_eq!(
+            Color::WHITE,
+            a.material().flat_color(a, Point::new(1.0, 2.0, 3.0))
+        );
+
+        assert!(e.redo());
+        assert!(e.redo());
+        assert!(!e.redo());
+
+        let a = e.world().body_named("a").unwrap();
+        assert_fuzzy_eq!(t, a.transform());
+        assert_fuzzy_eq!(
+            red.color,
+            a.material().flat_color(a, Point::new(1.0, 2.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn a_new_edit_forgets_what_was_undone() {
+        let mut e = editor();
+
+        e.apply(Edit::RemoveBody { name: name("a") }).unwrap();
+        e.undo();
+        assert!(e.can_redo());
+        e.apply(Edit::RemoveBody { name: name("b") }).unwrap();
+
+        assert!(!e.can_redo());
+        assert!(e.can_undo());
+    }
+
+    #[test]
+    fn edits_that_cant_be_made_leave_the_world_alone() {
+        let mut e = editor();
+
+        assert_eq!(
+            Err(EditError::NoSuchBody(name("z"))),
+            e.apply(Edit::RemoveBody { name: name("z") })
+        );
+        assert_eq!(
+            Err(EditError::NameTaken(name("a"))),
+            e.apply(Edit::AddBody {
+                name: name("a"),
+                body: Sphere::default().into(),
+            })
+        );
+        assert_eq!(3, e.world().bodies.len());
+        assert!(!e.can_undo());
+    }
+}
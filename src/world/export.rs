@@ -0,0 +1,615 @@
+use std::fmt::Write;
+
+use super::{ClippingPlane, LevelOfDetail, MaterialOverride, World};
+use crate::{
+    body::Body,
+    camera::{Camera, Projection as CameraProjection},
+    color::Color,
+    environment::Environment,
+    fuzzy_eq::FuzzyEq,
+    light::{Light, PointLight},
+    material::{Material, Phong},
+    matrix::Matrix,
+    medium::Medium,
+    pattern::{Anchor, Masonry, Pattern, PatternKind, Projection, SolidTexture, Tiling},
+    point::Point,
+    sky::Sky,
+    vector::Vector,
+};
+
+// What the generated code starts with. Not every scene needs everything, hence the allow.
+const PRELUDE: &str = "\
+// Generated by World::to_rust.
+#[allow(unused_imports)]
+use raytracer::{
+    body::Body,
+    camera::{Camera, Projection},
+    capsule::Capsule,
+    color::Color,
+    environment::Environment,
+    light::{DirectionalLight, Light, PointLight},
+    material::Phong,
+    matrix::Matrix,
+    medium::Medium,
+    pattern::{Anchor, Masonry, Pattern, Projection as PatternProjection, SolidTexture, Tiling},
+    plane::Plane,
+    point::Point,
+    shell::Shell,
+    sky::Sky,
+    sphere::Sphere,
+    starfield::Starfield,
+    triangle::Triangle,
+    vector::Vector,
+    world::{ClippingPlane, LevelOfDetail, MaterialOverride, World},
+};
+";
+
+fn float(x: f64) -> String {
+    format!("{:?}", x)
+}
+
+fn color(c: Color) -> String {
+    format!(
+        "Color::new({}, {}, {})",
+        float(c[0]),
+        float(c[1]),
+        float(c[2])
+    )
+}
+
+fn point(p: Point) -> String {
+    format!(
+        "Point::new({}, {}, {})",
+        float(p[0]),
+        float(p[1]),
+        float(p[2])
+    )
+}
+
+fn vector(v: Vector) -> String {
+    format!(
+        "Vector::new({}, {}, {})",
+        float(v[0]),
+        float(v[1]),
+        float(v[2])
+    )
+}
+
+fn matrix(m: Matrix<4>) -> String {
+    let rows: Vec<String> = (0..4)
+        .map(|row| {
+            let values: Vec<String> = (0..4).map(|col| float(m[row][col])).collect();
+            format!("[{}]", values.join(", "))
+        })
+        .collect();
+    format!("Matrix::from([{}])", rows.join(", "))
+}
+
+// `.with_transform(...)`, or nothing for the identity. Translations are written as such, since
+// they're the most common and the easiest to read.
+fn with_transform(m: Matrix<4>) -> String {
+    if m.fuzzy_eq(Matrix::identity()) {
+        return String::new();
+    }
+    let (x, y, z) = (m[0][3], m[1][3], m[2][3]);
+    if m.fuzzy_eq(Matrix::translate(x, y, z)) {
+        format!(
+            ".with_transform(Matrix::translate({}, {}, {}))",
+            float(x),
+            float(y),
+            float(z)
+        )
+    } else {
+        format!(".with_transform({})", matrix(m))
+    }
+}
+
+// The fields of a struct literal that differ from the default, followed by `..Default`.
+fn struct_literal(name: &str, fields: Vec<Option<String>>) -> String {
+    let mut out = format!("{} {{ ", name);
+    for field in fields.into_iter().flatten() {
+        write!(out, "{}, ", field).unwrap();
+    }
+    write!(out, "..{}::default() }}", name).unwrap();
+    out
+}
+
+// The field as `name: value` if it differs from the default.
+fn field<T: FuzzyEq + Copy>(
+    name: &str,
+    value: T,
+    default: T,
+    write: impl Fn(T) -> String,
+) -> Option<String> {
+    if value.fuzzy_eq(default) {
+        None
+    } else {
+        Some(format!("{}: {}", name, write(value)))
+    }
+}
+
+fn texture(t: SolidTexture) -> String {
+    format!(
+        "SolidTexture::new({}, {}).with_scale({}).with_turbulence({}).with_octaves({}).with_seed({})",
+        color(t.a),
+        color(t.b),
+        float(t.scale),
+        float(t.turbulence),
+        t.octaves,
+        t.seed
+    )
+}
+
+fn masonry(m: Masonry) -> String {
+    format!(
+        "Masonry {{ brick: {}, mortar: {}, size: [{}, {}, {}], mortar_width: {}, row_offset: {} }}",
+        color(m.brick),
+        color(m.mortar),
+        float(m.size[0]),
+        float(m.size[1]),
+        float(m.size[2]),
+        float(m.mortar_width),
+        float(m.row_offset)
+    )
+}
+
+// The pattern as code, or None if it can't be written as code.
+fn pattern(p: Pattern) -> Option<String> {
+    let mut out = match p.kind {
+        PatternKind::Stripe(a, b) => format!("Pattern::stripe({}, {})", color(a), color(b)),
+        PatternKind::Gradient(a, b) => format!("Pattern::gradient({}, {})", color(a), color(b)),
+        PatternKind::Ring(a, b) => format!("Pattern::ring({}, {})", color(a), color(b)),
+        PatternKind::Checker(a, b) => format!("Pattern::checker({}, {})", color(a), color(b)),
+        PatternKind::Wood(t) => format!("Pattern::wood({})", texture(t)),
+        PatternKind::Marble(t) => format!("Pattern::marble({})", texture(t)),
+        PatternKind::Granite(t) => format!("Pattern::granite({})", texture(t)),
+        PatternKind::Brick(m) => format!("Pattern::brick({})", masonry(m)),
+        PatternKind::SolidBrick(m) => format!("Pattern::solid_brick({})", masonry(m)),
+        PatternKind::Image(_) => return None,
+    };
+    out += &with_transform(p.transform);
+    if p.filter_width != 0.0 {
+        write!(out, ".with_filter_width({})", float(p.filter_width)).unwrap();
+    }
+    if let Projection::Triplanar { sharpness } = p.projection {
+        write!(
+            out,
+            ".with_projection(PatternProjection::Triplanar {{ sharpness: {} }})",
+            float(sharpness)
+        )
+        .unwrap();
+    }
+    if p.anchor == Anchor::World {
+        out += ".with_anchor(Anchor::World)";
+    }
+    if !p.tiling.fuzzy_eq(Tiling::default()) {
+        let pair = |[a, b]: [f64; 2]| format!("[{}, {}]", float(a), float(b));
+        write!(
+            out,
+            ".with_tiling(Tiling {{ offset: {}, scale: {}, rotation: {} }})",
+            pair(p.tiling.offset),
+            pair(p.tiling.scale),
+            float(p.tiling.rotation)
+        )
+        .unwrap();
+    }
+    Some(out)
+}
+
+// A pattern field of a Phong literal, with a note in `skipped` if it can't be written as code.
+fn pattern_field(name: &str, value: Option<Pattern>, skipped: &mut Vec<String>) -> Option<String> {
+    let code = pattern(value?);
+    if code.is_none() {
+        skipped.push(format!("{} (image textures can't be exported)", name));
+    }
+    Some(format!("{}: Some({})", name, code?))
+}
+
+fn material(m: Material, skipped: &mut Vec<String>) -> String {
+    match m {
+        Material::Phong(p) => {
+            let d = Phong::default();
+            let fields = vec![
+                field("color", p.color, d.color, color),
+                field("ambient", p.ambient, d.ambient, float),
+                field("diffuse", p.diffuse, d.diffuse, float),
+                field("specular", p.specular, d.specular, float),
+                field("shininess", p.shininess, d.shininess, float),
+                pattern_field("pattern", p.pattern, skipped),
+                field("reflective", p.reflective, d.reflective, float),
+                field("transparency", p.transparency, d.transparency, float),
+                field(
+                    "refractive_index",
+                    p.refractive_index,
+                    d.refractive_index,
+                    float,
+                ),
+                field("dispersion", p.dispersion, d.dispersion, float),
+                p.max_bounces.map(|n| format!("max_bounces: Some({})", n)),
+                pattern_field("specular_map", p.specular_map, skipped),
+                pattern_field("shininess_map", p.shininess_map, skipped),
+                pattern_field("transparency_map", p.transparency_map, skipped),
+            ];
+            format!("{}.into()", struct_literal("Phong", fields))
+        }
+        Material::Medium(m) => {
+            let d = Medium::default();
+            let fields = vec![
+                field("color", m.color, d.color, color),
+                field("density", m.density, d.density, float),
+                (m.steps != d.steps).then(|| format!("steps: {}", m.steps)),
+            ];
+            format!("{}.into()", struct_literal("Medium", fields))
+        }
+    }
+}
+
+fn body(b: &Body, skipped: &mut Vec<String>) -> String {
+    let shape = match b {
+        Body::Sphere(_) => "Sphere::default()".to_string(),
+        Body::Shell(s) => format!(
+            "Shell::default().with_inner_radius({})",
+            float(s.inner_radius())
+        ),
+        Body::Capsule(c) => {
+            let (a, b) = c.segment();
+            format!(
+                "Capsule::new({}, {}, {})",
+                point(a),
+                point(b),
+                float(c.radius())
+            )
+        }
+        Body::Plane(_) => "Plane::default()".to_string(),
+        Body::Triangle(t) => {
+            let (p1, p2, p3) = t.points();
+            let mut out = format!("Triangle::new({}, {}, {})", point(p1), point(p2), point(p3));
+            if t.culls_back_faces() {
+                out += ".with_back_face_culling()";
+            }
+            out
+        }
+    };
+    let mut out = shape + &with_transform(b.transform());
+    if !b.material().fuzzy_eq(Material::default()) {
+        write!(out, ".with_material({})", material(b.material(), skipped)).unwrap();
+    }
+    out
+}
+
+fn light(l: &Light) -> String {
+    match l {
+        Light::Point(p) => {
+            let mut out = format!(
+                "PointLight::new({}, {})",
+                point(p.position),
+                color(p.intensity)
+            );
+            let d = PointLight::new(p.position, p.intensity);
+            if p.radius != d.radius || p.samples != d.samples {
+                write!(out, ".with_radius({}, {})", float(p.radius), p.samples).unwrap();
+            }
+            if p.min_samples != p.samples {
+                write!(out, ".with_min_samples({})", p.min_samples).unwrap();
+            }
+            format!("{}.into()", out)
+        }
+        Light::Directional(d) => format!(
+            "DirectionalLight::new({}, {}).into()",
+            vector(d.direction),
+            color(d.intensity)
+        ),
+    }
+}
+
+// The environment as code, or None if it can't be written as code.
+fn environment(e: &Environment) -> Option<String> {
+    Some(match e {
+        Environment::Color(c) => format!("Environment::Color({})", color(*c)),
+        Environment::Gradient { horizon, zenith } => format!(
+            "Environment::Gradient {{ horizon: {}, zenith: {} }}",
+            color(*horizon),
+            color(*zenith)
+        ),
+        Environment::Sky(s) => {
+            let d = Sky::default();
+            let mut out = format!(
+                "Sky::default().with_sun_direction({}).with_turbidity({}).with_brightness({})",
+                vector(s.sun_direction()),
+                float(s.turbidity()),
+                float(s.brightness)
+            );
+            if !s.sun_radius.fuzzy_eq(d.sun_radius) || !s.sun_intensity.fuzzy_eq(d.sun_intensity)
+            {
+                write!(
+                    out,
+                    ".with_sun({}, {})",
+                    float(s.sun_radius),
+                    color(s.sun_intensity)
+                )
+                .unwrap();
+            }
+            format!("Environment::Sky({})", out)
+        }
+        Environment::Starfield(s) => format!(
+            "Environment::Starfield(Starfield::default().with_count({}).with_brightness({}, {}).with_size({}).with_seed({}))",
+            s.count,
+            float(s.brightness),
+            float(s.falloff),
+            float(s.size),
+            s.seed
+        ),
+        Environment::Skydome(_) | Environment::Probe(_) => return None,
+    })
+}
+
+fn clipping_plane(c: &ClippingPlane, skipped: &mut Vec<String>) -> String {
+    let mut out = format!(
+        "ClippingPlane::new({}, {})",
+        point(c.point),
+        vector(c.normal)
+    );
+    if let Some(cap) = &c.cap {
+        write!(out, ".with_cap({})", material(cap.clone(), skipped)).unwrap();
+    }
+    out
+}
+
+fn material_override(m: &MaterialOverride, skipped: &mut Vec<String>) -> String {
+    match m {
+        MaterialOverride::Material(m) => {
+            format!(
+                "MaterialOverride::Material({})",
+                material(m.clone(), skipped)
+            )
+        }
+        MaterialOverride::Wireframe { material: m, width } => format!(
+            "MaterialOverride::Wireframe {{ material: {}, width: {} }}",
+            material(m.clone(), skipped),
+            float(*width)
+        ),
+    }
+}
+
+fn level_of_detail(l: &LevelOfDetail) -> String {
+    let mut out = String::from("LevelOfDetail::new()");
+    if let Some(distance) = l.soft_shadow_distance {
+        write!(out, ".with_soft_shadows_within({})", float(distance)).unwrap();
+    }
+    if let Some(distance) = l.bounce_distance {
+        write!(
+            out,
+            ".with_bounces_within({}, {})",
+            float(distance),
+            l.far_bounces
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn camera_builder(c: &Camera) -> String {
+    let mut out = format!(
+        "Camera::new({}, {}, {}){}",
+        c.hsize,
+        c.vsize,
+        float(c.field_of_view),
+        with_transform(c.transform)
+    );
+    if c.aperture != 0.0 {
+        write!(
+            out,
+            ".with_aperture({}).with_focal_distance({})",
+            float(c.aperture),
+            float(c.focal_distance)
+        )
+        .unwrap();
+    }
+    if let CameraProjection::StereoPanorama {
+        interpupillary_distance,
+    } = c.projection
+    {
+        write!(
+            out,
+            ".with_projection(Projection::StereoPanorama {{ interpupillary_distance: {} }})",
+            float(interpupillary_distance)
+        )
+        .unwrap();
+    }
+    out
+}
+
+impl World {
+    // Rust source for a `scene` function that builds this world and the camera with the library's
+    // builders, e.g. to start a scene by hand from one made some other way. Anything that can't
+    // be written as code, like images and meshes, is listed in a comment at the top instead. The
+    // code is all on a few long lines, so run rustfmt over it.
+    pub fn to_rust(&self, camera: &Camera) -> String {
+        let mut skipped = Vec::new();
+        let mut world = String::from("World::new(vec![], vec![");
+        for l in &self.lights {
+            write!(world, "{}, ", light(l)).unwrap();
+        }
+        world += "])";
+        match environment(&self.environment) {
+            Some(e) => write!(world, ".with_environment({})", e).unwrap(),
+            None if matches!(self.environment, Environment::Probe(_)) => {
+                skipped.push("the light probe environment".to_string())
+            }
+            None => skipped.push("the skydome".to_string()),
+        }
+        for (i, b) in self.bodies.iter().enumerate() {
+            let code = body(b, &mut skipped);
+            match self.name_of(i) {
+                Some(name) => write!(world, ".with_named_body({:?}, {})", name, code).unwrap(),
+                None => write!(world, ".with_body({})", code).unwrap(),
+            }
+        }
+        if let Some(epsilon) = self.weld_epsilon {
+            write!(world, ".with_weld_epsilon({})", float(epsilon)).unwrap();
+        }
+        if let Some(c) = &self.clipping_plane {
+            let code = clipping_plane(c, &mut skipped);
+            write!(world, ".with_clipping_plane({})", code).unwrap();
+        }
+        if let Some(m) = &self.material_override {
+            let code = material_override(m, &mut skipped);
+            write!(world, ".with_material_override({})", code).unwrap();
+        }
+        if let Some(l) = &self.level_of_detail {
+            write!(world, ".with_level_of_detail({})", level_of_detail(l)).unwrap();
+        }
+        if !self.instances.is_empty() {
+            skipped.push(format!("{} mesh instances", self.instances.len()));
+        }
+        if !self.csgs.is_empty() {
+            skipped.push(format!("{} CSG solids", self.csgs.len()));
+        }
+        if !self.area_lights.is_empty() {
+            skipped.push(format!("{} area lights", self.area_lights.len()));
+        }
+        if !self.probes.is_empty() {
+            skipped.push(format!("{} light probes", self.probes.len()));
+        }
+        // Helpers are only there while laying a scene out, and the cache is rebuilt as it's
+        // rendered.
+        if !self.helpers.is_empty() {
+            skipped.push(format!("{} helpers", self.helpers.len()));
+        }
+        if self.shadow_cache.is_some() {
+            skipped.push("the shadow cache".to_string());
+        }
+        if self.index_field.is_some() {
+            skipped.push("the index field (its index is a closure)".to_string());
+        }
+
+        let mut out = String::from(PRELUDE);
+        for thing in skipped {
+            writeln!(out, "// Not exported: {}.", thing).unwrap();
+        }
+        // Numbers are written out in full, some of which clippy takes for badly rounded constants.
+        writeln!(out, "\n#[allow(clippy::approx_constant)]").unwrap();
+        writeln!(out, "pub fn scene() -> (World, Camera) {{").unwrap();
+        writeln!(out, "    let world = {};", world).unwrap();
+        writeln!(out, "    let camera = {};", camera_builder(camera)).unwrap();
+        writeln!(out, "    (world, camera)\n}}").unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bounds::Bounds,
+        canvas::Canvas,
+        instance::{Instance, Mesh},
+        medium::IndexField,
+        pattern::ImageTexture,
+        skydome::Skydome,
+        sphere::Sphere,
+        triangle::Triangle,
+        world::ShadowCache,
+    };
+
+    #[test]
+    fn bodies_are_written_with_their_builders() {
+        let world = World::default().with_named_body(
+            "ball",
+            Sphere::default().with_transform(Matrix::translate(1.0, 2.5, -3.0)),
+        );
+
+        let code = world.to_rust(&Camera::new(4, 2, 1.5));
+
+        assert!(code.contains(
+            ".with_named_body(\"ball\", Sphere::default().with_transform(Matrix::translate(1.0, 2.5, -3.0)))"
+        ));
+        assert!(code.contains("let camera = Camera::new(4, 2, 1.5);"));
+        assert!(!code.contains("Not exported"));
+    }
+
+    #[test]
+    fn masonry_is_written_out_in_full() {
+        let p = Pattern::brick(Masonry::tiles(Color::WHITE, Color::BLACK));
+
+        assert_eq!(
+            Some(concat!(
+                "Pattern::brick(Masonry { brick: Color::new(1.0, 1.0, 1.0), ",
+                "mortar: Color::new(0.0, 0.0, 0.0), size: [1.0, 1.0, 1.0], ",
+                "mortar_width: 0.03, row_offset: 0.0 })"
+            )),
+            pattern(p).as_deref()
+        );
+    }
+
+    #[test]
+    fn how_the_world_is_cut_and_shaded_is_written_with_its_builders() {
+        let triangle = Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        );
+        let world = World::default()
+            .with_body(triangle.with_back_face_culling())
+            .with_weld_epsilon(0.001)
+            .with_clipping_plane(
+                ClippingPlane::new(Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 1.0, 0.0))
+                    .with_cap(Material::default()),
+            )
+            .with_material_override(MaterialOverride::Wireframe {
+                material: Material::default(),
+                width: 0.1,
+            })
+            .with_level_of_detail(LevelOfDetail::new().with_bounces_within(20.0, 1));
+
+        let code = world.to_rust(&Camera::new(4, 2, 1.5));
+
+        assert!(code.contains(".with_back_face_culling())"));
+        assert!(code.contains(".with_weld_epsilon(0.001)"));
+        assert!(code.contains(concat!(
+            ".with_clipping_plane(ClippingPlane::new(Point::new(0.0, 0.5, 0.0), ",
+            "Vector::new(0.0, 1.0, 0.0)).with_cap(Phong { ..Phong::default() }.into()))"
+        )));
+        assert!(code.contains(concat!(
+            ".with_material_override(MaterialOverride::Wireframe { ",
+            "material: Phong { ..Phong::default() }.into(), width: 0.1 })"
+        )));
+        assert!(code
+            .contains(".with_level_of_detail(LevelOfDetail::new().with_bounces_within(20.0, 1))"));
+        assert!(!code.contains("Not exported"));
+    }
+
+    #[test]
+    fn what_cant_be_written_as_code_is_listed() {
+        let image = Pattern::image(ImageTexture::new(Canvas::new(2, 2)));
+        let body = Sphere::default().with_material(
+            Phong {
+                pattern: Some(image),
+                ..Phong::default()
+            }
+            .into(),
+        );
+        let mesh = std::sync::Arc::new(Mesh::new(vec![Sphere::default().into()]));
+        let world = World::new(vec![body.into()], vec![])
+            .with_environment(Environment::Skydome(Skydome::new(Canvas::new(2, 1))))
+            .with_instances(vec![Instance::new(mesh)])
+            .with_grid(1.0)
+            .with_index_field(IndexField::new(
+                Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0)),
+                |_| 1.0,
+            ))
+            .with_shadow_cache(std::sync::Arc::new(ShadowCache::new(1.0, 1)));
+
+        let code = world.to_rust(&Camera::new(4, 2, 1.5));
+
+        assert!(code.contains("// Not exported: the skydome."));
+        assert!(code.contains("// Not exported: pattern (image textures can't be exported)."));
+        assert!(code.contains("// Not exported: 1 mesh instances."));
+        assert!(code.contains("// Not exported: 1 helpers."));
+        assert!(code.contains("// Not exported: the shadow cache."));
+        assert!(code.contains("// Not exported: the index field (its index is a closure)."));
+        assert!(code.contains(
+            ".with_body(Sphere::default().with_material(Phong { ..Phong::default() }.into()))"
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use std::{ops::Deref, sync::Arc};
+
+use super::World;
+
+// A world that's done being built and is only rendered from now on. It can't be changed, so
+// cloning it just shares it, and any number of renders on any number of threads can use it at
+// once, e.g. one per camera, or one per frame of an animation where only the camera moves.
+#[derive(Clone)]
+pub struct FrozenWorld {
+    world: Arc<World>,
+}
+
+impl World {
+    pub fn freeze(self) -> FrozenWorld {
+        FrozenWorld {
+            world: Arc::new(self),
+        }
+    }
+}
+
+impl FrozenWorld {
+    // The world back for changing, if nothing else is sharing it. Otherwise the frozen world is
+    // given back as it was.
+    pub fn thaw(self) -> Result<World, FrozenWorld> {
+        Arc::try_unwrap(self.world).map_err(|world| FrozenWorld { world })
+    }
+}
+
+impl Deref for FrozenWorld {
+    type Target = World;
+
+    fn deref(&self) -> &World {
+        &self.world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::{
+        camera::Camera,
+        canvas::ToRgba,
+        render::{render, RenderSettings},
+    };
+
+    #[test]
+    fn renders_on_other_threads_share_a_frozen_world() {
+        let world = World::quickstart().freeze();
+        let cameras = vec![Camera::quickstart(16, 9), Camera::quickstart(8, 8)];
+
+        let renders: Vec<Vec<u8>> = cameras
+            .into_iter()
+            .map(|camera| {
+                let world = world.clone();
+                thread::spawn(move || {
+                    render(&world, &camera, &RenderSettings::default())
+                        .canvas
+                        .to_rgba()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        let alone = render(
+            &world,
+            &Camera::quickstart(16, 9),
+            &RenderSettings::default(),
+        );
+        assert_eq!(alone.canvas.to_rgba(), renders[0]);
+        assert_eq!(8 * 8 * 4, renders[1].len());
+    }
+
+    #[test]
+    fn a_world_can_only_be_thawed_once_nothing_shares_it() {
+        let world = World::quickstart().freeze();
+        let shared = world.clone();
+
+        let world = match world.thaw() {
+            Ok(_) => panic!("a shared world shouldn't thaw"),
+            Err(world) => world,
+        };
+        drop(shared);
+
+        assert!(world.thaw().is_ok());
+    }
+}
@@ -0,0 +1,162 @@
+use super::World;
+use crate::{
+    body::Body,
+    capsule::Capsule,
+    color::Color,
+    intersection::{HitRecord, Intersectable},
+    material::{Material, Phong},
+    plane::Plane,
+    point::Point,
+    ray::Ray,
+    vector::Vector,
+};
+
+// Things drawn into a scene to help lay it out, which aren't part of it. They only show up in
+// preview quality renders (see World::layout_color_at), so they can be left on while working on a
+// scene and never end up in the finished render, its shadows or its reflections.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Helper {
+    // One piece of a helper made of bodies, e.g. an arrow of the axes.
+    Body(Body),
+    // Lines along x and z across the ground at y = 0, `spacing` apart and `width` wide.
+    Grid {
+        spacing: f64,
+        width: f64,
+        color: Color,
+    },
+}
+
+impl Helper {
+    // Where the ray first hits the helper in front of it, if it does.
+    fn hit(&self, ray: Ray) -> Option<HitRecord> {
+        match self {
+            Helper::Body(body) => body.intersect(ray).into_iter().find(|x| x.t > 0.0),
+            Helper::Grid {
+                spacing,
+                width,
+                color,
+            } => {
+                if ray.direction[1] == 0.0 {
+                    return None;
+                }
+                let t = -ray.origin[1] / ray.direction[1];
+                let p = ray.position(t);
+                let off_line = |x: f64| {
+                    let along = x.rem_euclid(*spacing);
+                    along.min(spacing - along) > width / 2.0
+                };
+                if t <= 0.0 || (off_line(p[0]) && off_line(p[2])) {
+                    return None;
+                }
+                let plane = Plane::default().with_material(flat(*color).into());
+                Some(HitRecord::new(t, ray, plane.into()))
+            }
+        }
+    }
+}
+
+fn flat(color: Color) -> Phong {
+    Phong {
+        color,
+        specular: 0.0,
+        ..Phong::default()
+    }
+}
+
+impl World {
+    // Arrows `length` long from the origin along x, y and z, in red, green and blue. There's no
+    // cone body, so the arrows' heads are fatter capsules.
+    pub fn with_axes(mut self, length: f64) -> Self {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let axes = [
+            (Vector::new(1.0, 0.0, 0.0), Color::new(0.9, 0.1, 0.1)),
+            (Vector::new(0.0, 1.0, 0.0), Color::new(0.1, 0.9, 0.1)),
+            (Vector::new(0.0, 0.0, 1.0), Color::new(0.1, 0.1, 0.9)),
+        ];
+        for (axis, color) in axes {
+            let material: Material = flat(color).into();
+            let shaft = Capsule::new(origin, origin + axis * (0.85 * length), 0.02 * length);
+            let head = Capsule::new(
+                origin + axis * (0.8 * length),
+                origin + axis * (0.95 * length),
+                0.05 * length,
+            );
+            for piece in [shaft, head] {
+                self.helpers
+                    .push(Helper::Body(piece.with_material(material.clone()).into()));
+            }
+        }
+        self
+    }
+
+    // A grey grid of lines `spacing` apart across the ground.
+    pub fn with_grid(mut self, spacing: f64) -> Self {
+        if spacing <= 0.0 {
+            panic!("grid lines must be more than zero apart")
+        }
+        self.helpers.push(Helper::Grid {
+            spacing,
+            width: 0.03 * spacing,
+            color: Color::new(0.4, 0.4, 0.4),
+        });
+        self
+    }
+
+    // The nearest of the helpers the ray hits, if any.
+    pub(super) fn helper_hit(&self, ray: Ray) -> Option<HitRecord> {
+        self.helpers
+            .iter()
+            .filter_map(|helper| helper.hit(ray))
+            .min_by(|a, b| a.t.total_cmp(&b.t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_fuzzy_eq, environment::Environment, fuzzy_eq::FuzzyEq, light::PointLight};
+
+    #[test]
+    fn the_axes_only_show_up_in_layout_renders() {
+        let world = World::new(
+            vec![],
+            vec![PointLight::new(Point::new(0.0, 5.0, 5.0), Color::WHITE).into()],
+        )
+        .with_environment(Environment::Color(Color::BLACK))
+        .with_axes(1.0);
+        let along_x = Ray::new(Point::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+
+        assert_eq!(6, world.helpers.len());
+        let layout = world.layout_color_at(along_x);
+        assert!(layout[0] > 0.5 && layout[1] < 0.2);
+        assert!(world.intersect(along_x).is_empty());
+        assert_fuzzy_eq!(Color::BLACK, world.color_at(along_x));
+    }
+
+    #[test]
+    fn the_grid_is_only_its_lines() {
+        let world = World::default()
+            .with_environment(Environment::Color(Color::BLACK))
+            .with_grid(1.0);
+        let down = |x: f64, z: f64| Ray::new(Point::new(x, 3.0, z), Vector::new(0.0, -1.0, 0.0));
+
+        assert_fuzzy_eq!(Color::BLACK, world.layout_color_at(down(0.5, 0.5)));
+        assert_fuzzy_eq!(
+            Color::new(0.4, 0.4, 0.4),
+            world.layout_color_at(down(2.005, 0.5))
+        );
+        assert_fuzzy_eq!(
+            Color::new(0.4, 0.4, 0.4),
+            world.layout_color_at(down(0.3, -0.999))
+        );
+    }
+
+    #[test]
+    fn the_grid_draws_over_a_floor_in_the_same_place() {
+        let world = World::new(vec![Plane::default().into()], vec![]).with_grid(1.0);
+        let ray = Ray::new(Point::new(1.0, 3.0, 0.3), Vector::new(0.0, -1.0, 0.0));
+
+        assert_fuzzy_eq!(Color::new(0.4, 0.4, 0.4), world.layout_color_at(ray));
+    }
+}
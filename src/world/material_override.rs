@@ -0,0 +1,131 @@
+use super::World;
+use crate::{
+    body::Body,
+    color::Color,
+    intersection::HitRecord,
+    material::{Material, Phong},
+};
+
+// Replaces the materials of everything in the world as it's hit, without touching the bodies
+// themselves, so the same world can be rendered in several looks without being built again.
+#[derive(Clone, Debug)]
+pub enum MaterialOverride {
+    // Every surface gets the material, e.g. matte grey for a clay render that shows off the forms
+    // and the lighting without the textures getting in the way. Media are left as they are.
+    Material(Material),
+    // Only the edges of triangles show, in the material, and everything else is see-through.
+    // `width` is how far in from each edge the wire reaches, as a fraction of the way to the
+    // opposite corner. Bodies without edges, like spheres, don't show up at all.
+    Wireframe { material: Material, width: f64 },
+}
+
+impl MaterialOverride {
+    // Matte grey, with no highlights.
+    pub fn clay() -> Self {
+        MaterialOverride::Material(
+            Phong {
+                color: Color::new(0.7, 0.7, 0.7),
+                specular: 0.0,
+                ..Phong::default()
+            }
+            .into(),
+        )
+    }
+
+    pub fn wireframe(width: f64) -> Self {
+        MaterialOverride::Wireframe {
+            material: Phong {
+                color: Color::new(0.1, 0.1, 0.1),
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Phong::default()
+            }
+            .into(),
+            width,
+        }
+    }
+
+    // The hit with its material replaced, or None if the override leaves nothing to hit there.
+    pub fn apply(&self, hit: HitRecord) -> Option<HitRecord> {
+        let material = match (self, &hit.body) {
+            (_, body) if matches!(body.material(), Material::Medium(_)) => return Some(hit),
+            (MaterialOverride::Material(material), _) => material.clone(),
+            (MaterialOverride::Wireframe { material, width }, Body::Triangle(_)) => {
+                let nearest_edge = hit.u.min(hit.v).min(1.0 - hit.u - hit.v);
+                if nearest_edge >= *width {
+                    return None;
+                }
+                material.clone()
+            }
+            (MaterialOverride::Wireframe { .. }, _) => return None,
+        };
+        Some(HitRecord {
+            body: hit.body.with_material(material),
+            ..hit
+        })
+    }
+}
+
+impl World {
+    pub fn with_material_override(self, material_override: MaterialOverride) -> Self {
+        Self {
+            material_override: Some(material_override),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, fuzzy_eq::FuzzyEq, medium::Medium, point::Point, ray::Ray, sphere::Sphere,
+        triangle::Triangle, vector::Vector,
+    };
+
+    fn triangle() -> Body {
+        Triangle::new(
+            Point::new(-1.0, -1.0, 0.0),
+            Point::new(1.0, -1.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+        )
+        .into()
+    }
+
+    fn ray_at(x: f64, y: f64) -> Ray {
+        Ray::new(Point::new(x, y, -5.0), Vector::new(0.0, 0.0, 1.0))
+    }
+
+    #[test]
+    fn a_material_override_replaces_every_solid_material() {
+        let fog: Body = Sphere::default()
+            .with_material(Medium::default().into())
+            .into();
+        let world = World::new(vec![triangle(), fog], vec![])
+            .with_material_override(MaterialOverride::clay());
+        let clay = match MaterialOverride::clay() {
+            MaterialOverride::Material(material) => material,
+            _ => unreachable!(),
+        };
+
+        let xs = world.intersect(ray_at(0.0, 0.0));
+
+        assert_eq!(3, xs.len());
+        assert!(matches!(xs[0].body.material(), Material::Medium(_)));
+        assert_fuzzy_eq!(clay, xs[1].body.material());
+        // The bodies themselves keep their materials.
+        assert_fuzzy_eq!(Material::default(), world.bodies[0].material());
+    }
+
+    #[test]
+    fn a_wireframe_only_keeps_hits_near_triangle_edges() {
+        let world = World::new(vec![triangle(), Sphere::default().into()], vec![])
+            .with_material_override(MaterialOverride::wireframe(0.05));
+
+        assert!(world.intersect(ray_at(0.0, -0.3)).is_empty());
+        let edge = world.intersect(ray_at(0.0, -0.99));
+        assert_eq!(1, edge.len());
+        assert_fuzzy_eq!(5.0, edge[0].t);
+    }
+}
@@ -0,0 +1,150 @@
+use super::{first_solid_hit, World};
+use crate::{
+    body::Body, camera::Camera, canvas::Canvas, color::Color,
+    computed_intersection::ComputedIntersection, intersection::HitRecord, material::Material,
+    point::Point,
+};
+
+// What's under a pixel of the camera's image, e.g. for showing what was clicked on in a preview.
+#[derive(Clone, Debug)]
+pub struct Pick<'a> {
+    pub body_id: usize,
+    pub name: Option<&'a str>,
+    pub body: Body,
+    pub position: Point,
+    pub t: f64,
+    pub hit: HitRecord,
+}
+
+impl Pick<'_> {
+    pub fn material(&self) -> Material {
+        self.body.material()
+    }
+
+    // The normal, eye vector and so on at the picked point, as worked out for shading it, e.g. for
+    // focusing the camera on a surface or checking which way it faces. The refractive indices are
+    // left as air, since they depend on everything else along the ray.
+    pub fn computed(&self) -> ComputedIntersection<'_> {
+        self.hit.computed()
+    }
+}
+
+impl World {
+    // The body seen at the pixel, if any. Media can be seen through, so they can't be picked.
+    pub fn pick(&self, x: usize, y: usize, camera: &Camera) -> Option<Pick<'_>> {
+        let ray = camera.ray_for_pixel(x, y);
+        let xs = self.intersect(ray);
+        let hit = first_solid_hit(&xs)?;
+        Some(Pick {
+            body_id: hit.body_id,
+            name: self.name_of(hit.body_id),
+            body: hit.body.clone(),
+            position: ray.position(hit.t),
+            t: hit.t,
+            hit: hit.clone(),
+        })
+    }
+
+    // A copy of a render of the world with the body tinted towards the color wherever it's seen,
+    // to show which body is picked.
+    pub fn highlight(
+        &self,
+        canvas: &Canvas,
+        camera: &Camera,
+        body_id: usize,
+        color: Color,
+    ) -> Canvas {
+        let mut highlighted = canvas.clone();
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                if self
+                    .pick(x, y, camera)
+                    .is_some_and(|p| p.body_id == body_id)
+                {
+                    let original = canvas.read_pixel(x, y);
+                    highlighted.write_pixel(x, y, (original + color) * 0.5);
+                }
+            }
+        }
+        highlighted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Matrix, sphere::Sphere, vector::Vector,
+    };
+
+    fn world() -> World {
+        World::new(vec![], vec![])
+            .with_named_body(
+                "ball",
+                Sphere::default().with_transform(Matrix::translate(0.0, 0.0, 2.0)),
+            )
+            .with_named_body(
+                "marble",
+                Sphere::default().with_transform(Matrix::translate(5.0, 0.0, 2.0)),
+            )
+    }
+
+    fn camera() -> Camera {
+        Camera::new(11, 11, FRAC_PI_2).look_at_from_position(
+            Point::new(0.0, 0.0, -5.0),
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn picking_the_body_at_a_pixel() {
+        let w = world();
+        let pick = w.pick(5, 5, &camera()).unwrap();
+
+        assert_eq!(0, pick.body_id);
+        assert_eq!(Some("ball"), pick.name);
+        assert_fuzzy_eq!(6.0, pick.t);
+        assert_fuzzy_eq!(Point::new(0.0, 0.0, 1.0), pick.position);
+    }
+
+    #[test]
+    fn a_pick_knows_how_the_surface_faces() {
+        let w = world();
+        let pick = w.pick(5, 5, &camera()).unwrap();
+
+        let c = pick.computed();
+
+        assert_fuzzy_eq!(pick.position, c.position);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), c.normal);
+        assert_fuzzy_eq!(Vector::new(0.0, 0.0, -1.0), c.eye);
+    }
+
+    #[test]
+    fn picking_empty_space() {
+        assert!(world().pick(0, 0, &camera()).is_none());
+    }
+
+    #[test]
+    fn looking_up_bodies_by_name() {
+        let w = world();
+
+        assert_eq!(Some(1), w.body_id("marble"));
+        assert_eq!(Some("marble"), w.name_of(1));
+        assert_eq!(None, w.body_id("cube"));
+    }
+
+    #[test]
+    fn highlighting_only_tints_the_picked_body() {
+        let w = world();
+        let c = camera();
+        let canvas = Canvas::new(c.hsize, c.vsize);
+
+        let highlighted = w.highlight(&canvas, &c, 0, Color::new(1.0, 0.0, 0.0));
+
+        assert_fuzzy_eq!(Color::new(0.5, 0.0, 0.0), highlighted.read_pixel(5, 5));
+        assert_fuzzy_eq!(Color::BLACK, highlighted.read_pixel(0, 0));
+    }
+}
@@ -0,0 +1,99 @@
+use super::World;
+use crate::probe::LightProbe;
+
+impl World {
+    // Has the named body reflect what the probe captured rather than tracing its reflections,
+    // which is much quicker for shiny things that don't need to be exact, like the chrome on a car
+    // in the distance. The probe should be captured from somewhere near the body, and without it,
+    // so the body doesn't see itself. Refractions are still traced.
+    pub fn with_probe(mut self, name: &str, probe: LightProbe) -> Self {
+        if self.body_id(name).is_none() {
+            panic!("there is no body named {:?} to give the probe to", name)
+        }
+        self.probes.insert(name.to_string(), probe);
+        self
+    }
+
+    pub fn probes(&self) -> impl Iterator<Item = &LightProbe> {
+        self.probes.values()
+    }
+
+    pub(super) fn probe_for(&self, body_id: usize) -> Option<&LightProbe> {
+        self.probes.get(self.name_of(body_id)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, color::Color, environment::Environment, fuzzy_eq::FuzzyEq,
+        light::PointLight, material::Phong, matrix::Matrix, point::Point, ray::Ray, sphere::Sphere,
+        vector::Vector,
+    };
+
+    #[test]
+    fn bodies_with_a_probe_reflect_it_instead_of_the_scene() {
+        let mirror = Phong {
+            color: Color::BLACK,
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            reflective: 1.0,
+            ..Phong::default()
+        };
+        let ball = || Sphere::default().with_material(mirror.into());
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::WHITE);
+        let scene = World::new(vec![], vec![light.into()])
+            .with_environment(Environment::Color(Color::new(0.0, 0.0, 1.0)))
+            .with_named_body("ball", ball());
+        let elsewhere = World::default().with_environment(Color::new(1.0, 0.0, 0.0).into());
+        let probe = LightProbe::capture(&elsewhere, Point::new(0.0, 0.0, 0.0), 2);
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_fuzzy_eq!(Color::new(0.0, 0.0, 1.0), scene.color_at(ray));
+        let scene = scene.with_probe("ball", probe);
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), scene.color_at(ray));
+    }
+
+    #[test]
+    fn merged_worlds_bring_their_probes() {
+        let mirror = Phong {
+            color: Color::BLACK,
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            reflective: 1.0,
+            ..Phong::default()
+        };
+        let ball = || Sphere::default().with_material(mirror.clone().into());
+        let red = World::default().with_environment(Color::new(1.0, 0.0, 0.0).into());
+        let probe = LightProbe::capture(&red, Point::new(0.0, 0.0, 0.0), 2);
+        let prop = World::default()
+            .with_named_body("ball", ball())
+            .with_probe("ball", probe.clone());
+        let scene = World::default()
+            .with_environment(Environment::Color(Color::new(0.0, 0.0, 1.0)))
+            .with_named_body("ball", ball())
+            .with_probe("ball", probe)
+            .merge_with_transform(prop, Matrix::translate(0.0, 5.0, 0.0));
+
+        // The name, and its probe, move over to the merged ball.
+        let here = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let there = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_fuzzy_eq!(Color::new(0.0, 0.0, 1.0), scene.color_at(here));
+        assert_fuzzy_eq!(Color::new(1.0, 0.0, 0.0), scene.color_at(there));
+        assert_fuzzy_eq!(
+            Point::new(0.0, 5.0, 0.0),
+            scene.probes().next().unwrap().position
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "there is no body named")]
+    fn probes_are_given_to_named_bodies() {
+        let probe = LightProbe::capture(&World::default(), Point::new(0.0, 0.0, 0.0), 1);
+
+        World::default().with_probe("ball", probe);
+    }
+}
@@ -0,0 +1,277 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::{
+    bounds::Bounds, fuzzy_eq::FuzzyEq, light::Light, point::Point, ray::Ray, vector::Vector,
+};
+
+// Remembers how much of each light gets through to each cell of a grid over the scene, so that
+// renders after the first can skip most of their shadow rays. It's meant for animations where
+// the lights and most of the scene stay put: share one cache (in an Arc) between the worlds
+// for every frame, and invalidate what each moving body passes through before rendering a frame.
+//
+// Every point in a cell gets the same answer, so shadows are only as sharp as the cells are
+// small, and cells bigger than a thin wall can let light through it. Until a cell has
+// `samples_per_cell` shadow rays behind it, every point shaded in it adds its own rays, so the
+// cache starts rough and gets smoother the more it's used. Renders on several threads share
+// cells in whatever order they get to them, so they're no longer exactly the same every time.
+#[derive(Debug)]
+pub struct ShadowCache {
+    pub cell_size: f64,
+    pub samples_per_cell: usize,
+    // The cells for each light, by the light's index in the world.
+    cells: RwLock<HashMap<usize, Cells>>,
+}
+
+#[derive(Debug)]
+struct Cells {
+    // Where the light the cells were filled in for is.
+    light: [f64; 5],
+    // How many shadow rays got through, out of how many were cast, by cell.
+    samples: HashMap<(i64, i64, i64), (usize, usize)>,
+}
+
+// What's needed to tell whether a light has moved: its kind, and its position and radius or its
+// direction.
+fn light_key(light: &Light) -> [f64; 5] {
+    match light {
+        Light::Point(l) => [0.0, l.position[0], l.position[1], l.position[2], l.radius],
+        Light::Directional(l) => [1.0, l.direction[0], l.direction[1], l.direction[2], 0.0],
+    }
+}
+
+impl Cells {
+    fn are_for(&self, key: &[f64; 5]) -> bool {
+        self.light.iter().zip(key).all(|(a, b)| a.fuzzy_eq(*b))
+    }
+}
+
+impl ShadowCache {
+    pub fn new(cell_size: f64, samples_per_cell: usize) -> Self {
+        if cell_size <= 0.0 {
+            panic!("shadow cache cells must be bigger than zero")
+        }
+        if samples_per_cell == 0 {
+            panic!("a shadow cache needs at least one sample per cell")
+        }
+        Self {
+            cell_size,
+            samples_per_cell,
+            cells: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn cell(&self, position: Point) -> (i64, i64, i64) {
+        let index = |i: usize| (position[i] / self.cell_size).floor() as i64;
+        (index(0), index(1), index(2))
+    }
+
+    fn center(&self, (x, y, z): (i64, i64, i64)) -> Point {
+        let middle = |i: i64| (i as f64 + 0.5) * self.cell_size;
+        Point::new(middle(x), middle(y), middle(z))
+    }
+
+    // How many shadow rays got through to the light with the given index from the position's
+    // cell, out of how many were cast. If the cell doesn't have enough yet, `cast` casts some more
+    // from the position. Moving a light, or putting another one at its index, empties its cells.
+    pub fn samples(
+        &self,
+        index: usize,
+        light: &Light,
+        position: Point,
+        cast: impl FnOnce() -> (usize, usize),
+    ) -> (usize, usize) {
+        let key = light_key(light);
+        let cell = self.cell(position);
+        {
+            let lights = self.cells.read().unwrap();
+            if let Some(cells) = lights.get(&index).filter(|cells| cells.are_for(&key)) {
+                if let Some(&(clear, cast)) = cells.samples.get(&cell) {
+                    if cast >= self.samples_per_cell {
+                        return (clear, cast);
+                    }
+                }
+            }
+        }
+
+        let (clear, cast) = cast();
+        let mut lights = self.cells.write().unwrap();
+        let cells = lights.entry(index).or_insert_with(|| Cells {
+            light: key,
+            samples: HashMap::new(),
+        });
+        if !cells.are_for(&key) {
+            cells.light = key;
+            cells.samples.clear();
+        }
+        let samples = cells.samples.entry(cell).or_insert((0, 0));
+        samples.0 += clear;
+        samples.1 += cast;
+        *samples
+    }
+
+    // Forgets the cells whose view of the light passes through the bounds, e.g. everywhere a
+    // body that moved might have been casting a shadow, or might be now. Give it the bounds of
+    // where the body was and where it is.
+    pub fn invalidate(&self, bounds: &Bounds) {
+        for cells in self.cells.write().unwrap().values_mut() {
+            let light = cells.light;
+            // Soft shadow rays spread out over the light's radius, and points anywhere in the
+            // cell share its answer, so grow the bounds to cover them.
+            let margin = light[4] + self.cell_size * 3f64.sqrt() / 2.0;
+            let mut grown = *bounds;
+            for i in 0..3 {
+                grown.min[i] -= margin;
+                grown.max[i] += margin;
+            }
+
+            let ray_to_light = |from: Point| {
+                if light[0] == 0.0 {
+                    let to_light = Point::new(light[1], light[2], light[3]) - from;
+                    (Ray::new(from, to_light.normalize()), to_light.magnitude())
+                } else {
+                    let direction = Vector::new(light[1], light[2], light[3]);
+                    (Ray::new(from, -direction), f64::INFINITY)
+                }
+            };
+            cells.samples.retain(|&cell, _| {
+                let (ray, distance) = ray_to_light(self.center(cell));
+                !grown.intersects_segment(ray, distance)
+            });
+        }
+    }
+
+    pub fn clear(&self) {
+        self.cells.write().unwrap().clear();
+    }
+
+    // How many cells have something in them, over all the lights.
+    pub fn len(&self) -> usize {
+        self.cells
+            .read()
+            .unwrap()
+            .values()
+            .map(|cells| cells.samples.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, sync::Arc};
+
+    use super::*;
+    use crate::{
+        body::Body, bounds::Bounded, color::Color, light::PointLight, material::ShadowState,
+        matrix::Matrix, sphere::Sphere, world::World,
+    };
+
+    fn light() -> Light {
+        PointLight::new(Point::new(0.0, 10.0, 0.0), Color::WHITE).into()
+    }
+
+    #[test]
+    fn cells_stop_casting_once_they_have_enough_samples() {
+        let cache = ShadowCache::new(0.5, 4);
+        let casts = Cell::new(0);
+        let cast = || {
+            casts.set(casts.get() + 1);
+            (1, 2)
+        };
+
+        assert_eq!(
+            (1, 2),
+            cache.samples(0, &light(), Point::new(0.1, 0.0, 0.0), cast)
+        );
+        // Anywhere else in the cell adds to it until it has enough.
+        assert_eq!(
+            (2, 4),
+            cache.samples(0, &light(), Point::new(0.4, 0.2, 0.3), cast)
+        );
+        assert_eq!(
+            (2, 4),
+            cache.samples(0, &light(), Point::new(0.2, 0.0, 0.0), cast)
+        );
+        assert_eq!(2, casts.get());
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn moving_the_light_empties_the_cache() {
+        let cache = ShadowCache::new(0.5, 1);
+        let p = Point::new(0.0, 0.0, 0.0);
+        cache.samples(0, &light(), p, || (0, 1));
+        cache.samples(0, &light(), Point::new(3.0, 0.0, 0.0), || (0, 1));
+
+        let moved: Light = PointLight::new(Point::new(1.0, 10.0, 0.0), Color::WHITE).into();
+
+        assert_eq!((1, 1), cache.samples(0, &moved, p, || (1, 1)));
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn each_light_has_its_own_cells() {
+        let cache = ShadowCache::new(0.5, 1);
+        let p = Point::new(0.0, 0.0, 0.0);
+        let other: Light = PointLight::new(Point::new(10.0, 10.0, 0.0), Color::WHITE).into();
+        cache.samples(0, &light(), p, || (0, 1));
+
+        // Filling in the second light's cells leaves the first's alone.
+        assert_eq!((1, 1), cache.samples(1, &other, p, || (1, 1)));
+        assert_eq!((0, 1), cache.samples(0, &light(), p, || (1, 1)));
+        assert_eq!(2, cache.len());
+    }
+
+    #[test]
+    fn invalidating_forgets_the_cells_that_see_the_light_through_the_bounds() {
+        let cache = ShadowCache::new(0.5, 1);
+        let under = Point::new(0.1, 0.1, 0.1);
+        let aside = Point::new(5.1, 0.1, 0.1);
+        cache.samples(0, &light(), under, || (0, 1));
+        cache.samples(0, &light(), aside, || (1, 1));
+
+        cache.invalidate(&Bounds::new(
+            Point::new(-1.0, 4.0, -1.0),
+            Point::new(1.0, 6.0, 1.0),
+        ));
+
+        assert_eq!(1, cache.len());
+        assert_eq!((1, 1), cache.samples(0, &light(), aside, || (0, 1)));
+    }
+
+    #[test]
+    fn frames_sharing_a_cache_reuse_its_shadows_until_invalidated() {
+        let cache = Arc::new(ShadowCache::new(0.1, 1));
+        let blocker: Body = Sphere::default()
+            .with_transform(Matrix::translate(0.0, 5.0, 0.0))
+            .into();
+        let p = Point::new(0.0, 0.0, 0.0);
+        let first =
+            World::new(vec![blocker.clone()], vec![light()]).with_shadow_cache(cache.clone());
+        assert!(matches!(first.get_shadow_state(p), ShadowState::Shadow));
+
+        // The next frame, the blocker's gone, but the cache still remembers its shadow.
+        let second = World::new(vec![], vec![light()]).with_shadow_cache(cache.clone());
+        assert!(matches!(second.get_shadow_state(p), ShadowState::Shadow));
+
+        cache.invalidate(&blocker.bounds().transformed(blocker.transform()));
+        assert!(matches!(second.get_shadow_state(p), ShadowState::Clear));
+    }
+
+    #[test]
+    fn every_light_in_a_world_is_cached() {
+        let cache = Arc::new(ShadowCache::new(0.1, 1));
+        let fill: Light = PointLight::new(Point::new(10.0, 10.0, 0.0), Color::WHITE).into();
+        let p = Point::new(0.0, 0.0, 0.0);
+        let world = World::new(vec![], vec![light(), fill]).with_shadow_cache(cache.clone());
+
+        for index in 0..2 {
+            world.shadow_state(index, p, true, None);
+        }
+
+        assert_eq!(2, cache.len());
+    }
+}
@@ -0,0 +1,110 @@
+use super::{first_solid_hit, World};
+use crate::{intersection::HitRecord, parallel, ray::Ray};
+
+// What a ray hit, without anything needed for shading it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub t: f64,
+    // The id the world gave the body (see HitRecord::body_id); look it up with World::body_named
+    // or World::body_transform.
+    pub body_id: usize,
+}
+
+// For building other integrators, e.g. bidirectional path tracing, on top of the world's
+// geometry and bounding volume hierarchies without going through its shading. Rays are traced in
+// parallel, and the results are in the same order as the rays. As elsewhere, media can be seen
+// through, so rays only stop at solid surfaces.
+impl World {
+    // The first solid surface each ray hits in front of it, if any.
+    pub fn trace(&self, rays: &[Ray]) -> Vec<Option<HitRecord>> {
+        parallel::map(rays.to_vec(), |ray| {
+            first_solid_hit(&self.intersect(ray)).cloned()
+        })
+    }
+
+    // Like trace, for when only where the hits are and what was hit matter.
+    pub fn trace_hits(&self, rays: &[Ray]) -> Vec<Option<Hit>> {
+        self.trace(rays)
+            .into_iter()
+            .map(|hit| {
+                hit.map(|hit| Hit {
+                    t: hit.t,
+                    body_id: hit.body_id,
+                })
+            })
+            .collect()
+    }
+
+    // Whether something solid is in the way of each ray before it's gone its distance, e.g. for
+    // connecting paths to lights.
+    pub fn occluded(&self, rays: &[(Ray, f64)]) -> Vec<bool> {
+        parallel::map(rays.to_vec(), |(ray, distance)| {
+            self.is_blocked(ray, distance)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_fuzzy_eq, fuzzy_eq::FuzzyEq, matrix::Matrix, point::Point, sphere::Sphere,
+        vector::Vector,
+    };
+
+    fn two_spheres() -> World {
+        World::default()
+            .with_named_body("near", Sphere::default())
+            .with_named_body(
+                "far",
+                Sphere::default().with_transform(Matrix::translate(5.0, 0.0, 0.0)),
+            )
+    }
+
+    #[test]
+    fn tracing_a_batch_of_rays() {
+        let world = two_spheres();
+        let forward = Vector::new(0.0, 0.0, 1.0);
+        let rays = [
+            Ray::new(Point::new(5.0, 0.0, -5.0), forward),
+            Ray::new(Point::new(0.0, 3.0, -5.0), forward),
+            Ray::new(Point::new(0.0, 0.0, -5.0), forward),
+        ];
+
+        let hits = world.trace_hits(&rays);
+
+        assert_eq!(
+            vec![
+                Some(Hit {
+                    t: 4.0,
+                    body_id: world.body_id("far").unwrap()
+                }),
+                None,
+                Some(Hit {
+                    t: 4.0,
+                    body_id: world.body_id("near").unwrap()
+                }),
+            ],
+            hits
+        );
+        let records = world.trace(&rays);
+        assert_fuzzy_eq!(
+            Vector::new(0.0, 0.0, -1.0),
+            records[2].as_ref().unwrap().computed().normal
+        );
+    }
+
+    #[test]
+    fn checking_a_batch_of_rays_for_occlusion() {
+        let world = two_spheres();
+        let along_x = Vector::new(1.0, 0.0, 0.0);
+        let from = Point::new(-5.0, 0.0, 0.0);
+
+        let occluded = world.occluded(&[
+            (Ray::new(from, along_x), 3.0),
+            (Ray::new(from, along_x), 5.0),
+        ]);
+
+        assert_eq!(vec![false, true], occluded);
+    }
+}
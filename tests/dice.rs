@@ -0,0 +1,54 @@
+// Renders bits of the dice scene, which leans on CSG, patterns and shadows all at once, and
+// checks that each of them shows up where it should.
+
+use raytracer::{
+    color::Color, point::Point, prefabs::dice_scene, ray::Ray, vector::Vector, world::World,
+};
+
+// The color seen looking straight down at the point on the ground or on top of a die.
+fn looking_down_at(world: &World, x: f64, z: f64) -> Color {
+    world.color_at(Ray::new(
+        Point::new(x, 10.0, z),
+        Vector::new(0.0, -1.0, 0.0),
+    ))
+}
+
+fn brightness(c: Color) -> f64 {
+    (c[0] + c[1] + c[2]) / 3.0
+}
+
+#[test]
+fn the_pip_on_top_of_the_big_die_is_white_on_red() {
+    let w = dice_scene();
+
+    let pip = looking_down_at(&w, 0.0, 0.0);
+    let face = looking_down_at(&w, 0.4, 0.4);
+
+    assert!(pip[1] > 0.5, "the pip should be white, not {:?}", pip);
+    assert!(
+        face[0] > 3.0 * face[1],
+        "the face should be red, not {:?}",
+        face
+    );
+}
+
+#[test]
+fn the_big_die_shadows_the_floor_away_from_the_light() {
+    let w = dice_scene();
+
+    // The light is up and to the -x -z side, so the shadow falls towards +x +z.
+    let shadowed = looking_down_at(&w, 1.6, 1.6);
+    let lit = looking_down_at(&w, -1.6, -1.6);
+
+    assert!(brightness(shadowed) < 0.5 * brightness(lit));
+}
+
+#[test]
+fn the_floor_is_checkered() {
+    let w = dice_scene();
+
+    let a = looking_down_at(&w, -3.5, -3.5);
+    let b = looking_down_at(&w, -2.5, -3.5);
+
+    assert!((a[2] - b[2]).abs() > 0.05);
+}
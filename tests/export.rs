@@ -0,0 +1,26 @@
+// Checks that code written by World::to_rust builds. exported/quickstart.rs is what it wrote for
+// World::quickstart and Camera::quickstart(32, 18); write it again if the exporter changes.
+
+use raytracer::{
+    camera::Camera,
+    canvas::ToRgba,
+    render::{render, RenderSettings},
+    world::World,
+};
+
+mod exported {
+    include!("exported/quickstart.rs");
+}
+
+#[test]
+fn exported_code_builds_the_same_scene() {
+    let (world, camera) = exported::scene();
+    let original = World::quickstart();
+    let original_camera = Camera::quickstart(32, 18);
+
+    let settings = RenderSettings::default();
+    let exported = render(&world, &camera, &settings).canvas;
+    let expected = render(&original, &original_camera, &settings).canvas;
+
+    assert_eq!(expected.to_rgba(), exported.to_rgba());
+}
@@ -0,0 +1,30 @@
+// Generated by World::to_rust.
+#[allow(unused_imports)]
+use raytracer::{
+    body::Body,
+    camera::{Camera, Projection},
+    capsule::Capsule,
+    color::Color,
+    environment::Environment,
+    light::{DirectionalLight, Light, PointLight},
+    material::Phong,
+    matrix::Matrix,
+    medium::Medium,
+    pattern::{Anchor, Masonry, Pattern, Projection as PatternProjection, SolidTexture, Tiling},
+    plane::Plane,
+    point::Point,
+    shell::Shell,
+    sky::Sky,
+    sphere::Sphere,
+    starfield::Starfield,
+    triangle::Triangle,
+    vector::Vector,
+    world::{ClippingPlane, LevelOfDetail, MaterialOverride, World},
+};
+
+#[allow(clippy::approx_constant)]
+pub fn scene() -> (World, Camera) {
+    let world = World::new(vec![], vec![PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)).into(), ]).with_environment(Environment::Gradient { horizon: Color::new(0.85, 0.9, 1.0), zenith: Color::new(0.25, 0.45, 0.85) }).with_body(Plane::default().with_material(Phong { specular: 0.0, pattern: Some(Pattern::checker(Color::new(0.9, 0.9, 0.9), Color::new(0.3, 0.3, 0.35))), ..Phong::default() }.into())).with_body(Sphere::default().with_transform(Matrix::translate(0.0, 1.0, 0.0)).with_material(Phong { color: Color::new(0.8, 0.2, 0.1), diffuse: 0.7, specular: 0.5, ..Phong::default() }.into()));
+    let camera = Camera::new(32, 18, 1.0471975511965979).with_transform(Matrix::from([[-0.9950371902099892, 0.0, 0.0, 0.0], [0.0, 0.9900990099009901, 0.09900990099009901, -0.9900990099009901], [0.0, 0.09950371902099892, -0.9950371902099892, -5.1244415295814445], [0.0, 0.0, 0.0, 1.0]]));
+    (world, camera)
+}
@@ -0,0 +1,91 @@
+// Renders a fixed benchmark scene and fails if it's gotten slower than it was, so that a change
+// that quietly costs a lot of speed gets noticed. Timings depend on the machine, so the baseline
+// to compare against is kept locally rather than checked in: the first run records it, and
+// later runs compare against it.
+//
+//     cargo test --release --test performance -- --ignored
+//
+// Set RAYTRACER_PERF_UPDATE=1 to record a new baseline after a change that's meant to be slower,
+// or one that's faster, and RAYTRACER_PERF_TOLERANCE to how much slower is still fine (0.1, i.e.
+// 10%, by default).
+
+use std::{env, f64::consts::FRAC_PI_3, fs, path::PathBuf, time::Instant};
+
+use raytracer::{
+    camera::Camera,
+    point::Point,
+    prefabs::dice_scene,
+    render::{render, RenderSettings},
+    vector::Vector,
+};
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 180;
+// The fastest of a few runs is the least affected by whatever else the machine is doing.
+const RUNS: usize = 3;
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("performance-baseline.txt")
+}
+
+// Camera rays per second rendering the dice scene.
+fn rays_per_second() -> f64 {
+    let world = dice_scene();
+    let camera = Camera::new(WIDTH, HEIGHT, FRAC_PI_3).look_at_from_position(
+        Point::new(-1.0, 4.5, -7.0),
+        Point::new(1.0, 0.8, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    );
+    let settings = RenderSettings::default();
+
+    let fastest = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let output = render(&world, &camera, &settings);
+            assert!(output.complete);
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min);
+    (WIDTH * HEIGHT) as f64 / fastest
+}
+
+#[test]
+#[ignore = "slow, and only meaningful in release builds"]
+fn rendering_has_not_gotten_slower() {
+    let current = rays_per_second();
+    let path = baseline_path();
+    let update = env::var("RAYTRACER_PERF_UPDATE").is_ok_and(|v| v == "1");
+    let tolerance: f64 = env::var("RAYTRACER_PERF_TOLERANCE")
+        .map(|v| {
+            v.parse()
+                .expect("RAYTRACER_PERF_TOLERANCE must be a number")
+        })
+        .unwrap_or(0.1);
+
+    let baseline = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| s.trim().parse::<f64>().ok());
+    match baseline {
+        Some(baseline) if !update => {
+            println!(
+                "{:.0} rays/s against a baseline of {:.0} rays/s",
+                current, baseline
+            );
+            assert!(
+                current >= baseline * (1.0 - tolerance),
+                "rendering slowed down from {:.0} to {:.0} rays/s, more than {:.0}%",
+                baseline,
+                current,
+                tolerance * 100.0
+            );
+        }
+        _ => {
+            fs::write(&path, format!("{}\n", current)).expect("error writing the baseline");
+            println!(
+                "Recorded a baseline of {:.0} rays/s in {}",
+                current,
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Property tests for the math the rest of the raytracer leans on. They run with a fixed seed so
+// that failures reproduce exactly, and proptest shrinks any failure down to a minimal input.
+
+use std::f64::consts::PI;
+
+use proptest::{
+    prelude::*,
+    test_runner::{Config, RngSeed},
+};
+use raytracer::{
+    body::Body,
+    fuzzy_eq::FuzzyEq,
+    intersection::{HitRecord, Intersections},
+    matrix::{Matrix, Rotation, Shear},
+    point::Point,
+    ray::Ray,
+    sphere::Sphere,
+    vector::Vector,
+};
+
+fn config() -> Config {
+    Config {
+        rng_seed: RngSeed::Fixed(0x5eed),
+        failure_persistence: None,
+        ..Config::default()
+    }
+}
+
+fn coordinate() -> impl Strategy<Value = f64> {
+    -10.0..10.0
+}
+
+fn vector() -> impl Strategy<Value = Vector> {
+    (coordinate(), coordinate(), coordinate()).prop_map(|(x, y, z)| Vector::new(x, y, z))
+}
+
+fn non_zero_vector() -> impl Strategy<Value = Vector> {
+    vector().prop_filter("vector must not be zero", |v| v.magnitude() > 0.01)
+}
+
+fn scale_factor() -> impl Strategy<Value = f64> {
+    prop_oneof![-5.0..-0.2, 0.2..5.0]
+}
+
+fn angle() -> impl Strategy<Value = f64> {
+    -PI..PI
+}
+
+// Transforms built out of the primitive transforms, which are always invertible. Shears are kept
+// small so the result stays well conditioned.
+fn transform() -> impl Strategy<Value = Matrix<4>> {
+    (
+        (coordinate(), coordinate(), coordinate()),
+        (scale_factor(), scale_factor(), scale_factor()),
+        (angle(), angle(), angle()),
+        (-0.5..0.5, -0.5..0.5),
+    )
+        .prop_map(|((tx, ty, tz), (sx, sy, sz), (rx, ry, rz), (xy, zx))| {
+            Matrix::translate(tx, ty, tz)
+                * Matrix::rotate(Rotation::X, rx)
+                * Matrix::rotate(Rotation::Y, ry)
+                * Matrix::rotate(Rotation::Z, rz)
+                * Matrix::shear(&[Shear::XY(xy), Shear::ZX(zx)])
+                * Matrix::scale(sx, sy, sz)
+        })
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn a_matrix_times_its_inverse_is_the_identity(m in transform()) {
+        prop_assert!((m * m.inverse()).is_identity(), "{:?}", m * m.inverse());
+        prop_assert!((m.inverse() * m).is_identity(), "{:?}", m.inverse() * m);
+    }
+
+    #[test]
+    fn the_inverse_undoes_a_transform(m in transform(), (x, y, z) in (coordinate(), coordinate(), coordinate())) {
+        let p = Point::new(x, y, z);
+
+        prop_assert!((m.inverse() * (m * p)).fuzzy_eq(p));
+    }
+
+    #[test]
+    fn transposing_twice_is_the_identity(m in transform()) {
+        prop_assert!(m.transpose().transpose().fuzzy_eq(m));
+    }
+
+    #[test]
+    fn normalizing_preserves_direction(v in non_zero_vector()) {
+        let n = v.normalize();
+
+        prop_assert!(n.magnitude().fuzzy_eq(1.0));
+        prop_assert!((n * v.magnitude()).fuzzy_eq(v));
+        prop_assert!(n.cross(&v).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn reflecting_is_involutive(v in vector(), n in non_zero_vector()) {
+        let n = n.normalize();
+
+        prop_assert!(v.reflect(n).reflect(n).fuzzy_eq(v));
+        prop_assert!(v.reflect(n).magnitude().fuzzy_eq(v.magnitude()));
+    }
+
+    #[test]
+    fn intersections_are_sorted(ts in prop::collection::vec(-100.0..100.0f64, 0..32)) {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let body: Body = Sphere::default().into();
+        let xs = Intersections::from(
+            ts.iter()
+                .map(|&t| HitRecord::new(t, r, body.clone()))
+                .collect::<Vec<_>>(),
+        );
+
+        prop_assert_eq!(ts.len(), xs.len());
+        for i in 1..xs.len() {
+            prop_assert!(xs[i - 1].t <= xs[i].t);
+        }
+    }
+
+    #[test]
+    fn the_hit_is_the_closest_positive_intersection(ts in prop::collection::vec(-100.0..100.0f64, 0..32)) {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let body: Body = Sphere::default().into();
+        let xs = Intersections::from(
+            ts.iter()
+                .map(|&t| HitRecord::new(t, r, body.clone()))
+                .collect::<Vec<_>>(),
+        );
+
+        let closest = ts.iter().copied().filter(|&t| t > 0.0).fold(None, |min: Option<f64>, t| {
+            Some(min.map_or(t, |min| min.min(t)))
+        });
+        prop_assert_eq!(closest, xs.hit().map(|hit| hit.t));
+    }
+}
@@ -0,0 +1,29 @@
+// Renders the quick start scene from README.md, which is only built by its doctest, and checks
+// that the PNG it writes reads back.
+
+use std::{error::Error, fs::File};
+
+use raytracer::{
+    camera::Camera,
+    canvas::{Canvas, ToPng},
+    render::{render, RenderSettings},
+    world::World,
+};
+
+#[test]
+fn the_quick_start_renders_a_png() -> Result<(), Box<dyn Error>> {
+    // Named for the process, so that test runs at the same time don't write over each other.
+    let path =
+        std::env::temp_dir().join(format!("raytracer-quickstart-{}.png", std::process::id()));
+
+    // As in the README, only smaller.
+    let world = World::quickstart();
+    let camera = Camera::quickstart(64, 36);
+    let output = render(&world, &camera, &RenderSettings::default());
+    output.canvas.to_png(File::create(&path)?)?;
+
+    let read = Canvas::from_png(File::open(&path)?)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!((64, 36), (read.width, read.height));
+    Ok(())
+}